@@ -1,16 +1,29 @@
 use crate::app_handler::AppHandler;
+use crate::crash::install_panic_hook;
+use crate::plugin::{CameraControllerPlugin, Plugin, PluginSet};
+use crate::replay::{InputReplay, InputReplayMode};
+use crate::state::{GameState, StateStack};
+use crate::video_capture::VideoCapture;
 use asset_pipeline::cook_pending;
-use config::config::ConfigFile;
+use common::trace;
+use config::config::{ConfigFile, LatencyMode, WindowMode, WindowResolution};
+use config::paths;
 use core::asset_context::AssetContext;
+use core::asset_gc::AssetGcSettings;
 use core::{EngineConfig, EngineContext};
 use project::{AssetRegistry, Project};
+use std::path::{Path, PathBuf};
 use winit::event_loop::EventLoop;
 
-/// Entry point for the engine. Construct with `App::new` or `App::with_project`,
-/// configure the scene via `engine_context_mut`, then call `run`.
+/// Entry point for the engine. Construct with `App::builder`, `App::new` or
+/// `App::with_project`, configure the scene via `engine_context_mut`, then call `run`.
 pub struct App {
     event_loop: EventLoop<()>,
     engine_context: EngineContext,
+    states: StateStack,
+    plugins: PluginSet,
+    input_replay: Option<InputReplayMode>,
+    video_capture: Option<(PathBuf, u32)>,
 }
 
 impl Default for App {
@@ -22,15 +35,285 @@ impl Default for App {
 impl App {
     /// Discovers the `.eproj` file in the current directory and loads the project.
     pub fn new() -> Self {
-        let path = find_project_file().expect("no .eproj file found in the current directory");
-        Self::with_project(path)
+        Self::builder().build()
     }
 
     /// Loads a project from an explicit `.eproj` path.
-    pub fn with_project(path: impl AsRef<std::path::Path>) -> Self {
-        let path = path.as_ref();
-        let project = Project::load(path)
+    pub fn with_project(path: impl AsRef<Path>) -> Self {
+        Self::builder().project(path).build()
+    }
+
+    /// Starts configuring an app. Unset options fall back to the user config file.
+    pub fn builder() -> AppBuilder {
+        AppBuilder::default()
+    }
+
+    /// Queues a plugin. Plugins are built in dependency order the next time
+    /// `engine_context_mut` or `run` is called.
+    pub fn add_plugin(&mut self, plugin: impl Plugin) -> &mut Self {
+        self.plugins.add(plugin);
+        self
+    }
+
+    /// Returns the engine context for scene setup, building any queued plugins first so
+    /// their managers and systems are already registered.
+    pub fn engine_context_mut(&mut self) -> &mut EngineContext {
+        self.plugins.build(&mut self.engine_context);
+        &mut self.engine_context
+    }
+
+    /// Pushes the initial game state. It is entered on the first frame.
+    pub fn push_state(&mut self, state: impl GameState + 'static) {
+        self.states.push(Box::new(state));
+    }
+
+    pub fn run(mut self) {
+        self.plugins.build(&mut self.engine_context);
+        let replay = self
+            .input_replay
+            .map(|mode| InputReplay::new(mode, self.engine_context.config.fixed_timestep));
+        let capture = self.video_capture.map(|(dir, fps)| {
+            let step = replay.as_ref().map(InputReplay::fixed_delta);
+            if step.is_some_and(|step| (step * fps as f32 - 1.0).abs() > 1e-3) {
+                eprintln!(
+                    "warning: capturing at {} fps over an input replay with a {}s step; the video \
+                     will not play at real-time speed",
+                    fps,
+                    step.unwrap_or_default()
+                );
+            }
+            VideoCapture::new(dir, fps)
+        });
+        let mut handler = AppHandler::new(self.engine_context, self.states, replay, capture);
+        self.event_loop
+            .run_app(&mut handler)
+            .expect("Failed to run event loop");
+        if let Err(e) = trace::finish() {
+            eprintln!("warning: could not write trace: {}", e);
+        }
+    }
+}
+
+/// Configures window, rendering and timing before the engine starts.
+///
+/// ```ignore
+/// let app = App::builder()
+///     .project("sample/sample.eproj")
+///     .window_title("Sample")
+///     .window_size(1600, 900)
+///     .vsync(true)
+///     .fixed_timestep(60.0)
+///     .build();
+/// ```
+pub struct AppBuilder {
+    project_path: Option<PathBuf>,
+    window_title: Option<String>,
+    window_size: Option<(u32, u32)>,
+    window_mode: Option<WindowMode>,
+    vsync: Option<bool>,
+    latency_mode: Option<LatencyMode>,
+    max_frames_in_flight: Option<u32>,
+    target_fps: Option<u32>,
+    unfocused_fps_cap: Option<u32>,
+    max_frame_delta: f32,
+    delta_smoothing: f32,
+    fixed_rate: f32,
+    asset_gc: AssetGcSettings,
+    default_plugins: bool,
+    crash_handler: bool,
+    input_replay: Option<InputReplayMode>,
+    video_capture: Option<(PathBuf, u32)>,
+    trace_path: Option<PathBuf>,
+}
+
+impl Default for AppBuilder {
+    fn default() -> Self {
+        Self {
+            project_path: None,
+            window_title: None,
+            window_size: None,
+            window_mode: None,
+            vsync: None,
+            latency_mode: None,
+            max_frames_in_flight: None,
+            target_fps: None,
+            unfocused_fps_cap: None,
+            max_frame_delta: 0.1,
+            delta_smoothing: 0.0,
+            fixed_rate: 60.0,
+            asset_gc: AssetGcSettings::default(),
+            default_plugins: true,
+            crash_handler: true,
+            input_replay: None,
+            video_capture: None,
+            trace_path: None,
+        }
+    }
+}
+
+impl AppBuilder {
+    /// Path to the `.eproj` file. Relative paths are resolved against the working
+    /// directory, then the executable's directory. Defaults to the first `.eproj` in
+    /// either.
+    pub fn project(mut self, path: impl AsRef<Path>) -> Self {
+        self.project_path = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Window title. Defaults to the project name.
+    pub fn window_title(mut self, title: impl Into<String>) -> Self {
+        self.window_title = Some(title.into());
+        self
+    }
+
+    pub fn window_size(mut self, width: u32, height: u32) -> Self {
+        self.window_size = Some((width, height));
+        self
+    }
+
+    pub fn window_mode(mut self, mode: WindowMode) -> Self {
+        self.window_mode = Some(mode);
+        self
+    }
+
+    pub fn vsync(mut self, enabled: bool) -> Self {
+        self.vsync = Some(enabled);
+        self
+    }
+
+    /// Defaults to the `latency_mode` graphics setting (`Overlapped`). `Low` waits for
+    /// each frame to be presented before sampling input for the next one.
+    pub fn latency_mode(mut self, mode: LatencyMode) -> Self {
+        self.latency_mode = Some(mode);
+        self
+    }
+
+    /// Frames the CPU may record ahead of the GPU, clamped to 1..=3. Defaults to the
+    /// `max_frames_in_flight` graphics setting (2); 1 trades throughput for latency.
+    pub fn max_frames_in_flight(mut self, frames: u32) -> Self {
+        self.max_frames_in_flight = Some(frames);
+        self
+    }
+
+    /// Frame rate cap while focused; 0 disables it. Defaults to the `target_fps`
+    /// graphics setting (uncapped). Frames are paced with a sleep followed by a short
+    /// spin, so the cap holds to well under a millisecond.
+    pub fn target_fps(mut self, fps: u32) -> Self {
+        self.target_fps = Some(fps);
+        self
+    }
+
+    /// Frame rate cap while the window is unfocused; 0 disables it. Defaults to the
+    /// `unfocused_fps_cap` graphics setting (30).
+    pub fn unfocused_fps_cap(mut self, fps: u32) -> Self {
+        self.unfocused_fps_cap = Some(fps);
+        self
+    }
+
+    /// Longest frame delta systems will see, in seconds; longer frames (hitches,
+    /// breakpoints) are clamped to it. 0 disables clamping. Defaults to 0.1.
+    pub fn max_frame_delta(mut self, seconds: f32) -> Self {
+        assert!(seconds >= 0.0, "max frame delta must not be negative");
+        self.max_frame_delta = seconds;
+        self
+    }
+
+    /// Exponential smoothing of frame deltas, from 0 (off, the default) towards 1
+    /// (heavier smoothing). Evens out jitter in camera and animation motion.
+    pub fn delta_smoothing(mut self, factor: f32) -> Self {
+        self.delta_smoothing = factor;
+        self
+    }
+
+    /// Rate of fixed-update systems in Hz. Defaults to 60.
+    pub fn fixed_timestep(mut self, hz: f32) -> Self {
+        assert!(hz > 0.0, "fixed timestep rate must be positive");
+        self.fixed_rate = hz;
+        self
+    }
+
+    /// Unloads meshes, materials and textures no entity has referenced for
+    /// `idle_seconds`, releasing at most `max_per_frame` each frame. Off by default; see
+    /// the `core::asset_gc` module for what counts as a reference.
+    pub fn asset_gc(mut self, idle_seconds: f32, max_per_frame: usize) -> Self {
+        assert!(idle_seconds > 0.0, "asset GC idle time must be positive");
+        assert!(
+            max_per_frame > 0,
+            "asset GC must release at least one asset per frame"
+        );
+        self.asset_gc = AssetGcSettings {
+            idle_seconds,
+            max_releases_per_frame: max_per_frame,
+        };
+        self
+    }
+
+    /// Records every frame's input to `path` on exit, for reproducing bugs later with
+    /// `replay_input`. Frames advance by exactly one fixed step while recording.
+    pub fn record_input(mut self, path: impl AsRef<Path>) -> Self {
+        self.input_replay = Some(InputReplayMode::Record(path.as_ref().to_path_buf()));
+        self
+    }
+
+    /// Drives the app from a recording made with `record_input` instead of live input,
+    /// and exits when it ends. Useful for automated gameplay regression runs.
+    pub fn replay_input(mut self, path: impl AsRef<Path>) -> Self {
+        self.input_replay = Some(InputReplayMode::Replay(path.as_ref().to_path_buf()));
+        self
+    }
+
+    /// Writes every rendered frame to `dir` as `frame_000000.png`, `frame_000001.png`, ...
+    /// for encoding into a video. Each frame advances game time by exactly `1 / fps`
+    /// seconds, so the video plays at the right speed however slowly frames render.
+    /// Combined with `replay_input`, the replay keeps its recorded step, so pass the
+    /// matching frame rate (the fixed timestep rate by default). Frames are read back as
+    /// SDR sRGB, so captures made with HDR output enabled look washed out.
+    pub fn capture_video(mut self, dir: impl AsRef<Path>, fps: u32) -> Self {
+        assert!(fps > 0, "video capture needs a positive frame rate");
+        self.video_capture = Some((dir.as_ref().to_path_buf(), fps));
+        self
+    }
+
+    /// Records a profiling trace of the session to `path` in the Chrome trace format:
+    /// the time of every system, GPU time of every render pass and every asset load, from
+    /// `build` until `run` returns. Open it in `chrome://tracing` or Perfetto. GPU passes
+    /// are only timed on devices that support host query resets.
+    pub fn trace_session(mut self, path: impl AsRef<Path>) -> Self {
+        self.trace_path = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Skips the default plugins (currently the fly-camera controller).
+    pub fn without_default_plugins(mut self) -> Self {
+        self.default_plugins = false;
+        self
+    }
+
+    /// Skips the panic hook that writes crash reports to the user cache directory (see
+    /// `config::paths`), e.g. when the game installs its own.
+    pub fn without_crash_handler(mut self) -> Self {
+        self.crash_handler = false;
+        self
+    }
+
+    /// Loads and cooks the project, then creates the app.
+    pub fn build(self) -> App {
+        let path = match self.project_path {
+            Some(path) => paths::resolve_resource(path),
+            None => find_project_file()
+                .expect("no .eproj file found in the working directory or next to the executable"),
+        };
+        let project = Project::load(&path)
             .unwrap_or_else(|e| panic!("failed to load '{}': {}", path.display(), e));
+        if self.crash_handler {
+            let cache_dir = paths::cache_dir(&project.name).unwrap_or(project.cache_dir.clone());
+            install_panic_hook(project.name.clone(), cache_dir.join("crashes"));
+        }
+        if let Some(path) = &self.trace_path {
+            if let Err(e) = trace::start(path) {
+                eprintln!("warning: could not start trace '{}': {}", path.display(), e);
+            }
+        }
 
         let registry = AssetRegistry::load_or_scan(&project.cache_dir, &project.content_dir)
             .expect("failed to scan project content directory");
@@ -42,38 +325,65 @@ impl App {
             .unwrap_or_else(|e| eprintln!("warning: could not save asset registry: {}", e));
 
         let cfg = ConfigFile::load_or_default(&project.name);
+        let graphics = cfg.graphics_settings;
+
+        let window_resolution = match self.window_size {
+            Some((width, height)) => WindowResolution { width, height },
+            None => graphics.resolution_settings,
+        };
 
         let config = EngineConfig {
+            window_title: self.window_title.unwrap_or_else(|| project.name.clone()),
             name: project.name,
             content_dir: project.content_dir.clone(),
             cache_dir: project.cache_dir.clone(),
-            window_resolution: cfg.graphics_settings.resolution_settings,
-            window_mode: cfg.graphics_settings.window_mode,
+            window_resolution,
+            window_mode: self.window_mode.unwrap_or(graphics.window_mode),
+            display: graphics.display,
+            vsync: self.vsync.unwrap_or(graphics.vsync),
+            latency_mode: self.latency_mode.unwrap_or(graphics.latency_mode),
+            max_frames_in_flight: self
+                .max_frames_in_flight
+                .unwrap_or(graphics.max_frames_in_flight),
+            target_fps: self.target_fps.unwrap_or(graphics.target_fps),
+            unfocused_fps_cap: self.unfocused_fps_cap.unwrap_or(graphics.unfocused_fps_cap),
+            max_frame_delta: self.max_frame_delta,
+            delta_smoothing: self.delta_smoothing,
+            async_compute: graphics.async_compute,
+            gpu_diagnostics: graphics.gpu_diagnostics,
+            gpu_picking: graphics.gpu_picking,
+            gpu_particles: graphics.gpu_particles,
+            texture_budget_mb: graphics.texture_budget_mb,
+            fixed_timestep: 1.0 / self.fixed_rate,
+            shadow_settings: graphics.shadow_settings,
+            asset_gc: self.asset_gc,
         };
 
         let assets = AssetContext::new(project.cache_dir, project.content_dir, registry);
         let engine_context = EngineContext::new(config, assets);
         let event_loop = EventLoop::new().expect("failed to create event loop");
 
-        Self { event_loop, engine_context }
-    }
-
-    pub fn engine_context_mut(&mut self) -> &mut EngineContext {
-        &mut self.engine_context
-    }
-
-    pub fn run(self) {
-        let mut handler = AppHandler::new(self.engine_context);
-        self.event_loop
-            .run_app(&mut handler)
-            .expect("Failed to run event loop");
+        let mut app = App {
+            event_loop,
+            engine_context,
+            states: StateStack::new(),
+            plugins: PluginSet::default(),
+            input_replay: self.input_replay,
+            video_capture: self.video_capture,
+        };
+        if self.default_plugins {
+            app.add_plugin(CameraControllerPlugin);
+        }
+        app
     }
 }
 
-fn find_project_file() -> Option<std::path::PathBuf> {
-    std::fs::read_dir(".")
-        .ok()?
-        .filter_map(|e| e.ok())
-        .find(|e| e.path().extension().and_then(|x| x.to_str()) == Some("eproj"))
-        .map(|e| e.path())
+fn find_project_file() -> Option<PathBuf> {
+    paths::resource_dirs().into_iter().find_map(|dir| {
+        std::fs::read_dir(dir)
+            .ok()?
+            .filter_map(|e| e.ok())
+            .find(|e| e.path().extension().and_then(|x| x.to_str()) == Some("eproj"))
+            .map(|e| e.path())
+    })
 }
\ No newline at end of file
@@ -1,42 +1,60 @@
+use crate::display::Monitors;
 use crate::engine::Engine;
-use config::config::WindowMode;
+use crate::frame_pacer::FramePacer;
+use crate::replay::InputReplay;
+use crate::state::StateStack;
+use crate::video_capture::VideoCapture;
 use core::EngineContext;
 use winit::application::ApplicationHandler;
 use winit::dpi::LogicalSize;
 use winit::event::{DeviceEvent, DeviceId, WindowEvent};
-use winit::event_loop::ActiveEventLoop;
-use winit::window::{Fullscreen, Window, WindowId};
+use winit::event_loop::{ActiveEventLoop, ControlFlow};
+use winit::window::{Window, WindowId};
+
+/// What the engine is built from once the window exists.
+type PendingEngine = (
+    EngineContext,
+    StateStack,
+    Option<InputReplay>,
+    Option<VideoCapture>,
+);
 
 /// Winit `ApplicationHandler` implementation. Thin OS/event-loop adapter.
 /// Holds the pre-configured `EngineContext` until the window is ready, then
 /// constructs an `Engine` and forwards all events to it.
 pub struct AppHandler {
-    context: Option<EngineContext>,
+    context: Option<PendingEngine>,
     engine: Option<Engine>,
+    pacer: FramePacer,
 }
 
 impl AppHandler {
-    pub fn new(context: EngineContext) -> Self {
+    pub fn new(
+        context: EngineContext,
+        states: StateStack,
+        replay: Option<InputReplay>,
+        capture: Option<VideoCapture>,
+    ) -> Self {
         Self {
-            context: Some(context),
+            context: Some((context, states, replay, capture)),
             engine: None,
+            pacer: FramePacer::new(),
         }
     }
 
     fn create_window(&self, event_loop: &ActiveEventLoop) -> Window {
-        let ctx = self.context.as_ref().expect("context must be present before window creation");
+        let (ctx, ..) = self.context.as_ref().expect("context must be present before window creation");
         let res = &ctx.config.window_resolution;
 
-        let mut attrs = Window::default_attributes()
-            .with_title(&ctx.config.name)
-            .with_inner_size(LogicalSize::new(res.width, res.height));
+        let monitors =
+            Monitors::enumerate(event_loop.available_monitors(), event_loop.primary_monitor());
+        let fullscreen =
+            monitors.fullscreen(&ctx.config.window_mode, &ctx.config.display, res);
 
-        match ctx.config.window_mode {
-            WindowMode::BorderlessFullscreen =>
-                attrs = attrs.with_fullscreen(Some(Fullscreen::Borderless(None))),
-            // TODO: exclusive fullscreen requires a MonitorHandle from winit
-            WindowMode::Windowed | WindowMode::Fullscreen => {}
-        }
+        let attrs = Window::default_attributes()
+            .with_title(&ctx.config.window_title)
+            .with_inner_size(LogicalSize::new(res.width, res.height))
+            .with_fullscreen(fullscreen);
 
         event_loop.create_window(attrs).expect("Failed to create window")
     }
@@ -46,8 +64,38 @@ impl ApplicationHandler for AppHandler {
     fn resumed(&mut self, event_loop: &ActiveEventLoop) {
         if self.engine.is_none() {
             let window = self.create_window(event_loop);
-            let context = self.context.take().expect("EngineContext already consumed");
-            self.engine = Some(Engine::new(window, context));
+            let (context, states, replay, capture) =
+                self.context.take().expect("EngineContext already consumed");
+            self.engine = Some(Engine::new(window, context, states, replay, capture));
+        }
+    }
+
+    /// Paces the loop. Capped frames let the event loop wait out most of their interval,
+    /// then finish with a precise sleep; a minimized window may never deliver
+    /// `RedrawRequested`, so suspended frames tick directly.
+    fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
+        let Some(engine) = &mut self.engine else {
+            return;
+        };
+
+        let interval = engine.frame_interval();
+        if let Some(interval) = interval {
+            if let Some(wake) = self.pacer.coarse_wait(interval) {
+                event_loop.set_control_flow(ControlFlow::WaitUntil(wake));
+                return;
+            }
+            self.pacer.wait_precise(interval);
+        }
+        event_loop.set_control_flow(ControlFlow::Poll);
+
+        if engine.is_rendering_suspended() {
+            self.pacer.frame_started(interval);
+            engine.tick();
+            if engine.exit_requested() {
+                event_loop.exit();
+            }
+        } else {
+            engine.window().request_redraw();
         }
     }
 
@@ -57,17 +105,34 @@ impl ApplicationHandler for AppHandler {
         _window_id: WindowId,
         event: WindowEvent,
     ) {
+        let Some(engine) = &mut self.engine else {
+            return;
+        };
+
+        engine.handle_window_event(&event);
         match event {
             WindowEvent::CloseRequested => event_loop.exit(),
-            WindowEvent::RedrawRequested => {
-                if let Some(engine) = &mut self.engine {
-                    engine.tick();
+            WindowEvent::Resized(size) => engine.on_resized(size.width, size.height),
+            WindowEvent::ScaleFactorChanged { .. } => engine.on_scale_factor_changed(),
+            WindowEvent::Occluded(occluded) => engine.set_occluded(occluded),
+            WindowEvent::Focused(focused) => engine.set_focused(focused),
+            WindowEvent::RedrawRequested if !engine.is_rendering_suspended() => {
+                self.pacer.frame_started(engine.frame_interval());
+                engine.tick();
+                if engine.exit_requested() {
+                    event_loop.exit();
                 }
             }
             _ => {}
         }
     }
 
+    fn exiting(&mut self, _event_loop: &ActiveEventLoop) {
+        if let Some(engine) = self.engine.take() {
+            engine.shutdown();
+        }
+    }
+
     fn device_event(
         &mut self,
         _event_loop: &ActiveEventLoop,
@@ -0,0 +1,179 @@
+//! Panic hook that leaves a crash report behind.
+//!
+//! A panic in a shipped build otherwise just closes the console window. The hook keeps
+//! the default stderr output, flushes the console, then writes a report with the panic,
+//! a backtrace and the renderer's [`crash_context`] to `crashes/` in the user cache
+//! directory, which stays writable when the game is installed read-only. Panics on the
+//! main thread also show a message box on Windows pointing at the report.
+
+use common::crash_context::{self, CrashContext};
+use std::backtrace::Backtrace;
+use std::fmt::Write as _;
+use std::io::{self, Write as _};
+use std::panic::{self, PanicHookInfo};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Installs the hook. The previous hook still runs first.
+pub(crate) fn install_panic_hook(app_name: String, report_dir: PathBuf) {
+    let previous = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        previous(info);
+        let _ = io::stdout().flush();
+        let _ = io::stderr().flush();
+
+        let report = CrashReport::capture(&app_name, info);
+        match report.write(&report_dir) {
+            Ok(path) => {
+                eprintln!("crash report written to '{}'", path.display());
+                if report.thread == "main" {
+                    show_message_box(&app_name, &path);
+                }
+            }
+            Err(err) => eprintln!("could not write crash report: {}", err),
+        }
+    }));
+}
+
+struct CrashReport {
+    app_name: String,
+    /// Milliseconds since the Unix epoch.
+    time: u128,
+    thread: String,
+    message: String,
+    location: String,
+    backtrace: String,
+    context: CrashContext,
+}
+
+impl CrashReport {
+    fn capture(app_name: &str, info: &PanicHookInfo) -> Self {
+        let message = info
+            .payload_as_str()
+            .unwrap_or("<non-string panic payload>")
+            .to_string();
+        Self {
+            app_name: app_name.to_string(),
+            time: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |elapsed| elapsed.as_millis()),
+            thread: thread::current().name().unwrap_or("<unnamed>").to_string(),
+            message,
+            location: info
+                .location()
+                .map_or_else(|| "<unknown>".to_string(), ToString::to_string),
+            backtrace: Backtrace::force_capture().to_string(),
+            context: crash_context::snapshot(),
+        }
+    }
+
+    fn render(&self) -> String {
+        let mut report = format!("=== {} crashed ===\n", self.app_name);
+        let _ = writeln!(report, "Time: {} ms since Unix epoch", self.time);
+        let _ = writeln!(
+            report,
+            "Engine: {} ({} {})",
+            env!("CARGO_PKG_VERSION"),
+            std::env::consts::OS,
+            std::env::consts::ARCH
+        );
+        let _ = writeln!(report, "Thread: {}", self.thread);
+        let _ = writeln!(report, "Panic: {}", self.message);
+        let _ = writeln!(report, "Location: {}", self.location);
+
+        let context = &self.context;
+        let _ = writeln!(
+            report,
+            "\nGPU: {}",
+            context.device.as_deref().unwrap_or("<not initialized>")
+        );
+        let _ = writeln!(report, "Render frame: {}", context.frame);
+        if context.passes.is_empty() {
+            report.push_str("No passes were recorded this frame.\n");
+        } else {
+            report.push_str("Passes recorded this frame:\n");
+            for (i, pass) in context.passes.iter().enumerate() {
+                let _ = writeln!(report, "  #{} {}", i + 1, pass);
+            }
+        }
+        if let Some(device_lost) = &context.device_lost {
+            let _ = writeln!(report, "\n{}", device_lost.trim_end());
+        }
+
+        let _ = write!(report, "\nBacktrace:\n{}", self.backtrace);
+        report
+    }
+
+    fn write(&self, dir: &Path) -> io::Result<PathBuf> {
+        std::fs::create_dir_all(dir)?;
+        let path = dir.join(format!("crash-{}.txt", self.time));
+        std::fs::write(&path, self.render())?;
+        Ok(path)
+    }
+}
+
+#[cfg(windows)]
+fn show_message_box(app_name: &str, report: &Path) {
+    use std::ffi::c_void;
+
+    #[link(name = "user32")]
+    unsafe extern "system" {
+        fn MessageBoxW(hwnd: *mut c_void, text: *const u16, caption: *const u16, kind: u32) -> i32;
+    }
+    const MB_OK: u32 = 0x0;
+    const MB_ICONERROR: u32 = 0x10;
+
+    let wide = |s: &str| s.encode_utf16().chain(Some(0)).collect::<Vec<u16>>();
+    let text = wide(&format!(
+        "{} has crashed.\n\nA crash report was written to:\n{}\n\nPlease include it when \
+         reporting the problem.",
+        app_name,
+        report.display()
+    ));
+    let caption = wide(app_name);
+    unsafe {
+        MessageBoxW(
+            std::ptr::null_mut(),
+            text.as_ptr(),
+            caption.as_ptr(),
+            MB_OK | MB_ICONERROR,
+        );
+    }
+}
+
+#[cfg(not(windows))]
+fn show_message_box(_app_name: &str, _report: &Path) {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn report_includes_panic_and_render_context() {
+        let report = CrashReport {
+            app_name: "Sample".to_string(),
+            time: 42,
+            thread: "main".to_string(),
+            message: "index out of bounds".to_string(),
+            location: "src/main.rs:3:5".to_string(),
+            backtrace: "   0: main\n".to_string(),
+            context: CrashContext {
+                device: Some("Test GPU".to_string()),
+                frame: 7,
+                passes: vec!["Shadows".to_string(), "Lighting".to_string()],
+                device_lost: None,
+            },
+        };
+        let dir = std::env::temp_dir().join(format!("crash_{}", std::process::id()));
+
+        let path = report.write(&dir).unwrap();
+        let written = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_dir_all(&dir).ok();
+        assert_eq!(path.file_name().unwrap(), "crash-42.txt");
+        assert!(written.contains("Panic: index out of bounds"));
+        assert!(written.contains("GPU: Test GPU"));
+        assert!(written.contains("  #2 Lighting"));
+        assert!(written.ends_with("   0: main\n"));
+    }
+}
@@ -0,0 +1,91 @@
+use config::config::{DisplaySettings, VideoMode, WindowMode, WindowResolution};
+use core::display::{select_monitor, MonitorInfo};
+use winit::monitor::{MonitorHandle, VideoModeHandle};
+use winit::window::Fullscreen;
+
+/// Snapshot of the connected monitors: the engine-facing [`MonitorInfo`]s, and the winit
+/// handles fullscreen needs, at the same indices.
+pub(crate) struct Monitors {
+    infos: Vec<MonitorInfo>,
+    handles: Vec<MonitorHandle>,
+    /// Per monitor, the handle of each of its `video_modes`.
+    video_modes: Vec<Vec<VideoModeHandle>>,
+}
+
+impl Monitors {
+    pub fn enumerate(
+        available: impl Iterator<Item = MonitorHandle>,
+        primary: Option<MonitorHandle>,
+    ) -> Self {
+        let mut monitors = Self {
+            infos: Vec::new(),
+            handles: Vec::new(),
+            video_modes: Vec::new(),
+        };
+        for handle in available {
+            // Modes that differ only in bit depth are listed once, at the deepest.
+            let mut handles: Vec<VideoModeHandle> = handle.video_modes().collect();
+            handles.sort_by_key(|mode| std::cmp::Reverse(mode.bit_depth()));
+            let mut video_modes = Vec::new();
+            handles.retain(|mode| {
+                let size = mode.size();
+                let mode = VideoMode {
+                    width: size.width,
+                    height: size.height,
+                    refresh_rate_millihertz: mode.refresh_rate_millihertz(),
+                };
+                let new = !video_modes.contains(&mode);
+                if new {
+                    video_modes.push(mode);
+                }
+                new
+            });
+
+            let size = handle.size();
+            monitors.infos.push(MonitorInfo {
+                name: handle.name().unwrap_or_default(),
+                width: size.width,
+                height: size.height,
+                refresh_rate_millihertz: handle.refresh_rate_millihertz(),
+                scale_factor: handle.scale_factor(),
+                primary: primary.as_ref() == Some(&handle),
+                video_modes,
+            });
+            monitors.handles.push(handle);
+            monitors.video_modes.push(handles);
+        }
+        monitors
+    }
+
+    pub fn infos(&self) -> &[MonitorInfo] {
+        &self.infos
+    }
+
+    /// Winit fullscreen state for `window_mode` on the monitor `display` selects, `None`
+    /// when windowed. Exclusive fullscreen uses the selected video mode, or the closest
+    /// one to `resolution`, and falls back to borderless if the monitor reports none.
+    pub fn fullscreen(
+        &self,
+        window_mode: &WindowMode,
+        display: &DisplaySettings,
+        resolution: &WindowResolution,
+    ) -> Option<Fullscreen> {
+        let monitor = select_monitor(&self.infos, display);
+        let borderless = Fullscreen::Borderless(monitor.map(|index| self.handles[index].clone()));
+        match window_mode {
+            WindowMode::Windowed => None,
+            WindowMode::BorderlessFullscreen => Some(borderless),
+            WindowMode::Fullscreen => {
+                let exclusive = monitor.and_then(|index| {
+                    let mode = self.infos[index].closest_video_mode(
+                        display.video_mode,
+                        resolution.width,
+                        resolution.height,
+                    )?;
+                    Some(Fullscreen::Exclusive(self.video_modes[index][mode].clone()))
+                });
+                Some(exclusive.unwrap_or(borderless))
+            }
+        }
+    }
+}
@@ -1,127 +1,395 @@
-use core::EngineContext;
+use crate::display::Monitors;
+use crate::light_probes;
+use crate::replay::InputReplay;
+use crate::state::StateStack;
+use crate::video_capture::VideoCapture;
+use common::{trace, Color};
+use config::config::{ConfigFile, LatencyMode, WindowMode};
+use core::display::Displays;
+use core::draw2d::Draw2D;
+use core::environment::WorldEnvironment;
+use core::frame_budget::{BudgetExceeded, FrameBudgets, FrameStage};
+use core::post_process::PostProcessSettings;
+use core::render_settings::{
+    PickResult, RenderSettings, CAPTURE_FRAME_ACTION, DUMP_FRAME_ACTION, DUMP_GPU_MEMORY_ACTION,
+};
+use core::spline::{SplineComponent, SplineMode};
+use core::time::{DeltaFilter, Time};
+use core::ui::UiLayout;
+use core::wind::Wind;
+use core::{EngineContext, TransformComponent};
+use ecs::event::Events;
+use input::{CursorMode, RecordedInput};
+use nalgebra_glm::Vec3;
 use renderer::frame_data::{Resolution, ResolutionSettings};
 use renderer::render_data::RenderDataCollector;
-use renderer::renderer::{DebugBox, Renderer, RendererConfig};
-use rendering_backend::backend_impl::resource_manager::ResourceManager;
-use rendering_backend::backend_impl::vulkan_backend::VulkanBackend;
-use rendering_backend::camera::CameraMvpUbo;
-use std::time::Instant;
-use winit::event::{DeviceEvent, ElementState};
+use renderer::renderer::{DebugBox, DebugLine, Renderer, RendererConfig};
+use std::time::{Duration, Instant};
+use winit::event::{DeviceEvent, ElementState, Ime, WindowEvent};
 use winit::keyboard::KeyCode as WinitKeyCode;
-use winit::window::Window;
+use winit::window::{CursorGrabMode, Window};
 
-const WINDOW_TITLE: &str = "Vulkan Test";
 /// How often the title bar FPS/frametime counters are refreshed (seconds).
 const DISPLAY_INTERVAL: f32 = 0.5;
 
+/// Loop interval while rendering is suspended (minimized or fully occluded window).
+const SUSPENDED_FRAME_INTERVAL: Duration = Duration::from_millis(100);
+
+/// How often the monitor list is re-read. Winit has no event for monitors being plugged
+/// in or out.
+const MONITOR_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
 pub(crate) struct Engine {
     context: EngineContext,
-    vulkan_backend: VulkanBackend,
-    resource_manager: ResourceManager,
+    states: StateStack,
     renderer: Renderer,
+    /// Persists across frames so only changed transforms are re-uploaded.
+    render_data: RenderDataCollector,
     window: Window,
     last_frame_time: Instant,
+    delta_filter: DeltaFilter,
     frame_time_accum: f32,
     frame_count: u32,
     displayed_fps: f32,
     displayed_ms: f32,
+    minimized: bool,
+    occluded: bool,
+    focused: bool,
+    /// Input recording or replay, if the app was started with one.
+    replay: Option<InputReplay>,
+    /// Frame sequence output, if the app was started with one.
+    capture: Option<VideoCapture>,
+    monitors: Monitors,
+    last_monitor_poll: Instant,
 }
 
 impl Engine {
     /// Initialises Vulkan and the renderer, then takes ownership of the pre-configured context.
-    pub fn new(window: Window, context: EngineContext) -> Self {
+    pub fn new(
+        window: Window,
+        mut context: EngineContext,
+        states: StateStack,
+        replay: Option<InputReplay>,
+        capture: Option<VideoCapture>,
+    ) -> Self {
         let size = window.inner_size();
-        let mut vulkan_backend =
-            VulkanBackend::new(&window).expect("Failed to initialize Vulkan backend");
-
         let renderer = Renderer::new(
-            &mut vulkan_backend,
+            &window,
             RendererConfig {
+                vsync: context.config.vsync,
+                frames_in_flight: context.config.max_frames_in_flight as usize,
+                async_compute: context.config.async_compute,
+                gpu_diagnostics: context.config.gpu_diagnostics,
+                gpu_picking: context.config.gpu_picking,
+                gpu_particles: context.config.gpu_particles,
+                texture_budget_mb: context.config.texture_budget_mb,
                 resolution_settings: ResolutionSettings {
                     window_resolution: Resolution {
                         width: size.width,
                         height: size.height,
                     },
-                    shadow_resolutions: vec![
-                        Resolution { width: 2048, height: 2048 },
-                        Resolution { width: 2048, height: 2048 },
-                        Resolution { width: 1024, height: 1024 },
-                        Resolution { width: 1024, height: 1024 },
-                    ],
                 },
+                shadow_settings: context.config.shadow_settings.clone(),
                 asset_cache_dir: context.shader_cache_dir(),
             },
         );
 
+        let monitors = Monitors::enumerate(window.available_monitors(), window.primary_monitor());
+        context
+            .resources_mut()
+            .get_mut::<Displays>()
+            .set_monitors(monitors.infos().to_vec());
+
+        let delta_filter = DeltaFilter::new(
+            context.config.max_frame_delta,
+            context.config.delta_smoothing,
+        );
+
         Self {
             context,
-            vulkan_backend,
-            resource_manager: ResourceManager::new(),
+            states,
             renderer,
+            render_data: RenderDataCollector::new(),
             window,
             last_frame_time: Instant::now(),
+            delta_filter,
             frame_time_accum: 0.0,
             frame_count: 0,
             displayed_fps: 0.0,
             displayed_ms: 0.0,
+            minimized: size.width == 0 || size.height == 0,
+            occluded: false,
+            focused: true,
+            replay,
+            capture,
+            monitors,
+            last_monitor_poll: Instant::now(),
         }
     }
 
-    /// Runs one full engine frame: input → ECS → render → present.
+    pub fn on_resized(&mut self, width: u32, height: u32) {
+        self.minimized = width == 0 || height == 0;
+        self.renderer.on_resized();
+    }
+
+    /// The window moved to a display with a different DPI. Winit follows up with the
+    /// new size, but the swapchain is recreated either way so the UI is never drawn
+    /// stretched from the old resolution.
+    pub fn on_scale_factor_changed(&mut self) {
+        self.renderer.on_resized();
+    }
+
+    pub fn set_occluded(&mut self, occluded: bool) {
+        self.occluded = occluded;
+    }
+
+    pub fn set_focused(&mut self, focused: bool) {
+        self.focused = focused;
+        self.feed_input(RecordedInput::FocusChanged(focused));
+        self.apply_cursor_mode();
+    }
+
+    /// True while nothing on screen can change, so frames are simulated but not rendered.
+    pub fn is_rendering_suspended(&self) -> bool {
+        self.minimized || self.occluded
+    }
+
+    /// Minimum time between frames, or `None` to run as fast as presentation allows.
+    /// Unfocused, the lower of the target and unfocused caps applies.
+    pub fn frame_interval(&self) -> Option<Duration> {
+        if self.is_rendering_suspended() {
+            return Some(SUSPENDED_FRAME_INTERVAL);
+        }
+        let config = &self.context.config;
+        let (target, unfocused) = (config.target_fps, config.unfocused_fps_cap);
+        let cap = if self.focused || unfocused == 0 {
+            target
+        } else if target == 0 {
+            unfocused
+        } else {
+            target.min(unfocused)
+        };
+        (cap > 0).then(|| Duration::from_secs_f64(1.0 / cap as f64))
+    }
+
+    /// Runs one full engine frame: input → game states → ECS → render → present.
+    /// Rendering is skipped while suspended or until the swapchain can be recreated.
     pub fn tick(&mut self) {
-        let delta_time = self.last_frame_time.elapsed().as_secs_f32();
+        let raw_delta = self.last_frame_time.elapsed().as_secs_f32();
         self.last_frame_time = Instant::now();
+        let mut delta_time = self.delta_filter.filter(raw_delta);
+
+        if let Some(capture) = &self.capture {
+            delta_time = capture.frame_delta();
+        }
+        if let Some(replay) = &mut self.replay {
+            delta_time = replay.fixed_delta();
+            if !replay.begin_tick(self.context.input_mut()) {
+                println!("Input replay finished");
+                self.context.request_exit();
+                return;
+            }
+        }
 
         {
             let input = self.context.input_mut();
-            input.update();
+            input.update(delta_time);
             if input.is_key_just_pressed(input::KeyCode::F3) {
                 self.renderer.toggle_aabb_debug();
             }
-            input.end_frame();
+            if input.is_key_just_pressed(input::KeyCode::F4) {
+                self.renderer.toggle_cluster_debug();
+            }
+            let capture = input.is_action_just_pressed(CAPTURE_FRAME_ACTION);
+            let dump_frame = input.is_action_just_pressed(DUMP_FRAME_ACTION);
+            let dump_memory = input.is_action_just_pressed(DUMP_GPU_MEMORY_ACTION);
+            let mut settings = self.context.resources_mut().get_mut::<RenderSettings>();
+            if capture {
+                settings.trigger_capture();
+            }
+            if dump_frame {
+                settings.dump_frame();
+            }
+            if dump_memory {
+                settings.dump_gpu_memory();
+            }
         }
 
+        self.context.resources_mut().get_mut::<Time>().raw_delta = raw_delta;
+        self.update_display();
+        let size = self.window.inner_size();
+        let scale_factor = self.window.scale_factor() as f32;
+        self.context
+            .resources_mut()
+            .get_mut::<UiLayout>()
+            .set_viewport(size.width as f32, size.height as f32, scale_factor);
+        let update_span = trace::span("frame", "Update");
+        let update_start = Instant::now();
+        self.states.update(&mut self.context, delta_time);
         self.context.update(delta_time);
+        drop(update_span);
+        self.record_stage(FrameStage::Simulation, update_start.elapsed());
+        self.context.input_mut().end_frame();
+        self.apply_cursor_mode();
+        self.apply_text_input();
+        let released = self.context.take_released_assets();
+        self.renderer.release_assets(&released);
+        let output = *self.context.resources().get::<RenderSettings>().output();
+        self.renderer.set_output_settings(&output);
+        let post_process = *self.context.resources().get::<PostProcessSettings>();
+        self.renderer.set_post_process_settings(&post_process);
+        let wind = *self.context.resources().get::<Wind>();
+        let time = self.context.resources().get::<Time>();
+        let (elapsed, delta) = (time.elapsed, time.delta);
+        drop(time);
+        self.renderer.set_wind(&wind, elapsed as f32);
+        self.renderer.set_particle_delta(delta);
+
+        if !self.prepare_swapchain() {
+            return;
+        }
+
+        let capture_requested = self
+            .context
+            .resources_mut()
+            .get_mut::<RenderSettings>()
+            .take_capture_request();
+        if capture_requested && !self.renderer.trigger_capture() {
+            eprintln!("Frame capture requested, but RenderDoc is not attached");
+        }
+        let frame_dump_requested = self
+            .context
+            .resources_mut()
+            .get_mut::<RenderSettings>()
+            .take_frame_dump_request();
+        if frame_dump_requested {
+            let frame = self.context.resources().get::<Time>().frame;
+            let path = self
+                .context
+                .config
+                .cache_dir
+                .join("frame_dumps")
+                .join(format!("frame-{}.json", frame));
+            self.renderer.dump_frame(path);
+        }
 
         let size = self.window.inner_size();
         let aspect = size.width as f32 / size.height as f32;
 
+        let extract_start = Instant::now();
+        light_probes::bake_pending_grids(&mut self.context);
         let world = self.context.get_world();
-        let mut render_data = RenderDataCollector::new();
-        render_data.collect_from_world(world, aspect);
+        self.render_data.collect_from_world(world, aspect);
 
-        let camera_render_data = render_data.camera;
-        let camera_ubo = camera_render_data
-            .as_ref()
-            .map(|c| CameraMvpUbo { view: c.view, proj: c.proj })
-            .expect("No active camera in world");
-
-        let directional_light = render_data.directional_light;
+        let global_shadows = &self.context.config.shadow_settings;
+        let shadow_settings = match &self.render_data.directional_light {
+            Some(light) => light.shadow.resolve(global_shadows),
+            None => global_shadows.clone(),
+        };
+        self.renderer.set_shadow_settings(&shadow_settings);
 
         let debug_boxes = self
             .context
             .get_spatial_world()
             .iter_aabbs()
-            .map(|aabb| DebugBox { max: aabb.upper, min: aabb.lower })
+            .map(|aabb| DebugBox {
+                max: aabb.upper,
+                min: aabb.lower,
+                color: Color::GREEN,
+            })
             .collect::<Vec<_>>();
+        let debug_lines = if self.renderer.is_aabb_debug_enabled() {
+            spline_debug_lines(self.context.get_world())
+        } else {
+            Vec::new()
+        };
 
-        let (asset_store, material_manager) = self.context.render_resources_mut();
+        self.record_stage(FrameStage::Extraction, extract_start.elapsed());
 
+        let gpu_budget = self.context.resources().get::<FrameBudgets>().budget(FrameStage::Gpu);
+        self.renderer.set_gpu_frame_timing(gpu_budget.is_some());
+        let prewarm_requested = self
+            .context
+            .resources_mut()
+            .get_mut::<RenderSettings>()
+            .take_prewarm_request();
+        let (asset_store, material_manager, resources) = self.context.render_resources_mut();
+        if prewarm_requested {
+            self.renderer.prewarm_pipelines(
+                &self.render_data.mesh_requests,
+                material_manager,
+                asset_store,
+            );
+        }
+
+        let render_span = trace::span("frame", "Render");
+        let record_start = Instant::now();
         self.renderer.draw_frame(
-            &mut self.vulkan_backend,
-            &render_data.mesh_requests,
+            &mut self.render_data,
             material_manager,
             asset_store,
-            &mut self.resource_manager,
-            camera_ubo,
-            camera_render_data,
-            directional_light,
             &debug_boxes,
+            &debug_lines,
+            &resources.get::<UiLayout>(),
+            &resources.get::<Draw2D>(),
+            &resources.get::<WorldEnvironment>(),
         );
+        drop(render_span);
+        self.record_stage(FrameStage::Record, record_start.elapsed());
+        if let Some(gpu_time) = self.renderer.gpu_frame_time() {
+            self.record_stage(FrameStage::Gpu, gpu_time);
+        }
+        if self.context.config.latency_mode == LatencyMode::Low {
+            // Input for the next frame is read after this returns, so it reflects what
+            // the player saw on screen.
+            let _span = trace::span("frame", "Wait for present");
+            self.renderer.wait_for_presented_frame();
+        }
+        if let Some(capture) = &mut self.capture {
+            if let Some(frame) = self.renderer.read_frame() {
+                capture.write_frame(&frame);
+            }
+        }
+
+        let pick_request = self
+            .context
+            .resources_mut()
+            .get_mut::<RenderSettings>()
+            .take_pick_request();
+        if let Some(position) = pick_request {
+            let entity = self.renderer.pick_gpu(position);
+            self.context
+                .resources_mut()
+                .get_mut::<RenderSettings>()
+                .set_pick_result(PickResult { position, entity });
+        }
+
+        let dump_requested = self
+            .context
+            .resources_mut()
+            .get_mut::<RenderSettings>()
+            .take_gpu_memory_dump_request();
+        let output_mode = self.renderer.output_mode();
+        self.context
+            .resources_mut()
+            .get_mut::<RenderSettings>()
+            .set_active_output_mode(output_mode);
+        let compiling_pipelines = self.renderer.compiling_pipelines();
+        self.context
+            .resources_mut()
+            .get_mut::<RenderSettings>()
+            .set_compiling_pipelines(compiling_pipelines);
+        let gpu_memory = self.renderer.gpu_memory();
+        if dump_requested {
+            println!("{}", gpu_memory);
+            if let Some(streamed) = self.renderer.streamed_texture_memory() {
+                println!("streamed texture mips: {:.2} MiB", streamed as f64 / (1024.0 * 1024.0));
+            }
+        }
 
         // Accumulate frame time; update the displayed values every DISPLAY_INTERVAL seconds
         // so the title counter is stable and readable rather than flipping every frame.
-        self.frame_time_accum += delta_time;
+        self.frame_time_accum += raw_delta;
         self.frame_count += 1;
 
         if self.frame_time_accum >= DISPLAY_INTERVAL {
@@ -132,29 +400,227 @@ impl Engine {
         }
 
         self.window.set_title(&format!(
-            "{} - FPS: {:.0} - FrameTime: {:.2}ms",
-            WINDOW_TITLE, self.displayed_fps, self.displayed_ms
+            "{} - FPS: {:.0} - FrameTime: {:.2}ms - GPU: {:.0} MiB{}",
+            self.context.config.window_title,
+            self.displayed_fps,
+            self.displayed_ms,
+            gpu_memory.live_bytes() as f64 / (1024.0 * 1024.0),
+            self.over_budget_summary()
         ));
+    }
 
-        self.window.request_redraw();
+    /// Adds a stage's time to the frame budgets. Warns and sends a `BudgetExceeded` event
+    /// once the stage has been over budget for long enough.
+    fn record_stage(&mut self, stage: FrameStage, time: Duration) {
+        let resources = self.context.resources_mut();
+        let alert = {
+            let mut budgets = resources.get_mut::<FrameBudgets>();
+            if !budgets.is_enabled() {
+                return;
+            }
+            budgets.record(stage, time)
+        };
+        if let Some(alert) = alert {
+            eprintln!("warning: frame budget: {}", alert);
+            resources.get_mut::<Events<BudgetExceeded>>().send(alert);
+        }
+    }
+
+    /// Title bar note listing the stages currently over budget, with their averages.
+    fn over_budget_summary(&self) -> String {
+        let budgets = self.context.resources().get::<FrameBudgets>();
+        if !budgets.show_in_title {
+            return String::new();
+        }
+        let ms = |time: Option<Duration>| time.unwrap_or_default().as_secs_f64() * 1000.0;
+        let stages = FrameStage::ALL
+            .into_iter()
+            .filter(|&stage| budgets.is_over_budget(stage))
+            .map(|stage| {
+                let average = ms(budgets.average(stage));
+                let budget = ms(budgets.budget(stage));
+                format!("{} {:.2}/{:.2} ms", stage.name(), average, budget)
+            })
+            .collect::<Vec<_>>();
+        if stages.is_empty() {
+            String::new()
+        } else {
+            format!(" - OVER BUDGET: {}", stages.join(", "))
+        }
+    }
+
+    /// Applies a window mode change requested through [`Displays`] and saves it to the
+    /// settings file. When monitors were plugged in or out, fullscreen is re-applied so
+    /// it falls back to the primary monitor, or returns to the selected one.
+    fn update_display(&mut self) {
+        let mut monitors_changed = false;
+        if self.last_monitor_poll.elapsed() >= MONITOR_POLL_INTERVAL {
+            self.last_monitor_poll = Instant::now();
+            let monitors = Monitors::enumerate(
+                self.window.available_monitors(),
+                self.window.primary_monitor(),
+            );
+            if monitors.infos() != self.monitors.infos() {
+                self.context
+                    .resources_mut()
+                    .get_mut::<Displays>()
+                    .set_monitors(monitors.infos().to_vec());
+                self.monitors = monitors;
+                monitors_changed = true;
+            }
+        }
+
+        let request = self.context.resources_mut().get_mut::<Displays>().take_request();
+        if let Some(request) = &request {
+            self.context.config.window_mode = request.window_mode.clone();
+            self.context.config.display = request.display.clone();
+            self.save_display_settings();
+        }
+
+        let config = &self.context.config;
+        let fullscreen_changed = monitors_changed && config.window_mode != WindowMode::Windowed;
+        if request.is_some() || fullscreen_changed {
+            let fullscreen = self.monitors.fullscreen(
+                &config.window_mode,
+                &config.display,
+                &config.window_resolution,
+            );
+            self.window.set_fullscreen(fullscreen);
+        }
+    }
+
+    fn save_display_settings(&self) {
+        let config = &self.context.config;
+        let mut file = ConfigFile::load_or_default(&config.name);
+        file.graphics_settings.window_mode = config.window_mode.clone();
+        file.graphics_settings.display = config.display.clone();
+        if let Err(e) = file.save(&config.name) {
+            eprintln!("warning: could not save display settings: {}", e);
+        }
+    }
+
+    /// Applies a cursor mode change requested through the input manager to the window.
+    fn apply_cursor_mode(&mut self) {
+        let Some(mode) = self.context.input_mut().take_cursor_change() else {
+            return;
+        };
+
+        let grab = match mode {
+            CursorMode::Free | CursorMode::Hidden => CursorGrabMode::None,
+            CursorMode::Confined => CursorGrabMode::Confined,
+            CursorMode::Locked => CursorGrabMode::Locked,
+        };
+        // Not every platform supports both grab modes; fall back to the other one.
+        let result = self.window.set_cursor_grab(grab).or_else(|_| match grab {
+            CursorGrabMode::Locked => self.window.set_cursor_grab(CursorGrabMode::Confined),
+            CursorGrabMode::Confined => self.window.set_cursor_grab(CursorGrabMode::Locked),
+            CursorGrabMode::None => Ok(()),
+        });
+        if let Err(e) = result {
+            eprintln!("warning: could not set cursor mode {:?}: {}", mode, e);
+        }
+        self.window.set_cursor_visible(mode.is_visible());
+    }
+
+    /// Enables IME on the window while the input manager is in text input mode.
+    fn apply_text_input(&mut self) {
+        if let Some(enabled) = self.context.input_mut().take_text_input_change() {
+            self.window.set_ime_allowed(enabled);
+        }
+    }
+
+    /// Hands a raw input to the input manager, through the recorder if one is active.
+    /// Live input is dropped while a replay drives the app.
+    fn feed_input(&mut self, input: RecordedInput) {
+        if let Some(replay) = &mut self.replay {
+            if !replay.capture(&input) {
+                return;
+            }
+        }
+        self.context.input_mut().apply_recorded(&input);
+    }
+
+    /// Forwards the window-level mouse and text events the input manager tracks.
+    pub fn handle_window_event(&mut self, event: &WindowEvent) {
+        let input = match event {
+            WindowEvent::KeyboardInput { event, .. } if event.state == ElementState::Pressed => {
+                match &event.text {
+                    Some(text) => RecordedInput::Text(text.to_string()),
+                    None => return,
+                }
+            }
+            WindowEvent::Ime(Ime::Preedit(text, cursor)) => RecordedInput::ImePreedit {
+                text: text.clone(),
+                cursor: *cursor,
+            },
+            WindowEvent::Ime(Ime::Commit(text)) => RecordedInput::Text(text.clone()),
+            WindowEvent::CursorMoved { position, .. } => {
+                RecordedInput::MousePosition([position.x as f32, position.y as f32])
+            }
+            WindowEvent::CursorLeft { .. } => RecordedInput::CursorLeft,
+            _ => return,
+        };
+        self.feed_input(input);
+    }
+
+    /// Recreates the swapchain if it is stale. Returns false if this frame cannot be rendered.
+    fn prepare_swapchain(&mut self) -> bool {
+        if self.is_rendering_suspended() {
+            return false;
+        }
+        let size = self.window.inner_size();
+        self.renderer.prepare_surface(size.width, size.height)
+    }
+
+    /// True once a system, game state or the window asked the app to close.
+    pub fn exit_requested(&self) -> bool {
+        self.context.exit_requested()
+    }
+
+    /// Tears the engine down in dependency order: game states and their scenes, then the
+    /// engine context and its resources, then the renderer (which waits for the GPU before
+    /// freeing anything), and finally the window its surface was created from.
+    pub fn shutdown(self) {
+        let Engine {
+            mut context,
+            mut states,
+            renderer,
+            window,
+            replay,
+            capture,
+            ..
+        } = self;
+
+        if let Some(replay) = replay {
+            replay.finish();
+        }
+        if let Some(capture) = capture {
+            capture.finish();
+        }
+        states.clear(&mut context);
+        drop(states);
+        drop(context);
+
+        drop(renderer);
+        drop(window);
     }
 
     /// Forwards a winit device event to the input manager.
     pub fn handle_device_event(&mut self, event: DeviceEvent) {
-        let input = self.context.input_mut();
-
-        match event {
+        let input = match event {
             DeviceEvent::MouseMotion { delta } => {
-                input.on_mouse_moved(delta.0 as f32, delta.1 as f32);
+                RecordedInput::MouseMoved([delta.0 as f32, delta.1 as f32])
             }
             DeviceEvent::Key(raw) => {
-                if let winit::keyboard::PhysicalKey::Code(key_code) = raw.physical_key {
-                    if let Some(key) = convert_winit_keycode(key_code) {
-                        match raw.state {
-                            ElementState::Pressed => input.on_key_pressed(key),
-                            ElementState::Released => input.on_key_released(key),
-                        }
-                    }
+                let winit::keyboard::PhysicalKey::Code(key_code) = raw.physical_key else {
+                    return;
+                };
+                let Some(key) = convert_winit_keycode(key_code) else {
+                    return;
+                };
+                match raw.state {
+                    ElementState::Pressed => RecordedInput::KeyPressed(key),
+                    ElementState::Released => RecordedInput::KeyReleased(key),
                 }
             }
             DeviceEvent::MouseWheel { delta } => {
@@ -162,29 +628,27 @@ impl Engine {
                     winit::event::MouseScrollDelta::LineDelta(_, y) => y,
                     winit::event::MouseScrollDelta::PixelDelta(pos) => pos.y as f32 / 100.0,
                 };
-                input.on_mouse_wheel(scroll);
+                RecordedInput::MouseWheel(scroll)
             }
             DeviceEvent::Button { button, state } => {
                 let btn = match button {
-                    0 => Some(input::MouseButton::Left),
-                    1 => Some(input::MouseButton::Right),
-                    2 => Some(input::MouseButton::Middle),
-                    3 => Some(input::MouseButton::Button4),
-                    4 => Some(input::MouseButton::Button5),
-                    _ => None,
+                    0 => input::MouseButton::Left,
+                    1 => input::MouseButton::Right,
+                    2 => input::MouseButton::Middle,
+                    3 => input::MouseButton::Button4,
+                    4 => input::MouseButton::Button5,
+                    _ => return,
                 };
-                if let Some(btn) = btn {
-                    match state {
-                        ElementState::Pressed => input.on_mouse_button_pressed(btn),
-                        ElementState::Released => input.on_mouse_button_released(btn),
-                    }
+                match state {
+                    ElementState::Pressed => RecordedInput::MouseButtonPressed(btn),
+                    ElementState::Released => RecordedInput::MouseButtonReleased(btn),
                 }
             }
-            _ => {}
-        }
+            _ => return,
+        };
+        self.feed_input(input);
     }
 
-    #[allow(dead_code)]
     pub fn window(&self) -> &Window {
         &self.window
     }
@@ -257,3 +721,43 @@ fn convert_winit_keycode(key: WinitKeyCode) -> Option<input::KeyCode> {
         _ => return None,
     })
 }
+
+/// World-space lines tracing every spline, with a cross on each control point and, for
+/// Bezier splines, lines from the anchors to their handles.
+fn spline_debug_lines(world: &mut ecs::world::World) -> Vec<DebugLine> {
+    const POINTS_PER_SEGMENT: usize = 16;
+    const CROSS_SIZE: f32 = 0.15;
+
+    let mut lines = Vec::new();
+    let mut line = |from: Vec3, to: Vec3, color: Color| lines.push(DebugLine { from, to, color });
+    let mut query = world.query::<(&mut TransformComponent, &mut SplineComponent)>();
+    for (transform, spline) in query.iter() {
+        let model = transform.get_model_matrix();
+        let to_world = |p: &Vec3| (model * p.push(1.0)).xyz();
+
+        let curve = spline.polyline(POINTS_PER_SEGMENT);
+        for pair in curve.windows(2) {
+            line(to_world(&pair[0]), to_world(&pair[1]), Color::YELLOW);
+        }
+        let points = spline.points.iter().map(to_world).collect::<Vec<_>>();
+        for &point in &points {
+            for axis in [Vec3::x(), Vec3::y(), Vec3::z()] {
+                let offset = axis * CROSS_SIZE;
+                line(point - offset, point + offset, Color::MAGENTA);
+            }
+        }
+        if spline.mode == SplineMode::Bezier {
+            // Points past the last full segment are not part of the curve.
+            let handles = points.iter().enumerate().take(3 * spline.segment_count());
+            for (i, &point) in handles {
+                let anchor = match i % 3 {
+                    1 => points[i - 1],
+                    2 => points[(i + 1) % points.len()],
+                    _ => continue,
+                };
+                line(anchor, point, Color::MAGENTA);
+            }
+        }
+    }
+    lines
+}
@@ -0,0 +1,62 @@
+use std::time::{Duration, Instant};
+
+/// Tail of each wait that is busy-spun instead of slept. OS sleeps routinely overshoot by
+/// a millisecond or more, which is enough to miss a 144 Hz deadline.
+const SPIN_MARGIN: Duration = Duration::from_micros(1500);
+
+/// Waits longer than this are left to the event loop so window and input events keep
+/// flowing; only the final stretch blocks the thread.
+const EVENT_LOOP_WAIT_THRESHOLD: Duration = Duration::from_millis(4);
+
+/// Schedules frames on a fixed cadence for the CPU frame limiter.
+pub(crate) struct FramePacer {
+    last_frame: Instant,
+}
+
+impl FramePacer {
+    pub fn new() -> Self {
+        Self {
+            last_frame: Instant::now(),
+        }
+    }
+
+    /// Returns the instant the event loop should wait until before checking again, or
+    /// `None` once the next frame is close enough for [`FramePacer::wait_precise`].
+    pub fn coarse_wait(&self, interval: Duration) -> Option<Instant> {
+        let wake = self.last_frame + interval;
+        let wake = wake.checked_sub(EVENT_LOOP_WAIT_THRESHOLD)?;
+        (Instant::now() < wake).then_some(wake)
+    }
+
+    /// Blocks until the next frame is due, sleeping first and spinning the last stretch.
+    pub fn wait_precise(&self, interval: Duration) {
+        sleep_until(self.last_frame + interval);
+    }
+
+    /// Marks the start of a frame. While the loop keeps up, frames stay on the
+    /// `last + interval` grid so sleep overshoot doesn't accumulate into a lower rate.
+    pub fn frame_started(&mut self, interval: Option<Duration>) {
+        let now = Instant::now();
+        self.last_frame = match interval {
+            Some(interval) if now < self.last_frame + interval * 2 => {
+                (self.last_frame + interval).min(now)
+            }
+            _ => now,
+        };
+    }
+}
+
+/// Hybrid sleep: a regular sleep for the bulk of the wait, then a spin for precision.
+fn sleep_until(deadline: Instant) {
+    let now = Instant::now();
+    if deadline <= now {
+        return;
+    }
+    let remaining = deadline - now;
+    if remaining > SPIN_MARGIN {
+        std::thread::sleep(remaining - SPIN_MARGIN);
+    }
+    while Instant::now() < deadline {
+        std::hint::spin_loop();
+    }
+}
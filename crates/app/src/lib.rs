@@ -1,5 +1,16 @@
 mod app;
 mod app_handler;
+mod crash;
+mod display;
 mod engine;
+mod frame_pacer;
+mod light_probes;
+mod plugin;
+mod replay;
+mod state;
+mod video_capture;
 
 pub use app::*;
+pub use replay::InputReplayMode;
+pub use plugin::{CameraControllerPlugin, OrbitCameraPlugin, Plugin, PluginId};
+pub use state::{GameState, Scene, StateStack, Transition};
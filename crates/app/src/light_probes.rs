@@ -0,0 +1,129 @@
+//! Bakes light probe grids the first frame they show up without probe data.
+//!
+//! The bake runs on the main thread against every mesh in the world that isn't skinned,
+//! lit by the first directional light and the environment's ambient color. It is meant
+//! for coarse grids over small scenes; larger ones should be baked offline with
+//! `asset_pipeline::light_probe_baker` and saved with the scene.
+
+use asset_pipeline::light_probe_baker::{
+    bake_light_probes, ProbeBakeInstance, ProbeBakeSettings,
+};
+use asset_pipeline::lightmap_baker::BakeInstance;
+use assets::AssetStore;
+use common::block_compression::decompress;
+use common::{trace, Color, ColorSpace, ImageData, MeshHandle};
+use core::environment::WorldEnvironment;
+use core::{
+    DirectionalLightComponent, EngineContext, LightProbeGridComponent, MaterialComponent,
+    MeshComponent, SkinnedMeshComponent, TransformComponent,
+};
+use ecs::entity::Entity;
+use material::material_manager::{MaterialHandle, MaterialManager};
+use material::MaterialColorParameter;
+use nalgebra_glm::{Mat4, Vec3};
+use std::f32::consts::PI;
+
+/// Bakes every grid that has no probe data yet. Does nothing, beyond one query, when all
+/// grids are baked.
+pub(crate) fn bake_pending_grids(context: &mut EngineContext) {
+    let world = context.get_world();
+    let pending: Vec<(Entity, Vec<Vec3>)> = world
+        .query::<(Entity, &mut TransformComponent, &mut LightProbeGridComponent)>()
+        .iter()
+        .filter(|(_, _, grid)| !grid.is_baked())
+        .map(|(entity, transform, grid)| (entity, grid.probe_positions(&transform.location)))
+        .collect();
+    if pending.is_empty() {
+        return;
+    }
+
+    let _span = trace::span("frame", "Bake light probes");
+    let meshes: Vec<(MeshHandle, MaterialHandle, Mat4)> = world
+        .query::<(
+            &mut TransformComponent,
+            &mut MeshComponent,
+            &mut MaterialComponent,
+            Option<&mut SkinnedMeshComponent>,
+        )>()
+        .iter()
+        .filter(|(_, _, _, skin)| skin.is_none())
+        .map(|(transform, mesh, material, _)| {
+            (mesh.mesh_handle, material.material_handle, transform.get_model_matrix())
+        })
+        .collect();
+    // The lighting pass shades with `albedo * color * intensity * n·l`, which is the
+    // irradiance divided by π.
+    let sun = world
+        .query::<(&mut TransformComponent, &mut DirectionalLightComponent)>()
+        .iter()
+        .next()
+        .map(|(transform, light)| {
+            (transform.forward(), light.color.to_vec3() * (light.intensity * PI))
+        });
+
+    let environment = context.resources().get::<WorldEnvironment>();
+    let settings = ProbeBakeSettings {
+        sky: environment.ambient_color.to_vec3() * environment.ambient_intensity,
+        sun,
+        ..Default::default()
+    };
+    drop(environment);
+
+    let assets = context.asset_store();
+    let materials = context.materials();
+    let instances: Vec<ProbeBakeInstance> = meshes
+        .iter()
+        .filter_map(|&(mesh, material, transform)| {
+            Some(ProbeBakeInstance {
+                geometry: BakeInstance {
+                    mesh: assets.get(mesh)?,
+                    transform,
+                },
+                albedo: albedo(materials, assets, material),
+            })
+        })
+        .collect();
+    let baked: Vec<_> = pending
+        .into_iter()
+        .map(|(entity, positions)| (entity, bake_light_probes(&instances, &positions, &settings)))
+        .collect();
+
+    let world = context.get_world();
+    for (entity, grid) in world.query::<(Entity, &mut LightProbeGridComponent)>().iter() {
+        if let Some((_, probes)) = baked.iter().find(|(baked, _)| *baked == entity) {
+            grid.probes = probes.clone();
+        }
+    }
+}
+
+/// Linear diffuse color of a material: its constant base color, or the average of its
+/// base color texture.
+fn albedo(materials: &MaterialManager, assets: &AssetStore, material: MaterialHandle) -> Vec3 {
+    match materials.get_base_color(material) {
+        MaterialColorParameter::Constant(color) => color.xyz(),
+        MaterialColorParameter::Handle(texture) => assets
+            .get(texture)
+            .and_then(average_color)
+            .unwrap_or_else(|| Vec3::repeat(1.0)),
+    }
+}
+
+/// Average of the smallest mip level. `None` for formats that can't be decoded on the CPU.
+fn average_color(image: &ImageData) -> Option<Vec3> {
+    let mut smallest = image.mip_tail(image.mip_levels - 1);
+    if smallest.format.is_block_compressed() {
+        smallest = decompress(&smallest)?;
+    }
+    let texels = smallest.pixels.chunks_exact(4);
+    let count = texels.len().max(1) as f32;
+    let sum = texels.fold(Vec3::zeros(), |sum, texel| {
+        let color = match smallest.color_space {
+            ColorSpace::Srgb => Color::srgb_u8(texel[0], texel[1], texel[2]).to_vec3(),
+            ColorSpace::Linear => {
+                Vec3::new(texel[0] as f32, texel[1] as f32, texel[2] as f32) / 255.0
+            }
+        };
+        sum + color
+    });
+    Some(sum / count)
+}
@@ -0,0 +1,197 @@
+use core::system::System;
+use core::systems::{basic_camera_system, orbit_camera_system};
+use core::EngineContext;
+use std::any::TypeId;
+use std::collections::HashSet;
+
+/// Identifies a plugin type for dependency declarations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PluginId {
+    type_id: TypeId,
+    name: &'static str,
+}
+
+impl PluginId {
+    pub fn of<P: Plugin>() -> Self {
+        Self {
+            type_id: TypeId::of::<P>(),
+            name: std::any::type_name::<P>(),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+}
+
+/// Bundles a feature's managers, systems and input bindings into one registration.
+pub trait Plugin: 'static {
+    /// Registers everything the plugin needs on the engine context.
+    fn build(&self, ctx: &mut EngineContext);
+
+    /// Plugins that must be built before this one. Each must also be added to the app.
+    fn dependencies(&self) -> Vec<PluginId> {
+        Vec::new()
+    }
+}
+
+/// Registers the fly-camera controller for entities with a `CameraControllerComponent`.
+/// Added by default; see `AppBuilder::without_default_plugins`.
+pub struct CameraControllerPlugin;
+
+impl Plugin for CameraControllerPlugin {
+    fn build(&self, ctx: &mut EngineContext) {
+        ctx.register_system(Box::new(System::new(basic_camera_system)));
+    }
+}
+
+/// Registers the orbit camera for entities with an `OrbitCameraControllerComponent`.
+/// Use it alongside or instead of the fly camera; each system only drives its own component.
+pub struct OrbitCameraPlugin;
+
+impl Plugin for OrbitCameraPlugin {
+    fn build(&self, ctx: &mut EngineContext) {
+        ctx.register_system(Box::new(System::new(orbit_camera_system)));
+    }
+}
+
+struct PendingPlugin {
+    id: PluginId,
+    plugin: Box<dyn Plugin>,
+}
+
+/// Plugins added to an app, built in dependency order.
+#[derive(Default)]
+pub(crate) struct PluginSet {
+    pending: Vec<PendingPlugin>,
+    built: HashSet<PluginId>,
+}
+
+impl PluginSet {
+    /// Queues `plugin`. Adding a plugin type that is already present is ignored.
+    pub fn add<P: Plugin>(&mut self, plugin: P) {
+        let id = PluginId::of::<P>();
+        if self.built.contains(&id) || self.pending.iter().any(|p| p.id == id) {
+            eprintln!("warning: plugin '{}' added twice; ignoring", id.name);
+            return;
+        }
+        self.pending.push(PendingPlugin {
+            id,
+            plugin: Box::new(plugin),
+        });
+    }
+
+    /// Builds every queued plugin after its dependencies.
+    ///
+    /// Panics if a dependency was never added or the dependencies form a cycle.
+    pub fn build(&mut self, ctx: &mut EngineContext) {
+        for index in self.build_order() {
+            let pending = &self.pending[index];
+            pending.plugin.build(ctx);
+            self.built.insert(pending.id);
+        }
+        self.pending.clear();
+    }
+
+    fn build_order(&self) -> Vec<usize> {
+        let mut order = Vec::with_capacity(self.pending.len());
+        let mut done = self.built.clone();
+        let mut remaining = (0..self.pending.len()).collect::<Vec<_>>();
+
+        while !remaining.is_empty() {
+            let ready = remaining.iter().position(|&index| {
+                self.pending[index]
+                    .plugin
+                    .dependencies()
+                    .iter()
+                    .all(|dep| done.contains(dep))
+            });
+
+            let Some(position) = ready else {
+                panic!("{}", self.unresolved_message(&remaining, &done));
+            };
+            let index = remaining.remove(position);
+            done.insert(self.pending[index].id);
+            order.push(index);
+        }
+        order
+    }
+
+    fn unresolved_message(&self, remaining: &[usize], done: &HashSet<PluginId>) -> String {
+        let queued = remaining
+            .iter()
+            .map(|&index| self.pending[index].id)
+            .collect::<HashSet<_>>();
+
+        for &index in remaining {
+            let pending = &self.pending[index];
+            for dep in pending.plugin.dependencies() {
+                if !done.contains(&dep) && !queued.contains(&dep) {
+                    return format!(
+                        "plugin '{}' depends on '{}', which was never added",
+                        pending.id.name, dep.name
+                    );
+                }
+            }
+        }
+
+        let names = remaining
+            .iter()
+            .map(|&index| self.pending[index].id.name)
+            .collect::<Vec<_>>();
+        format!("plugin dependency cycle between: {}", names.join(", "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Base;
+    struct Middle;
+    struct Top;
+
+    impl Plugin for Base {
+        fn build(&self, _ctx: &mut EngineContext) {}
+    }
+
+    impl Plugin for Middle {
+        fn build(&self, _ctx: &mut EngineContext) {}
+        fn dependencies(&self) -> Vec<PluginId> {
+            vec![PluginId::of::<Base>()]
+        }
+    }
+
+    impl Plugin for Top {
+        fn build(&self, _ctx: &mut EngineContext) {}
+        fn dependencies(&self) -> Vec<PluginId> {
+            vec![PluginId::of::<Middle>(), PluginId::of::<Base>()]
+        }
+    }
+
+    #[test]
+    fn dependencies_build_first() {
+        let mut set = PluginSet::default();
+        set.add(Top);
+        set.add(Middle);
+        set.add(Base);
+
+        let order = set
+            .build_order()
+            .into_iter()
+            .map(|index| set.pending[index].id)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            order,
+            vec![PluginId::of::<Base>(), PluginId::of::<Middle>(), PluginId::of::<Top>()]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "never added")]
+    fn missing_dependency_panics() {
+        let mut set = PluginSet::default();
+        set.add(Middle);
+        set.build_order();
+    }
+}
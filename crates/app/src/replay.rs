@@ -0,0 +1,123 @@
+use input::{InputManager, InputRecording, RecordedInput};
+use std::path::PathBuf;
+
+/// Records live input to a file, or drives the app from a previous recording.
+///
+/// In both modes every frame advances time by exactly one fixed step, so a replay sees
+/// the same deltas, input and fixed ticks as the recorded run. Anything else the game
+/// reads (random seeds, wall-clock time) must be made deterministic by the game itself.
+#[derive(Debug, Clone)]
+pub enum InputReplayMode {
+    /// Records input and writes it to the path when the app exits.
+    Record(PathBuf),
+    /// Ignores live input and replays the recording; the app exits once it runs out.
+    Replay(PathBuf),
+}
+
+pub(crate) enum InputReplay {
+    Record {
+        path: PathBuf,
+        recording: InputRecording,
+        pending: Vec<RecordedInput>,
+    },
+    Replay {
+        recording: InputRecording,
+        next_tick: usize,
+    },
+}
+
+impl InputReplay {
+    pub fn new(mode: InputReplayMode, fixed_delta: f32) -> Self {
+        match mode {
+            InputReplayMode::Record(path) => InputReplay::Record {
+                path,
+                recording: InputRecording::new(fixed_delta),
+                pending: Vec::new(),
+            },
+            InputReplayMode::Replay(path) => {
+                let recording = InputRecording::load(&path).unwrap_or_else(|e| {
+                    panic!("failed to load input recording '{}': {}", path.display(), e)
+                });
+                if recording.fixed_delta != fixed_delta {
+                    eprintln!(
+                        "warning: input recording '{}' was made at a {}s fixed step, app runs at {}s",
+                        path.display(),
+                        recording.fixed_delta,
+                        fixed_delta
+                    );
+                }
+                InputReplay::Replay {
+                    recording,
+                    next_tick: 0,
+                }
+            }
+        }
+    }
+
+    /// Delta time of every frame while recording or replaying.
+    pub fn fixed_delta(&self) -> f32 {
+        match self {
+            InputReplay::Record { recording, .. } | InputReplay::Replay { recording, .. } => {
+                recording.fixed_delta
+            }
+        }
+    }
+
+    /// Returns true if live input should reach the input manager.
+    pub fn capture(&mut self, input: &RecordedInput) -> bool {
+        match self {
+            InputReplay::Record { pending, .. } => {
+                pending.push(input.clone());
+                true
+            }
+            InputReplay::Replay { .. } => false,
+        }
+    }
+
+    /// Closes the input gathered for this tick, or feeds the next recorded tick into
+    /// `input`. Returns false once a replay has no ticks left.
+    pub fn begin_tick(&mut self, input: &mut InputManager) -> bool {
+        match self {
+            InputReplay::Record {
+                recording, pending, ..
+            } => {
+                recording.push_tick(std::mem::take(pending));
+                true
+            }
+            InputReplay::Replay {
+                recording,
+                next_tick,
+            } => {
+                let Some(tick) = recording.ticks.get(*next_tick) else {
+                    return false;
+                };
+                for recorded in &tick.inputs {
+                    input.apply_recorded(recorded);
+                }
+                *next_tick += 1;
+                true
+            }
+        }
+    }
+
+    /// Writes a recording to disk. Replays have nothing to save.
+    pub fn finish(self) {
+        if let InputReplay::Record {
+            path, recording, ..
+        } = self
+        {
+            match recording.save(&path) {
+                Ok(()) => println!(
+                    "Recorded {} input ticks to '{}'",
+                    recording.ticks.len(),
+                    path.display()
+                ),
+                Err(e) => eprintln!(
+                    "warning: could not save input recording '{}': {}",
+                    path.display(),
+                    e
+                ),
+            }
+        }
+    }
+}
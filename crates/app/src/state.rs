@@ -0,0 +1,293 @@
+use core::system::SystemFunction;
+use core::EngineContext;
+use ecs::entity::Entity;
+
+/// What the state stack should do after a state's `update`.
+pub enum Transition {
+    None,
+    /// Pause the current state and enter a new one on top of it.
+    Push(Box<dyn GameState>),
+    /// Exit the current state and resume the one below.
+    Pop,
+    /// Exit the current state and enter a new one in its place.
+    Switch(Box<dyn GameState>),
+}
+
+/// Entities owned by a game state. Everything added here is despawned when the state
+/// exits, so each state can build its own scene without cleaning up after itself.
+#[derive(Default)]
+pub struct Scene {
+    entities: Vec<Entity>,
+}
+
+impl Scene {
+    /// Hands ownership of `entity` to the state. Do not despawn it elsewhere.
+    pub fn add(&mut self, entity: Entity) -> Entity {
+        self.entities.push(entity);
+        entity
+    }
+
+    pub fn entities(&self) -> &[Entity] {
+        &self.entities
+    }
+}
+
+/// One screen or mode of a game, such as loading, main menu, in-game or paused.
+///
+/// Only the top state on the stack is updated and only its systems run. States below
+/// it are paused but keep their scene, so a pause menu can be pushed over gameplay.
+pub trait GameState {
+    fn name(&self) -> &str;
+
+    /// Systems that run while this state is on top. Called once, when the state is entered.
+    fn systems(&mut self) -> Vec<Box<dyn SystemFunction>> {
+        Vec::new()
+    }
+
+    fn on_enter(&mut self, _ctx: &mut EngineContext, _scene: &mut Scene) {}
+
+    /// Called before the state's scene is despawned.
+    fn on_exit(&mut self, _ctx: &mut EngineContext) {}
+
+    /// Another state was pushed on top of this one.
+    fn on_pause(&mut self, _ctx: &mut EngineContext) {}
+
+    /// The state above this one was popped.
+    fn on_resume(&mut self, _ctx: &mut EngineContext) {}
+
+    /// Runs once per frame before the ECS systems.
+    fn update(&mut self, _ctx: &mut EngineContext, _dt: f32) -> Transition {
+        Transition::None
+    }
+}
+
+struct StateEntry {
+    state: Box<dyn GameState>,
+    scene: Scene,
+    /// The state's systems while it is paused. While on top they live in the context.
+    systems: Vec<Box<dyn SystemFunction>>,
+}
+
+/// Stack of game states driven by the engine loop.
+#[derive(Default)]
+pub struct StateStack {
+    entries: Vec<StateEntry>,
+    pending: Vec<Transition>,
+}
+
+impl StateStack {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queues a state to be pushed at the start of the next frame.
+    pub fn push(&mut self, state: Box<dyn GameState>) {
+        self.pending.push(Transition::Push(state));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty() && self.pending.is_empty()
+    }
+
+    /// Name of the state currently on top, if any.
+    pub fn current(&self) -> Option<&str> {
+        self.entries.last().map(|entry| entry.state.name())
+    }
+
+    /// Applies queued transitions, then updates the top state.
+    pub(crate) fn update(&mut self, ctx: &mut EngineContext, dt: f32) {
+        for transition in std::mem::take(&mut self.pending) {
+            self.apply(transition, ctx);
+        }
+
+        let Some(top) = self.entries.last_mut() else {
+            return;
+        };
+        let transition = top.state.update(ctx, dt);
+        self.apply(transition, ctx);
+    }
+
+    /// Exits every state, top first.
+    pub(crate) fn clear(&mut self, ctx: &mut EngineContext) {
+        self.pending.clear();
+        while !self.entries.is_empty() {
+            self.exit_top(ctx);
+        }
+    }
+
+    fn apply(&mut self, transition: Transition, ctx: &mut EngineContext) {
+        match transition {
+            Transition::None => {}
+            Transition::Push(state) => {
+                if let Some(top) = self.entries.last_mut() {
+                    top.systems = ctx.replace_state_systems(Vec::new());
+                    top.state.on_pause(ctx);
+                }
+                self.enter(state, ctx);
+            }
+            Transition::Pop => {
+                self.exit_top(ctx);
+                if let Some(top) = self.entries.last_mut() {
+                    top.state.on_resume(ctx);
+                    ctx.replace_state_systems(std::mem::take(&mut top.systems));
+                }
+            }
+            Transition::Switch(state) => {
+                self.exit_top(ctx);
+                self.enter(state, ctx);
+            }
+        }
+    }
+
+    fn enter(&mut self, mut state: Box<dyn GameState>, ctx: &mut EngineContext) {
+        let mut scene = Scene::default();
+        let systems = state.systems();
+        state.on_enter(ctx, &mut scene);
+        ctx.replace_state_systems(systems);
+        self.entries.push(StateEntry {
+            state,
+            scene,
+            systems: Vec::new(),
+        });
+    }
+
+    fn exit_top(&mut self, ctx: &mut EngineContext) {
+        let Some(mut entry) = self.entries.pop() else {
+            return;
+        };
+        ctx.replace_state_systems(Vec::new());
+        entry.state.on_exit(ctx);
+        let world = ctx.get_world();
+        for entity in entry.scene.entities {
+            world.remove_entity(entity);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::testing::headless_engine;
+    use core::TransformComponent;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    type Log = Rc<RefCell<Vec<String>>>;
+
+    /// Logs its callbacks, spawns one entity into its scene and returns `next` from its
+    /// first update.
+    struct Recorded {
+        name: &'static str,
+        log: Log,
+        next: Option<Transition>,
+    }
+
+    impl Recorded {
+        fn new(name: &'static str, log: &Log, next: Option<Transition>) -> Box<Self> {
+            Box::new(Self {
+                name,
+                log: log.clone(),
+                next,
+            })
+        }
+
+        fn record(&self, event: &str) {
+            self.log.borrow_mut().push(format!("{} {}", event, self.name));
+        }
+    }
+
+    impl GameState for Recorded {
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        fn on_enter(&mut self, ctx: &mut EngineContext, scene: &mut Scene) {
+            self.record("enter");
+            scene.add(ctx.get_world().create_entity((TransformComponent::default(),)));
+        }
+
+        fn on_exit(&mut self, _ctx: &mut EngineContext) {
+            self.record("exit");
+        }
+
+        fn on_pause(&mut self, _ctx: &mut EngineContext) {
+            self.record("pause");
+        }
+
+        fn on_resume(&mut self, _ctx: &mut EngineContext) {
+            self.record("resume");
+        }
+
+        fn update(&mut self, _ctx: &mut EngineContext, _dt: f32) -> Transition {
+            self.record("update");
+            self.next.take().unwrap_or(Transition::None)
+        }
+    }
+
+    fn entity_count(ctx: &mut EngineContext) -> usize {
+        ctx.get_world().query::<&mut TransformComponent>().iter().count()
+    }
+
+    #[test]
+    fn push_pop_and_switch_call_enter_and_exit_in_order() {
+        let mut ctx = headless_engine();
+        let log = Log::default();
+        let popped = Recorded::new("popped", &log, Some(Transition::Pop));
+        let switched = Recorded::new("switched", &log, None);
+        let base = Recorded::new("base", &log, Some(Transition::Push(popped)));
+        let mut stack = StateStack::new();
+        stack.push(base);
+
+        stack.update(&mut ctx, 0.0);
+        assert_eq!(stack.current(), Some("popped"));
+        assert_eq!(entity_count(&mut ctx), 2);
+        stack.update(&mut ctx, 0.0);
+        assert_eq!(stack.current(), Some("base"));
+        assert_eq!(entity_count(&mut ctx), 1);
+
+        stack.apply(Transition::Switch(switched), &mut ctx);
+        assert_eq!(stack.current(), Some("switched"));
+        assert_eq!(entity_count(&mut ctx), 1);
+        stack.clear(&mut ctx);
+        assert_eq!(entity_count(&mut ctx), 0);
+
+        assert_eq!(
+            *log.borrow(),
+            [
+                "enter base",
+                "update base",
+                "pause base",
+                "enter popped",
+                "update popped",
+                "exit popped",
+                "resume base",
+                "exit base",
+                "enter switched",
+                "exit switched",
+            ]
+        );
+    }
+
+    #[test]
+    fn queued_pushes_apply_at_the_start_of_the_next_update() {
+        let mut ctx = headless_engine();
+        let log = Log::default();
+        let mut stack = StateStack::new();
+        stack.push(Recorded::new("base", &log, None));
+        assert!(!stack.is_empty());
+        assert_eq!(stack.current(), None);
+        assert!(log.borrow().is_empty());
+
+        stack.update(&mut ctx, 0.0);
+        assert_eq!(stack.current(), Some("base"));
+        stack.push(Recorded::new("overlay", &log, None));
+        assert_eq!(stack.current(), Some("base"));
+
+        stack.update(&mut ctx, 0.0);
+        assert_eq!(stack.current(), Some("overlay"));
+        assert_eq!(
+            *log.borrow(),
+            ["enter base", "update base", "pause base", "enter overlay", "update overlay"]
+        );
+    }
+}
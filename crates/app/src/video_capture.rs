@@ -0,0 +1,83 @@
+use common::ImageData;
+use std::path::PathBuf;
+
+/// Writes every rendered frame to `dir` as a numbered PNG sequence while game time
+/// advances by exactly one video frame per engine frame, however long rendering takes.
+/// Encode the result with e.g. `ffmpeg -framerate 60 -i frame_%06d.png capture.mp4`.
+pub(crate) struct VideoCapture {
+    dir: PathBuf,
+    fps: u32,
+    frames_written: u32,
+}
+
+impl VideoCapture {
+    pub fn new(dir: PathBuf, fps: u32) -> Self {
+        std::fs::create_dir_all(&dir).unwrap_or_else(|e| {
+            panic!(
+                "failed to create capture directory '{}': {}",
+                dir.display(),
+                e
+            )
+        });
+        Self {
+            dir,
+            fps,
+            frames_written: 0,
+        }
+    }
+
+    /// Delta time of every frame while capturing.
+    pub fn frame_delta(&self) -> f32 {
+        1.0 / self.fps as f32
+    }
+
+    pub fn write_frame(&mut self, frame: &ImageData) {
+        let path = self
+            .dir
+            .join(format!("frame_{:06}.png", self.frames_written));
+        let result = image::save_buffer(
+            &path,
+            &frame.pixels,
+            frame.width,
+            frame.height,
+            image::ExtendedColorType::Rgba8,
+        );
+        match result {
+            Ok(()) => self.frames_written += 1,
+            Err(e) => eprintln!("failed to write capture frame '{}': {}", path.display(), e),
+        }
+    }
+
+    pub fn finish(self) {
+        println!(
+            "Captured {} frames at {} fps to '{}'",
+            self.frames_written,
+            self.fps,
+            self.dir.display()
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::ColorSpace;
+
+    #[test]
+    fn frames_are_numbered_pngs() {
+        let dir = std::env::temp_dir().join(format!("capture_{}", std::process::id()));
+        let mut capture = VideoCapture::new(dir.clone(), 30);
+        assert_eq!(capture.frame_delta(), 1.0 / 30.0);
+
+        let frame = ImageData::rgba8(vec![255, 0, 0, 255, 0, 0, 255, 255], 2, 1, ColorSpace::Srgb);
+        capture.write_frame(&frame);
+        capture.write_frame(&frame);
+
+        let second = image::open(dir.join("frame_000001.png"))
+            .unwrap()
+            .to_rgba8();
+        assert_eq!(second.dimensions(), (2, 1));
+        assert_eq!(second.as_raw(), &frame.pixels);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,10 +1,10 @@
 use assets::AssetStore;
+use common::math::{vec4, Vec4};
 use common::{Guid, ImageHandle};
 use material::{
     Material, MaterialColorParameter, MaterialParameter, MaterialParameterBinding,
     MaterialParameterBindingData, PbrMaterial, ShaderRef,
 };
-use nalgebra_glm::{vec4, Vec4};
 use project::{resolve_cooked_path, AssetRegistry};
 use serde::Deserialize;
 use std::collections::HashMap;
@@ -24,6 +24,9 @@ pub struct EmatFile {
     /// Fragment shader: a UUID string resolves to `ShaderRef::Asset`, a plain name to `ShaderRef::BuiltIn`.
     /// Required when `type` is absent (custom material). Defaults to built-in PBR for `type = "pbr"`.
     pub fragment_shader: Option<String>,
+    /// Scalar params left out default to 0.0, except `ambient_occlusion`, which defaults
+    /// to 1.0 (unoccluded) because it scales ambient light. Materials are built from the
+    /// source file on load, so the default applies to existing files without recooking.
     #[serde(default)]
     pub params: HashMap<String, ParamValue>,
 }
@@ -120,10 +123,17 @@ impl EmatFile {
             fragment_shader: parse_shader_ref(self.fragment_shader.as_deref(), "pbr.frag"),
             base_color: self.color_param("base_color", cache_dir, registry, assets)?,
             normal: self.color_param("normal", cache_dir, registry, assets)?,
-            ambient_occlusion: self.scalar_param("ambient_occlusion", cache_dir, registry, assets)?,
-            metallic: self.scalar_param("metallic", cache_dir, registry, assets)?,
-            roughness: self.scalar_param("roughness", cache_dir, registry, assets)?,
-            specular: self.scalar_param("specular", cache_dir, registry, assets)?,
+            // Not 0.0 like the other scalars: see `params`.
+            ambient_occlusion: self.scalar_param(
+                "ambient_occlusion",
+                1.0,
+                cache_dir,
+                registry,
+                assets,
+            )?,
+            metallic: self.scalar_param("metallic", 0.0, cache_dir, registry, assets)?,
+            roughness: self.scalar_param("roughness", 0.0, cache_dir, registry, assets)?,
+            specular: self.scalar_param("specular", 0.0, cache_dir, registry, assets)?,
         }
         .build())
     }
@@ -157,6 +167,7 @@ impl EmatFile {
             active_defines: Vec::new(),
             bindings,
             push_constants: Vec::new(),
+            base_color: MaterialColorParameter::Constant(vec4(1.0, 1.0, 1.0, 1.0)),
         })
     }
 
@@ -185,6 +196,7 @@ impl EmatFile {
     fn scalar_param(
         &self,
         name: &str,
+        default: f32,
         cache_dir: &Path,
         registry: &AssetRegistry,
         assets: &mut AssetStore,
@@ -199,7 +211,7 @@ impl EmatFile {
                 "param '{}': vec4 cannot be used for a scalar slot",
                 name
             ))),
-            None => Ok(MaterialParameter::Constant(0.0)),
+            None => Ok(MaterialParameter::Constant(default)),
         }
     }
 
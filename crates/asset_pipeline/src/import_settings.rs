@@ -0,0 +1,106 @@
+//! Typed views of the `[import]` table in a source asset's `.meta` sidecar:
+//!
+//! ```toml
+//! guid = "..."
+//!
+//! [import]
+//! scale = 0.01
+//! generate_normals = true
+//! ```
+//!
+//! Missing keys keep their defaults. Editing the table changes the asset's import hash,
+//! so the next cook picks the new settings up.
+
+use assets::TextureCompression;
+use common::{ColorSpace, PixelFormat, VertexEncoding};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MeshImportSettings {
+    /// Uniform scale applied to positions, e.g. `0.01` for sources authored in
+    /// centimeters.
+    pub scale: f32,
+    /// Replaces authored normals and tangents with generated smooth ones.
+    pub generate_normals: bool,
+    /// `"packed"` stores vertices in less than half the memory, at reduced precision;
+    /// see [`VertexEncoding::Packed`].
+    pub vertex_encoding: VertexEncoding,
+}
+
+impl Default for MeshImportSettings {
+    fn default() -> Self {
+        Self {
+            scale: 1.0,
+            generate_normals: false,
+            vertex_encoding: VertexEncoding::Full,
+        }
+    }
+}
+
+impl MeshImportSettings {
+    pub fn from_table(table: &toml::Table) -> Result<Self, toml::de::Error> {
+        from_table(table)
+    }
+}
+
+/// KTX2 sources keep the format, mips and color space stored in the file and only use
+/// `compression`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TextureImportSettings {
+    /// `"srgb"` for color textures such as albedo; the default `"linear"` for data
+    /// such as normal maps and masks.
+    pub color_space: ColorSpace,
+    pub compression: TextureCompression,
+    /// GPU format of the cooked texels: `"bc7"` for color, `"bc5"` for normal maps, or
+    /// the default `"rgba8"`. Both block formats take a quarter of the memory of RGBA8.
+    pub format: PixelFormat,
+    /// Generates the full mip chain down to 1x1.
+    pub mipmaps: bool,
+}
+
+impl Default for TextureImportSettings {
+    fn default() -> Self {
+        Self {
+            color_space: ColorSpace::default(),
+            compression: TextureCompression::default(),
+            format: PixelFormat::default(),
+            mipmaps: true,
+        }
+    }
+}
+
+impl TextureImportSettings {
+    pub fn from_table(table: &toml::Table) -> Result<Self, toml::de::Error> {
+        from_table(table)
+    }
+}
+
+/// Unknown keys are rejected, so a typo fails the cook instead of silently using the
+/// default.
+fn from_table<T: DeserializeOwned>(table: &toml::Table) -> Result<T, toml::de::Error> {
+    table.clone().try_into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_keys_default_and_unknown_keys_fail() {
+        let table: toml::Table = "color_space = \"srgb\"".parse().unwrap();
+        let settings = TextureImportSettings::from_table(&table).unwrap();
+        assert_eq!(settings.color_space, ColorSpace::Srgb);
+        assert_eq!(settings.compression, TextureCompression::None);
+        assert!(settings.mipmaps);
+
+        let table: toml::Table = "scael = 0.01".parse().unwrap();
+        assert!(MeshImportSettings::from_table(&table).is_err());
+        assert_eq!(
+            MeshImportSettings::from_table(&toml::Table::new()).unwrap(),
+            MeshImportSettings::default()
+        );
+    }
+}
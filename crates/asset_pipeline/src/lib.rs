@@ -1,11 +1,16 @@
 pub mod codegen;
 pub mod emat;
+pub mod import_settings;
+pub mod light_probe_baker;
+pub mod lightmap_baker;
 pub mod mesh_conditioner;
+pub mod mesh_geometry;
 mod shader;
 pub mod shader_conditioner;
 pub mod texture_conditioner;
 
 pub use emat::{EmatError, EmatFile};
+pub use import_settings::{MeshImportSettings, TextureImportSettings};
 pub use mesh_conditioner::{MeshConditionError, MeshConditioner};
 pub use shader_conditioner::{ShaderConditionError, ShaderConditioner};
 use std::path::{Path, PathBuf};
@@ -22,7 +27,10 @@ pub fn cook_pending(registry: &AssetRegistry, cache_dir: &Path, content_dir: &Pa
             AssetType::Mesh => {
                 let src = content_dir.join(&record.source_path);
                 let dst = resolve_cooked_path(cache_dir, &record.guid, "emesh");
-                match MeshConditioner::condition(&src, &dst) {
+                let result = MeshImportSettings::from_table(&record.import)
+                    .map_err(MeshConditionError::Settings)
+                    .and_then(|settings| MeshConditioner::condition(&src, &dst, &settings));
+                match result {
                     Ok(()) => println!("cooked mesh: {}", record.source_path.display()),
                     Err(e) => eprintln!(
                         "warning: failed to cook '{}': {}",
@@ -34,7 +42,10 @@ pub fn cook_pending(registry: &AssetRegistry, cache_dir: &Path, content_dir: &Pa
             AssetType::Texture => {
                 let src = content_dir.join(&record.source_path);
                 let dst = resolve_cooked_path(cache_dir, &record.guid, "etex");
-                match TextureConditioner::condition(&src, &dst) {
+                let result = TextureImportSettings::from_table(&record.import)
+                    .map_err(TextureConditionError::Settings)
+                    .and_then(|settings| TextureConditioner::condition(&src, &dst, &settings));
+                match result {
                     Ok(()) => println!("cooked texture: {}", record.source_path.display()),
                     Err(e) => eprintln!(
                         "warning: failed to cook '{}': {}",
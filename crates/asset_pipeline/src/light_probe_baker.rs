@@ -0,0 +1,184 @@
+use crate::lightmap_baker::{radical_inverse, BakeInstance, Occluder};
+use common::math::Vec3;
+use common::{LightProbe, SphericalHarmonics};
+use std::f32::consts::{PI, TAU};
+
+/// A static mesh placed in the scene being baked, with the color it reflects.
+pub struct ProbeBakeInstance<'a> {
+    pub geometry: BakeInstance<'a>,
+    /// Average linear diffuse color of the mesh's material.
+    pub albedo: Vec3,
+}
+
+#[derive(Clone, Debug)]
+pub struct ProbeBakeSettings {
+    /// Rays cast per probe.
+    pub samples: u32,
+    /// Rays that travel further than this without hitting anything see the sky.
+    pub max_distance: f32,
+    /// Radiance of the sky. It lights the surfaces rays hit from above the horizon,
+    /// ignoring occlusion.
+    pub sky: Vec3,
+    /// Direction the sun shines in and its irradiance on a surface facing it. Surfaces
+    /// rays hit are shadowed from it.
+    pub sun: Option<(Vec3, Vec3)>,
+}
+
+impl Default for ProbeBakeSettings {
+    fn default() -> Self {
+        Self {
+            samples: 256,
+            max_distance: 50.0,
+            sky: Vec3::repeat(0.3),
+            sun: None,
+        }
+    }
+}
+
+/// Bakes a light probe at each of `positions`, with every instance acting as a
+/// reflector and occluder. Returned in position order.
+///
+/// Each probe records one bounce: light from the sun and sky reflected towards it by the
+/// surfaces it sees, and how much of the sky it sees directly. Rays are tested against
+/// every triangle, so this is meant for small static scenes and coarse grids.
+pub fn bake_light_probes(
+    instances: &[ProbeBakeInstance],
+    positions: &[Vec3],
+    settings: &ProbeBakeSettings,
+) -> Vec<LightProbe> {
+    let occluders: Vec<Occluder> = instances
+        .iter()
+        .map(|instance| Occluder::new(&instance.geometry))
+        .collect();
+    positions
+        .iter()
+        .enumerate()
+        .map(|(index, position)| bake_probe(position, index, instances, &occluders, settings))
+        .collect()
+}
+
+fn bake_probe(
+    position: &Vec3,
+    index: usize,
+    instances: &[ProbeBakeInstance],
+    occluders: &[Occluder],
+    settings: &ProbeBakeSettings,
+) -> LightProbe {
+    let samples = settings.samples.max(1);
+    let weight = 4.0 * PI / samples as f32;
+    // Per-probe rotation of the sample pattern, so neighboring probes don't share
+    // the same blind spots.
+    let jitter = radical_inverse(index as u32 ^ 0x5bd1_e995);
+
+    let mut bounce = SphericalHarmonics::default();
+    let mut open = 0;
+    for i in 0..samples {
+        let z = 1.0 - 2.0 * (i as f32 + 0.5) / samples as f32;
+        let r = (1.0 - z * z).max(0.0).sqrt();
+        let phi = TAU * (radical_inverse(i) + jitter).fract();
+        let dir = Vec3::new(r * phi.cos(), z, r * phi.sin());
+
+        let hit = occluders
+            .iter()
+            .zip(instances)
+            .filter_map(|(occluder, instance)| {
+                occluder
+                    .nearest_hit(position, &dir, settings.max_distance)
+                    .map(|(t, normal)| (t, normal, instance.albedo))
+            })
+            .min_by(|a, b| a.0.total_cmp(&b.0));
+        let Some((t, normal, albedo)) = hit else {
+            open += 1;
+            continue;
+        };
+
+        let point = position + dir * t;
+        let mut irradiance = settings.sky * (PI * (0.5 + 0.5 * normal.y));
+        if let Some((sun_dir, sun)) = &settings.sun {
+            let to_sun = -sun_dir.normalize();
+            let facing = normal.dot(&to_sun);
+            let origin = point + normal * (settings.max_distance * 1e-4);
+            if facing > 0.0
+                && !occluders
+                    .iter()
+                    .any(|occluder| occluder.occludes(&origin, &to_sun, f32::MAX))
+            {
+                irradiance += sun * facing;
+            }
+        }
+        let radiance = albedo.component_mul(&irradiance) / PI;
+        bounce.add_sample(&dir, &radiance, weight);
+    }
+
+    LightProbe {
+        bounce,
+        sky_visibility: open as f32 / samples as f32,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::math::Mat4;
+    use common::{MeshData, SubMesh, Vertex, VertexEncoding};
+
+    /// A 10 by 10 quad on the YZ plane at `x`.
+    fn wall(x: f32) -> MeshData {
+        let vertex = |y: f32, z: f32| Vertex {
+            pos: Vec3::new(x, y, z),
+            normal: -Vec3::x(),
+            ..Default::default()
+        };
+        MeshData {
+            vertices: vec![
+                vertex(-5.0, -5.0),
+                vertex(5.0, -5.0),
+                vertex(5.0, 5.0),
+                vertex(-5.0, 5.0),
+            ],
+            vertex_encoding: VertexEncoding::Full,
+            indices: vec![0, 1, 2, 0, 2, 3],
+            extras: None,
+            skin: None,
+            submeshes: vec![SubMesh {
+                index_offset: 0,
+                index_count: 6,
+            }],
+        }
+    }
+
+    #[test]
+    fn red_wall_tints_the_side_facing_it() {
+        let wall = wall(1.0);
+        let settings = ProbeBakeSettings {
+            samples: 512,
+            max_distance: 10.0,
+            sky: Vec3::repeat(1.0),
+            sun: Some((Vec3::new(1.0, -1.0, 0.0), Vec3::repeat(3.0))),
+        };
+        let instances = [ProbeBakeInstance {
+            geometry: BakeInstance {
+                mesh: &wall,
+                transform: Mat4::identity(),
+            },
+            albedo: Vec3::new(0.8, 0.1, 0.1),
+        }];
+        let probes = bake_light_probes(&instances, &[Vec3::zeros()], &settings);
+
+        let probe = &probes[0];
+        // The wall covers a bit less than half of the probe's view.
+        assert!(
+            (0.5..0.75).contains(&probe.sky_visibility),
+            "{}",
+            probe.sky_visibility
+        );
+        let towards_wall = probe.bounce.irradiance(&Vec3::x());
+        let away = probe.bounce.irradiance(&-Vec3::x());
+        assert!(towards_wall.x > 4.0 * towards_wall.y, "{towards_wall}");
+        assert!(towards_wall.x > 4.0 * away.x, "{towards_wall} vs {away}");
+
+        let unlit = bake_light_probes(&[], &[Vec3::zeros()], &settings);
+        assert_eq!(unlit[0].sky_visibility, 1.0);
+        assert_eq!(unlit[0].bounce, SphericalHarmonics::default());
+    }
+}
@@ -0,0 +1,411 @@
+use crate::mesh_geometry::any_perpendicular;
+use common::math::{Mat3, Mat4, Point3, Vec2, Vec3};
+use common::{ColorSpace, ImageData, MeshData};
+use std::path::Path;
+
+/// A static mesh placed in the scene being baked.
+pub struct BakeInstance<'a> {
+    pub mesh: &'a MeshData,
+    /// Local-to-world transform.
+    pub transform: Mat4,
+}
+
+#[derive(Clone, Debug)]
+pub struct AoBakeSettings {
+    /// Width and height of each lightmap, in texels.
+    pub resolution: u32,
+    /// Rays cast per texel.
+    pub samples: u32,
+    /// Occluders further away than this do not darken a texel.
+    pub max_distance: f32,
+    /// Texels each UV island is grown by, so filtering at island edges doesn't pull in
+    /// unbaked texels.
+    pub padding: u32,
+}
+
+impl Default for AoBakeSettings {
+    fn default() -> Self {
+        Self {
+            resolution: 128,
+            samples: 64,
+            max_distance: 2.0,
+            padding: 2,
+        }
+    }
+}
+
+/// Bakes ambient occlusion for each instance into its own lightmap, with every instance
+/// acting as an occluder. Lightmaps are laid out with the mesh's second UV set, or its
+/// first if it has none, and returned in instance order.
+///
+/// Occlusion is stored in alpha and RGB is left black (no baked indirect light), which is
+/// the layout `LightmapComponent` expects. Rays are tested against every triangle, so
+/// this is meant for small static scenes.
+pub fn bake_ambient_occlusion(
+    instances: &[BakeInstance],
+    settings: &AoBakeSettings,
+) -> Vec<ImageData> {
+    let occluders: Vec<Occluder> = instances.iter().map(Occluder::new).collect();
+    instances
+        .iter()
+        .zip(&occluders)
+        .map(|(instance, world)| bake_instance(instance.mesh, world, &occluders, settings))
+        .collect()
+}
+
+/// Writes a baked lightmap as a PNG, to be imported like any other texture.
+pub fn save_lightmap(image: &ImageData, path: &Path) -> Result<(), image::ImageError> {
+    image::save_buffer(
+        path,
+        &image.pixels,
+        image.width,
+        image.height,
+        image::ColorType::Rgba8,
+    )
+}
+
+/// An instance's geometry in world space.
+pub(crate) struct Occluder {
+    positions: Vec<Vec3>,
+    normals: Vec<Vec3>,
+    indices: Vec<u32>,
+    min: Vec3,
+    max: Vec3,
+}
+
+impl Occluder {
+    pub(crate) fn new(instance: &BakeInstance) -> Self {
+        let normal_matrix = instance
+            .transform
+            .fixed_view::<3, 3>(0, 0)
+            .try_inverse()
+            .map_or_else(Mat3::identity, |m| m.transpose());
+        let positions: Vec<Vec3> = instance
+            .mesh
+            .vertices
+            .iter()
+            .map(|v| {
+                instance
+                    .transform
+                    .transform_point(&Point3::from(v.pos))
+                    .coords
+            })
+            .collect();
+        let normals = instance
+            .mesh
+            .vertices
+            .iter()
+            .map(|v| {
+                (normal_matrix * v.normal)
+                    .try_normalize(f32::EPSILON)
+                    .unwrap_or_else(Vec3::y)
+            })
+            .collect();
+
+        let mut min = Vec3::repeat(f32::MAX);
+        let mut max = Vec3::repeat(f32::MIN);
+        for p in &positions {
+            min = min.inf(p);
+            max = max.sup(p);
+        }
+
+        Self {
+            positions,
+            normals,
+            indices: instance.mesh.indices.clone(),
+            min,
+            max,
+        }
+    }
+
+    fn triangle(&self, tri: &[u32]) -> [Vec3; 3] {
+        [tri[0], tri[1], tri[2]].map(|i| self.positions[i as usize])
+    }
+
+    /// Whether the segment from `origin` along `dir` up to `max_t` hits this occluder.
+    pub(crate) fn occludes(&self, origin: &Vec3, dir: &Vec3, max_t: f32) -> bool {
+        ray_hits_box(origin, dir, max_t, &self.min, &self.max)
+            && self.indices.chunks_exact(3).any(|tri| {
+                ray_triangle(origin, dir, &self.triangle(tri)).is_some_and(|t| t < max_t)
+            })
+    }
+
+    /// The closest hit of the segment from `origin` along `dir` up to `max_t`: its
+    /// distance and the hit triangle's normal, turned to face the ray.
+    pub(crate) fn nearest_hit(
+        &self,
+        origin: &Vec3,
+        dir: &Vec3,
+        max_t: f32,
+    ) -> Option<(f32, Vec3)> {
+        if !ray_hits_box(origin, dir, max_t, &self.min, &self.max) {
+            return None;
+        }
+        let mut nearest: Option<(f32, [Vec3; 3])> = None;
+        for tri in self.indices.chunks_exact(3) {
+            let tri = self.triangle(tri);
+            if let Some(t) = ray_triangle(origin, dir, &tri) {
+                if t < nearest.map_or(max_t, |(nearest, _)| nearest) {
+                    nearest = Some((t, tri));
+                }
+            }
+        }
+        let (t, [a, b, c]) = nearest?;
+        let normal = (b - a).cross(&(c - a)).try_normalize(f32::EPSILON)?;
+        Some((t, if normal.dot(dir) > 0.0 { -normal } else { normal }))
+    }
+}
+
+fn bake_instance(
+    mesh: &MeshData,
+    world: &Occluder,
+    occluders: &[Occluder],
+    settings: &AoBakeSettings,
+) -> ImageData {
+    let res = settings.resolution.max(1) as usize;
+    let uvs: Vec<Vec2> = match &mesh.extras {
+        Some(extras) => extras.iter().map(|e| e.tex_coord1).collect(),
+        None => mesh.vertices.iter().map(|v| v.tex_coord).collect(),
+    };
+
+    let mut visibility: Vec<Option<f32>> = vec![None; res * res];
+    for tri in mesh.indices.chunks_exact(3) {
+        let [a, b, c] = [tri[0], tri[1], tri[2]].map(|i| i as usize);
+        let texel_uvs = [a, b, c].map(|i| uvs[i] * res as f32);
+
+        let lo = texel_uvs[0].inf(&texel_uvs[1]).inf(&texel_uvs[2]);
+        let hi = texel_uvs[0].sup(&texel_uvs[1]).sup(&texel_uvs[2]);
+        let x_range = texel_range(lo.x, hi.x, res);
+        let y_range = texel_range(lo.y, hi.y, res);
+
+        for y in y_range {
+            for x in x_range.clone() {
+                let texel = y * res + x;
+                if visibility[texel].is_some() {
+                    continue;
+                }
+                let center = Vec2::new(x as f32 + 0.5, y as f32 + 0.5);
+                let Some([wa, wb, wc]) = barycentric(&center, &texel_uvs) else {
+                    continue;
+                };
+                let pos =
+                    world.positions[a] * wa + world.positions[b] * wb + world.positions[c] * wc;
+                let normal =
+                    (world.normals[a] * wa + world.normals[b] * wb + world.normals[c] * wc)
+                        .try_normalize(f32::EPSILON)
+                        .unwrap_or_else(Vec3::y);
+                visibility[texel] = Some(sky_visibility(&pos, &normal, texel, occluders, settings));
+            }
+        }
+    }
+
+    for _ in 0..settings.padding {
+        dilate(&mut visibility, res);
+    }
+
+    let pixels = visibility
+        .iter()
+        .flat_map(|v| {
+            let ao = (v.unwrap_or(1.0).clamp(0.0, 1.0) * 255.0).round() as u8;
+            [0, 0, 0, ao]
+        })
+        .collect();
+    ImageData::rgba8(pixels, res as u32, res as u32, ColorSpace::Linear)
+}
+
+/// Texel indices whose centers can fall inside `[lo, hi]`.
+fn texel_range(lo: f32, hi: f32, res: usize) -> std::ops::Range<usize> {
+    let start = (lo - 0.5).ceil().max(0.0) as usize;
+    let end = ((hi - 0.5).floor() + 1.0).clamp(0.0, res as f32) as usize;
+    start..end.max(start)
+}
+
+fn barycentric(p: &Vec2, tri: &[Vec2; 3]) -> Option<[f32; 3]> {
+    let v0 = tri[1] - tri[0];
+    let v1 = tri[2] - tri[0];
+    let v2 = p - tri[0];
+    let det = v0.x * v1.y - v1.x * v0.y;
+    if det.abs() <= f32::EPSILON {
+        return None;
+    }
+    let wb = (v2.x * v1.y - v1.x * v2.y) / det;
+    let wc = (v0.x * v2.y - v2.x * v0.y) / det;
+    let wa = 1.0 - wb - wc;
+    const EDGE: f32 = -1e-4;
+    (wa >= EDGE && wb >= EDGE && wc >= EDGE).then_some([wa, wb, wc])
+}
+
+/// Fraction of cosine-weighted hemisphere rays around `normal` that escape within
+/// `max_distance`.
+fn sky_visibility(
+    pos: &Vec3,
+    normal: &Vec3,
+    texel: usize,
+    occluders: &[Occluder],
+    settings: &AoBakeSettings,
+) -> f32 {
+    let samples = settings.samples.max(1);
+    let tangent = any_perpendicular(normal);
+    let bitangent = normal.cross(&tangent);
+    let origin = pos + normal * (settings.max_distance * 1e-3);
+    // Per-texel rotation of the sample pattern, so banding turns into fine noise.
+    let jitter = radical_inverse(texel as u32 ^ 0x5bd1_e995);
+
+    let mut open = 0;
+    for i in 0..samples {
+        let u = (i as f32 + 0.5) / samples as f32;
+        let v = (radical_inverse(i) + jitter).fract();
+        let r = u.sqrt();
+        let phi = std::f32::consts::TAU * v;
+        let dir =
+            tangent * (r * phi.cos()) + bitangent * (r * phi.sin()) + normal * (1.0 - u).sqrt();
+        if !occluders
+            .iter()
+            .any(|occluder| occluder.occludes(&origin, &dir, settings.max_distance))
+        {
+            open += 1;
+        }
+    }
+    open as f32 / samples as f32
+}
+
+/// Van der Corput sequence in base 2.
+pub(crate) fn radical_inverse(i: u32) -> f32 {
+    i.reverse_bits() as f32 * (1.0 / 4_294_967_296.0)
+}
+
+/// Fills unbaked texels next to baked ones with the average of their baked neighbours.
+fn dilate(visibility: &mut [Option<f32>], res: usize) {
+    let source = visibility.to_vec();
+    for y in 0..res {
+        for x in 0..res {
+            if source[y * res + x].is_some() {
+                continue;
+            }
+            let (mut sum, mut count) = (0.0, 0);
+            for (dx, dy) in [(-1, 0), (1, 0), (0, -1), (0, 1)] {
+                let (nx, ny) = (x as i64 + dx, y as i64 + dy);
+                if nx < 0 || ny < 0 || nx >= res as i64 || ny >= res as i64 {
+                    continue;
+                }
+                if let Some(v) = source[ny as usize * res + nx as usize] {
+                    sum += v;
+                    count += 1;
+                }
+            }
+            if count > 0 {
+                visibility[y * res + x] = Some(sum / count as f32);
+            }
+        }
+    }
+}
+
+fn ray_hits_box(origin: &Vec3, dir: &Vec3, max_t: f32, min: &Vec3, max: &Vec3) -> bool {
+    let (mut t_near, mut t_far) = (0.0f32, max_t);
+    for axis in 0..3 {
+        let inv = 1.0 / dir[axis];
+        let t0 = (min[axis] - origin[axis]) * inv;
+        let t1 = (max[axis] - origin[axis]) * inv;
+        t_near = t_near.max(t0.min(t1));
+        t_far = t_far.min(t0.max(t1));
+    }
+    t_near <= t_far
+}
+
+/// Möller-Trumbore intersection, two-sided. Returns the distance along `dir`.
+fn ray_triangle(origin: &Vec3, dir: &Vec3, tri: &[Vec3; 3]) -> Option<f32> {
+    let e1 = tri[1] - tri[0];
+    let e2 = tri[2] - tri[0];
+    let p = dir.cross(&e2);
+    let det = e1.dot(&p);
+    if det.abs() <= f32::EPSILON {
+        return None;
+    }
+    let inv_det = 1.0 / det;
+    let s = origin - tri[0];
+    let u = s.dot(&p) * inv_det;
+    if !(0.0..=1.0).contains(&u) {
+        return None;
+    }
+    let q = s.cross(&e1);
+    let v = dir.dot(&q) * inv_det;
+    if v < 0.0 || u + v > 1.0 {
+        return None;
+    }
+    let t = e2.dot(&q) * inv_det;
+    (t > 0.0).then_some(t)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::{SubMesh, Vertex, VertexEncoding};
+
+    /// A unit quad on the XZ plane at height `y`, facing +Y, with UVs covering [0, 1].
+    fn quad(y: f32) -> MeshData {
+        let vertex = |x: f32, z: f32| Vertex {
+            pos: Vec3::new(x, y, z),
+            tex_coord: Vec2::new(x, z),
+            normal: Vec3::y(),
+            ..Default::default()
+        };
+        MeshData {
+            vertices: vec![
+                vertex(0.0, 0.0),
+                vertex(1.0, 0.0),
+                vertex(1.0, 1.0),
+                vertex(0.0, 1.0),
+            ],
+            vertex_encoding: VertexEncoding::Full,
+            indices: vec![0, 2, 1, 0, 3, 2],
+            extras: None,
+            skin: None,
+            submeshes: vec![SubMesh {
+                index_offset: 0,
+                index_count: 6,
+            }],
+        }
+    }
+
+    #[test]
+    fn covered_floor_is_darker_than_open_floor() {
+        let floor = quad(0.0);
+        let roof = quad(0.1);
+        let settings = AoBakeSettings {
+            resolution: 8,
+            samples: 32,
+            max_distance: 1.0,
+            padding: 1,
+        };
+        let center_ao = |image: &ImageData| image.pixels[(4 * 8 + 4) * 4 + 3];
+
+        let open = bake_ambient_occlusion(
+            &[BakeInstance {
+                mesh: &floor,
+                transform: Mat4::identity(),
+            }],
+            &settings,
+        );
+        assert_eq!(center_ao(&open[0]), 255);
+
+        // Offset the roof sideways so half the floor stays open.
+        let covered = bake_ambient_occlusion(
+            &[
+                BakeInstance {
+                    mesh: &floor,
+                    transform: Mat4::identity(),
+                },
+                BakeInstance {
+                    mesh: &roof,
+                    transform: Mat4::new_translation(&Vec3::new(0.5, 0.0, 0.0)),
+                },
+            ],
+            &settings,
+        );
+        let floor_ao = &covered[0];
+        let under_roof = floor_ao.pixels[(4 * 8 + 6) * 4 + 3];
+        let in_the_open = floor_ao.pixels[(4 * 8 + 1) * 4 + 3];
+        assert!(under_roof < 64, "{under_roof}");
+        assert!(in_the_open > under_roof, "{in_the_open} vs {under_roof}");
+    }
+}
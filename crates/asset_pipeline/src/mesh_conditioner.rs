@@ -1,6 +1,8 @@
+use crate::import_settings::MeshImportSettings;
+use crate::mesh_geometry::{generate_smooth_normals, generate_tangents};
 use assets::write_emesh;
-use common::Vertex;
-use nalgebra::{Vector2, Vector3};
+use common::math::{Vec2, Vec3, Vec4};
+use common::{Vertex, VertexExtra, VertexSkin};
 use std::fmt;
 use std::path::Path;
 
@@ -15,6 +17,8 @@ pub enum MeshConditionError {
     NoPositions,
     NoIndices,
     Write(assets::EmeshError),
+    /// The `.meta` import table does not match [`MeshImportSettings`].
+    Settings(toml::de::Error),
 }
 
 impl fmt::Display for MeshConditionError {
@@ -31,6 +35,7 @@ impl fmt::Display for MeshConditionError {
             MeshConditionError::NoPositions => write!(f, "mesh has no POSITION attribute"),
             MeshConditionError::NoIndices => write!(f, "mesh has no indices"),
             MeshConditionError::Write(e) => write!(f, "write: {}", e),
+            MeshConditionError::Settings(e) => write!(f, "import settings: {}", e),
         }
     }
 }
@@ -56,27 +61,58 @@ impl From<assets::EmeshError> for MeshConditionError {
     }
 }
 
+/// Vertices, the optional vertex extras and skin streams, and indices.
+type LoadedMesh = (
+    Vec<Vertex>,
+    Option<Vec<VertexExtra>>,
+    Option<Vec<VertexSkin>>,
+    Vec<u32>,
+);
+
 pub struct MeshConditioner;
 
 impl MeshConditioner {
     /// Reads a source mesh (`.obj`, `.gltf`, `.glb`) and writes a cooked
     /// `.emesh` binary to `dst_path`, creating parent directories as needed.
-    pub fn condition(src_path: &Path, dst_path: &Path) -> Result<(), MeshConditionError> {
-        let (vertices, indices) = match src_path.extension().and_then(|e| e.to_str()) {
-            Some("obj") => Self::load_obj(src_path)?,
-            Some("gltf") | Some("glb") => Self::load_gltf(src_path)?,
+    /// Missing normals are generated smooth; missing tangents are generated from UVs.
+    /// A glTF second UV set (`TEXCOORD_1`) and vertex colors (`COLOR_0`) are kept in
+    /// the mesh's extras stream, and skin joints and weights (`JOINTS_0`, `WEIGHTS_0`)
+    /// in its skin stream.
+    pub fn condition(
+        src_path: &Path,
+        dst_path: &Path,
+        settings: &MeshImportSettings,
+    ) -> Result<(), MeshConditionError> {
+        let (mut vertices, extras, skin, indices) = match src_path.extension().and_then(|e| e.to_str())
+        {
+            Some("obj") => Self::load_obj(src_path, settings)?,
+            Some("gltf") | Some("glb") => Self::load_gltf(src_path, settings)?,
             Some(ext) => return Err(MeshConditionError::UnsupportedFormat(ext.to_string())),
             None => return Err(MeshConditionError::UnsupportedFormat("(none)".to_string())),
         };
 
+        for vertex in &mut vertices {
+            *vertex = settings.vertex_encoding.quantize(vertex);
+        }
+
         if let Some(parent) = dst_path.parent() {
             std::fs::create_dir_all(parent)?;
         }
-        write_emesh(dst_path, vertices.as_slice(), &indices)?;
+        write_emesh(
+            dst_path,
+            vertices.as_slice(),
+            settings.vertex_encoding,
+            extras.as_deref(),
+            skin.as_deref(),
+            &indices,
+        )?;
         Ok(())
     }
 
-    fn load_obj(path: &Path) -> Result<(Vec<Vertex>, Vec<u32>), MeshConditionError> {
+    fn load_obj(
+        path: &Path,
+        settings: &MeshImportSettings,
+    ) -> Result<LoadedMesh, MeshConditionError> {
         let (models, _) = tobj::load_obj(path, &tobj::GPU_LOAD_OPTIONS)?;
         let model = models
             .into_iter()
@@ -85,42 +121,50 @@ impl MeshConditioner {
         let mesh = &model.mesh;
 
         let vert_count = mesh.positions.len() / 3;
+        let has_normals = mesh.normals.len() >= vert_count * 3;
         let mut vertices = Vec::with_capacity(vert_count);
 
         for i in 0..vert_count {
-            let pos = Vector3::new(
+            let pos = Vec3::new(
                 mesh.positions[i * 3],
                 mesh.positions[i * 3 + 1],
                 mesh.positions[i * 3 + 2],
-            );
-            let normal = if mesh.normals.len() >= (i + 1) * 3 {
-                Vector3::new(
+            ) * settings.scale;
+            let normal = if has_normals {
+                Vec3::new(
                     mesh.normals[i * 3],
                     mesh.normals[i * 3 + 1],
                     mesh.normals[i * 3 + 2],
                 )
             } else {
-                Vector3::new(0.0, 1.0, 0.0)
+                Vec3::zeros()
             };
             let tex_coord = if mesh.texcoords.len() >= (i + 1) * 2 {
-                Vector2::new(mesh.texcoords[i * 2], mesh.texcoords[i * 2 + 1])
+                Vec2::new(mesh.texcoords[i * 2], mesh.texcoords[i * 2 + 1])
             } else {
-                Vector2::new(0.0, 0.0)
+                Vec2::new(0.0, 0.0)
             };
 
             vertices.push(Vertex {
                 pos,
-                color: Vector3::new(1.0, 1.0, 1.0),
                 tex_coord,
                 normal,
                 ..Default::default()
             });
         }
 
-        Ok((vertices, mesh.indices.clone()))
+        if !has_normals || settings.generate_normals {
+            generate_smooth_normals(&mut vertices, &mesh.indices);
+        }
+        generate_tangents(&mut vertices, &mesh.indices);
+
+        Ok((vertices, None, None, mesh.indices.clone()))
     }
 
-    fn load_gltf(path: &Path) -> Result<(Vec<Vertex>, Vec<u32>), MeshConditionError> {
+    fn load_gltf(
+        path: &Path,
+        settings: &MeshImportSettings,
+    ) -> Result<LoadedMesh, MeshConditionError> {
         let (document, buffers, _) = gltf::import(path)?;
 
         let mesh = document.meshes().next().ok_or(MeshConditionError::NoMesh)?;
@@ -135,35 +179,93 @@ impl MeshConditioner {
             .ok_or(MeshConditionError::NoPositions)?
             .collect();
 
-        let normals: Vec<[f32; 3]> = reader
+        let normals: Option<Vec<[f32; 3]>> = reader
             .read_normals()
-            .map(|iter| iter.collect())
-            .unwrap_or_else(|| vec![[0.0, 1.0, 0.0]; positions.len()]);
+            .filter(|_| !settings.generate_normals)
+            .map(|iter| iter.collect());
+        let tangents: Option<Vec<[f32; 4]>> = reader.read_tangents().map(|iter| iter.collect());
 
         let tex_coords: Vec<[f32; 2]> = reader
             .read_tex_coords(0)
             .map(|iter| iter.into_f32().collect())
             .unwrap_or_else(|| vec![[0.0, 0.0]; positions.len()]);
 
+        let tex_coords1: Option<Vec<[f32; 2]>> = reader
+            .read_tex_coords(1)
+            .map(|iter| iter.into_f32().collect());
+        let colors: Option<Vec<[f32; 4]>> = reader
+            .read_colors(0)
+            .map(|iter| iter.into_rgba_f32().collect());
+        let joints: Option<Vec<[u16; 4]>> =
+            reader.read_joints(0).map(|iter| iter.into_u16().collect());
+        let weights: Option<Vec<[f32; 4]>> =
+            reader.read_weights(0).map(|iter| iter.into_f32().collect());
+
         let indices: Vec<u32> = reader
             .read_indices()
             .ok_or(MeshConditionError::NoIndices)?
             .into_u32()
             .collect();
 
-        let vertices = positions
+        let mut vertices: Vec<Vertex> = positions
             .iter()
-            .zip(normals.iter())
             .zip(tex_coords.iter())
-            .map(|((pos, norm), uv)| Vertex {
-                pos: Vector3::new(pos[0], pos[1], pos[2]),
-                normal: Vector3::new(norm[0], norm[1], norm[2]),
-                tex_coord: Vector2::new(uv[0], uv[1]),
-                color: Vector3::new(1.0, 1.0, 1.0),
+            .enumerate()
+            .map(|(i, (pos, uv))| Vertex {
+                pos: Vec3::new(pos[0], pos[1], pos[2]) * settings.scale,
+                normal: normals
+                    .as_ref()
+                    .map_or_else(Vec3::zeros, |n| Vec3::from(n[i])),
+                tangent: tangents
+                    .as_ref()
+                    .map_or_else(Vec4::zeros, |t| Vec4::from(t[i])),
+                tex_coord: Vec2::new(uv[0], uv[1]),
                 ..Default::default()
             })
             .collect();
 
-        Ok((vertices, indices))
+        if normals.is_none() {
+            generate_smooth_normals(&mut vertices, &indices);
+        }
+        // Authored tangents only make sense against authored normals.
+        if normals.is_none() || tangents.is_none() {
+            generate_tangents(&mut vertices, &indices);
+        }
+
+        let extras = (tex_coords1.is_some() || colors.is_some()).then(|| {
+            (0..vertices.len())
+                .map(|i| {
+                    let default = VertexExtra::default();
+                    VertexExtra {
+                        tex_coord1: tex_coords1
+                            .as_ref()
+                            .map_or(default.tex_coord1, |uv| Vec2::from(uv[i])),
+                        color: colors.as_ref().map_or(default.color, |c| Vec4::from(c[i])),
+                    }
+                })
+                .collect()
+        });
+
+        let skin = joints.zip(weights).map(|(joints, weights)| {
+            joints
+                .iter()
+                .zip(weights.iter())
+                .map(|(j, w)| {
+                    // Exporters don't always normalize; the shader assumes the weights sum to 1.
+                    let weights = Vec4::from(*w);
+                    let sum = weights.x + weights.y + weights.z + weights.w;
+                    VertexSkin {
+                        joints: j.map(u32::from),
+                        weights: if sum > 0.0 {
+                            weights / sum
+                        } else {
+                            Vec4::new(1.0, 0.0, 0.0, 0.0)
+                        },
+                    }
+                })
+                .collect()
+        });
+
+        Ok((vertices, extras, skin, indices))
     }
 }
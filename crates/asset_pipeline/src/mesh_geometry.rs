@@ -0,0 +1,139 @@
+use common::math::{Vec3, Vec4};
+use common::Vertex;
+use std::collections::HashMap;
+
+/// Replaces every vertex normal with the area-weighted average of the faces around it.
+/// Vertices at the same position share a normal, so UV seams stay smooth.
+pub fn generate_smooth_normals(vertices: &mut [Vertex], indices: &[u32]) {
+    let mut by_position: HashMap<[u32; 3], Vec3> = HashMap::new();
+    let key = |v: &Vertex| v.pos.map(f32::to_bits).into();
+
+    for tri in indices.chunks_exact(3) {
+        let [a, b, c] = [tri[0], tri[1], tri[2]].map(|i| &vertices[i as usize]);
+        // Unnormalized, so larger faces weigh more.
+        let face_normal = (b.pos - a.pos).cross(&(c.pos - a.pos));
+        for v in [a, b, c] {
+            *by_position.entry(key(v)).or_insert_with(Vec3::zeros) += face_normal;
+        }
+    }
+
+    for v in vertices.iter_mut() {
+        let sum = by_position
+            .get(&key(v))
+            .copied()
+            .unwrap_or_else(Vec3::zeros);
+        v.normal = sum.try_normalize(f32::EPSILON).unwrap_or_else(Vec3::y);
+    }
+}
+
+/// Computes per-vertex tangents from UV derivatives, following the MikkTSpace approach:
+/// per-face tangents and bitangents are accumulated, orthogonalized against the vertex
+/// normal, and the bitangent's handedness is stored in `tangent.w`.
+///
+/// Call after normals are final. Vertices with degenerate UVs get an arbitrary tangent
+/// perpendicular to their normal.
+pub fn generate_tangents(vertices: &mut [Vertex], indices: &[u32]) {
+    let mut tangents = vec![Vec3::zeros(); vertices.len()];
+    let mut bitangents = vec![Vec3::zeros(); vertices.len()];
+
+    for tri in indices.chunks_exact(3) {
+        let [i0, i1, i2] = [tri[0], tri[1], tri[2]].map(|i| i as usize);
+        let (v0, v1, v2) = (&vertices[i0], &vertices[i1], &vertices[i2]);
+
+        let e1 = v1.pos - v0.pos;
+        let e2 = v2.pos - v0.pos;
+        let duv1 = v1.tex_coord - v0.tex_coord;
+        let duv2 = v2.tex_coord - v0.tex_coord;
+
+        let det = duv1.x * duv2.y - duv2.x * duv1.y;
+        if det.abs() <= f32::EPSILON {
+            continue;
+        }
+        // Weighted by face size rather than divided by `det`, which blows up on tiny UV
+        // islands; only its sign matters for the direction.
+        let sign = det.signum();
+        let tangent = (e1 * duv2.y - e2 * duv1.y) * sign;
+        let bitangent = (e2 * duv1.x - e1 * duv2.x) * sign;
+
+        for i in [i0, i1, i2] {
+            tangents[i] += tangent;
+            bitangents[i] += bitangent;
+        }
+    }
+
+    for (i, v) in vertices.iter_mut().enumerate() {
+        let n = v.normal;
+        let t = tangents[i] - n * n.dot(&tangents[i]);
+        let t = t
+            .try_normalize(f32::EPSILON)
+            .unwrap_or_else(|| any_perpendicular(&n));
+        let handedness = if n.cross(&t).dot(&bitangents[i]) < 0.0 {
+            -1.0
+        } else {
+            1.0
+        };
+        v.tangent = Vec4::new(t.x, t.y, t.z, handedness);
+    }
+}
+
+pub(crate) fn any_perpendicular(n: &Vec3) -> Vec3 {
+    let axis = if n.x.abs() < 0.9 {
+        Vec3::x()
+    } else {
+        Vec3::y()
+    };
+    n.cross(&axis)
+        .try_normalize(f32::EPSILON)
+        .unwrap_or_else(Vec3::x)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::math::Vec2;
+
+    fn vertex(x: f32, z: f32, u: f32, v: f32) -> Vertex {
+        Vertex {
+            pos: Vec3::new(x, 0.0, z),
+            tex_coord: Vec2::new(u, v),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn flat_quad_gets_up_normals_and_u_aligned_tangents() {
+        // A quad on the XZ plane facing +Y, with U along +X and V along +Z.
+        let mut vertices = vec![
+            vertex(0.0, 0.0, 0.0, 0.0),
+            vertex(1.0, 0.0, 1.0, 0.0),
+            vertex(1.0, 1.0, 1.0, 1.0),
+            vertex(0.0, 1.0, 0.0, 1.0),
+        ];
+        let indices = [0, 2, 1, 0, 3, 2];
+
+        generate_smooth_normals(&mut vertices, &indices);
+        generate_tangents(&mut vertices, &indices);
+
+        for v in &vertices {
+            assert!((v.normal - Vec3::y()).norm() < 1e-5, "{:?}", v.normal);
+            assert!(
+                (v.tangent - Vec4::new(1.0, 0.0, 0.0, -1.0)).norm() < 1e-5,
+                "{:?}",
+                v.tangent
+            );
+        }
+
+        // Mirroring U flips the tangent and the handedness.
+        for v in &mut vertices {
+            v.tex_coord.x = 1.0 - v.tex_coord.x;
+        }
+        generate_tangents(&mut vertices, &indices);
+        for v in &vertices {
+            assert!(
+                (v.tangent - Vec4::new(-1.0, 0.0, 0.0, 1.0)).norm() < 1e-5,
+                "{:?}",
+                v.tangent
+            );
+        }
+    }
+}
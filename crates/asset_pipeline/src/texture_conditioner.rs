@@ -1,6 +1,11 @@
+use crate::import_settings::TextureImportSettings;
 use assets::write_etex;
-use common::ImageData;
+use common::{block_compression, full_mip_count, ColorSpace, ImageData, PixelFormat};
+use image::imageops::{self, FilterType};
+use image::RgbaImage;
+use ktx2::{Format, SupercompressionScheme};
 use std::fmt;
+use std::io::Read;
 use std::path::Path;
 
 #[derive(Debug)]
@@ -9,6 +14,11 @@ pub enum TextureConditionError {
     UnsupportedFormat(String),
     Image(image::ImageError),
     Write(assets::EtexError),
+    /// The `.meta` import table does not match [`TextureImportSettings`].
+    Settings(toml::de::Error),
+    Ktx2(ktx2::ParseError),
+    /// A valid KTX2 file the importer cannot read, e.g. Basis Universal payloads.
+    UnsupportedKtx2(String),
 }
 
 impl fmt::Display for TextureConditionError {
@@ -20,6 +30,9 @@ impl fmt::Display for TextureConditionError {
             }
             TextureConditionError::Image(e) => write!(f, "image: {}", e),
             TextureConditionError::Write(e) => write!(f, "write: {}", e),
+            TextureConditionError::Settings(e) => write!(f, "import settings: {}", e),
+            TextureConditionError::Ktx2(e) => write!(f, "ktx2: {}", e),
+            TextureConditionError::UnsupportedKtx2(e) => write!(f, "unsupported ktx2: {}", e),
         }
     }
 }
@@ -39,29 +52,49 @@ impl From<assets::EtexError> for TextureConditionError {
         TextureConditionError::Write(e)
     }
 }
+impl From<ktx2::ParseError> for TextureConditionError {
+    fn from(e: ktx2::ParseError) -> Self {
+        TextureConditionError::Ktx2(e)
+    }
+}
 
 pub struct TextureConditioner;
 
 impl TextureConditioner {
     /// Reads a source image (`.png`, `.jpg`, `.hdr`, etc.) and writes a cooked
     /// `.etex` binary to `dst_path`, creating parent directories as needed.
-    /// All formats are converted to RGBA8 for now.
-    pub fn condition(src_path: &Path, dst_path: &Path) -> Result<(), TextureConditionError> {
-        match src_path.extension().and_then(|e| e.to_str()) {
-            Some("png" | "jpg" | "jpeg" | "hdr" | "exr" | "bmp" | "tga") => {}
+    /// Images are converted to RGBA8, given mips and block compressed as `settings`
+    /// ask. `.ktx2` files are copied as they are, see [`Self::load_ktx2`].
+    pub fn condition(
+        src_path: &Path,
+        dst_path: &Path,
+        settings: &TextureImportSettings,
+    ) -> Result<(), TextureConditionError> {
+        let image = match src_path.extension().and_then(|e| e.to_str()) {
+            Some("png" | "jpg" | "jpeg" | "hdr" | "exr" | "bmp" | "tga") => {
+                let mut image = Self::load_image(src_path)?;
+                image.color_space = settings.color_space;
+                if settings.mipmaps {
+                    image = generate_mips(&image);
+                }
+                if settings.format.is_block_compressed() {
+                    image = block_compression::compress(&image, settings.format);
+                }
+                image
+            }
+            Some("ktx2") => Self::load_ktx2(&std::fs::read(src_path)?)?,
             Some(ext) => return Err(TextureConditionError::UnsupportedFormat(ext.to_string())),
             None => {
                 return Err(TextureConditionError::UnsupportedFormat(
                     "(none)".to_string(),
                 ))
             }
-        }
+        };
 
-        let image = Self::load_image(src_path)?;
         if let Some(parent) = dst_path.parent() {
             std::fs::create_dir_all(parent)?;
         }
-        write_etex(dst_path, &image)?;
+        write_etex(dst_path, &image, settings.compression)?;
         Ok(())
     }
 
@@ -74,10 +107,100 @@ impl TextureConditioner {
         let image_height = dyn_image.height();
         let image_data = dyn_image.to_rgba8().into_raw();
 
-        Ok(ImageData {
-            pixels: image_data,
-            width: image_width,
-            height: image_height,
-        })
+        Ok(ImageData::rgba8(
+            image_data,
+            image_width,
+            image_height,
+            ColorSpace::Linear,
+        ))
+    }
+
+    /// Reads a 2D KTX2 texture in RGBA8, BC5, BC7 or ASTC 4x4, optionally
+    /// Zstandard-supercompressed, with the mips stored in the file.
+    ///
+    /// Basis Universal payloads (BasisLZ/ETC1S or UASTC) are not supported yet: the engine
+    /// has no transcoder, so they fail with [`TextureConditionError::UnsupportedKtx2`].
+    /// Until one lands, re-encode such sources to BC7, BC5 or ASTC 4x4 with the KTX tools.
+    pub fn load_ktx2(bytes: &[u8]) -> Result<ImageData, TextureConditionError> {
+        let unsupported = |reason: &str| TextureConditionError::UnsupportedKtx2(reason.into());
+        let reader = ktx2::Reader::new(bytes)?;
+        let header = reader.header();
+        if header.layer_count > 1 || header.face_count > 1 || header.pixel_depth > 1 {
+            return Err(unsupported("only 2D textures are supported"));
+        }
+        let (format, color_space) = match header.format {
+            Some(Format::R8G8B8A8_UNORM) => (PixelFormat::Rgba8, ColorSpace::Linear),
+            Some(Format::R8G8B8A8_SRGB) => (PixelFormat::Rgba8, ColorSpace::Srgb),
+            Some(Format::BC5_UNORM_BLOCK) => (PixelFormat::Bc5, ColorSpace::Linear),
+            Some(Format::BC7_UNORM_BLOCK) => (PixelFormat::Bc7, ColorSpace::Linear),
+            Some(Format::BC7_SRGB_BLOCK) => (PixelFormat::Bc7, ColorSpace::Srgb),
+            Some(Format::ASTC_4x4_UNORM_BLOCK) => (PixelFormat::Astc4x4, ColorSpace::Linear),
+            Some(Format::ASTC_4x4_SRGB_BLOCK) => (PixelFormat::Astc4x4, ColorSpace::Srgb),
+            Some(other) => return Err(unsupported(&format!("format {:?}", other))),
+            None => {
+                return Err(unsupported(
+                    "Basis Universal payloads are not supported yet, re-encode to BC7, BC5 or ASTC",
+                ))
+            }
+        };
+
+        let mut pixels = Vec::new();
+        for level in reader.levels() {
+            match header.supercompression_scheme {
+                None => pixels.extend_from_slice(level.data),
+                Some(SupercompressionScheme::Zstandard) => {
+                    ruzstd::decoding::StreamingDecoder::new(level.data)
+                        .map_err(std::io::Error::other)?
+                        .read_to_end(&mut pixels)?;
+                }
+                Some(other) => return Err(unsupported(&format!("supercompression {:?}", other))),
+            }
+        }
+
+        let image = ImageData {
+            pixels,
+            width: header.pixel_width,
+            height: header.pixel_height.max(1),
+            color_space,
+            format,
+            mip_levels: header.level_count.max(1),
+        };
+        if image.pixels.len() != image.data_len() {
+            return Err(unsupported("level sizes do not match the format"));
+        }
+        Ok(image)
+    }
+}
+
+/// Adds the full mip chain to a single-level RGBA8 image, each level filtered down from
+/// the one above it.
+fn generate_mips(image: &ImageData) -> ImageData {
+    let mut level = RgbaImage::from_raw(image.width, image.height, image.pixels.clone())
+        .expect("RGBA8 pixels match the image size");
+    let mip_levels = full_mip_count(image.width, image.height);
+    let mut pixels = image.pixels.clone();
+    for mip in 1..mip_levels {
+        let (width, height) = image.level_extent(mip);
+        level = imageops::resize(&level, width, height, FilterType::Triangle);
+        pixels.extend_from_slice(level.as_raw());
+    }
+    ImageData {
+        pixels,
+        mip_levels,
+        ..image.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generated_mips_fill_the_chain() {
+        let image = ImageData::rgba8(vec![200; 6 * 4 * 4], 6, 4, ColorSpace::Srgb);
+        let mipped = generate_mips(&image);
+        assert_eq!(mipped.mip_levels, 3);
+        assert_eq!(mipped.pixels.len(), (6 * 4 + 3 * 2 + 1) * 4);
+        assert!(mipped.pixels.iter().all(|&value| value == 200));
     }
 }
@@ -1,6 +1,7 @@
 use crate::emesh::read_emesh;
 use crate::etex::read_etex;
 use crate::read_spv;
+use common::trace;
 use common::{Guid, Handle, ImageData, ImageHandle, MeshData, MeshHandle, ShaderData, ShaderHandle, TypedStore};
 use std::any::{Any, TypeId};
 use std::collections::HashMap;
@@ -10,6 +11,12 @@ pub struct AssetStore {
     stores: HashMap<TypeId, Box<dyn Any>>,
 }
 
+impl Default for AssetStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl AssetStore {
     pub fn new() -> Self {
         Self {
@@ -34,25 +41,69 @@ impl AssetStore {
     /// Loads a cooked `.emesh` file. Returns a cached handle if the same path
     /// was already loaded.
     pub fn load_mesh(&mut self, path: &Path, guid: Guid) -> Option<MeshHandle> {
-        self.store_for_mut::<MeshData>()
-            .get_or_insert(guid, || read_emesh(path).ok())
+        self.store_for_mut::<MeshData>().get_or_insert(guid, || {
+            let _span = trace::span("asset", format!("Load {}", path.display()));
+            read_emesh(path).ok()
+        })
     }
 
     /// Loads a cooked `.etex` file. Returns a cached handle if the same path
     /// was already loaded.
     pub fn load_texture(&mut self, path: &Path, guid: Guid) -> Option<ImageHandle> {
-        self.store_for_mut::<ImageData>()
-            .get_or_insert(guid, || read_etex(path).ok())
+        self.store_for_mut::<ImageData>().get_or_insert(guid, || {
+            let _span = trace::span("asset", format!("Load {}", path.display()));
+            read_etex(path).ok()
+        })
     }
 
     /// Loads a compiled `.spv` shader file. Returns a cached handle if the same path
     /// was already loaded.
     pub fn load_shader(&mut self, path: &Path, guid: Guid) -> Option<ShaderHandle> {
-        self.store_for_mut::<ShaderData>()
-            .get_or_insert(guid, || read_spv(path).ok())
+        self.store_for_mut::<ShaderData>().get_or_insert(guid, || {
+            let _span = trace::span("asset", format!("Load {}", path.display()));
+            read_spv(path).ok()
+        })
+    }
+
+    /// Adds a mesh decoded elsewhere, e.g. on a loader thread. Keeps the existing
+    /// handle if the GUID is already loaded.
+    pub fn insert_mesh(&mut self, guid: Guid, mesh: MeshData) -> MeshHandle {
+        self.store_for_mut::<MeshData>()
+            .get_or_insert(guid, || Some(mesh))
+            .expect("insert always yields a value")
+    }
+
+    /// Adds a texture decoded elsewhere. Keeps the existing handle if the GUID is
+    /// already loaded.
+    pub fn insert_texture(&mut self, guid: Guid, image: ImageData) -> ImageHandle {
+        self.store_for_mut::<ImageData>()
+            .get_or_insert(guid, || Some(image))
+            .expect("insert always yields a value")
+    }
+
+    /// The handle an asset of type `T` was loaded under, if it is loaded.
+    pub fn handle_of<T: 'static>(&self, guid: Guid) -> Option<Handle<T>> {
+        self.store_for::<T>()?.handle_of(guid)
     }
 
     pub fn get<T: 'static>(&self, handle: Handle<T>) -> Option<&T> {
         self.store_for::<T>()?.get(handle)
     }
+
+    /// The GUID a handle was loaded from.
+    pub fn guid_of<T: 'static>(&self, handle: Handle<T>) -> Option<Guid> {
+        self.store_for::<T>()?.guid_of(handle)
+    }
+
+    /// Handles of every loaded asset of type `T`.
+    pub fn handles<T: 'static>(&self) -> impl Iterator<Item = Handle<T>> + '_ {
+        self.store_for::<T>()
+            .into_iter()
+            .flat_map(TypedStore::handles)
+    }
+
+    /// Unloads an asset. Loading its GUID again yields a new handle.
+    pub fn remove<T: 'static>(&mut self, handle: Handle<T>) -> Option<T> {
+        self.store_for_mut::<T>().remove(handle)
+    }
 }
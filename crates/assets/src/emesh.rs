@@ -1,9 +1,17 @@
-use common::{MeshData, Vertex};
+use common::{MeshData, Vertex, VertexEncoding, VertexExtra, VertexSkin};
 use std::fmt;
 use std::path::Path;
 
 const MAGIC: [u8; 4] = *b"EMSH";
-const VERSION: u32 = 1;
+/// 2: vertices carry a tangent.
+/// 3: vertex color moved into an optional extras stream alongside a second UV set.
+/// 4: optional skin stream (joint indices and weights).
+const VERSION: u32 = 4;
+const HEADER_LEN: usize = 20;
+const FLAG_HAS_EXTRAS: u32 = 1;
+const FLAG_HAS_SKIN: u32 = 2;
+/// Vertices are quantized for and uploaded as `VertexEncoding::Packed`.
+const FLAG_PACKED_VERTICES: u32 = 4;
 
 #[derive(Debug)]
 pub enum EmeshError {
@@ -24,25 +32,71 @@ impl fmt::Display for EmeshError {
     }
 }
 
-/// Writes vertices and indices to a `.emesh` binary file.
+/// Writes vertices, optional vertex extras and skin streams, and indices to a `.emesh`
+/// binary file.
 ///
-/// Format: 4-byte magic + version u32 + vertex_count u32 + index_count u32
-/// + raw vertex bytes + raw index bytes (all little-endian).
-pub fn write_emesh(path: &Path, vertices: &[Vertex], indices: &[u32]) -> Result<(), EmeshError> {
-    let vertex_size = std::mem::size_of::<Vertex>();
-    let mut buf = Vec::with_capacity(16 + vertices.len() * vertex_size + indices.len() * 4);
+/// Format: 4-byte magic + version u32 + vertex_count u32 + index_count u32 + flags u32
+/// + raw vertex bytes + raw extra bytes (if flagged) + raw skin bytes (if flagged)
+/// + raw index bytes (all little-endian).
+///
+/// `extras` and `skin`, when present, must have one entry per vertex.
+pub fn write_emesh(
+    path: &Path,
+    vertices: &[Vertex],
+    vertex_encoding: VertexEncoding,
+    extras: Option<&[VertexExtra]>,
+    skin: Option<&[VertexSkin]>,
+    indices: &[u32],
+) -> Result<(), EmeshError> {
+    let vertex_bytes_len = std::mem::size_of_val(vertices);
+    let extra_bytes_len = extras.map_or(0, std::mem::size_of_val);
+    let skin_bytes_len = skin.map_or(0, std::mem::size_of_val);
+    let mut buf = Vec::with_capacity(
+        HEADER_LEN + vertex_bytes_len + extra_bytes_len + skin_bytes_len + indices.len() * 4,
+    );
 
+    let mut flags = 0;
+    if extras.is_some() {
+        flags |= FLAG_HAS_EXTRAS;
+    }
+    if skin.is_some() {
+        flags |= FLAG_HAS_SKIN;
+    }
+    if vertex_encoding == VertexEncoding::Packed {
+        flags |= FLAG_PACKED_VERTICES;
+    }
     buf.extend_from_slice(&MAGIC);
     buf.extend_from_slice(&VERSION.to_le_bytes());
     buf.extend_from_slice(&(vertices.len() as u32).to_le_bytes());
     buf.extend_from_slice(&(indices.len() as u32).to_le_bytes());
+    buf.extend_from_slice(&flags.to_le_bytes());
 
     // Safe: Vertex is #[repr(C)] with no padding that would expose uninit bytes
     let vert_bytes = unsafe {
-        std::slice::from_raw_parts(vertices.as_ptr() as *const u8, vertices.len() * vertex_size)
+        std::slice::from_raw_parts(vertices.as_ptr() as *const u8, vertex_bytes_len)
     };
     buf.extend_from_slice(vert_bytes);
 
+    if let Some(extras) = extras {
+        assert_eq!(extras.len(), vertices.len(), "one vertex extra per vertex");
+        // Safe: VertexExtra is #[repr(C)] and all f32, so it has no padding either
+        let extra_bytes =
+            unsafe { std::slice::from_raw_parts(extras.as_ptr() as *const u8, extra_bytes_len) };
+        buf.extend_from_slice(extra_bytes);
+    }
+
+    if let Some(skin) = skin {
+        assert_eq!(
+            skin.len(),
+            vertices.len(),
+            "one vertex skin entry per vertex"
+        );
+        // Safe: VertexSkin is #[repr(C)], four u32 then four f32, with no padding
+        let skin_bytes =
+            unsafe { std::slice::from_raw_parts(skin.as_ptr() as *const u8, skin_bytes_len) };
+        buf.extend_from_slice(skin_bytes);
+    }
+
     let idx_bytes =
         unsafe { std::slice::from_raw_parts(indices.as_ptr() as *const u8, indices.len() * 4) };
     buf.extend_from_slice(idx_bytes);
@@ -54,7 +108,7 @@ pub fn write_emesh(path: &Path, vertices: &[Vertex], indices: &[u32]) -> Result<
 pub fn read_emesh(path: &Path) -> Result<MeshData, EmeshError> {
     let data = std::fs::read(path).map_err(EmeshError::Io)?;
 
-    if data.len() < 16 {
+    if data.len() < HEADER_LEN {
         return Err(EmeshError::Truncated);
     }
     if data[0..4] != MAGIC {
@@ -66,11 +120,18 @@ pub fn read_emesh(path: &Path) -> Result<MeshData, EmeshError> {
     }
     let vertex_count = u32::from_le_bytes(data[8..12].try_into().unwrap()) as usize;
     let index_count = u32::from_le_bytes(data[12..16].try_into().unwrap()) as usize;
+    let flags = u32::from_le_bytes(data[16..20].try_into().unwrap());
+    let has_extras = flags & FLAG_HAS_EXTRAS != 0;
+    let has_skin = flags & FLAG_HAS_SKIN != 0;
 
     let vertex_size = std::mem::size_of::<Vertex>();
-    let vert_start = 16;
+    let vert_start = HEADER_LEN;
     let vert_end = vert_start + vertex_count * vertex_size;
-    let idx_end = vert_end + index_count * 4;
+    let extra_count = if has_extras { vertex_count } else { 0 };
+    let extra_end = vert_end + extra_count * std::mem::size_of::<VertexExtra>();
+    let skin_count = if has_skin { vertex_count } else { 0 };
+    let skin_end = extra_end + skin_count * std::mem::size_of::<VertexSkin>();
+    let idx_end = skin_end + index_count * 4;
 
     if data.len() < idx_end {
         return Err(EmeshError::Truncated);
@@ -81,14 +142,85 @@ pub fn read_emesh(path: &Path) -> Result<MeshData, EmeshError> {
         let ptr = data[vert_start..vert_end].as_ptr() as *const Vertex;
         std::slice::from_raw_parts(ptr, vertex_count).to_vec()
     };
+    let extras = has_extras.then(|| unsafe {
+        let ptr = data[vert_end..extra_end].as_ptr() as *const VertexExtra;
+        std::slice::from_raw_parts(ptr, vertex_count).to_vec()
+    });
+    let skin = has_skin.then(|| unsafe {
+        let ptr = data[extra_end..skin_end].as_ptr() as *const VertexSkin;
+        std::slice::from_raw_parts(ptr, vertex_count).to_vec()
+    });
     let indices = unsafe {
-        let ptr = data[vert_end..idx_end].as_ptr() as *const u32;
+        let ptr = data[skin_end..idx_end].as_ptr() as *const u32;
         std::slice::from_raw_parts(ptr, index_count).to_vec()
     };
 
+    let vertex_encoding = if flags & FLAG_PACKED_VERTICES != 0 {
+        VertexEncoding::Packed
+    } else {
+        VertexEncoding::Full
+    };
     Ok(MeshData {
         vertices,
+        vertex_encoding,
         indices,
+        extras,
+        skin,
         submeshes: Vec::new(),
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::math::{Vec2, Vec4};
+
+    #[test]
+    fn optional_streams_round_trip_only_when_present() {
+        let vertices = vec![Vertex::default(); 3];
+        let extras = vec![
+            VertexExtra {
+                tex_coord1: Vec2::new(0.25, 0.75),
+                color: Vec4::new(1.0, 0.0, 0.0, 1.0),
+            };
+            3
+        ];
+        let indices = [0, 1, 2];
+        let path = std::env::temp_dir().join(format!("emesh_extras_{}.emesh", std::process::id()));
+
+        let skin = vec![
+            VertexSkin {
+                joints: [3, 1, 0, 0],
+                weights: Vec4::new(0.75, 0.25, 0.0, 0.0),
+            };
+            3
+        ];
+
+        write_emesh(
+            &path,
+            &vertices,
+            VertexEncoding::Packed,
+            Some(&extras),
+            Some(&skin),
+            &indices,
+        )
+        .unwrap();
+        let mesh = read_emesh(&path).unwrap();
+        let read_extras = mesh.extras.expect("extras were written");
+        assert_eq!(read_extras.len(), 3);
+        assert_eq!(read_extras[2].tex_coord1, extras[2].tex_coord1);
+        assert_eq!(read_extras[2].color, extras[2].color);
+        let read_skin = mesh.skin.expect("skin was written");
+        assert_eq!(read_skin[1].joints, skin[1].joints);
+        assert_eq!(read_skin[1].weights, skin[1].weights);
+        assert_eq!(mesh.indices, indices);
+        assert_eq!(mesh.vertex_encoding, VertexEncoding::Packed);
+
+        write_emesh(&path, &vertices, VertexEncoding::Full, None, None, &indices).unwrap();
+        let mesh = read_emesh(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+        assert!(mesh.extras.is_none());
+        assert!(mesh.skin.is_none());
+        assert_eq!(mesh.indices, indices);
+    }
+}
@@ -1,9 +1,27 @@
+use common::{ColorSpace, ImageData, PixelFormat};
+use serde::{Deserialize, Serialize};
 use std::fmt;
 use std::path::Path;
-use common::ImageData;
 
 const MAGIC: [u8; 4] = *b"ETEX";
-const VERSION: u32 = 1;
+const VERSION: u32 = 3;
+const HEADER_LEN: usize = 28;
+/// Version 2 files hold a single RGBA8 level and lack the format and mip count fields.
+const V2_HEADER_LEN: usize = 20;
+
+const FLAG_SRGB: u32 = 1;
+const FLAG_DEFLATE: u32 = 2;
+
+/// How the pixel payload of a `.etex` file is stored on disk.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TextureCompression {
+    /// Texels stored as they are. Fastest to load.
+    #[default]
+    None,
+    /// Lossless DEFLATE. Smaller on disk, inflated at load time.
+    Deflate,
+}
 
 #[derive(Debug)]
 pub enum EtexError {
@@ -11,6 +29,9 @@ pub enum EtexError {
     InvalidMagic,
     UnsupportedVersion(u32),
     Truncated,
+    /// The DEFLATE payload could not be inflated.
+    Corrupt,
+    UnknownPixelFormat(u32),
 }
 
 impl fmt::Display for EtexError {
@@ -20,21 +41,45 @@ impl fmt::Display for EtexError {
             EtexError::InvalidMagic => write!(f, "invalid .etex magic bytes"),
             EtexError::UnsupportedVersion(v) => write!(f, "unsupported .etex version {}", v),
             EtexError::Truncated => write!(f, ".etex file is truncated"),
+            EtexError::Corrupt => write!(f, ".etex pixel data is corrupt"),
+            EtexError::UnknownPixelFormat(v) => write!(f, "unknown .etex pixel format {}", v),
         }
     }
 }
 
-/// Writes RGBA8 pixel data to a `.etex` binary file.
+/// Writes pixel data to a `.etex` binary file.
 ///
-/// Format: 4-byte magic + version u32 + width u32 + height u32 + raw RGBA8 bytes
-/// (all little-endian).
-pub fn write_etex(path: &Path, image_data: &ImageData) -> Result<(), EtexError> {
-    let mut buf = Vec::with_capacity(16 + image_data.pixels.len());
+/// Format: 4-byte magic + version u32 + width u32 + height u32 + flags u32 + pixel
+/// format u32 + mip count u32 + every mip level's bytes, largest first (all
+/// little-endian). Flags mark sRGB color and a DEFLATE-compressed payload.
+pub fn write_etex(
+    path: &Path,
+    image_data: &ImageData,
+    compression: TextureCompression,
+) -> Result<(), EtexError> {
+    let mut flags = 0;
+    if image_data.color_space == ColorSpace::Srgb {
+        flags |= FLAG_SRGB;
+    }
+    let deflated;
+    let payload = match compression {
+        TextureCompression::None => image_data.pixels.as_slice(),
+        TextureCompression::Deflate => {
+            flags |= FLAG_DEFLATE;
+            deflated = miniz_oxide::deflate::compress_to_vec(&image_data.pixels, 6);
+            deflated.as_slice()
+        }
+    };
+
+    let mut buf = Vec::with_capacity(HEADER_LEN + payload.len());
     buf.extend_from_slice(&MAGIC);
     buf.extend_from_slice(&VERSION.to_le_bytes());
     buf.extend_from_slice(&image_data.width.to_le_bytes());
     buf.extend_from_slice(&image_data.height.to_le_bytes());
-    buf.extend_from_slice(&image_data.pixels);
+    buf.extend_from_slice(&flags.to_le_bytes());
+    buf.extend_from_slice(&format_code(image_data.format).to_le_bytes());
+    buf.extend_from_slice(&image_data.mip_levels.to_le_bytes());
+    buf.extend_from_slice(payload);
     std::fs::write(path, buf).map_err(EtexError::Io)
 }
 
@@ -42,27 +87,111 @@ pub fn write_etex(path: &Path, image_data: &ImageData) -> Result<(), EtexError>
 pub fn read_etex(path: &Path) -> Result<ImageData, EtexError> {
     let data = std::fs::read(path).map_err(EtexError::Io)?;
 
-    if data.len() < 16 {
+    if data.len() < HEADER_LEN {
         return Err(EtexError::Truncated);
     }
     if data[0..4] != MAGIC {
         return Err(EtexError::InvalidMagic);
     }
-    let version = u32::from_le_bytes(data[4..8].try_into().unwrap());
-    if version != VERSION {
-        return Err(EtexError::UnsupportedVersion(version));
+    let read_u32 = |offset: usize| u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap());
+    let version = read_u32(4);
+    let header_len = match version {
+        VERSION => HEADER_LEN,
+        2 => V2_HEADER_LEN,
+        _ => return Err(EtexError::UnsupportedVersion(version)),
+    };
+    if data.len() < header_len {
+        return Err(EtexError::Truncated);
+    }
+    let flags = read_u32(16);
+    let mut image = ImageData::rgba8(
+        Vec::new(),
+        read_u32(8),
+        read_u32(12),
+        if flags & FLAG_SRGB != 0 {
+            ColorSpace::Srgb
+        } else {
+            ColorSpace::Linear
+        },
+    );
+    if version == VERSION {
+        image.format = pixel_format(read_u32(20))?;
+        image.mip_levels = read_u32(24).max(1);
     }
-    let width = u32::from_le_bytes(data[8..12].try_into().unwrap());
-    let height = u32::from_le_bytes(data[12..16].try_into().unwrap());
 
-    let expected = (width * height * 4) as usize;
-    if data.len() < 16 + expected {
+    let expected = image.data_len();
+    let pixels = if flags & FLAG_DEFLATE != 0 {
+        miniz_oxide::inflate::decompress_to_vec_with_limit(&data[header_len..], expected)
+            .map_err(|_| EtexError::Corrupt)?
+    } else {
+        data.get(header_len..header_len + expected)
+            .ok_or(EtexError::Truncated)?
+            .to_vec()
+    };
+    if pixels.len() != expected {
         return Err(EtexError::Truncated);
     }
+    image.pixels = pixels;
+    Ok(image)
+}
+
+fn format_code(format: PixelFormat) -> u32 {
+    match format {
+        PixelFormat::Rgba8 => 0,
+        PixelFormat::Bc5 => 1,
+        PixelFormat::Bc7 => 2,
+        PixelFormat::Astc4x4 => 3,
+    }
+}
+
+fn pixel_format(code: u32) -> Result<PixelFormat, EtexError> {
+    match code {
+        0 => Ok(PixelFormat::Rgba8),
+        1 => Ok(PixelFormat::Bc5),
+        2 => Ok(PixelFormat::Bc7),
+        3 => Ok(PixelFormat::Astc4x4),
+        other => Err(EtexError::UnknownPixelFormat(other)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deflated_srgb_texture_round_trips() {
+        let image = ImageData::rgba8(
+            (0..64u32).flat_map(|i| [i as u8, 0, 255, 255]).collect(),
+            8,
+            8,
+            ColorSpace::Srgb,
+        );
+        let path = std::env::temp_dir().join(format!("etex_{}.etex", std::process::id()));
 
-    Ok(ImageData {
-        pixels: data[16..16 + expected].to_vec(),
-        width,
-        height,
-    })
-}
\ No newline at end of file
+        write_etex(&path, &image, TextureCompression::Deflate).unwrap();
+        let read = read_etex(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+        assert_eq!(read.pixels, image.pixels);
+        assert_eq!((read.width, read.height), (8, 8));
+        assert_eq!(read.color_space, ColorSpace::Srgb);
+    }
+
+    #[test]
+    fn block_compressed_mips_round_trip() {
+        let image = ImageData {
+            pixels: (0..64u8).collect(),
+            width: 8,
+            height: 4,
+            color_space: ColorSpace::Linear,
+            format: PixelFormat::Bc5,
+            mip_levels: 3,
+        };
+        let path = std::env::temp_dir().join(format!("etex_bc_{}.etex", std::process::id()));
+
+        write_etex(&path, &image, TextureCompression::None).unwrap();
+        let read = read_etex(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+        assert_eq!((read.format, read.mip_levels), (PixelFormat::Bc5, 3));
+        assert_eq!(read.pixels, image.pixels);
+    }
+}
@@ -5,5 +5,5 @@ pub mod spv;
 
 pub use asset_store::*;
 pub use emesh::{write_emesh, EmeshError};
-pub use etex::{write_etex, EtexError};
+pub use etex::{write_etex, EtexError, TextureCompression};
 pub use spv::{read_spv, SpvError};
@@ -0,0 +1,640 @@
+//! Software BC5 and BC7 codecs. The cooker encodes RGBA8 textures with them, and the
+//! renderer decodes block-compressed textures for devices that cannot sample them.
+//!
+//! The BC7 encoder only emits mode 6 (one subset, 8-bit endpoints, 4-bit indices),
+//! which is fast and good enough for most color textures. The decoder reads all modes.
+
+use crate::{ImageData, PixelFormat};
+
+/// RGBA8 texels of one 4x4 block, row by row.
+pub type Block = [[u8; 4]; 16];
+
+/// Encodes the RGBA8 mips of `image` as `format`, BC5 or BC7. Edge blocks of mips that
+/// are not a multiple of 4 in size repeat their last row and column.
+pub fn compress(image: &ImageData, format: PixelFormat) -> ImageData {
+    assert_eq!(
+        image.format,
+        PixelFormat::Rgba8,
+        "only RGBA8 images can be compressed"
+    );
+    let encode_block: fn(&Block) -> [u8; 16] = match format {
+        PixelFormat::Bc5 => encode_bc5_block,
+        PixelFormat::Bc7 => encode_bc7_block,
+        other => panic!("no encoder for {other:?}"),
+    };
+
+    let mut pixels = Vec::new();
+    for (level, data) in image.levels().enumerate() {
+        let (width, height) = image.level_extent(level as u32);
+        for block_y in 0..height.div_ceil(4) {
+            for block_x in 0..width.div_ceil(4) {
+                let block = std::array::from_fn(|texel| {
+                    let x = (block_x * 4 + texel as u32 % 4).min(width - 1);
+                    let y = (block_y * 4 + texel as u32 / 4).min(height - 1);
+                    let offset = (y * width + x) as usize * 4;
+                    data[offset..offset + 4].try_into().unwrap()
+                });
+                pixels.extend_from_slice(&encode_block(&block));
+            }
+        }
+    }
+    ImageData {
+        pixels,
+        format,
+        ..image.clone()
+    }
+}
+
+/// Decodes a BC5 or BC7 image to RGBA8, keeping its mips. `None` for other formats.
+pub fn decompress(image: &ImageData) -> Option<ImageData> {
+    let decode_block: fn(&[u8; 16]) -> Block = match image.format {
+        PixelFormat::Bc5 => decode_bc5_block,
+        PixelFormat::Bc7 => decode_bc7_block,
+        _ => return None,
+    };
+
+    let mut pixels = Vec::with_capacity(
+        (0..image.mip_levels)
+            .map(|level| {
+                let (width, height) = image.level_extent(level);
+                PixelFormat::Rgba8.level_len(width, height)
+            })
+            .sum(),
+    );
+    for (level, data) in image.levels().enumerate() {
+        let (width, height) = image.level_extent(level as u32);
+        let blocks_wide = width.div_ceil(4) as usize;
+        let decoded: Vec<Block> = data
+            .chunks_exact(16)
+            .map(|block| decode_block(block.try_into().unwrap()))
+            .collect();
+        for y in 0..height as usize {
+            for x in 0..width as usize {
+                let block = &decoded[(y / 4) * blocks_wide + x / 4];
+                pixels.extend_from_slice(&block[(y % 4) * 4 + x % 4]);
+            }
+        }
+    }
+    Some(ImageData {
+        pixels,
+        format: PixelFormat::Rgba8,
+        ..image.clone()
+    })
+}
+
+/// Red and green, each as a BC4 block; blue and alpha are dropped.
+pub fn encode_bc5_block(block: &Block) -> [u8; 16] {
+    let mut out = [0; 16];
+    for channel in 0..2 {
+        let values = block.map(|texel| texel[channel]);
+        out[channel * 8..channel * 8 + 8].copy_from_slice(&encode_bc4(&values));
+    }
+    out
+}
+
+/// Decodes to red and green, with blue 0 and alpha 255.
+pub fn decode_bc5_block(bytes: &[u8; 16]) -> Block {
+    let red = decode_bc4(bytes[..8].try_into().unwrap());
+    let green = decode_bc4(bytes[8..].try_into().unwrap());
+    std::array::from_fn(|texel| [red[texel], green[texel], 0, 255])
+}
+
+/// Always uses the eight-value palette between the largest and smallest value.
+fn encode_bc4(values: &[u8; 16]) -> [u8; 8] {
+    let max = *values.iter().max().unwrap();
+    let min = *values.iter().min().unwrap();
+    let mut out = [0; 8];
+    out[0] = max;
+    out[1] = min;
+    if max == min {
+        return out;
+    }
+    let palette = bc4_palette(max, min);
+    let mut indices = 0u64;
+    for (texel, &value) in values.iter().enumerate() {
+        let index = (0..8)
+            .min_by_key(|&index| value.abs_diff(palette[index]))
+            .unwrap();
+        indices |= (index as u64) << (texel * 3);
+    }
+    out[2..].copy_from_slice(&indices.to_le_bytes()[..6]);
+    out
+}
+
+fn decode_bc4(bytes: &[u8; 8]) -> [u8; 16] {
+    let palette = bc4_palette(bytes[0], bytes[1]);
+    let mut index_bytes = [0; 8];
+    index_bytes[..6].copy_from_slice(&bytes[2..]);
+    let indices = u64::from_le_bytes(index_bytes);
+    std::array::from_fn(|texel| palette[(indices >> (texel * 3)) as usize & 7])
+}
+
+fn bc4_palette(first: u8, second: u8) -> [u8; 8] {
+    let (a, b) = (first as u32, second as u32);
+    let mut palette = [first, second, 0, 0, 0, 0, 0, 255];
+    if first > second {
+        for (i, entry) in palette.iter_mut().enumerate().skip(2) {
+            let i = i as u32;
+            *entry = (((8 - i) * a + (i - 1) * b + 3) / 7) as u8;
+        }
+    } else {
+        for (i, entry) in palette.iter_mut().enumerate().take(6).skip(2) {
+            let i = i as u32;
+            *entry = (((6 - i) * a + (i - 1) * b + 2) / 5) as u8;
+        }
+    }
+    palette
+}
+
+/// BC7 mode 6: endpoints at the extremes of the block along its principal axis.
+pub fn encode_bc7_block(block: &Block) -> [u8; 16] {
+    let texels = block.map(|texel| texel.map(f32::from));
+    let mean = texels.iter().fold([0.0; 4], |sum, texel| {
+        std::array::from_fn(|c| sum[c] + texel[c] / 16.0)
+    });
+    let axis = principal_axis(&texels, mean);
+    let project = |texel: &[f32; 4]| (0..4).map(|c| (texel[c] - mean[c]) * axis[c]).sum::<f32>();
+    let (low, high) = texels
+        .iter()
+        .fold((f32::MAX, f32::MIN), |(low, high), texel| {
+            let t = project(texel);
+            (low.min(t), high.max(t))
+        });
+    let endpoint = |t: f32| {
+        let color = std::array::from_fn(|c| mean[c] + axis[c] * t);
+        quantize_mode6_endpoint(color)
+    };
+    let mut endpoints = [endpoint(low), endpoint(high)];
+
+    let best_indices = |endpoints: &[[u8; 4]; 2]| {
+        let palette: [[u8; 4]; 16] = std::array::from_fn(|index| {
+            let weight = WEIGHTS_4[index];
+            std::array::from_fn(|c| interpolate(endpoints[0][c], endpoints[1][c], weight))
+        });
+        block.map(|texel| {
+            (0..16)
+                .min_by_key(|&index| squared_distance(&texel, &palette[index]))
+                .unwrap() as u32
+        })
+    };
+    let mut indices = best_indices(&endpoints);
+    // The anchor texel's index is stored without its top bit, so it must be below 8.
+    if indices[0] >= 8 {
+        endpoints.swap(0, 1);
+        indices = indices.map(|index| 15 - index);
+    }
+
+    let mut writer = BitWriter::default();
+    writer.write(1 << 6, 7);
+    for channel in 0..4 {
+        for endpoint in &endpoints {
+            writer.write(endpoint[channel] as u32 >> 1, 7);
+        }
+    }
+    for endpoint in &endpoints {
+        writer.write(endpoint[0] as u32 & 1, 1);
+    }
+    for (texel, &index) in indices.iter().enumerate() {
+        writer.write(index, if texel == 0 { 3 } else { 4 });
+    }
+    writer.bits.to_le_bytes()
+}
+
+/// Direction of greatest variance of the texels, found by power iteration.
+fn principal_axis(texels: &[[f32; 4]; 16], mean: [f32; 4]) -> [f32; 4] {
+    let mut covariance = [[0.0f32; 4]; 4];
+    for texel in texels {
+        for i in 0..4 {
+            for j in 0..4 {
+                covariance[i][j] += (texel[i] - mean[i]) * (texel[j] - mean[j]);
+            }
+        }
+    }
+    let mut axis = [1.0f32; 4];
+    for _ in 0..8 {
+        let next: [f32; 4] =
+            std::array::from_fn(|i| (0..4).map(|j| covariance[i][j] * axis[j]).sum());
+        let length = next.iter().map(|v| v * v).sum::<f32>().sqrt();
+        if length < 1e-6 {
+            break;
+        }
+        axis = next.map(|v| v / length);
+    }
+    let length = axis.iter().map(|v| v * v).sum::<f32>().sqrt();
+    axis.map(|v| v / length)
+}
+
+/// Mode 6 endpoints share one low bit across their four channels. Picks the bit that
+/// lands closest to `color`.
+fn quantize_mode6_endpoint(color: [f32; 4]) -> [u8; 4] {
+    let with_bit = |bit: u8| -> [u8; 4] {
+        color.map(|value| {
+            let high = ((value - bit as f32) / 2.0).round().clamp(0.0, 127.0) as u8;
+            high << 1 | bit
+        })
+    };
+    let error = |endpoint: &[u8; 4]| {
+        (0..4)
+            .map(|c| (endpoint[c] as f32 - color[c]).powi(2))
+            .sum::<f32>()
+    };
+    let (even, odd) = (with_bit(0), with_bit(1));
+    if error(&even) <= error(&odd) {
+        even
+    } else {
+        odd
+    }
+}
+
+fn squared_distance(a: &[u8; 4], b: &[u8; 4]) -> u32 {
+    (0..4).map(|c| (a[c].abs_diff(b[c]) as u32).pow(2)).sum()
+}
+
+/// Layout of one BC7 mode.
+struct Bc7Mode {
+    subsets: usize,
+    partition_bits: u32,
+    rotation_bits: u32,
+    index_selection_bits: u32,
+    color_bits: u32,
+    alpha_bits: u32,
+    /// One p-bit per endpoint.
+    endpoint_pbits: bool,
+    /// One p-bit per subset, shared by its two endpoints.
+    shared_pbits: bool,
+    index_bits: u32,
+    /// Separate alpha indices, modes 4 and 5 only.
+    index_bits2: u32,
+}
+
+#[allow(clippy::too_many_arguments)]
+const fn mode(
+    subsets: usize,
+    partition_bits: u32,
+    rotation_bits: u32,
+    index_selection_bits: u32,
+    color_bits: u32,
+    alpha_bits: u32,
+    endpoint_pbits: bool,
+    shared_pbits: bool,
+    index_bits: u32,
+    index_bits2: u32,
+) -> Bc7Mode {
+    Bc7Mode {
+        subsets,
+        partition_bits,
+        rotation_bits,
+        index_selection_bits,
+        color_bits,
+        alpha_bits,
+        endpoint_pbits,
+        shared_pbits,
+        index_bits,
+        index_bits2,
+    }
+}
+
+const BC7_MODES: [Bc7Mode; 8] = [
+    mode(3, 4, 0, 0, 4, 0, true, false, 3, 0),
+    mode(2, 6, 0, 0, 6, 0, false, true, 3, 0),
+    mode(3, 6, 0, 0, 5, 0, false, false, 2, 0),
+    mode(2, 6, 0, 0, 7, 0, true, false, 2, 0),
+    mode(1, 0, 2, 1, 5, 6, false, false, 2, 3),
+    mode(1, 0, 2, 0, 7, 8, false, false, 2, 2),
+    mode(1, 0, 0, 0, 7, 7, true, false, 4, 0),
+    mode(2, 6, 0, 0, 5, 5, true, false, 2, 0),
+];
+
+/// Decodes any BC7 block. Blocks with the reserved mode decode to transparent black.
+pub fn decode_bc7_block(bytes: &[u8; 16]) -> Block {
+    let mut reader = BitReader {
+        bits: u128::from_le_bytes(*bytes),
+        position: 0,
+    };
+    let mode_index = reader.bits.trailing_zeros();
+    if mode_index >= 8 {
+        return [[0; 4]; 16];
+    }
+    let mode = &BC7_MODES[mode_index as usize];
+    reader.position = mode_index + 1;
+    let partition = reader.read(mode.partition_bits) as usize;
+    let rotation = reader.read(mode.rotation_bits);
+    let index_selection = reader.read(mode.index_selection_bits);
+
+    let endpoint_count = mode.subsets * 2;
+    let mut endpoints = [[0u32; 4]; 6];
+    for channel in 0..3 {
+        for endpoint in &mut endpoints[..endpoint_count] {
+            endpoint[channel] = reader.read(mode.color_bits);
+        }
+    }
+    if mode.alpha_bits > 0 {
+        for endpoint in &mut endpoints[..endpoint_count] {
+            endpoint[3] = reader.read(mode.alpha_bits);
+        }
+    }
+    let mut pbits = [None; 6];
+    if mode.endpoint_pbits {
+        for pbit in &mut pbits[..endpoint_count] {
+            *pbit = Some(reader.read(1));
+        }
+    }
+    if mode.shared_pbits {
+        for subset in 0..mode.subsets {
+            let pbit = Some(reader.read(1));
+            pbits[subset * 2] = pbit;
+            pbits[subset * 2 + 1] = pbit;
+        }
+    }
+    let endpoints: [[u8; 4]; 6] = std::array::from_fn(|e| {
+        std::array::from_fn(|channel| {
+            let bits = if channel == 3 {
+                mode.alpha_bits
+            } else {
+                mode.color_bits
+            };
+            if bits == 0 {
+                return 255;
+            }
+            match pbits[e] {
+                Some(pbit) => expand(endpoints[e][channel] << 1 | pbit, bits + 1),
+                None => expand(endpoints[e][channel], bits),
+            }
+        })
+    });
+
+    let subset_of = |texel: usize| match mode.subsets {
+        2 => (PARTITIONS_2[partition] >> texel) as usize & 1,
+        3 => PARTITIONS_3[partition][texel] as usize,
+        _ => 0,
+    };
+    let is_anchor = |texel: usize| match mode.subsets {
+        2 => texel == 0 || texel == ANCHORS_2[partition] as usize,
+        3 => {
+            texel == 0
+                || texel == ANCHORS_3_SECOND[partition] as usize
+                || texel == ANCHORS_3_THIRD[partition] as usize
+        }
+        _ => texel == 0,
+    };
+    let mut primary = [0u32; 16];
+    for (texel, index) in primary.iter_mut().enumerate() {
+        *index = reader.read(mode.index_bits - is_anchor(texel) as u32);
+    }
+    let mut secondary = [0u32; 16];
+    if mode.index_bits2 > 0 {
+        for (texel, index) in secondary.iter_mut().enumerate() {
+            *index = reader.read(mode.index_bits2 - (texel == 0) as u32);
+        }
+    }
+    let (color_indices, color_bits, alpha_indices, alpha_bits) = if mode.index_bits2 == 0 {
+        (primary, mode.index_bits, primary, mode.index_bits)
+    } else if index_selection == 0 {
+        (primary, mode.index_bits, secondary, mode.index_bits2)
+    } else {
+        (secondary, mode.index_bits2, primary, mode.index_bits)
+    };
+
+    std::array::from_fn(|texel| {
+        let subset = subset_of(texel);
+        let (from, to) = (endpoints[subset * 2], endpoints[subset * 2 + 1]);
+        let color_weight = weights(color_bits)[color_indices[texel] as usize];
+        let alpha_weight = weights(alpha_bits)[alpha_indices[texel] as usize];
+        let mut color: [u8; 4] = std::array::from_fn(|c| {
+            let weight = if c == 3 { alpha_weight } else { color_weight };
+            interpolate(from[c], to[c], weight)
+        });
+        if rotation > 0 {
+            color.swap(3, rotation as usize - 1);
+        }
+        color
+    })
+}
+
+/// Widens a `bits`-wide endpoint component to 8 bits by repeating its top bits.
+fn expand(value: u32, bits: u32) -> u8 {
+    let value = value << (8 - bits);
+    (value | value >> bits) as u8
+}
+
+fn interpolate(from: u8, to: u8, weight: u32) -> u8 {
+    (((64 - weight) * from as u32 + weight * to as u32 + 32) >> 6) as u8
+}
+
+fn weights(index_bits: u32) -> &'static [u32] {
+    match index_bits {
+        2 => &WEIGHTS_2,
+        3 => &WEIGHTS_3,
+        _ => &WEIGHTS_4,
+    }
+}
+
+const WEIGHTS_2: [u32; 4] = [0, 21, 43, 64];
+const WEIGHTS_3: [u32; 8] = [0, 9, 18, 27, 37, 46, 55, 64];
+const WEIGHTS_4: [u32; 16] = [0, 4, 9, 13, 17, 21, 26, 30, 34, 38, 43, 47, 51, 55, 60, 64];
+
+#[derive(Default)]
+struct BitWriter {
+    bits: u128,
+    position: u32,
+}
+
+impl BitWriter {
+    fn write(&mut self, value: u32, count: u32) {
+        self.bits |= (value as u128 & ((1 << count) - 1)) << self.position;
+        self.position += count;
+    }
+}
+
+struct BitReader {
+    bits: u128,
+    position: u32,
+}
+
+impl BitReader {
+    fn read(&mut self, count: u32) -> u32 {
+        let value = (self.bits >> self.position) as u32 & ((1u64 << count) - 1) as u32;
+        self.position += count;
+        value
+    }
+}
+
+/// Subset of each texel for the two-subset partitions, bit `i` for texel `i`.
+#[rustfmt::skip]
+const PARTITIONS_2: [u16; 64] = [
+    0xcccc, 0x8888, 0xeeee, 0xecc8, 0xc880, 0xfeec, 0xfec8, 0xec80,
+    0xc800, 0xffec, 0xfe80, 0xe800, 0xffe8, 0xff00, 0xfff0, 0xf000,
+    0xf710, 0x008e, 0x7100, 0x08ce, 0x008c, 0x7310, 0x3100, 0x8cce,
+    0x088c, 0x3110, 0x6666, 0x366c, 0x17e8, 0x0ff0, 0x718e, 0x399c,
+    0xaaaa, 0xf0f0, 0x5a5a, 0x33cc, 0x3c3c, 0x55aa, 0x9696, 0xa55a,
+    0x73ce, 0x13c8, 0x324c, 0x3bdc, 0x6996, 0xc33c, 0x9966, 0x0660,
+    0x0272, 0x04e4, 0x4e40, 0x2720, 0xc936, 0x936c, 0x39c6, 0x639c,
+    0x9336, 0x9cc6, 0x817e, 0xe718, 0xccf0, 0x0fcc, 0x7744, 0xee22,
+];
+
+#[rustfmt::skip]
+const PARTITIONS_3: [[u8; 16]; 64] = [
+    [0, 0, 1, 1, 0, 0, 1, 1, 0, 2, 2, 1, 2, 2, 2, 2],
+    [0, 0, 0, 1, 0, 0, 1, 1, 2, 2, 1, 1, 2, 2, 2, 1],
+    [0, 0, 0, 0, 2, 0, 0, 1, 2, 2, 1, 1, 2, 2, 1, 1],
+    [0, 2, 2, 2, 0, 0, 2, 2, 0, 0, 1, 1, 0, 1, 1, 1],
+    [0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 2, 2, 1, 1, 2, 2],
+    [0, 0, 1, 1, 0, 0, 1, 1, 0, 0, 2, 2, 0, 0, 2, 2],
+    [0, 0, 2, 2, 0, 0, 2, 2, 1, 1, 1, 1, 1, 1, 1, 1],
+    [0, 0, 1, 1, 0, 0, 1, 1, 2, 2, 1, 1, 2, 2, 1, 1],
+    [0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2],
+    [0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1, 2, 2, 2, 2],
+    [0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 2, 2, 2, 2],
+    [0, 0, 1, 2, 0, 0, 1, 2, 0, 0, 1, 2, 0, 0, 1, 2],
+    [0, 1, 1, 2, 0, 1, 1, 2, 0, 1, 1, 2, 0, 1, 1, 2],
+    [0, 1, 2, 2, 0, 1, 2, 2, 0, 1, 2, 2, 0, 1, 2, 2],
+    [0, 0, 1, 1, 0, 1, 1, 2, 1, 1, 2, 2, 1, 2, 2, 2],
+    [0, 0, 1, 1, 2, 0, 0, 1, 2, 2, 0, 0, 2, 2, 2, 0],
+    [0, 0, 0, 1, 0, 0, 1, 1, 0, 1, 1, 2, 1, 1, 2, 2],
+    [0, 1, 1, 1, 0, 0, 1, 1, 2, 0, 0, 1, 2, 2, 0, 0],
+    [0, 0, 0, 0, 1, 1, 2, 2, 1, 1, 2, 2, 1, 1, 2, 2],
+    [0, 0, 2, 2, 0, 0, 2, 2, 0, 0, 2, 2, 1, 1, 1, 1],
+    [0, 1, 1, 1, 0, 1, 1, 1, 0, 2, 2, 2, 0, 2, 2, 2],
+    [0, 0, 0, 1, 0, 0, 0, 1, 2, 2, 2, 1, 2, 2, 2, 1],
+    [0, 0, 0, 0, 0, 0, 1, 1, 0, 1, 2, 2, 0, 1, 2, 2],
+    [0, 0, 0, 0, 1, 1, 0, 0, 2, 2, 1, 0, 2, 2, 1, 0],
+    [0, 1, 2, 2, 0, 1, 2, 2, 0, 0, 1, 1, 0, 0, 0, 0],
+    [0, 0, 1, 2, 0, 0, 1, 2, 1, 1, 2, 2, 2, 2, 2, 2],
+    [0, 1, 1, 0, 1, 2, 2, 1, 1, 2, 2, 1, 0, 1, 1, 0],
+    [0, 0, 0, 0, 0, 1, 1, 0, 1, 2, 2, 1, 1, 2, 2, 1],
+    [0, 0, 2, 2, 1, 1, 0, 2, 1, 1, 0, 2, 0, 0, 2, 2],
+    [0, 1, 1, 0, 0, 1, 1, 0, 2, 0, 0, 2, 2, 2, 2, 2],
+    [0, 0, 1, 1, 0, 1, 2, 2, 0, 1, 2, 2, 0, 0, 1, 1],
+    [0, 0, 0, 0, 2, 0, 0, 0, 2, 2, 1, 1, 2, 2, 2, 1],
+    [0, 0, 0, 0, 0, 0, 0, 2, 1, 1, 2, 2, 1, 2, 2, 2],
+    [0, 2, 2, 2, 0, 0, 2, 2, 0, 0, 1, 2, 0, 0, 1, 1],
+    [0, 0, 1, 1, 0, 0, 1, 2, 0, 0, 2, 2, 0, 2, 2, 2],
+    [0, 1, 2, 0, 0, 1, 2, 0, 0, 1, 2, 0, 0, 1, 2, 0],
+    [0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 0, 0, 0, 0],
+    [0, 1, 2, 0, 1, 2, 0, 1, 2, 0, 1, 2, 0, 1, 2, 0],
+    [0, 1, 2, 0, 2, 0, 1, 2, 1, 2, 0, 1, 0, 1, 2, 0],
+    [0, 0, 1, 1, 2, 2, 0, 0, 1, 1, 2, 2, 0, 0, 1, 1],
+    [0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 0, 0, 0, 0, 1, 1],
+    [0, 1, 0, 1, 0, 1, 0, 1, 2, 2, 2, 2, 2, 2, 2, 2],
+    [0, 0, 0, 0, 0, 0, 0, 0, 2, 1, 2, 1, 2, 1, 2, 1],
+    [0, 0, 2, 2, 1, 1, 2, 2, 0, 0, 2, 2, 1, 1, 2, 2],
+    [0, 0, 2, 2, 0, 0, 1, 1, 0, 0, 2, 2, 0, 0, 1, 1],
+    [0, 2, 2, 0, 1, 2, 2, 1, 0, 2, 2, 0, 1, 2, 2, 1],
+    [0, 1, 0, 1, 2, 2, 2, 2, 2, 2, 2, 2, 0, 1, 0, 1],
+    [0, 0, 0, 0, 2, 1, 2, 1, 2, 1, 2, 1, 2, 1, 2, 1],
+    [0, 1, 0, 1, 0, 1, 0, 1, 0, 1, 0, 1, 2, 2, 2, 2],
+    [0, 2, 2, 2, 0, 1, 1, 1, 0, 2, 2, 2, 0, 1, 1, 1],
+    [0, 0, 0, 2, 1, 1, 1, 2, 0, 0, 0, 2, 1, 1, 1, 2],
+    [0, 0, 0, 0, 2, 1, 1, 2, 2, 1, 1, 2, 2, 1, 1, 2],
+    [0, 2, 2, 2, 0, 1, 1, 1, 0, 1, 1, 1, 0, 2, 2, 2],
+    [0, 0, 0, 2, 1, 1, 1, 2, 1, 1, 1, 2, 0, 0, 0, 2],
+    [0, 1, 1, 0, 0, 1, 1, 0, 0, 1, 1, 0, 2, 2, 2, 2],
+    [0, 0, 0, 0, 0, 0, 0, 0, 2, 1, 1, 2, 2, 1, 1, 2],
+    [0, 1, 1, 0, 0, 1, 1, 0, 2, 2, 2, 2, 2, 2, 2, 2],
+    [0, 0, 2, 2, 0, 0, 1, 1, 0, 0, 1, 1, 0, 0, 2, 2],
+    [0, 0, 2, 2, 1, 1, 2, 2, 1, 1, 2, 2, 0, 0, 2, 2],
+    [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2, 1, 1, 2],
+    [0, 0, 0, 2, 0, 0, 0, 1, 0, 0, 0, 2, 0, 0, 0, 1],
+    [0, 2, 2, 2, 1, 2, 2, 2, 0, 2, 2, 2, 1, 2, 2, 2],
+    [0, 1, 0, 1, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2],
+    [0, 1, 1, 1, 2, 0, 1, 1, 2, 2, 0, 1, 2, 2, 2, 0],
+];
+
+/// Texel whose index of the second subset drops its top bit, per two-subset partition.
+#[rustfmt::skip]
+const ANCHORS_2: [u8; 64] = [
+    15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15,
+    15, 2, 8, 2, 2, 8, 8, 15, 2, 8, 2, 2, 8, 8, 2, 2,
+    15, 15, 6, 8, 2, 8, 15, 15, 2, 8, 2, 2, 2, 15, 15, 6,
+    6, 2, 6, 8, 15, 15, 2, 2, 15, 15, 15, 15, 15, 2, 2, 15,
+];
+
+#[rustfmt::skip]
+const ANCHORS_3_SECOND: [u8; 64] = [
+    3, 3, 15, 15, 8, 3, 15, 15, 8, 8, 6, 6, 6, 5, 3, 3,
+    3, 3, 8, 15, 3, 3, 6, 10, 5, 8, 8, 6, 8, 5, 15, 15,
+    8, 15, 3, 5, 6, 10, 8, 15, 15, 3, 15, 5, 15, 15, 15, 15,
+    3, 15, 5, 5, 5, 8, 5, 10, 5, 10, 8, 13, 15, 12, 3, 3,
+];
+
+#[rustfmt::skip]
+const ANCHORS_3_THIRD: [u8; 64] = [
+    15, 8, 8, 3, 15, 15, 3, 8, 15, 15, 15, 15, 15, 15, 15, 8,
+    15, 8, 15, 3, 15, 8, 15, 8, 3, 15, 6, 10, 15, 15, 10, 8,
+    15, 3, 15, 10, 10, 8, 9, 10, 6, 15, 8, 15, 3, 6, 6, 8,
+    15, 3, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 3, 15, 15, 8,
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ColorSpace;
+
+    fn gradient() -> Block {
+        std::array::from_fn(|texel| {
+            let t = texel as u8 * 16;
+            [t, 255 - t, t / 2, 255 - t / 4]
+        })
+    }
+
+    fn max_error(a: &Block, b: &Block, channels: usize) -> u8 {
+        a.iter()
+            .zip(b)
+            .flat_map(|(a, b)| (0..channels).map(move |c| a[c].abs_diff(b[c])))
+            .max()
+            .unwrap()
+    }
+
+    #[test]
+    fn bc7_and_bc5_round_trip_a_gradient() {
+        let block = gradient();
+        assert!(max_error(&block, &decode_bc7_block(&encode_bc7_block(&block)), 4) <= 8);
+        // BC4 spreads the 240 levels over 8 palette entries, half a step apart at worst.
+        assert!(max_error(&block, &decode_bc5_block(&encode_bc5_block(&block)), 2) <= 18);
+
+        let flat = [[10, 200, 30, 255]; 16];
+        // Mode 6 endpoints share a low bit across channels, so mixed parity is off by one.
+        assert!(max_error(&flat, &decode_bc7_block(&encode_bc7_block(&flat)), 4) <= 1);
+    }
+
+    #[test]
+    fn decodes_a_two_subset_bc7_block() {
+        // Mode 1, partition 0: columns 0-1 subset 0 (black), columns 2-3 subset 1
+        // (white), every index 0.
+        let mut writer = BitWriter::default();
+        writer.write(0b10, 2);
+        writer.write(0, 6);
+        for _channel in 0..3 {
+            for value in [0, 0, 63, 63] {
+                writer.write(value, 6);
+            }
+        }
+        writer.write(0, 1);
+        writer.write(1, 1);
+        let block = decode_bc7_block(&writer.bits.to_le_bytes());
+        for (texel, color) in block.iter().enumerate() {
+            let expected = if texel % 4 >= 2 { 255 } else { 0 };
+            assert_eq!(*color, [expected, expected, expected, 255], "texel {texel}");
+        }
+    }
+
+    #[test]
+    fn compress_pads_edge_blocks_and_keeps_mips() {
+        let image = ImageData {
+            pixels: vec![128; (6 * 5 + 3 * 2 + 1) * 4],
+            width: 6,
+            height: 5,
+            color_space: ColorSpace::Srgb,
+            format: PixelFormat::Rgba8,
+            mip_levels: 3,
+        };
+        let compressed = compress(&image, PixelFormat::Bc7);
+        assert_eq!(compressed.pixels.len(), compressed.data_len());
+        let decompressed = decompress(&compressed).unwrap();
+        assert_eq!(decompressed.pixels.len(), image.pixels.len());
+        assert!(decompressed
+            .pixels
+            .iter()
+            .all(|&value| value.abs_diff(128) <= 1));
+    }
+}
@@ -0,0 +1,190 @@
+//! Engine color type.
+//!
+//! Lighting, blending and everything the GPU consumes work in linear space, so that is
+//! what [`Color`] stores. The `srgb*`, `hex` and `hsv` constructors take the
+//! gamma-encoded values color pickers and art tools show and convert on the way in.
+
+use crate::math::{Vec3, Vec4};
+use serde::{Deserialize, Serialize};
+
+/// Linear RGBA color. Alpha is never gamma-encoded.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Color {
+    pub r: f32,
+    pub g: f32,
+    pub b: f32,
+    pub a: f32,
+}
+
+impl Color {
+    pub const WHITE: Self = Self::linear(1.0, 1.0, 1.0);
+    pub const BLACK: Self = Self::linear(0.0, 0.0, 0.0);
+    pub const TRANSPARENT: Self = Self::linear_rgba(0.0, 0.0, 0.0, 0.0);
+    pub const RED: Self = Self::linear(1.0, 0.0, 0.0);
+    pub const GREEN: Self = Self::linear(0.0, 1.0, 0.0);
+    pub const BLUE: Self = Self::linear(0.0, 0.0, 1.0);
+    pub const YELLOW: Self = Self::linear(1.0, 1.0, 0.0);
+    pub const CYAN: Self = Self::linear(0.0, 1.0, 1.0);
+    pub const MAGENTA: Self = Self::linear(1.0, 0.0, 1.0);
+    /// sRGB 50% gray.
+    pub const GRAY: Self = Self::linear(0.214_041, 0.214_041, 0.214_041);
+
+    pub const fn linear(r: f32, g: f32, b: f32) -> Self {
+        Self::linear_rgba(r, g, b, 1.0)
+    }
+
+    pub const fn linear_rgba(r: f32, g: f32, b: f32, a: f32) -> Self {
+        Self { r, g, b, a }
+    }
+
+    /// Opaque color from sRGB-encoded components in `[0, 1]`.
+    pub fn srgb(r: f32, g: f32, b: f32) -> Self {
+        Self::srgba(r, g, b, 1.0)
+    }
+
+    pub fn srgba(r: f32, g: f32, b: f32, a: f32) -> Self {
+        Self::linear_rgba(srgb_to_linear(r), srgb_to_linear(g), srgb_to_linear(b), a)
+    }
+
+    pub fn srgb_u8(r: u8, g: u8, b: u8) -> Self {
+        Self::srgba_u8(r, g, b, 255)
+    }
+
+    pub fn srgba_u8(r: u8, g: u8, b: u8, a: u8) -> Self {
+        let unit = |c: u8| c as f32 / 255.0;
+        Self::srgba(unit(r), unit(g), unit(b), unit(a))
+    }
+
+    /// Parses `RRGGBB` or `RRGGBBAA` sRGB hex, with or without a leading `#`.
+    pub fn hex(hex: &str) -> Option<Self> {
+        let digits = hex.strip_prefix('#').unwrap_or(hex);
+        if !matches!(digits.len(), 6 | 8) || !digits.is_ascii() {
+            return None;
+        }
+        let byte = |i: usize| u8::from_str_radix(&digits[i..i + 2], 16).ok();
+        let alpha = if digits.len() == 8 { byte(6)? } else { 255 };
+        Some(Self::srgba_u8(byte(0)?, byte(2)?, byte(4)?, alpha))
+    }
+
+    /// Opaque color from hue in degrees, saturation and value in `[0, 1]`. HSV is
+    /// defined over the sRGB-encoded components, as in color pickers.
+    pub fn hsv(hue: f32, saturation: f32, value: f32) -> Self {
+        let hue = hue.rem_euclid(360.0) / 60.0;
+        let chroma = value * saturation;
+        let x = chroma * (1.0 - (hue % 2.0 - 1.0).abs());
+        let (r, g, b) = match hue as u32 {
+            0 => (chroma, x, 0.0),
+            1 => (x, chroma, 0.0),
+            2 => (0.0, chroma, x),
+            3 => (0.0, x, chroma),
+            4 => (x, 0.0, chroma),
+            _ => (chroma, 0.0, x),
+        };
+        let m = value - chroma;
+        Self::srgb(r + m, g + m, b + m)
+    }
+
+    /// `(hue in degrees, saturation, value)`, the inverse of [`Color::hsv`]. Alpha is
+    /// dropped.
+    pub fn to_hsv(&self) -> (f32, f32, f32) {
+        let [r, g, b, _] = self.to_srgba();
+        let max = r.max(g).max(b);
+        let min = r.min(g).min(b);
+        let delta = max - min;
+        let hue = if delta == 0.0 {
+            0.0
+        } else if max == r {
+            60.0 * ((g - b) / delta).rem_euclid(6.0)
+        } else if max == g {
+            60.0 * ((b - r) / delta + 2.0)
+        } else {
+            60.0 * ((r - g) / delta + 4.0)
+        };
+        let saturation = if max == 0.0 { 0.0 } else { delta / max };
+        (hue, saturation, max)
+    }
+
+    /// sRGB-encoded `[r, g, b, a]`.
+    pub fn to_srgba(&self) -> [f32; 4] {
+        [
+            linear_to_srgb(self.r),
+            linear_to_srgb(self.g),
+            linear_to_srgb(self.b),
+            self.a,
+        ]
+    }
+
+    pub fn to_srgba_u8(&self) -> [u8; 4] {
+        self.to_srgba()
+            .map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u8)
+    }
+
+    pub fn with_alpha(mut self, a: f32) -> Self {
+        self.a = a;
+        self
+    }
+
+    /// Linear RGB, dropping alpha.
+    pub fn to_vec3(&self) -> Vec3 {
+        Vec3::new(self.r, self.g, self.b)
+    }
+
+    /// Linear RGBA.
+    pub fn to_vec4(&self) -> Vec4 {
+        Vec4::new(self.r, self.g, self.b, self.a)
+    }
+}
+
+impl Default for Color {
+    fn default() -> Self {
+        Self::WHITE
+    }
+}
+
+impl From<Color> for [f32; 4] {
+    fn from(color: Color) -> Self {
+        [color.r, color.g, color.b, color.a]
+    }
+}
+
+fn srgb_to_linear(c: f32) -> f32 {
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn linear_to_srgb(c: f32) -> f32 {
+    if c <= 0.0031308 {
+        c * 12.92
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn approx(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-3
+    }
+
+    #[test]
+    fn srgb_hex_and_hsv_agree() {
+        let orange = Color::hex("#ff8000").unwrap();
+        assert_eq!(orange, Color::srgb_u8(255, 128, 0));
+        assert_eq!(orange.to_srgba_u8(), [255, 128, 0, 255]);
+        assert!(approx(orange.g, 0.2158));
+        assert!(approx(Color::srgb(0.5, 0.5, 0.5).r, Color::GRAY.r));
+
+        let (hue, saturation, value) = orange.to_hsv();
+        assert!(approx(hue, 30.118) && approx(saturation, 1.0) && approx(value, 1.0));
+        let back = Color::hsv(hue, saturation, value);
+        assert!(approx(back.r, orange.r) && approx(back.g, orange.g) && approx(back.b, orange.b));
+
+        assert_eq!(Color::hex("#80ff0040").unwrap().to_srgba_u8()[3], 64);
+        assert_eq!(Color::hex("12345"), None);
+    }
+}
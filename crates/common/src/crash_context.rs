@@ -0,0 +1,56 @@
+//! Process-wide state for crash reports.
+//!
+//! Subsystems record what they know as they run: the renderer its GPU and the passes of
+//! the frame in flight. The app's panic hook reads it back with [`snapshot`], from
+//! whichever thread panicked, to describe what the engine was doing.
+
+use std::sync::{Mutex, MutexGuard};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CrashContext {
+    /// GPU name, driver and API version.
+    pub device: Option<String>,
+    /// Frame number of the renderer's current frame.
+    pub frame: u64,
+    /// Render passes begun this frame, in order.
+    pub passes: Vec<String>,
+    /// Breadcrumb report left behind when the GPU device was lost.
+    pub device_lost: Option<String>,
+}
+
+static CONTEXT: Mutex<CrashContext> = Mutex::new(CrashContext {
+    device: None,
+    frame: 0,
+    passes: Vec::new(),
+    device_lost: None,
+});
+
+/// A panic while the lock is held must not stop the report from being written.
+fn context() -> MutexGuard<'static, CrashContext> {
+    CONTEXT
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+pub fn set_device(description: String) {
+    context().device = Some(description);
+}
+
+pub fn begin_frame(frame: u64) {
+    let mut context = context();
+    context.frame = frame;
+    context.passes.clear();
+}
+
+pub fn record_pass(name: &str) {
+    context().passes.push(name.to_string());
+}
+
+pub fn set_device_lost(report: String) {
+    context().device_lost = Some(report);
+}
+
+/// Copy of everything recorded so far.
+pub fn snapshot() -> CrashContext {
+    context().clone()
+}
@@ -10,6 +10,7 @@ impl Guid {
         Self(uuid::Uuid::new_v4())
     }
 
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(s: &str) -> Option<Self> {
         uuid::Uuid::parse_str(s).ok().map(Self)
     }
@@ -17,6 +18,14 @@ impl Guid {
     pub const fn from_uuid(uuid: uuid::Uuid) -> Self {
         Self(uuid)
     }
+
+    pub const fn as_u128(&self) -> u128 {
+        self.0.as_u128()
+    }
+
+    pub const fn from_u128(value: u128) -> Self {
+        Self(uuid::Uuid::from_u128(value))
+    }
 }
 
 /// Creates a compile-time validated [`Guid`] constant from a UUID string literal.
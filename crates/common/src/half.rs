@@ -0,0 +1,63 @@
+//! IEEE 754 half-precision conversions, for vertex packing and reading back 16-bit float
+//! images.
+
+/// Nearest half-precision value, ties to even. Values past the half range become
+/// infinity.
+pub fn f32_to_f16(value: f32) -> u16 {
+    let bits = value.to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    let exponent = ((bits >> 23) & 0xff) as i32;
+    let mantissa = bits & 0x7f_ffff;
+    if exponent == 0xff {
+        let nan = if mantissa != 0 { 0x200 } else { 0 };
+        return sign | 0x7c00 | nan;
+    }
+
+    let half_exponent = exponent - 127 + 15;
+    if half_exponent >= 0x1f {
+        return sign | 0x7c00;
+    }
+    // Shifts the mantissa, implicit bit included for subnormal results, and rounds.
+    let round = |mantissa: u32, shift: u32| {
+        let truncated = mantissa >> shift;
+        let remainder = mantissa & ((1 << shift) - 1);
+        let halfway = 1 << (shift - 1);
+        truncated + (remainder > halfway || (remainder == halfway && truncated & 1 == 1)) as u32
+    };
+    if half_exponent <= 0 {
+        if half_exponent < -10 {
+            return sign;
+        }
+        return sign | round(mantissa | 0x80_0000, (14 - half_exponent) as u32) as u16;
+    }
+    // A carry out of the mantissa correctly bumps the exponent, up to infinity.
+    sign | round((half_exponent as u32) << 23 | mantissa, 13) as u16
+}
+
+pub fn f16_to_f32(half: u16) -> f32 {
+    let sign = ((half & 0x8000) as u32) << 16;
+    let exponent = ((half >> 10) & 0x1f) as u32;
+    let mantissa = (half & 0x3ff) as u32;
+    let bits = match exponent {
+        0 => {
+            let magnitude = mantissa as f32 * 2f32.powi(-24);
+            return f32::from_bits(sign | magnitude.to_bits());
+        }
+        0x1f => sign | 0x7f80_0000 | mantissa << 13,
+        _ => sign | (exponent + 112) << 23 | mantissa << 13,
+    };
+    f32::from_bits(bits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn half_round_trips() {
+        assert_eq!(f16_to_f32(f32_to_f16(1.5)), 1.5);
+        // Subnormal in half precision.
+        assert_eq!(f16_to_f32(f32_to_f16(-(2f32.powi(-20)))), -(2f32.powi(-20)));
+        assert_eq!(f32_to_f16(1.0e6), 0x7c00);
+    }
+}
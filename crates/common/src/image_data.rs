@@ -1,10 +1,147 @@
 use crate::handle::Handle;
+use serde::{Deserialize, Serialize};
+
+/// How a texture's 8-bit channels map to shading values.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ColorSpace {
+    /// Values are used as-is: normal maps, masks, lightmaps.
+    #[default]
+    Linear,
+    /// Gamma-encoded color, decoded to linear by the sampler: albedo, UI art.
+    Srgb,
+}
+
+/// How the texels of an [`ImageData`] are encoded. The block formats store 4x4 texel
+/// blocks of 16 bytes, a quarter of the size of RGBA8.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PixelFormat {
+    #[default]
+    Rgba8,
+    /// Two channels, red and green: tangent-space normal maps.
+    Bc5,
+    Bc7,
+    Astc4x4,
+}
+
+impl PixelFormat {
+    pub fn is_block_compressed(self) -> bool {
+        self != PixelFormat::Rgba8
+    }
+
+    /// Bytes of one `width` x `height` mip level.
+    pub fn level_len(self, width: u32, height: u32) -> usize {
+        if self.is_block_compressed() {
+            width.div_ceil(4) as usize * height.div_ceil(4) as usize * 16
+        } else {
+            width as usize * height as usize * 4
+        }
+    }
+}
 
 #[derive(Clone, Debug)]
 pub struct ImageData {
+    /// Every mip level, largest first, each tightly packed.
     pub pixels: Vec<u8>,
     pub width: u32,
     pub height: u32,
+    pub color_space: ColorSpace,
+    pub format: PixelFormat,
+    /// At least 1. Each level halves the previous one's size, rounding down to 1.
+    pub mip_levels: u32,
 }
 
-pub type ImageHandle = Handle<ImageData>;
\ No newline at end of file
+impl ImageData {
+    /// A single-level RGBA8 image.
+    pub fn rgba8(pixels: Vec<u8>, width: u32, height: u32, color_space: ColorSpace) -> Self {
+        Self {
+            pixels,
+            width,
+            height,
+            color_space,
+            format: PixelFormat::Rgba8,
+            mip_levels: 1,
+        }
+    }
+
+    /// Width and height of mip `level`.
+    pub fn level_extent(&self, level: u32) -> (u32, u32) {
+        ((self.width >> level).max(1), (self.height >> level).max(1))
+    }
+
+    /// Bytes of all mip levels together, i.e. the expected length of `pixels`.
+    pub fn data_len(&self) -> usize {
+        (0..self.mip_levels)
+            .map(|level| {
+                let (width, height) = self.level_extent(level);
+                self.format.level_len(width, height)
+            })
+            .sum()
+    }
+
+    /// The pixels of each mip level, largest first.
+    pub fn levels(&self) -> impl Iterator<Item = &[u8]> {
+        let mut offset = 0;
+        (0..self.mip_levels).map(move |level| {
+            let (width, height) = self.level_extent(level);
+            let len = self.format.level_len(width, height);
+            offset += len;
+            &self.pixels[offset - len..offset]
+        })
+    }
+
+    /// The image from mip `first_level` down, e.g. to upload only the smaller mips.
+    /// `first_level` is clamped to the last level.
+    pub fn mip_tail(&self, first_level: u32) -> ImageData {
+        let first_level = first_level.min(self.mip_levels - 1);
+        let skipped: usize = self.levels().take(first_level as usize).map(<[u8]>::len).sum();
+        let (width, height) = self.level_extent(first_level);
+        ImageData {
+            pixels: self.pixels[skipped..].to_vec(),
+            width,
+            height,
+            color_space: self.color_space,
+            format: self.format,
+            mip_levels: self.mip_levels - first_level,
+        }
+    }
+}
+
+/// Levels of a full mip chain for a `width` x `height` image, down to 1x1.
+pub fn full_mip_count(width: u32, height: u32) -> u32 {
+    32 - width.max(height).max(1).leading_zeros()
+}
+
+pub type ImageHandle = Handle<ImageData>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn levels_split_a_block_compressed_mip_chain() {
+        assert_eq!(full_mip_count(10, 4), 4);
+        let image = ImageData {
+            pixels: vec![0; 48],
+            width: 10,
+            height: 4,
+            color_space: ColorSpace::Linear,
+            format: PixelFormat::Bc7,
+            mip_levels: 4,
+        };
+        // 3x1 blocks, then 2x1 for 5x2, and a single block for 2x1 and 1x1.
+        assert_eq!(image.data_len(), 48 + 32 + 16 + 16);
+        let image = ImageData {
+            pixels: vec![0; image.data_len()],
+            ..image
+        };
+        let lens: Vec<usize> = image.levels().map(<[u8]>::len).collect();
+        assert_eq!(lens, [48, 32, 16, 16]);
+
+        let tail = image.mip_tail(1);
+        assert_eq!((tail.width, tail.height, tail.mip_levels), (5, 2, 3));
+        assert_eq!(tail.pixels.len(), 32 + 16 + 16);
+        assert_eq!(image.mip_tail(9).mip_levels, 1);
+    }
+}
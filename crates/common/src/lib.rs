@@ -1,17 +1,30 @@
+pub mod block_compression;
+mod color;
+pub mod crash_context;
 mod guid;
+pub mod half;
 mod handle;
 mod image_data;
+mod light_probe;
+pub mod math;
 mod mesh;
+mod output_mode;
 mod shader_data;
+pub mod trace;
 mod typed_store;
 mod types;
+mod vertex_packing;
 
+pub use color::Color;
 pub use guid::Guid;
 #[doc(hidden)]
 pub use uuid;
 pub use handle::Handle;
-pub use image_data::{ImageData, ImageHandle};
-pub use mesh::{MeshData, MeshHandle, SubMesh, Vertex};
+pub use image_data::{full_mip_count, ColorSpace, ImageData, ImageHandle, PixelFormat};
+pub use light_probe::{LightProbe, SphericalHarmonics};
+pub use mesh::{MeshData, MeshHandle, SubMesh, Vertex, VertexExtra, VertexSkin};
+pub use output_mode::{OutputMode, OutputSettings};
 pub use shader_data::{ShaderData, ShaderHandle};
 pub use typed_store::TypedStore;
 pub use types::*;
+pub use vertex_packing::{PackedVertex, VertexEncoding};
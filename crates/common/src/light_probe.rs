@@ -0,0 +1,148 @@
+//! Baked light probe samples.
+//!
+//! A probe stores the light arriving at a point from every direction as order-2 spherical
+//! harmonics, nine RGB coefficients. That is enough to reconstruct the diffuse irradiance
+//! a surface of any orientation receives there, and probes blend by weighting their
+//! coefficients.
+
+use crate::math::Vec3;
+use serde::{Deserialize, Serialize};
+use std::f32::consts::PI;
+
+/// Incoming RGB radiance projected onto the first nine real spherical harmonics.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct SphericalHarmonics {
+    pub coefficients: [Vec3; 9],
+}
+
+impl SphericalHarmonics {
+    /// Adds radiance arriving from `dir`, a unit vector, weighted by the solid angle
+    /// the sample stands for. Summing `4π / n` weighted uniform sphere samples projects
+    /// the radiance they sample.
+    pub fn add_sample(&mut self, dir: &Vec3, radiance: &Vec3, weight: f32) {
+        for (coefficient, basis) in self.coefficients.iter_mut().zip(basis(dir)) {
+            *coefficient += radiance * (basis * weight);
+        }
+    }
+
+    /// Adds `other` scaled by `weight`, for blending probes.
+    pub fn add_scaled(&mut self, other: &Self, weight: f32) {
+        for (coefficient, other) in self.coefficients.iter_mut().zip(&other.coefficients) {
+            *coefficient += other * weight;
+        }
+    }
+
+    /// Irradiance received by a surface facing `normal`, a unit vector. A diffuse
+    /// surface reflects `albedo * irradiance / π` of it.
+    pub fn irradiance(&self, normal: &Vec3) -> Vec3 {
+        let mut irradiance = Vec3::zeros();
+        for (coefficient, basis) in self.convolved().iter().zip(basis(normal)) {
+            irradiance += coefficient * basis;
+        }
+        irradiance.sup(&Vec3::zeros())
+    }
+
+    /// The coefficients convolved with the clamped cosine lobe. Weighting them by the
+    /// basis functions at a normal gives the irradiance there, before clamping negative
+    /// values to zero, which is how shaders evaluate probes.
+    pub fn convolved(&self) -> [Vec3; 9] {
+        let mut convolved = self.coefficients;
+        for (coefficient, scale) in convolved.iter_mut().zip(BAND_SCALE) {
+            *coefficient *= scale;
+        }
+        convolved
+    }
+
+    /// Irradiance averaged over every surface orientation; only the constant band
+    /// contributes.
+    pub fn average_irradiance(&self) -> Vec3 {
+        self.coefficients[0] * (PI * SH_Y00)
+    }
+}
+
+/// One baked probe.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct LightProbe {
+    /// Light bounced towards the probe by the surfaces around it. Light coming straight
+    /// from the sky is left out; the environment's ambient term covers that.
+    pub bounce: SphericalHarmonics,
+    /// Fraction of directions in which the probe sees the sky, in `[0, 1]`. Dims the
+    /// environment's ambient light on surfaces near the probe.
+    pub sky_visibility: f32,
+}
+
+impl LightProbe {
+    /// Adds `other` scaled by `weight`, for blending probes.
+    pub fn add_scaled(&mut self, other: &Self, weight: f32) {
+        self.bounce.add_scaled(&other.bounce, weight);
+        self.sky_visibility += other.sky_visibility * weight;
+    }
+}
+
+const SH_Y00: f32 = 0.282_095;
+
+/// Convolution with the clamped cosine lobe, per band.
+const BAND_SCALE: [f32; 9] = [
+    PI,
+    2.0 * PI / 3.0,
+    2.0 * PI / 3.0,
+    2.0 * PI / 3.0,
+    PI / 4.0,
+    PI / 4.0,
+    PI / 4.0,
+    PI / 4.0,
+    PI / 4.0,
+];
+
+fn basis(dir: &Vec3) -> [f32; 9] {
+    let (x, y, z) = (dir.x, dir.y, dir.z);
+    [
+        SH_Y00,
+        0.488_603 * y,
+        0.488_603 * z,
+        0.488_603 * x,
+        1.092_548 * x * y,
+        1.092_548 * y * z,
+        0.315_392 * (3.0 * z * z - 1.0),
+        1.092_548 * x * z,
+        0.546_274 * (x * x - y * y),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Projects `radiance(dir)` with a Fibonacci sphere of `n` samples.
+    fn project(n: usize, radiance: impl Fn(&Vec3) -> Vec3) -> SphericalHarmonics {
+        let mut sh = SphericalHarmonics::default();
+        let golden = PI * (3.0 - 5.0f32.sqrt());
+        for i in 0..n {
+            let z = 1.0 - 2.0 * (i as f32 + 0.5) / n as f32;
+            let r = (1.0 - z * z).sqrt();
+            let phi = golden * i as f32;
+            let dir = Vec3::new(r * phi.cos(), r * phi.sin(), z);
+            sh.add_sample(&dir, &radiance(&dir), 4.0 * PI / n as f32);
+        }
+        sh
+    }
+
+    #[test]
+    fn uniform_radiance_gives_pi_times_radiance_everywhere() {
+        let sh = project(2048, |_| Vec3::new(1.0, 0.5, 0.25));
+        for normal in [Vec3::x(), -Vec3::y(), Vec3::z()] {
+            let irradiance = sh.irradiance(&normal);
+            assert!((irradiance - Vec3::new(PI, PI * 0.5, PI * 0.25)).norm() < 0.02);
+        }
+        assert!((sh.average_irradiance().x - PI).abs() < 0.01);
+    }
+
+    #[test]
+    fn light_from_one_side_lights_surfaces_facing_it() {
+        let sh = project(2048, |dir| Vec3::repeat(dir.x.max(0.0)));
+        let facing = sh.irradiance(&Vec3::x()).x;
+        let away = sh.irradiance(&-Vec3::x()).x;
+        let sideways = sh.irradiance(&Vec3::y()).x;
+        assert!(facing > sideways && sideways > away, "{facing} {sideways} {away}");
+    }
+}
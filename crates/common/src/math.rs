@@ -0,0 +1,86 @@
+//! Engine-wide math types.
+//!
+//! The aliases name the same nalgebra types `nalgebra_glm` uses, so values move between
+//! crates (and in and out of glm functions) without conversion. Prefer them over spelling
+//! out `Vector3<f32>` in new code.
+
+use nalgebra::{Matrix3, Matrix4, Quaternion, Vector2, Vector3, Vector4};
+
+pub use nalgebra::{Point3, UnitQuaternion};
+
+pub type Vec2 = Vector2<f32>;
+pub type Vec3 = Vector3<f32>;
+pub type Vec4 = Vector4<f32>;
+pub type Mat3 = Matrix3<f32>;
+pub type Mat4 = Matrix4<f32>;
+pub type Quat = Quaternion<f32>;
+
+pub fn vec2(x: f32, y: f32) -> Vec2 {
+    Vec2::new(x, y)
+}
+
+pub fn vec3(x: f32, y: f32, z: f32) -> Vec3 {
+    Vec3::new(x, y, z)
+}
+
+pub fn vec4(x: f32, y: f32, z: f32, w: f32) -> Vec4 {
+    Vec4::new(x, y, z, w)
+}
+
+/// Conversion to and from the plain arrays used by file formats, importers and save
+/// data. Matrices are column-major, matching GLSL.
+pub trait ArrayConvert: Sized {
+    type Array;
+
+    fn to_array(&self) -> Self::Array;
+    fn from_array(array: Self::Array) -> Self;
+}
+
+macro_rules! array_convert {
+    ($ty:ty, $array:ty) => {
+        impl ArrayConvert for $ty {
+            type Array = $array;
+
+            fn to_array(&self) -> Self::Array {
+                (*self).into()
+            }
+
+            fn from_array(array: Self::Array) -> Self {
+                array.into()
+            }
+        }
+    };
+}
+
+array_convert!(Vec2, [f32; 2]);
+array_convert!(Vec3, [f32; 3]);
+array_convert!(Vec4, [f32; 4]);
+array_convert!(Mat3, [[f32; 3]; 3]);
+array_convert!(Mat4, [[f32; 4]; 4]);
+
+impl ArrayConvert for Quat {
+    /// `[x, y, z, w]`, the glTF order.
+    type Array = [f32; 4];
+
+    fn to_array(&self) -> Self::Array {
+        self.coords.into()
+    }
+
+    fn from_array(array: Self::Array) -> Self {
+        Quat::from_vector(array.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matrices_round_trip_column_major() {
+        let mut m = Mat4::identity();
+        m[(0, 3)] = 5.0;
+        let array = m.to_array();
+        assert_eq!(array[3][0], 5.0);
+        assert_eq!(Mat4::from_array(array), m);
+    }
+}
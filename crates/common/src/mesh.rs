@@ -1,16 +1,48 @@
 use crate::handle::Handle;
-use nalgebra::{Vector2, Vector3};
+use crate::math::{Vec2, Vec3, Vec4};
+use crate::VertexEncoding;
 
 #[repr(C)]
 #[derive(Clone, Debug, Copy, Default)]
 pub struct Vertex {
-    pub pos: Vector3<f32>,
-    pub color: Vector3<f32>,
-    pub tex_coord: Vector2<f32>,
-    pub normal: Vector3<f32>,
+    pub pos: Vec3,
+    pub tex_coord: Vec2,
+    pub normal: Vec3,
+    /// Tangent along +U in xyz; w is the bitangent sign (+1 or -1) for mirrored UVs.
+    pub tangent: Vec4,
     pub texture_index: u32,
 }
 
+/// Optional per-vertex attributes, stored in a second stream parallel to the vertices
+/// so meshes and pipelines that don't use them pay nothing for them.
+#[repr(C)]
+#[derive(Clone, Debug, Copy)]
+pub struct VertexExtra {
+    /// Second UV set, for lightmaps and detail maps.
+    pub tex_coord1: Vec2,
+    /// Linear RGBA, multiplied into the base color.
+    pub color: Vec4,
+}
+
+impl Default for VertexExtra {
+    fn default() -> Self {
+        Self {
+            tex_coord1: Vec2::zeros(),
+            color: Vec4::repeat(1.0),
+        }
+    }
+}
+
+/// Skinning attributes, stored in a third stream for meshes bound to a skeleton.
+#[repr(C)]
+#[derive(Clone, Debug, Copy, Default)]
+pub struct VertexSkin {
+    /// Indices into the skin's joint list.
+    pub joints: [u32; 4],
+    /// Blend weight of each joint; they sum to 1.
+    pub weights: Vec4,
+}
+
 /// A contiguous range of the index buffer that uses a single material slot.
 #[derive(Clone, Debug)]
 pub struct SubMesh {
@@ -24,9 +56,38 @@ pub struct SubMesh {
 #[derive(Clone, Debug)]
 pub struct MeshData {
     pub vertices: Vec<Vertex>,
+    /// GPU layout of `vertices`, which are already quantized to it.
+    pub vertex_encoding: VertexEncoding,
     pub indices: Vec<u32>,
+    /// One entry per vertex when the source had a second UV set or vertex colors.
+    pub extras: Option<Vec<VertexExtra>>,
+    /// One entry per vertex when the source mesh is skinned.
+    pub skin: Option<Vec<VertexSkin>>,
     /// One entry per material slot, in order. Must not be empty.
     pub submeshes: Vec<SubMesh>,
 }
 
+impl MeshData {
+    /// Sphere around every vertex, centered on their bounding box. Returns the center
+    /// and radius, or a zero sphere for a mesh without vertices.
+    pub fn bounding_sphere(&self) -> (Vec3, f32) {
+        let Some(first) = self.vertices.first() else {
+            return (Vec3::zeros(), 0.0);
+        };
+        let (min, max) = self
+            .vertices
+            .iter()
+            .fold((first.pos, first.pos), |(min, max), v| {
+                (min.inf(&v.pos), max.sup(&v.pos))
+            });
+        let center = (min + max) * 0.5;
+        let radius = self
+            .vertices
+            .iter()
+            .map(|v| (v.pos - center).norm())
+            .fold(0.0, f32::max);
+        (center, radius)
+    }
+}
+
 pub type MeshHandle = Handle<MeshData>;
@@ -0,0 +1,42 @@
+use serde::{Deserialize, Serialize};
+
+/// How the final image is encoded for the display.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputMode {
+    /// 8-bit sRGB. Supported everywhere; values above 1 are clipped.
+    #[default]
+    Sdr,
+    /// 10-bit Rec. 2020 with the PQ (SMPTE ST 2084) transfer function.
+    Hdr10,
+    /// 16-bit float, linear Rec. 709 primaries with 1.0 at 80 nits. Values above 1 are
+    /// brighter than SDR white.
+    ScRgb,
+}
+
+impl OutputMode {
+    pub fn is_hdr(self) -> bool {
+        self != OutputMode::Sdr
+    }
+}
+
+/// Display output requested by the game.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct OutputSettings {
+    pub mode: OutputMode,
+    /// Luminance in nits of a scene value of 1.0, and of the UI, in HDR modes.
+    pub paper_white_nits: f32,
+    /// Brightest luminance sent to the display in HDR modes. Brighter pixels are scaled
+    /// down, keeping their hue.
+    pub peak_nits: f32,
+}
+
+impl Default for OutputSettings {
+    fn default() -> Self {
+        Self {
+            mode: OutputMode::Sdr,
+            paper_white_nits: 200.0,
+            peak_nits: 1000.0,
+        }
+    }
+}
@@ -0,0 +1,252 @@
+//! Session traces in the Chrome trace event format.
+//!
+//! While a session is active, subsystems record timed spans: the engine its systems, the
+//! asset context its loads and the renderer the GPU time of each pass. Events are
+//! streamed to the session file as they arrive, so a whole play session can be traced
+//! without holding it in memory. Open the file in `chrome://tracing` or Perfetto.
+//!
+//! Recording is a no-op without an active session; [`is_active`] is a single atomic load
+//! for callers that want to skip building an event name.
+
+use std::borrow::Cow;
+use std::cell::Cell;
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+/// Track the GPU pass timings are recorded on.
+const GPU_TRACK: u64 = 0;
+
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+static SESSION: Mutex<Option<Session>> = Mutex::new(None);
+static NEXT_TRACK: AtomicU64 = AtomicU64::new(GPU_TRACK + 1);
+
+thread_local! {
+    static TRACK: Cell<u64> = const { Cell::new(GPU_TRACK) };
+}
+
+struct Session {
+    writer: TraceWriter<BufWriter<File>>,
+    epoch: Instant,
+    named_tracks: HashSet<u64>,
+}
+
+/// A panic while the lock is held must not lose the events recorded so far.
+fn session() -> MutexGuard<'static, Option<Session>> {
+    SESSION
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Starts a session writing to `path`, finishing any session already running.
+pub fn start(path: &Path) -> io::Result<()> {
+    let mut writer = TraceWriter::new(BufWriter::new(File::create(path)?))?;
+    writer.track_name(GPU_TRACK, "GPU")?;
+
+    let previous = session().replace(Session {
+        writer,
+        epoch: Instant::now(),
+        named_tracks: HashSet::from([GPU_TRACK]),
+    });
+    ACTIVE.store(true, Ordering::Release);
+    if let Some(previous) = previous {
+        previous.writer.finish()?;
+    }
+    Ok(())
+}
+
+/// Closes the session's event list and flushes the file. Does nothing without a session.
+pub fn finish() -> io::Result<()> {
+    ACTIVE.store(false, Ordering::Release);
+    match session().take() {
+        Some(session) => session.writer.finish(),
+        None => Ok(()),
+    }
+}
+
+pub fn is_active() -> bool {
+    ACTIVE.load(Ordering::Acquire)
+}
+
+/// Records a span on the calling thread's track.
+pub fn record(category: &str, name: &str, start: Instant, duration: Duration) {
+    if is_active() {
+        record_on(current_track(), category, name, start, duration);
+    }
+}
+
+/// Records GPU time of a render pass. `start` is the pass's position on the CPU clock.
+pub fn record_gpu(name: &str, start: Instant, duration: Duration) {
+    if is_active() {
+        record_on(GPU_TRACK, "gpu", name, start, duration);
+    }
+}
+
+/// Starts a span on the calling thread's track that is recorded when dropped.
+pub fn span(category: &'static str, name: impl Into<Cow<'static, str>>) -> Span {
+    Span {
+        category,
+        name: name.into(),
+        start: Instant::now(),
+    }
+}
+
+#[must_use = "the span is recorded when dropped"]
+pub struct Span {
+    category: &'static str,
+    name: Cow<'static, str>,
+    start: Instant,
+}
+
+impl Drop for Span {
+    fn drop(&mut self) {
+        record(self.category, &self.name, self.start, self.start.elapsed());
+    }
+}
+
+fn current_track() -> u64 {
+    TRACK.with(|track| {
+        if track.get() == GPU_TRACK {
+            track.set(NEXT_TRACK.fetch_add(1, Ordering::Relaxed));
+        }
+        track.get()
+    })
+}
+
+fn record_on(track: u64, category: &str, name: &str, start: Instant, duration: Duration) {
+    let mut guard = session();
+    let Some(session) = guard.as_mut() else {
+        return;
+    };
+
+    let mut result = Ok(());
+    if session.named_tracks.insert(track) {
+        let thread = std::thread::current();
+        let name = thread
+            .name()
+            .map_or_else(|| format!("Thread {track}"), str::to_string);
+        result = session.writer.track_name(track, &name);
+    }
+    let timestamp = start.saturating_duration_since(session.epoch);
+    result = result.and_then(|_| {
+        session
+            .writer
+            .event(track, category, name, timestamp, duration)
+    });
+
+    if let Err(error) = result {
+        eprintln!("Trace session stopped: {error}");
+        ACTIVE.store(false, Ordering::Release);
+        *guard = None;
+    }
+}
+
+/// Writes a `{"traceEvents": [...]}` document one event at a time.
+struct TraceWriter<W: Write> {
+    out: W,
+    first: bool,
+}
+
+impl<W: Write> TraceWriter<W> {
+    fn new(mut out: W) -> io::Result<Self> {
+        out.write_all(b"{\"displayTimeUnit\":\"ms\",\"traceEvents\":[")?;
+        Ok(Self { out, first: true })
+    }
+
+    /// A complete ("X") event; times are written in microseconds since the session began.
+    fn event(
+        &mut self,
+        track: u64,
+        category: &str,
+        name: &str,
+        timestamp: Duration,
+        duration: Duration,
+    ) -> io::Result<()> {
+        self.separator()?;
+        write!(
+            self.out,
+            "{{\"ph\":\"X\",\"pid\":1,\"tid\":{track},\"cat\":"
+        )?;
+        write_json_string(&mut self.out, category)?;
+        self.out.write_all(b",\"name\":")?;
+        write_json_string(&mut self.out, name)?;
+        write!(
+            self.out,
+            ",\"ts\":{:.3},\"dur\":{:.3}}}",
+            timestamp.as_secs_f64() * 1e6,
+            duration.as_secs_f64() * 1e6
+        )
+    }
+
+    /// Metadata event labelling a track in the viewer.
+    fn track_name(&mut self, track: u64, name: &str) -> io::Result<()> {
+        self.separator()?;
+        write!(
+            self.out,
+            "{{\"ph\":\"M\",\"pid\":1,\"tid\":{track},\"name\":\"thread_name\",\"args\":{{\"name\":"
+        )?;
+        write_json_string(&mut self.out, name)?;
+        self.out.write_all(b"}}")
+    }
+
+    fn separator(&mut self) -> io::Result<()> {
+        if !std::mem::take(&mut self.first) {
+            self.out.write_all(b",\n")?;
+        }
+        Ok(())
+    }
+
+    fn finish(mut self) -> io::Result<()> {
+        self.out.write_all(b"]}\n")?;
+        self.out.flush()
+    }
+}
+
+fn write_json_string(out: &mut impl Write, value: &str) -> io::Result<()> {
+    out.write_all(b"\"")?;
+    for c in value.chars() {
+        match c {
+            '"' => out.write_all(b"\\\"")?,
+            '\\' => out.write_all(b"\\\\")?,
+            '\n' => out.write_all(b"\\n")?,
+            c if (c as u32) < 0x20 => write!(out, "\\u{:04x}", c as u32)?,
+            c => write!(out, "{c}")?,
+        }
+    }
+    out.write_all(b"\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn writes_complete_events() {
+        let mut buffer = Vec::new();
+        let mut writer = TraceWriter::new(&mut buffer).unwrap();
+        writer.track_name(3, "Main").unwrap();
+        writer
+            .event(
+                3,
+                "system",
+                "game::\"spin\"",
+                Duration::from_micros(1500),
+                Duration::from_nanos(2500),
+            )
+            .unwrap();
+        writer.finish().unwrap();
+
+        let json = String::from_utf8(buffer).unwrap();
+        assert_eq!(
+            json,
+            "{\"displayTimeUnit\":\"ms\",\"traceEvents\":[\
+             {\"ph\":\"M\",\"pid\":1,\"tid\":3,\"name\":\"thread_name\",\"args\":{\"name\":\"Main\"}},\n\
+             {\"ph\":\"X\",\"pid\":1,\"tid\":3,\"cat\":\"system\",\"name\":\"game::\\\"spin\\\"\",\
+             \"ts\":1500.000,\"dur\":2.500}]}\n"
+        );
+    }
+}
@@ -33,7 +33,36 @@ impl<T> TypedStore<T> {
         Some(handle)
     }
     
+    /// The handle `guid` was loaded under, if it is loaded.
+    pub fn handle_of(&self, guid: Guid) -> Option<Handle<T>> {
+        self.guid_to_handle.get(&guid).copied()
+    }
+
     pub fn get(&self, handle: Handle<T>) -> Option<&T> {
         self.data.get(&handle)
     }
+
+    pub fn guid_of(&self, handle: Handle<T>) -> Option<Guid> {
+        self.handle_to_guid.get(&handle).copied()
+    }
+
+    /// Handles of every loaded value.
+    pub fn handles(&self) -> impl Iterator<Item = Handle<T>> + '_ {
+        self.data.keys().copied()
+    }
+
+    /// Unloads a value. Loading its GUID again yields a new handle.
+    pub fn remove(&mut self, handle: Handle<T>) -> Option<T> {
+        let value = self.data.remove(&handle)?;
+        if let Some(guid) = self.handle_to_guid.remove(&handle) {
+            self.guid_to_handle.remove(&guid);
+        }
+        Some(value)
+    }
+}
+
+impl<T> Default for TypedStore<T> {
+    fn default() -> Self {
+        Self::new()
+    }
 }
@@ -0,0 +1,121 @@
+use crate::half::{f16_to_f32, f32_to_f16};
+use crate::math::{Vec2, Vec3, Vec4};
+use crate::Vertex;
+use serde::{Deserialize, Serialize};
+
+/// How a mesh's vertices are laid out in GPU memory. Both encodings feed the same vertex
+/// shader inputs, so shaders work with either.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VertexEncoding {
+    /// [`Vertex`], 32-bit floats throughout.
+    #[default]
+    Full,
+    /// [`PackedVertex`], less than half the size. Positions keep about three significant
+    /// digits, so it suits props and dense meshes rather than large terrain pieces.
+    Packed,
+}
+
+impl VertexEncoding {
+    /// Every encoding, in declaration order, so `encoding as usize` indexes it.
+    pub const ALL: [VertexEncoding; 2] = [VertexEncoding::Full, VertexEncoding::Packed];
+
+    /// `vertex` as the GPU sees it in this encoding. Meshes store quantized vertices so
+    /// bounds, picking and collision match what is drawn.
+    pub fn quantize(self, vertex: &Vertex) -> Vertex {
+        match self {
+            VertexEncoding::Full => *vertex,
+            VertexEncoding::Packed => PackedVertex::pack(vertex).unpack(vertex.texture_index),
+        }
+    }
+}
+
+/// 20-byte vertex: half-float position and UV, and normal and tangent as signed
+/// normalized 10-10-10-2 values. The tangent's 2-bit w holds the bitangent sign.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PackedVertex {
+    /// xyz, with w padding the attribute to a format every GPU can fetch.
+    pub pos: [u16; 4],
+    pub tex_coord: [u16; 2],
+    pub normal: u32,
+    pub tangent: u32,
+}
+
+impl PackedVertex {
+    /// Drops `texture_index`, which no shader reads.
+    pub fn pack(vertex: &Vertex) -> Self {
+        let pos = vertex.pos.map(f32_to_f16);
+        Self {
+            pos: [pos.x, pos.y, pos.z, f32_to_f16(1.0)],
+            tex_coord: [
+                f32_to_f16(vertex.tex_coord.x),
+                f32_to_f16(vertex.tex_coord.y),
+            ],
+            normal: pack_snorm_10_10_10_2(vertex.normal.push(0.0)),
+            tangent: pack_snorm_10_10_10_2(vertex.tangent),
+        }
+    }
+
+    pub fn unpack(&self, texture_index: u32) -> Vertex {
+        Vertex {
+            pos: Vec3::from_fn(|i, _| f16_to_f32(self.pos[i])),
+            tex_coord: Vec2::new(f16_to_f32(self.tex_coord[0]), f16_to_f32(self.tex_coord[1])),
+            normal: unpack_snorm_10_10_10_2(self.normal).xyz(),
+            tangent: unpack_snorm_10_10_10_2(self.tangent),
+            texture_index,
+        }
+    }
+}
+
+/// x in the low bits, matching `A2B10G10R10_SNORM_PACK32`.
+fn pack_snorm_10_10_10_2(value: Vec4) -> u32 {
+    let snorm = |value: f32, max: f32, mask: u32| {
+        (value.clamp(-1.0, 1.0) * max).round() as i32 as u32 & mask
+    };
+    snorm(value.x, 511.0, 0x3ff)
+        | snorm(value.y, 511.0, 0x3ff) << 10
+        | snorm(value.z, 511.0, 0x3ff) << 20
+        | snorm(value.w, 1.0, 0x3) << 30
+}
+
+fn unpack_snorm_10_10_10_2(packed: u32) -> Vec4 {
+    // Sign-extends the field at `shift` by moving it to the top of an i32.
+    let snorm = |shift: u32, bits: u32, max: f32| {
+        let value = (packed << (32 - shift - bits)) as i32 >> (32 - bits);
+        (value as f32 / max).max(-1.0)
+    };
+    Vec4::new(
+        snorm(0, 10, 511.0),
+        snorm(10, 10, 511.0),
+        snorm(20, 10, 511.0),
+        snorm(30, 2, 1.0),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn packing_quantizes_once() {
+        assert_eq!(size_of::<PackedVertex>(), 20);
+
+        let vertex = Vertex {
+            pos: Vec3::new(12.345, -0.5, 100.1),
+            tex_coord: Vec2::new(0.3, 1.7),
+            normal: Vec3::new(0.0, 0.6, -0.8),
+            tangent: Vec4::new(1.0, 0.0, 0.0, -1.0),
+            texture_index: 2,
+        };
+        let quantized = VertexEncoding::Packed.quantize(&vertex);
+        assert!((quantized.pos - vertex.pos).norm() < 0.05);
+        assert!((quantized.normal - vertex.normal).norm() < 0.01);
+        assert_eq!(quantized.tangent, vertex.tangent);
+        assert_eq!(quantized.texture_index, 2);
+        // Quantized vertices pack to the same bits and survive a second pass unchanged.
+        assert_eq!(PackedVertex::pack(&quantized), PackedVertex::pack(&vertex));
+        let twice = VertexEncoding::Packed.quantize(&quantized);
+        assert_eq!((twice.pos, twice.normal), (quantized.pos, quantized.normal));
+    }
+}
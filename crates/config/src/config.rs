@@ -1,3 +1,4 @@
+use crate::paths;
 use serde::{Deserialize, Serialize};
 use std::fmt;
 use std::path::{Path, PathBuf};
@@ -10,12 +11,90 @@ pub struct ConfigFile {
     pub key_bindings: KeyBindings,
 }
 
-#[derive(Serialize, Deserialize, Debug, Default)]
+#[derive(Serialize, Deserialize, Debug)]
 pub struct GraphicsSettings {
     #[serde(default)]
     pub window_mode: WindowMode,
     #[serde(default)]
     pub resolution_settings: WindowResolution,
+    /// Monitor and video mode the fullscreen window modes use.
+    #[serde(default)]
+    pub display: DisplaySettings,
+    #[serde(default)]
+    pub shadow_settings: ShadowSettings,
+    #[serde(default)]
+    pub vsync: bool,
+    /// Trade GPU/CPU overlap for input latency.
+    #[serde(default)]
+    pub latency_mode: LatencyMode,
+    /// Frames the CPU may record before the GPU finishes the oldest, from 1 to 3. More
+    /// overlap hides stalls on either side, at a frame of input latency each.
+    #[serde(default = "default_max_frames_in_flight")]
+    pub max_frames_in_flight: u32,
+    /// CPU-side frame rate cap while focused. 0 runs as fast as presentation allows.
+    #[serde(default)]
+    pub target_fps: u32,
+    /// Frame rate cap while the window is unfocused. 0 disables the cap.
+    #[serde(default = "default_unfocused_fps_cap")]
+    pub unfocused_fps_cap: u32,
+    /// Run compute work on a dedicated queue when the GPU has one.
+    #[serde(default = "default_async_compute")]
+    pub async_compute: bool,
+    /// Leave NV/AMD crash breadcrumbs around render passes for device-lost reports.
+    #[serde(default)]
+    pub gpu_diagnostics: bool,
+    /// Write entity IDs in the G-buffer pass so pixels can be picked on the GPU.
+    #[serde(default)]
+    pub gpu_picking: bool,
+    /// Simulate particles in compute shaders, colliding with the depth buffer. Falls back
+    /// to the CPU when the graphics queue has no compute support.
+    #[serde(default = "default_gpu_particles")]
+    pub gpu_particles: bool,
+    /// VRAM for material texture mips, in MiB. Higher mips stream in as textures get
+    /// close to the camera. 0 disables streaming and uploads every mip up front.
+    #[serde(default = "default_texture_budget_mb")]
+    pub texture_budget_mb: u32,
+}
+
+fn default_unfocused_fps_cap() -> u32 {
+    30
+}
+
+fn default_max_frames_in_flight() -> u32 {
+    2
+}
+
+fn default_async_compute() -> bool {
+    true
+}
+
+fn default_gpu_particles() -> bool {
+    true
+}
+
+fn default_texture_budget_mb() -> u32 {
+    1024
+}
+
+impl Default for GraphicsSettings {
+    fn default() -> Self {
+        Self {
+            window_mode: WindowMode::default(),
+            resolution_settings: WindowResolution::default(),
+            display: DisplaySettings::default(),
+            shadow_settings: ShadowSettings::default(),
+            vsync: false,
+            latency_mode: LatencyMode::default(),
+            max_frames_in_flight: default_max_frames_in_flight(),
+            target_fps: 0,
+            unfocused_fps_cap: default_unfocused_fps_cap(),
+            async_compute: default_async_compute(),
+            gpu_diagnostics: false,
+            gpu_picking: false,
+            gpu_particles: default_gpu_particles(),
+            texture_budget_mb: default_texture_budget_mb(),
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -33,6 +112,116 @@ impl Default for WindowResolution {
     }
 }
 
+/// Maximum number of directional shadow cascades the renderer supports.
+pub const MAX_SHADOW_CASCADES: u32 = 4;
+
+/// Quality knobs for directional light cascaded shadow maps.
+///
+/// Changing these at runtime causes the renderer to recreate the cascade
+/// images on the next frame, so low-end GPUs can trade quality for speed.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct ShadowSettings {
+    /// `Off` skips the shadow maps altogether; entities with a `BlobShadowComponent` get
+    /// a blob shadow instead.
+    pub quality: ShadowQuality,
+    /// Number of active cascades, clamped to `1..=MAX_SHADOW_CASCADES`.
+    pub cascade_count: u32,
+    /// Resolution of the nearest cascades. The far half of the cascades use half of this.
+    pub resolution: u32,
+    /// Blend between uniform (0.0) and logarithmic (1.0) cascade splits.
+    pub split_lambda: f32,
+    /// View distance covered by the cascades, in world units.
+    pub shadow_distance: f32,
+    /// Width of the square PCF kernel in texels. Even values are rounded up to the next odd one.
+    pub pcf_kernel_size: u32,
+    /// Fraction of each cascade's depth range that is blended into the next cascade.
+    /// `0.0` disables blending.
+    pub cascade_blend: f32,
+    /// Constant depth bias applied by the rasterizer when rendering shadow maps.
+    pub depth_bias_constant: f32,
+    /// Slope-scaled depth bias applied by the rasterizer when rendering shadow maps.
+    pub depth_bias_slope: f32,
+    /// Depth bias subtracted from the receiver before comparison, in light-space depth units.
+    pub receiver_bias: f32,
+    /// Normal-offset bias in shadow map texels. Pushes the lookup position off the surface.
+    pub normal_offset_bias: f32,
+    /// Enables percentage-closer soft shadows (contact-hardening penumbrae).
+    pub pcss: bool,
+    /// Apparent light size for PCSS in shadow map UV units. Larger values soften penumbrae.
+    pub pcss_light_size: f32,
+}
+
+impl Default for ShadowSettings {
+    fn default() -> Self {
+        Self {
+            quality: ShadowQuality::default(),
+            cascade_count: MAX_SHADOW_CASCADES,
+            resolution: 2048,
+            split_lambda: 0.9,
+            shadow_distance: 100.0,
+            pcf_kernel_size: 3,
+            cascade_blend: 0.1,
+            depth_bias_constant: 0.0,
+            depth_bias_slope: 1.5,
+            receiver_bias: 0.002,
+            normal_offset_bias: 2.0,
+            pcss: false,
+            pcss_light_size: 0.01,
+        }
+    }
+}
+
+impl ShadowSettings {
+    /// Active cascade count, clamped to the supported range.
+    pub fn active_cascades(&self) -> u32 {
+        self.cascade_count.clamp(1, MAX_SHADOW_CASCADES)
+    }
+
+    /// Square resolution of cascade `index`, for `index < active_cascades()`.
+    pub fn cascade_resolution(&self, index: u32) -> u32 {
+        let count = self.active_cascades();
+        let resolution = self.resolution.max(1);
+        if index < count.div_ceil(2) { resolution } else { (resolution / 2).max(1) }
+    }
+
+    /// Half-width of the PCF kernel in texels (0 means a single tap).
+    pub fn pcf_radius(&self) -> u32 {
+        self.pcf_kernel_size.max(1) / 2
+    }
+}
+
+/// Whether directional light shadows come from cascaded shadow maps.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ShadowQuality {
+    /// No shadow maps; for low-end GPUs.
+    Off,
+    #[default]
+    Cascaded,
+}
+
+/// Per-light overrides of the global `ShadowSettings`. `None` keeps the global value.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct LightShadowSettings {
+    pub cascade_count: Option<u32>,
+    pub split_lambda: Option<f32>,
+    pub shadow_distance: Option<f32>,
+}
+
+impl LightShadowSettings {
+    /// The global settings with this light's overrides applied.
+    pub fn resolve(&self, global: &ShadowSettings) -> ShadowSettings {
+        ShadowSettings {
+            cascade_count: self.cascade_count.unwrap_or(global.cascade_count),
+            split_lambda: self.split_lambda.unwrap_or(global.split_lambda),
+            shadow_distance: self.shadow_distance.unwrap_or(global.shadow_distance),
+            ..global.clone()
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
 pub enum WindowMode {
@@ -42,6 +231,42 @@ pub enum WindowMode {
     Windowed,
 }
 
+/// How far the CPU may run ahead of the display.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LatencyMode {
+    /// The CPU simulates the next frame while the GPU renders the current one. Best
+    /// throughput, at up to a frame of extra input latency.
+    #[default]
+    Overlapped,
+    /// After each frame, wait until it is presented (with `VK_KHR_present_wait`) or at
+    /// least finished on the GPU, so the next frame samples input as late as possible.
+    /// Good for first-person camera feel; costs throughput on GPU-bound scenes. Only one
+    /// frame is ever in flight, whatever `max_frames_in_flight` allows.
+    Low,
+}
+
+/// Which monitor the window goes fullscreen on, and at which video mode.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(default)]
+pub struct DisplaySettings {
+    /// Monitor name as the OS reports it. `None`, or a monitor that is not connected,
+    /// selects the primary monitor.
+    pub monitor: Option<String>,
+    /// Video mode for exclusive fullscreen. `None`, or a mode the monitor does not
+    /// offer, selects the closest one to the window resolution.
+    pub video_mode: Option<VideoMode>,
+}
+
+/// Resolution and refresh rate of an exclusive fullscreen display.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VideoMode {
+    pub width: u32,
+    pub height: u32,
+    /// In millihertz, so 59.94 Hz is 59940.
+    pub refresh_rate_millihertz: u32,
+}
+
 #[derive(Serialize, Deserialize, Debug, Default)]
 pub struct KeyBindings(pub std::collections::HashMap<String, String>);
 
@@ -79,13 +304,10 @@ impl ConfigFile {
         Ok(toml::from_str(&content)?)
     }
 
-    /// Returns the OS-standard config directory for the given application name.
-    ///
-    /// - Windows: `%APPDATA%\<name>\`
-    /// - Linux:   `$XDG_CONFIG_HOME/<name>/` (falls back to `~/.config/<name>/`)
-    /// - macOS:   `~/Library/Application Support/<name>/`
+    /// Returns the OS-standard config directory for the given application name. See
+    /// [`paths`](crate::paths) for the location on each platform.
     pub fn config_dir(app_name: &str) -> Option<PathBuf> {
-        directories::ProjectDirs::from("", "", app_name).map(|d| d.config_dir().to_path_buf())
+        paths::config_dir(app_name)
     }
 
     /// Loads config from the OS config directory for `app_name`. Returns
@@ -107,10 +329,7 @@ impl ConfigFile {
         })?;
         std::fs::create_dir_all(&dir)?;
         let content = toml::to_string_pretty(self).map_err(|e| {
-            ConfigError::Io(std::io::Error::new(
-                std::io::ErrorKind::Other,
-                e.to_string(),
-            ))
+            ConfigError::Io(std::io::Error::other(e.to_string()))
         })?;
         std::fs::write(dir.join("config.toml"), content)?;
         Ok(())
@@ -1,4 +1,5 @@
 pub mod config;
+pub mod paths;
 
 pub fn add(left: u64, right: u64) -> u64 {
     left + right
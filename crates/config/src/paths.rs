@@ -0,0 +1,79 @@
+//! Platform directories, resolved without depending on the working directory.
+//!
+//! Bundled data such as the `.eproj` and its content is looked up in the working
+//! directory first, which is what `cargo run` from a project root expects, then next to
+//! the executable, which is where `publish` puts it. Per-user files live in the OS
+//! locations:
+//!
+//! - Windows: config in `%APPDATA%\<name>\config\`, cache in `%LOCALAPPDATA%\<name>\cache\`
+//! - Linux:   `$XDG_CONFIG_HOME/<name>/` and `$XDG_CACHE_HOME/<name>/`
+//!   (falling back to `~/.config/<name>/` and `~/.cache/<name>/`)
+//! - macOS:   `~/Library/Application Support/<name>/` and `~/Library/Caches/<name>/`
+
+use directories::ProjectDirs;
+use std::path::{Path, PathBuf};
+
+/// Directory containing the running executable, with symlinks resolved.
+pub fn executable_dir() -> Option<PathBuf> {
+    let exe = std::env::current_exe().ok()?;
+    let exe = exe.canonicalize().unwrap_or(exe);
+    exe.parent().map(Path::to_path_buf)
+}
+
+/// Directories searched for bundled data, in order: the working directory, then the
+/// executable's directory.
+pub fn resource_dirs() -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    if let Ok(cwd) = std::env::current_dir() {
+        dirs.push(cwd);
+    }
+    if let Some(exe_dir) = executable_dir()
+        && !dirs.contains(&exe_dir)
+    {
+        dirs.push(exe_dir);
+    }
+    dirs
+}
+
+/// The first existing `dir/path` over [`resource_dirs`]. Absolute paths, and relative
+/// paths found in none of them, are returned unchanged.
+pub fn resolve_resource(path: impl AsRef<Path>) -> PathBuf {
+    let path = path.as_ref();
+    if path.is_absolute() {
+        return path.to_path_buf();
+    }
+    resource_dirs()
+        .into_iter()
+        .map(|dir| dir.join(path))
+        .find(|candidate| candidate.exists())
+        .unwrap_or_else(|| path.to_path_buf())
+}
+
+/// Per-user settings directory for `app_name`.
+pub fn config_dir(app_name: &str) -> Option<PathBuf> {
+    ProjectDirs::from("", "", app_name).map(|dirs| dirs.config_dir().to_path_buf())
+}
+
+/// Per-user directory for files the app can regenerate or discard, e.g. crash reports.
+pub fn cache_dir(app_name: &str) -> Option<PathBuf> {
+    ProjectDirs::from("", "", app_name).map(|dirs| dirs.cache_dir().to_path_buf())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolves_relative_paths_against_the_working_directory_first() {
+        let manifest = resolve_resource("Cargo.toml");
+        assert_eq!(
+            manifest,
+            std::env::current_dir().unwrap().join("Cargo.toml")
+        );
+
+        let missing = Path::new("no/such/file.eproj");
+        assert_eq!(resolve_resource(missing), missing);
+        let absolute = std::env::temp_dir();
+        assert_eq!(resolve_resource(&absolute), absolute);
+    }
+}
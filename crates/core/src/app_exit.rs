@@ -0,0 +1,17 @@
+/// Resource that asks the app to close once the current frame finishes.
+///
+/// From a system: `ctx.res_mut::<AppExit>().request()`.
+#[derive(Debug, Default)]
+pub struct AppExit {
+    requested: bool,
+}
+
+impl AppExit {
+    pub fn request(&mut self) {
+        self.requested = true;
+    }
+
+    pub fn is_requested(&self) -> bool {
+        self.requested
+    }
+}
@@ -1,15 +1,20 @@
+use crate::behavior_tree::BehaviorTree;
+use crate::localization::StringTable;
 use asset_pipeline::EmatFile;
 use assets::AssetStore;
-use common::{Guid, MeshHandle};
+use common::{trace, Guid, ImageHandle, MeshHandle};
 use material::Material;
 use project::{resolve_cooked_path, AssetRegistry};
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 pub struct AssetContext {
     pub(crate) cache_dir: PathBuf,
     pub(crate) content_dir: PathBuf,
     pub(crate) registry: AssetRegistry,
     pub(crate) asset_store: AssetStore,
+    behavior_trees: HashMap<Guid, Arc<BehaviorTree>>,
 }
 
 impl AssetContext {
@@ -19,6 +24,7 @@ impl AssetContext {
             content_dir,
             registry,
             asset_store: AssetStore::new(),
+            behavior_trees: HashMap::new(),
         }
     }
 
@@ -35,6 +41,20 @@ impl AssetContext {
             })
     }
 
+    /// Loads a cooked texture, e.g. a lightmap for a `LightmapComponent`.
+    pub fn load_texture(&mut self, guid: Guid) -> ImageHandle {
+        let cooked = resolve_cooked_path(&self.cache_dir, &guid, "etex");
+        self.asset_store
+            .load_texture(&cooked, guid)
+            .unwrap_or_else(|| {
+                panic!(
+                    "cooked texture missing for '{}' (expected: {})",
+                    guid,
+                    cooked.display()
+                )
+            })
+    }
+
     /// Builds a `Material` from the `.emat` source file for the given GUID.
     /// Looks up the source path in the registry, then loads and builds the material.
     pub fn build_material(&mut self, guid: Guid) -> Material {
@@ -44,12 +64,51 @@ impl AssetContext {
             .unwrap_or_else(|| panic!("no asset record for guid '{}'", guid));
 
         let abs = self.content_dir.join(&record.source_path);
+        let _span = trace::span("asset", format!("Build material {}", abs.display()));
 
         EmatFile::load(&abs)
             .and_then(|f| f.build_material(&self.cache_dir, &self.registry, &mut self.asset_store))
             .unwrap_or_else(|e| panic!("failed to load material '{}': {}", abs.display(), e))
     }
 
+    /// Loads the `.strings` source file for the given GUID.
+    pub fn load_string_table(&self, guid: Guid) -> StringTable {
+        let record = self
+            .registry
+            .get(&guid)
+            .unwrap_or_else(|| panic!("no asset record for guid '{}'", guid));
+
+        let abs = self.content_dir.join(&record.source_path);
+
+        StringTable::load(&abs)
+            .unwrap_or_else(|e| panic!("failed to load string table '{}': {}", abs.display(), e))
+    }
+
+    /// Loads the `.btree` source file for the given GUID. Trees are cached and shared
+    /// by every entity that runs them.
+    pub fn load_behavior_tree(&mut self, guid: Guid) -> Arc<BehaviorTree> {
+        if let Some(tree) = self.behavior_trees.get(&guid) {
+            return tree.clone();
+        }
+        let record = self
+            .registry
+            .get(&guid)
+            .unwrap_or_else(|| panic!("no asset record for guid '{}'", guid));
+
+        let abs = self.content_dir.join(&record.source_path);
+
+        let tree = BehaviorTree::load(&abs)
+            .unwrap_or_else(|e| panic!("failed to load behavior tree '{}': {}", abs.display(), e));
+        let tree = Arc::new(tree);
+        self.behavior_trees.insert(guid, tree.clone());
+        tree
+    }
+
+    /// The project asset index, including the dependency graph between assets.
+    pub fn registry(&self) -> &AssetRegistry {
+        &self.registry
+    }
+
     pub(crate) fn store(&self) -> &AssetStore {
         &self.asset_store
     }
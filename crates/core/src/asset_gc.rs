@@ -0,0 +1,230 @@
+//! Releases assets nothing has used for a while.
+//!
+//! Streaming loads meshes, materials and textures as cells come into range but never
+//! unloads them, so memory grows for as long as the player keeps moving. Once enabled
+//! through [`EngineConfig::asset_gc`](crate::EngineConfig::asset_gc), the engine records
+//! the time each loaded asset was last referenced and, after every update, unloads a
+//! bounded number of assets idle for longer than the configured time. The renderer frees
+//! their GPU copies through the backend's deferred deletion queue.
+//!
+//! An asset counts as used while a `MeshComponent`, `MaterialComponent` or
+//! `LightmapComponent` refers to it; a texture also while a loaded material binds it or
+//! it is the [`WorldEnvironment`] skybox.
+//! Handles kept anywhere else, e.g. in a resource to spawn from later, are not seen and
+//! go stale once the asset is released. Load the asset by GUID again instead. Only
+//! GUID-loaded assets are collected; materials built at runtime are never released.
+
+use crate::environment::WorldEnvironment;
+use crate::{LightmapComponent, MaterialComponent, MeshComponent};
+use assets::AssetStore;
+use common::{ImageData, ImageHandle, MeshData, MeshHandle};
+use ecs::world::World;
+use material::material_manager::{MaterialHandle, MaterialManager};
+use material::MaterialParameterBindingData;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AssetGcSettings {
+    /// Seconds an asset must go unused before it is released. 0 disables collection.
+    pub idle_seconds: f32,
+    /// Most assets released in one frame, to spread unloading over several frames.
+    pub max_releases_per_frame: usize,
+}
+
+impl Default for AssetGcSettings {
+    fn default() -> Self {
+        Self {
+            idle_seconds: 0.0,
+            max_releases_per_frame: 4,
+        }
+    }
+}
+
+/// An asset tracked by the collector.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AssetId {
+    Mesh(MeshHandle),
+    Texture(ImageHandle),
+    Material(MaterialHandle),
+}
+
+#[derive(Default)]
+pub(crate) struct AssetGc {
+    /// Elapsed time each loaded asset was last referenced at.
+    last_used: HashMap<AssetId, f64>,
+    /// Released since the renderer last collected them.
+    released: Vec<AssetId>,
+}
+
+impl AssetGc {
+    /// Marks what `world` and `environment` reference at `now`, then unloads the assets
+    /// idle longest.
+    pub(crate) fn collect(
+        &mut self,
+        settings: &AssetGcSettings,
+        now: f64,
+        world: &World,
+        environment: &WorldEnvironment,
+        asset_store: &mut AssetStore,
+        materials: &mut MaterialManager,
+    ) {
+        if settings.idle_seconds <= 0.0 {
+            return;
+        }
+
+        // Assets seen for the first time get a full idle period from now.
+        let loaded = asset_store
+            .handles::<MeshData>()
+            .map(AssetId::Mesh)
+            .chain(asset_store.handles::<ImageData>().map(AssetId::Texture))
+            .chain(materials.loaded_handles().map(AssetId::Material));
+        for id in loaded {
+            self.last_used.entry(id).or_insert(now);
+        }
+
+        world.for_each_component::<MeshComponent>(|_, mesh| {
+            self.touch(AssetId::Mesh(mesh.mesh_handle), now);
+        });
+        world.for_each_component::<MaterialComponent>(|_, material| {
+            self.touch(AssetId::Material(material.material_handle), now);
+        });
+        world.for_each_component::<LightmapComponent>(|_, lightmap| {
+            self.touch(AssetId::Texture(lightmap.lightmap), now);
+        });
+        if let Some(skybox) = environment.skybox {
+            self.touch(AssetId::Texture(skybox), now);
+        }
+        // A texture outlives every material binding it, so the material's descriptor
+        // set never points at a freed image.
+        for material in materials.handles() {
+            for binding in materials.get_bindings(material) {
+                match &binding.data {
+                    MaterialParameterBindingData::Texture(texture) => {
+                        self.touch(AssetId::Texture(*texture), now);
+                    }
+                    MaterialParameterBindingData::PackedTexture(packed) => {
+                        let channels = [
+                            packed.channel_r,
+                            packed.channel_g,
+                            packed.channel_b,
+                            packed.channel_a,
+                        ];
+                        for texture in channels.into_iter().flatten() {
+                            self.touch(AssetId::Texture(texture), now);
+                        }
+                    }
+                }
+            }
+        }
+
+        for id in self.idle(settings, now) {
+            self.last_used.remove(&id);
+            let removed = match id {
+                AssetId::Mesh(handle) => asset_store.remove(handle).is_some(),
+                AssetId::Texture(handle) => asset_store.remove(handle).is_some(),
+                AssetId::Material(handle) => materials.remove(handle).is_some(),
+            };
+            if removed {
+                self.released.push(id);
+            }
+        }
+    }
+
+    /// Assets released since the last call. The renderer must free their GPU resources.
+    pub(crate) fn take_released(&mut self) -> Vec<AssetId> {
+        std::mem::take(&mut self.released)
+    }
+
+    fn touch(&mut self, id: AssetId, now: f64) {
+        if let Some(last_used) = self.last_used.get_mut(&id) {
+            *last_used = now;
+        }
+    }
+
+    /// Up to `max_releases_per_frame` assets past the idle time, longest idle first.
+    fn idle(&self, settings: &AssetGcSettings, now: f64) -> Vec<AssetId> {
+        let mut idle = self
+            .last_used
+            .iter()
+            .filter(|(_, &last_used)| now - last_used >= settings.idle_seconds as f64)
+            .map(|(&id, &last_used)| (last_used, id))
+            .collect::<Vec<_>>();
+        idle.sort_by(|a, b| a.0.total_cmp(&b.0));
+        idle.into_iter()
+            .take(settings.max_releases_per_frame)
+            .map(|(_, id)| id)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::{Guid, VertexEncoding};
+
+    fn empty_mesh() -> MeshData {
+        MeshData {
+            vertices: Vec::new(),
+            vertex_encoding: VertexEncoding::Full,
+            indices: Vec::new(),
+            extras: None,
+            skin: None,
+            submeshes: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn releases_idle_assets_oldest_first_within_the_budget() {
+        let settings = AssetGcSettings {
+            idle_seconds: 10.0,
+            max_releases_per_frame: 2,
+        };
+        let mut store = AssetStore::new();
+        let mut materials = MaterialManager::new();
+        let mut world = World::new();
+        let environment = WorldEnvironment::default();
+        let mut gc = AssetGc::default();
+
+        let meshes = (0..4)
+            .map(|_| store.insert_mesh(Guid::generate(), empty_mesh()))
+            .collect::<Vec<_>>();
+        gc.collect(
+            &settings,
+            0.0,
+            &world,
+            &environment,
+            &mut store,
+            &mut materials,
+        );
+        for (i, &mesh) in meshes.iter().enumerate().skip(1) {
+            gc.last_used.insert(AssetId::Mesh(mesh), i as f64);
+        }
+        world.create_entity((MeshComponent::new(meshes[0]),));
+
+        gc.collect(
+            &settings,
+            13.0,
+            &world,
+            &environment,
+            &mut store,
+            &mut materials,
+        );
+        assert_eq!(
+            gc.take_released(),
+            vec![AssetId::Mesh(meshes[1]), AssetId::Mesh(meshes[2])]
+        );
+        assert!(store.get(meshes[1]).is_none());
+        assert!(store.get(meshes[0]).is_some());
+        assert!(store.get(meshes[3]).is_some());
+
+        gc.collect(
+            &settings,
+            13.5,
+            &world,
+            &environment,
+            &mut store,
+            &mut materials,
+        );
+        assert_eq!(gc.take_released(), vec![AssetId::Mesh(meshes[3])]);
+    }
+}
@@ -0,0 +1,497 @@
+//! Behavior trees for NPC decision making.
+//!
+//! Trees are `.btree` assets: JSON describing composites, decorators and leaves. Leaves
+//! either test a blackboard value or run a task, a closure registered by name in the
+//! [`BehaviorTasks`] resource. Give an entity a [`BehaviorTreeComponent`] and a
+//! [`BlackboardComponent`]; the engine ticks the tree once per frame. Tasks talk to the
+//! rest of the game through the blackboard, e.g. by writing a move target that a
+//! movement system reads.
+//!
+//! ```json
+//! { "type": "selector", "children": [
+//!     { "type": "sequence", "children": [
+//!         { "type": "condition", "key": "sees_player" },
+//!         { "type": "task", "name": "chase" } ] },
+//!     { "type": "task", "name": "patrol" } ] }
+//! ```
+
+use crate::system::Context;
+use ecs::command_buffer::Commands;
+use ecs::component::Component;
+use ecs::query::Query;
+use nalgebra_glm::Vec3;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+use std::sync::Arc;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BehaviorStatus {
+    Success,
+    Failure,
+    /// Not finished; the node is ticked again next frame.
+    Running,
+}
+
+#[derive(Debug)]
+pub enum BehaviorTreeError {
+    Io(std::io::Error),
+    Parse(serde_json::Error),
+}
+
+impl fmt::Display for BehaviorTreeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BehaviorTreeError::Io(e) => write!(f, "I/O error: {e}"),
+            BehaviorTreeError::Parse(e) => write!(f, "invalid behavior tree: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for BehaviorTreeError {}
+
+impl From<std::io::Error> for BehaviorTreeError {
+    fn from(e: std::io::Error) -> Self {
+        BehaviorTreeError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for BehaviorTreeError {
+    fn from(e: serde_json::Error) -> Self {
+        BehaviorTreeError::Parse(e)
+    }
+}
+
+/// A tree node as written in a `.btree` file.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BehaviorNode {
+    /// Runs children in order until one fails.
+    Sequence { children: Vec<BehaviorNode> },
+    /// Runs children in order until one succeeds.
+    Selector { children: Vec<BehaviorNode> },
+    /// Runs the child whose blackboard score is highest, re-chosen every tick.
+    Utility { children: Vec<UtilityChild> },
+    /// Swaps success and failure.
+    Inverter { child: Box<BehaviorNode> },
+    /// Reports failure as success.
+    Succeeder { child: Box<BehaviorNode> },
+    /// Reruns the child `count` times, or forever when `count` is absent. Fails as soon
+    /// as the child fails.
+    Repeat {
+        child: Box<BehaviorNode>,
+        count: Option<u32>,
+    },
+    /// Runs for `seconds`, then succeeds.
+    Wait { seconds: f32 },
+    /// Succeeds if the blackboard holds `true` under `key`.
+    Condition { key: String },
+    /// Runs the task registered under `name` in [`BehaviorTasks`].
+    Task { name: String },
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct UtilityChild {
+    /// Blackboard key holding this option's score. Missing scores count as 0.
+    pub score: String,
+    pub node: BehaviorNode,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+enum NodeKind {
+    Sequence,
+    Selector,
+    Utility { scores: Vec<String> },
+    Inverter,
+    Succeeder,
+    Repeat { count: Option<u32> },
+    Wait { seconds: f32 },
+    Condition { key: String },
+    Task { name: String },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct FlatNode {
+    kind: NodeKind,
+    children: Vec<usize>,
+    /// One past the last node of this node's subtree.
+    end: usize,
+}
+
+/// A loaded tree, flattened in pre-order so every subtree is a contiguous range.
+/// Shared between all entities running it; per-entity progress lives in
+/// [`BehaviorTreeComponent`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BehaviorTree {
+    nodes: Vec<FlatNode>,
+}
+
+impl BehaviorTree {
+    pub fn new(root: &BehaviorNode) -> Self {
+        let mut nodes = Vec::new();
+        flatten(root, &mut nodes);
+        Self { nodes }
+    }
+
+    pub fn load(path: &Path) -> Result<Self, BehaviorTreeError> {
+        let text = std::fs::read_to_string(path)?;
+        let root: BehaviorNode = serde_json::from_str(&text)?;
+        Ok(Self::new(&root))
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+}
+
+fn flatten(node: &BehaviorNode, nodes: &mut Vec<FlatNode>) -> usize {
+    let index = nodes.len();
+    let (kind, children): (NodeKind, Vec<&BehaviorNode>) = match node {
+        BehaviorNode::Sequence { children } => (NodeKind::Sequence, children.iter().collect()),
+        BehaviorNode::Selector { children } => (NodeKind::Selector, children.iter().collect()),
+        BehaviorNode::Utility { children } => (
+            NodeKind::Utility {
+                scores: children.iter().map(|c| c.score.clone()).collect(),
+            },
+            children.iter().map(|c| &c.node).collect(),
+        ),
+        BehaviorNode::Inverter { child } => (NodeKind::Inverter, vec![child.as_ref()]),
+        BehaviorNode::Succeeder { child } => (NodeKind::Succeeder, vec![child.as_ref()]),
+        BehaviorNode::Repeat { child, count } => {
+            (NodeKind::Repeat { count: *count }, vec![child.as_ref()])
+        }
+        BehaviorNode::Wait { seconds } => (NodeKind::Wait { seconds: *seconds }, vec![]),
+        BehaviorNode::Condition { key } => (NodeKind::Condition { key: key.clone() }, vec![]),
+        BehaviorNode::Task { name } => (NodeKind::Task { name: name.clone() }, vec![]),
+    };
+    nodes.push(FlatNode {
+        kind,
+        children: Vec::new(),
+        end: index + 1,
+    });
+    let children = children
+        .into_iter()
+        .map(|child| flatten(child, nodes))
+        .collect();
+    nodes[index].children = children;
+    nodes[index].end = nodes.len();
+    index
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum BlackboardValue {
+    Bool(bool),
+    Float(f32),
+    Int(i64),
+    Vec3(Vec3),
+}
+
+impl From<bool> for BlackboardValue {
+    fn from(v: bool) -> Self {
+        BlackboardValue::Bool(v)
+    }
+}
+
+impl From<f32> for BlackboardValue {
+    fn from(v: f32) -> Self {
+        BlackboardValue::Float(v)
+    }
+}
+
+impl From<i64> for BlackboardValue {
+    fn from(v: i64) -> Self {
+        BlackboardValue::Int(v)
+    }
+}
+
+impl From<Vec3> for BlackboardValue {
+    fn from(v: Vec3) -> Self {
+        BlackboardValue::Vec3(v)
+    }
+}
+
+/// Per-entity key/value memory shared by AI tasks and gameplay systems.
+#[derive(Debug, Clone, Default, Component, Serialize, Deserialize)]
+pub struct BlackboardComponent {
+    values: HashMap<String, BlackboardValue>,
+}
+
+impl BlackboardComponent {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set(&mut self, key: &str, value: impl Into<BlackboardValue>) {
+        self.values.insert(key.to_owned(), value.into());
+    }
+
+    pub fn get(&self, key: &str) -> Option<BlackboardValue> {
+        self.values.get(key).copied()
+    }
+
+    pub fn remove(&mut self, key: &str) -> Option<BlackboardValue> {
+        self.values.remove(key)
+    }
+
+    /// `false` if missing or not a bool.
+    pub fn get_bool(&self, key: &str) -> bool {
+        matches!(self.get(key), Some(BlackboardValue::Bool(true)))
+    }
+
+    /// Floats and ints as `f32`; `None` for anything else.
+    pub fn get_float(&self, key: &str) -> Option<f32> {
+        match self.get(key)? {
+            BlackboardValue::Float(v) => Some(v),
+            BlackboardValue::Int(v) => Some(v as f32),
+            _ => None,
+        }
+    }
+
+    pub fn get_vec3(&self, key: &str) -> Option<Vec3> {
+        match self.get(key)? {
+            BlackboardValue::Vec3(v) => Some(v),
+            _ => None,
+        }
+    }
+}
+
+type TaskFn = Box<dyn Fn(&mut BlackboardComponent, f32) -> BehaviorStatus>;
+
+/// Resource mapping task names used in `.btree` files to the closures that run them.
+/// Tasks receive the entity's blackboard and the frame delta. Leaves naming an
+/// unregistered task fail.
+#[derive(Default)]
+pub struct BehaviorTasks {
+    tasks: HashMap<String, TaskFn>,
+}
+
+impl BehaviorTasks {
+    pub fn register(
+        &mut self,
+        name: &str,
+        task: impl Fn(&mut BlackboardComponent, f32) -> BehaviorStatus + 'static,
+    ) {
+        self.tasks.insert(name.to_owned(), Box::new(task));
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.tasks.contains_key(name)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+struct NodeMemory {
+    /// Child to resume for sequences and selectors, the chosen child for utility nodes.
+    child: usize,
+    elapsed: f32,
+    iterations: u32,
+}
+
+/// Runs a [`BehaviorTree`] on this entity, against its [`BlackboardComponent`].
+///
+/// Saved with a copy of its tree, so a loaded save resumes the tree it was made with
+/// even if the `.btree` asset has changed since.
+#[derive(Debug, Clone, Component, Serialize, Deserialize)]
+pub struct BehaviorTreeComponent {
+    tree: Arc<BehaviorTree>,
+    memory: Vec<NodeMemory>,
+    status: Option<BehaviorStatus>,
+}
+
+impl BehaviorTreeComponent {
+    pub fn new(tree: Arc<BehaviorTree>) -> Self {
+        let memory = vec![NodeMemory::default(); tree.len()];
+        Self {
+            tree,
+            memory,
+            status: None,
+        }
+    }
+
+    /// Status of the root after the last tick; `None` before the first.
+    pub fn status(&self) -> Option<BehaviorStatus> {
+        self.status
+    }
+
+    /// Drops all progress so the next tick starts from the root.
+    pub fn reset(&mut self) {
+        self.memory.fill(NodeMemory::default());
+        self.status = None;
+    }
+
+    pub fn tick(
+        &mut self,
+        blackboard: &mut BlackboardComponent,
+        tasks: &BehaviorTasks,
+        dt: f32,
+    ) -> BehaviorStatus {
+        let status = if self.tree.is_empty() {
+            BehaviorStatus::Failure
+        } else {
+            let mut ticker = Ticker {
+                nodes: &self.tree.nodes,
+                memory: &mut self.memory,
+                blackboard,
+                tasks,
+                dt,
+            };
+            ticker.tick(0)
+        };
+        self.status = Some(status);
+        status
+    }
+}
+
+struct Ticker<'a> {
+    nodes: &'a [FlatNode],
+    memory: &'a mut [NodeMemory],
+    blackboard: &'a mut BlackboardComponent,
+    tasks: &'a BehaviorTasks,
+    dt: f32,
+}
+
+impl Ticker<'_> {
+    fn tick(&mut self, index: usize) -> BehaviorStatus {
+        let nodes = self.nodes;
+        let node = &nodes[index];
+        let status = match &node.kind {
+            NodeKind::Sequence => self.tick_composite(index, BehaviorStatus::Success),
+            NodeKind::Selector => self.tick_composite(index, BehaviorStatus::Failure),
+            NodeKind::Utility { scores } => {
+                let score = |key: &String| self.blackboard.get_float(key).unwrap_or(0.0);
+                let chosen = (0..scores.len())
+                    .max_by(|&a, &b| score(&scores[a]).total_cmp(&score(&scores[b])));
+                match chosen {
+                    Some(chosen) => {
+                        if self.memory[index].child != chosen {
+                            // Switching options abandons the previous one mid-run.
+                            self.reset(index + 1, node.end);
+                            self.memory[index].child = chosen;
+                        }
+                        self.tick(node.children[chosen])
+                    }
+                    None => BehaviorStatus::Failure,
+                }
+            }
+            NodeKind::Inverter => match self.tick(node.children[0]) {
+                BehaviorStatus::Success => BehaviorStatus::Failure,
+                BehaviorStatus::Failure => BehaviorStatus::Success,
+                BehaviorStatus::Running => BehaviorStatus::Running,
+            },
+            NodeKind::Succeeder => match self.tick(node.children[0]) {
+                BehaviorStatus::Running => BehaviorStatus::Running,
+                _ => BehaviorStatus::Success,
+            },
+            NodeKind::Repeat { count } => match self.tick(node.children[0]) {
+                BehaviorStatus::Success => {
+                    self.memory[index].iterations += 1;
+                    match count {
+                        Some(count) if self.memory[index].iterations >= *count => {
+                            BehaviorStatus::Success
+                        }
+                        _ => BehaviorStatus::Running,
+                    }
+                }
+                status => status,
+            },
+            NodeKind::Wait { seconds } => {
+                self.memory[index].elapsed += self.dt;
+                if self.memory[index].elapsed >= *seconds {
+                    BehaviorStatus::Success
+                } else {
+                    BehaviorStatus::Running
+                }
+            }
+            NodeKind::Condition { key } => {
+                if self.blackboard.get_bool(key) {
+                    BehaviorStatus::Success
+                } else {
+                    BehaviorStatus::Failure
+                }
+            }
+            NodeKind::Task { name } => match self.tasks.tasks.get(name) {
+                Some(task) => task(self.blackboard, self.dt),
+                None => BehaviorStatus::Failure,
+            },
+        };
+        if status != BehaviorStatus::Running {
+            self.reset(index, node.end);
+        }
+        status
+    }
+
+    /// Sequence and selector: run children from the remembered one until a child
+    /// returns something other than `pass`.
+    fn tick_composite(&mut self, index: usize, pass: BehaviorStatus) -> BehaviorStatus {
+        let nodes = self.nodes;
+        let children = &nodes[index].children;
+        while self.memory[index].child < children.len() {
+            let status = self.tick(children[self.memory[index].child]);
+            if status != pass {
+                return status;
+            }
+            self.memory[index].child += 1;
+        }
+        pass
+    }
+
+    fn reset(&mut self, start: usize, end: usize) {
+        self.memory[start..end].fill(NodeMemory::default());
+    }
+}
+
+/// Ticks every entity's behavior tree once per frame.
+pub fn behavior_tree_system(
+    mut query: Query<(&mut BehaviorTreeComponent, &mut BlackboardComponent)>,
+    context: &mut Context,
+    _commands: &mut Commands,
+) {
+    let Ok(tasks) = context.try_res::<BehaviorTasks>() else {
+        return;
+    };
+    for (tree, blackboard) in query.iter() {
+        tree.tick(blackboard, &tasks, context.dt);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sequence_resumes_running_child_and_selector_falls_back() {
+        let root: BehaviorNode = serde_json::from_str(
+            r#"{ "type": "selector", "children": [
+                { "type": "sequence", "children": [
+                    { "type": "condition", "key": "alert" },
+                    { "type": "wait", "seconds": 1.0 },
+                    { "type": "task", "name": "attack" } ] },
+                { "type": "task", "name": "idle" } ] }"#,
+        )
+        .unwrap();
+        let mut tasks = BehaviorTasks::default();
+        tasks.register("attack", |bb, _| {
+            bb.set("attacked", true);
+            BehaviorStatus::Success
+        });
+        tasks.register("idle", |_, _| BehaviorStatus::Running);
+
+        let mut tree = BehaviorTreeComponent::new(Arc::new(BehaviorTree::new(&root)));
+        let mut bb = BlackboardComponent::new();
+
+        assert_eq!(tree.tick(&mut bb, &tasks, 0.5), BehaviorStatus::Running);
+        bb.set("alert", true);
+        // The selector remembers it is running `idle` and keeps ticking it.
+        assert_eq!(tree.tick(&mut bb, &tasks, 0.5), BehaviorStatus::Running);
+
+        tree.reset();
+        assert_eq!(tree.tick(&mut bb, &tasks, 0.6), BehaviorStatus::Running);
+        assert_eq!(tree.tick(&mut bb, &tasks, 0.6), BehaviorStatus::Success);
+        assert!(bb.get_bool("attacked"));
+    }
+}
@@ -1,11 +1,15 @@
+use crate::types::frustum::Frustum;
 use crate::types::transform::Transform;
-use common::MeshHandle;
+use common::{Color, ImageHandle, LightProbe, MeshHandle};
+use config::config::LightShadowSettings;
 use ecs::component::Component;
 use material::material_manager::MaterialHandle;
-use nalgebra_glm::Vec3;
+use nalgebra_glm::{Mat4, Vec2, Vec3, Vec4};
+use serde::{Deserialize, Serialize};
+use spatial::{ColliderId, Ray, SpatialWorld};
 use std::ops::{Deref, DerefMut};
 
-#[derive(Clone, Debug, Component, Default)]
+#[derive(Clone, Debug, Component, Default, Serialize, Deserialize)]
 pub struct TransformComponent(pub Transform);
 
 impl Deref for TransformComponent {
@@ -21,6 +25,37 @@ impl DerefMut for TransformComponent {
     }
 }
 
+/// Cached world matrix of an entity's `TransformComponent`. Render extraction only
+/// recomputes and re-uploads the matrix when the transform changed since the last frame.
+/// Optional on entities with a `MeshComponent`: for meshes spawned without one, render
+/// extraction keeps the same cache itself.
+#[derive(Clone, Debug, Component, Default)]
+pub struct GlobalTransformComponent {
+    model: Mat4,
+    /// Transform `model` was computed from; `None` until the first sync.
+    source: Option<Transform>,
+    /// Slot of this entity's matrix in the renderer's transform storage. Assigned and
+    /// owned by render extraction.
+    pub gpu_slot: Option<u32>,
+}
+
+impl GlobalTransformComponent {
+    pub fn model(&self) -> &Mat4 {
+        &self.model
+    }
+
+    /// Recomputes the cached matrix if `transform` differs from the one it was built from.
+    /// Returns true when the matrix changed.
+    pub fn sync(&mut self, transform: &Transform) -> bool {
+        if self.source.as_ref() == Some(transform) {
+            return false;
+        }
+        self.model = transform.get_model_matrix();
+        self.source = Some(*transform);
+        true
+    }
+}
+
 #[derive(Clone, Debug, Component)]
 pub struct MeshComponent {
     pub mesh_handle: MeshHandle,
@@ -43,7 +78,364 @@ impl MaterialComponent {
     }
 }
 
-#[derive(Clone, Debug, Component)]
+/// Render layer membership, as a bitmask of up to 32 layers.
+///
+/// On a mesh, the layers it belongs to; on a camera, the layers it renders. A mesh is
+/// drawn when its layers intersect the active camera's. Entities without the component
+/// are on [`RenderLayers::DEFAULT`] alone, so e.g. a first-person weapon can sit on a
+/// layer only the main camera sees.
+#[derive(Clone, Copy, Debug, Component, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct RenderLayers(pub u32);
+
+impl RenderLayers {
+    /// Layer 0 only.
+    pub const DEFAULT: Self = Self(1);
+    pub const ALL: Self = Self(u32::MAX);
+    pub const NONE: Self = Self(0);
+
+    /// Only `layer`, which must be below 32.
+    pub const fn layer(layer: u32) -> Self {
+        assert!(layer < 32, "render layers are numbered 0-31");
+        Self(1 << layer)
+    }
+
+    pub const fn with(self, layer: u32) -> Self {
+        Self(self.0 | Self::layer(layer).0)
+    }
+
+    pub const fn without(self, layer: u32) -> Self {
+        Self(self.0 & !Self::layer(layer).0)
+    }
+
+    pub const fn contains(self, layer: u32) -> bool {
+        self.0 & Self::layer(layer).0 != 0
+    }
+
+    /// Whether the two masks share at least one layer.
+    pub const fn intersects(self, other: Self) -> bool {
+        self.0 & other.0 != 0
+    }
+}
+
+impl Default for RenderLayers {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// Hides an entity's mesh or takes it out of shadowing without despawning it. Entities
+/// without the component are visible and both cast and receive shadows.
+#[derive(Clone, Copy, Debug, Component, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VisibilityComponent {
+    pub visible: bool,
+    pub cast_shadows: bool,
+    pub receive_shadows: bool,
+}
+
+impl VisibilityComponent {
+    pub const HIDDEN: Self = Self {
+        visible: false,
+        cast_shadows: true,
+        receive_shadows: true,
+    };
+}
+
+impl Default for VisibilityComponent {
+    fn default() -> Self {
+        Self {
+            visible: true,
+            cast_shadows: true,
+            receive_shadows: true,
+        }
+    }
+}
+
+/// Sways a foliage mesh in the [`Wind`](crate::wind::Wind) with the vegetation vertex
+/// shader. Per-vertex weights come from the mesh's vertex colors: red for branch sway
+/// (0 at the trunk, 1 at the tips), green for leaf flutter and blue for the flutter's
+/// phase. Meshes without vertex colors, skinned meshes and custom vertex shaders stay
+/// still.
+#[derive(Clone, Copy, Debug, Component, PartialEq, Serialize, Deserialize)]
+pub struct VegetationComponent {
+    /// World units a branch tip bends per unit of wind strength.
+    pub branch_sway: f32,
+    /// World units a leaf moves along its normal per unit of wind strength.
+    pub leaf_flutter: f32,
+}
+
+impl Default for VegetationComponent {
+    fn default() -> Self {
+        Self {
+            branch_sway: 0.3,
+            leaf_flutter: 0.05,
+        }
+    }
+}
+
+/// Darkens a soft ellipse on whatever lies below the entity while shadow maps are off
+/// (`ShadowQuality::Off`), so characters stay grounded on low-end settings. Ignored with
+/// cascaded shadows, and on entities whose [`VisibilityComponent`] hides them or stops
+/// them casting shadows.
+#[derive(Clone, Copy, Debug, Component, PartialEq, Serialize, Deserialize)]
+pub struct BlobShadowComponent {
+    /// World-space width and length of the ellipse. The length runs along the entity's
+    /// forward axis, flattened onto the ground.
+    pub size: Vec2,
+    /// Darkness at the center, from 0 to 1.
+    pub opacity: f32,
+    /// How far below the entity's location the blob reaches. It fades out with the
+    /// distance, so jumping characters leave a lighter shadow.
+    pub max_distance: f32,
+}
+
+impl Default for BlobShadowComponent {
+    fn default() -> Self {
+        Self {
+            size: Vec2::new(1.0, 1.0),
+            opacity: 0.6,
+            max_distance: 3.0,
+        }
+    }
+}
+
+/// Reflects a cube map captured at the entity's location on the surfaces inside its box,
+/// instead of the skybox, for plausible local reflections in interiors. Reflections are
+/// box projected onto the box's walls, so it should match the room it sits in.
+///
+/// The renderer captures the probe once, the first time it sees it, over six frames that
+/// show the probe's view under the UI; capture behind a loading screen. Call
+/// [`recapture`](Self::recapture) after the surroundings change.
+#[derive(Clone, Debug, Component, PartialEq, Serialize, Deserialize)]
+pub struct ReflectionProbeComponent {
+    /// World-space size of the axis-aligned box the probe affects, centered on the
+    /// entity's location. The entity's rotation and scale are ignored.
+    pub size: Vec3,
+    /// Distance inside the box over which the probe fades out towards neighboring probes
+    /// and the skybox.
+    pub blend_distance: f32,
+    /// Multiplies the captured reflections.
+    pub intensity: f32,
+    #[serde(skip)]
+    capture_generation: u32,
+}
+
+impl ReflectionProbeComponent {
+    /// Captures the probe again on the next frames.
+    pub fn recapture(&mut self) {
+        self.capture_generation = self.capture_generation.wrapping_add(1);
+    }
+
+    /// Changes with every [`recapture`](Self::recapture) request.
+    pub fn capture_generation(&self) -> u32 {
+        self.capture_generation
+    }
+}
+
+impl Default for ReflectionProbeComponent {
+    fn default() -> Self {
+        Self {
+            size: Vec3::new(10.0, 4.0, 10.0),
+            blend_distance: 1.0,
+            intensity: 1.0,
+            capture_generation: 0,
+        }
+    }
+}
+
+/// A grid of baked light probes centered on the entity's location, reaching half a
+/// spacing past its outer probes; the entity's rotation and scale are ignored. Meshes
+/// without a lightmap inside the grid pick up the light bounced off the surrounding
+/// scenery, blended from the probes around their origin and shaded along each surface's
+/// normal, and receive less of the environment's ambient light where the probes see
+/// little sky.
+///
+/// The engine bakes a grid the first frame it sees it without probe data, from every
+/// mesh in the world that isn't skinned, so spawn it once the static scene has loaded.
+/// Baked probes are saved with the scene; call [`rebake`](Self::rebake) after the
+/// scenery changes.
+#[derive(Clone, Debug, Component, PartialEq, Serialize, Deserialize)]
+pub struct LightProbeGridComponent {
+    /// Probes along x, y and z, at least one each.
+    pub counts: [u32; 3],
+    /// Distance between neighboring probes along each axis.
+    pub spacing: Vec3,
+    /// Multiplies the bounced light. The sky's occlusion is applied as-is.
+    pub intensity: f32,
+    /// Baked probes, x varying fastest, then y, then z. Empty until baked.
+    pub probes: Vec<LightProbe>,
+}
+
+impl LightProbeGridComponent {
+    pub fn probe_count(&self) -> usize {
+        self.counts.iter().map(|&count| count.max(1) as usize).product()
+    }
+
+    /// Whether `probes` holds one probe per grid point.
+    pub fn is_baked(&self) -> bool {
+        self.probes.len() == self.probe_count()
+    }
+
+    /// Drops the baked probes, so the engine bakes them again.
+    pub fn rebake(&mut self) {
+        self.probes.clear();
+    }
+
+    /// World-space position of every probe, in `probes` order, for a grid centered on
+    /// `center`.
+    pub fn probe_positions(&self, center: &Vec3) -> Vec<Vec3> {
+        let [nx, ny, nz] = self.counts.map(|count| count.max(1));
+        let corner = self.corner(center);
+        let mut positions = Vec::with_capacity(self.probe_count());
+        for z in 0..nz {
+            for y in 0..ny {
+                for x in 0..nx {
+                    let offset = Vec3::new(x as f32, y as f32, z as f32);
+                    positions.push(corner + offset.component_mul(&self.spacing));
+                }
+            }
+        }
+        positions
+    }
+
+    /// Trilinear blend of the eight probes around `point`, for a grid centered on
+    /// `center`. `None` if the grid isn't baked or `point` lies outside it.
+    pub fn sample(&self, center: &Vec3, point: &Vec3) -> Option<LightProbe> {
+        if !self.is_baked() {
+            return None;
+        }
+        let counts = self.counts.map(|count| count.max(1) as usize);
+        let local = point - self.corner(center);
+        let mut cell = [0; 3];
+        let mut fraction = [0.0; 3];
+        for axis in 0..3 {
+            let last = (counts[axis] - 1) as f32;
+            let coordinate = if self.spacing[axis] > 0.0 {
+                local[axis] / self.spacing[axis]
+            } else {
+                0.0
+            };
+            // The grid reaches half a spacing past its outer probes.
+            if !(-0.5..=last + 0.5).contains(&coordinate) {
+                return None;
+            }
+            let coordinate = coordinate.clamp(0.0, last);
+            cell[axis] = (coordinate.floor() as usize).min(counts[axis].saturating_sub(2));
+            fraction[axis] = coordinate - cell[axis] as f32;
+        }
+
+        let mut blended = LightProbe::default();
+        for corner in 0..8 {
+            let mut index = 0;
+            let mut weight = 1.0;
+            for axis in (0..3).rev() {
+                let upper = corner >> axis & 1 == 1;
+                let i = (cell[axis] + upper as usize).min(counts[axis] - 1);
+                index = index * counts[axis] + i;
+                weight *= if upper {
+                    fraction[axis]
+                } else {
+                    1.0 - fraction[axis]
+                };
+            }
+            if weight > 0.0 {
+                blended.add_scaled(&self.probes[index], weight);
+            }
+        }
+        Some(blended)
+    }
+
+    fn corner(&self, center: &Vec3) -> Vec3 {
+        let extent = Vec3::from(self.counts.map(|count| count.max(1) as f32 - 1.0));
+        center - extent.component_mul(&self.spacing) * 0.5
+    }
+}
+
+impl Default for LightProbeGridComponent {
+    fn default() -> Self {
+        Self {
+            counts: [4, 2, 4],
+            spacing: Vec3::new(3.0, 2.0, 3.0),
+            intensity: 1.0,
+            probes: Vec::new(),
+        }
+    }
+}
+
+/// Marks helpers that only make sense while editing, such as spawn markers or trigger
+/// volumes drawn as meshes. Game builds, without the `dev` feature, never draw them.
+#[derive(Clone, Copy, Debug, Default, Component, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EditorOnly;
+
+impl EditorOnly {
+    /// Whether editor-only entities are drawn in this build.
+    pub const DRAWN: bool = cfg!(feature = "dev");
+}
+
+/// Per-entity tweaks applied on top of the entity's material, without creating a new
+/// material. Changes are picked up on the next frame; suited to damage flashes, team
+/// colors or scrolling textures.
+#[derive(Clone, Debug, Component, PartialEq, Serialize, Deserialize)]
+pub struct MaterialOverrideComponent {
+    /// Multiplies the material's base color.
+    pub tint: Vec4,
+    /// Adds `base color * tint * emissive_strength` as unlit emission.
+    pub emissive_strength: f32,
+    /// Added to the mesh's texture coordinates.
+    pub uv_offset: Vec2,
+}
+
+impl Default for MaterialOverrideComponent {
+    fn default() -> Self {
+        Self {
+            tint: Vec4::new(1.0, 1.0, 1.0, 1.0),
+            emissive_strength: 0.0,
+            uv_offset: Vec2::zeros(),
+        }
+    }
+}
+
+/// Skinning palette for a mesh whose vertices carry joint indices and weights. The mesh
+/// is deformed on the GPU in both the geometry and shadow passes.
+#[derive(Clone, Debug, Component, Default, Serialize, Deserialize)]
+pub struct SkinnedMeshComponent {
+    /// Final skinning matrices in model space (joint global transform times inverse bind
+    /// matrix), indexed by the mesh's joint indices. Written each frame by animation code.
+    pub joint_matrices: Vec<Mat4>,
+}
+
+impl SkinnedMeshComponent {
+    /// A palette of `joint_count` identity matrices, i.e. the mesh in its bind pose.
+    pub fn new(joint_count: usize) -> Self {
+        Self {
+            joint_matrices: vec![Mat4::identity(); joint_count],
+        }
+    }
+}
+
+/// Baked lighting for a static mesh. The lightmap is sampled with the mesh's second UV
+/// set (the first if it has none), remapped by `scale_offset` into the mesh's region of
+/// the lightmap. RGB holds baked indirect light and alpha baked ambient occlusion.
+#[derive(Clone, Debug, Component, PartialEq)]
+pub struct LightmapComponent {
+    pub lightmap: ImageHandle,
+    /// xy: UV scale, zw: UV offset into the lightmap.
+    pub scale_offset: Vec4,
+    /// Multiplies the baked light in RGB. Occlusion is applied as-is.
+    pub intensity: f32,
+}
+
+impl LightmapComponent {
+    /// A lightmap covering the whole texture.
+    pub fn new(lightmap: ImageHandle) -> Self {
+        Self {
+            lightmap,
+            scale_offset: Vec4::new(1.0, 1.0, 0.0, 0.0),
+            intensity: 1.0,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Component, Serialize, Deserialize)]
 pub struct CameraComponent {
     pub near_clip: f32,
     pub far_clip: f32,
@@ -51,27 +443,265 @@ pub struct CameraComponent {
     pub active: bool,
 }
 
-#[derive(Component, Debug, Clone)]
+impl CameraComponent {
+    /// World-space view volume of this camera placed at `transform`.
+    pub fn frustum(&self, transform: &Transform, aspect_ratio: f32) -> Frustum {
+        Frustum::from_perspective(
+            &transform.get_view_matrix(),
+            aspect_ratio,
+            self.fov,
+            self.near_clip,
+            self.far_clip,
+        )
+    }
+}
+
+/// Fly camera driven by `basic_camera_system`: mouse-look on the `MOUSE_X`/`MOUSE_Y`
+/// axes, movement on `horizontal`/`vertical`, Shift to sprint and the scroll wheel to
+/// change speed.
+#[derive(Component, Debug, Clone, Serialize, Deserialize)]
 pub struct CameraControllerComponent {
+    /// Movement speed in units per second. Adjusted by the scroll wheel.
     pub speed: f32,
+    pub min_speed: f32,
+    pub max_speed: f32,
+    /// Speed multiplier while Shift is held.
+    pub sprint_multiplier: f32,
+    /// Radians of rotation per unit of mouse axis (per pixel with a scale-1 binding).
+    pub sensitivity: f32,
+    /// Time constant of the exponential smoothing applied to look and movement, in
+    /// seconds. 0 disables smoothing.
+    pub smoothing: f32,
+    /// Target orientation. The camera eases towards it according to `smoothing`.
     pub yaw: f32,
     pub pitch: f32,
+    #[serde(skip)]
+    smoothed: Option<(f32, f32)>,
+    #[serde(skip, default = "Vec3::zeros")]
+    velocity: Vec3,
 }
 
 impl CameraControllerComponent {
     pub fn new(speed: f32) -> Self {
         Self {
             speed,
+            min_speed: 0.5,
+            max_speed: 500.0,
+            sprint_multiplier: 3.0,
+            sensitivity: 0.002,
+            smoothing: 0.03,
             yaw: 0.0,
             pitch: 0.0,
+            smoothed: None,
+            velocity: Vec3::zeros(),
+        }
+    }
+
+    pub fn with_sensitivity(mut self, sensitivity: f32) -> Self {
+        self.sensitivity = sensitivity;
+        self
+    }
+
+    pub fn with_smoothing(mut self, smoothing: f32) -> Self {
+        self.smoothing = smoothing;
+        self
+    }
+
+    pub fn with_sprint_multiplier(mut self, multiplier: f32) -> Self {
+        self.sprint_multiplier = multiplier;
+        self
+    }
+
+    /// Fraction of the remaining distance to cover this frame.
+    pub(crate) fn smoothing_factor(&self, dt: f32) -> f32 {
+        if self.smoothing <= 0.0 {
+            1.0
+        } else {
+            1.0 - (-dt / self.smoothing).exp()
         }
     }
+
+    /// Eases the smoothed orientation towards `yaw`/`pitch` and returns it.
+    pub(crate) fn smooth_rotation(&mut self, dt: f32) -> (f32, f32) {
+        let t = self.smoothing_factor(dt);
+        let (yaw, pitch) = self.smoothed.unwrap_or((self.yaw, self.pitch));
+        let next = (yaw + (self.yaw - yaw) * t, pitch + (self.pitch - pitch) * t);
+        self.smoothed = Some(next);
+        next
+    }
+
+    /// Eases the velocity towards `target` and returns it.
+    pub(crate) fn smooth_velocity(&mut self, target: Vec3, dt: f32) -> Vec3 {
+        let t = self.smoothing_factor(dt);
+        self.velocity += (target - self.velocity) * t;
+        self.velocity
+    }
 }
 
-#[derive(Clone, Debug, Component)]
+/// Editor-style camera driven by `orbit_camera_system`: orbits `target` while the right
+/// mouse button is held, pans with the middle button and zooms with the scroll wheel.
+#[derive(Component, Debug, Clone, Serialize, Deserialize)]
+pub struct OrbitCameraControllerComponent {
+    /// Point the camera looks at and orbits around.
+    pub target: Vec3,
+    pub distance: f32,
+    pub min_distance: f32,
+    pub max_distance: f32,
+    pub yaw: f32,
+    pub pitch: f32,
+    /// Radians of rotation per unit of mouse axis.
+    pub sensitivity: f32,
+    /// Pan distance per unit of mouse axis, as a fraction of `distance`.
+    pub pan_speed: f32,
+    /// Distance factor per notch of the scroll wheel.
+    pub zoom_step: f32,
+}
+
+impl OrbitCameraControllerComponent {
+    pub fn new(target: Vec3, distance: f32) -> Self {
+        Self {
+            target,
+            distance,
+            min_distance: 0.5,
+            max_distance: 1000.0,
+            yaw: 0.0,
+            pitch: -0.4,
+            sensitivity: 0.005,
+            pan_speed: 0.002,
+            zoom_step: 1.1,
+        }
+    }
+
+    /// Re-centers the orbit on `target`, e.g. a newly selected entity.
+    pub fn focus(&mut self, target: Vec3) {
+        self.target = target;
+    }
+}
+
+/// Third-person boom for an orbit camera. `orbit_camera_system` sweeps a sphere from the
+/// orbit target towards the camera and pulls the camera in front of the first collider
+/// in the way, then lets it extend back smoothly once the view is clear.
+///
+/// Obstructions come from the spatial world as synced at the end of the previous frame.
+#[derive(Component, Debug, Clone)]
+pub struct SpringArmComponent {
+    /// Radius of the swept probe. Keep it at least the camera's near-plane half-size so
+    /// geometry is not clipped at the edges of the view.
+    pub probe_radius: f32,
+    /// Rate, per second, at which the arm extends back to full length. Shortening is
+    /// immediate so the camera never ends up inside geometry.
+    pub return_speed: f32,
+    /// Collider the sweep ignores, usually the followed character's own. The sweep
+    /// starts at the target, so that collider would otherwise block it at once.
+    pub ignore: Option<ColliderId>,
+    current_length: Option<f32>,
+}
+
+impl SpringArmComponent {
+    pub fn new(probe_radius: f32) -> Self {
+        Self {
+            probe_radius,
+            return_speed: 5.0,
+            ignore: None,
+            current_length: None,
+        }
+    }
+
+    pub fn ignoring(mut self, collider: ColliderId) -> Self {
+        self.ignore = Some(collider);
+        self
+    }
+
+    /// Arm length after the last update; `None` before the first.
+    pub fn current_length(&self) -> Option<f32> {
+        self.current_length
+    }
+
+    /// Returns how far along `arm` (pivot to desired camera position) the camera can sit
+    /// this frame.
+    pub fn update(&mut self, spatial: &SpatialWorld, pivot: Vec3, arm: Vec3, dt: f32) -> f32 {
+        let desired = arm.norm();
+        if desired <= f32::EPSILON {
+            self.current_length = Some(0.0);
+            return 0.0;
+        }
+
+        let ignore = self.ignore;
+        let ray = Ray::new(pivot, arm / desired);
+        let target = spatial
+            .sphere_cast(&ray, self.probe_radius, desired, |id| Some(id) != ignore)
+            .map_or(desired, |hit| hit.distance);
+
+        let length = match self.current_length {
+            Some(current) if current < target => {
+                current + (target - current) * (1.0 - (-self.return_speed * dt).exp())
+            }
+            _ => target,
+        };
+        self.current_length = Some(length);
+        length
+    }
+}
+
+/// Sun-style light. Shines along the forward axis of the entity's `TransformComponent`,
+/// so rotating the transform at runtime moves the light and its shadow cascades.
+#[derive(Clone, Debug, Component, Serialize, Deserialize)]
 pub struct DirectionalLightComponent {
-    pub color: Vec3,
+    pub color: Color,
+    pub intensity: f32,
+    /// Cascade overrides for this light's shadows.
+    pub shadow: LightShadowSettings,
+}
+
+/// Omnidirectional light at the entity's `TransformComponent` location. Shaded through
+/// the clustered light lists, so scenes can hold many of them; they cast no shadows.
+#[derive(Clone, Debug, Component, Serialize, Deserialize)]
+pub struct PointLightComponent {
+    pub color: Color,
+    pub intensity: f32,
+    /// Distance at which the light's contribution falls to zero.
+    pub range: f32,
+}
+
+impl PointLightComponent {
+    pub fn new(color: Color, intensity: f32, range: f32) -> Self {
+        Self {
+            color,
+            intensity,
+            range,
+        }
+    }
+}
+
+/// Rectangular light centered on the entity's `TransformComponent` location, spanning
+/// its local X (width) and Y (height) axes and shining along its forward axis, for soft
+/// studio-style lighting from windows, panels or light boxes. Not clustered, so keep
+/// the count low; they cast no shadows.
+///
+/// Only diffuse light is computed, from the rectangle's exact form factor; there are no
+/// specular highlights from area lights (no GGX LTC term).
+#[derive(Clone, Debug, Component, Serialize, Deserialize)]
+pub struct AreaLightComponent {
+    pub color: Color,
+    /// Brightness of the rectangle's surface. Larger rectangles give off more light.
     pub intensity: f32,
-    pub ambient_color: Vec3,
-    pub ambient_intensity: f32,
+    pub width: f32,
+    pub height: f32,
+    /// Whether the back face emits light too.
+    pub two_sided: bool,
+    /// Distance from the center at which the light's contribution falls to zero.
+    pub range: f32,
+}
+
+impl Default for AreaLightComponent {
+    fn default() -> Self {
+        Self {
+            color: Color::WHITE,
+            intensity: 5.0,
+            width: 1.0,
+            height: 1.0,
+            two_sided: false,
+            range: 10.0,
+        }
+    }
 }
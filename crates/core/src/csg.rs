@@ -0,0 +1,525 @@
+//! Constructive solid geometry brushes for blocking out levels in code.
+//!
+//! Spawn entities with a [`BrushComponent`] and a `TransformComponent`, then call
+//! `EngineContext::build_brushes` once the scene is set up. It merges every brush into
+//! one render mesh, with the faces hidden inside the solid removed, and spawns a
+//! collider for each additive brush:
+//!
+//! ```ignore
+//! let world = ctx.get_world();
+//! world.create_entity((TransformComponent(wall), BrushComponent::new(BrushShape::cube())));
+//! world.create_entity((TransformComponent(door), BrushComponent::subtract(BrushShape::cube())));
+//! ctx.build_brushes(grid_material);
+//! ```
+//!
+//! Brushes apply in ascending [`BrushComponent::order`]: a subtraction carves everything
+//! added before it, and later additions fill the hole again. All brush shapes are convex,
+//! so merging only clips faces against brush planes. UVs are projected along the
+//! dominant axis of each face in world space, one texture repeat per unit, so grid
+//! textures line up across brushes.
+//!
+//! The spatial world only tests collider bounds, so each additive brush collides as its
+//! world-space box, and subtractions do not cut holes into colliders.
+
+use common::{MeshData, SubMesh, Vertex, VertexEncoding};
+use ecs::component::Component;
+use nalgebra_glm::{self as glm, Mat4, Vec2, Vec3, Vec4};
+use serde::{Deserialize, Serialize};
+use spatial::AABB;
+
+/// Distance below which a point counts as lying on a plane.
+const PLANE_EPSILON: f32 = 1e-4;
+/// Offset from a face at which the solid is sampled to decide if the face is visible.
+const SAMPLE_OFFSET: f32 = 1e-3;
+
+/// Convex shape of a brush in its local space, centered on the origin.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum BrushShape {
+    Box {
+        size: Vec3,
+    },
+    /// Upright along Y, approximated by `sides` flat faces.
+    Cylinder {
+        radius: f32,
+        height: f32,
+        sides: u32,
+    },
+    /// A wedge filling the box of `size` below a slope that rises from the bottom edge at
+    /// -Z to the top edge at +Z.
+    Ramp {
+        size: Vec3,
+    },
+}
+
+impl BrushShape {
+    /// A 1x1x1 box, to be sized by the entity's scale.
+    pub fn cube() -> Self {
+        BrushShape::Box {
+            size: Vec3::repeat(1.0),
+        }
+    }
+
+    /// Faces with their corners counter-clockwise seen from outside.
+    fn faces(&self) -> Vec<Vec<Vec3>> {
+        match *self {
+            BrushShape::Box { size } => {
+                let h = size * 0.5;
+                let c = |x: f32, y: f32, z: f32| Vec3::new(x * h.x, y * h.y, z * h.z);
+                #[rustfmt::skip]
+                let faces = vec![
+                    vec![c(1., -1., -1.), c(1., 1., -1.), c(1., 1., 1.), c(1., -1., 1.)],
+                    vec![c(-1., -1., 1.), c(-1., 1., 1.), c(-1., 1., -1.), c(-1., -1., -1.)],
+                    vec![c(-1., 1., -1.), c(-1., 1., 1.), c(1., 1., 1.), c(1., 1., -1.)],
+                    vec![c(-1., -1., 1.), c(-1., -1., -1.), c(1., -1., -1.), c(1., -1., 1.)],
+                    vec![c(-1., -1., 1.), c(1., -1., 1.), c(1., 1., 1.), c(-1., 1., 1.)],
+                    vec![c(1., -1., -1.), c(-1., -1., -1.), c(-1., 1., -1.), c(1., 1., -1.)],
+                ];
+                faces
+            }
+            BrushShape::Cylinder {
+                radius,
+                height,
+                sides,
+            } => {
+                let sides = sides.max(3);
+                let half = height * 0.5;
+                let rim = (0..sides)
+                    .map(|i| {
+                        let angle = i as f32 / sides as f32 * std::f32::consts::TAU;
+                        Vec2::new(angle.cos(), angle.sin()) * radius
+                    })
+                    .collect::<Vec<_>>();
+                let at = |p: Vec2, y: f32| Vec3::new(p.x, y, p.y);
+                let mut faces = (0..rim.len())
+                    .map(|i| {
+                        let (a, b) = (rim[i], rim[(i + 1) % rim.len()]);
+                        vec![at(a, -half), at(a, half), at(b, half), at(b, -half)]
+                    })
+                    .collect::<Vec<_>>();
+                faces.push(rim.iter().rev().map(|&p| at(p, half)).collect());
+                faces.push(rim.iter().map(|&p| at(p, -half)).collect());
+                faces
+            }
+            BrushShape::Ramp { size } => {
+                let h = size * 0.5;
+                let c = |x: f32, y: f32, z: f32| Vec3::new(x * h.x, y * h.y, z * h.z);
+                #[rustfmt::skip]
+                let faces = vec![
+                    // Bottom, back and slope.
+                    vec![c(-1., -1., 1.), c(-1., -1., -1.), c(1., -1., -1.), c(1., -1., 1.)],
+                    vec![c(-1., -1., 1.), c(1., -1., 1.), c(1., 1., 1.), c(-1., 1., 1.)],
+                    vec![c(-1., -1., -1.), c(-1., 1., 1.), c(1., 1., 1.), c(1., -1., -1.)],
+                    // Sides.
+                    vec![c(1., -1., -1.), c(1., 1., 1.), c(1., -1., 1.)],
+                    vec![c(-1., -1., 1.), c(-1., 1., 1.), c(-1., -1., -1.)],
+                ];
+                faces
+            }
+        }
+    }
+}
+
+/// Whether a brush adds to the level's solid or carves it out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum BrushOperation {
+    #[default]
+    Add,
+    Subtract,
+}
+
+/// A blockout brush, placed, rotated and scaled by the entity's transform.
+#[derive(Clone, Debug, Component, PartialEq, Serialize, Deserialize)]
+pub struct BrushComponent {
+    pub shape: BrushShape,
+    pub operation: BrushOperation,
+    /// Brushes apply from lowest to highest order. Ties go by entity index.
+    pub order: i32,
+}
+
+impl BrushComponent {
+    pub fn new(shape: BrushShape) -> Self {
+        Self {
+            shape,
+            operation: BrushOperation::Add,
+            order: 0,
+        }
+    }
+
+    pub fn subtract(shape: BrushShape) -> Self {
+        Self {
+            operation: BrushOperation::Subtract,
+            ..Self::new(shape)
+        }
+    }
+
+    pub fn with_order(mut self, order: i32) -> Self {
+        self.order = order;
+        self
+    }
+}
+
+/// Plane of points `p` with `normal.dot(p) == distance`; the normal points outside.
+#[derive(Debug, Clone, Copy)]
+struct Plane {
+    normal: Vec3,
+    distance: f32,
+}
+
+impl Plane {
+    fn signed_distance(&self, point: &Vec3) -> f32 {
+        self.normal.dot(point) - self.distance
+    }
+}
+
+/// A brush moved into world space.
+struct WorldBrush {
+    faces: Vec<Vec<Vec3>>,
+    planes: Vec<Plane>,
+    operation: BrushOperation,
+    bounds: AABB,
+}
+
+impl WorldBrush {
+    fn new(model: &Mat4, brush: &BrushComponent) -> Self {
+        // A mirroring transform turns the faces inside out; flip them back.
+        let mirrored = glm::mat4_to_mat3(model).determinant() < 0.0;
+        let faces = brush
+            .shape
+            .faces()
+            .into_iter()
+            .map(|face| {
+                let mut face = face
+                    .iter()
+                    .map(|p| (model * p.push(1.0)).xyz())
+                    .collect::<Vec<_>>();
+                if mirrored {
+                    face.reverse();
+                }
+                face
+            })
+            .collect::<Vec<_>>();
+        let planes = faces
+            .iter()
+            .map(|face| {
+                let normal = face_normal(face);
+                Plane {
+                    normal,
+                    distance: normal.dot(&face[0]),
+                }
+            })
+            .collect();
+        let mut bounds = AABB::new(faces[0][0], faces[0][0]);
+        for &point in faces.iter().flatten() {
+            bounds = bounds.union(&AABB::new(point, point));
+        }
+        Self {
+            faces,
+            planes,
+            operation: brush.operation,
+            bounds,
+        }
+    }
+
+    fn contains(&self, point: &Vec3) -> bool {
+        self.planes
+            .iter()
+            .all(|plane| plane.signed_distance(point) < 0.0)
+    }
+
+    /// Whether `point` lies on a face of this brush whose visible side faces `normal`.
+    fn has_face_at(&self, point: &Vec3, normal: &Vec3) -> bool {
+        let sign = match self.operation {
+            BrushOperation::Add => 1.0,
+            BrushOperation::Subtract => -1.0,
+        };
+        self.planes.iter().any(|plane| {
+            plane.normal.dot(normal) * sign > 1.0 - 1e-4
+                && plane.signed_distance(point).abs() < PLANE_EPSILON
+                && self
+                    .planes
+                    .iter()
+                    .all(|other| other.signed_distance(point) < PLANE_EPSILON)
+        })
+    }
+}
+
+/// Unit normal of a convex polygon with counter-clockwise corners.
+fn face_normal(face: &[Vec3]) -> Vec3 {
+    let mut normal = Vec3::zeros();
+    for i in 1..face.len() - 1 {
+        normal += (face[i] - face[0]).cross(&(face[i + 1] - face[0]));
+    }
+    normal.normalize()
+}
+
+/// Whether `point` is inside the solid after applying every brush in order.
+fn solid_contains(brushes: &[WorldBrush], point: &Vec3) -> bool {
+    brushes
+        .iter()
+        .fold(false, |solid, brush| match brush.operation {
+            BrushOperation::Add => solid || brush.contains(point),
+            BrushOperation::Subtract => solid && !brush.contains(point),
+        })
+}
+
+/// Splits a convex polygon along `plane` into the parts in front of and behind it.
+fn split(polygon: &[Vec3], plane: &Plane) -> (Option<Vec<Vec3>>, Option<Vec<Vec3>>) {
+    let distances = polygon
+        .iter()
+        .map(|p| plane.signed_distance(p))
+        .collect::<Vec<_>>();
+    if distances.iter().all(|&d| d <= PLANE_EPSILON) {
+        return (None, Some(polygon.to_vec()));
+    }
+    if distances.iter().all(|&d| d >= -PLANE_EPSILON) {
+        return (Some(polygon.to_vec()), None);
+    }
+    let (mut front, mut back) = (Vec::new(), Vec::new());
+    for i in 0..polygon.len() {
+        let j = (i + 1) % polygon.len();
+        let (a, b) = (polygon[i], polygon[j]);
+        let (da, db) = (distances[i], distances[j]);
+        if da >= -PLANE_EPSILON {
+            front.push(a);
+        }
+        if da <= PLANE_EPSILON {
+            back.push(a);
+        }
+        if (da > PLANE_EPSILON && db < -PLANE_EPSILON)
+            || (da < -PLANE_EPSILON && db > PLANE_EPSILON)
+        {
+            let crossing = a + (b - a) * (da / (da - db));
+            front.push(crossing);
+            back.push(crossing);
+        }
+    }
+    let keep = |polygon: Vec<Vec3>| (polygon.len() >= 3).then_some(polygon);
+    (keep(front), keep(back))
+}
+
+/// Cuts `polygon` into pieces that each lie wholly inside or wholly outside `brush`.
+fn split_by_brush(polygon: Vec<Vec3>, brush: &WorldBrush, pieces: &mut Vec<Vec<Vec3>>) {
+    let mut rest = polygon;
+    for plane in &brush.planes {
+        let (front, back) = split(&rest, plane);
+        pieces.extend(front);
+        match back {
+            Some(back) => rest = back,
+            None => return,
+        }
+    }
+    pieces.push(rest);
+}
+
+/// Merges brushes, each with its world transform and in the order they apply, into a
+/// single-material mesh in world space. Faces buried in the solid are removed, and
+/// subtractions leave the faces of their cavity.
+pub fn build_mesh(brushes: &[(Mat4, BrushComponent)]) -> MeshData {
+    let brushes = brushes
+        .iter()
+        .map(|(model, brush)| WorldBrush::new(model, brush))
+        .collect::<Vec<_>>();
+    let mut mesh = MeshData {
+        vertices: Vec::new(),
+        vertex_encoding: VertexEncoding::Full,
+        indices: Vec::new(),
+        extras: None,
+        skin: None,
+        submeshes: Vec::new(),
+    };
+
+    for (index, brush) in brushes.iter().enumerate() {
+        for face in &brush.faces {
+            let mut pieces = vec![face.clone()];
+            for (other_index, other) in brushes.iter().enumerate() {
+                if other_index == index || !other.bounds.intersects(&brush.bounds) {
+                    continue;
+                }
+                let mut split_pieces = Vec::new();
+                for piece in pieces {
+                    split_by_brush(piece, other, &mut split_pieces);
+                }
+                pieces = split_pieces;
+            }
+
+            for mut piece in pieces {
+                let mut normal = face_normal(&piece);
+                if brush.operation == BrushOperation::Subtract {
+                    piece.reverse();
+                    normal = -normal;
+                }
+                let center = piece.iter().sum::<Vec3>() / piece.len() as f32;
+                let visible = solid_contains(&brushes, &(center - normal * SAMPLE_OFFSET))
+                    && !solid_contains(&brushes, &(center + normal * SAMPLE_OFFSET));
+                // Coplanar faces of several brushes are drawn once, by the first brush.
+                let covered = brushes[..index]
+                    .iter()
+                    .any(|earlier| earlier.has_face_at(&center, &normal));
+                if visible && !covered {
+                    push_polygon(&mut mesh, &piece, normal);
+                }
+            }
+        }
+    }
+
+    mesh.submeshes.push(SubMesh {
+        index_offset: 0,
+        index_count: mesh.indices.len() as u32,
+    });
+    mesh
+}
+
+/// World-space bounds of each additive brush, for colliders.
+pub fn collider_bounds(brushes: &[(Mat4, BrushComponent)]) -> Vec<AABB> {
+    brushes
+        .iter()
+        .filter(|(_, brush)| brush.operation == BrushOperation::Add)
+        .map(|(model, brush)| WorldBrush::new(model, brush).bounds)
+        .collect()
+}
+
+/// Adds a convex polygon as a triangle fan, with UVs projected along the dominant axis
+/// of `normal`.
+fn push_polygon(mesh: &mut MeshData, polygon: &[Vec3], normal: Vec3) {
+    let abs = normal.abs();
+    let (u_axis, v_axis) = if abs.x >= abs.y && abs.x >= abs.z {
+        (Vec3::z(), -Vec3::y())
+    } else if abs.y >= abs.z {
+        (Vec3::x(), Vec3::z())
+    } else {
+        (Vec3::x(), -Vec3::y())
+    };
+    let tangent = (u_axis - normal * normal.dot(&u_axis)).normalize();
+    let sign = if normal.cross(&tangent).dot(&v_axis) >= 0.0 {
+        1.0
+    } else {
+        -1.0
+    };
+
+    let first = mesh.vertices.len() as u32;
+    for point in polygon {
+        mesh.vertices.push(Vertex {
+            pos: *point,
+            tex_coord: Vec2::new(point.dot(&u_axis), point.dot(&v_axis)),
+            normal,
+            tangent: Vec4::new(tangent.x, tangent.y, tangent.z, sign),
+            texture_index: 0,
+        });
+    }
+    for i in 1..polygon.len() as u32 - 1 {
+        mesh.indices
+            .extend_from_slice(&[first, first + i, first + i + 1]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use glm::vec3;
+
+    fn placed(location: Vec3, brush: BrushComponent) -> (Mat4, BrushComponent) {
+        (glm::translation(&location), brush)
+    }
+
+    fn cube(size: f32) -> BrushShape {
+        BrushShape::Box {
+            size: Vec3::repeat(size),
+        }
+    }
+
+    /// Total triangle area, after checking every triangle faces along its normals.
+    fn surface_area(mesh: &MeshData) -> f32 {
+        mesh.indices
+            .chunks(3)
+            .map(|triangle| {
+                let [a, b, c] = [0, 1, 2].map(|i| &mesh.vertices[triangle[i] as usize]);
+                let cross = (b.pos - a.pos).cross(&(c.pos - a.pos));
+                assert!(cross.dot(&a.normal) >= 0.0, "triangle faces inwards");
+                cross.norm() * 0.5
+            })
+            .sum()
+    }
+
+    /// Checks every vertex normal points away from `interior`, a point inside a convex
+    /// solid.
+    fn assert_faces_outwards(mesh: &MeshData, interior: Vec3) {
+        for vertex in &mesh.vertices {
+            assert!(vertex.normal.dot(&(vertex.pos - interior)) > 0.0);
+        }
+    }
+
+    #[test]
+    fn single_box_keeps_all_faces() {
+        let mesh = build_mesh(&[placed(Vec3::zeros(), BrushComponent::new(cube(2.0)))]);
+        assert_eq!(mesh.vertices.len(), 24);
+        assert_eq!(mesh.indices.len(), 36);
+        assert!((surface_area(&mesh) - 24.0).abs() < 1e-3);
+        assert_faces_outwards(&mesh, Vec3::zeros());
+    }
+
+    #[test]
+    fn union_removes_hidden_and_duplicate_faces() {
+        // Overlapping along X with coplanar top, bottom, front and back faces.
+        let mesh = build_mesh(&[
+            placed(vec3(0.0, 0.0, 0.0), BrushComponent::new(cube(1.0))),
+            placed(vec3(0.5, 0.0, 0.0), BrushComponent::new(cube(1.0))),
+        ]);
+        // A 1.5 x 1 x 1 box.
+        assert!((surface_area(&mesh) - 8.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn subtraction_carves_a_tunnel_and_later_brushes_fill_it() {
+        let wall = placed(Vec3::zeros(), BrushComponent::new(cube(2.0)));
+        let tunnel = BrushShape::Box {
+            size: vec3(1.0, 1.0, 3.0),
+        };
+        let carved = build_mesh(&[
+            wall.clone(),
+            placed(Vec3::zeros(), BrushComponent::subtract(tunnel)),
+        ]);
+        // Outer faces minus both openings, plus the four tunnel walls.
+        assert!((surface_area(&carved) - 30.0).abs() < 1e-3);
+
+        let filled = build_mesh(&[
+            wall,
+            placed(Vec3::zeros(), BrushComponent::subtract(tunnel)),
+            placed(Vec3::zeros(), BrushComponent::new(tunnel)),
+        ]);
+        // The box, with the refill poking out 0.5 on either side.
+        assert!((surface_area(&filled) - 28.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn ramp_and_cylinder_are_closed_solids() {
+        let ramp = BrushShape::Ramp {
+            size: Vec3::repeat(1.0),
+        };
+        let mesh = build_mesh(&[placed(Vec3::zeros(), BrushComponent::new(ramp))]);
+        // Bottom, back, two half sides and the slope.
+        assert!((surface_area(&mesh) - (3.0 + 2f32.sqrt())).abs() < 1e-3);
+        assert_faces_outwards(&mesh, vec3(0.0, -0.25, 0.25));
+
+        let cylinder = BrushShape::Cylinder {
+            radius: 1.0,
+            height: 2.0,
+            sides: 64,
+        };
+        let mesh = build_mesh(&[placed(Vec3::zeros(), BrushComponent::new(cylinder))]);
+        let expected = 2.0 * std::f32::consts::PI * 2.0 + 2.0 * std::f32::consts::PI;
+        assert!((surface_area(&mesh) - expected).abs() < 0.1);
+        assert_faces_outwards(&mesh, Vec3::zeros());
+    }
+
+    #[test]
+    fn colliders_cover_additive_brushes_only() {
+        let brushes = [
+            placed(vec3(1.0, 0.0, 0.0), BrushComponent::new(cube(2.0))),
+            placed(Vec3::zeros(), BrushComponent::subtract(cube(1.0))),
+        ];
+        let bounds = collider_bounds(&brushes);
+        assert_eq!(bounds.len(), 1);
+        assert_eq!(bounds[0].lower, vec3(0.0, -1.0, -1.0));
+        assert_eq!(bounds[0].upper, vec3(2.0, 1.0, 1.0));
+    }
+}
@@ -0,0 +1,66 @@
+//! Piecewise-linear curves over normalized time, for values that change over an effect's
+//! lifetime such as a trail's width and color.
+
+use crate::tween::Tweenable;
+use serde::{Deserialize, Serialize};
+
+/// Keys of `(time, value)`, sorted by time and usually spanning `[0, 1]`. Sampling
+/// interpolates linearly between neighboring keys and holds the first and last values
+/// outside them. There is always at least one key.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Curve<T> {
+    pub keys: Vec<(f32, T)>,
+}
+
+impl<T: Tweenable> Curve<T> {
+    /// Sorts `keys` by time. Panics if `keys` is empty.
+    pub fn new(mut keys: Vec<(f32, T)>) -> Self {
+        assert!(!keys.is_empty(), "a curve needs at least one key");
+        keys.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Self { keys }
+    }
+
+    pub fn constant(value: T) -> Self {
+        Self::new(vec![(0.0, value)])
+    }
+
+    /// From `start` at 0 to `end` at 1.
+    pub fn linear(start: T, end: T) -> Self {
+        Self::new(vec![(0.0, start), (1.0, end)])
+    }
+
+    pub fn sample(&self, t: f32) -> T {
+        let next = self.keys.partition_point(|(time, _)| *time <= t);
+        match (next.checked_sub(1), self.keys.get(next)) {
+            (Some(i), Some((end_time, end))) => {
+                let (start_time, start) = &self.keys[i];
+                let span = end_time - start_time;
+                start.lerp(
+                    end,
+                    if span > 0.0 {
+                        (t - start_time) / span
+                    } else {
+                        0.0
+                    },
+                )
+            }
+            (Some(i), None) => self.keys[i].1,
+            (None, _) => self.keys[0].1,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn samples_interpolate_between_keys_and_clamp_outside() {
+        let curve = Curve::new(vec![(1.0, 0.0), (0.0, 2.0), (0.5, 4.0)]);
+        assert_eq!(curve.sample(-1.0), 2.0);
+        assert_eq!(curve.sample(0.25), 3.0);
+        assert_eq!(curve.sample(0.75), 2.0);
+        assert_eq!(curve.sample(2.0), 0.0);
+        assert_eq!(Curve::constant(5.0).sample(0.3), 5.0);
+    }
+}
@@ -0,0 +1,176 @@
+//! Time of day. [`day_night_cycle_system`] moves the sun across the sky and fades the
+//! light and ambient between day and night while a [`DayNightCycle`] resource is
+//! registered. A [`ProceduralSky`](crate::environment::ProceduralSky) follows the sun
+//! automatically, since it is lit by the same directional light.
+
+use crate::components::{DirectionalLightComponent, TransformComponent};
+use crate::environment::WorldEnvironment;
+use crate::system::Context;
+use common::Color;
+use ecs::command_buffer::Commands;
+use ecs::query::Query;
+use nalgebra_glm::{vec3, Vec3};
+use std::f32::consts::{FRAC_PI_2, TAU};
+
+/// Resource driving the day-night cycle. Register it to start the clock:
+/// `engine.insert_resource(DayNightCycle::default())`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DayNightCycle {
+    /// Hours since midnight. The sun rises at 6 and sets at 18.
+    pub time_of_day: f32,
+    /// Real seconds for a full 24 hours.
+    pub day_length: f32,
+    pub paused: bool,
+    /// Compass angle of sunrise in radians, counterclockwise from +X seen from above.
+    pub sunrise_heading: f32,
+    /// Height of the sun at noon in radians. The noon sun stands a quarter turn
+    /// counterclockwise from sunrise.
+    pub noon_elevation: f32,
+    /// Directional light intensity while the sun is up.
+    pub sun_intensity: f32,
+    pub noon_color: Color,
+    /// Light color with the sun low over the horizon.
+    pub horizon_color: Color,
+    pub day_ambient: Color,
+    pub day_ambient_intensity: f32,
+    pub night_ambient: Color,
+    pub night_ambient_intensity: f32,
+}
+
+impl Default for DayNightCycle {
+    fn default() -> Self {
+        Self {
+            time_of_day: 8.0,
+            day_length: 600.0,
+            paused: false,
+            sunrise_heading: 0.0,
+            noon_elevation: 60f32.to_radians(),
+            sun_intensity: 3.0,
+            noon_color: Color::linear(1.0, 0.96, 0.9),
+            horizon_color: Color::linear(1.0, 0.45, 0.2),
+            day_ambient: Color::linear(0.6, 0.7, 0.9),
+            day_ambient_intensity: 0.2,
+            night_ambient: Color::linear(0.1, 0.12, 0.25),
+            night_ambient_intensity: 0.03,
+        }
+    }
+}
+
+impl DayNightCycle {
+    /// Normalized direction towards the sun. It follows a circle through sunrise,
+    /// the noon elevation and sunset, passing below the horizon at night.
+    pub fn sun_direction(&self) -> Vec3 {
+        let angle = self.time_of_day / 24.0 * TAU - FRAC_PI_2;
+        let (heading_sin, heading_cos) = self.sunrise_heading.sin_cos();
+        let east = vec3(heading_cos, 0.0, -heading_sin);
+        let up = Vec3::y();
+        let noon_side = up.cross(&east);
+        let noon = up * self.noon_elevation.sin() + noon_side * self.noon_elevation.cos();
+        (east * angle.cos() + noon * angle.sin()).normalize()
+    }
+
+    /// 1 in full daylight, 0 at night, blending through dawn and dusk.
+    pub fn daylight(&self) -> f32 {
+        smoothstep(-0.1, 0.15, self.sun_direction().y)
+    }
+}
+
+/// Advances the clock and points every directional light at the sun.
+pub fn day_night_cycle_system(
+    mut query: Query<(&mut TransformComponent, &mut DirectionalLightComponent)>,
+    context: &mut Context,
+    _commands: &mut Commands,
+) {
+    let Ok(mut cycle) = context.try_res_mut::<DayNightCycle>() else {
+        return;
+    };
+    if !cycle.paused && cycle.day_length > 0.0 {
+        cycle.time_of_day =
+            (cycle.time_of_day + context.dt / cycle.day_length * 24.0).rem_euclid(24.0);
+    }
+
+    let sun = cycle.sun_direction();
+    let daylight = cycle.daylight();
+    // Lights shine along their forward vector, away from the sun.
+    let rotation = rotation_facing(-sun);
+    let sun_color = mix(
+        cycle.horizon_color,
+        cycle.noon_color,
+        smoothstep(0.0, 0.5, sun.y),
+    );
+    let sun_intensity = cycle.sun_intensity * smoothstep(-0.02, 0.1, sun.y);
+    for (transform, light) in query.iter() {
+        transform.rotation = rotation;
+        light.color = sun_color;
+        light.intensity = sun_intensity;
+    }
+
+    let mut environment = context.res_mut::<WorldEnvironment>();
+    environment.ambient_color = mix(cycle.night_ambient, cycle.day_ambient, daylight);
+    environment.ambient_intensity = cycle.night_ambient_intensity
+        + (cycle.day_ambient_intensity - cycle.night_ambient_intensity) * daylight;
+}
+
+/// Euler rotation whose `Transform::forward` is `forward`, with no roll.
+fn rotation_facing(forward: Vec3) -> Vec3 {
+    vec3(
+        forward.y.clamp(-1.0, 1.0).asin(),
+        (-forward.x).atan2(-forward.z),
+        0.0,
+    )
+}
+
+fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+fn mix(a: Color, b: Color, t: f32) -> Color {
+    let lerp = |a: f32, b: f32| a + (b - a) * t;
+    Color::linear_rgba(
+        lerp(a.r, b.r),
+        lerp(a.g, b.g),
+        lerp(a.b, b.b),
+        lerp(a.a, b.a),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::transform::Transform;
+
+    #[test]
+    fn sun_rises_east_and_peaks_at_noon() {
+        let mut cycle = DayNightCycle {
+            time_of_day: 6.0,
+            ..DayNightCycle::default()
+        };
+        assert!((cycle.sun_direction() - Vec3::x()).norm() < 1e-5);
+
+        cycle.time_of_day = 12.0;
+        let noon = cycle.sun_direction();
+        assert!((noon.y - cycle.noon_elevation.sin()).abs() < 1e-5);
+        assert!(noon.z < 0.0);
+        assert_eq!(cycle.daylight(), 1.0);
+
+        cycle.time_of_day = 0.0;
+        assert!(cycle.sun_direction().y < 0.0);
+        assert_eq!(cycle.daylight(), 0.0);
+    }
+
+    #[test]
+    fn light_rotation_faces_away_from_the_sun() {
+        let cycle = DayNightCycle {
+            time_of_day: 9.5,
+            sunrise_heading: 0.7,
+            ..DayNightCycle::default()
+        };
+        let forward = -cycle.sun_direction();
+        let transform = Transform {
+            rotation: rotation_facing(forward),
+            ..Transform::default()
+        };
+        assert!((transform.forward() - forward).norm() < 1e-5);
+    }
+}
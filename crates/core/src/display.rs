@@ -0,0 +1,187 @@
+//! Connected monitors and the display the window goes fullscreen on. The engine keeps the
+//! [`Displays`] resource up to date as monitors are plugged in and out. Systems read it
+//! to list monitors and video modes, and call [`Displays::request`] to switch window
+//! mode, monitor or video mode. The engine applies the request before the next frame
+//! and saves it to the settings file.
+//!
+//! When the selected monitor is unplugged, fullscreen falls back to the primary monitor.
+//! The selection itself is kept, so the window moves back once the monitor returns.
+
+use config::config::{DisplaySettings, VideoMode, WindowMode};
+
+/// A connected monitor.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MonitorInfo {
+    /// Name as the OS reports it, the key [`DisplaySettings::monitor`] is matched against.
+    pub name: String,
+    /// Current resolution in physical pixels.
+    pub width: u32,
+    pub height: u32,
+    /// Current refresh rate in millihertz, when the OS reports one.
+    pub refresh_rate_millihertz: Option<u32>,
+    /// Physical pixels per logical pixel.
+    pub scale_factor: f64,
+    pub primary: bool,
+    /// Modes exclusive fullscreen can switch to.
+    pub video_modes: Vec<VideoMode>,
+}
+
+impl MonitorInfo {
+    /// Index of `wanted` in `video_modes` if the monitor offers it. Otherwise the mode
+    /// closest in size to `width`x`height`, preferring higher refresh rates.
+    pub fn closest_video_mode(
+        &self,
+        wanted: Option<VideoMode>,
+        width: u32,
+        height: u32,
+    ) -> Option<usize> {
+        if let Some(index) = wanted.and_then(|w| self.video_modes.iter().position(|m| *m == w)) {
+            return Some(index);
+        }
+        let (width, height) = wanted.map_or((width, height), |w| (w.width, w.height));
+        self.video_modes
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, mode)| {
+                let distance =
+                    mode.width.abs_diff(width) as u64 + mode.height.abs_diff(height) as u64;
+                (distance, std::cmp::Reverse(mode.refresh_rate_millihertz))
+            })
+            .map(|(index, _)| index)
+    }
+}
+
+/// Window mode and display the app asked to switch to.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DisplayRequest {
+    pub window_mode: WindowMode,
+    pub display: DisplaySettings,
+}
+
+/// Resource listing the connected monitors, and queueing window mode changes.
+///
+/// From a system:
+/// `ctx.res_mut::<Displays>().request(WindowMode::Fullscreen, display)`.
+#[derive(Debug, Default)]
+pub struct Displays {
+    monitors: Vec<MonitorInfo>,
+    request: Option<DisplayRequest>,
+}
+
+impl Displays {
+    /// Connected monitors, in the order the OS lists them.
+    pub fn monitors(&self) -> &[MonitorInfo] {
+        &self.monitors
+    }
+
+    /// Replaces the monitor list. Called by the engine when monitors change.
+    pub fn set_monitors(&mut self, monitors: Vec<MonitorInfo>) {
+        self.monitors = monitors;
+    }
+
+    /// The primary monitor, or the first one if the OS does not say.
+    pub fn primary(&self) -> Option<&MonitorInfo> {
+        self.monitors
+            .iter()
+            .find(|m| m.primary)
+            .or(self.monitors.first())
+    }
+
+    /// The monitor `display` selects. See [`select_monitor`].
+    pub fn selected_monitor(&self, display: &DisplaySettings) -> Option<&MonitorInfo> {
+        select_monitor(&self.monitors, display).map(|index| &self.monitors[index])
+    }
+
+    /// Switches the window to `window_mode` on the display `display` selects. Applied
+    /// before the next frame and saved to the settings file.
+    pub fn request(&mut self, window_mode: WindowMode, display: DisplaySettings) {
+        self.request = Some(DisplayRequest {
+            window_mode,
+            display,
+        });
+    }
+
+    /// Returns and clears a pending request. Called by the engine before each frame.
+    pub fn take_request(&mut self) -> Option<DisplayRequest> {
+        self.request.take()
+    }
+}
+
+/// Index of the monitor `display` selects: the named monitor when it is connected,
+/// otherwise the primary one, otherwise the first.
+pub fn select_monitor(monitors: &[MonitorInfo], display: &DisplaySettings) -> Option<usize> {
+    let named = display
+        .monitor
+        .as_ref()
+        .and_then(|name| monitors.iter().position(|m| &m.name == name));
+    named
+        .or_else(|| monitors.iter().position(|m| m.primary))
+        .or((!monitors.is_empty()).then_some(0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn monitor(name: &str, primary: bool, video_modes: Vec<VideoMode>) -> MonitorInfo {
+        MonitorInfo {
+            name: name.to_string(),
+            width: 1920,
+            height: 1080,
+            refresh_rate_millihertz: Some(60000),
+            scale_factor: 1.0,
+            primary,
+            video_modes,
+        }
+    }
+
+    fn mode(width: u32, height: u32, hz: u32) -> VideoMode {
+        VideoMode {
+            width,
+            height,
+            refresh_rate_millihertz: hz * 1000,
+        }
+    }
+
+    #[test]
+    fn unplugged_monitor_falls_back_to_primary() {
+        let monitors = vec![
+            monitor("Left", false, Vec::new()),
+            monitor("Main", true, Vec::new()),
+        ];
+        let mut display = DisplaySettings {
+            monitor: Some("Left".to_string()),
+            video_mode: None,
+        };
+        assert_eq!(select_monitor(&monitors, &display), Some(0));
+
+        display.monitor = Some("Unplugged".to_string());
+        assert_eq!(select_monitor(&monitors, &display), Some(1));
+        assert_eq!(
+            select_monitor(&monitors, &DisplaySettings::default()),
+            Some(1)
+        );
+        assert_eq!(select_monitor(&[], &display), None);
+    }
+
+    #[test]
+    fn video_mode_matches_exactly_or_closest() {
+        let modes = vec![
+            mode(1280, 720, 60),
+            mode(1920, 1080, 60),
+            mode(1920, 1080, 144),
+        ];
+        let monitor = monitor("Main", true, modes);
+
+        assert_eq!(
+            monitor.closest_video_mode(Some(mode(1920, 1080, 60)), 0, 0),
+            Some(1)
+        );
+        // Not offered: same size at the highest refresh rate.
+        assert_eq!(
+            monitor.closest_video_mode(Some(mode(1920, 1080, 75)), 0, 0),
+            Some(2)
+        );
+        assert_eq!(monitor.closest_video_mode(None, 1366, 768), Some(0));
+    }
+}
@@ -0,0 +1,171 @@
+//! Immediate-mode 2D drawing for overlays such as health bars, crosshairs and profiler
+//! graphs. Systems add shapes to the [`Draw2D`] resource each frame, in the UI's logical
+//! pixels with the origin at the top-left. The renderer draws them over the frame, UI
+//! included, in call order. The engine clears the list before systems run.
+
+use crate::ui::UiRect;
+use common::{Color, ImageHandle};
+use nalgebra_glm::{Vec2, Vec4};
+use std::f32::consts::TAU;
+
+/// Segments of a full circle.
+const CIRCLE_SEGMENTS: usize = 32;
+
+/// One vertex of the tessellated shapes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Draw2DVertex {
+    pub position: Vec2,
+    pub uv: Vec2,
+    pub color: Vec4,
+}
+
+/// Consecutive triangles drawn with one texture, `None` for untextured shapes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Draw2DBatch {
+    pub texture: Option<ImageHandle>,
+    pub first_vertex: u32,
+    pub vertex_count: u32,
+}
+
+/// Shapes queued for this frame, tessellated into triangles. Shapes that follow each
+/// other with the same texture share a batch.
+#[derive(Debug, Default)]
+pub struct Draw2D {
+    vertices: Vec<Draw2DVertex>,
+    batches: Vec<Draw2DBatch>,
+}
+
+impl Draw2D {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn rect(&mut self, rect: UiRect, color: Color) {
+        self.quad(rect_corners(rect), [Vec2::zeros(); 4], color, None);
+    }
+
+    /// A line `thickness` pixels wide, centered on the segment from `from` to `to`.
+    pub fn line(&mut self, from: Vec2, to: Vec2, thickness: f32, color: Color) {
+        let direction = to - from;
+        let length = direction.norm();
+        if length <= f32::EPSILON {
+            return;
+        }
+        let side = Vec2::new(-direction.y, direction.x) * (thickness * 0.5 / length);
+        let corners = [from - side, to - side, to + side, from + side];
+        self.quad(corners, [Vec2::zeros(); 4], color, None);
+    }
+
+    /// A filled circle.
+    pub fn circle(&mut self, center: Vec2, radius: f32, color: Color) {
+        if radius <= 0.0 {
+            return;
+        }
+        let color = color.to_vec4();
+        let point = |segment: usize| {
+            let angle = segment as f32 / CIRCLE_SEGMENTS as f32 * TAU;
+            center + Vec2::new(angle.cos(), angle.sin()) * radius
+        };
+        let vertices = (0..CIRCLE_SEGMENTS).flat_map(|segment| {
+            [center, point(segment), point(segment + 1)].map(|position| Draw2DVertex {
+                position,
+                uv: Vec2::zeros(),
+                color,
+            })
+        });
+        self.push(None, vertices);
+    }
+
+    /// `texture` over `rect`, sampling the region `uv` of it, multiplied by `tint`.
+    pub fn textured_quad(&mut self, rect: UiRect, texture: ImageHandle, uv: UiRect, tint: Color) {
+        self.quad(rect_corners(rect), rect_corners(uv), tint, Some(texture));
+    }
+
+    pub fn vertices(&self) -> &[Draw2DVertex] {
+        &self.vertices
+    }
+
+    pub fn batches(&self) -> &[Draw2DBatch] {
+        &self.batches
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.vertices.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.vertices.clear();
+        self.batches.clear();
+    }
+
+    /// Two triangles over `corners`, given in winding order.
+    fn quad(
+        &mut self,
+        corners: [Vec2; 4],
+        uvs: [Vec2; 4],
+        color: Color,
+        texture: Option<ImageHandle>,
+    ) {
+        let color = color.to_vec4();
+        let vertices = [0, 1, 2, 0, 2, 3].map(|corner| Draw2DVertex {
+            position: corners[corner],
+            uv: uvs[corner],
+            color,
+        });
+        self.push(texture, vertices);
+    }
+
+    fn push(
+        &mut self,
+        texture: Option<ImageHandle>,
+        vertices: impl IntoIterator<Item = Draw2DVertex>,
+    ) {
+        let first_vertex = self.vertices.len() as u32;
+        self.vertices.extend(vertices);
+        let vertex_count = self.vertices.len() as u32 - first_vertex;
+        match self.batches.last_mut() {
+            Some(batch) if batch.texture == texture => batch.vertex_count += vertex_count,
+            _ => self.batches.push(Draw2DBatch {
+                texture,
+                first_vertex,
+                vertex_count,
+            }),
+        }
+    }
+}
+
+/// Corners of `rect` clockwise on screen, starting at the top-left.
+fn rect_corners(rect: UiRect) -> [Vec2; 4] {
+    let (min, max) = (rect.min, rect.max);
+    [min, Vec2::new(max.x, min.y), max, Vec2::new(min.x, max.y)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn batches_break_only_on_texture_changes() {
+        let rect = UiRect::new(Vec2::zeros(), Vec2::new(10.0, 10.0));
+        let texture = ImageHandle::new(1);
+        let mut draw = Draw2D::new();
+        draw.rect(rect, Color::WHITE);
+        draw.line(Vec2::zeros(), Vec2::new(5.0, 0.0), 2.0, Color::RED);
+        draw.textured_quad(rect, texture, rect, Color::WHITE);
+        draw.textured_quad(rect, texture, rect, Color::WHITE);
+        draw.circle(Vec2::zeros(), 4.0, Color::WHITE);
+
+        let batches = draw.batches();
+        assert_eq!(batches.len(), 3);
+        assert_eq!((batches[0].texture, batches[0].vertex_count), (None, 12));
+        assert_eq!(
+            (batches[1].texture, batches[1].first_vertex),
+            (Some(texture), 12)
+        );
+        assert_eq!(batches[2].vertex_count, CIRCLE_SEGMENTS as u32 * 3);
+        assert_eq!(draw.vertices().len(), 24 + CIRCLE_SEGMENTS * 3);
+
+        draw.clear();
+        assert!(draw.is_empty() && draw.batches().is_empty());
+    }
+}
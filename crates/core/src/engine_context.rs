@@ -1,15 +1,63 @@
+use crate::app_exit::AppExit;
 use crate::asset_context::AssetContext;
-use crate::system::{Context, SystemFunction};
-use crate::TransformComponent;
+use crate::asset_gc::{AssetGc, AssetGcSettings, AssetId};
+use crate::behavior_tree::{behavior_tree_system, BehaviorTasks, BehaviorTree};
+use crate::csg::{self, BrushComponent};
+use crate::day_night::day_night_cycle_system;
+use crate::display::Displays;
+use crate::draw2d::Draw2D;
+use crate::entity_id::EntityIds;
+use crate::environment::WorldEnvironment;
+use crate::frame_budget::{BudgetExceeded, FrameBudgets};
+use crate::localization::{localized_text_system, Localization};
+use crate::post_process::PostProcessSettings;
+use crate::preload::{Preload, PreloadError, PreloadId, PreloadProgress};
+use crate::render_settings::{
+    RenderSettings, CAPTURE_FRAME_ACTION, DUMP_FRAME_ACTION, DUMP_GPU_MEMORY_ACTION,
+};
+use crate::save_game::{register_engine_components, AssetRemap, SaveGame, SceneSnapshot};
+use crate::spline::{self, SplineComponent, SplineMeshSettings};
+use crate::streaming::{CellContext, WorldStreamer};
+use crate::system::{Context, System, SystemFunction, SystemId, SystemToggles};
+use crate::systems::{tween_system, tween_transform_system};
+use crate::time::Time;
+use crate::trails::trail_system;
+use crate::trigger::{TriggerEvent, TriggerTracker};
+use crate::tween::TweenCompleted;
+use crate::types::transform::Transform;
+use crate::ui::{update_ui, UiLayout};
+use crate::wind::Wind;
+use crate::{
+    CameraComponent, GlobalTransformComponent, MaterialComponent, MeshComponent,
+    TransformComponent,
+};
 use assets::AssetStore;
-use config::config::{WindowMode, WindowResolution};
+use common::trace;
+use config::config::{
+    DisplaySettings, LatencyMode, ShadowSettings, WindowMode, WindowResolution,
+};
+use ecs::entity::Entity;
+use ecs::event::Events;
+use ecs::resource::Resources;
+use ecs::snapshot::{SnapshotError, SnapshotRegistry};
 use ecs::world::World;
-use input::InputManager;
+use input::{InputBinding, InputManager, KeyCode};
 use material::material_manager::{MaterialHandle, MaterialManager};
+use nalgebra_glm::Vec3;
 use project::Guid;
-use spatial::{ColliderComponent, SpatialWorld};
-use std::collections::HashMap;
-use std::path::PathBuf;
+use spatial::{ColliderComponent, Shape, SpatialWorld};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Entities spawned by [`EngineContext::build_brushes`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct BrushGeometry {
+    /// The merged render mesh, drawn with the material passed in.
+    pub mesh: Entity,
+    /// One box collider per additive brush.
+    pub colliders: Vec<Entity>,
+}
 
 /// Provides simultaneous mutable access to both worlds, avoiding split-borrow issues.
 pub struct WorldSetup<'a> {
@@ -19,13 +67,49 @@ pub struct WorldSetup<'a> {
 
 pub struct EngineConfig {
     pub name: String,
+    pub window_title: String,
     pub content_dir: PathBuf,
     pub cache_dir: PathBuf,
     pub window_resolution: WindowResolution,
     pub window_mode: WindowMode,
+    /// Monitor and video mode of the fullscreen modes. Change it through
+    /// [`Displays::request`](crate::display::Displays::request).
+    pub display: DisplaySettings,
+    pub vsync: bool,
+    /// Whether each frame waits for the previous one to be presented. Read every frame.
+    pub latency_mode: LatencyMode,
+    /// Frames the CPU may record ahead of the GPU, from 1 to 3. Read once at startup.
+    pub max_frames_in_flight: u32,
+    /// Frame rate cap while focused. 0 disables the cap.
+    pub target_fps: u32,
+    /// Frame rate cap while the window is unfocused. 0 disables the cap.
+    pub unfocused_fps_cap: u32,
+    /// Longest frame delta handed to systems, in seconds. 0 disables clamping.
+    pub max_frame_delta: f32,
+    /// Exponential smoothing applied to frame deltas, in `0.0..1.0`. 0 disables it.
+    pub delta_smoothing: f32,
+    /// Use a dedicated compute queue when available. Read once at startup.
+    pub async_compute: bool,
+    /// Enable GPU crash breadcrumbs. Read once at startup.
+    pub gpu_diagnostics: bool,
+    /// Render the entity ID buffer used by `RenderSettings::request_pick`. Read once at
+    /// startup.
+    pub gpu_picking: bool,
+    /// Simulate particles on the GPU when supported. Read once at startup.
+    pub gpu_particles: bool,
+    /// VRAM budget of streamed texture mips in MiB; 0 disables streaming. Read once at
+    /// startup.
+    pub texture_budget_mb: u32,
+    /// Step length of fixed-update systems, in seconds.
+    pub fixed_timestep: f32,
+    /// Live shadow quality settings. The renderer picks up changes on the next frame.
+    pub shadow_settings: ShadowSettings,
+    /// Unloading of idle assets. Read every frame; disabled by default.
+    pub asset_gc: AssetGcSettings,
 }
 
-/// Central engine context. Owns engine config, asset context, ECS world, spatial world, input, and materials.
+/// Central engine context. Owns engine config, asset context, ECS world, spatial world, input,
+/// materials, and user resources.
 pub struct EngineContext {
     pub config: EngineConfig,
     assets: AssetContext,
@@ -33,20 +117,93 @@ pub struct EngineContext {
     input_manager: InputManager,
     world: World,
     spatial_world: SpatialWorld,
-    systems: Vec<Box<dyn SystemFunction>>,
+    resources: Resources,
+    systems: Vec<(SystemId, Box<dyn SystemFunction>)>,
+    state_systems: Vec<Box<dyn SystemFunction>>,
+    fixed_systems: Vec<(SystemId, Box<dyn SystemFunction>)>,
+    fixed_accumulator: f32,
+    streamer: Option<WorldStreamer>,
+    snapshot_registry: SnapshotRegistry,
+    /// Publishes each registered `Events<T>` resource at the start of a frame.
+    event_updaters: Vec<fn(&Resources)>,
+    trigger_tracker: TriggerTracker,
+    preloads: Vec<Preload>,
+    asset_gc: AssetGc,
 }
 
+/// Upper bound on fixed steps per frame. After a long stall the remaining backlog is
+/// dropped instead of running hundreds of catch-up steps.
+const MAX_FIXED_STEPS_PER_FRAME: u32 = 8;
+
 impl EngineContext {
     pub fn new(config: EngineConfig, assets: AssetContext) -> EngineContext {
-        Self {
+        let mut resources = Resources::new();
+        resources.insert(Time::new(config.fixed_timestep));
+        resources.insert(AppExit::default());
+        resources.insert(RenderSettings::default());
+        resources.insert(SaveGame::default());
+        resources.insert(UiLayout::default());
+        resources.insert(Displays::default());
+        resources.insert(FrameBudgets::default());
+        resources.insert(Draw2D::default());
+        resources.insert(WorldEnvironment::default());
+        resources.insert(PostProcessSettings::default());
+        resources.insert(Wind::default());
+        resources.insert(Localization::default());
+        resources.insert(BehaviorTasks::default());
+        resources.insert(EntityIds::default());
+        resources.insert(SystemToggles::default());
+
+        let mut snapshot_registry = SnapshotRegistry::new();
+        register_engine_components(&mut snapshot_registry);
+
+        let mut input_manager = InputManager::new();
+        input_manager.bind_action(CAPTURE_FRAME_ACTION, vec![InputBinding::Key(KeyCode::F10)]);
+        input_manager.bind_action(DUMP_FRAME_ACTION, vec![InputBinding::Key(KeyCode::F8)]);
+        input_manager.bind_action(DUMP_GPU_MEMORY_ACTION, vec![InputBinding::Key(KeyCode::F9)]);
+
+        let mut context = Self {
             config,
             assets,
             material_manager: MaterialManager::new(),
-            input_manager: InputManager::new(),
+            input_manager,
             world: World::new(),
             spatial_world: SpatialWorld::new(),
+            resources,
             systems: Vec::new(),
+            state_systems: Vec::new(),
+            fixed_systems: Vec::new(),
+            fixed_accumulator: 0.0,
+            streamer: None,
+            snapshot_registry,
+            event_updaters: Vec::new(),
+            trigger_tracker: TriggerTracker::default(),
+            preloads: Vec::new(),
+            asset_gc: AssetGc::default(),
+        };
+        context.add_event::<TriggerEvent>();
+        context.add_event::<BudgetExceeded>();
+        context.add_event::<TweenCompleted<f32>>();
+        context.add_event::<TweenCompleted<Vec3>>();
+        context.add_event::<TweenCompleted<Transform>>();
+        for system in Self::builtin_systems() {
+            context.register_system(system);
         }
+        context
+    }
+
+    /// Engine systems that run before any user-registered system each frame.
+    fn builtin_systems() -> Vec<Box<dyn SystemFunction>> {
+        vec![
+            Box::new(System::new(tween_system::<f32>)),
+            Box::new(System::new(tween_system::<Vec3>)),
+            Box::new(System::new(tween_system::<Transform>)),
+            Box::new(System::new(tween_transform_system)),
+            Box::new(System::new(localized_text_system)),
+            Box::new(System::new(behavior_tree_system)),
+            Box::new(System::new(day_night_cycle_system)),
+            Box::new(System::new(trail_system)),
+        ]
     }
 
     // ── Asset loading ──────────────────────────────────────────────────────
@@ -60,6 +217,111 @@ impl EngineContext {
         self.material_manager.get_or_insert(guid, || assets.build_material(guid))
     }
 
+    /// Loads a `.strings` table and adds it to the [`Localization`] resource.
+    pub fn load_string_table(&mut self, guid: Guid) {
+        let table = self.assets.load_string_table(guid);
+        self.resources.get_mut::<Localization>().add_table(table);
+    }
+
+    /// Loads a `.btree` asset, returning the cached tree if already loaded.
+    pub fn load_behavior_tree(&mut self, guid: Guid) -> Arc<BehaviorTree> {
+        self.assets.load_behavior_tree(guid)
+    }
+
+    /// Sweeps a profile along `spline` and adds the result to the asset store as a new
+    /// mesh, for roads, pipes and rivers built while a level loads. The mesh is in the
+    /// spline's local space; spawn it with the same transform. It has no source file, so
+    /// save games cannot load it back, and the asset GC releases it like any other mesh
+    /// once no `MeshComponent` uses it. Build it again in either case.
+    pub fn create_spline_mesh(
+        &mut self,
+        spline: &SplineComponent,
+        settings: &SplineMeshSettings,
+    ) -> common::MeshHandle {
+        let mesh = spline::extrude(spline, settings);
+        self.assets.asset_store.insert_mesh(Guid::generate(), mesh)
+    }
+
+    /// Merges every [`BrushComponent`] in the world into one mesh drawn with `material`,
+    /// and spawns a collider per additive brush. Call it once the scene's brushes are
+    /// spawned. The brush entities are left as they are; to rebuild after moving them,
+    /// remove the returned entities and call it again. See [`csg`] for how brushes
+    /// combine.
+    pub fn build_brushes(&mut self, material: MaterialHandle) -> BrushGeometry {
+        let mut brushes = {
+            let mut query = self
+                .world
+                .query::<(Entity, &mut TransformComponent, &mut BrushComponent)>();
+            query
+                .iter()
+                .map(|(entity, transform, brush)| {
+                    (entity, transform.get_model_matrix(), brush.clone())
+                })
+                .collect::<Vec<_>>()
+        };
+        brushes.sort_by_key(|(entity, _, brush)| (brush.order, entity.index()));
+        let brushes = brushes
+            .into_iter()
+            .map(|(_, model, brush)| (model, brush))
+            .collect::<Vec<_>>();
+
+        let mesh_handle = self
+            .assets
+            .asset_store
+            .insert_mesh(Guid::generate(), csg::build_mesh(&brushes));
+        let mesh = self.world.create_entity((
+            TransformComponent::default(),
+            GlobalTransformComponent::default(),
+            MeshComponent::new(mesh_handle),
+            MaterialComponent::new(material),
+        ));
+        let colliders = csg::collider_bounds(&brushes)
+            .into_iter()
+            .map(|bounds| {
+                let half_extents = (bounds.upper - bounds.lower) * 0.5;
+                let center = bounds.lower + half_extents;
+                let id = self
+                    .spatial_world
+                    .register_collider(Shape::Cuboid { half_extents });
+                self.world.create_entity((
+                    TransformComponent(Transform::default().with_location(center)),
+                    ColliderComponent { id },
+                ))
+            })
+            .collect();
+        BrushGeometry { mesh, colliders }
+    }
+
+    /// Spawns an entity at `location` with a collider of `shape` registered in the spatial
+    /// world. Queries and triggers see it once the spatial tree is synced at the end of
+    /// the next update.
+    pub fn spawn_collider(&mut self, location: Vec3, shape: Shape) -> Entity {
+        let id = self.spatial_world.register_collider(shape);
+        self.world.create_entity((
+            TransformComponent(Transform::default().with_location(location)),
+            ColliderComponent { id },
+        ))
+    }
+
+    /// Starts loading the asset at `source_path` (relative to the content directory),
+    /// usually a `.scene`, and everything it depends on. Loading continues across frames;
+    /// read [`Self::preload_progress`] to drive a loading screen.
+    pub fn preload(&mut self, source_path: impl AsRef<Path>) -> Result<PreloadId, PreloadError> {
+        let path = source_path.as_ref();
+        let root = self
+            .assets
+            .registry()
+            .find_by_source_path(path)
+            .ok_or_else(|| PreloadError::UnknownAsset(path.to_path_buf()))?
+            .guid;
+        self.preloads.push(Preload::start(&self.assets, &[root]));
+        Ok(PreloadId(self.preloads.len() - 1))
+    }
+
+    pub fn preload_progress(&self, id: PreloadId) -> PreloadProgress {
+        self.preloads[id.0].progress()
+    }
+
     // ── Renderer-facing accessors ──────────────────────────────────────────
 
     pub fn shader_cache_dir(&self) -> PathBuf {
@@ -78,8 +340,15 @@ impl EngineContext {
         &mut self.material_manager
     }
 
-    pub fn render_resources_mut(&mut self) -> (&AssetStore, &mut MaterialManager) {
-        (&self.assets.asset_store, &mut self.material_manager)
+    /// Assets unloaded by the asset GC since the last call. Hand them to the renderer
+    /// before drawing so it frees their GPU resources.
+    pub fn take_released_assets(&mut self) -> Vec<AssetId> {
+        self.asset_gc.take_released()
+    }
+
+    /// Everything the renderer reads each frame, borrowed at once.
+    pub fn render_resources_mut(&mut self) -> (&AssetStore, &mut MaterialManager, &Resources) {
+        (&self.assets.asset_store, &mut self.material_manager, &self.resources)
     }
 
     pub fn input(&self) -> &InputManager {
@@ -90,6 +359,47 @@ impl EngineContext {
         &mut self.input_manager
     }
 
+    // ── Resources ──────────────────────────────────────────────────────────
+
+    /// Registers a manager or game-wide value that systems read with `Context::res`.
+    pub fn insert_resource<T: 'static>(&mut self, value: T) {
+        self.resources.insert(value);
+    }
+
+    /// Registers a `Send + Sync` resource that can also be handed to worker threads
+    /// through `Resources::shared_handle`.
+    pub fn insert_shared_resource<T: Send + Sync + 'static>(&mut self, value: T) {
+        self.resources.insert_shared(value);
+    }
+
+    /// Registers an `Events<T>` resource and publishes it every frame. Events sent
+    /// during one frame are readable by all systems during the next.
+    pub fn add_event<T: 'static>(&mut self) {
+        if self.resources.contains::<Events<T>>() {
+            return;
+        }
+        self.resources.insert(Events::<T>::new());
+        self.event_updaters
+            .push(|resources| resources.get_mut::<Events<T>>().update());
+    }
+
+    /// Asks the app to close after the current frame.
+    pub fn request_exit(&mut self) {
+        self.resources.get_mut::<AppExit>().request();
+    }
+
+    pub fn exit_requested(&self) -> bool {
+        self.resources.get::<AppExit>().is_requested()
+    }
+
+    pub fn resources(&self) -> &Resources {
+        &self.resources
+    }
+
+    pub fn resources_mut(&mut self) -> &mut Resources {
+        &mut self.resources
+    }
+
     // ── World access ───────────────────────────────────────────────────────
 
     pub fn world_setup(&mut self) -> WorldSetup<'_> {
@@ -111,35 +421,251 @@ impl EngineContext {
         &mut self.spatial_world
     }
 
+    // ── Save games ─────────────────────────────────────────────────────────
+
+    /// Components included in snapshots. Engine components are registered already; add
+    /// game components here before saving.
+    pub fn snapshot_registry_mut(&mut self) -> &mut SnapshotRegistry {
+        &mut self.snapshot_registry
+    }
+
+    /// Encodes the world's registered components and the [`WorldEnvironment`], with asset
+    /// handles stored as GUIDs. Entities spawned by the world streamer are left out; they
+    /// are streamed back in.
+    pub fn save_snapshot(&mut self) -> Result<Vec<u8>, SnapshotError> {
+        let streamed = self
+            .streamer
+            .as_ref()
+            .map(|streamer| streamer.streamed_entities().collect::<HashSet<_>>())
+            .unwrap_or_default();
+        let mut remap = AssetRemap {
+            assets: &mut self.assets,
+            materials: &mut self.material_manager,
+            spatial: &mut self.spatial_world,
+        };
+        let world =
+            self.world
+                .save_snapshot_filtered(&self.snapshot_registry, &mut remap, |entity| {
+                    !streamed.contains(&entity)
+                })?;
+        let environment = self.resources.get::<WorldEnvironment>().save(&mut remap)?;
+        SceneSnapshot { world, environment }.encode()
+    }
+
+    /// Replaces the world's entities and environment with a snapshot, loading referenced
+    /// assets by GUID. Streamed cells are unloaded first and stream back in on the next
+    /// update. `EntityRef`s saved in the snapshot resolve straight away.
+    pub fn load_snapshot(&mut self, bytes: &[u8]) -> Result<Vec<Entity>, SnapshotError> {
+        let snapshot = SceneSnapshot::decode(bytes)?;
+        if let Some(streamer) = self.streamer.as_mut() {
+            streamer.unload_all(&mut CellContext {
+                world: &mut self.world,
+                spatial: &mut self.spatial_world,
+                assets: &mut self.assets,
+                material_manager: &mut self.material_manager,
+            });
+        }
+
+        let mut remap = AssetRemap {
+            assets: &mut self.assets,
+            materials: &mut self.material_manager,
+            spatial: &mut self.spatial_world,
+        };
+        let environment = WorldEnvironment::load(snapshot.environment, &mut remap)?;
+        let entities =
+            self.world
+                .load_snapshot(&self.snapshot_registry, &mut remap, &snapshot.world)?;
+        self.resources.insert(environment);
+        self.resources.get_mut::<EntityIds>().rebuild(&self.world);
+        // Loaded entities reuse ids; overlaps already inside a trigger enter it again.
+        self.trigger_tracker = TriggerTracker::default();
+        Ok(entities)
+    }
+
+    fn handle_save_requests(&mut self) {
+        let (save, load) = self.resources.get_mut::<SaveGame>().take_requests();
+        if save {
+            let result = self.save_snapshot().map(Some);
+            self.resources.get_mut::<SaveGame>().store_result(result);
+        }
+        if load {
+            let quicksave = self.resources.get::<SaveGame>().quicksave().map(<[u8]>::to_vec);
+            if let Some(bytes) = quicksave {
+                let result = self.load_snapshot(&bytes).map(|_| None);
+                self.resources.get_mut::<SaveGame>().store_result(result);
+            }
+        }
+    }
+
     // ── Frame update ───────────────────────────────────────────────────────
 
     pub fn update(&mut self, delta_time: f32) {
+        let fixed_delta = self.config.fixed_timestep;
+        {
+            let mut time = self.resources.get_mut::<Time>();
+            time.delta = delta_time;
+            time.elapsed += delta_time as f64;
+            time.frame += 1;
+            time.fixed_delta = fixed_delta;
+        }
+        for update_events in &self.event_updaters {
+            update_events(&self.resources);
+        }
+        for preload in &mut self.preloads {
+            preload.poll(&mut self.assets, &mut self.material_manager);
+        }
+        self.resources.get_mut::<Draw2D>().clear();
+        update_ui(
+            &self.world,
+            &mut self.resources.get_mut::<UiLayout>(),
+            &mut self.input_manager,
+        );
+
+        let fixed_systems = std::mem::take(&mut self.fixed_systems);
+        if !fixed_systems.is_empty() && fixed_delta > 0.0 {
+            self.fixed_accumulator += delta_time;
+            let mut steps = 0;
+            while self.fixed_accumulator >= fixed_delta {
+                if steps == MAX_FIXED_STEPS_PER_FRAME {
+                    self.fixed_accumulator = 0.0;
+                    break;
+                }
+                let scheduled = fixed_systems.iter().map(|(id, system)| (Some(*id), system));
+                self.run_systems(scheduled, fixed_delta);
+                self.fixed_accumulator -= fixed_delta;
+                steps += 1;
+            }
+            self.resources.get_mut::<Time>().fixed_alpha = self.fixed_accumulator / fixed_delta;
+        }
+        self.fixed_systems = fixed_systems;
+
         let systems = std::mem::take(&mut self.systems);
-        let custom = HashMap::new();
+        let state_systems = std::mem::take(&mut self.state_systems);
+        let scheduled = systems
+            .iter()
+            .map(|(id, system)| (Some(*id), system))
+            .chain(state_systems.iter().map(|system| (None, system)));
+        self.run_systems(scheduled, delta_time);
+        self.systems = systems;
+        self.state_systems = state_systems;
+
+        self.handle_save_requests();
+        self.update_streaming();
+        self.sync_spatial();
+        self.resources.get_mut::<EntityIds>().rebuild(&self.world);
+        self.trigger_tracker.update(
+            &self.world,
+            &self.spatial_world,
+            &mut self.resources.get_mut::<Events<TriggerEvent>>(),
+        );
+        let now = self.resources.get::<Time>().elapsed;
+        self.asset_gc.collect(
+            &self.config.asset_gc,
+            now,
+            &self.world,
+            &self.resources.get::<WorldEnvironment>(),
+            &mut self.assets.asset_store,
+            &mut self.material_manager,
+        );
+    }
 
+    fn run_systems<'s>(
+        &mut self,
+        systems: impl Iterator<Item = (Option<SystemId>, &'s Box<dyn SystemFunction>)>,
+        delta_time: f32,
+    ) {
         let queue = {
             let mut access = self.world.system_access();
-            for system in &systems {
+            for (id, system) in systems {
+                if id.is_some_and(|id| !self.resources.get::<SystemToggles>().is_enabled(id)) {
+                    continue;
+                }
                 let mut ctx = Context {
                     dt: delta_time,
                     assets: &mut self.assets,
                     material_manager: &mut self.material_manager,
                     input: &self.input_manager,
-                    custom: &custom,
+                    resources: &self.resources,
+                    spatial: &self.spatial_world,
                 };
-                system.run(&mut access.archetypes, &mut ctx, &mut access.commands);
+                if !system.should_run(&ctx) {
+                    continue;
+                }
+                let _span = trace::span("system", system.name());
+                system.run(
+                    access.archetypes,
+                    access.sparse_sets,
+                    &mut ctx,
+                    &mut access.commands,
+                );
             }
             access.into_queue()
         };
 
         self.world.flush_queue(queue);
+    }
 
-        self.systems = systems;
-        self.sync_spatial();
+    /// Registers a system that runs every frame, after the engine's own systems. The
+    /// returned id switches it on and off through [`SystemToggles`].
+    pub fn register_system(&mut self, system: Box<dyn SystemFunction>) -> SystemId {
+        let id = self.resources.get_mut::<SystemToggles>().register(system.name());
+        self.systems.push((id, system));
+        id
+    }
+
+    /// Registers a system that runs at the fixed rate set by `EngineConfig::fixed_timestep`,
+    /// zero or more times per frame, before the per-frame systems. `Context::dt` is the
+    /// fixed step length. The returned id switches it on and off like
+    /// [`Self::register_system`]'s.
+    pub fn register_fixed_system(&mut self, system: Box<dyn SystemFunction>) -> SystemId {
+        let id = self.resources.get_mut::<SystemToggles>().register(system.name());
+        self.fixed_systems.push((id, system));
+        id
+    }
+
+    /// Replaces the systems owned by the active game state, returning the previous set.
+    /// State systems run after all globally registered systems.
+    pub fn replace_state_systems(
+        &mut self,
+        systems: Vec<Box<dyn SystemFunction>>,
+    ) -> Vec<Box<dyn SystemFunction>> {
+        std::mem::replace(&mut self.state_systems, systems)
+    }
+
+    /// Installs a world streamer. Cells are loaded around the active camera each frame.
+    pub fn set_world_streamer(&mut self, streamer: WorldStreamer) {
+        self.streamer = Some(streamer);
+    }
+
+    pub fn world_streamer_mut(&mut self) -> Option<&mut WorldStreamer> {
+        self.streamer.as_mut()
     }
 
-    pub fn register_system(&mut self, system: Box<dyn SystemFunction>) {
-        self.systems.push(system);
+    fn update_streaming(&mut self) {
+        let Some(streamer) = self.streamer.as_mut() else {
+            return;
+        };
+
+        let focus = {
+            let mut query = self
+                .world
+                .query::<(&mut TransformComponent, &mut CameraComponent)>();
+            query
+                .iter()
+                .find(|(_, camera)| camera.active)
+                .map(|(transform, _)| transform.location)
+        };
+        let Some(focus) = focus else {
+            return;
+        };
+
+        let mut ctx = CellContext {
+            world: &mut self.world,
+            spatial: &mut self.spatial_world,
+            assets: &mut self.assets,
+            material_manager: &mut self.material_manager,
+        };
+        streamer.update(focus, &mut ctx);
     }
 
     fn sync_spatial(&mut self) {
@@ -0,0 +1,195 @@
+//! Persistent entity ids for references that survive saving and loading.
+//!
+//! `Entity` values are reassigned whenever a snapshot is loaded, so a saved component
+//! must not hold one, unless its `Persist` impl remaps it within the snapshot. For
+//! references that also hold across separate saves and runs, give the target entity a
+//! [`PersistentId`], store an [`EntityRef`] to it, and resolve the reference through the
+//! [`EntityIds`] resource:
+//!
+//! ```ignore
+//! if let Some(target) = follow.target.resolve(&ctx.res::<EntityIds>()) { /* ... */ }
+//! ```
+//!
+//! The engine refreshes `EntityIds` at the end of every update and after loading a
+//! snapshot, so entities spawned this frame resolve from the next one. The refresh only
+//! rescans the world when a `PersistentId` was added or removed since the last one; an
+//! id changed in place through a query isn't picked up until then.
+//!
+//! [`PersistentId::generate`] gives a different id on every call and every run, so an
+//! entity spawned from code at startup gets a new one each time. Use
+//! [`PersistentId::derive`] when the same entity must get the same id on every run.
+
+use common::Guid;
+use ecs::component::Component;
+use ecs::entity::Entity;
+use ecs::world::World;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Id of an entity that stays the same across saves, loads and runs. Saved with the
+/// entity under `core.persistent_id`.
+#[derive(Clone, Copy, Debug, Component, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct PersistentId(pub Guid);
+
+impl PersistentId {
+    /// A new random id, different on every call and every run.
+    pub fn generate() -> Self {
+        Self(Guid::generate())
+    }
+
+    /// The id for `key` within `scope`, the same on every run. `scope` is typically the
+    /// GUID of the level or prefab spawning the entity, and `key` its name there.
+    pub fn derive(scope: Guid, key: &str) -> Self {
+        // FNV-1a, 128-bit.
+        const OFFSET: u128 = 0x6c62272e07bb014262b821756295c58d;
+        const PRIME: u128 = 0x0000000001000000000000000000013b;
+        let bytes = scope.as_u128().to_le_bytes();
+        let hash = bytes
+            .iter()
+            .chain(key.as_bytes())
+            .fold(OFFSET, |hash, &byte| (hash ^ byte as u128).wrapping_mul(PRIME));
+        Self(Guid::from_u128(hash))
+    }
+
+    /// A reference to the entity carrying this id.
+    pub fn to_ref(self) -> EntityRef {
+        EntityRef(self.0)
+    }
+}
+
+/// Serializable reference to the entity whose [`PersistentId`] holds this GUID.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct EntityRef(pub Guid);
+
+impl EntityRef {
+    /// The referenced entity, or `None` if no live entity carries the id.
+    pub fn resolve(&self, ids: &EntityIds) -> Option<Entity> {
+        ids.get(self.0)
+    }
+}
+
+impl From<PersistentId> for EntityRef {
+    fn from(id: PersistentId) -> Self {
+        id.to_ref()
+    }
+}
+
+/// Resource mapping persistent ids to the entities currently carrying them.
+#[derive(Debug, Default)]
+pub struct EntityIds {
+    entities: HashMap<Guid, Entity>,
+    /// `World::component_changes::<PersistentId>()` at the last rescan.
+    indexed: Option<u64>,
+}
+
+impl EntityIds {
+    pub fn get(&self, id: Guid) -> Option<Entity> {
+        self.entities.get(&id).copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entities.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entities.is_empty()
+    }
+
+    /// Re-indexes every `PersistentId` in `world` if one was added or removed since the
+    /// last call. If several entities share an id, the first in storage order wins.
+    pub(crate) fn rebuild(&mut self, world: &World) {
+        let changes = world.component_changes::<PersistentId>();
+        if self.indexed == Some(changes) {
+            return;
+        }
+        self.indexed = Some(changes);
+        self.entities.clear();
+        world.for_each_component::<PersistentId>(|entity, id| {
+            self.entities.entry(id.0).or_insert(entity);
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ecs::snapshot::{NoRemap, SnapshotRegistry};
+
+    #[derive(Component, Serialize, Deserialize)]
+    struct Follow {
+        target: EntityRef,
+    }
+
+    #[test]
+    fn references_resolve_after_a_snapshot_round_trip() {
+        let mut registry = SnapshotRegistry::new();
+        registry.register::<PersistentId>("core.persistent_id");
+        registry.register::<Follow>("follow");
+
+        let leader = PersistentId::generate();
+        let mut world = World::new();
+        world.create_entity((Follow {
+            target: leader.to_ref(),
+        },));
+        world.create_entity((leader,));
+        let bytes = world.save_snapshot(&registry, &mut NoRemap).unwrap();
+
+        let mut loaded = World::new();
+        let entities = loaded
+            .load_snapshot(&registry, &mut NoRemap, &bytes)
+            .unwrap();
+        let mut ids = EntityIds::default();
+        ids.rebuild(&loaded);
+
+        let mut query = loaded.query::<&mut Follow>();
+        let target = query.iter().next().unwrap().target;
+        assert_eq!(target.resolve(&ids), Some(entities[1]));
+        assert_eq!(ids.len(), 1);
+    }
+
+    #[test]
+    fn derived_ids_depend_only_on_scope_and_key() {
+        let level = Guid::from_u128(7);
+        assert_eq!(
+            PersistentId::derive(level, "door"),
+            PersistentId::derive(level, "door")
+        );
+        assert_ne!(
+            PersistentId::derive(level, "door"),
+            PersistentId::derive(level, "gate")
+        );
+        assert_ne!(
+            PersistentId::derive(level, "door"),
+            PersistentId::derive(Guid::from_u128(8), "door")
+        );
+    }
+
+    #[test]
+    fn rebuild_rescans_only_when_ids_are_added_or_removed() {
+        let mut world = World::new();
+        let mut ids = EntityIds::default();
+        let first = PersistentId::generate();
+        let entity = world.create_entity((first,));
+        ids.rebuild(&world);
+        assert_eq!(ids.get(first.0), Some(entity));
+
+        // Not added or removed, so the stale entry survives the next rebuild.
+        let moved = PersistentId::generate();
+        for id in world.query::<&mut PersistentId>().iter() {
+            *id = moved;
+        }
+        ids.rebuild(&world);
+        assert_eq!(ids.get(first.0), Some(entity));
+
+        let second = PersistentId::generate();
+        let other = world.create_entity((second,));
+        ids.rebuild(&world);
+        assert_eq!(ids.get(moved.0), Some(entity));
+        assert_eq!(ids.get(second.0), Some(other));
+
+        world.remove_entity(entity);
+        ids.rebuild(&world);
+        assert_eq!(ids.get(moved.0), None);
+        assert_eq!(ids.len(), 1);
+    }
+}
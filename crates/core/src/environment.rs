@@ -0,0 +1,141 @@
+//! Scene-wide rendering settings that belong to no entity: the background, ambient light
+//! and fog. The renderer reads [`WorldEnvironment`] every frame, and save games store it
+//! next to the world's entities.
+
+use common::{Color, ImageData, ImageHandle};
+use ecs::snapshot::{HandleRemap, SnapshotError};
+use serde::{Deserialize, Serialize};
+
+/// How fog thickens with the distance from the camera.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum FogMode {
+    #[default]
+    Off,
+    /// Clear up to `start` world units, opaque from `end` on.
+    Linear { start: f32, end: f32 },
+    /// Visibility falls off as `exp(-density * distance)`.
+    Exponential,
+    /// Visibility falls off as `exp(-(density * distance)^2)`: a clearer near field
+    /// with a sharper falloff.
+    ExponentialSquared,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Fog {
+    pub mode: FogMode,
+    pub color: Color,
+    /// Used by the exponential modes.
+    pub density: f32,
+}
+
+impl Default for Fog {
+    fn default() -> Self {
+        Self {
+            mode: FogMode::Off,
+            color: Color::linear(0.5, 0.6, 0.7),
+            density: 0.01,
+        }
+    }
+}
+
+/// Analytic daylight sky (Preetham) lit by the scene's directional light, drawn behind all
+/// geometry in place of a skybox panorama. It does not light the scene; pair it with a
+/// matching ambient color, e.g. from a [`DayNightCycle`](crate::day_night::DayNightCycle).
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ProceduralSky {
+    /// Haze in the air: around 2 for a clear sky, up to 10 for a hazy one.
+    pub turbidity: f32,
+    /// Scales the model's luminance, in kcd/m², to the scene's light units.
+    pub exposure: f32,
+    /// Radiance of the sun disk relative to the directional light's intensity.
+    pub sun_disk_intensity: f32,
+}
+
+impl Default for ProceduralSky {
+    fn default() -> Self {
+        Self {
+            turbidity: 2.5,
+            exposure: 0.1,
+            sun_disk_intensity: 20.0,
+        }
+    }
+}
+
+/// Background, ambient light and fog of the current scene.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WorldEnvironment {
+    /// Shown where no geometry is drawn and there is no skybox.
+    pub clear_color: Color,
+    /// Scales the ambient light: a constant term without a skybox, the light reflected
+    /// from the skybox with one.
+    pub ambient_color: Color,
+    pub ambient_intensity: f32,
+    pub fog: Fog,
+    /// Equirectangular panorama drawn behind all geometry. It also lights the scene: the
+    /// renderer prefilters it for diffuse ambient light and reflections when it changes.
+    pub skybox: Option<ImageHandle>,
+    /// Sky drawn when there is no skybox.
+    pub procedural_sky: Option<ProceduralSky>,
+}
+
+impl Default for WorldEnvironment {
+    fn default() -> Self {
+        Self {
+            clear_color: Color::BLACK,
+            ambient_color: Color::WHITE,
+            ambient_intensity: 0.1,
+            fog: Fog::default(),
+            skybox: None,
+            procedural_sky: None,
+        }
+    }
+}
+
+/// [`WorldEnvironment`] as stored in a save, with the skybox as an asset GUID.
+#[derive(Serialize, Deserialize)]
+pub(crate) struct SavedEnvironment {
+    clear_color: Color,
+    ambient_color: Color,
+    ambient_intensity: f32,
+    fog: Fog,
+    skybox: Option<u128>,
+    procedural_sky: Option<ProceduralSky>,
+}
+
+impl WorldEnvironment {
+    pub(crate) fn save(
+        &self,
+        remap: &mut dyn HandleRemap,
+    ) -> Result<SavedEnvironment, SnapshotError> {
+        let skybox = self
+            .skybox
+            .map(|skybox| remap.save_handle::<ImageData>(skybox.raw()))
+            .transpose()?;
+        Ok(SavedEnvironment {
+            clear_color: self.clear_color,
+            ambient_color: self.ambient_color,
+            ambient_intensity: self.ambient_intensity,
+            fog: self.fog,
+            skybox,
+            procedural_sky: self.procedural_sky,
+        })
+    }
+
+    pub(crate) fn load(
+        saved: SavedEnvironment,
+        remap: &mut dyn HandleRemap,
+    ) -> Result<Self, SnapshotError> {
+        let skybox = saved
+            .skybox
+            .map(|skybox| remap.load_handle::<ImageData>(skybox))
+            .transpose()?;
+        Ok(Self {
+            clear_color: saved.clear_color,
+            ambient_color: saved.ambient_color,
+            ambient_intensity: saved.ambient_intensity,
+            fog: saved.fog,
+            skybox: skybox.map(ImageHandle::new),
+            procedural_sky: saved.procedural_sky,
+        })
+    }
+}
@@ -0,0 +1,209 @@
+//! Frame budget alerts for catching performance regressions during development.
+//!
+//! Give the [`FrameBudgets`] resource a budget for any stage of the frame. The engine
+//! times every stage each frame and keeps rolling averages. When a stage runs over its
+//! budget for `consecutive_frames` frames in a row, the engine prints a warning and sends
+//! a [`BudgetExceeded`] event, readable through `Events<BudgetExceeded>`. The alert is
+//! sent once; it re-arms after the stage comes back within budget.
+//!
+//! ```ignore
+//! ctx.res_mut::<FrameBudgets>().set_budget(FrameStage::Gpu, Some(Duration::from_millis(12)));
+//! ```
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::time::Duration;
+
+/// Frames averaged by [`FrameBudgets::average`].
+const AVERAGE_FRAMES: usize = 60;
+
+/// A coarse stage of the engine frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FrameStage {
+    /// Game states, systems and fixed updates.
+    Simulation,
+    /// Gathering render data from the world.
+    Extraction,
+    /// Recording and submitting the frame's GPU commands on the CPU.
+    Record,
+    /// GPU time spanned by the frame's passes. Reported a frame late, once the GPU has
+    /// finished.
+    Gpu,
+}
+
+impl FrameStage {
+    pub const ALL: [FrameStage; 4] = [
+        FrameStage::Simulation,
+        FrameStage::Extraction,
+        FrameStage::Record,
+        FrameStage::Gpu,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            FrameStage::Simulation => "simulation",
+            FrameStage::Extraction => "extraction",
+            FrameStage::Record => "record",
+            FrameStage::Gpu => "GPU",
+        }
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// A stage ran over its budget for `frames` consecutive frames.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BudgetExceeded {
+    pub stage: FrameStage,
+    pub budget: Duration,
+    /// Rolling average of the stage when the alert fired.
+    pub average: Duration,
+    /// The stage's time in the frame that fired the alert.
+    pub last: Duration,
+    pub frames: u32,
+}
+
+impl fmt::Display for BudgetExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} over its {:.2} ms budget for {} frames (last {:.2} ms, average {:.2} ms)",
+            self.stage.name(),
+            self.budget.as_secs_f64() * 1000.0,
+            self.frames,
+            self.last.as_secs_f64() * 1000.0,
+            self.average.as_secs_f64() * 1000.0
+        )
+    }
+}
+
+#[derive(Debug, Default)]
+struct StageStats {
+    budget: Option<Duration>,
+    samples: VecDeque<Duration>,
+    /// Consecutive frames over budget.
+    over: u32,
+    alerted: bool,
+}
+
+/// Resource holding per-stage frame budgets and their timing history. All budgets are
+/// unset by default, which leaves GPU timing off.
+#[derive(Debug)]
+pub struct FrameBudgets {
+    stages: [StageStats; 4],
+    /// Frames in a row a stage must exceed its budget before an alert.
+    pub consecutive_frames: u32,
+    /// List the stages over budget in the title bar stats while they are.
+    pub show_in_title: bool,
+}
+
+impl Default for FrameBudgets {
+    fn default() -> Self {
+        Self {
+            stages: Default::default(),
+            consecutive_frames: 30,
+            show_in_title: true,
+        }
+    }
+}
+
+impl FrameBudgets {
+    /// Sets or clears the budget of `stage`, resetting its alert.
+    pub fn set_budget(&mut self, stage: FrameStage, budget: Option<Duration>) {
+        let stats = &mut self.stages[stage.index()];
+        stats.budget = budget;
+        stats.over = 0;
+        stats.alerted = false;
+    }
+
+    pub fn budget(&self, stage: FrameStage) -> Option<Duration> {
+        self.stages[stage.index()].budget
+    }
+
+    /// True if any stage has a budget. Stages are only timed while this holds.
+    pub fn is_enabled(&self) -> bool {
+        self.stages.iter().any(|stats| stats.budget.is_some())
+    }
+
+    /// Mean of the stage's last 60 recorded frames.
+    pub fn average(&self, stage: FrameStage) -> Option<Duration> {
+        let samples = &self.stages[stage.index()].samples;
+        if samples.is_empty() {
+            return None;
+        }
+        Some(samples.iter().sum::<Duration>() / samples.len() as u32)
+    }
+
+    /// True while `stage` has been over budget for at least `consecutive_frames` frames.
+    pub fn is_over_budget(&self, stage: FrameStage) -> bool {
+        let stats = &self.stages[stage.index()];
+        stats.budget.is_some() && stats.over >= self.consecutive_frames.max(1)
+    }
+
+    /// Adds one frame's time for `stage`. Returns an alert when the stage has just been
+    /// over budget for `consecutive_frames` frames. Called by the engine every frame.
+    pub fn record(&mut self, stage: FrameStage, time: Duration) -> Option<BudgetExceeded> {
+        let threshold = self.consecutive_frames.max(1);
+        let stats = &mut self.stages[stage.index()];
+        if stats.samples.len() == AVERAGE_FRAMES {
+            stats.samples.pop_front();
+        }
+        stats.samples.push_back(time);
+
+        let budget = stats.budget?;
+        if time <= budget {
+            stats.over = 0;
+            stats.alerted = false;
+            return None;
+        }
+        stats.over = stats.over.saturating_add(1);
+        if stats.over < threshold || stats.alerted {
+            return None;
+        }
+        stats.alerted = true;
+        Some(BudgetExceeded {
+            stage,
+            budget,
+            average: self.average(stage).unwrap_or(time),
+            last: time,
+            frames: threshold,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn alerts_once_after_consecutive_frames_over_budget() {
+        let ms = Duration::from_millis;
+        let mut budgets = FrameBudgets {
+            consecutive_frames: 3,
+            ..Default::default()
+        };
+        budgets.set_budget(FrameStage::Gpu, Some(ms(10)));
+
+        assert_eq!(budgets.record(FrameStage::Gpu, ms(12)), None);
+        assert_eq!(budgets.record(FrameStage::Gpu, ms(12)), None);
+        // A frame within budget restarts the count.
+        assert_eq!(budgets.record(FrameStage::Gpu, ms(8)), None);
+        assert_eq!(budgets.record(FrameStage::Gpu, ms(12)), None);
+        assert_eq!(budgets.record(FrameStage::Gpu, ms(12)), None);
+        let alert = budgets.record(FrameStage::Gpu, ms(14)).unwrap();
+        assert_eq!(alert.frames, 3);
+        assert_eq!(alert.last, ms(14));
+        assert_eq!(alert.average, ms(70) / 6);
+        assert!(budgets.is_over_budget(FrameStage::Gpu));
+
+        assert_eq!(budgets.record(FrameStage::Gpu, ms(14)), None);
+        budgets.record(FrameStage::Gpu, ms(8));
+        assert!(!budgets.is_over_budget(FrameStage::Gpu));
+
+        // Stages without a budget are averaged but never alert.
+        assert_eq!(budgets.record(FrameStage::Record, ms(100)), None);
+        assert_eq!(budgets.average(FrameStage::Record), Some(ms(100)));
+    }
+}
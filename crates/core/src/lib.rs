@@ -1,12 +1,43 @@
+pub mod app_exit;
 pub mod asset_context;
+pub mod asset_gc;
+pub mod behavior_tree;
 pub mod components;
+pub mod csg;
+pub mod curve;
+pub mod day_night;
+pub mod display;
+pub mod draw2d;
 mod engine_context;
+pub mod entity_id;
+pub mod environment;
+pub mod frame_budget;
+pub mod localization;
+pub mod particles;
+pub mod post_process;
+pub mod preload;
+pub mod render_settings;
+pub mod run_condition;
+pub mod save_game;
+pub mod spline;
+pub mod streaming;
 pub mod system;
 pub mod systems;
+pub mod testing;
+pub mod time;
+pub mod trails;
+pub mod trigger;
+pub mod tween;
 pub mod types;
+pub mod ui;
+pub mod wind;
 
 pub use components::{
-    CameraComponent, CameraControllerComponent, DirectionalLightComponent, MaterialComponent,
-    MeshComponent, TransformComponent,
+    AreaLightComponent, BlobShadowComponent, CameraComponent, CameraControllerComponent,
+    DirectionalLightComponent, EditorOnly, GlobalTransformComponent, LightProbeGridComponent,
+    LightmapComponent, MaterialComponent, MaterialOverrideComponent, MeshComponent,
+    OrbitCameraControllerComponent, PointLightComponent, ReflectionProbeComponent, RenderLayers,
+    SkinnedMeshComponent, SpringArmComponent, TransformComponent, VegetationComponent,
+    VisibilityComponent,
 };
 pub use engine_context::*;
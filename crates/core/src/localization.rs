@@ -0,0 +1,293 @@
+//! Per-language string tables and lookups with fallback.
+//!
+//! String tables are `.strings` assets: JSON with the language tag and a flat key/value
+//! map, e.g. `{ "language": "fr", "strings": { "menu.start": "Commencer" } }`. Load them
+//! with [`crate::EngineContext::load_string_table`], then look text up through the
+//! [`Localization`] resource or the [`tr!`](crate::tr) macro. Values may contain `{name}`
+//! placeholders, filled in by [`Localization::format`].
+
+use crate::system::Context;
+use ecs::command_buffer::Commands;
+use ecs::component::Component;
+use ecs::query::Query;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+
+/// Looks up a localized string, formatting any `name = value` arguments into its
+/// `{name}` placeholders. Returns a `String`; missing keys come back as the key itself.
+///
+/// ```ignore
+/// let loc = ctx.res::<Localization>();
+/// let title = tr!(loc, "menu.title");
+/// let greeting = tr!(loc, "hud.greeting", player = name, level = 3);
+/// ```
+#[macro_export]
+macro_rules! tr {
+    ($loc:expr, $key:expr $(, $name:ident = $value:expr)* $(,)?) => {
+        $loc.format(
+            $key,
+            &[$((stringify!($name), &$value as &dyn ::std::fmt::Display)),*],
+        )
+    };
+}
+
+#[derive(Debug)]
+pub enum StringTableError {
+    Io(std::io::Error),
+    Parse(serde_json::Error),
+}
+
+impl fmt::Display for StringTableError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StringTableError::Io(e) => write!(f, "I/O error: {e}"),
+            StringTableError::Parse(e) => write!(f, "invalid string table: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for StringTableError {}
+
+impl From<std::io::Error> for StringTableError {
+    fn from(e: std::io::Error) -> Self {
+        StringTableError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for StringTableError {
+    fn from(e: serde_json::Error) -> Self {
+        StringTableError::Parse(e)
+    }
+}
+
+/// The strings of one language, as stored in a `.strings` file.
+#[derive(Debug, Clone, Deserialize)]
+pub struct StringTable {
+    /// BCP 47 style tag, e.g. `en`, `fr` or `pt-BR`.
+    pub language: String,
+    pub strings: HashMap<String, String>,
+}
+
+impl StringTable {
+    pub fn load(path: &Path) -> Result<Self, StringTableError> {
+        let text = std::fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&text)?)
+    }
+}
+
+/// Resource holding every loaded string table and the active language.
+///
+/// Lookups walk a fallback chain: the active language, its parent tags (`pt-BR` then
+/// `pt`), then the default language. A key missing from all of them resolves to itself
+/// so untranslated text stays visible instead of blank.
+#[derive(Debug)]
+pub struct Localization {
+    tables: HashMap<String, HashMap<String, String>>,
+    default_language: String,
+    language: String,
+    chain: Vec<String>,
+    revision: u64,
+}
+
+impl Default for Localization {
+    fn default() -> Self {
+        Self::new("en")
+    }
+}
+
+impl Localization {
+    /// Creates an empty registry with `default_language` as both the active language
+    /// and the last fallback.
+    pub fn new(default_language: &str) -> Self {
+        let mut localization = Self {
+            tables: HashMap::new(),
+            default_language: default_language.to_owned(),
+            language: default_language.to_owned(),
+            chain: Vec::new(),
+            revision: 0,
+        };
+        localization.rebuild_chain();
+        localization
+    }
+
+    /// Adds a table, merging it over any strings already loaded for its language.
+    pub fn add_table(&mut self, table: StringTable) {
+        self.tables
+            .entry(table.language)
+            .or_default()
+            .extend(table.strings);
+        self.revision += 1;
+    }
+
+    /// Switches the active language. Text resolved through [`LocalizedText`] refreshes
+    /// on the next frame.
+    pub fn set_language(&mut self, language: &str) {
+        if self.language == language {
+            return;
+        }
+        self.language = language.to_owned();
+        self.rebuild_chain();
+        self.revision += 1;
+    }
+
+    pub fn language(&self) -> &str {
+        &self.language
+    }
+
+    /// Languages that have at least one table loaded.
+    pub fn languages(&self) -> impl Iterator<Item = &str> {
+        self.tables.keys().map(String::as_str)
+    }
+
+    /// Languages searched by lookups, most specific first.
+    pub fn fallback_chain(&self) -> &[String] {
+        &self.chain
+    }
+
+    /// Bumped whenever a lookup may resolve differently: on a language switch or when a
+    /// table is added. Compare against a stored value to know when to re-resolve text.
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    /// Looks `key` up along the fallback chain.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.chain
+            .iter()
+            .filter_map(|language| self.tables.get(language))
+            .find_map(|table| table.get(key))
+            .map(String::as_str)
+    }
+
+    /// Looks `key` up along the fallback chain, returning the key itself if missing.
+    pub fn tr<'a>(&'a self, key: &'a str) -> &'a str {
+        self.get(key).unwrap_or(key)
+    }
+
+    /// Looks `key` up and replaces each `{name}` placeholder with its argument. `{{` and
+    /// `}}` produce literal braces; unknown placeholders are left as written.
+    pub fn format(&self, key: &str, args: &[(&str, &dyn fmt::Display)]) -> String {
+        let template = self.tr(key);
+        let mut out = String::with_capacity(template.len());
+        let mut rest = template;
+        while let Some(start) = rest.find(['{', '}']) {
+            out.push_str(&rest[..start]);
+            let tail = &rest[start..];
+            if tail.starts_with("{{") || tail.starts_with("}}") {
+                out.push_str(&tail[..1]);
+                rest = &tail[2..];
+                continue;
+            }
+            let placeholder = tail
+                .strip_prefix('{')
+                .and_then(|t| t.find('}').map(|end| &t[..end]));
+            match placeholder {
+                Some(name) => {
+                    match args.iter().find(|(arg, _)| *arg == name) {
+                        Some((_, value)) => out.push_str(&value.to_string()),
+                        None => out.push_str(&tail[..name.len() + 2]),
+                    }
+                    rest = &tail[name.len() + 2..];
+                }
+                None => {
+                    out.push_str(&tail[..1]);
+                    rest = &tail[1..];
+                }
+            }
+        }
+        out.push_str(rest);
+        out
+    }
+
+    fn rebuild_chain(&mut self) {
+        self.chain.clear();
+        let mut tag = self.language.as_str();
+        loop {
+            self.chain.push(tag.to_owned());
+            match tag.rfind('-') {
+                Some(end) => tag = &tag[..end],
+                None => break,
+            }
+        }
+        if !self.chain.contains(&self.default_language) {
+            self.chain.push(self.default_language.clone());
+        }
+    }
+}
+
+/// Text bound to a string table key. `text` is re-resolved by the engine whenever the
+/// language changes or tables are loaded, so UI and HUD code can read it every frame.
+#[derive(Debug, Clone, Component)]
+pub struct LocalizedText {
+    pub key: String,
+    pub text: String,
+    revision: Option<u64>,
+}
+
+impl LocalizedText {
+    pub fn new(key: impl Into<String>) -> Self {
+        let key = key.into();
+        Self {
+            text: key.clone(),
+            key,
+            revision: None,
+        }
+    }
+}
+
+/// Refreshes [`LocalizedText`] components that were resolved against an older
+/// [`Localization::revision`].
+pub fn localized_text_system(
+    mut query: Query<&mut LocalizedText>,
+    context: &mut Context,
+    _commands: &mut Commands,
+) {
+    let Ok(localization) = context.try_res::<Localization>() else {
+        return;
+    };
+    let revision = localization.revision();
+    for text in query.iter() {
+        if text.revision != Some(revision) {
+            text.text = localization.tr(&text.key).to_owned();
+            text.revision = Some(revision);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table(language: &str, strings: &[(&str, &str)]) -> StringTable {
+        StringTable {
+            language: language.to_owned(),
+            strings: strings
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn lookups_fall_back_through_parent_and_default_languages() {
+        let mut loc = Localization::new("en");
+        loc.add_table(table(
+            "en",
+            &[("quit", "Quit"), ("hello", "Hello, {name}!")],
+        ));
+        loc.add_table(table("pt", &[("hello", "Olá, {name}!")]));
+        loc.add_table(table("pt-BR", &[]));
+
+        let before = loc.revision();
+        loc.set_language("pt-BR");
+        assert!(loc.revision() > before);
+        assert_eq!(loc.fallback_chain(), ["pt-BR", "pt", "en"]);
+
+        assert_eq!(tr!(loc, "hello", name = "Zoë"), "Olá, Zoë!");
+        assert_eq!(loc.tr("quit"), "Quit");
+        assert_eq!(loc.tr("missing.key"), "missing.key");
+        assert_eq!(loc.format("quit", &[]), "Quit");
+    }
+}
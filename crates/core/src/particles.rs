@@ -0,0 +1,77 @@
+//! Particle emitters. The renderer owns the particles themselves: it simulates them in
+//! compute shaders, colliding with the depth buffer, or on the CPU without collision
+//! when the GPU path is unavailable or disabled in the graphics settings.
+
+use common::Color;
+use ecs::component::Component;
+use nalgebra_glm::Vec3;
+use serde::{Deserialize, Serialize};
+
+/// How particles are composited over the scene.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ParticleBlendMode {
+    /// Adds light; suited to sparks, fire and magic. Order independent.
+    #[default]
+    Additive,
+    /// Covers what is behind by the particle's alpha; suited to smoke and dust.
+    /// Particles are not sorted, so overlapping ones may blend in the wrong order.
+    AlphaBlend,
+}
+
+/// Emits camera-facing particles from the entity's location, in a cone around its
+/// forward axis. Particles already alive keep simulating in world space when the emitter
+/// moves or stops emitting, and disappear with the component.
+#[derive(Clone, Debug, Component, PartialEq, Serialize, Deserialize)]
+pub struct ParticleEmitterComponent {
+    /// Whether new particles are spawned.
+    pub emitting: bool,
+    /// Particles alive at once. Emission pauses while the pool is full.
+    pub max_particles: u32,
+    /// Particles spawned per second.
+    pub emission_rate: f32,
+    /// Seconds each particle lives.
+    pub lifetime: f32,
+    /// Initial speed in world units per second.
+    pub speed: f32,
+    /// Half-angle of the emission cone in radians. 0 emits straight ahead, π in all
+    /// directions.
+    pub spread: f32,
+    /// Acceleration in world units per second squared.
+    pub gravity: Vec3,
+    /// Fraction of velocity lost per second.
+    pub drag: f32,
+    /// World-space width at birth.
+    pub start_size: f32,
+    /// World-space width at death; the size is interpolated over the lifetime.
+    pub end_size: f32,
+    pub start_color: Color,
+    /// Color at death, alpha included, so particles can fade out.
+    pub end_color: Color,
+    /// Bounce off the scene's depth buffer. Only on the GPU path.
+    pub collide: bool,
+    /// Fraction of the velocity into a surface that is kept when bouncing off it.
+    pub bounce: f32,
+    pub blend: ParticleBlendMode,
+}
+
+impl Default for ParticleEmitterComponent {
+    fn default() -> Self {
+        Self {
+            emitting: true,
+            max_particles: 1024,
+            emission_rate: 100.0,
+            lifetime: 2.0,
+            speed: 2.0,
+            spread: 0.3,
+            gravity: Vec3::new(0.0, -9.81, 0.0),
+            drag: 0.1,
+            start_size: 0.1,
+            end_size: 0.02,
+            start_color: Color::linear(1.0, 0.8, 0.4),
+            end_color: Color::linear_rgba(1.0, 0.2, 0.0, 0.0),
+            collide: true,
+            bounce: 0.4,
+            blend: ParticleBlendMode::Additive,
+        }
+    }
+}
@@ -0,0 +1,88 @@
+//! Camera effects applied to the lit scene before it is encoded for the display. The
+//! renderer reads [`PostProcessSettings`] every frame, so fields can be tweaked live, e.g.
+//! refocusing on whatever a GPU pick found under the cursor.
+
+use nalgebra_glm::Vec3;
+use serde::{Deserialize, Serialize};
+
+/// Height of the simulated sensor in millimeters, that of a full-frame camera.
+const SENSOR_HEIGHT_MM: f32 = 24.0;
+
+/// Thin-lens depth of field: everything off the focus plane blurs by how far a real lens
+/// with these settings would defocus it. World units are taken as meters.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct DepthOfField {
+    pub enabled: bool,
+    /// Distance from the camera that is in perfect focus.
+    pub focus_distance: f32,
+    /// Lens f-number; lower values blur more.
+    pub f_stop: f32,
+    /// Lens focal length in millimeters; longer lenses blur more. Independent of the
+    /// camera's field of view, so the blur can be exaggerated.
+    pub focal_length_mm: f32,
+    /// Largest blur radius in pixels of the full-resolution image.
+    pub max_blur_radius: f32,
+}
+
+impl Default for DepthOfField {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            focus_distance: 10.0,
+            f_stop: 2.8,
+            focal_length_mm: 50.0,
+            max_blur_radius: 16.0,
+        }
+    }
+}
+
+impl DepthOfField {
+    /// Focuses on `target` as seen from `camera_position`.
+    pub fn focus_on(&mut self, camera_position: Vec3, target: Vec3) {
+        self.focus_distance = nalgebra_glm::distance(&camera_position, &target);
+    }
+
+    /// Blur radius in pixels of an image `image_height` pixels tall, per unit of
+    /// `(distance - focus_distance) / distance`: the radius of objects at infinity.
+    pub fn coc_scale(&self, image_height: f32) -> f32 {
+        let focal_length = self.focal_length_mm;
+        let focus_mm = self.focus_distance * 1000.0;
+        if focus_mm <= focal_length || self.f_stop <= 0.0 {
+            return 0.0;
+        }
+        let sensor_coc = focal_length * focal_length / (self.f_stop * (focus_mm - focal_length));
+        sensor_coc / SENSOR_HEIGHT_MM * image_height
+    }
+}
+
+/// Resource for the post-processing effects.
+///
+/// From a system: `ctx.res_mut::<PostProcessSettings>().depth_of_field.enabled = true`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct PostProcessSettings {
+    pub depth_of_field: DepthOfField,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn coc_scale_follows_the_thin_lens_model() {
+        let dof = DepthOfField {
+            focus_distance: 5.0,
+            ..DepthOfField::default()
+        };
+        // 50mm at f/2.8 focused at 5m: 0.18mm on a 24mm sensor, 8.1 of 1080 pixels.
+        assert!((dof.coc_scale(1080.0) - 8.1).abs() < 0.05);
+
+        let wide_open = DepthOfField { f_stop: 1.4, ..dof };
+        assert!((wide_open.coc_scale(1080.0) - 2.0 * dof.coc_scale(1080.0)).abs() < 1e-3);
+
+        let inside_lens = DepthOfField {
+            focus_distance: 0.01,
+            ..dof
+        };
+        assert_eq!(inside_lens.coc_scale(1080.0), 0.0);
+    }
+}
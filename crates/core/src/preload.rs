@@ -0,0 +1,276 @@
+//! Background preloading for loading screens.
+//!
+//! [`EngineContext::preload`](crate::EngineContext::preload) walks the registry's
+//! dependency graph from one asset, usually a `.scene` manifest, and loads everything it
+//! needs. Cooked meshes and textures are read and decoded on a worker thread; materials
+//! and behavior trees are built on the main thread once the worker is done, since they
+//! bind the data it loaded. The engine polls every preload at the start of a frame.
+//! [`WorldStreamer`](crate::streaming::WorldStreamer) uses the same worker to decode a
+//! streaming cell's assets before spawning it.
+
+use crate::asset_context::AssetContext;
+use assets::emesh::read_emesh;
+use assets::etex::read_etex;
+use common::{trace, Guid, ImageData, MeshData};
+use material::material_manager::MaterialManager;
+use project::{resolve_cooked_path, AssetType};
+use std::collections::HashSet;
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+
+/// Identifies a preload started with `EngineContext::preload`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PreloadId(pub(crate) usize);
+
+/// Aggregate progress of a preload, counted in assets.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PreloadProgress {
+    pub loaded: usize,
+    /// Assets that could not be loaded, e.g. because their cooked output is missing,
+    /// or whose dependencies could not.
+    pub failed: usize,
+    pub total: usize,
+}
+
+impl PreloadProgress {
+    /// Share of assets finished, loaded or failed, in `[0, 1]`.
+    pub fn fraction(&self) -> f32 {
+        if self.total == 0 {
+            1.0
+        } else {
+            (self.loaded + self.failed) as f32 / self.total as f32
+        }
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.loaded + self.failed == self.total
+    }
+}
+
+#[derive(Debug)]
+pub enum PreloadError {
+    /// No registered asset has this source path.
+    UnknownAsset(PathBuf),
+}
+
+impl fmt::Display for PreloadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PreloadError::UnknownAsset(path) => {
+                write!(f, "no asset registered at '{}'", path.display())
+            }
+        }
+    }
+}
+
+impl std::error::Error for PreloadError {}
+
+enum Decoded {
+    Mesh(MeshData),
+    Texture(ImageData),
+}
+
+pub(crate) struct Preload {
+    progress: PreloadProgress,
+    /// Worker output. `None` once the worker has finished.
+    receiver: Option<Receiver<(Guid, Option<Decoded>)>>,
+    /// Worker jobs not received yet; counted as failed if the worker dies.
+    outstanding: usize,
+    /// Materials and behavior trees, dependencies first.
+    main_thread: Vec<(Guid, AssetType)>,
+    failed: HashSet<Guid>,
+}
+
+impl Preload {
+    /// Resolves the dependency closure of `roots` and starts the worker, unless there is
+    /// nothing to decode. Assets that are already loaded count as loaded straight away.
+    pub(crate) fn start(assets: &AssetContext, roots: &[Guid]) -> Self {
+        let registry = assets.registry();
+        let mut progress = PreloadProgress::default();
+        let mut jobs = Vec::new();
+        let mut main_thread = Vec::new();
+
+        for guid in registry.load_order(roots) {
+            let Some(record) = registry.get(&guid) else {
+                continue;
+            };
+            let loaded = match record.asset_type {
+                AssetType::Mesh => assets.store().handle_of::<MeshData>(guid).is_some(),
+                AssetType::Texture => assets.store().handle_of::<ImageData>(guid).is_some(),
+                AssetType::Material | AssetType::BehaviorTree => {
+                    progress.total += 1;
+                    main_thread.push((guid, record.asset_type));
+                    continue;
+                }
+                _ => continue,
+            };
+            progress.total += 1;
+            if loaded {
+                progress.loaded += 1;
+            } else if let Some(extension) = record.asset_type.cooked_extension() {
+                let cooked = resolve_cooked_path(&assets.cache_dir, &guid, extension);
+                jobs.push((guid, record.asset_type, cooked));
+            }
+        }
+
+        let outstanding = jobs.len();
+        if jobs.is_empty() {
+            return Self {
+                progress,
+                receiver: None,
+                outstanding,
+                main_thread,
+                failed: HashSet::new(),
+            };
+        }
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            for (guid, asset_type, path) in jobs {
+                let span = trace::span("asset", format!("Preload {}", path.display()));
+                let decoded = match asset_type {
+                    AssetType::Mesh => read_emesh(&path).ok().map(Decoded::Mesh),
+                    _ => read_etex(&path).ok().map(Decoded::Texture),
+                };
+                drop(span);
+                if sender.send((guid, decoded)).is_err() {
+                    return;
+                }
+            }
+        });
+
+        Self {
+            progress,
+            receiver: Some(receiver),
+            outstanding,
+            main_thread,
+            failed: HashSet::new(),
+        }
+    }
+
+    pub(crate) fn progress(&self) -> PreloadProgress {
+        self.progress
+    }
+
+    /// Stores whatever the worker has decoded so far. Once it is done, builds the
+    /// main-thread assets in dependency order.
+    pub(crate) fn poll(&mut self, assets: &mut AssetContext, materials: &mut MaterialManager) {
+        if let Some(receiver) = &self.receiver {
+            loop {
+                match receiver.try_recv() {
+                    Ok((guid, decoded)) => {
+                        self.outstanding -= 1;
+                        match decoded {
+                            Some(Decoded::Mesh(mesh)) => {
+                                assets.asset_store.insert_mesh(guid, mesh);
+                            }
+                            Some(Decoded::Texture(image)) => {
+                                assets.asset_store.insert_texture(guid, image);
+                            }
+                            None => {
+                                self.failed.insert(guid);
+                                self.progress.failed += 1;
+                                continue;
+                            }
+                        }
+                        self.progress.loaded += 1;
+                    }
+                    Err(TryRecvError::Empty) => return,
+                    Err(TryRecvError::Disconnected) => break,
+                }
+            }
+            self.progress.failed += self.outstanding;
+            self.outstanding = 0;
+            self.receiver = None;
+        }
+
+        for (guid, asset_type) in std::mem::take(&mut self.main_thread) {
+            let dependencies = assets.registry().dependencies(&guid);
+            if dependencies.iter().any(|d| self.failed.contains(d)) {
+                self.failed.insert(guid);
+                self.progress.failed += 1;
+                continue;
+            }
+            match asset_type {
+                AssetType::Material => {
+                    materials.get_or_insert(guid, || assets.build_material(guid));
+                }
+                _ => {
+                    assets.load_behavior_tree(guid);
+                }
+            }
+            self.progress.loaded += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assets::write_emesh;
+    use common::{Vertex, VertexEncoding};
+    use project::{AssetMeta, AssetRegistry};
+    use std::path::Path;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn loads_the_dependency_closure_and_reports_failures() {
+        let dir = std::env::temp_dir().join(format!("preload_{}", std::process::id()));
+        let cache = dir.join(".cache");
+        std::fs::create_dir_all(cache.join("cooked")).unwrap();
+        let mesh = Guid::from_u128(1);
+        let texture = Guid::from_u128(2);
+        let scene = Guid::from_u128(3);
+        let write = |name: &str, guid: Guid, content: String| {
+            let path = dir.join(name);
+            std::fs::write(&path, content).unwrap();
+            AssetMeta {
+                guid,
+                import: Default::default(),
+            }
+            .save(AssetMeta::meta_path_for(&path))
+            .unwrap();
+        };
+        write("cube.obj", mesh, String::new());
+        // Never cooked, so decoding it fails.
+        write("wall.png", texture, String::new());
+        write(
+            "level.scene",
+            scene,
+            format!("meshes = [\"{mesh}\"]\ntextures = [\"{texture}\"]\n"),
+        );
+        let cooked_mesh = resolve_cooked_path(&cache, &mesh, "emesh");
+        write_emesh(
+            &cooked_mesh,
+            &[Vertex::default(); 3],
+            VertexEncoding::Full,
+            None,
+            None,
+            &[0, 1, 2],
+        )
+        .unwrap();
+
+        let registry = AssetRegistry::scan(&cache, &dir, None).unwrap();
+        let mut assets = AssetContext::new(cache.clone(), dir.clone(), registry);
+        let mut materials = MaterialManager::new();
+        let root = assets
+            .registry()
+            .find_by_source_path(Path::new("level.scene"))
+            .unwrap()
+            .guid;
+
+        let mut preload = Preload::start(&assets, &[root]);
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while !preload.progress().is_complete() && Instant::now() < deadline {
+            preload.poll(&mut assets, &mut materials);
+            thread::sleep(Duration::from_millis(1));
+        }
+        std::fs::remove_dir_all(&dir).ok();
+
+        let progress = preload.progress();
+        assert_eq!((progress.loaded, progress.failed, progress.total), (1, 1, 2));
+        assert_eq!(progress.fraction(), 1.0);
+        assert!(assets.store().handle_of::<MeshData>(mesh).is_some());
+    }
+}
@@ -0,0 +1,146 @@
+use common::{OutputMode, OutputSettings};
+use ecs::entity::Entity;
+
+/// Name of the action that requests a RenderDoc capture of the current frame. Bound to F10
+/// by default; rebind it like any other action.
+pub const CAPTURE_FRAME_ACTION: &str = "capture_frame";
+
+/// Name of the action that writes the next frame's draw list to
+/// `<cache_dir>/frame_dumps`. Bound to F8 by default.
+pub const DUMP_FRAME_ACTION: &str = "dump_frame";
+
+/// Name of the action that prints GPU memory usage to stdout. Bound to F9 by default.
+pub const DUMP_GPU_MEMORY_ACTION: &str = "dump_gpu_memory";
+
+/// What a GPU pick found under a window position.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PickResult {
+    /// Window coordinates the pick was requested at.
+    pub position: [f32; 2],
+    /// Entity whose mesh covers the pixel, `None` for background or when the renderer
+    /// has no entity ID buffer.
+    pub entity: Option<Entity>,
+}
+
+/// Resource for runtime render requests from game code.
+///
+/// From a system: `ctx.res_mut::<RenderSettings>().trigger_capture()`.
+#[derive(Debug, Default)]
+pub struct RenderSettings {
+    capture_requested: bool,
+    gpu_memory_dump_requested: bool,
+    frame_dump_requested: bool,
+    output: OutputSettings,
+    active_output_mode: OutputMode,
+    pick_request: Option<[f32; 2]>,
+    pick_result: Option<PickResult>,
+    prewarm_requested: bool,
+    compiling_pipelines: usize,
+}
+
+impl RenderSettings {
+    /// Captures the frame being simulated with RenderDoc, if the app was launched from it.
+    /// Call it where a bug reproduces to get exactly that frame.
+    pub fn trigger_capture(&mut self) {
+        self.capture_requested = true;
+    }
+
+    /// Returns and clears a pending capture request. Called by the engine before rendering.
+    pub fn take_capture_request(&mut self) -> bool {
+        std::mem::take(&mut self.capture_requested)
+    }
+
+    /// Writes the draw list of the frame being simulated to `<cache_dir>/frame_dumps` as
+    /// JSON.
+    pub fn dump_frame(&mut self) {
+        self.frame_dump_requested = true;
+    }
+
+    /// Returns and clears a pending frame dump request. Called by the engine before
+    /// rendering.
+    pub fn take_frame_dump_request(&mut self) -> bool {
+        std::mem::take(&mut self.frame_dump_requested)
+    }
+
+    /// Prints live, peak and last-frame GPU memory per resource type after the frame
+    /// is rendered.
+    pub fn dump_gpu_memory(&mut self) {
+        self.gpu_memory_dump_requested = true;
+    }
+
+    /// Returns and clears a pending memory dump request. Called by the engine after
+    /// rendering.
+    pub fn take_gpu_memory_dump_request(&mut self) -> bool {
+        std::mem::take(&mut self.gpu_memory_dump_requested)
+    }
+
+    /// Reads the entity drawn at window coordinates `position`, e.g. the mouse position,
+    /// once the frame being simulated is rendered. Pixel-accurate for skinned and
+    /// alpha-tested meshes; needs `gpu_picking` in the graphics settings. The result is
+    /// returned by [`take_pick_result`](Self::take_pick_result) from the next frame.
+    pub fn request_pick(&mut self, position: [f32; 2]) {
+        self.pick_request = Some(position);
+    }
+
+    /// Returns and clears a pending pick request. Called by the engine after rendering.
+    pub fn take_pick_request(&mut self) -> Option<[f32; 2]> {
+        self.pick_request.take()
+    }
+
+    /// Returns and clears the result of the last pick request.
+    pub fn take_pick_result(&mut self) -> Option<PickResult> {
+        self.pick_result.take()
+    }
+
+    /// Records the result of a pick. Called by the engine after rendering.
+    pub fn set_pick_result(&mut self, result: PickResult) {
+        self.pick_result = Some(result);
+    }
+
+    /// Compiles the pipelines of every mesh in the world before the next frame is drawn,
+    /// whether or not a camera sees it. Request it once the scene is spawned behind a
+    /// loading screen, then wait for [`compiling_pipelines`](Self::compiling_pipelines)
+    /// to reach zero before revealing it.
+    pub fn prewarm_pipelines(&mut self) {
+        self.prewarm_requested = true;
+    }
+
+    /// Returns and clears a pending pre-warm request. Called by the engine before
+    /// rendering.
+    pub fn take_prewarm_request(&mut self) -> bool {
+        std::mem::take(&mut self.prewarm_requested)
+    }
+
+    /// Pipelines still compiling in the background as of the last frame. Meshes waiting
+    /// on one are drawn with a placeholder pipeline, or skipped.
+    pub fn compiling_pipelines(&self) -> usize {
+        self.compiling_pipelines
+    }
+
+    /// Records how many pipelines are compiling. Called by the engine after rendering.
+    pub fn set_compiling_pipelines(&mut self, count: usize) {
+        self.compiling_pipelines = count;
+    }
+
+    /// Requested swapchain encoding and HDR brightness.
+    pub fn output(&self) -> &OutputSettings {
+        &self.output
+    }
+
+    /// Switches between SDR, HDR10 and scRGB output; the swapchain is recreated before
+    /// the next frame. Check [`active_output_mode`](Self::active_output_mode) afterwards,
+    /// as displays without the mode fall back to SDR.
+    pub fn set_output(&mut self, output: OutputSettings) {
+        self.output = output;
+    }
+
+    /// Encoding the swapchain actually uses.
+    pub fn active_output_mode(&self) -> OutputMode {
+        self.active_output_mode
+    }
+
+    /// Records the swapchain's encoding. Called by the engine after rendering.
+    pub fn set_active_output_mode(&mut self, mode: OutputMode) {
+        self.active_output_mode = mode;
+    }
+}
@@ -0,0 +1,76 @@
+//! Common run conditions for [`System::run_if`](crate::system::System::run_if).
+//!
+//! ```ignore
+//! ctx.register_system(Box::new(
+//!     System::new(enemy_ai_system).run_if(resource_equals(GameState::InGame)),
+//! ));
+//! ctx.register_system(Box::new(
+//!     System::new(rebuild_navmesh_system).run_if(every_n_frames(30)),
+//! ));
+//! ```
+
+use crate::system::Context;
+use crate::time::Time;
+
+/// Holds while the resource `T` exists and equals `value`, e.g. a game state enum.
+pub fn resource_equals<T: PartialEq + 'static>(value: T) -> impl Fn(&Context) -> bool {
+    move |ctx| ctx.try_res::<T>().is_ok_and(|resource| *resource == value)
+}
+
+/// Holds while a resource of type `T` is registered.
+pub fn resource_exists<T: 'static>() -> impl Fn(&Context) -> bool {
+    |ctx| ctx.try_res::<T>().is_ok()
+}
+
+/// Holds on one frame in `n`, counted by `Time::frame`. A fixed-rate system runs on
+/// every step of such a frame.
+pub fn every_n_frames(n: u64) -> impl Fn(&Context) -> bool {
+    move |ctx| ctx.res::<Time>().frame % n.max(1) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::system::System;
+    use crate::testing::TestContext;
+    use ecs::command_buffer::Commands;
+    use ecs::entity::Entity;
+    use ecs::query::Query;
+    use ecs::testing::TestWorld;
+
+    #[derive(Debug, PartialEq)]
+    enum GameState {
+        Menu,
+        InGame,
+    }
+
+    #[derive(Default)]
+    struct Runs(u32);
+
+    fn count_system(_: Query<Entity>, ctx: &mut Context, _: &mut Commands) {
+        ctx.res_mut::<Runs>().0 += 1;
+    }
+
+    #[test]
+    fn systems_run_only_while_their_conditions_hold() {
+        let mut world = TestWorld::new();
+        let mut ctx = TestContext::new()
+            .with_resource(Runs::default())
+            .with_resource(GameState::Menu);
+        let system = System::new(count_system)
+            .run_if(resource_equals(GameState::InGame))
+            .run_if(every_n_frames(2));
+
+        for _ in 0..4 {
+            ctx.run(&mut world, &system);
+        }
+        assert_eq!(ctx.resources().get::<Runs>().0, 0);
+
+        *ctx.resources().get_mut::<GameState>() = GameState::InGame;
+        for _ in 0..4 {
+            ctx.run(&mut world, &system);
+        }
+        // Frames 5 to 8, of which 6 and 8 are even.
+        assert_eq!(ctx.resources().get::<Runs>().0, 2);
+    }
+}
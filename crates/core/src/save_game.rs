@@ -0,0 +1,335 @@
+use crate::asset_context::AssetContext;
+use crate::behavior_tree::{BehaviorTreeComponent, BlackboardComponent};
+use crate::components::{
+    AreaLightComponent, BlobShadowComponent, CameraComponent, CameraControllerComponent,
+    DirectionalLightComponent, EditorOnly, GlobalTransformComponent, LightProbeGridComponent,
+    LightmapComponent, MaterialComponent, MaterialOverrideComponent, MeshComponent,
+    OrbitCameraControllerComponent, PointLightComponent, ReflectionProbeComponent, RenderLayers,
+    SkinnedMeshComponent, TransformComponent, VegetationComponent, VisibilityComponent,
+};
+use crate::csg::BrushComponent;
+use crate::entity_id::PersistentId;
+use crate::environment::SavedEnvironment;
+use crate::localization::LocalizedText;
+use crate::particles::ParticleEmitterComponent;
+use crate::spline::SplineComponent;
+use crate::trails::TrailComponent;
+use crate::trigger::TriggerVolumeComponent;
+use crate::tween::Tween;
+use crate::types::transform::Transform;
+use crate::ui::UiNodeComponent;
+use common::{Color, Guid, Handle, ImageData, MeshData};
+use ecs::name::{NameComponent, TagsComponent};
+use ecs::snapshot::{HandleRemap, Persist, SnapshotError, SnapshotRegistry};
+use material::material_manager::{MaterialData, MaterialManager};
+use nalgebra_glm::Vec3;
+use serde::{Deserialize, Serialize};
+use spatial::{ColliderComponent, SpatialWorld};
+use std::any::{Any, TypeId};
+
+/// Resource for quicksave/quickload from game code. Requests are handled after the
+/// frame's systems have run and their commands are applied.
+///
+/// From a system: `ctx.res_mut::<SaveGame>().request_quicksave()`.
+#[derive(Debug, Default)]
+pub struct SaveGame {
+    save_requested: bool,
+    load_requested: bool,
+    quicksave: Option<Vec<u8>>,
+    last_error: Option<SnapshotError>,
+}
+
+impl SaveGame {
+    pub fn request_quicksave(&mut self) {
+        self.save_requested = true;
+    }
+
+    /// Restores the last quicksave. Does nothing if none was made.
+    pub fn request_quickload(&mut self) {
+        self.load_requested = true;
+    }
+
+    /// The last quicksave blob, to write to disk if the game wants it to persist.
+    pub fn quicksave(&self) -> Option<&[u8]> {
+        self.quicksave.as_deref()
+    }
+
+    /// Replaces the quicksave slot, e.g. with a blob read back from disk.
+    pub fn set_quicksave(&mut self, bytes: Vec<u8>) {
+        self.quicksave = Some(bytes);
+    }
+
+    /// Error from the last failed quicksave or quickload.
+    pub fn last_error(&self) -> Option<&SnapshotError> {
+        self.last_error.as_ref()
+    }
+
+    pub(crate) fn take_requests(&mut self) -> (bool, bool) {
+        (
+            std::mem::take(&mut self.save_requested),
+            std::mem::take(&mut self.load_requested),
+        )
+    }
+
+    pub(crate) fn store_result(&mut self, result: Result<Option<Vec<u8>>, SnapshotError>) {
+        match result {
+            Ok(Some(bytes)) => self.quicksave = Some(bytes),
+            Ok(None) => {}
+            Err(err) => self.last_error = Some(err),
+        }
+    }
+}
+
+/// What a save holds: the world's entities, encoded through the snapshot registry, and
+/// the scene-wide settings beside them.
+#[derive(Serialize, Deserialize)]
+pub(crate) struct SceneSnapshot {
+    pub(crate) world: Vec<u8>,
+    pub(crate) environment: SavedEnvironment,
+}
+
+impl SceneSnapshot {
+    pub(crate) fn encode(&self) -> Result<Vec<u8>, SnapshotError> {
+        bincode::serialize(self).map_err(|err| SnapshotError::Encode(err.to_string()))
+    }
+
+    pub(crate) fn decode(bytes: &[u8]) -> Result<Self, SnapshotError> {
+        bincode::deserialize(bytes).map_err(|err| SnapshotError::Decode(err.to_string()))
+    }
+}
+
+/// Maps mesh, texture and material handles to their asset GUIDs while saving, and loads the
+/// assets back by GUID while loading. Colliders are read from and registered again in
+/// `spatial`.
+pub struct AssetRemap<'a> {
+    pub assets: &'a mut AssetContext,
+    pub materials: &'a mut MaterialManager,
+    pub spatial: &'a mut SpatialWorld,
+}
+
+impl HandleRemap for AssetRemap<'_> {
+    fn stable_id(&mut self, kind: TypeId, handle: u64) -> Option<u128> {
+        let guid = if kind == TypeId::of::<MeshData>() {
+            self.assets
+                .asset_store
+                .guid_of(Handle::<MeshData>::new(handle))
+        } else if kind == TypeId::of::<ImageData>() {
+            self.assets
+                .asset_store
+                .guid_of(Handle::<ImageData>::new(handle))
+        } else if kind == TypeId::of::<MaterialData>() {
+            self.materials.guid_of(Handle::new(handle))
+        } else {
+            None
+        };
+        guid.map(|guid| guid.as_u128())
+    }
+
+    fn resolve(&mut self, kind: TypeId, stable: u128) -> Option<u64> {
+        let guid = Guid::from_u128(stable);
+        self.assets.registry.get(&guid)?;
+        let handle = if kind == TypeId::of::<MeshData>() {
+            self.assets.load_mesh(guid).raw()
+        } else if kind == TypeId::of::<ImageData>() {
+            self.assets.load_texture(guid).raw()
+        } else if kind == TypeId::of::<MaterialData>() {
+            let assets = &mut self.assets;
+            self.materials
+                .get_or_insert(guid, || assets.build_material(guid))
+                .raw()
+        } else {
+            return None;
+        };
+        Some(handle)
+    }
+
+    fn context(&mut self, kind: TypeId) -> Option<&mut dyn Any> {
+        if kind == TypeId::of::<SpatialWorld>() {
+            Some(&mut *self.spatial)
+        } else {
+            None
+        }
+    }
+}
+
+impl Persist for MeshComponent {
+    type Saved = u128;
+
+    fn save(&self, remap: &mut dyn HandleRemap) -> Result<u128, SnapshotError> {
+        remap.save_handle::<MeshData>(self.mesh_handle.raw())
+    }
+
+    fn load(saved: u128, remap: &mut dyn HandleRemap) -> Result<Self, SnapshotError> {
+        Ok(Self::new(Handle::new(
+            remap.load_handle::<MeshData>(saved)?,
+        )))
+    }
+}
+
+impl Persist for MaterialComponent {
+    type Saved = u128;
+
+    fn save(&self, remap: &mut dyn HandleRemap) -> Result<u128, SnapshotError> {
+        remap.save_handle::<MaterialData>(self.material_handle.raw())
+    }
+
+    fn load(saved: u128, remap: &mut dyn HandleRemap) -> Result<Self, SnapshotError> {
+        Ok(Self::new(Handle::new(
+            remap.load_handle::<MaterialData>(saved)?,
+        )))
+    }
+}
+
+impl Persist for LightmapComponent {
+    type Saved = (u128, [f32; 4], f32);
+
+    fn save(&self, remap: &mut dyn HandleRemap) -> Result<Self::Saved, SnapshotError> {
+        let lightmap = remap.save_handle::<ImageData>(self.lightmap.raw())?;
+        Ok((lightmap, self.scale_offset.into(), self.intensity))
+    }
+
+    fn load(saved: Self::Saved, remap: &mut dyn HandleRemap) -> Result<Self, SnapshotError> {
+        let (lightmap, scale_offset, intensity) = saved;
+        Ok(Self {
+            lightmap: Handle::new(remap.load_handle::<ImageData>(lightmap)?),
+            scale_offset: scale_offset.into(),
+            intensity,
+        })
+    }
+}
+
+/// Only its presence is saved; the matrix and GPU slot are rebuilt after loading.
+impl Persist for GlobalTransformComponent {
+    type Saved = ();
+
+    fn save(&self, _remap: &mut dyn HandleRemap) -> Result<(), SnapshotError> {
+        Ok(())
+    }
+
+    fn load(_saved: (), _remap: &mut dyn HandleRemap) -> Result<Self, SnapshotError> {
+        Ok(Self::default())
+    }
+}
+
+/// Saved without its parent, which goes through the snapshot's entity remapping. A
+/// parent that isn't saved with it leaves the node a root.
+impl Persist for UiNodeComponent {
+    type Saved = (Option<u128>, UiNodeComponent);
+
+    fn save(&self, remap: &mut dyn HandleRemap) -> Result<Self::Saved, SnapshotError> {
+        let parent = self.parent.map(|parent| remap.save_entity(parent)).transpose()?;
+        Ok((parent, self.clone()))
+    }
+
+    fn load(saved: Self::Saved, remap: &mut dyn HandleRemap) -> Result<Self, SnapshotError> {
+        let (parent, mut node) = saved;
+        node.parent = parent.and_then(|parent| remap.load_entity(parent));
+        Ok(node)
+    }
+}
+
+/// Only the key is saved; the text is resolved again for the current language.
+impl Persist for LocalizedText {
+    type Saved = String;
+
+    fn save(&self, _remap: &mut dyn HandleRemap) -> Result<String, SnapshotError> {
+        Ok(self.key.clone())
+    }
+
+    fn load(saved: String, _remap: &mut dyn HandleRemap) -> Result<Self, SnapshotError> {
+        Ok(Self::new(saved))
+    }
+}
+
+/// Registers the engine's built-in components under `core.*` names.
+pub fn register_engine_components(registry: &mut SnapshotRegistry) {
+    registry.register::<PersistentId>("core.persistent_id");
+    registry.register::<NameComponent>("core.name");
+    registry.register::<TagsComponent>("core.tags");
+    registry.register::<TransformComponent>("core.transform");
+    registry.register_persist::<GlobalTransformComponent>("core.global_transform");
+    registry.register_persist::<MeshComponent>("core.mesh");
+    registry.register_persist::<MaterialComponent>("core.material");
+    registry.register::<MaterialOverrideComponent>("core.material_override");
+    registry.register_persist::<LightmapComponent>("core.lightmap");
+    registry.register::<RenderLayers>("core.render_layers");
+    registry.register::<VisibilityComponent>("core.visibility");
+    registry.register::<VegetationComponent>("core.vegetation");
+    registry.register::<BlobShadowComponent>("core.blob_shadow");
+    registry.register::<ParticleEmitterComponent>("core.particle_emitter");
+    registry.register::<TrailComponent>("core.trail");
+    registry.register::<SplineComponent>("core.spline");
+    registry.register::<BrushComponent>("core.brush");
+    registry.register::<EditorOnly>("core.editor_only");
+    registry.register::<CameraComponent>("core.camera");
+    registry.register::<CameraControllerComponent>("core.camera_controller");
+    registry.register::<OrbitCameraControllerComponent>("core.orbit_camera_controller");
+    registry.register::<DirectionalLightComponent>("core.directional_light");
+    registry.register::<PointLightComponent>("core.point_light");
+    registry.register::<AreaLightComponent>("core.area_light");
+    registry.register::<ReflectionProbeComponent>("core.reflection_probe");
+    registry.register::<LightProbeGridComponent>("core.light_probe_grid");
+    registry.register::<SkinnedMeshComponent>("core.skinned_mesh");
+    registry.register_persist::<ColliderComponent>("core.collider");
+    registry.register::<TriggerVolumeComponent>("core.trigger_volume");
+    registry.register::<BehaviorTreeComponent>("core.behavior_tree");
+    registry.register::<BlackboardComponent>("core.blackboard");
+    registry.register_persist::<UiNodeComponent>("core.ui_node");
+    registry.register_persist::<LocalizedText>("core.localized_text");
+    registry.register::<Tween<f32>>("core.tween_f32");
+    registry.register::<Tween<Vec3>>("core.tween_vec3");
+    registry.register::<Tween<Color>>("core.tween_color");
+    registry.register::<Tween<Transform>>("core.tween_transform");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::headless_engine;
+    use crate::trigger::{TriggerEvent, TriggerEventKind};
+    use crate::EngineContext;
+    use ecs::component::Component;
+    use ecs::entity::Entity;
+    use ecs::event::Events;
+    use spatial::Shape;
+
+    /// Not registered for snapshots, so it is dropped by a load.
+    #[derive(Component)]
+    struct Scratch;
+
+    fn entered(engine: &EngineContext) -> Vec<(Entity, Entity)> {
+        engine
+            .resources()
+            .get::<Events<TriggerEvent>>()
+            .iter()
+            .filter(|event| event.kind == TriggerEventKind::Enter)
+            .map(|event| (event.trigger, event.other))
+            .collect()
+    }
+
+    #[test]
+    fn triggers_still_fire_for_colliders_after_a_round_trip() {
+        let mut engine = headless_engine();
+        engine.get_world().create_entity((Scratch,));
+        let trigger = engine.get_world().create_entity((
+            TransformComponent::default(),
+            TriggerVolumeComponent::new_sphere(2.0),
+        ));
+        let collider = engine.spawn_collider(Vec3::new(1.0, 0.0, 0.0), Shape::Sphere {
+            radius: 0.5,
+        });
+        engine.update(1.0 / 60.0);
+        engine.update(1.0 / 60.0);
+        assert_eq!(entered(&engine), [(trigger, collider)]);
+
+        let bytes = engine.save_snapshot().unwrap();
+        let entities = engine.load_snapshot(&bytes).unwrap();
+        assert_eq!(entities.len(), 2);
+        assert!(engine.get_world().get::<ColliderComponent>(entities[1]).is_some());
+        engine.update(1.0 / 60.0);
+        engine.update(1.0 / 60.0);
+        // Without the scratch entity, both entities load under new ids.
+        assert_ne!(entities[0], trigger);
+        assert_eq!(entered(&engine), [(entities[0], entities[1])]);
+    }
+}
@@ -0,0 +1,549 @@
+//! Splines for roads, pipes, rivers and anything else that follows a path.
+//!
+//! A [`SplineComponent`] holds control points in the entity's local space and evaluates
+//! positions and directions along the curve. Movement at constant speed goes through an
+//! [`ArcLengthTable`], which maps distances along the spline to curve parameters.
+//!
+//! [`extrude`] sweeps a 2D [`SplineProfile`] along a spline into a mesh.
+//! `EngineContext::create_spline_mesh` adds that mesh to the asset store, so level code
+//! can build its roads while loading and spawn them with a `MeshComponent`:
+//!
+//! ```ignore
+//! let settings = SplineMeshSettings::new(SplineProfile::strip(6.0));
+//! let road = ctx.create_spline_mesh(&spline, &settings);
+//! ctx.get_world().create_entity((transform, spline, MeshComponent::new(road), material));
+//! ```
+//!
+//! While the AABB overlay is on (F3), every spline is drawn with its control points.
+
+use common::{MeshData, SubMesh, Vertex, VertexEncoding};
+use ecs::component::Component;
+use nalgebra_glm::{Vec2, Vec3, Vec4};
+use serde::{Deserialize, Serialize};
+
+/// Steps each segment is split into when measuring arc length.
+const LENGTH_STEPS_PER_SEGMENT: usize = 16;
+
+/// How a [`SplineComponent`]'s points shape the curve.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum SplineMode {
+    /// Passes through every point, with tangents taken from the neighbors. Uses the
+    /// centripetal form, which does not loop or overshoot where points are unevenly
+    /// spaced.
+    #[default]
+    CatmullRom,
+    /// Cubic Bezier segments sharing their end points: anchor, two handles, anchor, two
+    /// handles, ... An open spline needs `3n + 1` points and a closed one `3n`, where
+    /// the last segment ends at the first anchor. Points past the last full segment
+    /// are ignored.
+    Bezier,
+}
+
+/// A curve through control points in the entity's local space.
+#[derive(Clone, Debug, Component, Default, PartialEq, Serialize, Deserialize)]
+pub struct SplineComponent {
+    pub points: Vec<Vec3>,
+    pub mode: SplineMode,
+    /// Joins the last point back to the first.
+    pub closed: bool,
+}
+
+impl SplineComponent {
+    pub fn new(mode: SplineMode, points: Vec<Vec3>) -> Self {
+        Self {
+            points,
+            mode,
+            closed: false,
+        }
+    }
+
+    pub fn catmull_rom(points: Vec<Vec3>) -> Self {
+        Self::new(SplineMode::CatmullRom, points)
+    }
+
+    pub fn bezier(points: Vec<Vec3>) -> Self {
+        Self::new(SplineMode::Bezier, points)
+    }
+
+    pub fn with_closed(mut self, closed: bool) -> Self {
+        self.closed = closed;
+        self
+    }
+
+    /// Number of cubic segments. Each covers an equal share of the parameter range.
+    pub fn segment_count(&self) -> usize {
+        let n = self.points.len();
+        match (self.mode, self.closed) {
+            (SplineMode::CatmullRom, false) => n.saturating_sub(1),
+            (SplineMode::CatmullRom, true) if n >= 2 => n,
+            (SplineMode::CatmullRom, true) => 0,
+            (SplineMode::Bezier, false) => n.saturating_sub(1) / 3,
+            (SplineMode::Bezier, true) => n / 3,
+        }
+    }
+
+    /// Position at `t`, from 0 at the start to 1 at the end. Closed splines wrap `t`;
+    /// open ones clamp it. A spline without segments sits at its first point.
+    pub fn position(&self, t: f32) -> Vec3 {
+        match self.locate(t) {
+            Some((segment, local)) => {
+                let [p0, p1, p2, p3] = self.bezier_points(segment);
+                let u = 1.0 - local;
+                p0 * (u * u * u)
+                    + p1 * (3.0 * u * u * local)
+                    + p2 * (3.0 * u * local * local)
+                    + p3 * (local * local * local)
+            }
+            None => self.points.first().copied().unwrap_or_else(Vec3::zeros),
+        }
+    }
+
+    /// Derivative of [`Self::position`] with respect to `t`. Its length is the speed
+    /// the curve is traced at, which varies along most splines.
+    pub fn tangent(&self, t: f32) -> Vec3 {
+        let Some((segment, local)) = self.locate(t) else {
+            return Vec3::zeros();
+        };
+        let [p0, p1, p2, p3] = self.bezier_points(segment);
+        let u = 1.0 - local;
+        let derivative = (p1 - p0) * (3.0 * u * u)
+            + (p2 - p1) * (6.0 * u * local)
+            + (p3 - p2) * (3.0 * local * local);
+        derivative * self.segment_count() as f32
+    }
+
+    /// Unit direction of travel at `t`, or zero where the curve stops.
+    pub fn direction(&self, t: f32) -> Vec3 {
+        let tangent = self.tangent(t);
+        let length = tangent.norm();
+        if length > f32::EPSILON {
+            tangent / length
+        } else {
+            Vec3::zeros()
+        }
+    }
+
+    /// Length of the curve, measured along [`LENGTH_STEPS_PER_SEGMENT`] chords per
+    /// segment.
+    pub fn length(&self) -> f32 {
+        self.arc_length_table().length()
+    }
+
+    /// Table for evaluating the spline by distance. Build it once and keep it while the
+    /// points don't change.
+    pub fn arc_length_table(&self) -> ArcLengthTable {
+        let steps = self.segment_count() * LENGTH_STEPS_PER_SEGMENT;
+        let mut distances = Vec::with_capacity(steps + 1);
+        distances.push(0.0);
+        let mut previous = self.position(0.0);
+        for step in 1..=steps {
+            let position = self.position(step as f32 / steps as f32);
+            let last = distances.last().copied().unwrap_or(0.0);
+            distances.push(last + (position - previous).norm());
+            previous = position;
+        }
+        ArcLengthTable { distances }
+    }
+
+    /// Points along the curve, `points_per_segment` per segment plus the end point.
+    /// Used to draw the spline.
+    pub fn polyline(&self, points_per_segment: usize) -> Vec<Vec3> {
+        let steps = self.segment_count() * points_per_segment.max(1);
+        if steps == 0 {
+            return self.points.first().copied().into_iter().collect();
+        }
+        (0..=steps)
+            .map(|step| self.position(step as f32 / steps as f32))
+            .collect()
+    }
+
+    /// Segment index and the parameter within it for `t`.
+    fn locate(&self, t: f32) -> Option<(usize, f32)> {
+        let segments = self.segment_count();
+        if segments == 0 {
+            return None;
+        }
+        let t = if self.closed {
+            t.rem_euclid(1.0)
+        } else {
+            t.clamp(0.0, 1.0)
+        };
+        let scaled = t * segments as f32;
+        let segment = (scaled as usize).min(segments - 1);
+        Some((segment, scaled - segment as f32))
+    }
+
+    /// Control points of `segment` as a cubic Bezier. Catmull-Rom segments are
+    /// converted; the ends of an open one mirror their neighbor.
+    fn bezier_points(&self, segment: usize) -> [Vec3; 4] {
+        let points = &self.points;
+        let n = points.len();
+        match self.mode {
+            SplineMode::Bezier => [
+                points[3 * segment],
+                points[3 * segment + 1],
+                points[3 * segment + 2],
+                points[(3 * segment + 3) % n],
+            ],
+            SplineMode::CatmullRom => {
+                let p1 = points[segment];
+                let p2 = points[(segment + 1) % n];
+                let (p0, p3) = if self.closed {
+                    (points[(segment + n - 1) % n], points[(segment + 2) % n])
+                } else {
+                    let p0 = segment.checked_sub(1).map_or(p1 * 2.0 - p2, |i| points[i]);
+                    let p3 = points.get(segment + 2).copied().unwrap_or(p2 * 2.0 - p1);
+                    (p0, p3)
+                };
+                // Knots spaced by the square root of the chord lengths.
+                let knot = |a: Vec3, b: Vec3| (a - b).norm().sqrt().max(1e-4);
+                let (d0, d1, d2) = (knot(p1, p0), knot(p2, p1), knot(p3, p2));
+                let m1 = (p1 - p0) / d0 - (p2 - p0) / (d0 + d1) + (p2 - p1) / d1;
+                let m2 = (p2 - p1) / d1 - (p3 - p1) / (d1 + d2) + (p3 - p2) / d2;
+                [p1, p1 + m1 * (d1 / 3.0), p2 - m2 * (d1 / 3.0), p2]
+            }
+        }
+    }
+}
+
+/// Cumulative length of a spline at evenly spaced parameters, from
+/// [`SplineComponent::arc_length_table`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArcLengthTable {
+    /// Distance from the start at parameter `i / (len - 1)`.
+    distances: Vec<f32>,
+}
+
+impl ArcLengthTable {
+    pub fn length(&self) -> f32 {
+        self.distances.last().copied().unwrap_or(0.0)
+    }
+
+    /// Parameter of the point `distance` along the spline, clamped to its ends. Pass the
+    /// result to [`SplineComponent::position`] to move at constant speed.
+    pub fn parameter_at(&self, distance: f32) -> f32 {
+        let steps = self.distances.len().saturating_sub(1);
+        if steps == 0 || self.length() <= 0.0 {
+            return 0.0;
+        }
+        let distance = distance.clamp(0.0, self.length());
+        let next = self
+            .distances
+            .partition_point(|&d| d < distance)
+            .clamp(1, steps);
+        let (start, end) = (self.distances[next - 1], self.distances[next]);
+        let local = if end > start {
+            (distance - start) / (end - start)
+        } else {
+            0.0
+        };
+        (next - 1) as f32 / steps as f32 + local / steps as f32
+    }
+}
+
+/// Cross-section swept along a spline by [`extrude`]. Points are in the plane across the
+/// spline, `x` to its right and `y` up. List them so the surface should face to the right
+/// of each edge when walking the points in order: right to left along the top of a road,
+/// counter-clockwise around a pipe.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SplineProfile {
+    pub points: Vec<Vec2>,
+    /// Joins the last point back to the first, for pipes and other closed shapes.
+    pub closed: bool,
+}
+
+impl SplineProfile {
+    /// A flat, upward-facing strip `width` wide, centered on the spline. For roads and
+    /// rivers.
+    pub fn strip(width: f32) -> Self {
+        let half = width * 0.5;
+        Self {
+            points: vec![Vec2::new(half, 0.0), Vec2::new(-half, 0.0)],
+            closed: false,
+        }
+    }
+
+    /// A circle of `radius` around the spline with `sides` edges, facing outwards. For
+    /// pipes, cables and tubes.
+    pub fn circle(radius: f32, sides: usize) -> Self {
+        let sides = sides.max(3);
+        let points = (0..sides)
+            .map(|i| {
+                let angle = i as f32 / sides as f32 * std::f32::consts::TAU;
+                Vec2::new(angle.cos(), angle.sin()) * radius
+            })
+            .collect();
+        Self {
+            points,
+            closed: true,
+        }
+    }
+
+    /// The points in order, with the first repeated at the end of a closed profile so
+    /// the texture seam gets its own vertices.
+    fn ring(&self) -> Vec<Vec2> {
+        let mut ring = self.points.clone();
+        if self.closed {
+            ring.extend(self.points.first().copied());
+        }
+        ring
+    }
+}
+
+/// How [`extrude`] builds a mesh from a spline.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SplineMeshSettings {
+    pub profile: SplineProfile,
+    /// Distance between cross-sections along the spline. Smaller values follow tight
+    /// bends more closely.
+    pub spacing: f32,
+    /// Distance along the spline one repeat of the texture covers. V runs along the
+    /// spline and U across the profile, from 0 to 1.
+    pub texture_length: f32,
+    /// Direction the profile's `y` axis follows, in the spline's local space. Where the
+    /// spline runs parallel to it, the previous cross-section's orientation is kept.
+    pub up: Vec3,
+}
+
+impl SplineMeshSettings {
+    pub fn new(profile: SplineProfile) -> Self {
+        Self {
+            profile,
+            spacing: 1.0,
+            texture_length: 1.0,
+            up: Vec3::y(),
+        }
+    }
+
+    pub fn with_spacing(mut self, spacing: f32) -> Self {
+        self.spacing = spacing;
+        self
+    }
+
+    pub fn with_texture_length(mut self, texture_length: f32) -> Self {
+        self.texture_length = texture_length;
+        self
+    }
+}
+
+/// Sweeps `settings.profile` along `spline` into a single-material mesh in the spline's
+/// local space. Cross-sections are evenly spaced by distance, so the texture does not
+/// stretch where control points bunch up. Returns a mesh without vertices when the
+/// spline has no length or the profile fewer than two points.
+pub fn extrude(spline: &SplineComponent, settings: &SplineMeshSettings) -> MeshData {
+    let table = spline.arc_length_table();
+    let length = table.length();
+    let ring = settings.profile.ring();
+    let mut mesh = MeshData {
+        vertices: Vec::new(),
+        vertex_encoding: VertexEncoding::Full,
+        indices: Vec::new(),
+        extras: None,
+        skin: None,
+        submeshes: Vec::new(),
+    };
+    if length <= 0.0 || ring.len() < 2 {
+        mesh.submeshes.push(SubMesh {
+            index_offset: 0,
+            index_count: 0,
+        });
+        return mesh;
+    }
+
+    // Distance across the profile at each ring point, for U.
+    let mut across = vec![0.0];
+    for edge in ring.windows(2) {
+        across.push(across.last().unwrap() + (edge[1] - edge[0]).norm());
+    }
+    let profile_length = across.last().copied().unwrap_or(0.0).max(f32::EPSILON);
+
+    let sections = ((length / settings.spacing.max(0.01)).ceil() as usize).max(1);
+    let texture_length = settings.texture_length.max(f32::EPSILON);
+    let mut up = settings.up.normalize();
+    for section in 0..=sections {
+        let distance = length * section as f32 / sections as f32;
+        let t = table.parameter_at(distance);
+        let center = spline.position(t);
+        let forward = spline.direction(t);
+        let projected = settings.up - forward * settings.up.dot(&forward);
+        let kept = up - forward * up.dot(&forward);
+        if projected.norm() > 1e-3 {
+            up = projected.normalize();
+        } else if kept.norm() > 1e-3 {
+            up = kept.normalize();
+        }
+        let right = forward.cross(&up);
+
+        for (i, point) in ring.iter().enumerate() {
+            let previous = ring[i.saturating_sub(1)];
+            let next = ring[(i + 1).min(ring.len() - 1)];
+            // Closed profiles smooth across the seam.
+            let (previous, next) = match (settings.profile.closed, i) {
+                (true, 0) => (ring[ring.len() - 2], next),
+                (true, i) if i == ring.len() - 1 => (previous, ring[1]),
+                _ => (previous, next),
+            };
+            let edge = (next - previous).normalize();
+            let normal = right * edge.y - up * edge.x;
+            let tangent = right * edge.x + up * edge.y;
+            // V runs along `forward`; the sign makes cross(normal, tangent) follow it.
+            let sign = if normal.cross(&tangent).dot(&forward) >= 0.0 {
+                1.0
+            } else {
+                -1.0
+            };
+            mesh.vertices.push(Vertex {
+                pos: center + right * point.x + up * point.y,
+                tex_coord: Vec2::new(across[i] / profile_length, distance / texture_length),
+                normal,
+                tangent: Vec4::new(tangent.x, tangent.y, tangent.z, sign),
+                texture_index: 0,
+            });
+        }
+    }
+
+    let width = ring.len() as u32;
+    for section in 0..sections as u32 {
+        for i in 0..width - 1 {
+            let a = section * width + i;
+            let b = a + 1;
+            let c = a + width;
+            let d = c + 1;
+            mesh.indices.extend_from_slice(&[a, c, b, b, c, d]);
+        }
+    }
+    mesh.submeshes.push(SubMesh {
+        index_offset: 0,
+        index_count: mesh.indices.len() as u32,
+    });
+    mesh
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: Vec3, b: Vec3) -> bool {
+        (a - b).norm() < 1e-3
+    }
+
+    #[test]
+    fn catmull_rom_passes_through_its_points() {
+        let points = vec![
+            Vec3::new(0.0, 0.0, 0.0),
+            Vec3::new(4.0, 0.0, 0.0),
+            Vec3::new(4.0, 0.0, 4.0),
+        ];
+        let spline = SplineComponent::catmull_rom(points.clone());
+        assert_eq!(spline.segment_count(), 2);
+        assert!(close(spline.position(0.0), points[0]));
+        assert!(close(spline.position(0.5), points[1]));
+        assert!(close(spline.position(1.0), points[2]));
+        assert!(close(spline.direction(0.0), Vec3::x()));
+
+        let closed = spline.with_closed(true);
+        assert_eq!(closed.segment_count(), 3);
+        assert!(close(closed.position(1.0), points[0]));
+        assert!(close(closed.position(-1.0 / 3.0), points[2]));
+    }
+
+    #[test]
+    fn bezier_segments_share_anchors() {
+        let spline = SplineComponent::bezier(vec![
+            Vec3::new(0.0, 0.0, 0.0),
+            Vec3::new(1.0, 0.0, 0.0),
+            Vec3::new(2.0, 0.0, 0.0),
+            Vec3::new(3.0, 0.0, 0.0),
+            Vec3::new(4.0, 0.0, 0.0),
+            Vec3::new(5.0, 0.0, 0.0),
+            Vec3::new(6.0, 0.0, 0.0),
+            // Ignored: not a full segment.
+            Vec3::new(7.0, 0.0, 0.0),
+        ]);
+        assert_eq!(spline.segment_count(), 2);
+        assert!(close(spline.position(0.5), Vec3::new(3.0, 0.0, 0.0)));
+        assert!(close(spline.position(1.0), Vec3::new(6.0, 0.0, 0.0)));
+        // Evenly spaced handles trace a straight line at constant speed.
+        assert!(close(spline.tangent(0.3), Vec3::new(6.0, 0.0, 0.0)));
+        assert!((spline.length() - 6.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn arc_length_table_maps_distance_to_parameter() {
+        // Uneven points: the first segment is much shorter than the second.
+        let spline = SplineComponent::catmull_rom(vec![
+            Vec3::new(0.0, 0.0, 0.0),
+            Vec3::new(1.0, 0.0, 0.0),
+            Vec3::new(10.0, 0.0, 0.0),
+        ]);
+        let table = spline.arc_length_table();
+        let length = table.length();
+        for fraction in [0.0, 0.05, 0.25, 0.5, 0.9, 1.0] {
+            let position = spline.position(table.parameter_at(length * fraction));
+            assert!(
+                (position.x - length * fraction).abs() < 0.05,
+                "at {fraction}"
+            );
+        }
+        assert_eq!(table.parameter_at(-1.0), 0.0);
+        assert_eq!(table.parameter_at(length + 1.0), 1.0);
+    }
+
+    #[test]
+    fn extruded_strip_faces_up_and_tiles_along_the_spline() {
+        let spline = SplineComponent::catmull_rom(vec![
+            Vec3::new(0.0, 0.0, 0.0),
+            Vec3::new(0.0, 0.0, -10.0),
+        ]);
+        let settings = SplineMeshSettings::new(SplineProfile::strip(4.0))
+            .with_spacing(2.5)
+            .with_texture_length(5.0);
+        let mesh = extrude(&spline, &settings);
+
+        // 5 cross-sections of 2 points, 4 quads.
+        assert_eq!(mesh.vertices.len(), 10);
+        assert_eq!(mesh.indices.len(), 24);
+        assert_eq!(mesh.submeshes[0].index_count, 24);
+        let first = &mesh.vertices[0];
+        assert!(close(first.pos, Vec3::new(2.0, 0.0, 0.0)));
+        assert!(close(first.normal, Vec3::y()));
+        assert!((mesh.vertices[9].tex_coord.y - 2.0).abs() < 1e-3);
+
+        // Counter-clockwise triangles face along the vertex normals.
+        for triangle in mesh.indices.chunks(3) {
+            let [a, b, c] = [0, 1, 2].map(|i| mesh.vertices[triangle[i] as usize].pos);
+            let face = (b - a).cross(&(c - a));
+            assert!(face.dot(&Vec3::y()) > 0.0);
+        }
+    }
+
+    #[test]
+    fn extruded_pipe_closes_its_profile_with_a_seam() {
+        let spline =
+            SplineComponent::catmull_rom(vec![Vec3::new(0.0, 0.0, 0.0), Vec3::new(3.0, 0.0, 0.0)]);
+        let settings = SplineMeshSettings::new(SplineProfile::circle(0.5, 8)).with_spacing(1.0);
+        let mesh = extrude(&spline, &settings);
+
+        // 4 cross-sections of 9 points: the seam point is repeated.
+        assert_eq!(mesh.vertices.len(), 36);
+        assert!(close(mesh.vertices[0].pos, mesh.vertices[8].pos));
+        assert_eq!(mesh.vertices[8].tex_coord.x, 1.0);
+        for triangle in mesh.indices.chunks(3) {
+            let [a, b, c] = [0, 1, 2].map(|i| &mesh.vertices[triangle[i] as usize]);
+            let face = (b.pos - a.pos).cross(&(c.pos - a.pos));
+            assert!(face.dot(&a.normal) > 0.0);
+            // Normals point away from the spline.
+            assert!(a.normal.dot(&(a.pos - Vec3::new(a.pos.x, 0.0, 0.0))) > 0.0);
+        }
+    }
+
+    #[test]
+    fn empty_spline_extrudes_to_an_empty_mesh() {
+        let spline = SplineComponent::catmull_rom(vec![Vec3::new(1.0, 2.0, 3.0)]);
+        assert_eq!(spline.segment_count(), 0);
+        assert!(close(spline.position(0.5), Vec3::new(1.0, 2.0, 3.0)));
+        let mesh = extrude(&spline, &SplineMeshSettings::new(SplineProfile::strip(1.0)));
+        assert!(mesh.vertices.is_empty());
+        assert_eq!(mesh.submeshes.len(), 1);
+    }
+}
@@ -0,0 +1,478 @@
+use crate::asset_context::AssetContext;
+use crate::preload::Preload;
+use common::{Guid, MeshHandle};
+use ecs::entity::Entity;
+use ecs::world::World;
+use material::material_manager::{MaterialHandle, MaterialManager};
+use nalgebra_glm::{vec3, Vec3};
+use spatial::SpatialWorld;
+use std::collections::HashMap;
+
+/// Integer coordinate of a streaming cell on the XZ plane.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CellCoord {
+    pub x: i32,
+    pub z: i32,
+}
+
+impl CellCoord {
+    pub fn new(x: i32, z: i32) -> Self {
+        Self { x, z }
+    }
+
+    /// Returns the cell containing `position` for the given cell size.
+    pub fn from_position(position: Vec3, cell_size: f32) -> Self {
+        Self {
+            x: (position.x / cell_size).floor() as i32,
+            z: (position.z / cell_size).floor() as i32,
+        }
+    }
+
+    /// World-space center of the cell at height 0.
+    pub fn center(&self, cell_size: f32) -> Vec3 {
+        vec3(
+            (self.x as f32 + 0.5) * cell_size,
+            0.0,
+            (self.z as f32 + 0.5) * cell_size,
+        )
+    }
+}
+
+/// Mutable access handed to `CellLoader`s while a cell is loaded or unloaded.
+pub struct CellContext<'a> {
+    pub world: &'a mut World,
+    pub spatial: &'a mut SpatialWorld,
+    pub assets: &'a mut AssetContext,
+    pub material_manager: &'a mut MaterialManager,
+}
+
+impl<'a> CellContext<'a> {
+    /// Loads a mesh by GUID, returning the cached handle if already loaded.
+    pub fn load_mesh(&mut self, guid: Guid) -> MeshHandle {
+        self.assets.load_mesh(guid)
+    }
+
+    /// Loads a material by GUID, returning the cached handle if already loaded.
+    pub fn load_material(&mut self, guid: Guid) -> MaterialHandle {
+        let assets = &mut self.assets;
+        self.material_manager
+            .get_or_insert(guid, || assets.build_material(guid))
+    }
+}
+
+/// Populates streaming cells. Register one loader per content type (meshes, terrain,
+/// colliders, ...) so each streams alongside the others.
+pub trait CellLoader {
+    /// Assets `cell` needs, such as its meshes and their materials. The streamer decodes
+    /// their cooked meshes and textures on a worker thread and calls `load_cell` once
+    /// they are in, so loading them there is only a cache lookup. Dependencies are
+    /// followed, so listing a material is enough to bring in its textures.
+    fn cell_assets(&self, _cell: CellCoord) -> Vec<Guid> {
+        Vec::new()
+    }
+
+    /// Spawns the content of `cell` and returns every entity it created. The streamer
+    /// owns these entities and removes them when the cell unloads, so gameplay code
+    /// must not despawn them directly.
+    fn load_cell(&mut self, cell: CellCoord, ctx: &mut CellContext) -> Vec<Entity>;
+
+    /// Called before the cell's entities are removed. Override to release state kept
+    /// outside the world, such as terrain tiles or registered colliders.
+    fn unload_cell(&mut self, _cell: CellCoord, _ctx: &mut CellContext) {}
+}
+
+#[derive(Debug, Clone)]
+pub struct StreamingSettings {
+    /// Edge length of one square cell in world units.
+    pub cell_size: f32,
+    /// Cells whose center is within this distance of the focus point are loaded.
+    pub load_radius: f32,
+    /// Cells whose center is further than this are unloaded. Keep it larger than
+    /// `load_radius` so cells on the boundary do not thrash.
+    pub unload_radius: f32,
+    /// Upper bound on cells spawned per update, spreading load spikes over several
+    /// frames. Also bounds the cells whose assets are being decoded at once.
+    pub max_loads_per_update: usize,
+}
+
+impl Default for StreamingSettings {
+    fn default() -> Self {
+        Self {
+            cell_size: 64.0,
+            load_radius: 160.0,
+            unload_radius: 224.0,
+            max_loads_per_update: 2,
+        }
+    }
+}
+
+struct LoadedCell {
+    /// Entities spawned for this cell, one list per registered loader.
+    entities: Vec<Vec<Entity>>,
+}
+
+/// Loads and unloads cell content around a focus point, usually the active camera.
+///
+/// A cell loads in two steps. Its assets (see [`CellLoader::cell_assets`]) are read and
+/// decoded on a worker thread, then its entities are spawned on the main thread. At most
+/// `max_loads_per_update` cells are in the first step at once and spawned per call,
+/// nearest first, so a fast-moving camera never stalls a frame on a whole ring of cells.
+pub struct WorldStreamer {
+    settings: StreamingSettings,
+    loaders: Vec<Box<dyn CellLoader>>,
+    loaded: HashMap<CellCoord, LoadedCell>,
+    /// Cells whose assets are still being decoded.
+    pending: HashMap<CellCoord, Preload>,
+}
+
+impl WorldStreamer {
+    pub fn new(settings: StreamingSettings) -> Self {
+        Self {
+            settings,
+            loaders: Vec::new(),
+            loaded: HashMap::new(),
+            pending: HashMap::new(),
+        }
+    }
+
+    pub fn settings(&self) -> &StreamingSettings {
+        &self.settings
+    }
+
+    pub fn add_loader(&mut self, loader: Box<dyn CellLoader>) {
+        self.loaders.push(loader);
+    }
+
+    pub fn is_loaded(&self, cell: CellCoord) -> bool {
+        self.loaded.contains_key(&cell)
+    }
+
+    pub fn loaded_cells(&self) -> impl Iterator<Item = &CellCoord> {
+        self.loaded.keys()
+    }
+
+    /// Whether `cell`'s assets are being decoded before it is spawned.
+    pub fn is_pending(&self, cell: CellCoord) -> bool {
+        self.pending.contains_key(&cell)
+    }
+
+    /// Every entity spawned by a loader for a currently loaded cell.
+    pub fn streamed_entities(&self) -> impl Iterator<Item = Entity> + '_ {
+        self.loaded
+            .values()
+            .flat_map(|cell| cell.entities.iter().flatten().copied())
+    }
+
+    /// Unloads far cells and drops far pending ones, starts decoding the nearest missing
+    /// cells, then spawns the nearest decoded cells, both within the budget.
+    pub fn update(&mut self, focus: Vec3, ctx: &mut CellContext) {
+        let cell_size = self.settings.cell_size;
+        let unload_radius = self.settings.unload_radius;
+        let budget = self.settings.max_loads_per_update;
+        let is_far =
+            |cell: &CellCoord| planar_distance(cell.center(cell_size), focus) > unload_radius;
+
+        let to_unload = self.loaded.keys().copied().filter(is_far).collect::<Vec<_>>();
+        for cell in to_unload {
+            self.unload(cell, ctx);
+        }
+        // The worker stops once it finds the receiving end gone.
+        self.pending.retain(|cell, _| !is_far(cell));
+
+        let mut wanted = self
+            .cells_in_radius(focus)
+            .into_iter()
+            .filter(|cell| !self.loaded.contains_key(cell) && !self.pending.contains_key(cell))
+            .collect::<Vec<_>>();
+        sort_nearest_first(&mut wanted, focus, cell_size);
+        let free = budget.saturating_sub(self.pending.len());
+        for cell in wanted.into_iter().take(free) {
+            let assets = self
+                .loaders
+                .iter()
+                .flat_map(|loader| loader.cell_assets(cell))
+                .collect::<Vec<_>>();
+            self.pending.insert(cell, Preload::start(ctx.assets, &assets));
+        }
+
+        let mut ready = Vec::new();
+        for (&cell, preload) in &mut self.pending {
+            preload.poll(ctx.assets, ctx.material_manager);
+            if preload.progress().is_complete() {
+                ready.push(cell);
+            }
+        }
+        sort_nearest_first(&mut ready, focus, cell_size);
+        for cell in ready.into_iter().take(budget) {
+            self.pending.remove(&cell);
+            let entities = self
+                .loaders
+                .iter_mut()
+                .map(|loader| loader.load_cell(cell, ctx))
+                .collect();
+            self.loaded.insert(cell, LoadedCell { entities });
+        }
+    }
+
+    /// Unloads every loaded cell and drops pending ones, e.g. before switching levels.
+    pub fn unload_all(&mut self, ctx: &mut CellContext) {
+        self.pending.clear();
+        let cells = self.loaded.keys().copied().collect::<Vec<_>>();
+        for cell in cells {
+            self.unload(cell, ctx);
+        }
+    }
+
+    fn unload(&mut self, cell: CellCoord, ctx: &mut CellContext) {
+        let Some(loaded) = self.loaded.remove(&cell) else {
+            return;
+        };
+        for (loader, entities) in self.loaders.iter_mut().zip(loaded.entities) {
+            loader.unload_cell(cell, ctx);
+            for entity in entities {
+                ctx.world.remove_entity(entity);
+            }
+        }
+    }
+
+    fn cells_in_radius(&self, focus: Vec3) -> Vec<CellCoord> {
+        let cell_size = self.settings.cell_size;
+        let radius = self.settings.load_radius;
+        let reach = (radius / cell_size).ceil() as i32;
+        let origin = CellCoord::from_position(focus, cell_size);
+
+        let mut cells = Vec::new();
+        for dz in -reach..=reach {
+            for dx in -reach..=reach {
+                let cell = CellCoord::new(origin.x + dx, origin.z + dz);
+                if planar_distance(cell.center(cell_size), focus) <= radius {
+                    cells.push(cell);
+                }
+            }
+        }
+        cells
+    }
+}
+
+fn sort_nearest_first(cells: &mut [CellCoord], focus: Vec3, cell_size: f32) {
+    cells.sort_by(|a, b| {
+        let da = planar_distance(a.center(cell_size), focus);
+        let db = planar_distance(b.center(cell_size), focus);
+        da.total_cmp(&db)
+    });
+}
+
+fn planar_distance(a: Vec3, b: Vec3) -> f32 {
+    let dx = a.x - b.x;
+    let dz = a.z - b.z;
+    (dx * dx + dz * dz).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assets::write_emesh;
+    use common::{MeshData, Vertex, VertexEncoding};
+    use ecs::component::Component;
+    use project::{resolve_cooked_path, AssetMeta, AssetRegistry};
+    use std::cell::RefCell;
+    use std::path::PathBuf;
+    use std::rc::Rc;
+    use std::time::{Duration, Instant};
+
+    #[derive(Component)]
+    struct Streamed;
+
+    #[derive(Default)]
+    struct Log {
+        loads: Vec<CellCoord>,
+        unloads: Vec<CellCoord>,
+        /// Whether `mesh` was already in the store when each cell loaded.
+        mesh_ready: Vec<bool>,
+    }
+
+    /// Spawns one entity per cell, needing `mesh` if set.
+    struct Recorder {
+        log: Rc<RefCell<Log>>,
+        mesh: Option<Guid>,
+    }
+
+    impl CellLoader for Recorder {
+        fn cell_assets(&self, _cell: CellCoord) -> Vec<Guid> {
+            self.mesh.into_iter().collect()
+        }
+
+        fn load_cell(&mut self, cell: CellCoord, ctx: &mut CellContext) -> Vec<Entity> {
+            let mut log = self.log.borrow_mut();
+            log.loads.push(cell);
+            if let Some(mesh) = self.mesh {
+                let ready = ctx.assets.store().handle_of::<MeshData>(mesh).is_some();
+                log.mesh_ready.push(ready);
+            }
+            vec![ctx.world.create_entity((Streamed,))]
+        }
+
+        fn unload_cell(&mut self, cell: CellCoord, _ctx: &mut CellContext) {
+            self.log.borrow_mut().unloads.push(cell);
+        }
+    }
+
+    struct Harness {
+        world: World,
+        spatial: SpatialWorld,
+        assets: AssetContext,
+        materials: MaterialManager,
+    }
+
+    impl Harness {
+        fn new(assets: AssetContext) -> Self {
+            Self {
+                world: World::new(),
+                spatial: SpatialWorld::new(),
+                assets,
+                materials: MaterialManager::new(),
+            }
+        }
+
+        fn update(&mut self, streamer: &mut WorldStreamer, focus: Vec3) {
+            streamer.update(
+                focus,
+                &mut CellContext {
+                    world: &mut self.world,
+                    spatial: &mut self.spatial,
+                    assets: &mut self.assets,
+                    material_manager: &mut self.materials,
+                },
+            );
+        }
+
+        fn streamed(&self) -> usize {
+            let mut count = 0;
+            self.world.for_each_component::<Streamed>(|_, _| count += 1);
+            count
+        }
+    }
+
+    fn empty_assets() -> AssetContext {
+        AssetContext::new(PathBuf::new(), PathBuf::new(), AssetRegistry::default())
+    }
+
+    /// Ten-unit cells: around the center of cell (0, 0), it and its four neighbors are
+    /// in range, the diagonal ones at about 14 units are not.
+    fn streamer(max_loads_per_update: usize) -> (WorldStreamer, Rc<RefCell<Log>>) {
+        let log = Rc::new(RefCell::new(Log::default()));
+        let mut streamer = WorldStreamer::new(StreamingSettings {
+            cell_size: 10.0,
+            load_radius: 12.0,
+            unload_radius: 25.0,
+            max_loads_per_update,
+        });
+        streamer.add_loader(Box::new(Recorder {
+            log: log.clone(),
+            mesh: None,
+        }));
+        (streamer, log)
+    }
+
+    #[test]
+    fn cells_load_within_the_radius_and_unload_when_left_behind() {
+        let (mut streamer, log) = streamer(16);
+        let mut harness = Harness::new(empty_assets());
+
+        harness.update(&mut streamer, vec3(5.0, 0.0, 5.0));
+        let mut loaded = streamer.loaded_cells().copied().collect::<Vec<_>>();
+        loaded.sort_by_key(|cell| (cell.x, cell.z));
+        let expected = [(-1, 0), (0, -1), (0, 0), (0, 1), (1, 0)]
+            .map(|(x, z)| CellCoord::new(x, z));
+        assert_eq!(loaded, expected);
+        assert_eq!(harness.streamed(), 5);
+
+        harness.update(&mut streamer, vec3(505.0, 0.0, 5.0));
+        assert!(!streamer.is_loaded(CellCoord::new(0, 0)));
+        assert!(streamer.is_loaded(CellCoord::new(50, 0)));
+        assert_eq!(log.borrow().unloads.len(), 5);
+        assert_eq!(harness.streamed(), 5);
+    }
+
+    #[test]
+    fn loads_are_spread_over_updates_nearest_first() {
+        let (mut streamer, log) = streamer(2);
+        let mut harness = Harness::new(empty_assets());
+        let focus = vec3(5.0, 0.0, 5.0);
+
+        let mut counts = Vec::new();
+        for _ in 0..4 {
+            harness.update(&mut streamer, focus);
+            counts.push(streamer.loaded_cells().count());
+        }
+        assert_eq!(counts, [2, 4, 5, 5]);
+        assert_eq!(log.borrow().loads[0], CellCoord::new(0, 0));
+    }
+
+    #[test]
+    fn cells_between_the_load_and_unload_radius_stay_loaded() {
+        let (mut streamer, _) = streamer(16);
+        let mut harness = Harness::new(empty_assets());
+        let cell = CellCoord::new(0, 0);
+
+        harness.update(&mut streamer, vec3(5.0, 0.0, 5.0));
+        assert!(streamer.is_loaded(cell));
+
+        // 20 units from the cell's center: too far to load it, too near to unload it.
+        harness.update(&mut streamer, vec3(25.0, 0.0, 5.0));
+        assert!(streamer.is_loaded(cell));
+
+        harness.update(&mut streamer, vec3(35.0, 0.0, 5.0));
+        assert!(!streamer.is_loaded(cell));
+    }
+
+    #[test]
+    fn cell_assets_are_decoded_before_the_cell_loads() {
+        let dir = std::env::temp_dir().join(format!("streaming_{}", std::process::id()));
+        let cache = dir.join(".cache");
+        std::fs::create_dir_all(cache.join("cooked")).unwrap();
+        let mesh = Guid::from_u128(1);
+        let source = dir.join("cube.obj");
+        std::fs::write(&source, "").unwrap();
+        AssetMeta {
+            guid: mesh,
+            import: Default::default(),
+        }
+        .save(AssetMeta::meta_path_for(&source))
+        .unwrap();
+        write_emesh(
+            &resolve_cooked_path(&cache, &mesh, "emesh"),
+            &[Vertex::default(); 3],
+            VertexEncoding::Full,
+            None,
+            None,
+            &[0, 1, 2],
+        )
+        .unwrap();
+        let registry = AssetRegistry::scan(&cache, &dir, None).unwrap();
+        let mut harness = Harness::new(AssetContext::new(cache, dir.clone(), registry));
+
+        let log = Rc::new(RefCell::new(Log::default()));
+        let mut streamer = WorldStreamer::new(StreamingSettings {
+            cell_size: 10.0,
+            load_radius: 1.0,
+            unload_radius: 25.0,
+            max_loads_per_update: 1,
+        });
+        streamer.add_loader(Box::new(Recorder {
+            log: log.clone(),
+            mesh: Some(mesh),
+        }));
+        let cell = CellCoord::new(0, 0);
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while !streamer.is_loaded(cell) && Instant::now() < deadline {
+            harness.update(&mut streamer, vec3(5.0, 0.0, 5.0));
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        std::fs::remove_dir_all(&dir).ok();
+
+        assert!(streamer.is_loaded(cell));
+        assert!(!streamer.is_pending(cell));
+        assert_eq!(log.borrow().mesh_ready, [true]);
+    }
+}
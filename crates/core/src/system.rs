@@ -1,22 +1,26 @@
 use crate::asset_context::AssetContext;
+use crate::behavior_tree::BehaviorTree;
 use common::{Guid, MeshHandle};
 use ecs::command_buffer::Commands;
 use ecs::component::archetype::Archetype;
+use ecs::component::sparse_set::SparseSets;
 use ecs::query::{Query, QueryParameter};
+use ecs::resource::{Res, ResMut, ResourceError, Resources};
 use input::InputManager;
 use material::material_manager::{MaterialHandle, MaterialManager};
-use std::any::{Any, TypeId};
-use std::cell::RefCell;
-use std::collections::HashMap;
+use spatial::SpatialWorld;
 use std::marker::PhantomData;
-use std::rc::Rc;
+use std::sync::Arc;
 
 pub struct Context<'a> {
     pub dt: f32,
     pub assets: &'a mut AssetContext,
     pub material_manager: &'a mut MaterialManager,
     pub input: &'a InputManager,
-    pub custom: &'a HashMap<TypeId, Rc<RefCell<dyn Any>>>,
+    pub resources: &'a Resources,
+    /// Collider bounds as synced at the end of the previous frame, for raycasts and
+    /// overlap queries.
+    pub spatial: &'a SpatialWorld,
 }
 
 impl<'a> Context<'a> {
@@ -31,33 +35,179 @@ impl<'a> Context<'a> {
         self.material_manager
             .get_or_insert(guid, || assets.build_material(guid))
     }
+
+    /// Loads a behavior tree by GUID, returning the cached tree if already loaded.
+    pub fn load_behavior_tree(&mut self, guid: Guid) -> Arc<BehaviorTree> {
+        self.assets.load_behavior_tree(guid)
+    }
+
+    /// Borrows a resource registered on the `EngineContext`. Panics, naming the type,
+    /// if it is missing or already mutably borrowed.
+    pub fn res<T: 'static>(&self) -> Res<'a, T> {
+        self.resources.get::<T>()
+    }
+
+    /// Mutably borrows a resource registered on the `EngineContext`. Panics, naming the
+    /// type, if it is missing or already borrowed.
+    pub fn res_mut<T: 'static>(&self) -> ResMut<'a, T> {
+        self.resources.get_mut::<T>()
+    }
+
+    pub fn try_res<T: 'static>(&self) -> Result<Res<'a, T>, ResourceError> {
+        self.resources.try_get::<T>()
+    }
+
+    pub fn try_res_mut<T: 'static>(&self) -> Result<ResMut<'a, T>, ResourceError> {
+        self.resources.try_get_mut::<T>()
+    }
 }
 
+type SystemFn<T> = dyn Fn(Query<'_, T>, &mut Context, &mut Commands);
+
+/// Decides each frame whether a system runs. See [`System::run_if`].
+pub type RunCondition = dyn Fn(&Context) -> bool;
+
 pub struct System<T: 'static + QueryParameter> {
-    func: fn(Query<'_, T>, &mut Context, &mut Commands),
+    func: Box<SystemFn<T>>,
+    name: &'static str,
+    conditions: Vec<Box<RunCondition>>,
     _phantom: PhantomData<T>,
 }
 
 impl<T: 'static + QueryParameter> System<T> {
-    pub fn new(func: fn(Query<'_, T>, &mut Context, &mut Commands)) -> Self {
+    /// The system is named after `func`'s type, which for a function item is its path.
+    pub fn new<F>(func: F) -> Self
+    where
+        F: Fn(Query<'_, T>, &mut Context, &mut Commands) + 'static,
+    {
         Self {
-            func,
+            func: Box::new(func),
+            name: std::any::type_name::<F>(),
+            conditions: Vec::new(),
             _phantom: PhantomData,
         }
     }
+
+    /// Replaces the name shown in traces and matched by [`SystemToggles::find`], e.g.
+    /// for a closure.
+    pub fn with_name(mut self, name: &'static str) -> Self {
+        self.name = name;
+        self
+    }
+
+    /// Only runs the system on frames where `condition` holds; with several conditions,
+    /// all must hold. [`crate::run_condition`] has the common ones:
+    /// `System::new(menu_system).run_if(resource_equals(GameState::Paused))`.
+    pub fn run_if(mut self, condition: impl Fn(&Context) -> bool + 'static) -> Self {
+        self.conditions.push(Box::new(condition));
+        self
+    }
 }
 
 impl<T> SystemFunction for System<T>
 where
     T: for<'a> QueryParameter + 'static,
 {
-    fn run(&self, archetypes: &mut Vec<Archetype>, ctx: &mut Context, commands: &mut Commands) {
-        let mut query = Query::new(archetypes);
+    fn run(
+        &self,
+        archetypes: &mut Vec<Archetype>,
+        sparse_sets: &mut SparseSets,
+        ctx: &mut Context,
+        commands: &mut Commands,
+    ) {
+        let mut query = Query::new(archetypes, sparse_sets);
         query.build_matches();
         (self.func)(query, ctx, commands);
     }
+
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn should_run(&self, ctx: &Context) -> bool {
+        self.conditions.iter().all(|condition| condition(ctx))
+    }
 }
 
 pub trait SystemFunction {
-    fn run(&self, archetypes: &mut Vec<Archetype>, ctx: &mut Context, commands: &mut Commands);
+    fn run(
+        &self,
+        archetypes: &mut Vec<Archetype>,
+        sparse_sets: &mut SparseSets,
+        ctx: &mut Context,
+        commands: &mut Commands,
+    );
+
+    /// Shown in profiling traces.
+    fn name(&self) -> &'static str {
+        std::any::type_name::<Self>()
+    }
+
+    /// Whether the system runs this frame. Checked before every run.
+    fn should_run(&self, _ctx: &Context) -> bool {
+        true
+    }
+}
+
+/// Id of a system registered on the `EngineContext`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SystemId(usize);
+
+/// Resource switching registered systems on and off at runtime, e.g. expensive or debug
+/// systems from a console:
+///
+/// ```ignore
+/// let mut toggles = ctx.res_mut::<SystemToggles>();
+/// if let Some(id) = toggles.find("debug_draw_system") {
+///     toggles.set_enabled(id, false);
+/// }
+/// ```
+///
+/// A disabled system is skipped without checking its run conditions. Systems owned by
+/// game states are not listed; they run while their state is active.
+#[derive(Debug, Default)]
+pub struct SystemToggles {
+    /// Name and enabled flag, indexed by `SystemId`.
+    systems: Vec<(&'static str, bool)>,
+}
+
+impl SystemToggles {
+    /// Adds an enabled system. Called by the engine when a system is registered.
+    pub(crate) fn register(&mut self, name: &'static str) -> SystemId {
+        self.systems.push((name, true));
+        SystemId(self.systems.len() - 1)
+    }
+
+    pub fn set_enabled(&mut self, id: SystemId, enabled: bool) {
+        self.systems[id.0].1 = enabled;
+    }
+
+    pub fn is_enabled(&self, id: SystemId) -> bool {
+        self.systems[id.0].1
+    }
+
+    pub fn name(&self, id: SystemId) -> &'static str {
+        self.systems[id.0].0
+    }
+
+    /// The first system named `name`, either in full or by its last path segment.
+    pub fn find(&self, name: &str) -> Option<SystemId> {
+        self.systems
+            .iter()
+            .position(|(full, _)| {
+                *full == name
+                    || full
+                        .strip_suffix(name)
+                        .is_some_and(|prefix| prefix.ends_with("::"))
+            })
+            .map(SystemId)
+    }
+
+    /// Every registered system with its name and whether it is enabled, in run order.
+    pub fn iter(&self) -> impl Iterator<Item = (SystemId, &'static str, bool)> + '_ {
+        self.systems
+            .iter()
+            .enumerate()
+            .map(|(index, &(name, enabled))| (SystemId(index), name, enabled))
+    }
 }
\ No newline at end of file
@@ -1,11 +1,20 @@
-use crate::components::{CameraComponent, CameraControllerComponent};
+use crate::components::{
+    CameraComponent, CameraControllerComponent, OrbitCameraControllerComponent, SpringArmComponent,
+};
 use crate::system::Context;
+use crate::tween::{Tween, TweenCompleted, Tweenable};
+use crate::types::transform::Transform;
 use crate::TransformComponent;
+use ecs::entity::Entity;
+use ecs::event::Events;
 use ecs::query::Query;
-use input::AxisAction;
-use nalgebra_glm::{identity, rotate_x, rotate_y, vec3, Vec4};
+use input::{AxisAction, KeyCode, MouseButton};
+use nalgebra_glm::{identity, rotate_x, rotate_y, vec3, Vec3, Vec4};
 use ecs::command_buffer::Commands;
 
+/// Speed change per notch of the scroll wheel.
+const SCROLL_SPEED_STEP: f32 = 1.1;
+
 pub fn basic_camera_system(
     mut query: Query<(
         &mut CameraComponent,
@@ -20,29 +29,245 @@ pub fn basic_camera_system(
             let delta = context.dt;
             let input = context.input;
 
+            // Mouse deltas are already per frame, so they are not scaled by delta time.
             let mouse_x = input.get_axis(AxisAction::MOUSE_X);
             let mouse_y = input.get_axis(AxisAction::MOUSE_Y);
 
-            controller.yaw -= mouse_x * delta;
-            controller.pitch -= mouse_y * delta;
+            controller.yaw -= mouse_x * controller.sensitivity;
+            controller.pitch -= mouse_y * controller.sensitivity;
 
             controller.pitch = controller
                 .pitch
                 .clamp(-89.0_f32.to_radians(), 89.0_f32.to_radians());
 
-            transform.rotation.x = controller.pitch;
-            transform.rotation.y = controller.yaw;
+            let wheel = input.get_mouse_wheel();
+            if wheel != 0.0 {
+                controller.speed = (controller.speed * SCROLL_SPEED_STEP.powf(wheel))
+                    .clamp(controller.min_speed, controller.max_speed);
+            }
+
+            let (yaw, pitch) = controller.smooth_rotation(delta);
+            transform.rotation.x = pitch;
+            transform.rotation.y = yaw;
 
             let movement_x = input.get_axis("horizontal");
             let movement_z = -input.get_axis("vertical");
 
-            let rot_x = rotate_x(&identity(), controller.pitch);
-            let rot_y = rotate_y(&identity(), controller.yaw);
+            let rot_x = rotate_x(&identity(), pitch);
+            let rot_y = rotate_y(&identity(), yaw);
             let rotation = rot_y * rot_x;
 
-            let velocity =
-                rotation * (Vec4::new(movement_x, 0.0, movement_z, 0.0) * controller.speed * delta);
-            transform.location += vec3(velocity.x, velocity.y, velocity.z);
+            let mut speed = controller.speed;
+            if input.is_key_down(KeyCode::Shift) {
+                speed *= controller.sprint_multiplier;
+            }
+
+            let direction = rotation * Vec4::new(movement_x, 0.0, movement_z, 0.0);
+            let target = vec3(direction.x, direction.y, direction.z) * speed;
+            let velocity = controller.smooth_velocity(target, delta);
+            transform.location += velocity * delta;
+        }
+    }
+}
+
+pub fn orbit_camera_system(
+    mut query: Query<(
+        &mut CameraComponent,
+        &mut TransformComponent,
+        &mut OrbitCameraControllerComponent,
+        Option<&mut SpringArmComponent>,
+    )>,
+    context: &mut Context,
+    _commands: &mut Commands,
+) {
+    for (camera, transform, orbit, spring_arm) in query.iter() {
+        if !camera.active {
+            continue;
         }
+        let input = context.input;
+        let mouse_x = input.get_axis(AxisAction::MOUSE_X);
+        let mouse_y = input.get_axis(AxisAction::MOUSE_Y);
+
+        if input.is_mouse_button_down(MouseButton::Right) {
+            orbit.yaw -= mouse_x * orbit.sensitivity;
+            orbit.pitch -= mouse_y * orbit.sensitivity;
+            orbit.pitch = orbit
+                .pitch
+                .clamp(-89.0_f32.to_radians(), 89.0_f32.to_radians());
+        }
+
+        let rotation = rotate_y(&identity(), orbit.yaw) * rotate_x(&identity(), orbit.pitch);
+        let axis = |x: f32, y: f32, z: f32| -> Vec3 {
+            let v = rotation * Vec4::new(x, y, z, 0.0);
+            vec3(v.x, v.y, v.z)
+        };
+
+        if input.is_mouse_button_down(MouseButton::Middle) {
+            let scale = orbit.pan_speed * orbit.distance;
+            orbit.target += (axis(-mouse_x, 0.0, 0.0) + axis(0.0, mouse_y, 0.0)) * scale;
+        }
+
+        let wheel = input.get_mouse_wheel();
+        if wheel != 0.0 {
+            orbit.distance = (orbit.distance / orbit.zoom_step.powf(wheel))
+                .clamp(orbit.min_distance, orbit.max_distance);
+        }
+
+        // The camera looks along -Z, so it sits behind the target along +Z.
+        let arm = axis(0.0, 0.0, orbit.distance);
+        let length = match spring_arm {
+            Some(spring_arm) => spring_arm.update(context.spatial, orbit.target, arm, context.dt),
+            None => orbit.distance,
+        };
+        transform.location = orbit.target + axis(0.0, 0.0, length);
+        transform.rotation.x = orbit.pitch;
+        transform.rotation.y = orbit.yaw;
+    }
+}
+
+/// Advances every `Tween<T>` and sends a [`TweenCompleted`] for each completed cycle, if
+/// `Events<TweenCompleted<T>>` is registered. Registered by the engine for `f32`, `Vec3`
+/// and `Transform`; register it yourself for other `Tweenable` types.
+pub fn tween_system<T: Tweenable>(
+    mut query: Query<(Entity, &mut Tween<T>)>,
+    context: &mut Context,
+    _commands: &mut Commands,
+) {
+    let mut events = context.try_res_mut::<Events<TweenCompleted<T>>>().ok();
+    for (entity, tween) in query.iter() {
+        if tween.tick(context.dt) {
+            if let Some(events) = events.as_mut() {
+                events.send(TweenCompleted::new(entity, tween));
+            }
+        }
+    }
+}
+
+/// Writes the current value of each `Tween<Transform>` into its entity's transform. A
+/// finished tween writes its end value once and then leaves the transform alone.
+pub fn tween_transform_system(
+    mut query: Query<(&mut Tween<Transform>, &mut TransformComponent)>,
+    _context: &mut Context,
+    _commands: &mut Commands,
+) {
+    for (tween, transform) in query.iter() {
+        if tween.is_finished() && !tween.just_completed() {
+            continue;
+        }
+        transform.0 = tween.value();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TestContext;
+    use ecs::testing::TestWorld;
+    use input::{AxisBinding, InputBinding};
+
+    #[test]
+    fn fly_camera_moves_forward_while_key_is_held() {
+        let camera = CameraComponent {
+            near_clip: 0.1,
+            far_clip: 100.0,
+            fov: 60.0,
+            active: true,
+        };
+        let controller = CameraControllerComponent::new(6.0).with_smoothing(0.0);
+        let mut world =
+            TestWorld::new().with_entity((camera, TransformComponent::default(), controller));
+
+        let mut ctx = TestContext::new().with_dt(0.5);
+        let input = ctx.input_mut();
+        input.bind_action("forward", vec![InputBinding::Key(KeyCode::W)]);
+        input.bind_action("backward", vec![InputBinding::Key(KeyCode::S)]);
+        input.bind_axis(
+            AxisAction::VERTICAL,
+            AxisBinding::Composite {
+                positive: "forward".into(),
+                negative: "backward".into(),
+                scale: 1.0,
+            },
+        );
+
+        input.on_key_pressed(KeyCode::W);
+        ctx.run_system(&mut world, basic_camera_system);
+        ctx.run_system(&mut world, basic_camera_system);
+
+        let transform = world.get::<TransformComponent>(world.entity(0));
+        assert_eq!(transform.location, vec3(0.0, 0.0, -6.0));
+    }
+
+    #[test]
+    fn finished_transform_tweens_report_completion_and_release_the_transform() {
+        let end = Transform::default().with_location(vec3(2.0, 0.0, 0.0));
+        let mut world = TestWorld::new().with_entity((
+            TransformComponent::default(),
+            Tween::new(Transform::default(), end, 1.0),
+        ));
+        let mut ctx = TestContext::new()
+            .with_dt(0.6)
+            .with_resource(Events::<TweenCompleted<Transform>>::new());
+        let frame = |ctx: &mut TestContext, world: &mut TestWorld| {
+            ctx.run_system(world, tween_system::<Transform>);
+            ctx.run_system(world, tween_transform_system);
+            ctx.resources().get_mut::<Events<TweenCompleted<Transform>>>().update();
+        };
+
+        frame(&mut ctx, &mut world);
+        assert!(ctx.resources().get::<Events<TweenCompleted<Transform>>>().is_empty());
+        frame(&mut ctx, &mut world);
+        let events = ctx
+            .resources()
+            .get::<Events<TweenCompleted<Transform>>>()
+            .iter()
+            .copied()
+            .collect::<Vec<_>>();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].entity, world.entity(0));
+        assert!(events[0].finished);
+        let location = world.get::<TransformComponent>(world.entity(0)).location;
+        assert_eq!(location, end.location);
+
+        // Other systems may move the entity once the tween is done.
+        for transform in world.world_mut().query::<&mut TransformComponent>().iter() {
+            transform.location = vec3(0.0, 5.0, 0.0);
+        }
+        frame(&mut ctx, &mut world);
+        let location = world.get::<TransformComponent>(world.entity(0)).location;
+        assert_eq!(location, vec3(0.0, 5.0, 0.0));
+        assert!(ctx.resources().get::<Events<TweenCompleted<Transform>>>().is_empty());
+    }
+
+    #[test]
+    fn spring_arm_pulls_camera_in_front_of_walls_and_eases_back() {
+        let camera = CameraComponent {
+            near_clip: 0.1,
+            far_clip: 100.0,
+            fov: 60.0,
+            active: true,
+        };
+        let mut orbit = OrbitCameraControllerComponent::new(Vec3::zeros(), 10.0);
+        orbit.pitch = 0.0;
+        let mut world = TestWorld::new().with_entity((
+            camera,
+            TransformComponent::default(),
+            orbit,
+            SpringArmComponent::new(0.5),
+        ));
+
+        let mut ctx = TestContext::new().with_dt(0.1);
+        let wall = ctx.spatial_mut().register_collider(spatial::Shape::Cuboid {
+            half_extents: vec3(5.0, 5.0, 0.5),
+        });
+        ctx.spatial_mut().insert_collider(wall, vec3(0.0, 0.0, 5.0));
+        ctx.run_system(&mut world, orbit_camera_system);
+        let z = world.get::<TransformComponent>(world.entity(0)).location.z;
+        assert!((z - 4.0).abs() < 1e-4, "camera at z = {z}");
+
+        ctx.spatial_mut().clear_tree();
+        ctx.run_system(&mut world, orbit_camera_system);
+        let z = world.get::<TransformComponent>(world.entity(0)).location.z;
+        assert!(z > 4.0 && z < 10.0, "camera at z = {z}");
     }
-}
\ No newline at end of file
+}
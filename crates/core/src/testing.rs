@@ -0,0 +1,165 @@
+//! Headless harness for running engine systems in unit tests, with a real
+//! `InputManager` driven by hand instead of a window.
+//!
+//! ```ignore
+//! let mut world = TestWorld::new().with_entity((TransformComponent::default(), Player));
+//! let mut ctx = TestContext::new();
+//! ctx.input_mut().bind_action("jump", vec![InputBinding::Key(KeyCode::Space)]);
+//! ctx.input_mut().on_key_pressed(KeyCode::Space);
+//! ctx.run_system(&mut world, jump_system);
+//! ```
+
+use crate::app_exit::AppExit;
+use crate::asset_context::AssetContext;
+use crate::system::{Context, System, SystemFunction};
+use crate::time::Time;
+use crate::{EngineConfig, EngineContext};
+use ecs::command_buffer::Commands;
+use ecs::query::{Query, QueryParameter};
+use ecs::resource::Resources;
+use ecs::testing::TestWorld;
+use input::InputManager;
+use material::material_manager::MaterialManager;
+use project::AssetRegistry;
+use spatial::SpatialWorld;
+use std::path::PathBuf;
+
+/// The parts of `EngineContext` a system sees through `Context`, with no window, GPU or
+/// project on disk. Asset loads panic, as there is no content to load from.
+pub struct TestContext {
+    assets: AssetContext,
+    material_manager: MaterialManager,
+    input: InputManager,
+    resources: Resources,
+    spatial_world: SpatialWorld,
+    dt: f32,
+}
+
+impl Default for TestContext {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TestContext {
+    /// Starts with `Time` and `AppExit` resources and a 60 Hz frame delta.
+    pub fn new() -> Self {
+        let dt = 1.0 / 60.0;
+        let mut resources = Resources::new();
+        resources.insert(Time::new(dt));
+        resources.insert(AppExit::default());
+
+        Self {
+            assets: AssetContext::new(PathBuf::new(), PathBuf::new(), AssetRegistry::default()),
+            material_manager: MaterialManager::new(),
+            input: InputManager::new(),
+            resources,
+            spatial_world: SpatialWorld::new(),
+            dt,
+        }
+    }
+
+    /// Frame delta passed to systems and added to `Time` on every run.
+    pub fn with_dt(mut self, dt: f32) -> Self {
+        self.dt = dt;
+        self
+    }
+
+    pub fn with_resource<T: 'static>(mut self, value: T) -> Self {
+        self.resources.insert(value);
+        self
+    }
+
+    /// Bind actions and axes here, and feed key and mouse events through the `on_*`
+    /// handlers before a run.
+    pub fn input_mut(&mut self) -> &mut InputManager {
+        &mut self.input
+    }
+
+    pub fn resources(&self) -> &Resources {
+        &self.resources
+    }
+
+    /// Register and insert colliders here for systems that query `Context::spatial`.
+    pub fn spatial_mut(&mut self) -> &mut SpatialWorld {
+        &mut self.spatial_world
+    }
+
+    /// Runs one frame of `system` on `world` in engine order: input is updated, `Time`
+    /// advances, the system runs and its commands are applied, then per-frame input is
+    /// cleared. Keys stay held across runs until released.
+    pub fn run_system<T: QueryParameter + 'static>(
+        &mut self,
+        world: &mut TestWorld,
+        system: fn(Query<'_, T>, &mut Context, &mut Commands),
+    ) -> &mut Self {
+        self.run(world, &System::new(system))
+    }
+
+    /// Like [`Self::run_system`], for a built [`System`] or any other `SystemFunction`.
+    /// The frame advances either way; the system only runs if its run conditions hold.
+    pub fn run(&mut self, world: &mut TestWorld, system: &dyn SystemFunction) -> &mut Self {
+        self.input.update(self.dt);
+        {
+            let mut time = self.resources.get_mut::<Time>();
+            time.delta = self.dt;
+            time.elapsed += self.dt as f64;
+            time.frame += 1;
+        }
+
+        world.run_with(|access| {
+            let mut ctx = Context {
+                dt: self.dt,
+                assets: &mut self.assets,
+                material_manager: &mut self.material_manager,
+                input: &self.input,
+                resources: &self.resources,
+                spatial: &self.spatial_world,
+            };
+            if !system.should_run(&ctx) {
+                return;
+            }
+            system.run(
+                access.archetypes,
+                access.sparse_sets,
+                &mut ctx,
+                &mut access.commands,
+            );
+        });
+
+        self.input.end_frame();
+        self
+    }
+}
+
+/// A whole `EngineContext` with default settings and no window, GPU or project on disk,
+/// for tests that drive the engine's own update, such as saving or game states. Asset
+/// loads panic, as with `TestContext`.
+pub fn headless_engine() -> EngineContext {
+    let config = EngineConfig {
+        name: String::new(),
+        window_title: String::new(),
+        content_dir: PathBuf::new(),
+        cache_dir: PathBuf::new(),
+        window_resolution: Default::default(),
+        window_mode: Default::default(),
+        display: Default::default(),
+        vsync: false,
+        latency_mode: Default::default(),
+        max_frames_in_flight: 1,
+        target_fps: 0,
+        unfocused_fps_cap: 0,
+        max_frame_delta: 0.0,
+        delta_smoothing: 0.0,
+        async_compute: false,
+        gpu_diagnostics: false,
+        gpu_picking: false,
+        gpu_particles: false,
+        texture_budget_mb: 0,
+        fixed_timestep: 1.0 / 60.0,
+        shadow_settings: Default::default(),
+        asset_gc: Default::default(),
+    };
+    let assets = AssetContext::new(PathBuf::new(), PathBuf::new(), AssetRegistry::default());
+    EngineContext::new(config, assets)
+}
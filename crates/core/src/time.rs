@@ -0,0 +1,91 @@
+/// Frame timing, available to systems as a resource through `Context::res::<Time>()`.
+#[derive(Debug, Clone)]
+pub struct Time {
+    /// Seconds since the previous frame, clamped and smoothed by the engine's
+    /// [`DeltaFilter`].
+    pub delta: f32,
+    /// Measured wall-clock seconds since the previous frame, before filtering.
+    pub raw_delta: f32,
+    /// Seconds since the engine started.
+    pub elapsed: f64,
+    /// Number of frames updated so far.
+    pub frame: u64,
+    /// Step length used by fixed-timestep systems, in seconds.
+    pub fixed_delta: f32,
+    /// How far the next fixed step is, as a fraction of `fixed_delta`. Use it to
+    /// interpolate rendering between fixed updates.
+    pub fixed_alpha: f32,
+}
+
+impl Time {
+    pub fn new(fixed_delta: f32) -> Self {
+        Self {
+            delta: 0.0,
+            raw_delta: 0.0,
+            elapsed: 0.0,
+            frame: 0,
+            fixed_delta,
+            fixed_alpha: 0.0,
+        }
+    }
+}
+
+impl Default for Time {
+    fn default() -> Self {
+        Self::new(1.0 / 60.0)
+    }
+}
+
+/// Conditions measured frame times before systems see them. Long stalls (loading hitches,
+/// a debugger breakpoint, window drags) are clamped to `max_delta` so physics and cameras
+/// don't jump, and an optional exponential moving average evens out scheduler jitter.
+#[derive(Debug, Clone)]
+pub struct DeltaFilter {
+    /// Longest delta handed to systems, in seconds. 0 disables clamping.
+    pub max_delta: f32,
+    /// Weight of the previous smoothed delta, in `0.0..1.0`. 0 disables smoothing.
+    pub smoothing: f32,
+    smoothed: Option<f32>,
+}
+
+impl DeltaFilter {
+    pub fn new(max_delta: f32, smoothing: f32) -> Self {
+        Self {
+            max_delta,
+            smoothing: smoothing.clamp(0.0, 0.99),
+            smoothed: None,
+        }
+    }
+
+    /// Clamps then smooths `raw`, returning the delta systems should use.
+    pub fn filter(&mut self, raw: f32) -> f32 {
+        let clamped = if self.max_delta > 0.0 {
+            raw.min(self.max_delta)
+        } else {
+            raw
+        };
+        let delta = match self.smoothed {
+            Some(previous) => previous + (clamped - previous) * (1.0 - self.smoothing),
+            None => clamped,
+        };
+        self.smoothed = Some(delta);
+        delta
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spikes_are_clamped_and_smoothed() {
+        let mut filter = DeltaFilter::new(0.1, 0.0);
+        assert_eq!(filter.filter(0.016), 0.016);
+        assert_eq!(filter.filter(5.0), 0.1);
+
+        let mut filter = DeltaFilter::new(0.1, 0.5);
+        assert_eq!(filter.filter(0.02), 0.02);
+        assert!((filter.filter(0.04) - 0.03).abs() < 1e-6);
+        assert!((filter.filter(5.0) - 0.065).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,141 @@
+//! Trails and ribbons. [`trail_system`] records where each entity with a
+//! [`TrailComponent`] has been; the renderer turns the points into a camera-facing strip
+//! drawn with the transparent effects.
+
+use crate::components::TransformComponent;
+use crate::curve::Curve;
+use crate::particles::ParticleBlendMode;
+use crate::system::Context;
+use common::Color;
+use ecs::command_buffer::Commands;
+use ecs::component::Component;
+use ecs::query::Query;
+use nalgebra_glm::Vec3;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+/// A recorded position of a trail.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TrailPoint {
+    pub position: Vec3,
+    /// Seconds since the entity left this point.
+    pub age: f32,
+}
+
+/// Leaves a ribbon behind the entity, for projectiles, sword swings or motion indicators.
+/// Width and color follow curves over each point's normalized age: 0 at the entity, 1
+/// where points expire.
+#[derive(Clone, Debug, Component, PartialEq, Serialize, Deserialize)]
+pub struct TrailComponent {
+    /// Whether new points are recorded. Recorded points fade out either way.
+    pub emitting: bool,
+    /// Seconds a point lasts after the entity left it.
+    pub lifetime: f32,
+    /// Distance the entity moves before a new point is recorded. Smaller values give
+    /// smoother curves at the cost of more points.
+    pub min_vertex_distance: f32,
+    /// Oldest points are dropped beyond this count.
+    pub max_points: usize,
+    /// World-space width over normalized age.
+    pub width: Curve<f32>,
+    /// Color over normalized age, alpha included.
+    pub color: Curve<Color>,
+    pub blend: ParticleBlendMode,
+    /// Newest first; the first point follows the entity.
+    #[serde(skip)]
+    points: VecDeque<TrailPoint>,
+}
+
+impl Default for TrailComponent {
+    fn default() -> Self {
+        Self {
+            emitting: true,
+            lifetime: 0.5,
+            min_vertex_distance: 0.1,
+            max_points: 64,
+            width: Curve::linear(0.2, 0.0),
+            color: Curve::linear(Color::WHITE, Color::WHITE.with_alpha(0.0)),
+            blend: ParticleBlendMode::Additive,
+            points: VecDeque::new(),
+        }
+    }
+}
+
+impl TrailComponent {
+    /// Recorded points, newest first.
+    pub fn points(&self) -> impl ExactSizeIterator<Item = &TrailPoint> {
+        self.points.iter()
+    }
+
+    /// Forgets every recorded point, e.g. after teleporting the entity.
+    pub fn clear(&mut self) {
+        self.points.clear();
+    }
+
+    /// Ages the points by `dt`, drops expired ones and records `position`.
+    pub fn update(&mut self, position: Vec3, dt: f32) {
+        for point in &mut self.points {
+            point.age += dt;
+        }
+        while self
+            .points
+            .back()
+            .is_some_and(|point| point.age >= self.lifetime)
+        {
+            self.points.pop_back();
+        }
+        if !self.emitting {
+            return;
+        }
+
+        let head = TrailPoint { position, age: 0.0 };
+        // The first point follows the entity until it is far enough from the second one
+        // to stay behind.
+        let head_follows = self.points.len() >= 2
+            && (self.points[0].position - self.points[1].position).norm()
+                < self.min_vertex_distance;
+        if head_follows {
+            self.points[0] = head;
+        } else {
+            self.points.push_front(head);
+        }
+        self.points.truncate(self.max_points.max(2));
+    }
+}
+
+/// Records the positions of every entity with a [`TrailComponent`].
+pub fn trail_system(
+    mut query: Query<(&mut TransformComponent, &mut TrailComponent)>,
+    context: &mut Context,
+    _commands: &mut Commands,
+) {
+    for (transform, trail) in query.iter() {
+        trail.update(transform.location, context.dt);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn points_are_recorded_by_distance_and_expire() {
+        let mut trail = TrailComponent {
+            lifetime: 1.0,
+            min_vertex_distance: 1.0,
+            ..TrailComponent::default()
+        };
+        for x in [0.0, 0.3, 0.6, 1.2, 1.5] {
+            trail.update(Vec3::new(x, 0.0, 0.0), 0.1);
+        }
+        let xs: Vec<f32> = trail.points().map(|point| point.position.x).collect();
+        // 1.2 left the point at 0 behind; the head follows the entity.
+        assert_eq!(xs, [1.5, 1.2, 0.0]);
+
+        trail.emitting = false;
+        trail.update(Vec3::zeros(), 0.65);
+        assert_eq!(trail.points().len(), 2);
+        trail.update(Vec3::zeros(), 0.5);
+        assert_eq!(trail.points().len(), 0);
+    }
+}
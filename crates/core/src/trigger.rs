@@ -0,0 +1,207 @@
+//! Trigger volumes: overlap detection without physics response.
+//!
+//! An entity with a [`TriggerVolumeComponent`] and a `TransformComponent` is tested
+//! each frame against the bounds of every collider in the spatial world. Changes are
+//! reported as [`TriggerEvent`]s in the `Events<TriggerEvent>` resource, readable by
+//! systems on the following frame:
+//!
+//! ```ignore
+//! for event in ctx.res::<Events<TriggerEvent>>().iter() {
+//!     if event.kind == TriggerEventKind::Enter { /* open the door */ }
+//! }
+//! ```
+
+use crate::TransformComponent;
+use ecs::component::Component;
+use ecs::entity::Entity;
+use ecs::event::Events;
+use ecs::world::World;
+use nalgebra_glm::Vec3;
+use serde::{Deserialize, Serialize};
+use spatial::{ColliderComponent, ColliderId, SpatialWorld, AABB};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum TriggerShape {
+    /// Axis-aligned box with per-axis half-extents.
+    Box {
+        half_extents: Vec3,
+    },
+    Sphere {
+        radius: f32,
+    },
+}
+
+#[derive(Debug, Clone, Copy, Component, Serialize, Deserialize)]
+pub struct TriggerVolumeComponent {
+    pub shape: TriggerShape,
+    /// Center of the volume relative to the entity's location.
+    pub offset: Vec3,
+    /// Disabled volumes report `Exit` for everything inside and then stay silent.
+    pub enabled: bool,
+}
+
+impl TriggerVolumeComponent {
+    pub fn new_box(half_extents: Vec3) -> Self {
+        Self {
+            shape: TriggerShape::Box { half_extents },
+            offset: Vec3::zeros(),
+            enabled: true,
+        }
+    }
+
+    pub fn new_sphere(radius: f32) -> Self {
+        Self {
+            shape: TriggerShape::Sphere { radius },
+            offset: Vec3::zeros(),
+            enabled: true,
+        }
+    }
+
+    pub fn with_offset(mut self, offset: Vec3) -> Self {
+        self.offset = offset;
+        self
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TriggerEventKind {
+    /// `other` started overlapping the volume this frame.
+    Enter,
+    /// `other` was already overlapping and still is.
+    Stay,
+    /// `other` stopped overlapping, or one of the two entities was removed.
+    Exit,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TriggerEvent {
+    pub trigger: Entity,
+    pub other: Entity,
+    pub kind: TriggerEventKind,
+}
+
+/// Overlaps seen on the previous frame, per trigger entity.
+#[derive(Default)]
+pub(crate) struct TriggerTracker {
+    overlaps: HashMap<Entity, Vec<Entity>>,
+}
+
+impl TriggerTracker {
+    /// Tests every enabled trigger against the current spatial tree and sends events
+    /// for what changed. Call after the tree has been rebuilt for this frame.
+    pub fn update(
+        &mut self,
+        world: &World,
+        spatial_world: &SpatialWorld,
+        events: &mut Events<TriggerEvent>,
+    ) {
+        let mut current: HashMap<Entity, Vec<Entity>> = HashMap::new();
+        let mut owners: Option<HashMap<ColliderId, Entity>> = None;
+
+        world.for_each_component::<TriggerVolumeComponent>(|trigger, volume| {
+            if !volume.enabled {
+                return;
+            }
+            let Some(transform) = world.get::<TransformComponent>(trigger) else {
+                return;
+            };
+            let owners = owners.get_or_insert_with(|| {
+                let mut owners = HashMap::new();
+                world.for_each_component::<ColliderComponent>(|entity, collider| {
+                    owners.insert(collider.id, entity);
+                });
+                owners
+            });
+
+            let center = transform.location + volume.offset;
+            let hits = match volume.shape {
+                TriggerShape::Box { half_extents } => spatial_world
+                    .query_aabb(&AABB::new(center - half_extents, center + half_extents)),
+                TriggerShape::Sphere { radius } => spatial_world.query_sphere(center, radius),
+            };
+            let mut inside: Vec<Entity> = hits
+                .into_iter()
+                .filter_map(|id| owners.get(&id).copied())
+                .filter(|&other| other != trigger)
+                .collect();
+            inside.dedup();
+            current.insert(trigger, inside);
+        });
+
+        for (&trigger, inside) in &current {
+            let previous = self.overlaps.get(&trigger);
+            for &other in inside {
+                let kind = if previous.is_some_and(|p| p.contains(&other)) {
+                    TriggerEventKind::Stay
+                } else {
+                    TriggerEventKind::Enter
+                };
+                events.send(TriggerEvent {
+                    trigger,
+                    other,
+                    kind,
+                });
+            }
+        }
+        for (&trigger, previous) in &self.overlaps {
+            let inside = current.get(&trigger);
+            for &other in previous {
+                if !inside.is_some_and(|i| i.contains(&other)) {
+                    events.send(TriggerEvent {
+                        trigger,
+                        other,
+                        kind: TriggerEventKind::Exit,
+                    });
+                }
+            }
+        }
+        self.overlaps = current;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::transform::Transform;
+    use spatial::Shape;
+
+    #[test]
+    fn reports_enter_stay_and_exit() {
+        let mut world = World::new();
+        let mut spatial_world = SpatialWorld::new();
+        let mut events = Events::new();
+        let mut tracker = TriggerTracker::default();
+
+        let trigger = world.create_entity((
+            TransformComponent(Transform::default()),
+            TriggerVolumeComponent::new_sphere(1.0),
+        ));
+        let collider = spatial_world.register_collider(Shape::Sphere { radius: 0.5 });
+        let other = world.create_entity((ColliderComponent { id: collider },));
+
+        let mut step = |center: Vec3, events: &mut Events<TriggerEvent>| {
+            spatial_world.clear_tree();
+            spatial_world.insert_collider(collider, center);
+            tracker.update(&world, &spatial_world, events);
+            events.update();
+            events.iter().map(|e| e.kind).collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            step(Vec3::new(1.2, 0.0, 0.0), &mut events),
+            [TriggerEventKind::Enter]
+        );
+        assert_eq!(
+            step(Vec3::new(1.2, 0.0, 0.0), &mut events),
+            [TriggerEventKind::Stay]
+        );
+        assert_eq!(
+            step(Vec3::new(5.0, 0.0, 0.0), &mut events),
+            [TriggerEventKind::Exit]
+        );
+        assert!(events
+            .iter()
+            .all(|e| e.trigger == trigger && e.other == other));
+    }
+}
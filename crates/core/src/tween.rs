@@ -0,0 +1,296 @@
+use crate::types::transform::Transform;
+use common::Color;
+use ecs::component::Component;
+use ecs::entity::Entity;
+use nalgebra_glm::Vec3;
+use serde::{Deserialize, Serialize};
+use std::f32::consts::PI;
+use std::marker::PhantomData;
+
+/// Maps linear progress in `[0, 1]` to eased progress. Elastic curves overshoot the range.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Easing {
+    #[default]
+    Linear,
+    CubicIn,
+    CubicOut,
+    CubicInOut,
+    ElasticIn,
+    ElasticOut,
+    ElasticInOut,
+}
+
+impl Easing {
+    pub fn apply(self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        // Period of the elastic oscillation, in units of progress.
+        const C4: f32 = 2.0 * PI / 3.0;
+        const C5: f32 = 2.0 * PI / 4.5;
+
+        match self {
+            Easing::Linear => t,
+            Easing::CubicIn => t * t * t,
+            Easing::CubicOut => 1.0 - (1.0 - t).powi(3),
+            Easing::CubicInOut => {
+                if t < 0.5 {
+                    4.0 * t * t * t
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(3) / 2.0
+                }
+            }
+            _ if t == 0.0 || t == 1.0 => t,
+            Easing::ElasticIn => -(2.0f32.powf(10.0 * t - 10.0)) * ((t * 10.0 - 10.75) * C4).sin(),
+            Easing::ElasticOut => 2.0f32.powf(-10.0 * t) * ((t * 10.0 - 0.75) * C4).sin() + 1.0,
+            Easing::ElasticInOut => {
+                if t < 0.5 {
+                    -(2.0f32.powf(20.0 * t - 10.0) * ((20.0 * t - 11.125) * C5).sin()) / 2.0
+                } else {
+                    2.0f32.powf(-20.0 * t + 10.0) * ((20.0 * t - 11.125) * C5).sin() / 2.0 + 1.0
+                }
+            }
+        }
+    }
+}
+
+/// What a tween does when it reaches the end.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum TweenRepeat {
+    /// Stop at `to` and report finished.
+    #[default]
+    Once,
+    /// Jump back to `from` and play again.
+    Loop,
+    /// Play back and forth between `from` and `to`.
+    PingPong,
+}
+
+/// A value that can be animated by a `Tween`.
+pub trait Tweenable: Copy + 'static {
+    fn lerp(&self, to: &Self, t: f32) -> Self;
+}
+
+impl Tweenable for f32 {
+    fn lerp(&self, to: &Self, t: f32) -> Self {
+        self + (to - self) * t
+    }
+}
+
+impl Tweenable for Vec3 {
+    fn lerp(&self, to: &Self, t: f32) -> Self {
+        self + (to - self) * t
+    }
+}
+
+/// Interpolates the linear components, alpha included.
+impl Tweenable for Color {
+    fn lerp(&self, to: &Self, t: f32) -> Self {
+        let lerp = |a: f32, b: f32| a + (b - a) * t;
+        Color::linear_rgba(
+            lerp(self.r, to.r),
+            lerp(self.g, to.g),
+            lerp(self.b, to.b),
+            lerp(self.a, to.a),
+        )
+    }
+}
+
+impl Tweenable for Transform {
+    /// Interpolates location, Euler rotation and scale component-wise.
+    fn lerp(&self, to: &Self, t: f32) -> Self {
+        Transform {
+            location: self.location.lerp(&to.location, t),
+            rotation: self.rotation.lerp(&to.rotation, t),
+            scale: self.scale.lerp(&to.scale, t),
+        }
+    }
+}
+
+/// Sent through `Events<TweenCompleted<T>>` by `systems::tween_system::<T>` each frame a
+/// `Tween<T>` completes a cycle. The engine registers the events for the tween types it
+/// drives; register them with `EngineContext::add_event` for others.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TweenCompleted<T: Tweenable> {
+    pub entity: Entity,
+    /// Cycles the tween has completed, this one included.
+    pub cycles: u32,
+    /// True if the tween finished and stopped.
+    pub finished: bool,
+    value: PhantomData<fn() -> T>,
+}
+
+impl<T: Tweenable> TweenCompleted<T> {
+    pub fn new(entity: Entity, tween: &Tween<T>) -> Self {
+        Self {
+            entity,
+            cycles: tween.completed_cycles(),
+            finished: tween.is_finished(),
+            value: PhantomData,
+        }
+    }
+}
+
+/// Animates a value from `from` to `to` over `duration` seconds.
+///
+/// `Tween<f32>`, `Tween<Vec3>` and `Tween<Transform>` are advanced by the engine every
+/// frame; a `Tween<Transform>` also drives the entity's `TransformComponent`. Other
+/// `Tweenable` types can be driven by registering `systems::tween_system::<T>`.
+#[derive(Component, Debug, Clone, Serialize, Deserialize)]
+pub struct Tween<T: Tweenable> {
+    pub from: T,
+    pub to: T,
+    pub duration: f32,
+    pub easing: Easing,
+    pub repeat: TweenRepeat,
+    pub paused: bool,
+    elapsed: f32,
+    reversed: bool,
+    finished: bool,
+    just_completed: bool,
+    completed_cycles: u32,
+}
+
+impl<T: Tweenable> Tween<T> {
+    pub fn new(from: T, to: T, duration: f32) -> Self {
+        Self {
+            from,
+            to,
+            duration,
+            easing: Easing::Linear,
+            repeat: TweenRepeat::Once,
+            paused: false,
+            elapsed: 0.0,
+            reversed: false,
+            finished: false,
+            just_completed: false,
+            completed_cycles: 0,
+        }
+    }
+
+    pub fn with_easing(mut self, easing: Easing) -> Self {
+        self.easing = easing;
+        self
+    }
+
+    pub fn with_repeat(mut self, repeat: TweenRepeat) -> Self {
+        self.repeat = repeat;
+        self
+    }
+
+    /// Advances the tween by `dt` seconds. Returns true if a cycle completed during this tick.
+    pub fn tick(&mut self, dt: f32) -> bool {
+        self.just_completed = false;
+        if self.paused || self.finished {
+            return false;
+        }
+
+        if self.duration <= 0.0 {
+            self.elapsed = 0.0;
+            self.finished = true;
+            self.just_completed = true;
+            self.completed_cycles += 1;
+            return true;
+        }
+
+        self.elapsed += dt;
+        while self.elapsed >= self.duration {
+            self.just_completed = true;
+            self.completed_cycles += 1;
+            match self.repeat {
+                TweenRepeat::Once => {
+                    self.elapsed = self.duration;
+                    self.finished = true;
+                    break;
+                }
+                TweenRepeat::Loop => self.elapsed -= self.duration,
+                TweenRepeat::PingPong => {
+                    self.elapsed -= self.duration;
+                    self.reversed = !self.reversed;
+                }
+            }
+        }
+        self.just_completed
+    }
+
+    /// Linear progress through the current cycle in `[0, 1]`, before easing.
+    pub fn progress(&self) -> f32 {
+        if self.duration <= 0.0 {
+            return 1.0;
+        }
+        let t = (self.elapsed / self.duration).clamp(0.0, 1.0);
+        if self.reversed { 1.0 - t } else { t }
+    }
+
+    /// The eased value at the current time.
+    pub fn value(&self) -> T {
+        self.from.lerp(&self.to, self.easing.apply(self.progress()))
+    }
+
+    /// True once a `TweenRepeat::Once` tween has reached `to`. Looping tweens never finish.
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// True for the frame in which a cycle completed. Systems after the tween's can also
+    /// read [`TweenCompleted`] events the next frame.
+    pub fn just_completed(&self) -> bool {
+        self.just_completed
+    }
+
+    /// Number of cycles completed so far, counting each direction of a ping-pong.
+    pub fn completed_cycles(&self) -> u32 {
+        self.completed_cycles
+    }
+
+    /// Rewinds to `from` and resumes playback.
+    pub fn restart(&mut self) {
+        self.elapsed = 0.0;
+        self.reversed = false;
+        self.finished = false;
+        self.just_completed = false;
+        self.paused = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn easings_hit_endpoints() {
+        for easing in [
+            Easing::Linear,
+            Easing::CubicIn,
+            Easing::CubicOut,
+            Easing::CubicInOut,
+            Easing::ElasticIn,
+            Easing::ElasticOut,
+            Easing::ElasticInOut,
+        ] {
+            assert!(easing.apply(0.0).abs() < 1e-5, "{:?}", easing);
+            assert!((easing.apply(1.0) - 1.0).abs() < 1e-5, "{:?}", easing);
+        }
+    }
+
+    #[test]
+    fn once_finishes_and_reports_completion() {
+        let mut tween = Tween::new(0.0f32, 10.0, 1.0);
+        assert!(!tween.tick(0.5));
+        assert!((tween.value() - 5.0).abs() < 1e-5);
+
+        assert!(tween.tick(0.75));
+        assert!(tween.is_finished());
+        assert_eq!(tween.value(), 10.0);
+
+        assert!(!tween.tick(0.1));
+        assert!(!tween.just_completed());
+    }
+
+    #[test]
+    fn ping_pong_reverses() {
+        let mut tween = Tween::new(0.0f32, 10.0, 1.0).with_repeat(TweenRepeat::PingPong);
+        tween.tick(1.25);
+        assert!(tween.just_completed());
+        assert!((tween.value() - 7.5).abs() < 1e-5);
+        assert!(!tween.is_finished());
+    }
+}
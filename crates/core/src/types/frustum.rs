@@ -0,0 +1,153 @@
+use nalgebra_glm::{self as glm, Mat4, Vec3, Vec4};
+use spatial::AABB;
+
+/// Depth range of the clip space a projection matrix maps to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClipDepth {
+    /// Vulkan/D3D convention, e.g. `perspective_rh_zo`.
+    ZeroToOne,
+    /// OpenGL convention, e.g. `nalgebra_glm::perspective`.
+    NegativeOneToOne,
+}
+
+/// Plane `normal · p + distance = 0`, with the normal pointing into the frustum.
+#[derive(Debug, Clone, Copy)]
+pub struct Plane {
+    pub normal: Vec3,
+    pub distance: f32,
+}
+
+impl Plane {
+    fn from_row(row: Vec4) -> Self {
+        let normal = row.xyz();
+        let length = glm::length(&normal);
+        Self {
+            normal: normal / length,
+            distance: row.w / length,
+        }
+    }
+
+    /// Signed distance from the plane; positive on the inside.
+    pub fn signed_distance(&self, point: &Vec3) -> f32 {
+        glm::dot(&self.normal, point) + self.distance
+    }
+}
+
+/// World-space view volume of a camera, extracted from its view-projection matrix.
+///
+/// Planes are ordered left, right, bottom, top, near, far. Corners are ordered near
+/// plane first, each plane as (-x,-y), (+x,-y), (+x,+y), (-x,+y) in clip space.
+#[derive(Debug, Clone)]
+pub struct Frustum {
+    planes: [Plane; 6],
+    corners: [Vec3; 8],
+}
+
+impl Frustum {
+    pub fn from_view_proj(view_proj: &Mat4, depth: ClipDepth) -> Self {
+        let row = |i: usize| view_proj.row(i).transpose();
+        let (r0, r1, r2, r3) = (row(0), row(1), row(2), row(3));
+        let near = match depth {
+            ClipDepth::ZeroToOne => r2,
+            ClipDepth::NegativeOneToOne => r3 + r2,
+        };
+        let planes = [
+            Plane::from_row(r3 + r0),
+            Plane::from_row(r3 - r0),
+            Plane::from_row(r3 + r1),
+            Plane::from_row(r3 - r1),
+            Plane::from_row(near),
+            Plane::from_row(r3 - r2),
+        ];
+
+        let near_z = match depth {
+            ClipDepth::ZeroToOne => 0.0,
+            ClipDepth::NegativeOneToOne => -1.0,
+        };
+        let inverse = glm::inverse(view_proj);
+        let mut corners = [Vec3::zeros(); 8];
+        for (i, corner) in corners.iter_mut().enumerate() {
+            let x = if matches!(i % 4, 1 | 2) { 1.0 } else { -1.0 };
+            let y = if i % 4 >= 2 { 1.0 } else { -1.0 };
+            let z = if i < 4 { near_z } else { 1.0 };
+            let world = inverse * Vec4::new(x, y, z, 1.0);
+            *corner = world.xyz() / world.w;
+        }
+
+        Self { planes, corners }
+    }
+
+    /// Frustum of a perspective camera with a Vulkan-style projection, as used by the
+    /// renderer. `fov` is the vertical field of view in degrees.
+    pub fn from_perspective(view: &Mat4, aspect: f32, fov: f32, near: f32, far: f32) -> Self {
+        let proj = glm::perspective_rh_zo(aspect, fov.to_radians(), near, far);
+        Self::from_view_proj(&(proj * view), ClipDepth::ZeroToOne)
+    }
+
+    pub fn planes(&self) -> &[Plane; 6] {
+        &self.planes
+    }
+
+    pub fn corners(&self) -> &[Vec3; 8] {
+        &self.corners
+    }
+
+    /// Center of the corners and the distance to the farthest one.
+    pub fn bounding_sphere(&self) -> (Vec3, f32) {
+        let center = self.corners.iter().sum::<Vec3>() / 8.0;
+        let radius = self
+            .corners
+            .iter()
+            .map(|corner| glm::distance(corner, &center))
+            .fold(0.0, f32::max);
+        (center, radius)
+    }
+
+    pub fn contains_point(&self, point: &Vec3) -> bool {
+        self.planes.iter().all(|plane| plane.signed_distance(point) >= 0.0)
+    }
+
+    /// True if the sphere is at least partly inside.
+    pub fn contains_sphere(&self, center: &Vec3, radius: f32) -> bool {
+        self.planes
+            .iter()
+            .all(|plane| plane.signed_distance(center) >= -radius)
+    }
+
+    /// True if the box is at least partly inside. Conservative: boxes near a corner of
+    /// the frustum may be reported visible while just outside it.
+    pub fn contains_aabb(&self, aabb: &AABB) -> bool {
+        self.planes.iter().all(|plane| {
+            // The box corner furthest along the plane normal.
+            let positive = Vec3::new(
+                if plane.normal.x >= 0.0 { aabb.upper.x } else { aabb.lower.x },
+                if plane.normal.y >= 0.0 { aabb.upper.y } else { aabb.lower.y },
+                if plane.normal.z >= 0.0 { aabb.upper.z } else { aabb.lower.z },
+            );
+            plane.signed_distance(&positive) >= 0.0
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn culls_against_camera_volume() {
+        let view = glm::look_at(&Vec3::zeros(), &Vec3::new(0.0, 0.0, -1.0), &Vec3::y());
+        let frustum = Frustum::from_perspective(&view, 1.0, 90.0, 0.1, 100.0);
+
+        assert!(frustum.contains_point(&Vec3::new(0.0, 0.0, -10.0)));
+        assert!(!frustum.contains_point(&Vec3::new(0.0, 0.0, 10.0)));
+        assert!(!frustum.contains_point(&Vec3::new(0.0, 0.0, -200.0)));
+        assert!(frustum.contains_sphere(&Vec3::new(12.0, 0.0, -10.0), 3.0));
+        assert!(!frustum.contains_aabb(&AABB::new(
+            Vec3::new(20.0, -1.0, -11.0),
+            Vec3::new(22.0, 1.0, -9.0)
+        )));
+
+        let far = frustum.corners()[6];
+        assert!((far - Vec3::new(100.0, 100.0, -100.0)).norm() < 1e-2);
+    }
+}
@@ -1,2 +1,3 @@
+pub mod frustum;
 pub mod resolution;
 pub mod transform;
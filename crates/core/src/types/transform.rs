@@ -1,6 +1,9 @@
-use nalgebra_glm::{identity, rotate_x, rotate_y, rotate_z, scaling, translate, vec3, Mat4, Vec3};
+use nalgebra_glm::{
+    identity, rotate_x, rotate_y, rotate_z, scaling, translate, vec3, Mat4, Vec3, Vec4,
+};
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Debug, Copy)]
+#[derive(Clone, Debug, Copy, PartialEq, Serialize, Deserialize)]
 pub struct Transform {
     pub location: Vec3,
     pub rotation: Vec3,
@@ -54,6 +57,16 @@ impl Transform {
         translation * rotation * scale
     }
 
+    /// Returns the world-space forward vector (-Z rotated by `rotation`), matching
+    /// the orientation used by `get_model_matrix` and `get_view_matrix`.
+    pub fn forward(&self) -> Vec3 {
+        let rot_x = rotate_x(&identity(), self.rotation.x);
+        let rot_y = rotate_y(&identity(), self.rotation.y);
+        let rot_z = rotate_z(&identity(), self.rotation.z);
+        let forward = rot_z * rot_y * rot_x * Vec4::new(0.0, 0.0, -1.0, 0.0);
+        nalgebra_glm::normalize(&vec3(forward.x, forward.y, forward.z))
+    }
+
     pub fn get_view_matrix(&self) -> Mat4 {
         let translation = translate(&identity(), &self.location);
         let rot_x = rotate_x(&identity(), self.rotation.x);
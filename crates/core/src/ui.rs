@@ -0,0 +1,526 @@
+//! Retained UI: nodes positioned by anchors or by row/column containers, laid out into
+//! window-pixel rects once per frame, with pointer hit-testing for menus and HUDs.
+//!
+//! Give an entity a [`UiNodeComponent`] to put it on screen. The engine lays every node
+//! out before systems run and publishes the result in the [`UiLayout`] resource, where
+//! systems read rects, hover and clicks. Coordinates are logical pixels, origin top-left:
+//! window pixels divided by the display's scale factor, so layouts keep their size on
+//! high-DPI monitors.
+
+use common::Color;
+use ecs::component::Component;
+use ecs::entity::Entity;
+use ecs::world::World;
+use input::{CursorMode, InputManager, MouseButton};
+use nalgebra_glm::Vec2;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Axis-aligned screen rectangle in logical pixels.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct UiRect {
+    pub min: Vec2,
+    pub max: Vec2,
+}
+
+impl UiRect {
+    pub fn new(min: Vec2, max: Vec2) -> Self {
+        Self { min, max }
+    }
+
+    pub fn size(&self) -> Vec2 {
+        self.max - self.min
+    }
+
+    pub fn contains(&self, point: Vec2) -> bool {
+        point.x >= self.min.x
+            && point.x < self.max.x
+            && point.y >= self.min.y
+            && point.y < self.max.y
+    }
+
+    /// The rect shrunk by `edges` on each side. Never inverts.
+    pub fn inset(&self, edges: &UiEdges) -> Self {
+        let min = self.min + Vec2::new(edges.left, edges.top);
+        let max = self.max - Vec2::new(edges.right, edges.bottom);
+        Self {
+            min,
+            max: max.sup(&min),
+        }
+    }
+}
+
+/// Per-side distances in pixels, for margins and padding.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct UiEdges {
+    pub left: f32,
+    pub top: f32,
+    pub right: f32,
+    pub bottom: f32,
+}
+
+impl UiEdges {
+    pub fn all(value: f32) -> Self {
+        Self {
+            left: value,
+            top: value,
+            right: value,
+            bottom: value,
+        }
+    }
+}
+
+/// Where a node attaches inside its parent, as fractions of the parent rect. On an axis
+/// where `min == max` the node keeps its `size` and sits at that point; where they differ
+/// it stretches between them.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct UiAnchors {
+    pub min: Vec2,
+    pub max: Vec2,
+}
+
+impl UiAnchors {
+    pub fn point(x: f32, y: f32) -> Self {
+        Self {
+            min: Vec2::new(x, y),
+            max: Vec2::new(x, y),
+        }
+    }
+
+    pub fn top_left() -> Self {
+        Self::point(0.0, 0.0)
+    }
+
+    pub fn center() -> Self {
+        Self::point(0.5, 0.5)
+    }
+
+    /// Stretches over the whole parent.
+    pub fn fill() -> Self {
+        Self {
+            min: Vec2::zeros(),
+            max: Vec2::new(1.0, 1.0),
+        }
+    }
+}
+
+/// How a node arranges its children.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum UiContainer {
+    /// Each child places itself with its anchors.
+    #[default]
+    Free,
+    /// Children are stacked left to right and fill the height.
+    Row,
+    /// Children are stacked top to bottom and fill the width.
+    Column,
+}
+
+/// A UI element. Roots (no `parent`, or a parent that is not a UI node) are laid out
+/// against the whole window.
+#[derive(Debug, Clone, Component, Serialize, Deserialize)]
+pub struct UiNodeComponent {
+    /// Saved separately, as a reference snapshots can remap.
+    #[serde(skip)]
+    pub parent: Option<Entity>,
+    /// Sibling order, for container placement and drawing. Lower comes first.
+    pub order: i32,
+    pub anchors: UiAnchors,
+    /// On stretched axes, the distance kept from each anchor edge. On point-anchored
+    /// axes, `left`/`top` offset the node from its anchor point.
+    pub margins: UiEdges,
+    /// Size in pixels on point-anchored axes, and along the main axis inside a container.
+    pub size: Vec2,
+    /// Which point of the node sits on its anchor, as a fraction of its size.
+    pub pivot: Vec2,
+    pub container: UiContainer,
+    /// Space between this node's edges and its children.
+    pub padding: UiEdges,
+    /// Space between children of a `Row` or `Column`.
+    pub gap: f32,
+    /// Share of the free space this node takes inside a `Row` or `Column`, on top of
+    /// its `size`. 0 keeps the size as-is.
+    pub grow: f32,
+    /// Fill color. `None` draws nothing.
+    pub background: Option<Color>,
+    /// Takes part in hit-testing and blocks the pointer from nodes below it.
+    pub interactive: bool,
+    pub visible: bool,
+}
+
+impl UiNodeComponent {
+    /// A visible, non-interactive node of `size` pixels at the parent's top-left corner.
+    pub fn new(size: Vec2) -> Self {
+        Self {
+            parent: None,
+            order: 0,
+            anchors: UiAnchors::top_left(),
+            margins: UiEdges::default(),
+            size,
+            pivot: Vec2::zeros(),
+            container: UiContainer::Free,
+            padding: UiEdges::default(),
+            gap: 0.0,
+            grow: 0.0,
+            background: None,
+            interactive: false,
+            visible: true,
+        }
+    }
+
+    pub fn with_parent(mut self, parent: Entity) -> Self {
+        self.parent = Some(parent);
+        self
+    }
+
+    pub fn with_anchors(mut self, anchors: UiAnchors, pivot: Vec2) -> Self {
+        self.anchors = anchors;
+        self.pivot = pivot;
+        self
+    }
+
+    pub fn with_background(mut self, color: Color) -> Self {
+        self.background = Some(color);
+        self
+    }
+
+    pub fn interactive(mut self) -> Self {
+        self.interactive = true;
+        self
+    }
+}
+
+/// One filled rectangle of the UI, in draw order.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct UiDrawRect {
+    pub rect: UiRect,
+    pub color: Color,
+}
+
+/// Result of the last UI layout and pointer pass.
+#[derive(Debug)]
+pub struct UiLayout {
+    /// Window size in logical pixels.
+    viewport: Vec2,
+    /// Physical pixels per logical pixel.
+    scale_factor: f32,
+    rects: HashMap<Entity, UiRect>,
+    /// Visible interactive nodes, back to front.
+    interactive: Vec<Entity>,
+    draw_list: Vec<UiDrawRect>,
+    hovered: Option<Entity>,
+    pressed: Option<Entity>,
+    clicked: Option<Entity>,
+}
+
+impl Default for UiLayout {
+    fn default() -> Self {
+        Self {
+            viewport: Vec2::zeros(),
+            scale_factor: 1.0,
+            rects: HashMap::new(),
+            interactive: Vec::new(),
+            draw_list: Vec::new(),
+            hovered: None,
+            pressed: None,
+            clicked: None,
+        }
+    }
+}
+
+impl UiLayout {
+    /// Sets the window size in physical pixels and the display's scale factor. The
+    /// platform layer calls this before each frame.
+    pub fn set_viewport(&mut self, width: f32, height: f32, scale_factor: f32) {
+        self.scale_factor = if scale_factor > 0.0 { scale_factor } else { 1.0 };
+        self.viewport = Vec2::new(width, height) / self.scale_factor;
+    }
+
+    /// Window size in logical pixels, the space rects and [`Draw2D`](crate::draw2d::Draw2D)
+    /// shapes are placed in.
+    pub fn viewport(&self) -> Vec2 {
+        self.viewport
+    }
+
+    /// Window size in physical pixels.
+    pub fn physical_viewport(&self) -> Vec2 {
+        self.viewport * self.scale_factor
+    }
+
+    /// Physical pixels per logical pixel, 2 on a typical high-DPI monitor.
+    pub fn scale_factor(&self) -> f32 {
+        self.scale_factor
+    }
+
+    /// Converts a point in physical window pixels, such as the cursor, to logical pixels.
+    pub fn to_logical(&self, point: Vec2) -> Vec2 {
+        point / self.scale_factor
+    }
+
+    /// Screen rect of `entity` from the last layout, if it is a visible UI node.
+    pub fn rect(&self, entity: Entity) -> Option<UiRect> {
+        self.rects.get(&entity).copied()
+    }
+
+    /// Filled rects to draw this frame, back to front.
+    pub fn draw_list(&self) -> &[UiDrawRect] {
+        &self.draw_list
+    }
+
+    /// Topmost interactive node under the pointer.
+    pub fn hovered(&self) -> Option<Entity> {
+        self.hovered
+    }
+
+    /// Node the primary button went down on, while it is held.
+    pub fn pressed(&self) -> Option<Entity> {
+        self.pressed
+    }
+
+    /// Node clicked this frame: the button went down and up over it.
+    pub fn clicked(&self) -> Option<Entity> {
+        self.clicked
+    }
+
+    pub fn is_hovered(&self, entity: Entity) -> bool {
+        self.hovered == Some(entity)
+    }
+
+    pub fn is_clicked(&self, entity: Entity) -> bool {
+        self.clicked == Some(entity)
+    }
+
+    /// Topmost interactive node containing `point`.
+    pub fn hit_test(&self, point: Vec2) -> Option<Entity> {
+        self.interactive
+            .iter()
+            .rev()
+            .copied()
+            .find(|e| self.rects[e].contains(point))
+    }
+}
+
+/// Lays out every UI node in `world` into `layout` and updates pointer state. Hit-testing
+/// only runs while the cursor is free; the result is reported to `input` so gameplay can
+/// ignore clicks that land on the UI.
+pub(crate) fn update_ui(world: &World, layout: &mut UiLayout, input: &mut InputManager) {
+    let mut nodes = HashMap::new();
+    world.for_each_component::<UiNodeComponent>(|entity, node| {
+        nodes.insert(entity, node.clone());
+    });
+    let (rects, draw_order) = compute_layout(&nodes, layout.viewport);
+
+    layout.draw_list = draw_order
+        .iter()
+        .filter_map(|e| {
+            let color = nodes[e].background?;
+            Some(UiDrawRect {
+                rect: rects[e],
+                color,
+            })
+        })
+        .collect();
+    layout.interactive = draw_order
+        .into_iter()
+        .filter(|e| nodes[e].interactive)
+        .collect();
+    layout.rects = rects;
+
+    let pointer = match input.cursor_mode() {
+        CursorMode::Free | CursorMode::Hidden => input.get_mouse_position(),
+        CursorMode::Confined | CursorMode::Locked => None,
+    };
+    layout.hovered =
+        pointer.and_then(|[x, y]| layout.hit_test(layout.to_logical(Vec2::new(x, y))));
+
+    layout.clicked = None;
+    if input.is_mouse_button_just_pressed(MouseButton::Left) {
+        layout.pressed = layout.hovered;
+    }
+    if input.is_mouse_button_just_released(MouseButton::Left) {
+        if layout.pressed.is_some() && layout.pressed == layout.hovered {
+            layout.clicked = layout.pressed;
+        }
+        layout.pressed = None;
+    }
+    input.set_pointer_over_ui(layout.hovered.is_some());
+}
+
+/// Computes rects for visible nodes, top-down from the roots. Returns the rects and the
+/// draw order, parents before children and siblings by `order`.
+fn compute_layout(
+    nodes: &HashMap<Entity, UiNodeComponent>,
+    viewport: Vec2,
+) -> (HashMap<Entity, UiRect>, Vec<Entity>) {
+    let mut children: HashMap<Option<Entity>, Vec<Entity>> = HashMap::new();
+    for (&entity, node) in nodes {
+        let parent = node.parent.filter(|p| nodes.contains_key(p));
+        children.entry(parent).or_default().push(entity);
+    }
+    for list in children.values_mut() {
+        list.sort_by_key(|e| (nodes[e].order, e.index()));
+    }
+
+    let mut layout = (HashMap::new(), Vec::new());
+    let screen = UiRect::new(Vec2::zeros(), viewport);
+    layout_children(
+        None,
+        &screen,
+        UiContainer::Free,
+        0.0,
+        nodes,
+        &children,
+        &mut layout,
+    );
+    layout
+}
+
+/// Places the visible children of `parent` inside `content`, then recurses into each,
+/// so every subtree is drawn over the siblings before it.
+fn layout_children(
+    parent: Option<Entity>,
+    content: &UiRect,
+    container: UiContainer,
+    gap: f32,
+    nodes: &HashMap<Entity, UiNodeComponent>,
+    children: &HashMap<Option<Entity>, Vec<Entity>>,
+    out: &mut (HashMap<Entity, UiRect>, Vec<Entity>),
+) {
+    let Some(list) = children.get(&parent) else {
+        return;
+    };
+    let visible: Vec<Entity> = list.iter().copied().filter(|e| nodes[e].visible).collect();
+    let placed: Vec<UiRect> = match container {
+        UiContainer::Free => visible
+            .iter()
+            .map(|e| anchored_rect(&nodes[e], content))
+            .collect(),
+        UiContainer::Row => stacked_rects(&visible, nodes, content, gap, 0),
+        UiContainer::Column => stacked_rects(&visible, nodes, content, gap, 1),
+    };
+    for (entity, rect) in visible.into_iter().zip(placed) {
+        let node = &nodes[&entity];
+        out.0.insert(entity, rect);
+        out.1.push(entity);
+        let inner = rect.inset(&node.padding);
+        layout_children(
+            Some(entity),
+            &inner,
+            node.container,
+            node.gap,
+            nodes,
+            children,
+            out,
+        );
+    }
+}
+
+fn anchored_rect(node: &UiNodeComponent, parent: &UiRect) -> UiRect {
+    let parent_size = parent.size();
+    let mut min = Vec2::zeros();
+    let mut max = Vec2::zeros();
+    let start = [node.margins.left, node.margins.top];
+    let end = [node.margins.right, node.margins.bottom];
+    for axis in 0..2 {
+        let a0 = parent.min[axis] + node.anchors.min[axis] * parent_size[axis];
+        let a1 = parent.min[axis] + node.anchors.max[axis] * parent_size[axis];
+        if node.anchors.min[axis] == node.anchors.max[axis] {
+            min[axis] = a0 + start[axis] - node.pivot[axis] * node.size[axis];
+            max[axis] = min[axis] + node.size[axis];
+        } else {
+            min[axis] = a0 + start[axis];
+            max[axis] = (a1 - end[axis]).max(min[axis]);
+        }
+    }
+    UiRect::new(min, max)
+}
+
+/// Places `entities` one after another along `axis` (0 = x, 1 = y), sharing the space
+/// left after their sizes and gaps out by `grow`. They fill the cross axis.
+fn stacked_rects(
+    entities: &[Entity],
+    nodes: &HashMap<Entity, UiNodeComponent>,
+    content: &UiRect,
+    gap: f32,
+    axis: usize,
+) -> Vec<UiRect> {
+    let cross = 1 - axis;
+    let used: f32 = entities.iter().map(|e| nodes[e].size[axis]).sum::<f32>()
+        + gap * entities.len().saturating_sub(1) as f32;
+    let free = (content.size()[axis] - used).max(0.0);
+    let total_grow: f32 = entities.iter().map(|e| nodes[e].grow).sum();
+
+    let mut cursor = content.min[axis];
+    entities
+        .iter()
+        .map(|e| {
+            let node = &nodes[e];
+            let mut extent = node.size[axis];
+            if total_grow > 0.0 {
+                extent += free * node.grow / total_grow;
+            }
+            let mut min = Vec2::zeros();
+            let mut max = Vec2::zeros();
+            min[axis] = cursor;
+            max[axis] = cursor + extent;
+            min[cross] = content.min[cross];
+            max[cross] = content.max[cross];
+            cursor += extent + gap;
+            UiRect::new(min, max)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn anchors_and_rows_place_children() {
+        let panel = Entity(0);
+        let fixed = Entity(1);
+        let grown = Entity(2);
+        let mut nodes = HashMap::new();
+        let mut panel_node = UiNodeComponent::new(Vec2::new(200.0, 100.0))
+            .with_anchors(UiAnchors::center(), Vec2::new(0.5, 0.5));
+        panel_node.container = UiContainer::Row;
+        panel_node.padding = UiEdges::all(10.0);
+        panel_node.gap = 20.0;
+        nodes.insert(panel, panel_node);
+        nodes.insert(
+            fixed,
+            UiNodeComponent::new(Vec2::new(50.0, 0.0)).with_parent(panel),
+        );
+        let mut grown_node = UiNodeComponent::new(Vec2::zeros()).with_parent(panel);
+        grown_node.grow = 1.0;
+        grown_node.order = 1;
+        nodes.insert(grown, grown_node);
+
+        let (rects, order) = compute_layout(&nodes, Vec2::new(800.0, 600.0));
+        assert_eq!(order, vec![panel, fixed, grown]);
+        assert_eq!(
+            rects[&panel],
+            UiRect::new(Vec2::new(300.0, 250.0), Vec2::new(500.0, 350.0))
+        );
+        assert_eq!(
+            rects[&fixed],
+            UiRect::new(Vec2::new(310.0, 260.0), Vec2::new(360.0, 340.0))
+        );
+        assert_eq!(
+            rects[&grown],
+            UiRect::new(Vec2::new(380.0, 260.0), Vec2::new(490.0, 340.0))
+        );
+    }
+
+    #[test]
+    fn viewport_is_in_logical_pixels() {
+        let mut layout = UiLayout::default();
+        layout.set_viewport(1600.0, 1200.0, 2.0);
+        assert_eq!(layout.viewport(), Vec2::new(800.0, 600.0));
+        assert_eq!(layout.physical_viewport(), Vec2::new(1600.0, 1200.0));
+        assert_eq!(layout.to_logical(Vec2::new(300.0, 100.0)), Vec2::new(150.0, 50.0));
+
+        layout.set_viewport(800.0, 600.0, 0.0);
+        assert_eq!(layout.scale_factor(), 1.0);
+    }
+}
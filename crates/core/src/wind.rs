@@ -0,0 +1,44 @@
+//! Global wind for vertex animation. The renderer reads [`Wind`] every frame; entities
+//! with a [`VegetationComponent`](crate::VegetationComponent) sway in it.
+
+use nalgebra_glm::Vec3;
+use serde::{Deserialize, Serialize};
+
+/// Resource describing the wind over the whole scene.
+///
+/// From a system: `ctx.res_mut::<Wind>().strength = 2.0`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Wind {
+    /// Direction the wind blows towards. Only its horizontal part is used.
+    pub direction: Vec3,
+    /// Steady strength; 1 bends branches by their full `branch_sway`.
+    pub strength: f32,
+    /// How far gusts push the strength above and below `strength`, as a fraction of it.
+    pub gust_strength: f32,
+    /// Gusts per second passing a given point.
+    pub gust_frequency: f32,
+    /// World units across a gust, so nearby plants move together.
+    pub gust_size: f32,
+}
+
+impl Default for Wind {
+    fn default() -> Self {
+        Self {
+            direction: Vec3::new(1.0, 0.0, 0.0),
+            strength: 1.0,
+            gust_strength: 0.5,
+            gust_frequency: 0.2,
+            gust_size: 20.0,
+        }
+    }
+}
+
+impl Wind {
+    /// Normalized horizontal blowing direction, +X if `direction` has none.
+    pub fn horizontal_direction(&self) -> Vec3 {
+        let horizontal = Vec3::new(self.direction.x, 0.0, self.direction.z);
+        horizontal
+            .try_normalize(f32::EPSILON)
+            .unwrap_or_else(Vec3::x)
+    }
+}
@@ -0,0 +1,168 @@
+//! Storage and query benchmarks: `cargo bench -p ecs`. Compare runs before and after
+//! changes to archetype storage or query matching.
+
+use criterion::{BatchSize, Criterion, Throughput, criterion_group, criterion_main};
+use ecs::component::Component;
+use ecs::entity::Entity;
+use ecs::world::World;
+use std::hint::black_box;
+
+#[derive(Component, Clone, Copy)]
+struct Position([f32; 3]);
+
+#[derive(Component, Clone, Copy)]
+struct Velocity([f32; 3]);
+
+/// Gives entities that share `Position` and `Velocity` one archetype per `N`.
+#[derive(Component)]
+struct Fragment<const N: usize>;
+
+#[derive(Component)]
+#[component(storage = "sparse")]
+struct Selected;
+
+const SPAWN_COUNT: usize = 10_000;
+const QUERY_COUNT: usize = 1_000_000;
+const MIGRATE_COUNT: usize = 10_000;
+const FRAGMENTS: usize = 16;
+
+fn moving(index: usize) -> (Position, Velocity) {
+    let x = index as f32;
+    (Position([x, 0.0, -x]), Velocity([1.0, 0.5, 0.25]))
+}
+
+fn spawn_fragment<const N: usize>(world: &mut World, count: usize) {
+    for index in 0..count {
+        let (position, velocity) = moving(index);
+        world.create_entity((position, velocity, Fragment::<N>));
+    }
+}
+
+/// `count` moving entities spread evenly over [`FRAGMENTS`] archetypes.
+fn fragmented_world(count: usize) -> World {
+    let mut world = World::new();
+    let per_fragment = count / FRAGMENTS;
+    spawn_fragment::<0>(&mut world, per_fragment);
+    spawn_fragment::<1>(&mut world, per_fragment);
+    spawn_fragment::<2>(&mut world, per_fragment);
+    spawn_fragment::<3>(&mut world, per_fragment);
+    spawn_fragment::<4>(&mut world, per_fragment);
+    spawn_fragment::<5>(&mut world, per_fragment);
+    spawn_fragment::<6>(&mut world, per_fragment);
+    spawn_fragment::<7>(&mut world, per_fragment);
+    spawn_fragment::<8>(&mut world, per_fragment);
+    spawn_fragment::<9>(&mut world, per_fragment);
+    spawn_fragment::<10>(&mut world, per_fragment);
+    spawn_fragment::<11>(&mut world, per_fragment);
+    spawn_fragment::<12>(&mut world, per_fragment);
+    spawn_fragment::<13>(&mut world, per_fragment);
+    spawn_fragment::<14>(&mut world, per_fragment);
+    spawn_fragment::<15>(&mut world, per_fragment);
+    world
+}
+
+fn single_archetype_world(count: usize) -> (World, Vec<Entity>) {
+    let mut world = World::new();
+    let entities = (0..count)
+        .map(|index| world.create_entity(moving(index)))
+        .collect();
+    (world, entities)
+}
+
+fn integrate(world: &mut World) {
+    for (position, velocity) in world.query::<(&mut Position, &mut Velocity)>().iter() {
+        for axis in 0..3 {
+            position.0[axis] += velocity.0[axis] * 0.016;
+        }
+    }
+}
+
+fn spawn(c: &mut Criterion) {
+    let mut group = c.benchmark_group("spawn");
+    group.throughput(Throughput::Elements(SPAWN_COUNT as u64));
+    group.bench_function("empty_world", |b| {
+        b.iter_batched(
+            World::new,
+            |mut world| {
+                for index in 0..SPAWN_COUNT {
+                    world.create_entity(moving(index));
+                }
+                world
+            },
+            BatchSize::SmallInput,
+        )
+    });
+    // Existing archetype with its columns already grown.
+    group.bench_function("populated_world", |b| {
+        b.iter_batched(
+            || single_archetype_world(SPAWN_COUNT).0,
+            |mut world| {
+                for index in 0..SPAWN_COUNT {
+                    world.create_entity(moving(index));
+                }
+                world
+            },
+            BatchSize::LargeInput,
+        )
+    });
+    group.finish();
+}
+
+fn query_iteration(c: &mut Criterion) {
+    let mut group = c.benchmark_group("query_1m");
+    group.throughput(Throughput::Elements(QUERY_COUNT as u64));
+    group.sample_size(20);
+
+    let (mut world, _) = single_archetype_world(QUERY_COUNT);
+    group.bench_function("single_archetype", |b| b.iter(|| integrate(&mut world)));
+
+    let mut world = fragmented_world(QUERY_COUNT);
+    group.bench_function("16_archetypes", |b| b.iter(|| integrate(&mut world)));
+
+    // Matching cost alone, which every `World::query` call pays.
+    group.bench_function("build_matches", |b| {
+        b.iter(|| {
+            black_box(world.query::<(&mut Position, &mut Velocity)>());
+        })
+    });
+    group.finish();
+}
+
+/// Table components cannot be added to a live entity, so changing an entity's
+/// archetype means respawning it; sparse components toggle in place.
+fn add_remove(c: &mut Criterion) {
+    let mut group = c.benchmark_group("add_remove");
+    group.throughput(Throughput::Elements(MIGRATE_COUNT as u64));
+
+    group.bench_function("sparse_toggle", |b| {
+        let (mut world, entities) = single_archetype_world(MIGRATE_COUNT);
+        b.iter(|| {
+            for &entity in &entities {
+                world.insert_sparse(entity, Selected);
+            }
+            for &entity in &entities {
+                black_box(world.remove_sparse::<Selected>(entity));
+            }
+        })
+    });
+
+    group.bench_function("respawn_into_archetype", |b| {
+        b.iter_batched(
+            || single_archetype_world(MIGRATE_COUNT),
+            |(mut world, entities)| {
+                for entity in entities {
+                    let position = *world.get::<Position>(entity).unwrap();
+                    let velocity = *world.get::<Velocity>(entity).unwrap();
+                    world.remove_entity(entity);
+                    world.create_entity((position, velocity, Fragment::<0>));
+                }
+                world
+            },
+            BatchSize::LargeInput,
+        )
+    });
+    group.finish();
+}
+
+criterion_group!(benches, spawn, query_iteration, add_remove);
+criterion_main!(benches);
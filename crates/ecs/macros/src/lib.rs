@@ -1,8 +1,8 @@
 use proc_macro::TokenStream;
 use quote::quote;
-use syn::{DeriveInput, parse_macro_input};
+use syn::{DeriveInput, LitStr, parse_macro_input};
 
-#[proc_macro_derive(Component)]
+#[proc_macro_derive(Component, attributes(component))]
 pub fn derive_component(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
     let name = &input.ident;
@@ -10,8 +10,36 @@ pub fn derive_component(input: TokenStream) -> TokenStream {
     // Get generics if any
     let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
 
+    // `#[component(storage = "sparse")]` opts into sparse-set storage.
+    let mut storage = None;
+    for attr in input
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("component"))
+    {
+        let parsed = attr.parse_nested_meta(|meta| {
+            if !meta.path.is_ident("storage") {
+                return Err(meta.error("expected `storage`"));
+            }
+            let value: LitStr = meta.value()?.parse()?;
+            storage = Some(match value.value().as_str() {
+                "table" => quote! { ::ecs::component::StorageType::Table },
+                "sparse" => quote! { ::ecs::component::StorageType::Sparse },
+                _ => return Err(meta.error("expected \"table\" or \"sparse\"")),
+            });
+            Ok(())
+        });
+        if let Err(err) = parsed {
+            return err.to_compile_error().into();
+        }
+    }
+    let storage =
+        storage.map(|storage| quote! { const STORAGE: ::ecs::component::StorageType = #storage; });
+
     let expanded = quote! {
-        impl #impl_generics Component for #name #ty_generics #where_clause {}
+        impl #impl_generics Component for #name #ty_generics #where_clause {
+            #storage
+        }
     };
 
     TokenStream::from(expanded)
@@ -1,6 +1,8 @@
+use crate::component::Component;
 use crate::component::component_storage::ComponentInsertion;
 use crate::entity::Entity;
 use crate::world::{EntityAllocator, World};
+use std::any::TypeId;
 
 pub struct Commands<'a> {
     pub(crate) queue: Vec<Command>,
@@ -10,6 +12,8 @@ pub struct Commands<'a> {
 pub enum Command {
     SpawnEntity(Entity, Box<dyn FnOnce(&mut World)>),
     DespawnEntity(Entity),
+    InsertSparse(Entity, Box<dyn FnOnce(&mut World)>),
+    RemoveSparse(Entity, TypeId),
 }
 
 impl<'a> Commands<'a> {
@@ -31,6 +35,18 @@ impl<'a> Commands<'a> {
         self.queue.push(Command::DespawnEntity(entity));
     }
 
+    /// Queues [`World::insert_sparse`]. Skipped if the entity is despawned first.
+    pub fn insert_sparse<T: Component>(&mut self, entity: Entity, component: T) {
+        self.queue.push(Command::InsertSparse(
+            entity,
+            Box::new(move |w: &mut World| w.insert_sparse(entity, component)),
+        ));
+    }
+
+    pub fn remove_sparse<T: Component>(&mut self, entity: Entity) {
+        self.queue.push(Command::RemoveSparse(entity, TypeId::of::<T>()));
+    }
+
     pub fn into_queue(self) -> Vec<Command> {
         self.queue
     }
@@ -1,4 +1,5 @@
-use crate::component::Component;
+use crate::component::sparse_set::{SparseSet, SparseSetFactory};
+use crate::component::{Component, StorageType};
 use crate::entity::Entity;
 use std::any::{Any, TypeId};
 use std::collections::HashMap;
@@ -7,7 +8,7 @@ use std::error::Error;
 pub struct Archetype {
     pub components: HashMap<TypeId, usize>,
     pub(crate) columns: Vec<Column>,
-    entities: Vec<Entity>,
+    pub(crate) entities: Vec<Entity>,
 }
 
 impl Archetype {
@@ -78,8 +79,9 @@ pub struct Column {
 pub(crate) trait ColumnData: Any {
     fn push_erased(&mut self, value: ComponentValue) -> Result<(), Box<dyn Error>>;
     fn swap_remove_erased(&mut self, row: usize);
+    fn as_any(&self) -> &dyn Any;
     fn as_any_mut(&mut self) -> &mut dyn Any;
-    fn len(&self) -> usize;
+    fn type_name(&self) -> &'static str;
 }
 
 impl<T: Component + 'static> ColumnData for Vec<T> {
@@ -94,23 +96,37 @@ impl<T: Component + 'static> ColumnData for Vec<T> {
         self.swap_remove(row);
     }
 
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
     fn as_any_mut(&mut self) -> &mut dyn Any {
         self
     }
 
-    fn len(&self) -> usize {
-        self.len()
+    fn type_name(&self) -> &'static str {
+        std::any::type_name::<T>()
     }
 }
 
 pub(crate) type ColumnFactory = fn() -> Box<dyn ColumnData>;
 
-pub(crate) trait HasColumnFactory {
-    fn get_factory() -> ColumnFactory;
+/// Creates the storage a component's values go into, depending on its [`StorageType`].
+#[derive(Clone, Copy)]
+pub(crate) enum StorageFactory {
+    Table(ColumnFactory),
+    Sparse(SparseSetFactory),
 }
 
-impl<T: Component> HasColumnFactory for T {
-    fn get_factory() -> ColumnFactory {
-        || Box::new(Vec::<T>::new())
+pub(crate) trait HasStorageFactory {
+    fn get_factory() -> StorageFactory;
+}
+
+impl<T: Component> HasStorageFactory for T {
+    fn get_factory() -> StorageFactory {
+        match T::STORAGE {
+            StorageType::Table => StorageFactory::Table(|| Box::new(Vec::<T>::new())),
+            StorageType::Sparse => StorageFactory::Sparse(|| Box::new(SparseSet::<T>::new())),
+        }
     }
 }
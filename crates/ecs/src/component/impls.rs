@@ -1,13 +1,13 @@
-use crate::component::archetype::{ColumnFactory, ComponentValue, HasColumnFactory};
+use crate::component::archetype::{ComponentValue, HasStorageFactory, StorageFactory};
 use crate::component::Component;
 use crate::component::component_storage::ComponentInsertion;
 use std::any::TypeId;
 
 macro_rules! impl_component_insertion {
     ($($t:ident),*) => {
-        impl<$($t: Component + HasColumnFactory),*> ComponentInsertion for ($($t,)*) {
+        impl<$($t: Component + HasStorageFactory),*> ComponentInsertion for ($($t,)*) {
             #[allow(non_snake_case)]
-            fn for_each_component(self, mut f: impl FnMut(TypeId, ComponentValue, ColumnFactory)) {
+            fn for_each_component(self, mut f: impl FnMut(TypeId, ComponentValue, StorageFactory)) {
                 let ($($t,)*) = self;
                 $(
                     f(
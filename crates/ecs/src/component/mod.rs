@@ -3,7 +3,12 @@ use std::any::Any;
 pub mod archetype;
 pub mod component_storage;
 mod impls;
+pub mod sparse_set;
 
 pub use ecs_macros::Component;
+pub use sparse_set::StorageType;
 
-pub trait Component: Any {}
+pub trait Component: Any {
+    /// Set with `#[component(storage = "sparse")]` on the derive.
+    const STORAGE: StorageType = StorageType::Table;
+}
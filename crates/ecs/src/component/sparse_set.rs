@@ -0,0 +1,255 @@
+use crate::component::Component;
+use crate::component::archetype::ComponentValue;
+use crate::entity::Entity;
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+
+/// Where the values of a component type live.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StorageType {
+    /// A column in the entity's archetype. Fastest to iterate, but fixed at spawn time.
+    Table,
+    /// A per-type sparse set keyed by entity. Can be inserted and removed on live
+    /// entities without moving them between archetypes; suited to rare or transient
+    /// markers such as `Selected` or `Dying`.
+    Sparse,
+}
+
+/// Densely packed values of one sparse component type, indexed by entity.
+pub struct SparseSet<T> {
+    /// Entity index to position in `dense`.
+    sparse: Vec<Option<usize>>,
+    dense: Vec<T>,
+    entities: Vec<Entity>,
+}
+
+impl<T> SparseSet<T> {
+    pub fn new() -> Self {
+        Self {
+            sparse: Vec::new(),
+            dense: Vec::new(),
+            entities: Vec::new(),
+        }
+    }
+
+    /// Inserts or replaces `entity`'s value, returning the previous one.
+    pub fn insert(&mut self, entity: Entity, value: T) -> Option<T> {
+        if let Some(index) = self.index_of(entity) {
+            return Some(std::mem::replace(&mut self.dense[index], value));
+        }
+
+        if self.sparse.len() <= entity.0 {
+            self.sparse.resize(entity.0 + 1, None);
+        }
+        self.sparse[entity.0] = Some(self.dense.len());
+        self.dense.push(value);
+        self.entities.push(entity);
+        None
+    }
+
+    pub fn remove(&mut self, entity: Entity) -> Option<T> {
+        let index = self.index_of(entity)?;
+        self.sparse[entity.0] = None;
+        self.entities.swap_remove(index);
+        if let Some(&moved) = self.entities.get(index) {
+            self.sparse[moved.0] = Some(index);
+        }
+        Some(self.dense.swap_remove(index))
+    }
+
+    pub fn contains(&self, entity: Entity) -> bool {
+        self.index_of(entity).is_some()
+    }
+
+    pub fn get(&self, entity: Entity) -> Option<&T> {
+        self.index_of(entity).map(|index| &self.dense[index])
+    }
+
+    pub fn get_mut(&mut self, entity: Entity) -> Option<&mut T> {
+        self.index_of(entity).map(|index| &mut self.dense[index])
+    }
+
+    pub fn len(&self) -> usize {
+        self.dense.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.dense.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Entity, &T)> {
+        self.entities.iter().copied().zip(&self.dense)
+    }
+
+    fn index_of(&self, entity: Entity) -> Option<usize> {
+        self.sparse.get(entity.0).copied().flatten()
+    }
+}
+
+impl<T> Default for SparseSet<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub(crate) trait SparseSetData: Any {
+    fn insert_erased(&mut self, entity: Entity, value: ComponentValue);
+    /// Returns true if `entity` had a component here.
+    fn remove_erased(&mut self, entity: Entity) -> bool;
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+    fn type_name(&self) -> &'static str;
+    fn is_empty(&self) -> bool;
+}
+
+impl<T: Component> SparseSetData for SparseSet<T> {
+    fn insert_erased(&mut self, entity: Entity, value: ComponentValue) {
+        let value = value
+            .take::<T>()
+            .expect("type mismatch on insert! sparse set key is wrong");
+        self.insert(entity, value);
+    }
+
+    fn remove_erased(&mut self, entity: Entity) -> bool {
+        self.remove(entity).is_some()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn type_name(&self) -> &'static str {
+        std::any::type_name::<T>()
+    }
+
+    fn is_empty(&self) -> bool {
+        SparseSet::is_empty(self)
+    }
+}
+
+pub(crate) type SparseSetFactory = fn() -> Box<dyn SparseSetData>;
+
+/// Every sparse set in a world, keyed by component type.
+#[derive(Default)]
+pub struct SparseSets {
+    sets: HashMap<TypeId, Box<dyn SparseSetData>>,
+}
+
+impl SparseSets {
+    pub fn get<T: Component>(&self) -> Option<&SparseSet<T>> {
+        self.sets
+            .get(&TypeId::of::<T>())
+            .map(|set| set.as_any().downcast_ref::<SparseSet<T>>().unwrap())
+    }
+
+    pub fn get_mut<T: Component>(&mut self) -> Option<&mut SparseSet<T>> {
+        self.sets
+            .get_mut(&TypeId::of::<T>())
+            .map(|set| set.as_any_mut().downcast_mut::<SparseSet<T>>().unwrap())
+    }
+
+    pub(crate) fn get_or_create<T: Component>(&mut self) -> &mut SparseSet<T> {
+        self.sets
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| Box::new(SparseSet::<T>::new()))
+            .as_any_mut()
+            .downcast_mut::<SparseSet<T>>()
+            .unwrap()
+    }
+
+    pub(crate) fn insert_erased(
+        &mut self,
+        entity: Entity,
+        value: ComponentValue,
+        factory: SparseSetFactory,
+    ) {
+        self.sets
+            .entry(value.type_id())
+            .or_insert_with(factory)
+            .insert_erased(entity, value);
+    }
+
+    pub(crate) fn remove(&mut self, type_id: TypeId, entity: Entity) {
+        if let Some(set) = self.sets.get_mut(&type_id) {
+            set.remove_erased(entity);
+        }
+    }
+
+    /// Type ids and names of the sparse components some entity has.
+    pub(crate) fn occupied(&self) -> impl Iterator<Item = (TypeId, &'static str)> + '_ {
+        self.sets
+            .iter()
+            .filter(|(_, set)| !set.is_empty())
+            .map(|(&type_id, set)| (type_id, set.type_name()))
+    }
+
+    /// Drops every sparse component `entity` has, calling `removed` with each one's type.
+    pub(crate) fn remove_entity(&mut self, entity: Entity, mut removed: impl FnMut(TypeId)) {
+        for (&type_id, set) in &mut self.sets {
+            if set.remove_erased(entity) {
+                removed(type_id);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::World;
+
+    #[derive(Component)]
+    struct Health(u32);
+
+    #[derive(Component)]
+    #[component(storage = "sparse")]
+    struct Selected;
+
+    #[test]
+    fn queries_see_sparse_components_toggled_on_live_entities() {
+        let mut world = World::new();
+        let a = world.create_entity((Health(1),));
+        let b = world.create_entity((Health(2), Selected));
+        world.create_entity((Health(3),));
+
+        let selected = |world: &mut World| {
+            let mut query = world.query::<(&mut Health, &mut Selected)>();
+            let mut health = query.iter().map(|(health, _)| health.0).collect::<Vec<_>>();
+            health.sort_unstable();
+            health
+        };
+        assert_eq!(selected(&mut world), [2]);
+
+        world.insert_sparse(a, Selected);
+        world.remove_sparse::<Selected>(b);
+        assert_eq!(selected(&mut world), [1]);
+
+        let mut query = world.query::<(&mut Health, Option<&mut Selected>)>();
+        assert_eq!(
+            query
+                .iter()
+                .filter(|(_, selected)| selected.is_some())
+                .count(),
+            1
+        );
+        assert_eq!(query.iter().count(), 3);
+    }
+
+    #[test]
+    fn removing_keeps_remaining_entities_indexed() {
+        let mut set = SparseSet::new();
+        set.insert(Entity(4), 'a');
+        set.insert(Entity(1), 'b');
+        set.insert(Entity(7), 'c');
+
+        assert_eq!(set.remove(Entity(4)), Some('a'));
+        assert_eq!(set.get(Entity(7)), Some(&'c'));
+        assert_eq!(set.get(Entity(1)), Some(&'b'));
+        assert!(!set.contains(Entity(4)));
+        assert_eq!(set.len(), 2);
+    }
+}
@@ -0,0 +1,83 @@
+/// Double-buffered event channel, stored as a resource.
+///
+/// Events sent during a frame become readable when the owner calls [`Events::update`]
+/// at the start of the next one, and stay readable for that whole frame. Every system
+/// therefore sees each event exactly once, regardless of system order.
+#[derive(Debug)]
+pub struct Events<T> {
+    readable: Vec<T>,
+    pending: Vec<T>,
+}
+
+impl<T> Default for Events<T> {
+    fn default() -> Self {
+        Self {
+            readable: Vec::new(),
+            pending: Vec::new(),
+        }
+    }
+}
+
+impl<T> Events<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queues an event for readers in the next frame.
+    pub fn send(&mut self, event: T) {
+        self.pending.push(event);
+    }
+
+    pub fn send_batch(&mut self, events: impl IntoIterator<Item = T>) {
+        self.pending.extend(events);
+    }
+
+    /// Events published by the last [`Events::update`].
+    pub fn iter(&self) -> std::slice::Iter<'_, T> {
+        self.readable.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.readable.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.readable.is_empty()
+    }
+
+    /// Publishes the events sent since the last call and drops the ones already read.
+    pub fn update(&mut self) {
+        self.readable.clear();
+        std::mem::swap(&mut self.readable, &mut self.pending);
+    }
+}
+
+impl<'a, T> IntoIterator for &'a Events<T> {
+    type Item = &'a T;
+    type IntoIter = std::slice::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn events_are_readable_for_one_update() {
+        let mut events = Events::new();
+        events.send(1);
+        assert!(events.is_empty());
+
+        events.update();
+        events.send(2);
+        assert_eq!(events.iter().copied().collect::<Vec<_>>(), [1]);
+
+        events.update();
+        assert_eq!(events.iter().copied().collect::<Vec<_>>(), [2]);
+        events.update();
+        assert!(events.is_empty());
+    }
+}
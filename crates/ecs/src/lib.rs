@@ -1,7 +1,14 @@
+// Lets the derive macros' `::ecs::` paths resolve inside this crate too.
+extern crate self as ecs;
+
 pub mod component;
 pub mod entity;
+pub mod event;
+pub mod name;
 pub mod query;
-pub mod systems;
+pub mod resource;
+pub mod snapshot;
+pub mod testing;
 pub mod world;
 
 pub mod command_buffer;
@@ -0,0 +1,176 @@
+//! Names and tags for finding entities without holding on to their ids.
+//!
+//! Give an entity a [`NameComponent`] or [`TagsComponent`] when spawning it, or later
+//! through [`World::set_name`] and [`World::add_tag`]. The world indexes both as they
+//! are added and removed, so [`World::find_by_name`] and [`World::with_tag`] cost a hash
+//! lookup. Change names and tags through the world rather than through a query, which
+//! would bypass the index.
+//!
+//! [`World::set_name`]: crate::world::World::set_name
+//! [`World::add_tag`]: crate::world::World::add_tag
+//! [`World::find_by_name`]: crate::world::World::find_by_name
+//! [`World::with_tag`]: crate::world::World::with_tag
+
+use crate::component::Component;
+use crate::component::sparse_set::SparseSets;
+use crate::entity::Entity;
+use serde::{Deserialize, Serialize};
+use std::any::TypeId;
+use std::collections::HashMap;
+
+/// Name of an entity, e.g. `"player"` or `"door_02"`. Names need not be unique.
+#[derive(Clone, Debug, Component, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[component(storage = "sparse")]
+pub struct NameComponent(String);
+
+impl NameComponent {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self(name.into())
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+/// Labels grouping entities, e.g. `"enemy"` or `"interactable"`. Each tag is listed once.
+#[derive(Clone, Debug, Component, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[component(storage = "sparse")]
+pub struct TagsComponent {
+    tags: Vec<String>,
+}
+
+impl TagsComponent {
+    pub fn new<S: Into<String>>(tags: impl IntoIterator<Item = S>) -> Self {
+        let mut component = Self::default();
+        for tag in tags {
+            component.insert(tag.into());
+        }
+        component
+    }
+
+    pub fn contains(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t == tag)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        self.tags.iter().map(String::as_str)
+    }
+
+    pub(crate) fn insert(&mut self, tag: String) -> bool {
+        let new = !self.contains(&tag);
+        if new {
+            self.tags.push(tag);
+        }
+        new
+    }
+
+    pub(crate) fn remove(&mut self, tag: &str) -> bool {
+        let len = self.tags.len();
+        self.tags.retain(|t| t != tag);
+        self.tags.len() != len
+    }
+}
+
+/// Entities by name and by tag, each list in the order the entities were indexed.
+#[derive(Debug, Default)]
+pub(crate) struct NameIndex {
+    names: HashMap<String, Vec<Entity>>,
+    tags: HashMap<String, Vec<Entity>>,
+}
+
+impl NameIndex {
+    /// Whether components of `type_id` are indexed.
+    pub(crate) fn indexes(type_id: TypeId) -> bool {
+        type_id == TypeId::of::<NameComponent>() || type_id == TypeId::of::<TagsComponent>()
+    }
+
+    pub(crate) fn first_named(&self, name: &str) -> Option<Entity> {
+        self.names.get(name)?.first().copied()
+    }
+
+    pub(crate) fn tagged(&self, tag: &str) -> &[Entity] {
+        self.tags.get(tag).map_or(&[], Vec::as_slice)
+    }
+
+    /// Indexes the name and tags `entity` currently has in `sets`.
+    pub(crate) fn insert(&mut self, entity: Entity, sets: &SparseSets) {
+        if let Some(name) = sets.get::<NameComponent>().and_then(|set| set.get(entity)) {
+            self.names.entry(name.0.clone()).or_default().push(entity);
+        }
+        for tag in sets
+            .get::<TagsComponent>()
+            .and_then(|set| set.get(entity))
+            .into_iter()
+            .flat_map(TagsComponent::iter)
+        {
+            self.tags.entry(tag.to_string()).or_default().push(entity);
+        }
+    }
+
+    /// Drops the name and tags `entity` currently has in `sets` from the index.
+    pub(crate) fn remove(&mut self, entity: Entity, sets: &SparseSets) {
+        if let Some(name) = sets.get::<NameComponent>().and_then(|set| set.get(entity)) {
+            remove_from(&mut self.names, &name.0, entity);
+        }
+        for tag in sets
+            .get::<TagsComponent>()
+            .and_then(|set| set.get(entity))
+            .into_iter()
+            .flat_map(TagsComponent::iter)
+        {
+            remove_from(&mut self.tags, tag, entity);
+        }
+    }
+}
+
+fn remove_from(map: &mut HashMap<String, Vec<Entity>>, key: &str, entity: Entity) {
+    if let Some(entities) = map.get_mut(key) {
+        entities.retain(|&e| e != entity);
+        if entities.is_empty() {
+            map.remove(key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::World;
+
+    #[test]
+    fn index_follows_spawns_changes_and_despawns() {
+        let mut world = World::new();
+        let player = world.create_entity((NameComponent::new("player"),));
+        let orc = world.create_entity((
+            NameComponent::new("orc"),
+            TagsComponent::new(["enemy", "green"]),
+        ));
+        let bat = world.create_entity((TagsComponent::new(["enemy"]),));
+
+        assert_eq!(world.find_by_name("player"), Some(player));
+        assert_eq!(world.with_tag("enemy").collect::<Vec<_>>(), [orc, bat]);
+
+        world.set_name(player, "hero");
+        assert_eq!(world.find_by_name("player"), None);
+        assert_eq!(world.find_by_name("hero"), Some(player));
+
+        world.add_tag(player, "friendly");
+        world.remove_tag(orc, "enemy");
+        assert_eq!(world.with_tag("enemy").collect::<Vec<_>>(), [bat]);
+        assert_eq!(world.with_tag("friendly").collect::<Vec<_>>(), [player]);
+        assert!(world.get::<TagsComponent>(orc).unwrap().contains("green"));
+
+        world.remove_entity(bat);
+        assert_eq!(world.with_tag("enemy").count(), 0);
+        world.remove_sparse::<NameComponent>(orc);
+        assert_eq!(world.find_by_name("orc"), None);
+
+        // A queued insert goes through the index as well.
+        let mut commands = world.system_access().commands;
+        commands.insert_sparse(orc, NameComponent::new("boss"));
+        let queue = commands.into_queue();
+        world.flush_queue(queue);
+        assert_eq!(world.find_by_name("boss"), Some(orc));
+    }
+}
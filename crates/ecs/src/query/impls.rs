@@ -1,6 +1,7 @@
-use crate::component::Component;
 use crate::component::archetype::{Archetype, Column};
-use crate::query::QueryParameter;
+use crate::component::{Component, StorageType};
+use crate::entity::Entity;
+use crate::query::{FetchRow, QueryParameter, MISSING_COLUMN};
 use std::any::TypeId;
 
 impl<T1: Component> QueryParameter for &mut T1 {
@@ -14,21 +15,80 @@ impl<T1: Component> QueryParameter for &mut T1 {
         vec![TypeId::of::<T1>()]
     }
 
+    /// Sparse components match every archetype and are filtered per entity on fetch.
     fn check_match(archetype: &Archetype) -> Option<Self::MatchKey> {
-        archetype.components.get(&TypeId::of::<T1>()).copied()
+        match T1::STORAGE {
+            StorageType::Table => archetype.components.get(&TypeId::of::<T1>()).copied(),
+            StorageType::Sparse => Some(MISSING_COLUMN),
+        }
     }
 
     fn collect_columns(state: usize, columns_out: &mut Vec<usize>) {
         columns_out.push(state);
     }
 
-    unsafe fn fetch<'w>(columns: &mut [*mut Column], row: usize) -> Self::Item<'w> {
+    unsafe fn fetch<'w>(columns: &mut [*mut Column], row: FetchRow) -> Option<Self::Item<'w>> {
         unsafe {
+            if T1::STORAGE == StorageType::Sparse {
+                return row.sparse_set::<T1>()?.get_mut(row.entity);
+            }
+
             let column = &mut *columns[0];
             let data = column.data.as_any_mut().downcast_mut::<Vec<T1>>().unwrap();
 
-            &mut data[row]
+            Some(&mut data[row.row])
+        }
+    }
+}
+
+/// Matches every archetype; yields `None` for entities without a `T1`.
+impl<T1: Component> QueryParameter for Option<&mut T1> {
+    type Item<'w> = Option<&'w mut T1>;
+
+    type MatchKey = Option<usize>;
+
+    const COLUMN_COUNT: usize = 1;
+
+    fn component_type() -> Vec<TypeId> {
+        vec![TypeId::of::<T1>()]
+    }
+
+    fn check_match(archetype: &Archetype) -> Option<Self::MatchKey> {
+        Some(<&mut T1 as QueryParameter>::check_match(archetype))
+    }
+
+    fn collect_columns(state: Option<usize>, columns_out: &mut Vec<usize>) {
+        columns_out.push(state.unwrap_or(MISSING_COLUMN));
+    }
+
+    unsafe fn fetch<'w>(columns: &mut [*mut Column], row: FetchRow) -> Option<Self::Item<'w>> {
+        if T1::STORAGE == StorageType::Table && columns[0].is_null() {
+            return Some(None);
         }
+        unsafe { Some(<&mut T1 as QueryParameter>::fetch(columns, row)) }
+    }
+}
+
+/// Yields the entity being fetched, e.g. `world.query::<(Entity, &mut Health)>()`.
+impl QueryParameter for Entity {
+    type Item<'w> = Entity;
+
+    type MatchKey = ();
+
+    const COLUMN_COUNT: usize = 0;
+
+    fn component_type() -> Vec<TypeId> {
+        vec![]
+    }
+
+    fn check_match(_archetype: &Archetype) -> Option<Self::MatchKey> {
+        Some(())
+    }
+
+    fn collect_columns(_state: (), _columns_out: &mut Vec<usize>) {}
+
+    unsafe fn fetch<'w>(_columns: &mut [*mut Column], row: FetchRow) -> Option<Self::Item<'w>> {
+        Some(row.entity)
     }
 }
 
@@ -64,24 +124,24 @@ macro_rules! impl_query_parameter {
             }
 
             #[allow(non_snake_case, unused_assignments)]
-            unsafe fn fetch<'w>(columns: &mut [*mut Column], row: usize) -> Self::Item<'w> {
+            unsafe fn fetch<'w>(columns: &mut [*mut Column], row: FetchRow) -> Option<Self::Item<'w>> {
                 let mut offset = 0;
 
                 let $first = {
                     let slice = &mut columns[offset..offset + $first::COLUMN_COUNT];
                     offset += $first::COLUMN_COUNT;
-                    unsafe { $first::fetch(slice, row) }
+                    unsafe { $first::fetch(slice, row)? }
                 };
 
                 $(
                     let $rest = {
                         let slice = &mut columns[offset..offset + $rest::COLUMN_COUNT];
                         offset += $rest::COLUMN_COUNT;
-                        unsafe { $rest::fetch(slice, row) }
+                        unsafe { $rest::fetch(slice, row)? }
                     };
                 )*
 
-                ($first, $($rest,)*)
+                Some(($first, $($rest,)*))
             }
         }
     };
@@ -1,10 +1,36 @@
 mod impls;
 
+use crate::component::Component;
 use crate::component::archetype::{Archetype, Column};
+use crate::component::sparse_set::{SparseSet, SparseSets};
+use crate::entity::Entity;
 use crate::world::ArchetypeId;
 use std::any::TypeId;
 use std::marker::PhantomData;
 
+/// Column index collected for an optional component the archetype does not have. Fetched
+/// as a null column pointer. Sparse components also collect it, as they have no column.
+pub const MISSING_COLUMN: usize = usize::MAX;
+
+/// The entity a fetch reads: its row in the archetype's columns, plus what is needed to
+/// look up its sparse components.
+#[derive(Clone, Copy)]
+pub struct FetchRow {
+    pub row: usize,
+    pub entity: Entity,
+    sparse_sets: *mut SparseSets,
+}
+
+impl FetchRow {
+    /// # Safety
+    ///
+    /// The sparse sets pointer must be valid for `'w`, and no other reference may be
+    /// held into the `T` set.
+    pub(crate) unsafe fn sparse_set<'w, T: Component>(&self) -> Option<&'w mut SparseSet<T>> {
+        unsafe { self.sparse_sets.as_mut()?.get_mut::<T>() }
+    }
+}
+
 pub trait QueryParameter {
     type Item<'w>;
 
@@ -18,7 +44,8 @@ pub trait QueryParameter {
 
     fn collect_columns(state: Self::MatchKey, columns_out: &mut Vec<usize>);
 
-    /// Fetches component references for the given row.
+    /// Fetches component references for the given row, or `None` if the entity lacks a
+    /// required sparse component.
     ///
     /// # Safety
     ///
@@ -26,8 +53,8 @@ pub trait QueryParameter {
     /// must be within bounds for all referenced column slices.
     unsafe fn fetch<'w>(
         columns: &mut [*mut Column],
-        row: usize,
-    ) -> <Self as QueryParameter>::Item<'w>;
+        row: FetchRow,
+    ) -> Option<<Self as QueryParameter>::Item<'w>>;
 }
 
 pub struct Match<Q: QueryParameter> {
@@ -37,19 +64,21 @@ pub struct Match<Q: QueryParameter> {
 
 pub struct Query<'a, Q: QueryParameter> {
     pub(crate) archetypes: &'a mut Vec<Archetype>,
+    pub(crate) sparse_sets: &'a mut SparseSets,
     pub(crate) matches: Vec<Match<Q>>,
 }
 
 impl<'a, Q: QueryParameter> Query<'a, Q> {
-    pub fn new(archetypes: &'a mut Vec<Archetype>) -> Self {
+    pub fn new(archetypes: &'a mut Vec<Archetype>, sparse_sets: &'a mut SparseSets) -> Self {
         Self {
             archetypes,
+            sparse_sets,
             matches: Vec::new(),
         }
     }
 
     pub fn iter(&mut self) -> QueryIter<'_, Q> {
-        QueryIter::new(&mut self.matches, self.archetypes)
+        QueryIter::new(&mut self.matches, self.archetypes, self.sparse_sets)
     }
 
     pub fn build_matches(&mut self) {
@@ -83,15 +112,21 @@ pub struct QueryIter<'w, Q: QueryParameter> {
     matches_iter: std::slice::IterMut<'w, Match<Q>>,
     current_archetype: Option<ArchetypeIter<'w, Q>>,
     world_archetypes: *mut Vec<Archetype>,
+    sparse_sets: *mut SparseSets,
     _phantom: PhantomData<Q>,
 }
 
 impl<'w, Q: QueryParameter> QueryIter<'w, Q> {
-    pub fn new(matches: &'w mut [Match<Q>], world_archetypes: *mut Vec<Archetype>) -> Self {
+    pub fn new(
+        matches: &'w mut [Match<Q>],
+        world_archetypes: *mut Vec<Archetype>,
+        sparse_sets: *mut SparseSets,
+    ) -> Self {
         Self {
             matches_iter: matches.iter_mut(),
             current_archetype: None,
             world_archetypes,
+            sparse_sets,
             _phantom: PhantomData,
         }
     }
@@ -99,6 +134,7 @@ impl<'w, Q: QueryParameter> QueryIter<'w, Q> {
 
 struct ArchetypeIter<'w, Q: QueryParameter> {
     column_ptrs: Vec<*mut Column>,
+    entities: *const Entity,
     current_row: usize,
     total_rows: usize,
     _phantom: PhantomData<&'w mut Q>,
@@ -116,9 +152,21 @@ impl<'w, Q: QueryParameter> Iterator for QueryIter<'w, Q> {
                 let row = archetype_iter.current_row;
                 archetype_iter.current_row += 1;
 
-                // SAFETY: column_ptrs are valid for 'w lifetime
+                // SAFETY: column_ptrs and entities are valid for 'w lifetime
                 // and row is within bounds
-                return Some(unsafe { Q::fetch(&mut archetype_iter.column_ptrs, row) });
+                let item = unsafe {
+                    let row = FetchRow {
+                        row,
+                        entity: *archetype_iter.entities.add(row),
+                        sparse_sets: self.sparse_sets,
+                    };
+                    Q::fetch(&mut archetype_iter.column_ptrs, row)
+                };
+                // Rows missing a required sparse component are skipped.
+                if let Some(item) = item {
+                    return Some(item);
+                }
+                continue;
             }
 
             // SAFETY: world_data pointer is valid for 'w lifetime
@@ -132,22 +180,24 @@ impl<'w, Q: QueryParameter> Iterator for QueryIter<'w, Q> {
                     .get_mut(column_match.archetype_id.0)
                     .expect("Archetype not registered");
 
-                let total_rows = if archetype.columns.is_empty() {
-                    0
-                } else {
-                    archetype.columns[0].data.len()
-                };
+                // Counted from the entities, as an archetype holding only sparse
+                // components has no columns.
+                let total_rows = archetype.entities.len();
 
                 let mut column_indices = Vec::with_capacity(Q::COLUMN_COUNT);
                 Q::collect_columns(column_match.match_key, &mut column_indices);
 
                 let column_ptrs = column_indices
                     .into_iter()
-                    .map(|index| &mut archetype.columns[index] as *mut Column)
+                    .map(|index| match index {
+                        MISSING_COLUMN => std::ptr::null_mut(),
+                        index => &mut archetype.columns[index] as *mut Column,
+                    })
                     .collect::<Vec<_>>();
 
                 self.current_archetype = Some(ArchetypeIter {
                     column_ptrs,
+                    entities: archetype.entities.as_ptr(),
                     current_row: 0,
                     total_rows,
                     _phantom: PhantomData,
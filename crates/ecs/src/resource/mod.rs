@@ -0,0 +1,283 @@
+use std::any::{Any, TypeId};
+use std::cell::{Ref, RefCell, RefMut};
+use std::collections::HashMap;
+use std::fmt;
+use std::ops::{Deref, DerefMut};
+use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard, TryLockError};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ResourceError {
+    Missing(&'static str),
+    /// Requested shared access while the resource is mutably borrowed.
+    BorrowedMutably(&'static str),
+    /// Requested mutable access while the resource is borrowed.
+    Borrowed(&'static str),
+    /// A thread panicked while holding a shared resource's lock.
+    Poisoned(&'static str),
+}
+
+impl fmt::Display for ResourceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ResourceError::Missing(name) => write!(f, "resource '{}' is not registered", name),
+            ResourceError::BorrowedMutably(name) => write!(
+                f,
+                "resource '{}' cannot be read: it is already borrowed mutably",
+                name
+            ),
+            ResourceError::Borrowed(name) => write!(
+                f,
+                "resource '{}' cannot be borrowed mutably: it is already borrowed",
+                name
+            ),
+            ResourceError::Poisoned(name) => {
+                write!(f, "resource '{}' lock was poisoned by a panicking thread", name)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ResourceError {}
+
+enum Storage {
+    /// `RefCell<T>`, single-threaded.
+    Local(Box<dyn Any>),
+    /// `Arc<RwLock<T>>`, shareable with worker threads.
+    Shared(Box<dyn Any>),
+}
+
+struct Entry {
+    name: &'static str,
+    storage: Storage,
+}
+
+/// Type-keyed store of engine managers and game state that systems access through
+/// `Res<T>` / `ResMut<T>`.
+///
+/// Borrows are checked at runtime. Conflicts return a `ResourceError` naming the type,
+/// instead of the bare `BorrowMutError` panic a raw `RefCell` would give.
+#[derive(Default)]
+pub struct Resources {
+    entries: HashMap<TypeId, Entry>,
+}
+
+impl Resources {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Inserts a single-threaded resource, replacing any previous value of the same type.
+    pub fn insert<T: 'static>(&mut self, value: T) {
+        self.entries.insert(
+            TypeId::of::<T>(),
+            Entry {
+                name: std::any::type_name::<T>(),
+                storage: Storage::Local(Box::new(RefCell::new(value))),
+            },
+        );
+    }
+
+    /// Inserts a resource behind `Arc<RwLock>` so `shared_handle` can hand it to other threads.
+    pub fn insert_shared<T: Send + Sync + 'static>(&mut self, value: T) {
+        self.entries.insert(
+            TypeId::of::<T>(),
+            Entry {
+                name: std::any::type_name::<T>(),
+                storage: Storage::Shared(Box::new(Arc::new(RwLock::new(value)))),
+            },
+        );
+    }
+
+    pub fn contains<T: 'static>(&self) -> bool {
+        self.entries.contains_key(&TypeId::of::<T>())
+    }
+
+    /// Removes a resource and returns it. A shared resource stays registered, and `None`
+    /// is returned, while another handle to it is alive or its lock is poisoned.
+    pub fn remove<T: 'static>(&mut self) -> Option<T> {
+        let entry = self.entries.get(&TypeId::of::<T>())?;
+        if let Storage::Shared(any) = &entry.storage {
+            let arc = any.downcast_ref::<Arc<RwLock<T>>>()?;
+            if Arc::strong_count(arc) > 1 || arc.is_poisoned() {
+                return None;
+            }
+        }
+        let entry = self.entries.remove(&TypeId::of::<T>())?;
+        match entry.storage {
+            Storage::Local(any) => any.downcast::<RefCell<T>>().ok().map(|cell| cell.into_inner()),
+            Storage::Shared(any) => {
+                let arc = *any.downcast::<Arc<RwLock<T>>>().ok()?;
+                Arc::try_unwrap(arc).ok()?.into_inner().ok()
+            }
+        }
+    }
+
+    /// Returns a clone of the lock around a shared resource, for use off the main thread.
+    pub fn shared_handle<T: Send + Sync + 'static>(&self) -> Option<Arc<RwLock<T>>> {
+        match &self.entries.get(&TypeId::of::<T>())?.storage {
+            Storage::Shared(any) => any.downcast_ref::<Arc<RwLock<T>>>().cloned(),
+            Storage::Local(_) => None,
+        }
+    }
+
+    pub fn try_get<T: 'static>(&self) -> Result<Res<'_, T>, ResourceError> {
+        let entry = self.entry::<T>()?;
+        let inner = match &entry.storage {
+            Storage::Local(any) => {
+                let cell = any.downcast_ref::<RefCell<T>>().unwrap();
+                ResInner::Local(
+                    cell.try_borrow()
+                        .map_err(|_| ResourceError::BorrowedMutably(entry.name))?,
+                )
+            }
+            Storage::Shared(any) => {
+                let lock = any.downcast_ref::<Arc<RwLock<T>>>().unwrap();
+                ResInner::Shared(lock.try_read().map_err(|e| match e {
+                    TryLockError::WouldBlock => ResourceError::BorrowedMutably(entry.name),
+                    TryLockError::Poisoned(_) => ResourceError::Poisoned(entry.name),
+                })?)
+            }
+        };
+        Ok(Res { inner })
+    }
+
+    pub fn try_get_mut<T: 'static>(&self) -> Result<ResMut<'_, T>, ResourceError> {
+        let entry = self.entry::<T>()?;
+        let inner = match &entry.storage {
+            Storage::Local(any) => {
+                let cell = any.downcast_ref::<RefCell<T>>().unwrap();
+                ResMutInner::Local(
+                    cell.try_borrow_mut()
+                        .map_err(|_| ResourceError::Borrowed(entry.name))?,
+                )
+            }
+            Storage::Shared(any) => {
+                let lock = any.downcast_ref::<Arc<RwLock<T>>>().unwrap();
+                ResMutInner::Shared(lock.try_write().map_err(|e| match e {
+                    TryLockError::WouldBlock => ResourceError::Borrowed(entry.name),
+                    TryLockError::Poisoned(_) => ResourceError::Poisoned(entry.name),
+                })?)
+            }
+        };
+        Ok(ResMut { inner })
+    }
+
+    /// Borrows a resource, panicking with the resource name on a missing type or conflict.
+    pub fn get<T: 'static>(&self) -> Res<'_, T> {
+        self.try_get::<T>().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Mutably borrows a resource, panicking with the resource name on a missing type or conflict.
+    pub fn get_mut<T: 'static>(&self) -> ResMut<'_, T> {
+        self.try_get_mut::<T>().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    fn entry<T: 'static>(&self) -> Result<&Entry, ResourceError> {
+        self.entries
+            .get(&TypeId::of::<T>())
+            .ok_or(ResourceError::Missing(std::any::type_name::<T>()))
+    }
+}
+
+enum ResInner<'a, T> {
+    Local(Ref<'a, T>),
+    Shared(RwLockReadGuard<'a, T>),
+}
+
+/// Shared borrow of a resource.
+pub struct Res<'a, T> {
+    inner: ResInner<'a, T>,
+}
+
+impl<T> Deref for Res<'_, T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        match &self.inner {
+            ResInner::Local(r) => r,
+            ResInner::Shared(r) => r,
+        }
+    }
+}
+
+enum ResMutInner<'a, T> {
+    Local(RefMut<'a, T>),
+    Shared(RwLockWriteGuard<'a, T>),
+}
+
+/// Exclusive borrow of a resource.
+pub struct ResMut<'a, T> {
+    inner: ResMutInner<'a, T>,
+}
+
+impl<T> Deref for ResMut<'_, T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        match &self.inner {
+            ResMutInner::Local(r) => r,
+            ResMutInner::Shared(r) => r,
+        }
+    }
+}
+
+impl<T> DerefMut for ResMut<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        match &mut self.inner {
+            ResMutInner::Local(r) => r,
+            ResMutInner::Shared(r) => r,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Score(u32);
+
+    #[test]
+    fn conflicting_borrows_report_the_type() {
+        let mut resources = Resources::new();
+        resources.insert(Score(1));
+
+        let read = resources.get::<Score>();
+        let err = resources.try_get_mut::<Score>().err().unwrap();
+        assert!(matches!(err, ResourceError::Borrowed(name) if name.ends_with("Score")));
+        assert_eq!(read.0, 1);
+        drop(read);
+
+        resources.get_mut::<Score>().0 += 1;
+        assert_eq!(resources.get::<Score>().0, 2);
+        assert!(matches!(
+            resources.try_get::<u64>(),
+            Err(ResourceError::Missing(_))
+        ));
+    }
+
+    #[test]
+    fn shared_resources_are_reachable_from_threads() {
+        let mut resources = Resources::new();
+        resources.insert_shared(Score(0));
+
+        let handle = resources.shared_handle::<Score>().unwrap();
+        std::thread::spawn(move || handle.write().unwrap().0 = 5)
+            .join()
+            .unwrap();
+
+        assert_eq!(resources.get::<Score>().0, 5);
+        assert_eq!(resources.remove::<Score>().map(|s| s.0), Some(5));
+    }
+
+    #[test]
+    fn shared_resources_in_use_elsewhere_stay_registered() {
+        let mut resources = Resources::new();
+        resources.insert_shared(Score(3));
+
+        let handle = resources.shared_handle::<Score>().unwrap();
+        assert!(resources.remove::<Score>().is_none());
+        assert_eq!(resources.get::<Score>().0, 3);
+
+        drop(handle);
+        assert_eq!(resources.remove::<Score>().map(|s| s.0), Some(3));
+        assert!(!resources.contains::<Score>());
+    }
+}
@@ -0,0 +1,515 @@
+//! Save-game snapshots: every entity's registered components, encoded into one blob that
+//! can be written to disk and loaded back into a world.
+//!
+//! Only components added to a [`SnapshotRegistry`] are saved. Runtime handles, such as
+//! asset handles, are not stable across runs; components holding them implement
+//! [`Persist`] and swap them for stable ids through a [`HandleRemap`]. The same goes for
+//! references to other entities in the snapshot, saved with `save_entity`.
+
+use crate::component::Component;
+use crate::component::archetype::{ComponentValue, HasStorageFactory, StorageFactory};
+use crate::entity::Entity;
+use crate::world::World;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::any::{Any, TypeId, type_name};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+/// Bumped whenever the blob layout changes. Older snapshots are rejected.
+const SNAPSHOT_VERSION: u32 = 2;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SnapshotError {
+    Encode(String),
+    Decode(String),
+    VersionMismatch {
+        found: u32,
+        expected: u32,
+    },
+    /// The snapshot contains a component name no registered type answers to.
+    UnknownComponent(String),
+    /// A handle had no stable id, or a stable id could not be resolved back.
+    UnresolvedHandle {
+        kind: &'static str,
+        id: String,
+    },
+    /// A component needed engine state the remap doesn't carry.
+    MissingContext(&'static str),
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnapshotError::Encode(err) => write!(f, "failed to encode snapshot: {}", err),
+            SnapshotError::Decode(err) => write!(f, "failed to decode snapshot: {}", err),
+            SnapshotError::VersionMismatch { found, expected } => write!(
+                f,
+                "snapshot version {} is not supported (expected {})",
+                found, expected
+            ),
+            SnapshotError::UnknownComponent(name) => {
+                write!(f, "snapshot contains unregistered component '{}'", name)
+            }
+            SnapshotError::UnresolvedHandle { kind, id } => {
+                write!(f, "no stable id mapping for {} handle {}", kind, id)
+            }
+            SnapshotError::MissingContext(kind) => {
+                write!(f, "snapshot remap does not provide {}", kind)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {}
+
+/// Translates runtime handles to ids that stay valid across runs, and back. `kind` is the
+/// `TypeId` of the asset type the handle points to.
+pub trait HandleRemap {
+    fn stable_id(&mut self, kind: TypeId, handle: u64) -> Option<u128>;
+    /// Resolves a stable id to a handle in this run, loading the asset if needed.
+    fn resolve(&mut self, kind: TypeId, stable: u128) -> Option<u64>;
+
+    /// Engine state of type `kind` that components need to save or rebuild themselves,
+    /// such as the collision world holding their shapes. `None` if the remap has none.
+    fn context(&mut self, _kind: TypeId) -> Option<&mut dyn Any> {
+        None
+    }
+}
+
+impl dyn HandleRemap + '_ {
+    pub fn save_handle<A: 'static>(&mut self, handle: u64) -> Result<u128, SnapshotError> {
+        self.stable_id(TypeId::of::<A>(), handle)
+            .ok_or_else(|| SnapshotError::UnresolvedHandle {
+                kind: type_name::<A>(),
+                id: handle.to_string(),
+            })
+    }
+
+    pub fn load_handle<A: 'static>(&mut self, stable: u128) -> Result<u64, SnapshotError> {
+        self.resolve(TypeId::of::<A>(), stable)
+            .ok_or_else(|| SnapshotError::UnresolvedHandle {
+                kind: type_name::<A>(),
+                id: format!("{:032x}", stable),
+            })
+    }
+
+    /// Saves a reference to another entity. See [`HandleRemap::load_entity`].
+    pub fn save_entity(&mut self, entity: Entity) -> Result<u128, SnapshotError> {
+        self.save_handle::<Entity>(entity.0 as u64)
+    }
+
+    /// The entity loaded in place of a saved reference, or `None` if the referenced entity
+    /// was not part of the snapshot.
+    pub fn load_entity(&mut self, stable: u128) -> Option<Entity> {
+        self.resolve(TypeId::of::<Entity>(), stable)
+            .map(|entity| Entity(entity as usize))
+    }
+
+    pub fn context_mut<C: 'static>(&mut self) -> Result<&mut C, SnapshotError> {
+        self.context(TypeId::of::<C>())
+            .and_then(|context| context.downcast_mut::<C>())
+            .ok_or(SnapshotError::MissingContext(type_name::<C>()))
+    }
+}
+
+/// For worlds whose saved components hold no handles.
+pub struct NoRemap;
+
+impl HandleRemap for NoRemap {
+    fn stable_id(&mut self, _kind: TypeId, _handle: u64) -> Option<u128> {
+        None
+    }
+
+    fn resolve(&mut self, _kind: TypeId, _stable: u128) -> Option<u64> {
+        None
+    }
+}
+
+/// A component saved in a different form than it has at runtime, typically to replace
+/// handles with stable ids.
+pub trait Persist: Component + Sized {
+    type Saved: Serialize + DeserializeOwned;
+
+    fn save(&self, remap: &mut dyn HandleRemap) -> Result<Self::Saved, SnapshotError>;
+    fn load(saved: Self::Saved, remap: &mut dyn HandleRemap) -> Result<Self, SnapshotError>;
+}
+
+#[derive(Serialize, Deserialize)]
+struct WorldSnapshot {
+    version: u32,
+    /// Index each saved entity had in the saving world, for references between them.
+    ids: Vec<u64>,
+    entities: Vec<Vec<SavedComponent>>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SavedComponent {
+    name: String,
+    data: Vec<u8>,
+}
+
+/// Saved components keyed by entity index, so entities keep their relative order.
+type SavedEntities = BTreeMap<usize, Vec<SavedComponent>>;
+type SaveFn = fn(&World, &str, &mut SaveTarget) -> Result<(), SnapshotError>;
+type LoadFn = fn(&[u8], &mut dyn HandleRemap) -> Result<DecodedComponent, SnapshotError>;
+
+pub(crate) type DecodedComponent = (TypeId, ComponentValue, StorageFactory);
+
+/// Maps references to other entities on top of the caller's remap. Entities are saved by
+/// their index and load as the entity restored at the same position in the snapshot.
+struct EntityRemap<'a> {
+    inner: &'a mut dyn HandleRemap,
+    /// Saved index to load position; empty while saving.
+    positions: HashMap<u128, u64>,
+}
+
+impl HandleRemap for EntityRemap<'_> {
+    fn stable_id(&mut self, kind: TypeId, handle: u64) -> Option<u128> {
+        if kind == TypeId::of::<Entity>() {
+            Some(handle as u128)
+        } else {
+            self.inner.stable_id(kind, handle)
+        }
+    }
+
+    fn resolve(&mut self, kind: TypeId, stable: u128) -> Option<u64> {
+        if kind == TypeId::of::<Entity>() {
+            self.positions.get(&stable).copied()
+        } else {
+            self.inner.resolve(kind, stable)
+        }
+    }
+
+    fn context(&mut self, kind: TypeId) -> Option<&mut dyn Any> {
+        self.inner.context(kind)
+    }
+}
+
+struct SaveTarget<'a> {
+    remap: &'a mut dyn HandleRemap,
+    keep: &'a dyn Fn(Entity) -> bool,
+    entities: SavedEntities,
+}
+
+impl SaveTarget<'_> {
+    fn push(
+        &mut self,
+        entity: Entity,
+        name: &str,
+        value: &impl Serialize,
+    ) -> Result<(), SnapshotError> {
+        let data =
+            bincode::serialize(value).map_err(|err| SnapshotError::Encode(err.to_string()))?;
+        self.entities
+            .entry(entity.0)
+            .or_default()
+            .push(SavedComponent {
+                name: name.to_string(),
+                data,
+            });
+        Ok(())
+    }
+}
+
+struct SnapshotType {
+    type_id: TypeId,
+    name: &'static str,
+    save: SaveFn,
+    load: LoadFn,
+}
+
+/// The component types written to snapshots. Each is saved under a name that identifies
+/// it in the blob; keep names stable, or old saves stop loading.
+#[derive(Default)]
+pub struct SnapshotRegistry {
+    types: Vec<SnapshotType>,
+    by_name: HashMap<&'static str, usize>,
+}
+
+impl SnapshotRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a component that is saved as its own serde encoding.
+    pub fn register<T: Component + Serialize + DeserializeOwned>(&mut self, name: &'static str) {
+        self.insert::<T>(name, save_serde::<T>, load_serde::<T>);
+    }
+
+    /// Registers a component that is saved through its [`Persist`] form.
+    pub fn register_persist<T: Persist>(&mut self, name: &'static str) {
+        self.insert::<T>(name, save_persist::<T>, load_persist::<T>);
+    }
+
+    /// Whether components of type `type_id` are written to snapshots.
+    pub fn contains(&self, type_id: TypeId) -> bool {
+        self.types.iter().any(|ty| ty.type_id == type_id)
+    }
+
+    fn insert<T: Component>(&mut self, name: &'static str, save: SaveFn, load: LoadFn) {
+        assert!(
+            !self.by_name.contains_key(name),
+            "snapshot component name '{}' registered twice",
+            name
+        );
+        self.by_name.insert(name, self.types.len());
+        self.types.push(SnapshotType {
+            type_id: TypeId::of::<T>(),
+            name,
+            save,
+            load,
+        });
+    }
+
+    pub(crate) fn save(
+        &self,
+        world: &World,
+        remap: &mut dyn HandleRemap,
+        keep: &dyn Fn(Entity) -> bool,
+    ) -> Result<Vec<u8>, SnapshotError> {
+        let mut remap = EntityRemap {
+            inner: remap,
+            positions: HashMap::new(),
+        };
+        let mut target = SaveTarget {
+            remap: &mut remap,
+            keep,
+            entities: SavedEntities::new(),
+        };
+        for ty in &self.types {
+            (ty.save)(world, ty.name, &mut target)?;
+        }
+
+        let snapshot = WorldSnapshot {
+            version: SNAPSHOT_VERSION,
+            ids: target.entities.keys().map(|&index| index as u64).collect(),
+            entities: target.entities.into_values().collect(),
+        };
+        bincode::serialize(&snapshot).map_err(|err| SnapshotError::Encode(err.to_string()))
+    }
+
+    /// Decodes every entity in `bytes` without touching a world, so a bad snapshot fails
+    /// before anything is despawned. References between entities resolve to
+    /// `Entity(position)`, so the world must restore entity `i` of the result as
+    /// `Entity(i)`.
+    pub(crate) fn load(
+        &self,
+        bytes: &[u8],
+        remap: &mut dyn HandleRemap,
+    ) -> Result<Vec<Vec<DecodedComponent>>, SnapshotError> {
+        let snapshot: WorldSnapshot =
+            bincode::deserialize(bytes).map_err(|err| SnapshotError::Decode(err.to_string()))?;
+        if snapshot.version != SNAPSHOT_VERSION {
+            return Err(SnapshotError::VersionMismatch {
+                found: snapshot.version,
+                expected: SNAPSHOT_VERSION,
+            });
+        }
+
+        let mut remap = EntityRemap {
+            inner: remap,
+            positions: snapshot
+                .ids
+                .iter()
+                .enumerate()
+                .map(|(position, &index)| (index as u128, position as u64))
+                .collect(),
+        };
+        snapshot
+            .entities
+            .into_iter()
+            .map(|components| {
+                components
+                    .into_iter()
+                    .map(|component| {
+                        let index = *self
+                            .by_name
+                            .get(component.name.as_str())
+                            .ok_or(SnapshotError::UnknownComponent(component.name))?;
+                        (self.types[index].load)(&component.data, &mut remap)
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+fn decode<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, SnapshotError> {
+    bincode::deserialize(bytes).map_err(|err| SnapshotError::Decode(err.to_string()))
+}
+
+fn decoded<T: Component>(component: T) -> DecodedComponent {
+    (
+        TypeId::of::<T>(),
+        ComponentValue::new(component),
+        T::get_factory(),
+    )
+}
+
+fn save_serde<T: Component + Serialize>(
+    world: &World,
+    name: &str,
+    target: &mut SaveTarget,
+) -> Result<(), SnapshotError> {
+    let mut result = Ok(());
+    world.for_each_component::<T>(|entity, component| {
+        if result.is_ok() && (target.keep)(entity) {
+            result = target.push(entity, name, component);
+        }
+    });
+    result
+}
+
+fn load_serde<T: Component + DeserializeOwned>(
+    bytes: &[u8],
+    _remap: &mut dyn HandleRemap,
+) -> Result<DecodedComponent, SnapshotError> {
+    Ok(decoded(decode::<T>(bytes)?))
+}
+
+fn save_persist<T: Persist>(
+    world: &World,
+    name: &str,
+    target: &mut SaveTarget,
+) -> Result<(), SnapshotError> {
+    let mut result = Ok(());
+    world.for_each_component::<T>(|entity, component| {
+        if result.is_ok() && (target.keep)(entity) {
+            result = component
+                .save(target.remap)
+                .and_then(|saved| target.push(entity, name, &saved));
+        }
+    });
+    result
+}
+
+fn load_persist<T: Persist>(
+    bytes: &[u8],
+    remap: &mut dyn HandleRemap,
+) -> Result<DecodedComponent, SnapshotError> {
+    let saved = decode::<T::Saved>(bytes)?;
+    Ok(decoded(T::load(saved, remap)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Component, Serialize, Deserialize, Debug, PartialEq)]
+    struct Health(u32);
+
+    /// Holds a runtime handle that is saved as a stable id.
+    #[derive(Component, Debug, PartialEq)]
+    struct Model(u64);
+
+    impl Persist for Model {
+        type Saved = u128;
+
+        fn save(&self, remap: &mut dyn HandleRemap) -> Result<u128, SnapshotError> {
+            remap.save_handle::<Model>(self.0)
+        }
+
+        fn load(saved: u128, remap: &mut dyn HandleRemap) -> Result<Self, SnapshotError> {
+            Ok(Model(remap.load_handle::<Model>(saved)?))
+        }
+    }
+
+    /// Handles are offset by 100 in the "next run".
+    struct Offset;
+
+    impl HandleRemap for Offset {
+        fn stable_id(&mut self, _kind: TypeId, handle: u64) -> Option<u128> {
+            Some(handle as u128 + 1000)
+        }
+
+        fn resolve(&mut self, _kind: TypeId, stable: u128) -> Option<u64> {
+            Some((stable - 1000) as u64 + 100)
+        }
+    }
+
+    #[test]
+    fn round_trip_remaps_handles() {
+        let mut registry = SnapshotRegistry::new();
+        registry.register::<Health>("health");
+        registry.register_persist::<Model>("model");
+
+        let mut world = World::new();
+        world.create_entity((Health(5), Model(1)));
+        world.create_entity((Health(7),));
+        let bytes = world.save_snapshot(&registry, &mut Offset).unwrap();
+
+        let mut loaded = World::new();
+        loaded.create_entity((Health(99),));
+        let entities = loaded
+            .load_snapshot(&registry, &mut Offset, &bytes)
+            .unwrap();
+        assert_eq!(entities.len(), 2);
+
+        let mut query = loaded.query::<(&mut Health, Option<&mut Model>)>();
+        let mut state = query
+            .iter()
+            .map(|(health, model)| (health.0, model.map(|model| model.0)))
+            .collect::<Vec<_>>();
+        state.sort_unstable();
+        assert_eq!(state, [(5, Some(101)), (7, None)]);
+    }
+
+    /// Refers to another entity.
+    #[derive(Component, Debug, PartialEq)]
+    struct Parent(Option<Entity>);
+
+    impl Persist for Parent {
+        type Saved = Option<u128>;
+
+        fn save(&self, remap: &mut dyn HandleRemap) -> Result<Self::Saved, SnapshotError> {
+            self.0.map(|parent| remap.save_entity(parent)).transpose()
+        }
+
+        fn load(saved: Self::Saved, remap: &mut dyn HandleRemap) -> Result<Self, SnapshotError> {
+            Ok(Parent(saved.and_then(|parent| remap.load_entity(parent))))
+        }
+    }
+
+    #[test]
+    fn entity_references_follow_their_targets() {
+        let mut registry = SnapshotRegistry::new();
+        registry.register::<Health>("health");
+        registry.register_persist::<Parent>("parent");
+
+        let mut world = World::new();
+        let skipped = world.create_entity((Health(1),));
+        let parent = world.create_entity((Health(2),));
+        world.create_entity((Health(3), Parent(Some(parent))));
+        world.create_entity((Health(4), Parent(Some(skipped))));
+        let bytes = world
+            .save_snapshot_filtered(&registry, &mut NoRemap, |entity| entity != skipped)
+            .unwrap();
+
+        let mut loaded = World::new();
+        loaded.create_entity((Health(99),));
+        let entities = loaded
+            .load_snapshot(&registry, &mut NoRemap, &bytes)
+            .unwrap();
+        assert_eq!(entities.len(), 3);
+        assert_eq!(loaded.get::<Health>(entities[0]).unwrap().0, 2);
+        assert_eq!(loaded.get::<Parent>(entities[1]), Some(&Parent(Some(entities[0]))));
+        // The target wasn't saved, so the reference is dropped.
+        assert_eq!(loaded.get::<Parent>(entities[2]), Some(&Parent(None)));
+    }
+
+    #[test]
+    fn unknown_components_fail_without_touching_the_world() {
+        let mut registry = SnapshotRegistry::new();
+        registry.register::<Health>("health");
+        let mut world = World::new();
+        world.create_entity((Health(5),));
+        let bytes = world.save_snapshot(&registry, &mut NoRemap).unwrap();
+
+        let err = world
+            .load_snapshot(&SnapshotRegistry::new(), &mut NoRemap, &bytes)
+            .unwrap_err();
+        assert_eq!(err, SnapshotError::UnknownComponent("health".into()));
+        assert_eq!(world.query::<&mut Health>().iter().count(), 1);
+    }
+}
@@ -0,0 +1,131 @@
+//! Fixtures for testing systems without starting the app.
+//!
+//! ```ignore
+//! let mut world = TestWorld::new()
+//!     .with_entity((Health(10),))
+//!     .with_entity((Health(3), Poisoned));
+//! world.run_system(poison);
+//! assert_eq!(world.get::<Health>(world.entity(1)).0, 0);
+//! ```
+
+use crate::command_buffer::Commands;
+use crate::component::Component;
+use crate::component::component_storage::ComponentInsertion;
+use crate::entity::Entity;
+use crate::query::{Query, QueryParameter};
+use crate::world::{SystemAccess, World};
+
+/// A [`World`] that remembers the entities spawned into it, in spawn order.
+#[derive(Default)]
+pub struct TestWorld {
+    world: World,
+    entities: Vec<Entity>,
+}
+
+impl TestWorld {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[allow(private_bounds)]
+    pub fn with_entity(mut self, components: impl ComponentInsertion) -> Self {
+        self.spawn(components);
+        self
+    }
+
+    #[allow(private_bounds)]
+    pub fn spawn(&mut self, components: impl ComponentInsertion) -> Entity {
+        let entity = self.world.create_entity(components);
+        self.entities.push(entity);
+        entity
+    }
+
+    /// The `index`th entity spawned through `with_entity` or `spawn`.
+    pub fn entity(&self, index: usize) -> Entity {
+        self.entities[index]
+    }
+
+    pub fn entities(&self) -> &[Entity] {
+        &self.entities
+    }
+
+    /// Returns `entity`'s `T`. Panics if it has none, so assertions read directly.
+    pub fn get<T: Component>(&self, entity: Entity) -> &T {
+        self.world
+            .get::<T>(entity)
+            .unwrap_or_else(|| panic!("{:?} has no {}", entity, std::any::type_name::<T>()))
+    }
+
+    pub fn world(&self) -> &World {
+        &self.world
+    }
+
+    pub fn world_mut(&mut self) -> &mut World {
+        &mut self.world
+    }
+
+    /// Runs a system that needs no engine context, then applies its commands.
+    pub fn run_system<Q: QueryParameter>(
+        &mut self,
+        system: impl FnOnce(Query<'_, Q>, &mut Commands),
+    ) -> &mut Self {
+        self.run_with(|access| {
+            let mut query = Query::new(access.archetypes, access.sparse_sets);
+            query.build_matches();
+            system(query, &mut access.commands);
+        })
+    }
+
+    /// Hands `f` the same split access the engine gives its systems, then applies the
+    /// recorded commands. Crates with their own system context build harnesses on this.
+    pub fn run_with(&mut self, f: impl FnOnce(&mut SystemAccess)) -> &mut Self {
+        let queue = {
+            let mut access = self.world.system_access();
+            f(&mut access);
+            access.into_queue()
+        };
+        self.world.flush_queue(queue);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Component, Debug, PartialEq)]
+    struct Health(u32);
+
+    #[derive(Component)]
+    struct Poisoned;
+
+    #[derive(Component)]
+    struct Corpse;
+
+    fn poison(mut query: Query<'_, (&mut Health, &mut Poisoned)>, _commands: &mut Commands) {
+        for (health, _) in query.iter() {
+            health.0 = health.0.saturating_sub(5);
+        }
+    }
+
+    fn leave_corpses(mut query: Query<'_, &mut Health>, commands: &mut Commands) {
+        for health in query.iter() {
+            if health.0 == 0 {
+                commands.spawn_entity((Corpse,));
+            }
+        }
+    }
+
+    #[test]
+    fn runs_systems_and_applies_their_commands() {
+        let mut world = TestWorld::new()
+            .with_entity((Health(10),))
+            .with_entity((Health(3), Poisoned));
+
+        world.run_system(poison).run_system(leave_corpses);
+
+        assert_eq!(world.get::<Health>(world.entity(0)), &Health(10));
+        assert_eq!(world.get::<Health>(world.entity(1)), &Health(0));
+        assert_eq!(world.world_mut().query::<&mut Corpse>().iter().count(), 1);
+    }
+}
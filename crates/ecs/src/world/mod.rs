@@ -1,21 +1,30 @@
 use crate::command_buffer::{Command, Commands};
-use crate::component::archetype::{Archetype, ColumnFactory};
+use crate::component::archetype::{Archetype, ColumnFactory, StorageFactory};
 use crate::component::component_storage::ComponentInsertion;
+use crate::component::sparse_set::{SparseSet, SparseSets};
+use crate::component::{Component, StorageType};
 use crate::entity::Entity;
+use crate::name::{NameComponent, NameIndex, TagsComponent};
 use crate::query::{Query, QueryParameter};
+use crate::snapshot::{DecodedComponent, HandleRemap, SnapshotError, SnapshotRegistry};
 use std::any::TypeId;
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
 
 pub struct World {
     pub(crate) archetypes: Vec<Archetype>,
     pub(crate) archetype_index: HashMap<ArchetypeKey, ArchetypeId>,
     column_registry: ColumnRegistry,
+    pub(crate) sparse_sets: SparseSets,
     pub(crate) entity_allocator: EntityAllocator,
+    name_index: NameIndex,
+    /// Per component type, how many times one was added to or removed from an entity.
+    changes: HashMap<TypeId, u64>,
 }
 
 /// Provides split access to archetypes and command recording without exposing World directly.
 pub struct SystemAccess<'a> {
     pub archetypes: &'a mut Vec<Archetype>,
+    pub sparse_sets: &'a mut SparseSets,
     pub commands: Commands<'a>,
 }
 
@@ -74,7 +83,10 @@ impl World {
             archetypes: vec![],
             archetype_index: HashMap::new(),
             column_registry: ColumnRegistry::new(),
+            sparse_sets: SparseSets::default(),
             entity_allocator: EntityAllocator::new(),
+            name_index: NameIndex::default(),
+            changes: HashMap::new(),
             //query_cache: HashMap::new(),
         }
     }
@@ -82,6 +94,7 @@ impl World {
     pub fn system_access(&mut self) -> SystemAccess<'_> {
         SystemAccess {
             archetypes: &mut self.archetypes,
+            sparse_sets: &mut self.sparse_sets,
             commands: Commands {
                 queue: vec![],
                 entity_allocator: &mut self.entity_allocator,
@@ -90,7 +103,7 @@ impl World {
     }
 
     pub fn query<Q: QueryParameter>(&mut self) -> Query<'_, Q> {
-        let mut query = Query::new(&mut self.archetypes);
+        let mut query = Query::new(&mut self.archetypes, &mut self.sparse_sets);
         query.build_matches();
         query
     }
@@ -106,13 +119,31 @@ impl World {
 
     #[allow(private_bounds)]
     pub fn create_reserved_entity(&mut self, entity: Entity, components: impl ComponentInsertion) {
+        let mut parts = vec![];
+        components.for_each_component(|type_id, component_value, factory| {
+            parts.push((type_id, component_value, factory))
+        });
+        self.insert_components(entity, parts);
+    }
+
+    /// Stores a reserved entity's components, whose types are only known at runtime.
+    pub(crate) fn insert_components(&mut self, entity: Entity, parts: Vec<DecodedComponent>) {
         let mut values = vec![];
         let mut type_ids = vec![];
-        components.for_each_component(|type_id, component_value, column_factory| {
-            self.column_registry.ensure(type_id, column_factory);
-            values.push(component_value);
-            type_ids.push(type_id);
-        });
+        let mut sparse_values = vec![];
+        for (type_id, component_value, factory) in parts {
+            *self.changes.entry(type_id).or_default() += 1;
+            match factory {
+                StorageFactory::Table(column_factory) => {
+                    self.column_registry.ensure(type_id, column_factory);
+                    values.push(component_value);
+                    type_ids.push(type_id);
+                }
+                StorageFactory::Sparse(set_factory) => {
+                    sparse_values.push((component_value, set_factory))
+                }
+            }
+        }
 
         type_ids.sort_unstable();
         let key = ArchetypeKey { type_ids };
@@ -129,6 +160,227 @@ impl World {
         };
         let row = self.archetypes[archetype_id.0].insert(entity, values);
         self.entity_allocator.entity_meta[entity.0] = Some(EntityStorageData { archetype_id, row });
+
+        for (value, set_factory) in sparse_values {
+            self.sparse_sets.insert_erased(entity, value, set_factory);
+        }
+        self.name_index.insert(entity, &self.sparse_sets);
+    }
+
+    /// Adds or replaces a sparse component on a live entity. Table components are fixed
+    /// at spawn, as entities cannot move between archetypes.
+    pub fn insert_sparse<T: Component>(&mut self, entity: Entity, component: T) {
+        assert!(
+            T::STORAGE == StorageType::Sparse,
+            "{} is not a sparse component; mark it #[component(storage = \"sparse\")]",
+            std::any::type_name::<T>()
+        );
+        if self.is_alive(entity) {
+            self.reindex_names(TypeId::of::<T>(), entity, |sets| {
+                sets.get_or_create::<T>().insert(entity, component);
+            });
+        }
+    }
+
+    pub fn remove_sparse<T: Component>(&mut self, entity: Entity) -> Option<T> {
+        self.reindex_names(TypeId::of::<T>(), entity, |sets| {
+            sets.get_mut::<T>()?.remove(entity)
+        })
+    }
+
+    /// Runs `change` on the sparse sets, keeping the name and tag index in step when it
+    /// touches `entity`'s component of type `type_id`.
+    fn reindex_names<R>(
+        &mut self,
+        type_id: TypeId,
+        entity: Entity,
+        change: impl FnOnce(&mut SparseSets) -> R,
+    ) -> R {
+        *self.changes.entry(type_id).or_default() += 1;
+        if !NameIndex::indexes(type_id) {
+            return change(&mut self.sparse_sets);
+        }
+        self.name_index.remove(entity, &self.sparse_sets);
+        let result = change(&mut self.sparse_sets);
+        self.name_index.insert(entity, &self.sparse_sets);
+        result
+    }
+
+    /// A counter that advances whenever a `T` is added to or removed from an entity,
+    /// including by spawning and despawning. Changing a component in place through a
+    /// query doesn't count. Compare against an earlier value to skip rebuilding an index
+    /// of `T` when nothing was added or removed.
+    pub fn component_changes<T: Component>(&self) -> u64 {
+        self.changes.get(&TypeId::of::<T>()).copied().unwrap_or(0)
+    }
+
+    /// The first entity given `name` that still has it. See [`crate::name`].
+    pub fn find_by_name(&self, name: &str) -> Option<Entity> {
+        self.name_index.first_named(name)
+    }
+
+    /// Every entity tagged `tag`, in the order they were tagged.
+    pub fn with_tag(&self, tag: &str) -> impl Iterator<Item = Entity> + '_ {
+        self.name_index.tagged(tag).iter().copied()
+    }
+
+    /// Names `entity`, replacing any name it had.
+    pub fn set_name(&mut self, entity: Entity, name: impl Into<String>) {
+        self.insert_sparse(entity, NameComponent::new(name));
+    }
+
+    /// Adds `tag` to `entity`'s tags, giving it a [`TagsComponent`] if it has none.
+    pub fn add_tag(&mut self, entity: Entity, tag: impl Into<String>) {
+        let mut tags = self.get::<TagsComponent>(entity).cloned().unwrap_or_default();
+        if tags.insert(tag.into()) {
+            self.insert_sparse(entity, tags);
+        }
+    }
+
+    /// Removes `tag` from `entity`'s tags, if it has it.
+    pub fn remove_tag(&mut self, entity: Entity, tag: &str) {
+        let Some(mut tags) = self.get::<TagsComponent>(entity).cloned() else {
+            return;
+        };
+        if tags.remove(tag) {
+            self.insert_sparse(entity, tags);
+        }
+    }
+
+    /// All values of the sparse component `T`, or `None` if none was ever inserted.
+    pub fn sparse_set<T: Component>(&self) -> Option<&SparseSet<T>> {
+        self.sparse_sets.get::<T>()
+    }
+
+    /// Returns `entity`'s `T`, if it is alive and has one.
+    pub fn get<T: Component>(&self, entity: Entity) -> Option<&T> {
+        if T::STORAGE == StorageType::Sparse {
+            return self.sparse_sets.get::<T>()?.get(entity);
+        }
+
+        let meta = self.entity_allocator.entity_meta.get(entity.0)?.as_ref()?;
+        let archetype = &self.archetypes[meta.archetype_id.0];
+        let &column = archetype.components.get(&TypeId::of::<T>())?;
+        archetype.columns[column]
+            .data
+            .as_any()
+            .downcast_ref::<Vec<T>>()
+            .unwrap()
+            .get(meta.row)
+    }
+
+    /// Calls `f` with every entity that has a `T`, in storage order.
+    pub fn for_each_component<T: Component>(&self, mut f: impl FnMut(Entity, &T)) {
+        if T::STORAGE == StorageType::Sparse {
+            for (entity, component) in self.sparse_sets.get::<T>().into_iter().flat_map(SparseSet::iter) {
+                f(entity, component);
+            }
+            return;
+        }
+
+        for archetype in &self.archetypes {
+            let Some(&column) = archetype.components.get(&TypeId::of::<T>()) else {
+                continue;
+            };
+            let data = archetype.columns[column]
+                .data
+                .as_any()
+                .downcast_ref::<Vec<T>>()
+                .unwrap();
+            for (&entity, component) in archetype.entities.iter().zip(data) {
+                f(entity, component);
+            }
+        }
+    }
+
+    /// Encodes every entity's registered components. Entities with none are skipped.
+    pub fn save_snapshot(
+        &self,
+        registry: &SnapshotRegistry,
+        remap: &mut dyn HandleRemap,
+    ) -> Result<Vec<u8>, SnapshotError> {
+        registry.save(self, remap, &|_| true)
+    }
+
+    /// Like [`World::save_snapshot`], but only saves entities for which `keep` is true.
+    pub fn save_snapshot_filtered(
+        &self,
+        registry: &SnapshotRegistry,
+        remap: &mut dyn HandleRemap,
+        keep: impl Fn(Entity) -> bool,
+    ) -> Result<Vec<u8>, SnapshotError> {
+        registry.save(self, remap, &keep)
+    }
+
+    /// Replaces every entity with those in `bytes`, returning the new entities in saved
+    /// order. Entity ids are not preserved; the loaded entities are numbered from 0. The
+    /// world is left untouched if decoding fails.
+    ///
+    /// Components whose type is not in `registry` were never saved, so they are lost
+    /// here; a warning names each such type.
+    pub fn load_snapshot(
+        &mut self,
+        registry: &SnapshotRegistry,
+        remap: &mut dyn HandleRemap,
+        bytes: &[u8],
+    ) -> Result<Vec<Entity>, SnapshotError> {
+        let decoded = registry.load(bytes, remap)?;
+
+        for name in self.unregistered_components(registry) {
+            eprintln!(
+                "Loading a snapshot drops '{}' components: the type is not registered for \
+                 snapshots",
+                name
+            );
+        }
+        self.clear();
+        // Entity references in the snapshot were resolved to the positions entities are
+        // restored at.
+        self.entity_allocator = EntityAllocator::new();
+        Ok(decoded
+            .into_iter()
+            .map(|parts| {
+                let entity = self.entity_allocator.reserve();
+                self.insert_components(entity, parts);
+                entity
+            })
+            .collect())
+    }
+
+    /// Names of the component types on live entities that `registry` doesn't save, sorted.
+    fn unregistered_components(&self, registry: &SnapshotRegistry) -> BTreeSet<&'static str> {
+        let columns = self
+            .archetypes
+            .iter()
+            .filter(|archetype| !archetype.entities.is_empty())
+            .flat_map(|archetype| {
+                archetype.components.iter().map(|(&type_id, &column)| {
+                    (type_id, archetype.columns[column].data.type_name())
+                })
+            });
+        columns
+            .chain(self.sparse_sets.occupied())
+            .filter(|(type_id, _)| !registry.contains(*type_id))
+            .map(|(_, name)| name)
+            .collect()
+    }
+
+    /// Despawns every entity.
+    pub fn clear(&mut self) {
+        let alive = (0..self.entity_allocator.entity_meta.len())
+            .map(Entity)
+            .filter(|&entity| self.is_alive(entity))
+            .collect::<Vec<_>>();
+        for entity in alive {
+            self.remove_entity(entity);
+        }
+    }
+
+    pub fn is_alive(&self, entity: Entity) -> bool {
+        self.entity_allocator
+            .entity_meta
+            .get(entity.0)
+            .is_some_and(Option::is_some)
     }
 
     pub fn remove_entity(&mut self, entity: Entity) {
@@ -139,6 +391,9 @@ impl World {
         let row = meta.row;
 
         let archetype = &mut self.archetypes[archetype_id.0];
+        for &type_id in archetype.components.keys() {
+            *self.changes.entry(type_id).or_default() += 1;
+        }
         if let Some(swapped) = archetype.remove(row) {
             self.entity_allocator.entity_meta[swapped.0]
                 .as_mut()
@@ -146,6 +401,10 @@ impl World {
                 .row = row;
         }
 
+        self.name_index.remove(entity, &self.sparse_sets);
+        let changes = &mut self.changes;
+        self.sparse_sets
+            .remove_entity(entity, |type_id| *changes.entry(type_id).or_default() += 1);
         self.entity_allocator.entity_meta[entity.0] = None;
         self.entity_allocator.free_list.push(entity.0);
     }
@@ -155,6 +414,14 @@ impl World {
             match cmd {
                 Command::SpawnEntity(_, inserter) => inserter(self),
                 Command::DespawnEntity(entity) => self.remove_entity(entity),
+                Command::InsertSparse(entity, inserter) => {
+                    if self.is_alive(entity) {
+                        inserter(self)
+                    }
+                }
+                Command::RemoveSparse(entity, type_id) => {
+                    self.reindex_names(type_id, entity, |sets| sets.remove(type_id, entity))
+                }
             }
         }
     }
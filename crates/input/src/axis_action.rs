@@ -27,16 +27,56 @@ impl From<&str> for AxisAction {
 
 #[derive(Debug, Clone)]
 pub enum AxisBinding {
+    /// +`scale` while `positive` is pressed, -`scale` while `negative` is pressed.
     Composite {
         positive: InputAction,
         negative: InputAction,
+        scale: f32,
     },
+    /// Raw source value times `sensitivity`. Values whose magnitude is below `dead_zone`,
+    /// measured before scaling, read as zero.
     Analog {
         source: AnalogSource,
         sensitivity: f32,
+        dead_zone: f32,
     },
 }
 
+impl AxisBinding {
+    pub fn composite(positive: impl Into<InputAction>, negative: impl Into<InputAction>) -> Self {
+        AxisBinding::Composite {
+            positive: positive.into(),
+            negative: negative.into(),
+            scale: 1.0,
+        }
+    }
+
+    pub fn analog(source: AnalogSource, sensitivity: f32) -> Self {
+        AxisBinding::Analog {
+            source,
+            sensitivity,
+            dead_zone: 0.0,
+        }
+    }
+
+    /// Sets the dead zone of an analog binding. Composite bindings are unaffected.
+    pub fn with_dead_zone(mut self, value: f32) -> Self {
+        if let AxisBinding::Analog { dead_zone, .. } = &mut self {
+            *dead_zone = value;
+        }
+        self
+    }
+
+    /// Sets the scale of a composite binding or the sensitivity of an analog one.
+    pub fn with_scale(mut self, value: f32) -> Self {
+        match &mut self {
+            AxisBinding::Composite { scale, .. } => *scale = value,
+            AxisBinding::Analog { sensitivity, .. } => *sensitivity = value,
+        }
+        self
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum AnalogSource {
     MouseX,
@@ -1,9 +1,10 @@
 use crate::axis_action::{AxisAction, AxisBinding};
-use crate::input_action::{InputAction, InputBinding};
+use crate::input_action::{ActionBinding, InputAction};
 use std::collections::HashMap;
 
 #[derive(Debug, Default)]
 pub struct InputConfig {
-    pub action_binding: HashMap<InputAction, Vec<InputBinding>>,
-    pub axis_binding: HashMap<AxisAction, AxisBinding>,
+    pub action_binding: HashMap<InputAction, ActionBinding>,
+    /// Values of all bindings of an axis are summed.
+    pub axis_binding: HashMap<AxisAction, Vec<AxisBinding>>,
 }
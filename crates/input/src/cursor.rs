@@ -0,0 +1,30 @@
+/// How the OS cursor behaves over the window. Applied by the platform layer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CursorMode {
+    /// Visible and free to leave the window.
+    #[default]
+    Free,
+    /// Invisible while over the window, but free to leave it.
+    Hidden,
+    /// Visible and kept inside the window.
+    Confined,
+    /// Invisible and held in place. Use with `MouseMotion::Raw` for mouse-look.
+    Locked,
+}
+
+impl CursorMode {
+    pub fn is_visible(self) -> bool {
+        matches!(self, CursorMode::Free | CursorMode::Confined)
+    }
+}
+
+/// Where the `MouseX`/`MouseY` analog sources read their motion from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MouseMotion {
+    /// Change in cursor position inside the window. Stops at the window edges and
+    /// is affected by OS pointer acceleration.
+    #[default]
+    Window,
+    /// Unaccelerated device motion, reported even while the cursor is locked.
+    Raw,
+}
@@ -0,0 +1,17 @@
+use crate::device::{KeyCode, MouseButton};
+use std::time::Instant;
+
+/// A button edge, stamped when the platform layer reported it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InputEvent {
+    pub kind: InputEventKind,
+    pub timestamp: Instant,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputEventKind {
+    KeyPressed(KeyCode),
+    KeyReleased(KeyCode),
+    MouseButtonPressed(MouseButton),
+    MouseButtonReleased(MouseButton),
+}
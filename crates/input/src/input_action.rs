@@ -1,4 +1,5 @@
 use crate::device::{KeyCode, MouseButton};
+use std::ops::BitOr;
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct InputAction(String);
@@ -24,13 +25,71 @@ impl From<String> for InputAction {
     }
 }
 
+/// Set of modifier keys held for a chord. Combine with `|`, e.g. `Modifiers::CTRL | Modifiers::SHIFT`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Modifiers {
+    pub ctrl: bool,
+    pub shift: bool,
+    pub alt: bool,
+}
+
+impl Modifiers {
+    pub const NONE: Modifiers = Modifiers { ctrl: false, shift: false, alt: false };
+    pub const CTRL: Modifiers = Modifiers { ctrl: true, shift: false, alt: false };
+    pub const SHIFT: Modifiers = Modifiers { ctrl: false, shift: true, alt: false };
+    pub const ALT: Modifiers = Modifiers { ctrl: false, shift: false, alt: true };
+}
+
+impl BitOr for Modifiers {
+    type Output = Modifiers;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Modifiers {
+            ctrl: self.ctrl || rhs.ctrl,
+            shift: self.shift || rhs.shift,
+            alt: self.alt || rhs.alt,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum InputBinding {
     Key(KeyCode),
     Mouse(MouseButton),
+    /// `key` pressed while exactly `modifiers` are held, e.g. Ctrl+S. While a chord
+    /// matches, plain `Key` bindings of the same key are suppressed, so `S` bound to
+    /// gameplay does not fire on Ctrl+S.
+    Chord { modifiers: Modifiers, key: KeyCode },
     //Gamepad
 }
 
+impl InputBinding {
+    pub fn chord(modifiers: Modifiers, key: KeyCode) -> Self {
+        InputBinding::Chord { modifiers, key }
+    }
+}
+
+/// How held bindings turn into action presses. Durations are in seconds.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Activation {
+    /// Pressed while any binding is held.
+    #[default]
+    Press,
+    /// Pressed for one frame when a binding is released within `max_duration` of being pressed.
+    Tap { max_duration: f32 },
+    /// Pressed once a binding has been held for `duration`, until it is released.
+    Hold { duration: f32 },
+    /// Pressed when a binding is pressed a second time within `max_gap`, until it is released.
+    DoubleTap { max_gap: f32 },
+}
+
+/// Every binding of one action and how they activate it.
+#[derive(Debug, Clone, Default)]
+pub struct ActionBinding {
+    pub bindings: Vec<InputBinding>,
+    pub activation: Activation,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum InputState {
     Pressed,
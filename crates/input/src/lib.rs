@@ -1,11 +1,19 @@
 mod axis_action;
 mod config;
+mod cursor;
 mod device;
+mod event;
 mod input_action;
 mod manager;
+mod replay;
+mod text;
 
 pub use axis_action::{AnalogSource, AxisAction, AxisBinding};
 pub use config::InputConfig;
+pub use cursor::{CursorMode, MouseMotion};
 pub use device::{KeyCode, MouseButton};
-pub use input_action::{InputAction, InputBinding, InputState};
+pub use event::{InputEvent, InputEventKind};
+pub use input_action::{ActionBinding, Activation, InputAction, InputBinding, InputState, Modifiers};
 pub use manager::{GameInputState, InputManager};
+pub use replay::{InputRecording, RecordedInput, RecordedTick, ReplayError};
+pub use text::TextInputEvent;
@@ -1,31 +1,72 @@
 use crate::axis_action::{AnalogSource, AxisAction, AxisBinding};
 use crate::config::InputConfig;
+use crate::cursor::{CursorMode, MouseMotion};
 use crate::device::{KeyCode, MouseButton};
-use crate::input_action::{InputAction, InputBinding, InputState};
+use crate::event::{InputEvent, InputEventKind};
+use crate::input_action::{ActionBinding, Activation, InputAction, InputBinding, InputState, Modifiers};
+use crate::text::TextInputEvent;
 use std::collections::{HashMap, HashSet};
+use std::time::Instant;
 
 #[derive(Debug, Default)]
 pub struct GameInputState {
     keys_down: HashSet<KeyCode>,
-    prev_keys_down: HashSet<KeyCode>,
-
     mouse_buttons_down: HashSet<MouseButton>,
-    prev_mouse_buttons_down: HashSet<MouseButton>,
+
+    /// Edges reported since the last `update`.
+    pending_events: Vec<InputEvent>,
+    /// Edges visible this frame, moved from `pending_events` by `update`.
+    events: Vec<InputEvent>,
 
     action_states: HashMap<InputAction, InputState>,
+    action_trackers: HashMap<InputAction, ActionTracker>,
     axis_values: HashMap<AxisAction, f32>,
 
-    mouse_position: [f32; 2],
+    mouse_position: Option<[f32; 2]>,
     mouse_delta: [f32; 2],
+    raw_mouse_delta: [f32; 2],
     mouse_wheel: f32,
+
+    text_events: Vec<TextInputEvent>,
+
+    /// Seconds of input time, advanced by `update`. Drives tap/hold/double-tap timing.
+    time: f32,
+}
+
+/// Per-action history for activation types that depend on timing.
+#[derive(Debug, Default)]
+struct ActionTracker {
+    /// Any binding was down last frame.
+    down: bool,
+    /// The action was active last frame.
+    active: bool,
+    pressed_at: f32,
+    last_tap: Option<f32>,
+    double_tapped: bool,
+}
+
+/// Cursor mode requested by the game and the conditions that override it.
+#[derive(Debug, Default)]
+struct CursorState {
+    requested: CursorMode,
+    motion: MouseMotion,
+    unfocused: bool,
+    ui_contexts: u32,
+    /// Last mode handed to the platform layer by `take_cursor_change`.
+    applied: CursorMode,
 }
 
 /// Manages keyboard, mouse button, and axis input. Registered as a manager in
 /// `EngineContext`. Call `update` once per frame before reading any state, then
-/// `end_frame` after all systems have run to advance the prev-frame snapshot.
+/// `end_frame` after all systems have run to clear per-frame accumulations.
 pub struct InputManager {
     input_state: GameInputState,
     config: InputConfig,
+    cursor: CursorState,
+    text_input: bool,
+    /// `text_input` as last handed to the platform layer.
+    text_input_applied: bool,
+    pointer_over_ui: bool,
 }
 
 impl InputManager {
@@ -34,6 +75,10 @@ impl InputManager {
         Self {
             input_state: GameInputState::default(),
             config: InputConfig::default(),
+            cursor: CursorState::default(),
+            text_input: false,
+            text_input_applied: false,
+            pointer_over_ui: false,
         }
     }
 }
@@ -45,52 +90,251 @@ impl Default for InputManager {
 }
 
 impl InputManager {
-
-    /// Binds one or more `InputBinding`s to a named action. Any of the bindings
-    /// being held counts as the action being down.
+    /// Binds one or more `InputBinding`s to a named action, replacing any existing
+    /// bindings. Any of the bindings being held counts as the action being down.
     pub fn bind_action(&mut self, action: impl Into<InputAction>, bindings: Vec<InputBinding>) {
+        self.bind_action_with(action, bindings, Activation::Press);
+    }
+
+    /// Like `bind_action`, with an activation type such as tap, hold or double-tap.
+    pub fn bind_action_with(
+        &mut self,
+        action: impl Into<InputAction>,
+        bindings: Vec<InputBinding>,
+        activation: Activation,
+    ) {
         let action = action.into();
-        self.config.action_binding.insert(action, bindings);
+        self.input_state.action_trackers.remove(&action);
+        self.config
+            .action_binding
+            .insert(action, ActionBinding { bindings, activation });
+    }
+
+    /// Adds a binding to an action, keeping the ones already bound.
+    pub fn add_action_binding(&mut self, action: impl Into<InputAction>, binding: InputBinding) {
+        self.config
+            .action_binding
+            .entry(action.into())
+            .or_default()
+            .bindings
+            .push(binding);
     }
 
-    /// Binds an axis to either a composite key pair or an analog source.
-    pub fn bind_axis(&mut self, action: impl Into<AxisAction>, bindings: AxisBinding) {
+    /// Binds an axis to either a composite key pair or an analog source, replacing any
+    /// existing bindings.
+    pub fn bind_axis(&mut self, action: impl Into<AxisAction>, binding: AxisBinding) {
         let action = action.into();
-        self.config.axis_binding.insert(action, bindings);
+        self.config.axis_binding.insert(action, vec![binding]);
+    }
+
+    /// Adds a binding to an axis. The values of all its bindings are summed.
+    pub fn add_axis_binding(&mut self, action: impl Into<AxisAction>, binding: AxisBinding) {
+        self.config
+            .axis_binding
+            .entry(action.into())
+            .or_default()
+            .push(binding);
+    }
+
+    // ---- Cursor -------------------------------------------------------------
+
+    /// Locks and hides the cursor and switches mouse axes to raw device motion.
+    pub fn grab_cursor(&mut self) {
+        self.set_cursor_mode(CursorMode::Locked);
+        self.cursor.motion = MouseMotion::Raw;
+    }
+
+    /// Frees the cursor and switches mouse axes back to window motion.
+    pub fn release_cursor(&mut self) {
+        self.set_cursor_mode(CursorMode::Free);
+        self.cursor.motion = MouseMotion::Window;
+    }
+
+    /// Requests a cursor mode. It takes effect while the window is focused and no UI
+    /// context is active.
+    pub fn set_cursor_mode(&mut self, mode: CursorMode) {
+        self.cursor.requested = mode;
+    }
+
+    pub fn set_mouse_motion(&mut self, motion: MouseMotion) {
+        self.cursor.motion = motion;
+    }
+
+    pub fn mouse_motion(&self) -> MouseMotion {
+        self.cursor.motion
+    }
+
+    /// The cursor mode currently in effect. Always `Free` while the window is unfocused
+    /// or a UI context is active.
+    pub fn cursor_mode(&self) -> CursorMode {
+        if self.cursor.unfocused || self.cursor.ui_contexts > 0 {
+            CursorMode::Free
+        } else {
+            self.cursor.requested
+        }
+    }
+
+    /// True while the cursor is locked or confined for the game.
+    pub fn is_cursor_grabbed(&self) -> bool {
+        matches!(self.cursor_mode(), CursorMode::Locked | CursorMode::Confined)
+    }
+
+    /// Releases the cursor while a menu, console or other UI owns the mouse. Contexts
+    /// nest; the requested mode returns when the last one is popped.
+    pub fn push_ui_context(&mut self) {
+        self.cursor.ui_contexts += 1;
+    }
+
+    pub fn pop_ui_context(&mut self) {
+        self.cursor.ui_contexts = self.cursor.ui_contexts.saturating_sub(1);
+    }
+
+    /// Marks whether the pointer is over an interactive UI element. Set by the UI layout
+    /// pass each frame.
+    pub fn set_pointer_over_ui(&mut self, over: bool) {
+        self.pointer_over_ui = over;
+    }
+
+    /// True while the pointer is over interactive UI. Gameplay should ignore mouse
+    /// clicks while this is set.
+    pub fn is_pointer_over_ui(&self) -> bool {
+        self.pointer_over_ui
+    }
+
+    /// Returns the effective cursor mode if it changed since the last call. The platform
+    /// layer applies it to the window.
+    pub fn take_cursor_change(&mut self) -> Option<CursorMode> {
+        let mode = self.cursor_mode();
+        if mode == self.cursor.applied {
+            return None;
+        }
+        self.cursor.applied = mode;
+        Some(mode)
+    }
+
+    // ---- Text input ---------------------------------------------------------
+
+    /// Starts collecting typed text, e.g. when a text field gains focus. While active,
+    /// key bindings are suppressed so typing does not trigger gameplay actions; raw key
+    /// queries such as `is_key_just_pressed` still work for Enter, Backspace and arrows.
+    pub fn begin_text_input(&mut self) {
+        self.text_input = true;
+    }
+
+    pub fn end_text_input(&mut self) {
+        self.text_input = false;
+        self.input_state.text_events.clear();
+    }
+
+    pub fn is_text_input_active(&self) -> bool {
+        self.text_input
+    }
+
+    /// Text typed since the last `end_frame`, in order.
+    pub fn text_events(&self) -> &[TextInputEvent] {
+        &self.input_state.text_events
+    }
+
+    /// Returns whether text input (IME) should be enabled if that changed since the last
+    /// call. The platform layer applies it to the window.
+    pub fn take_text_input_change(&mut self) -> Option<bool> {
+        if self.text_input == self.text_input_applied {
+            return None;
+        }
+        self.text_input_applied = self.text_input;
+        Some(self.text_input)
     }
 
     // ---- Raw event handlers (called by the platform layer) ------------------
 
-    /// Records a key-down event. Called by the winit event loop.
+    /// Records a key-down event. Called by the winit event loop. Repeats while the key
+    /// is already down are ignored.
     pub fn on_key_pressed(&mut self, key: KeyCode) {
-        self.input_state.keys_down.insert(key);
+        if self.input_state.keys_down.insert(key) {
+            self.push_event(InputEventKind::KeyPressed(key));
+        }
     }
 
     /// Records a key-up event. Called by the winit event loop.
     pub fn on_key_released(&mut self, key: KeyCode) {
-        self.input_state.keys_down.remove(&key);
+        if self.input_state.keys_down.remove(&key) {
+            self.push_event(InputEventKind::KeyReleased(key));
+        }
     }
 
     /// Records a mouse button down event. Called by the winit event loop.
     pub fn on_mouse_button_pressed(&mut self, button: MouseButton) {
-        self.input_state.mouse_buttons_down.insert(button);
+        if self.input_state.mouse_buttons_down.insert(button) {
+            self.push_event(InputEventKind::MouseButtonPressed(button));
+        }
     }
 
     /// Records a mouse button up event. Called by the winit event loop.
     pub fn on_mouse_button_released(&mut self, button: MouseButton) {
-        self.input_state.mouse_buttons_down.remove(&button);
+        if self.input_state.mouse_buttons_down.remove(&button) {
+            self.push_event(InputEventKind::MouseButtonReleased(button));
+        }
     }
 
-    /// Accumulates raw mouse motion delta. Called by the winit event loop.
-    /// Delta is reset to zero by `end_frame`.
+    fn push_event(&mut self, kind: InputEventKind) {
+        self.input_state.pending_events.push(InputEvent {
+            kind,
+            timestamp: Instant::now(),
+        });
+    }
+
+    /// Accumulates raw device motion. Called by the winit event loop.
+    /// Ignored while the window is unfocused or a UI context is active, since device
+    /// events arrive regardless of focus. Delta is reset to zero by `end_frame`.
     pub fn on_mouse_moved(&mut self, delta_x: f32, delta_y: f32) {
-        self.input_state.mouse_delta[0] += delta_x;
-        self.input_state.mouse_delta[1] += delta_y;
+        if self.cursor.unfocused || self.cursor.ui_contexts > 0 {
+            return;
+        }
+        self.input_state.raw_mouse_delta[0] += delta_x;
+        self.input_state.raw_mouse_delta[1] += delta_y;
     }
 
-    /// Updates the absolute mouse position in window coordinates.
+    /// Updates the absolute mouse position in window coordinates and accumulates the
+    /// window-space delta.
     pub fn on_mouse_position(&mut self, x: f32, y: f32) {
-        self.input_state.mouse_position = [x, y];
+        if let Some([prev_x, prev_y]) = self.input_state.mouse_position {
+            self.input_state.mouse_delta[0] += x - prev_x;
+            self.input_state.mouse_delta[1] += y - prev_y;
+        }
+        self.input_state.mouse_position = Some([x, y]);
+    }
+
+    /// Forgets the cursor position when it leaves the window, so re-entering at a
+    /// different edge does not produce a jump.
+    pub fn on_cursor_left(&mut self) {
+        self.input_state.mouse_position = None;
+    }
+
+    /// Records committed text from a key press or IME. Ignored unless text input is active.
+    pub fn on_text(&mut self, text: &str) {
+        if !self.text_input {
+            return;
+        }
+        let text = text.chars().filter(|c| !c.is_control()).collect::<String>();
+        if !text.is_empty() {
+            self.input_state.text_events.push(TextInputEvent::Text(text));
+        }
+    }
+
+    /// Records the current IME composition. Ignored unless text input is active.
+    pub fn on_ime_preedit(&mut self, text: &str, cursor: Option<(usize, usize)>) {
+        if !self.text_input {
+            return;
+        }
+        self.input_state.text_events.push(TextInputEvent::Preedit {
+            text: text.to_string(),
+            cursor,
+        });
+    }
+
+    /// Records window focus. Losing focus releases the cursor until focus returns.
+    pub fn on_focus_changed(&mut self, focused: bool) {
+        self.cursor.unfocused = !focused;
     }
 
     /// Accumulates mouse wheel scroll. Called by the winit event loop.
@@ -144,10 +388,15 @@ impl InputManager {
         self.input_state.keys_down.contains(&key)
     }
 
-    /// Returns true only on the frame the key first became pressed.
+    /// Returns true if the key was pressed since the previous frame, even if it was
+    /// released again before this one.
     pub fn is_key_just_pressed(&self, key: KeyCode) -> bool {
-        self.input_state.keys_down.contains(&key)
-            && !self.input_state.prev_keys_down.contains(&key)
+        self.has_event(InputEventKind::KeyPressed(key))
+    }
+
+    /// Returns true if the key was released since the previous frame.
+    pub fn is_key_just_released(&self, key: KeyCode) -> bool {
+        self.has_event(InputEventKind::KeyReleased(key))
     }
 
     /// Returns true if the mouse button is currently held down.
@@ -155,13 +404,37 @@ impl InputManager {
         self.input_state.mouse_buttons_down.contains(&button)
     }
 
-    /// Returns the raw mouse movement delta accumulated since the last `end_frame`.
+    /// Returns true if the mouse button was pressed since the previous frame.
+    pub fn is_mouse_button_just_pressed(&self, button: MouseButton) -> bool {
+        self.has_event(InputEventKind::MouseButtonPressed(button))
+    }
+
+    /// Returns true if the mouse button was released since the previous frame.
+    pub fn is_mouse_button_just_released(&self, button: MouseButton) -> bool {
+        self.has_event(InputEventKind::MouseButtonReleased(button))
+    }
+
+    /// Button edges that arrived since the previous frame, oldest first.
+    pub fn events(&self) -> &[InputEvent] {
+        &self.input_state.events
+    }
+
+    fn has_event(&self, kind: InputEventKind) -> bool {
+        self.input_state.events.iter().any(|event| event.kind == kind)
+    }
+
+    /// Returns the mouse movement accumulated since the last `end_frame`, from the
+    /// source selected by `set_mouse_motion`.
     pub fn get_mouse_delta(&self) -> [f32; 2] {
-        self.input_state.mouse_delta
+        match self.cursor.motion {
+            MouseMotion::Window => self.input_state.mouse_delta,
+            MouseMotion::Raw => self.input_state.raw_mouse_delta,
+        }
     }
 
-    /// Returns the current absolute mouse position in window coordinates.
-    pub fn get_mouse_position(&self) -> [f32; 2] {
+    /// Returns the last mouse position in window coordinates, or `None` while the
+    /// cursor is outside the window.
+    pub fn get_mouse_position(&self) -> Option<[f32; 2]> {
         self.input_state.mouse_position
     }
 
@@ -172,87 +445,118 @@ impl InputManager {
 
     // ---- Per-frame lifecycle ------------------------------------------------
 
-    /// Recalculates action and axis states from the current raw input.
-    /// Call once at the start of each frame, before any system reads input.
-    pub fn update(&mut self) {
+    /// Advances input time by `dt` seconds and recalculates action and axis states
+    /// from the current raw input. Call once at the start of each frame, before any
+    /// system reads input.
+    pub fn update(&mut self, dt: f32) {
+        self.input_state.time += dt;
+        self.input_state.events = std::mem::take(&mut self.input_state.pending_events);
         self.update_action_states();
         self.update_axis_values();
     }
 
-    /// Advances the prev-frame snapshot and clears per-frame accumulations
-    /// (mouse delta, mouse wheel). Call after all systems have read input.
+    /// Clears per-frame accumulations (mouse delta, mouse wheel, text). Call after all
+    /// systems have read input.
     pub fn end_frame(&mut self) {
-        self.input_state.prev_keys_down = self.input_state.keys_down.clone();
-        self.input_state.prev_mouse_buttons_down = self.input_state.mouse_buttons_down.clone();
-
         self.input_state.mouse_delta = [0.0; 2];
+        self.input_state.raw_mouse_delta = [0.0; 2];
         self.input_state.mouse_wheel = 0.0;
+        self.input_state.text_events.clear();
     }
 
     // ---- Internal state machine ---------------------------------------------
 
     fn update_action_states(&mut self) {
-        for (action_name, bindings) in &self.config.action_binding {
-            let is_down = bindings.iter().any(|binding| self.is_binding_down(binding));
-            let just_pressed = bindings
-                .iter()
-                .any(|binding| self.is_binding_just_pressed(binding));
-            let just_released = bindings
-                .iter()
-                .any(|binding| self.is_binding_just_released(binding));
-
-            // Priority: JustPressed > JustReleased > Pressed > Idle.
-            // JustPressed wins so one-frame events are never masked by Pressed.
-            let new_state = if just_pressed {
-                InputState::JustPressed
-            } else if just_released {
-                InputState::JustReleased
-            } else if is_down {
-                InputState::Pressed
-            } else {
-                InputState::Idle
+        let now = self.input_state.time;
+        let chorded_keys = self.chorded_keys();
+        let downs: Vec<(InputAction, Activation, bool)> = self
+            .config
+            .action_binding
+            .iter()
+            .map(|(action, binding)| {
+                let down = binding
+                    .bindings
+                    .iter()
+                    .any(|b| self.is_binding_down(b, &chorded_keys));
+                (action.clone(), binding.activation, down)
+            })
+            .collect();
+
+        for (action, activation, down) in downs {
+            let tracker = self.input_state.action_trackers.entry(action.clone()).or_default();
+            let pressed = down && !tracker.down;
+            let released = !down && tracker.down;
+            let held_for = now - tracker.pressed_at;
+
+            let active = match activation {
+                Activation::Press => down,
+                Activation::Tap { max_duration } => released && held_for <= max_duration,
+                Activation::Hold { duration } => down && !pressed && held_for >= duration,
+                Activation::DoubleTap { max_gap } => {
+                    if pressed {
+                        tracker.double_tapped =
+                            tracker.last_tap.is_some_and(|t| now - t <= max_gap);
+                        // A double tap consumes both presses; a third starts a new pair.
+                        tracker.last_tap = (!tracker.double_tapped).then_some(now);
+                    }
+                    tracker.double_tapped &= down;
+                    tracker.double_tapped
+                }
+            };
+            if pressed {
+                tracker.pressed_at = now;
+            }
+
+            let new_state = match (tracker.active, active) {
+                (false, true) => InputState::JustPressed,
+                (true, false) => InputState::JustReleased,
+                (true, true) => InputState::Pressed,
+                (false, false) => InputState::Idle,
             };
+            tracker.down = down;
+            tracker.active = active;
 
-            self.input_state
-                .action_states
-                .insert(action_name.clone(), new_state);
+            self.input_state.action_states.insert(action, new_state);
         }
     }
 
     fn update_axis_values(&mut self) {
+        let mouse_delta = self.get_mouse_delta();
         let axis_values: Vec<(AxisAction, f32)> = self
             .config
             .axis_binding
             .iter()
-            .map(|(axis_name, binding)| {
-                let value = match binding {
-                    AxisBinding::Composite { positive, negative } => {
-                        let pos_state = self.input_state.action_states.get(positive);
-                        let neg_state = self.input_state.action_states.get(negative);
-
-                        let pos_value = match pos_state {
-                            Some(InputState::Pressed) | Some(InputState::JustPressed) => 1.0,
-                            _ => 0.0,
-                        };
-                        let neg_value = match neg_state {
-                            Some(InputState::Pressed) | Some(InputState::JustPressed) => 1.0,
-                            _ => 0.0,
-                        };
-
-                        pos_value - neg_value
-                    }
-                    AxisBinding::Analog {
-                        source,
-                        sensitivity,
-                    } => {
-                        let raw_value = match source {
-                            AnalogSource::MouseX => self.input_state.mouse_delta[0],
-                            AnalogSource::MouseY => self.input_state.mouse_delta[1],
-                            AnalogSource::MouseWheel => self.input_state.mouse_wheel,
-                        };
-                        raw_value * sensitivity
-                    }
-                };
+            .map(|(axis_name, bindings)| {
+                let value = bindings
+                    .iter()
+                    .map(|binding| match binding {
+                        AxisBinding::Composite {
+                            positive,
+                            negative,
+                            scale,
+                        } => {
+                            let pos_value = if self.is_action_pressed(positive.clone()) { 1.0 } else { 0.0 };
+                            let neg_value = if self.is_action_pressed(negative.clone()) { 1.0 } else { 0.0 };
+                            (pos_value - neg_value) * scale
+                        }
+                        AxisBinding::Analog {
+                            source,
+                            sensitivity,
+                            dead_zone,
+                        } => {
+                            let raw_value = match source {
+                                AnalogSource::MouseX => mouse_delta[0],
+                                AnalogSource::MouseY => mouse_delta[1],
+                                AnalogSource::MouseWheel => self.input_state.mouse_wheel,
+                            };
+                            if raw_value.abs() < *dead_zone {
+                                0.0
+                            } else {
+                                raw_value * sensitivity
+                            }
+                        }
+                    })
+                    .sum();
                 (axis_name.clone(), value)
             })
             .collect();
@@ -262,36 +566,160 @@ impl InputManager {
         }
     }
 
-    fn is_binding_down(&self, binding: &InputBinding) -> bool {
-        match binding {
-            InputBinding::Key(key) => self.input_state.keys_down.contains(key),
-            InputBinding::Mouse(button) => self.input_state.mouse_buttons_down.contains(button),
+    fn modifiers(&self) -> Modifiers {
+        let keys = &self.input_state.keys_down;
+        Modifiers {
+            ctrl: keys.contains(&KeyCode::Control),
+            shift: keys.contains(&KeyCode::Shift),
+            alt: keys.contains(&KeyCode::Alt),
         }
     }
 
-    fn is_binding_just_pressed(&self, binding: &InputBinding) -> bool {
+    /// Keys of every bound chord whose modifiers are held exactly. Plain key bindings of
+    /// these keys are suppressed so the chord does not also trigger them.
+    fn chorded_keys(&self) -> HashSet<KeyCode> {
+        let modifiers = self.modifiers();
+        self.config
+            .action_binding
+            .values()
+            .flat_map(|binding| binding.bindings.iter())
+            .filter_map(|binding| match binding {
+                InputBinding::Chord { modifiers: m, key }
+                    if *m == modifiers && *m != Modifiers::NONE =>
+                {
+                    Some(*key)
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn is_binding_down(&self, binding: &InputBinding, chorded_keys: &HashSet<KeyCode>) -> bool {
+        if self.text_input && !matches!(binding, InputBinding::Mouse(_)) {
+            return false;
+        }
+        // A press that was already released still counts for one frame, so taps shorter
+        // than a frame are not lost.
+        let key_down = |key: &KeyCode| {
+            self.input_state.keys_down.contains(key) || self.is_key_just_pressed(*key)
+        };
         match binding {
-            InputBinding::Key(key) => {
-                self.input_state.keys_down.contains(key)
-                    && !self.input_state.prev_keys_down.contains(key)
-            }
+            InputBinding::Key(key) => key_down(key) && !chorded_keys.contains(key),
             InputBinding::Mouse(button) => {
                 self.input_state.mouse_buttons_down.contains(button)
-                    && !self.input_state.prev_mouse_buttons_down.contains(button)
+                    || self.is_mouse_button_just_pressed(*button)
             }
+            InputBinding::Chord { modifiers, key } => key_down(key) && self.modifiers() == *modifiers,
         }
     }
+}
 
-    fn is_binding_just_released(&self, binding: &InputBinding) -> bool {
-        match binding {
-            InputBinding::Key(key) => {
-                !self.input_state.keys_down.contains(key)
-                    && self.input_state.prev_keys_down.contains(key)
-            }
-            InputBinding::Mouse(button) => {
-                !self.input_state.mouse_buttons_down.contains(button)
-                    && self.input_state.prev_mouse_buttons_down.contains(button)
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn focus_loss_and_ui_release_the_cursor() {
+        let mut input = InputManager::new();
+        input.grab_cursor();
+        assert_eq!(input.take_cursor_change(), Some(CursorMode::Locked));
+
+        input.on_focus_changed(false);
+        assert_eq!(input.take_cursor_change(), Some(CursorMode::Free));
+        input.on_mouse_moved(4.0, 2.0);
+        assert_eq!(input.get_mouse_delta(), [0.0, 0.0]);
+
+        input.on_focus_changed(true);
+        input.push_ui_context();
+        assert_eq!(input.take_cursor_change(), None);
+        assert!(!input.is_cursor_grabbed());
+
+        input.pop_ui_context();
+        assert_eq!(input.take_cursor_change(), Some(CursorMode::Locked));
+        input.on_mouse_moved(4.0, 2.0);
+        assert_eq!(input.get_mouse_delta(), [4.0, 2.0]);
+    }
+
+    #[test]
+    fn chord_suppresses_plain_key() {
+        let mut input = InputManager::new();
+        input.bind_action("move_backward", vec![InputBinding::Key(KeyCode::S)]);
+        input.bind_action("save", vec![InputBinding::chord(Modifiers::CTRL, KeyCode::S)]);
+
+        input.on_key_pressed(KeyCode::Control);
+        input.on_key_pressed(KeyCode::S);
+        input.update(0.016);
+        assert!(input.is_action_just_pressed("save"));
+        assert!(!input.is_action_pressed("move_backward"));
+
+        input.end_frame();
+        input.on_key_released(KeyCode::Control);
+        input.update(0.016);
+        assert!(input.is_action_just_released("save"));
+        assert!(input.is_action_just_pressed("move_backward"));
+    }
+
+    #[test]
+    fn double_tap_needs_two_presses_within_gap() {
+        let mut input = InputManager::new();
+        input.bind_action_with(
+            "dash",
+            vec![InputBinding::Key(KeyCode::W)],
+            Activation::DoubleTap { max_gap: 0.3 },
+        );
+
+        let frame = |input: &mut InputManager, down: bool| {
+            if down {
+                input.on_key_pressed(KeyCode::W);
+            } else {
+                input.on_key_released(KeyCode::W);
             }
-        }
+            input.update(0.1);
+            let pressed = input.is_action_just_pressed("dash");
+            input.end_frame();
+            pressed
+        };
+
+        assert!(!frame(&mut input, true));
+        assert!(!frame(&mut input, false));
+        assert!(frame(&mut input, true));
+    }
+
+    #[test]
+    fn text_input_suppresses_key_bindings() {
+        let mut input = InputManager::new();
+        input.bind_action("jump", vec![InputBinding::Key(KeyCode::Space)]);
+        input.begin_text_input();
+
+        input.on_key_pressed(KeyCode::Space);
+        input.on_text(" ");
+        input.on_text("\r");
+        input.update(0.016);
+        assert!(!input.is_action_pressed("jump"));
+        assert_eq!(input.text_events(), &[TextInputEvent::Text(" ".to_string())]);
+
+        input.end_frame();
+        input.end_text_input();
+        input.update(0.016);
+        assert!(input.is_action_just_pressed("jump"));
+    }
+
+    #[test]
+    fn tap_within_one_frame_is_not_lost() {
+        let mut input = InputManager::new();
+        input.bind_action("fire", vec![InputBinding::Mouse(MouseButton::Left)]);
+
+        input.on_mouse_button_pressed(MouseButton::Left);
+        input.on_mouse_button_released(MouseButton::Left);
+        input.update(0.016);
+        assert!(input.is_mouse_button_just_pressed(MouseButton::Left));
+        assert!(input.is_mouse_button_just_released(MouseButton::Left));
+        assert!(input.is_action_just_pressed("fire"));
+        assert_eq!(input.events().len(), 2);
+
+        input.end_frame();
+        input.update(0.016);
+        assert!(input.is_action_just_released("fire"));
+        assert!(input.events().is_empty());
     }
 }
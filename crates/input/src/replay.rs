@@ -0,0 +1,182 @@
+use crate::device::{KeyCode, MouseButton};
+use crate::manager::InputManager;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::Path;
+
+/// Bumped whenever the file layout changes. Older recordings are rejected.
+const RECORDING_VERSION: u32 = 1;
+
+/// One raw platform input, as handed to the `on_*` handlers of [`InputManager`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum RecordedInput {
+    KeyPressed(KeyCode),
+    KeyReleased(KeyCode),
+    MouseButtonPressed(MouseButton),
+    MouseButtonReleased(MouseButton),
+    MouseMoved([f32; 2]),
+    MousePosition([f32; 2]),
+    CursorLeft,
+    MouseWheel(f32),
+    Text(String),
+    ImePreedit {
+        text: String,
+        cursor: Option<(usize, usize)>,
+    },
+    FocusChanged(bool),
+}
+
+/// The raw input that arrived before one tick's `InputManager::update`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RecordedTick {
+    /// Seconds since recording started, at the start of this tick.
+    pub time: f64,
+    pub inputs: Vec<RecordedInput>,
+}
+
+#[derive(Debug)]
+pub enum ReplayError {
+    Io(std::io::Error),
+    Encode(String),
+    Decode(String),
+    VersionMismatch { found: u32, expected: u32 },
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayError::Io(err) => write!(f, "{}", err),
+            ReplayError::Encode(err) => write!(f, "failed to encode input recording: {}", err),
+            ReplayError::Decode(err) => write!(f, "failed to decode input recording: {}", err),
+            ReplayError::VersionMismatch { found, expected } => write!(
+                f,
+                "input recording version {} is not supported (expected {})",
+                found, expected
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+impl From<std::io::Error> for ReplayError {
+    fn from(err: std::io::Error) -> Self {
+        ReplayError::Io(err)
+    }
+}
+
+/// Raw input captured tick by tick at a fixed step. Replaying it into an `InputManager`
+/// with the same bindings, stepped by the same `fixed_delta`, reproduces the same
+/// action and axis states on every tick.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InputRecording {
+    version: u32,
+    /// Step length the recording was made at, in seconds.
+    pub fixed_delta: f32,
+    pub ticks: Vec<RecordedTick>,
+}
+
+impl InputRecording {
+    pub fn new(fixed_delta: f32) -> Self {
+        Self {
+            version: RECORDING_VERSION,
+            fixed_delta,
+            ticks: Vec::new(),
+        }
+    }
+
+    /// Appends the next tick, stamped with its time since the recording started.
+    pub fn push_tick(&mut self, inputs: Vec<RecordedInput>) {
+        let time = self.ticks.len() as f64 * self.fixed_delta as f64;
+        self.ticks.push(RecordedTick { time, inputs });
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), ReplayError> {
+        let bytes = bincode::serialize(self).map_err(|err| ReplayError::Encode(err.to_string()))?;
+        std::fs::write(path, bytes)?;
+        Ok(())
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ReplayError> {
+        let bytes = std::fs::read(path)?;
+        let recording: Self =
+            bincode::deserialize(&bytes).map_err(|err| ReplayError::Decode(err.to_string()))?;
+        if recording.version != RECORDING_VERSION {
+            return Err(ReplayError::VersionMismatch {
+                found: recording.version,
+                expected: RECORDING_VERSION,
+            });
+        }
+        Ok(recording)
+    }
+}
+
+impl InputManager {
+    /// Feeds a recorded input through the matching raw event handler.
+    pub fn apply_recorded(&mut self, input: &RecordedInput) {
+        match input {
+            RecordedInput::KeyPressed(key) => self.on_key_pressed(*key),
+            RecordedInput::KeyReleased(key) => self.on_key_released(*key),
+            RecordedInput::MouseButtonPressed(button) => self.on_mouse_button_pressed(*button),
+            RecordedInput::MouseButtonReleased(button) => self.on_mouse_button_released(*button),
+            RecordedInput::MouseMoved([x, y]) => self.on_mouse_moved(*x, *y),
+            RecordedInput::MousePosition([x, y]) => self.on_mouse_position(*x, *y),
+            RecordedInput::CursorLeft => self.on_cursor_left(),
+            RecordedInput::MouseWheel(delta) => self.on_mouse_wheel(*delta),
+            RecordedInput::Text(text) => self.on_text(text),
+            RecordedInput::ImePreedit { text, cursor } => self.on_ime_preedit(text, *cursor),
+            RecordedInput::FocusChanged(focused) => self.on_focus_changed(*focused),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input_action::InputBinding;
+
+    fn run(recording: &InputRecording) -> Vec<(bool, f32)> {
+        let mut input = InputManager::new();
+        input.bind_action("jump", vec![InputBinding::Key(KeyCode::Space)]);
+        input.bind_axis(
+            "look",
+            crate::AxisBinding::Analog {
+                source: crate::AnalogSource::MouseX,
+                sensitivity: 0.5,
+                dead_zone: 0.0,
+            },
+        );
+        input.set_mouse_motion(crate::MouseMotion::Raw);
+
+        let mut states = Vec::new();
+        for tick in &recording.ticks {
+            for recorded in &tick.inputs {
+                input.apply_recorded(recorded);
+            }
+            input.update(recording.fixed_delta);
+            states.push((input.is_action_just_pressed("jump"), input.get_axis("look")));
+            input.end_frame();
+        }
+        states
+    }
+
+    #[test]
+    fn replay_reproduces_action_and_axis_states() {
+        let mut recording = InputRecording::new(1.0 / 60.0);
+        recording.push_tick(vec![RecordedInput::MouseMoved([4.0, 0.0])]);
+        recording.push_tick(vec![
+            RecordedInput::KeyPressed(KeyCode::Space),
+            RecordedInput::KeyReleased(KeyCode::Space),
+        ]);
+        recording.push_tick(Vec::new());
+
+        let path = std::env::temp_dir().join(format!("input_replay_{}.bin", std::process::id()));
+        recording.save(&path).unwrap();
+        let loaded = InputRecording::load(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+
+        assert_eq!(loaded, recording);
+        assert_eq!(loaded.ticks[2].time, 2.0 * loaded.fixed_delta as f64);
+        assert_eq!(run(&loaded), [(false, 2.0), (true, 0.0), (false, 0.0)]);
+    }
+}
@@ -0,0 +1,13 @@
+/// Text typed while text input is active. Read with `InputManager::text_events`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TextInputEvent {
+    /// Committed text: a typed character or the final result of an IME composition.
+    /// Control characters are filtered out; read Enter, Backspace and arrows as keys.
+    Text(String),
+    /// In-progress IME composition to display at the caret. An empty string clears it.
+    /// `cursor` is the byte range of the composition cursor within `text`, if shown.
+    Preedit {
+        text: String,
+        cursor: Option<(usize, usize)>,
+    },
+}
@@ -2,5 +2,7 @@ mod macros;
 
 mod material;
 pub mod material_manager;
+mod permutation;
 
 pub use material::*;
+pub use permutation::ShaderFeatures;
@@ -1,9 +1,10 @@
+use crate::ShaderFeatures;
 use common::{Guid, ImageHandle};
 use nalgebra_glm::{vec4, Vec4};
 use serde::Serialize;
 
 /// Identifies a shader for one stage of a material pass.
-#[derive(Clone, Eq, PartialEq, Hash)]
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
 pub enum ShaderRef {
     /// Engine built-in, loaded by name from the engine shader directory. No GUID, no asset registry.
     BuiltIn(String),
@@ -23,6 +24,9 @@ pub struct Material {
     pub active_defines: Vec<String>,
     pub bindings: Vec<MaterialParameterBinding>,
     pub push_constants: Vec<u8>,
+    /// Color the material reflects diffusely, for light baking on the CPU. Custom
+    /// materials report white.
+    pub base_color: MaterialColorParameter,
 }
 
 /// A PBR material builder. Holds typed, named parameters and assembles them into a flat `Material`.
@@ -49,25 +53,26 @@ impl PbrMaterial {
             active_defines,
             bindings,
             push_constants,
+            base_color: self.base_color,
         }
     }
 
     fn compute_defines(&self) -> Vec<String> {
-        let mut d = Vec::new();
+        let mut features = ShaderFeatures::empty();
         if self.base_color.as_handle().is_some() {
-            d.push("HAS_COLOR_TEXTURE".into());
+            features |= ShaderFeatures::COLOR_TEXTURE;
         }
         if self.normal.as_handle().is_some() {
-            d.push("HAS_NORMAL_TEXTURE".into());
+            features |= ShaderFeatures::NORMAL_MAP;
         }
         if self.ambient_occlusion.as_handle().is_some()
             || self.metallic.as_handle().is_some()
             || self.roughness.as_handle().is_some()
             || self.specular.as_handle().is_some()
         {
-            d.push("HAS_ORM_TEXTURE".into());
+            features |= ShaderFeatures::ORM_TEXTURE;
         }
-        d
+        features.defines()
     }
 
     fn compute_bindings(&self) -> Vec<MaterialParameterBinding> {
@@ -135,12 +140,13 @@ impl PbrMaterial {
 
 #[repr(C)]
 #[derive(Serialize)]
+/// Mirrors `MaterialConstants` in `shader.frag`; keep the field order in sync.
 pub struct PbrPushConstants {
     base_color: Vec4,
     normal: Vec4,
-    ambient_occlusion: f32,
-    metallic: f32,
     roughness: f32,
+    metallic: f32,
+    ambient_occlusion: f32,
     specular: f32,
 }
 
@@ -165,6 +171,7 @@ impl MaterialParameter {
     }
 }
 
+#[derive(Clone, Copy)]
 pub enum MaterialColorParameter {
     Handle(ImageHandle),
     Constant(Vec4),
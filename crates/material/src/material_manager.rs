@@ -1,4 +1,4 @@
-use crate::{Material, MaterialParameterBinding, ShaderRef};
+use crate::{Material, MaterialColorParameter, MaterialParameterBinding, ShaderRef};
 use common::{Guid, Handle};
 use std::collections::HashMap;
 
@@ -49,6 +49,32 @@ impl MaterialManager {
         handle
     }
 
+    /// The GUID a material was loaded from. `None` for materials built at runtime.
+    pub fn guid_of(&self, handle: MaterialHandle) -> Option<Guid> {
+        self.guid_index
+            .iter()
+            .find(|(_, &loaded)| loaded == handle)
+            .map(|(&guid, _)| guid)
+    }
+
+    /// Handles of every material, including ones built at runtime.
+    pub fn handles(&self) -> impl Iterator<Item = MaterialHandle> + '_ {
+        self.materials.keys().copied()
+    }
+
+    /// Handles of materials loaded from a GUID.
+    pub fn loaded_handles(&self) -> impl Iterator<Item = MaterialHandle> + '_ {
+        self.guid_index.values().copied()
+    }
+
+    /// Unloads a material. Loading its GUID again yields a new handle. Shader variants
+    /// stay registered, since other materials may share them.
+    pub fn remove(&mut self, handle: MaterialHandle) -> Option<Material> {
+        let material = self.materials.remove(&handle)?;
+        self.guid_index.retain(|_, &mut loaded| loaded != handle);
+        Some(material)
+    }
+
     pub fn get_variants(&self) -> Vec<&MaterialVariant> {
         self.shader_variants.values().collect()
     }
@@ -69,6 +95,13 @@ impl MaterialManager {
             .push_constants
     }
 
+    pub fn get_base_color(&self, handle: MaterialHandle) -> MaterialColorParameter {
+        self.materials
+            .get(&handle)
+            .unwrap_or_else(|| panic!("MaterialManager: invalid handle {:?}", handle))
+            .base_color
+    }
+
     pub fn get_variant(&self, handle: MaterialHandle) -> &MaterialVariant {
         let material = self
             .materials
@@ -0,0 +1,90 @@
+bitflags::bitflags! {
+    /// Material and mesh features a geometry pass pipeline is compiled for. Together with
+    /// the material's shaders and the vertex encoding it identifies one permutation in the
+    /// renderer's pipeline cache. Each feature turns on one shader define; features a
+    /// shader has no permutation for are masked out of its key, so they don't multiply
+    /// pipelines.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+    pub struct ShaderFeatures: u32 {
+        /// Deformed by the joint palette in the frame set.
+        const SKINNED = 1 << 0;
+        /// Discards fragments below the material's alpha cutoff.
+        const ALPHA_TEST = 1 << 1;
+        /// Reads the vertex extras stream: vertex color and the second UV set.
+        const VERTEX_COLOR = 1 << 2;
+        /// Samples a tangent-space normal map.
+        const NORMAL_MAP = 1 << 3;
+        /// Drawn in batches merged by GPU culling rather than one draw per mesh.
+        const INSTANCED = 1 << 4;
+        /// Sways in the wind by its vertex color weights.
+        const WIND = 1 << 5;
+        /// Samples a base color texture.
+        const COLOR_TEXTURE = 1 << 6;
+        /// Samples a packed occlusion, roughness, metallic and specular texture.
+        const ORM_TEXTURE = 1 << 7;
+    }
+}
+
+impl ShaderFeatures {
+    /// Features the vertex shader is compiled for.
+    pub const VERTEX_STAGE: Self = Self::SKINNED
+        .union(Self::VERTEX_COLOR)
+        .union(Self::INSTANCED)
+        .union(Self::WIND);
+    /// Features the fragment shader is compiled for.
+    pub const FRAGMENT_STAGE: Self = Self::ALPHA_TEST
+        .union(Self::NORMAL_MAP)
+        .union(Self::COLOR_TEXTURE)
+        .union(Self::ORM_TEXTURE);
+
+    /// Shader defines of the features, sorted like the names of compiled permutations.
+    pub fn defines(self) -> Vec<String> {
+        let mut defines: Vec<_> = self.iter().map(|feature| define(feature).to_string()).collect();
+        defines.sort_unstable();
+        defines
+    }
+
+    /// Features whose define is in `defines`. Other defines are ignored.
+    pub fn from_defines(defines: &[String]) -> Self {
+        Self::all()
+            .iter()
+            .filter(|&feature| defines.iter().any(|d| d == define(feature)))
+            .collect()
+    }
+}
+
+fn define(feature: ShaderFeatures) -> &'static str {
+    match feature {
+        ShaderFeatures::SKINNED => "HAS_SKINNING",
+        ShaderFeatures::ALPHA_TEST => "HAS_ALPHA_TEST",
+        ShaderFeatures::VERTEX_COLOR => "HAS_VERTEX_EXTRAS",
+        ShaderFeatures::NORMAL_MAP => "HAS_NORMAL_TEXTURE",
+        ShaderFeatures::INSTANCED => "HAS_INSTANCING",
+        ShaderFeatures::WIND => "HAS_WIND",
+        ShaderFeatures::COLOR_TEXTURE => "HAS_COLOR_TEXTURE",
+        ShaderFeatures::ORM_TEXTURE => "HAS_ORM_TEXTURE",
+        _ => unreachable!("not a single feature: {feature:?}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn defines_round_trip_and_ignore_unknown_ones() {
+        let features = ShaderFeatures::SKINNED | ShaderFeatures::VERTEX_COLOR;
+        assert_eq!(features.defines(), ["HAS_SKINNING", "HAS_VERTEX_EXTRAS"]);
+
+        let mut defines = features.defines();
+        defines.push("MY_CUSTOM_DEFINE".to_string());
+        assert_eq!(ShaderFeatures::from_defines(&defines), features);
+    }
+
+    #[test]
+    fn every_feature_belongs_to_one_stage() {
+        let stages = ShaderFeatures::VERTEX_STAGE | ShaderFeatures::FRAGMENT_STAGE;
+        assert_eq!(stages, ShaderFeatures::all());
+        assert!(!ShaderFeatures::VERTEX_STAGE.intersects(ShaderFeatures::FRAGMENT_STAGE));
+    }
+}
@@ -0,0 +1,148 @@
+use crate::NetError;
+use nalgebra_glm::{Vec3, Vec4};
+
+/// Little-endian append-only encoder used for packets and component payloads.
+#[derive(Default)]
+pub struct ByteWriter {
+    buf: Vec<u8>,
+}
+
+impl ByteWriter {
+    pub fn new() -> Self {
+        Self { buf: Vec::new() }
+    }
+
+    pub fn into_inner(self) -> Vec<u8> {
+        self.buf
+    }
+
+    pub fn as_slice(&self) -> &[u8] {
+        &self.buf
+    }
+
+    pub fn len(&self) -> usize {
+        self.buf.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buf.is_empty()
+    }
+
+    pub fn write_u8(&mut self, value: u8) {
+        self.buf.push(value);
+    }
+
+    pub fn write_u16(&mut self, value: u16) {
+        self.buf.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub fn write_u32(&mut self, value: u32) {
+        self.buf.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub fn write_f32(&mut self, value: f32) {
+        self.buf.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub fn write_vec3(&mut self, value: &Vec3) {
+        self.write_f32(value.x);
+        self.write_f32(value.y);
+        self.write_f32(value.z);
+    }
+
+    pub fn write_vec4(&mut self, value: &Vec4) {
+        self.write_f32(value.x);
+        self.write_f32(value.y);
+        self.write_f32(value.z);
+        self.write_f32(value.w);
+    }
+
+    /// Writes a u16 length prefix followed by the bytes.
+    pub fn write_bytes(&mut self, bytes: &[u8]) {
+        debug_assert!(
+            bytes.len() <= u16::MAX as usize,
+            "payload too large for u16 prefix"
+        );
+        self.write_u16(bytes.len() as u16);
+        self.buf.extend_from_slice(bytes);
+    }
+
+    /// Appends bytes without a length prefix.
+    pub fn write_raw(&mut self, bytes: &[u8]) {
+        self.buf.extend_from_slice(bytes);
+    }
+}
+
+/// Cursor over a received buffer. Every read fails with `NetError::Truncated`
+/// instead of panicking on short input.
+pub struct ByteReader<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> ByteReader<'a> {
+    pub fn new(buf: &'a [u8]) -> Self {
+        Self { buf, pos: 0 }
+    }
+
+    pub fn remaining(&self) -> usize {
+        self.buf.len() - self.pos
+    }
+
+    fn take(&mut self, count: usize) -> Result<&'a [u8], NetError> {
+        if self.remaining() < count {
+            return Err(NetError::Truncated);
+        }
+        let slice = &self.buf[self.pos..self.pos + count];
+        self.pos += count;
+        Ok(slice)
+    }
+
+    pub fn read_u8(&mut self) -> Result<u8, NetError> {
+        Ok(self.take(1)?[0])
+    }
+
+    pub fn read_u16(&mut self) -> Result<u16, NetError> {
+        let bytes = self.take(2)?;
+        Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    pub fn read_u32(&mut self) -> Result<u32, NetError> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    pub fn read_f32(&mut self) -> Result<f32, NetError> {
+        Ok(f32::from_bits(self.read_u32()?))
+    }
+
+    pub fn read_vec3(&mut self) -> Result<Vec3, NetError> {
+        Ok(Vec3::new(
+            self.read_f32()?,
+            self.read_f32()?,
+            self.read_f32()?,
+        ))
+    }
+
+    pub fn read_vec4(&mut self) -> Result<Vec4, NetError> {
+        Ok(Vec4::new(
+            self.read_f32()?,
+            self.read_f32()?,
+            self.read_f32()?,
+            self.read_f32()?,
+        ))
+    }
+
+    /// Reads a u16 length prefix followed by that many bytes.
+    pub fn read_bytes(&mut self) -> Result<&'a [u8], NetError> {
+        let len = self.read_u16()? as usize;
+        self.take(len)
+    }
+
+    /// Returns everything not yet consumed.
+    pub fn read_rest(&mut self) -> &'a [u8] {
+        let rest = &self.buf[self.pos..];
+        self.pos = self.buf.len();
+        rest
+    }
+}
@@ -0,0 +1,31 @@
+use std::fmt;
+
+#[derive(Debug)]
+pub enum NetError {
+    Io(std::io::Error),
+    /// A packet or payload ended before all expected fields were read.
+    Truncated,
+    /// A packet carried a different protocol id or an unknown packet kind.
+    InvalidPacket,
+    /// A delta snapshot referenced a baseline tick the receiver does not have.
+    MissingBaseline(u32),
+}
+
+impl fmt::Display for NetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NetError::Io(e) => write!(f, "io: {}", e),
+            NetError::Truncated => write!(f, "payload truncated"),
+            NetError::InvalidPacket => write!(f, "invalid packet"),
+            NetError::MissingBaseline(tick) => write!(f, "missing baseline snapshot {}", tick),
+        }
+    }
+}
+
+impl std::error::Error for NetError {}
+
+impl From<std::io::Error> for NetError {
+    fn from(e: std::io::Error) -> Self {
+        NetError::Io(e)
+    }
+}
@@ -0,0 +1,97 @@
+use crate::Snapshot;
+use std::collections::VecDeque;
+
+/// Client-side buffer that renders remote state slightly in the past so there are
+/// always two snapshots to blend between.
+///
+/// The playback clock advances with frame time and is nudged toward
+/// `latest_tick - delay_ticks` so it tracks the server without visible jumps.
+pub struct SnapshotBuffer {
+    snapshots: VecDeque<Snapshot>,
+    capacity: usize,
+    tick_rate: f32,
+    delay_ticks: f32,
+    render_tick: Option<f32>,
+}
+
+impl SnapshotBuffer {
+    /// `tick_rate` is the server snapshot rate in Hz. A delay of two to three ticks
+    /// hides a single lost snapshot.
+    pub fn new(tick_rate: f32, delay_ticks: f32) -> Self {
+        Self {
+            snapshots: VecDeque::new(),
+            capacity: 32,
+            tick_rate,
+            delay_ticks,
+            render_tick: None,
+        }
+    }
+
+    /// Inserts a received snapshot, keeping the buffer sorted. Duplicates and snapshots
+    /// older than the playback position are dropped.
+    pub fn push(&mut self, snapshot: Snapshot) {
+        if let Some(render_tick) = self.render_tick
+            && (snapshot.tick as f32) < render_tick.floor()
+        {
+            return;
+        }
+        let index = self.snapshots.partition_point(|s| s.tick < snapshot.tick);
+        if self
+            .snapshots
+            .get(index)
+            .is_some_and(|s| s.tick == snapshot.tick)
+        {
+            return;
+        }
+        self.snapshots.insert(index, snapshot);
+        if self.snapshots.len() > self.capacity {
+            self.snapshots.pop_front();
+        }
+    }
+
+    pub fn latest(&self) -> Option<&Snapshot> {
+        self.snapshots.back()
+    }
+
+    /// Current playback position in ticks, once any snapshot has arrived.
+    pub fn render_tick(&self) -> Option<f32> {
+        self.render_tick
+    }
+
+    /// Advances playback by `dt` seconds and drops snapshots no longer needed.
+    pub fn advance(&mut self, dt: f32) {
+        let Some(latest) = self.snapshots.back() else {
+            return;
+        };
+        let target = latest.tick as f32 - self.delay_ticks;
+        let render_tick = match self.render_tick {
+            Some(tick) => {
+                let tick = tick + dt * self.tick_rate;
+                if (target - tick).abs() > self.delay_ticks * 2.0 {
+                    target
+                } else {
+                    tick + (target - tick) * 0.1
+                }
+            }
+            None => target,
+        };
+        self.render_tick = Some(render_tick);
+
+        while self.snapshots.len() > 2 && (self.snapshots[1].tick as f32) <= render_tick {
+            self.snapshots.pop_front();
+        }
+    }
+
+    /// Returns the snapshots surrounding the playback position and the blend factor
+    /// between them. Before the second snapshot arrives both sides are the same.
+    pub fn sample(&self) -> Option<(&Snapshot, &Snapshot, f32)> {
+        let render_tick = self.render_tick?;
+        let from = self.snapshots.front()?;
+        let Some(to) = self.snapshots.get(1) else {
+            return Some((from, from, 0.0));
+        };
+        let span = (to.tick - from.tick) as f32;
+        let t = ((render_tick - from.tick as f32) / span).clamp(0.0, 1.0);
+        Some((from, to, t))
+    }
+}
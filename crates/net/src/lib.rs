@@ -0,0 +1,20 @@
+//! Networking: a UDP transport with per-message reliability, component replication
+//! through delta-compressed world snapshots, and client-side snapshot interpolation.
+//!
+//! A typical server loop is `poll` -> `ReplicationRegistry::capture` -> encode the
+//! snapshot against each client's acknowledged baseline -> `send` -> `flush`. Clients
+//! decode into a `SnapshotBuffer` and apply interpolated state every frame.
+
+mod bytes;
+mod error;
+pub mod interpolation;
+pub mod replication;
+pub mod snapshot;
+pub mod transport;
+
+pub use bytes::{ByteReader, ByteWriter};
+pub use error::NetError;
+pub use interpolation::SnapshotBuffer;
+pub use replication::{Interpolate, NetworkId, Replicate, ReplicationRegistry};
+pub use snapshot::{Snapshot, SnapshotHistory};
+pub use transport::{Delivery, Endpoint, NetEvent, TransportConfig};
@@ -0,0 +1,225 @@
+use crate::{ByteReader, ByteWriter, NetError, Snapshot};
+use ecs::component::Component;
+use ecs::world::World;
+use std::any::TypeId;
+use std::collections::{BTreeSet, HashMap};
+
+/// Identity of a replicated entity, shared by server and clients. Entities without a
+/// `NetworkId` are never replicated; the server assigns ids when spawning.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct NetworkId(pub u32);
+
+/// A component that is sent over the network. Encodings should be compact and
+/// deterministic: snapshot deltas compare encoded bytes to detect changes.
+pub trait Replicate: Component + Sized {
+    fn encode(&self, writer: &mut ByteWriter);
+    fn decode(reader: &mut ByteReader) -> Result<Self, NetError>;
+}
+
+/// Blends between two replicated values for client-side interpolation.
+pub trait Interpolate {
+    fn interpolate(&self, other: &Self, t: f32) -> Self;
+}
+
+type CaptureFn = fn(&mut World, u16, &mut Snapshot);
+type ApplyFn = fn(&mut World, u16, &Snapshot, &mut BTreeSet<NetworkId>) -> Result<(), NetError>;
+type InterpolateFn = fn(&mut World, u16, &Snapshot, &Snapshot, f32) -> Result<(), NetError>;
+
+struct ReplicatedType {
+    capture: CaptureFn,
+    apply: ApplyFn,
+    interpolate: Option<InterpolateFn>,
+}
+
+/// The set of component types that are replicated. Registration order assigns each type
+/// a component kind, so server and client must register the same types in the same order.
+#[derive(Default)]
+pub struct ReplicationRegistry {
+    types: Vec<ReplicatedType>,
+    kinds: HashMap<TypeId, u16>,
+}
+
+impl ReplicationRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a component that snaps to the newest received value.
+    pub fn register<T: Replicate>(&mut self) -> u16 {
+        self.insert::<T>(None)
+    }
+
+    /// Registers a component that is blended between snapshots on clients.
+    pub fn register_interpolated<T: Replicate + Interpolate>(&mut self) -> u16 {
+        self.insert::<T>(Some(interpolate_component::<T>))
+    }
+
+    fn insert<T: Replicate>(&mut self, interpolate: Option<InterpolateFn>) -> u16 {
+        if let Some(kind) = self.kinds.get(&TypeId::of::<T>()) {
+            return *kind;
+        }
+        let kind = self.types.len() as u16;
+        self.types.push(ReplicatedType {
+            capture: capture_component::<T>,
+            apply: apply_component::<T>,
+            interpolate,
+        });
+        self.kinds.insert(TypeId::of::<T>(), kind);
+        kind
+    }
+
+    pub fn kind_of<T: Replicate>(&self) -> Option<u16> {
+        self.kinds.get(&TypeId::of::<T>()).copied()
+    }
+
+    /// Encodes every registered component on entities with a `NetworkId`.
+    pub fn capture(&self, world: &mut World, tick: u32) -> Snapshot {
+        let mut snapshot = Snapshot::new(tick);
+        for (kind, ty) in self.types.iter().enumerate() {
+            (ty.capture)(world, kind as u16, &mut snapshot);
+        }
+        snapshot
+    }
+
+    /// Writes `snapshot` into matching entities. Returns the ids present in the snapshot
+    /// that have no entity in `world` yet, so the game can spawn them with whatever
+    /// local-only components they need and apply again.
+    pub fn apply(
+        &self,
+        world: &mut World,
+        snapshot: &Snapshot,
+    ) -> Result<Vec<NetworkId>, NetError> {
+        let mut seen = BTreeSet::new();
+        for (kind, ty) in self.types.iter().enumerate() {
+            (ty.apply)(world, kind as u16, snapshot, &mut seen)?;
+        }
+        Ok(snapshot
+            .entities
+            .keys()
+            .filter(|id| !seen.contains(id))
+            .copied()
+            .collect())
+    }
+
+    /// Applies `to`, then overwrites interpolated components with a blend of `from` and
+    /// `to` at `t`. Entities missing from `from` snap to `to`.
+    pub fn apply_interpolated(
+        &self,
+        world: &mut World,
+        from: &Snapshot,
+        to: &Snapshot,
+        t: f32,
+    ) -> Result<Vec<NetworkId>, NetError> {
+        let missing = self.apply(world, to)?;
+        for (kind, ty) in self.types.iter().enumerate() {
+            if let Some(interpolate) = ty.interpolate {
+                interpolate(world, kind as u16, from, to, t)?;
+            }
+        }
+        Ok(missing)
+    }
+}
+
+fn capture_component<T: Replicate>(world: &mut World, kind: u16, snapshot: &mut Snapshot) {
+    for (id, component) in world.query::<(&mut NetworkId, &mut T)>().iter() {
+        let mut writer = ByteWriter::new();
+        component.encode(&mut writer);
+        snapshot
+            .entities
+            .entry(*id)
+            .or_default()
+            .insert(kind, writer.into_inner());
+    }
+}
+
+fn apply_component<T: Replicate>(
+    world: &mut World,
+    kind: u16,
+    snapshot: &Snapshot,
+    seen: &mut BTreeSet<NetworkId>,
+) -> Result<(), NetError> {
+    for (id, component) in world.query::<(&mut NetworkId, &mut T)>().iter() {
+        let Some(state) = snapshot.entities.get(id) else {
+            continue;
+        };
+        seen.insert(*id);
+        if let Some(bytes) = state.get(&kind) {
+            *component = T::decode(&mut ByteReader::new(bytes))?;
+        }
+    }
+    Ok(())
+}
+
+fn interpolate_component<T: Replicate + Interpolate>(
+    world: &mut World,
+    kind: u16,
+    from: &Snapshot,
+    to: &Snapshot,
+    t: f32,
+) -> Result<(), NetError> {
+    for (id, component) in world.query::<(&mut NetworkId, &mut T)>().iter() {
+        let (Some(a), Some(b)) = (
+            from.entities.get(id).and_then(|state| state.get(&kind)),
+            to.entities.get(id).and_then(|state| state.get(&kind)),
+        ) else {
+            continue;
+        };
+        let a = T::decode(&mut ByteReader::new(a))?;
+        let b = T::decode(&mut ByteReader::new(b))?;
+        *component = a.interpolate(&b, t);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Component, Debug, Clone, Copy, PartialEq)]
+    struct Position(f32);
+
+    impl Replicate for Position {
+        fn encode(&self, writer: &mut ByteWriter) {
+            writer.write_f32(self.0);
+        }
+
+        fn decode(reader: &mut ByteReader) -> Result<Self, NetError> {
+            Ok(Position(reader.read_f32()?))
+        }
+    }
+
+    impl Interpolate for Position {
+        fn interpolate(&self, other: &Self, t: f32) -> Self {
+            Position(self.0 + (other.0 - self.0) * t)
+        }
+    }
+
+    #[test]
+    fn capture_and_apply_interpolated() {
+        let mut registry = ReplicationRegistry::new();
+        registry.register_interpolated::<Position>();
+
+        let mut server = World::new();
+        server.create_entity((NetworkId(7), Position(0.0)));
+        let from = registry.capture(&mut server, 1);
+        for (_, position) in server.query::<(&mut NetworkId, &mut Position)>().iter() {
+            position.0 = 10.0;
+        }
+        let to = registry.capture(&mut server, 2);
+
+        let mut client = World::new();
+        assert_eq!(
+            registry.apply(&mut client, &to).unwrap(),
+            vec![NetworkId(7)]
+        );
+
+        client.create_entity((NetworkId(7), Position(0.0)));
+        let missing = registry
+            .apply_interpolated(&mut client, &from, &to, 0.25)
+            .unwrap();
+        assert!(missing.is_empty());
+        let mut query = client.query::<(&mut NetworkId, &mut Position)>();
+        let (_, position) = query.iter().next().unwrap();
+        assert_eq!(*position, Position(2.5));
+    }
+}
@@ -0,0 +1,223 @@
+use crate::replication::NetworkId;
+use crate::{ByteReader, ByteWriter, NetError};
+use std::collections::{BTreeMap, VecDeque};
+
+/// Marks a full snapshot in the encoded header.
+const NO_BASELINE: u32 = u32::MAX;
+
+/// Encoded component payloads of one replicated entity, keyed by component kind.
+pub type EntityState = BTreeMap<u16, Vec<u8>>;
+
+/// Replicated state of the world at one server tick.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Snapshot {
+    pub tick: u32,
+    pub entities: BTreeMap<NetworkId, EntityState>,
+}
+
+impl Snapshot {
+    pub fn new(tick: u32) -> Self {
+        Self {
+            tick,
+            entities: BTreeMap::new(),
+        }
+    }
+
+    /// Encodes this snapshot relative to `baseline`, the newest snapshot the receiver has
+    /// acknowledged. Only entities and components whose bytes changed are written, plus
+    /// the ids and kinds that were removed. Pass None to send a full snapshot.
+    pub fn encode_delta(&self, baseline: Option<&Snapshot>) -> Vec<u8> {
+        let empty = BTreeMap::new();
+        let base_entities = baseline.map_or(&empty, |b| &b.entities);
+
+        let mut writer = ByteWriter::new();
+        writer.write_u32(self.tick);
+        writer.write_u32(baseline.map_or(NO_BASELINE, |b| b.tick));
+
+        let removed = base_entities
+            .keys()
+            .filter(|id| !self.entities.contains_key(id))
+            .collect::<Vec<_>>();
+        writer.write_u16(removed.len() as u16);
+        for id in removed {
+            writer.write_u32(id.0);
+        }
+
+        let mut changed = ByteWriter::new();
+        let mut changed_count = 0u16;
+        for (id, state) in &self.entities {
+            let base = base_entities.get(id);
+            let updated = state
+                .iter()
+                .filter(|(kind, bytes)| base.and_then(|b| b.get(kind)) != Some(*bytes))
+                .collect::<Vec<_>>();
+            let removed_kinds = base
+                .map(|b| {
+                    b.keys()
+                        .filter(|kind| !state.contains_key(kind))
+                        .collect::<Vec<_>>()
+                })
+                .unwrap_or_default();
+            if updated.is_empty() && removed_kinds.is_empty() && base.is_some() {
+                continue;
+            }
+
+            changed.write_u32(id.0);
+            changed.write_u8(updated.len() as u8);
+            for (kind, bytes) in updated {
+                changed.write_u16(*kind);
+                changed.write_bytes(bytes);
+            }
+            changed.write_u8(removed_kinds.len() as u8);
+            for kind in removed_kinds {
+                changed.write_u16(*kind);
+            }
+            changed_count += 1;
+        }
+
+        writer.write_u16(changed_count);
+        writer.write_raw(changed.as_slice());
+        writer.into_inner()
+    }
+
+    /// Reads the baseline tick a delta was encoded against, or None for a full snapshot.
+    pub fn peek_baseline(bytes: &[u8]) -> Result<Option<u32>, NetError> {
+        let mut reader = ByteReader::new(bytes);
+        reader.read_u32()?;
+        let baseline = reader.read_u32()?;
+        Ok((baseline != NO_BASELINE).then_some(baseline))
+    }
+
+    /// Rebuilds a snapshot from `encode_delta` output. `baseline` must be the snapshot
+    /// named by `peek_baseline`.
+    pub fn decode_delta(bytes: &[u8], baseline: Option<&Snapshot>) -> Result<Snapshot, NetError> {
+        let mut reader = ByteReader::new(bytes);
+        let tick = reader.read_u32()?;
+        let baseline_tick = reader.read_u32()?;
+
+        let mut entities = if baseline_tick == NO_BASELINE {
+            BTreeMap::new()
+        } else {
+            match baseline {
+                Some(base) if base.tick == baseline_tick => base.entities.clone(),
+                _ => return Err(NetError::MissingBaseline(baseline_tick)),
+            }
+        };
+
+        for _ in 0..reader.read_u16()? {
+            entities.remove(&NetworkId(reader.read_u32()?));
+        }
+
+        for _ in 0..reader.read_u16()? {
+            let state = entities.entry(NetworkId(reader.read_u32()?)).or_default();
+            for _ in 0..reader.read_u8()? {
+                let kind = reader.read_u16()?;
+                state.insert(kind, reader.read_bytes()?.to_vec());
+            }
+            for _ in 0..reader.read_u8()? {
+                state.remove(&reader.read_u16()?);
+            }
+        }
+
+        Ok(Snapshot { tick, entities })
+    }
+}
+
+/// Ring of recent snapshots kept so deltas can be encoded against whichever tick a
+/// peer last acknowledged, or decoded against whichever baseline the sender chose.
+pub struct SnapshotHistory {
+    snapshots: VecDeque<Snapshot>,
+    capacity: usize,
+}
+
+impl SnapshotHistory {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            snapshots: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    pub fn push(&mut self, snapshot: Snapshot) {
+        if self.snapshots.len() == self.capacity {
+            self.snapshots.pop_front();
+        }
+        self.snapshots.push_back(snapshot);
+    }
+
+    pub fn get(&self, tick: u32) -> Option<&Snapshot> {
+        self.snapshots.iter().find(|snapshot| snapshot.tick == tick)
+    }
+
+    pub fn latest(&self) -> Option<&Snapshot> {
+        self.snapshots.back()
+    }
+
+    /// Decodes `bytes` against the stored baseline it names and stores the result.
+    pub fn decode_and_push(&mut self, bytes: &[u8]) -> Result<&Snapshot, NetError> {
+        let baseline = Snapshot::peek_baseline(bytes)?.and_then(|tick| self.get(tick));
+        let snapshot = Snapshot::decode_delta(bytes, baseline)?;
+        self.push(snapshot);
+        Ok(self.snapshots.back().unwrap())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Components<'a> = &'a [(u16, &'a [u8])];
+
+    fn snapshot(tick: u32, entities: &[(u32, Components)]) -> Snapshot {
+        let mut snapshot = Snapshot::new(tick);
+        for (id, components) in entities {
+            let state = components
+                .iter()
+                .map(|(kind, bytes)| (*kind, bytes.to_vec()))
+                .collect();
+            snapshot.entities.insert(NetworkId(*id), state);
+        }
+        snapshot
+    }
+
+    #[test]
+    fn delta_roundtrip_with_changes_and_removals() {
+        let base = snapshot(
+            1,
+            &[
+                (1, &[(0, &[1, 2]), (1, &[9])]),
+                (2, &[(0, &[3])]),
+                (3, &[(0, &[4])]),
+            ],
+        );
+        let next = snapshot(
+            2,
+            &[(1, &[(0, &[1, 2])]), (3, &[(0, &[5])]), (4, &[(0, &[6])])],
+        );
+
+        let delta = next.encode_delta(Some(&base));
+        let decoded = Snapshot::decode_delta(&delta, Some(&base)).unwrap();
+        assert_eq!(decoded, next);
+    }
+
+    #[test]
+    fn unchanged_snapshot_encodes_header_only() {
+        let base = snapshot(1, &[(1, &[(0, &[1, 2, 3])]), (2, &[(0, &[4])])]);
+        let mut next = base.clone();
+        next.tick = 2;
+
+        assert_eq!(next.encode_delta(Some(&base)).len(), 4 + 4 + 2 + 2);
+    }
+
+    #[test]
+    fn delta_requires_matching_baseline() {
+        let base = snapshot(1, &[(1, &[(0, &[1])])]);
+        let next = snapshot(2, &[(1, &[(0, &[2])])]);
+        let delta = next.encode_delta(Some(&base));
+
+        assert!(matches!(
+            Snapshot::decode_delta(&delta, None),
+            Err(NetError::MissingBaseline(1))
+        ));
+    }
+}
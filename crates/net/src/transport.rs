@@ -0,0 +1,562 @@
+use crate::{ByteReader, ByteWriter, NetError};
+use std::collections::{HashMap, VecDeque};
+use std::io::ErrorKind;
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+use std::time::{Duration, Instant};
+
+/// Largest datagram the transport will build. Kept under the common 1280 byte IPv6
+/// minimum MTU so packets are never fragmented.
+const MAX_PACKET_SIZE: usize = 1200;
+const HEADER_SIZE: usize = 4 + 1 + 2 + 2 + 4;
+const MESSAGE_OVERHEAD: usize = 1 + 2 + 2;
+/// Number of recently received reliable message ids kept for duplicate detection.
+const RELIABLE_HISTORY: usize = 1024;
+/// Sent packets older than this many sequence numbers can no longer be acked.
+const SENT_PACKET_WINDOW: u16 = 256;
+
+const FLAG_RELIABLE: u8 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Delivery {
+    /// Fire and forget. Lost packets are not resent; use for state that is superseded
+    /// every tick, such as snapshots.
+    Unreliable,
+    /// Resent until acknowledged. Delivery is guaranteed but order is not.
+    Reliable,
+}
+
+#[derive(Debug)]
+pub enum NetEvent {
+    Connected(SocketAddr),
+    Disconnected(SocketAddr),
+    Message { from: SocketAddr, payload: Vec<u8> },
+}
+
+#[derive(Debug, Clone)]
+pub struct TransportConfig {
+    /// Must match on both ends; packets with a different id are dropped.
+    pub protocol_id: u32,
+    /// Maximum connections a listening endpoint accepts.
+    pub max_connections: usize,
+    /// Interval between resends of an unacknowledged reliable message or connect request.
+    pub resend_interval: Duration,
+    /// An empty packet is sent after this much silence so acks keep flowing.
+    pub heartbeat_interval: Duration,
+    /// A connection that receives nothing for this long is dropped.
+    pub timeout: Duration,
+}
+
+impl Default for TransportConfig {
+    fn default() -> Self {
+        Self {
+            protocol_id: 0x5247_4531,
+            max_connections: 32,
+            resend_interval: Duration::from_millis(100),
+            heartbeat_interval: Duration::from_millis(250),
+            timeout: Duration::from_secs(5),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PacketKind {
+    Connect = 0,
+    Accept = 1,
+    Payload = 2,
+    Disconnect = 3,
+}
+
+impl PacketKind {
+    fn from_u8(value: u8) -> Result<Self, NetError> {
+        match value {
+            0 => Ok(PacketKind::Connect),
+            1 => Ok(PacketKind::Accept),
+            2 => Ok(PacketKind::Payload),
+            3 => Ok(PacketKind::Disconnect),
+            _ => Err(NetError::InvalidPacket),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ConnectionState {
+    Connecting,
+    Connected,
+}
+
+struct PendingReliable {
+    id: u16,
+    payload: Vec<u8>,
+    last_sent: Option<Instant>,
+}
+
+struct Connection {
+    state: ConnectionState,
+    local_sequence: u16,
+    remote_sequence: u16,
+    /// Bit `i` is set when packet `remote_sequence - 1 - i` was received.
+    received_bits: u32,
+    /// Reliable message ids carried by each in-flight packet.
+    sent_packets: HashMap<u16, Vec<u16>>,
+    next_reliable_id: u16,
+    pending_reliable: Vec<PendingReliable>,
+    received_reliable: VecDeque<u16>,
+    unreliable: Vec<Vec<u8>>,
+    last_sent: Instant,
+    last_received: Instant,
+}
+
+impl Connection {
+    fn new(state: ConnectionState, now: Instant) -> Self {
+        Self {
+            state,
+            local_sequence: 0,
+            remote_sequence: 0,
+            received_bits: 0,
+            sent_packets: HashMap::new(),
+            next_reliable_id: 0,
+            pending_reliable: Vec::new(),
+            received_reliable: VecDeque::new(),
+            unreliable: Vec::new(),
+            // Force an immediate first send.
+            last_sent: now - Duration::from_secs(3600),
+            last_received: now,
+        }
+    }
+
+    fn record_received(&mut self, sequence: u16) {
+        if sequence_greater_than(sequence, self.remote_sequence) {
+            let shift = sequence.wrapping_sub(self.remote_sequence) as u32;
+            self.received_bits = if shift >= 32 {
+                0
+            } else {
+                (self.received_bits << shift) | (1 << (shift - 1))
+            };
+            self.remote_sequence = sequence;
+        } else {
+            let distance = self.remote_sequence.wrapping_sub(sequence) as u32;
+            if (1..=32).contains(&distance) {
+                self.received_bits |= 1 << (distance - 1);
+            }
+        }
+    }
+
+    fn process_acks(&mut self, ack: u16, ack_bits: u32) {
+        self.ack_packet(ack);
+        for i in 0..32 {
+            if ack_bits & (1 << i) != 0 {
+                self.ack_packet(ack.wrapping_sub(i + 1));
+            }
+        }
+    }
+
+    fn ack_packet(&mut self, sequence: u16) {
+        if let Some(ids) = self.sent_packets.remove(&sequence) {
+            self.pending_reliable
+                .retain(|message| !ids.contains(&message.id));
+        }
+    }
+
+    /// Returns false if the reliable message was already delivered.
+    fn accept_reliable(&mut self, id: u16) -> bool {
+        if self.received_reliable.contains(&id) {
+            return false;
+        }
+        if self.received_reliable.len() == RELIABLE_HISTORY {
+            self.received_reliable.pop_front();
+        }
+        self.received_reliable.push_back(id);
+        true
+    }
+
+    fn write_header(&mut self, writer: &mut ByteWriter, protocol_id: u32, kind: PacketKind) -> u16 {
+        let sequence = self.local_sequence;
+        self.local_sequence = self.local_sequence.wrapping_add(1);
+
+        writer.write_u32(protocol_id);
+        writer.write_u8(kind as u8);
+        writer.write_u16(sequence);
+        writer.write_u16(self.remote_sequence);
+        writer.write_u32(self.received_bits);
+        sequence
+    }
+
+    /// Builds the next payload packet, or None if there is nothing to send and no
+    /// heartbeat is due.
+    fn build_payload(&mut self, config: &TransportConfig, now: Instant) -> Option<Vec<u8>> {
+        let resend_due = |message: &PendingReliable| {
+            message
+                .last_sent
+                .is_none_or(|sent| now.duration_since(sent) >= config.resend_interval)
+        };
+        let has_reliable = self.pending_reliable.iter().any(resend_due);
+        let heartbeat_due = now.duration_since(self.last_sent) >= config.heartbeat_interval;
+        if self.unreliable.is_empty() && !has_reliable && !heartbeat_due {
+            return None;
+        }
+
+        let mut body = ByteWriter::new();
+        let mut count = 0u8;
+        let mut size = HEADER_SIZE + 1;
+        let mut reliable_ids = Vec::new();
+
+        for message in self.pending_reliable.iter_mut() {
+            if count == u8::MAX || !resend_due(message) {
+                continue;
+            }
+            if size + MESSAGE_OVERHEAD + message.payload.len() > MAX_PACKET_SIZE {
+                continue;
+            }
+            body.write_u8(FLAG_RELIABLE);
+            body.write_u16(message.id);
+            body.write_bytes(&message.payload);
+            message.last_sent = Some(now);
+            reliable_ids.push(message.id);
+            size += MESSAGE_OVERHEAD + message.payload.len();
+            count += 1;
+        }
+
+        // Unreliable messages that do not fit are dropped rather than carried over,
+        // since stale state is worse than none.
+        for payload in self.unreliable.drain(..) {
+            if count == u8::MAX || size + MESSAGE_OVERHEAD + payload.len() > MAX_PACKET_SIZE {
+                continue;
+            }
+            body.write_u8(0);
+            body.write_bytes(&payload);
+            size += MESSAGE_OVERHEAD + payload.len();
+            count += 1;
+        }
+
+        let mut writer = ByteWriter::new();
+        let protocol_id = config.protocol_id;
+        let sequence = self.write_header(&mut writer, protocol_id, PacketKind::Payload);
+        writer.write_u8(count);
+        writer.write_raw(body.as_slice());
+
+        self.sent_packets
+            .remove(&sequence.wrapping_sub(SENT_PACKET_WINDOW));
+        if !reliable_ids.is_empty() {
+            self.sent_packets.insert(sequence, reliable_ids);
+        }
+        self.last_sent = now;
+        Some(writer.into_inner())
+    }
+}
+
+/// A non-blocking UDP endpoint that acts as a client, a server, or both.
+///
+/// Call `poll` once per frame to receive, then `send` any messages, then `flush` to
+/// put them on the wire. Reliability is per message: every packet acks the last 33
+/// packets received from the peer, and reliable messages are resent until a packet
+/// carrying them is acked.
+pub struct Endpoint {
+    socket: UdpSocket,
+    config: TransportConfig,
+    listening: bool,
+    connections: HashMap<SocketAddr, Connection>,
+    events: Vec<NetEvent>,
+}
+
+impl Endpoint {
+    /// Binds a socket that only makes outgoing connections.
+    pub fn client(config: TransportConfig) -> Result<Self, NetError> {
+        Self::bind("0.0.0.0:0", config, false)
+    }
+
+    /// Binds a socket that accepts incoming connections on `addr`.
+    pub fn server(addr: impl ToSocketAddrs, config: TransportConfig) -> Result<Self, NetError> {
+        Self::bind(addr, config, true)
+    }
+
+    fn bind(
+        addr: impl ToSocketAddrs,
+        config: TransportConfig,
+        listening: bool,
+    ) -> Result<Self, NetError> {
+        let socket = UdpSocket::bind(addr)?;
+        socket.set_nonblocking(true)?;
+        Ok(Self {
+            socket,
+            config,
+            listening,
+            connections: HashMap::new(),
+            events: Vec::new(),
+        })
+    }
+
+    pub fn local_addr(&self) -> Result<SocketAddr, NetError> {
+        Ok(self.socket.local_addr()?)
+    }
+
+    /// Starts connecting to `addr`. A `NetEvent::Connected` is emitted once the peer accepts.
+    pub fn connect(&mut self, addr: SocketAddr) {
+        self.connections
+            .entry(addr)
+            .or_insert_with(|| Connection::new(ConnectionState::Connecting, Instant::now()));
+    }
+
+    /// Drops the connection and tells the peer. Does not emit `Disconnected` locally.
+    pub fn disconnect(&mut self, addr: SocketAddr) {
+        if let Some(mut connection) = self.connections.remove(&addr) {
+            let mut writer = ByteWriter::new();
+            connection.write_header(&mut writer, self.config.protocol_id, PacketKind::Disconnect);
+            let _ = self.socket.send_to(writer.as_slice(), addr);
+        }
+    }
+
+    pub fn is_connected(&self, addr: SocketAddr) -> bool {
+        self.connections
+            .get(&addr)
+            .is_some_and(|connection| connection.state == ConnectionState::Connected)
+    }
+
+    pub fn connections(&self) -> impl Iterator<Item = SocketAddr> + '_ {
+        self.connections
+            .iter()
+            .filter(|(_, connection)| connection.state == ConnectionState::Connected)
+            .map(|(addr, _)| *addr)
+    }
+
+    /// Queues a message for `addr`. Messages for unknown or still-connecting peers are dropped.
+    pub fn send(&mut self, addr: SocketAddr, payload: Vec<u8>, delivery: Delivery) {
+        let Some(connection) = self.connections.get_mut(&addr) else {
+            return;
+        };
+        if connection.state != ConnectionState::Connected {
+            return;
+        }
+        debug_assert!(
+            payload.len() + HEADER_SIZE + 1 + MESSAGE_OVERHEAD <= MAX_PACKET_SIZE,
+            "message exceeds the packet size"
+        );
+        match delivery {
+            Delivery::Unreliable => connection.unreliable.push(payload),
+            Delivery::Reliable => {
+                let id = connection.next_reliable_id;
+                connection.next_reliable_id = id.wrapping_add(1);
+                connection.pending_reliable.push(PendingReliable {
+                    id,
+                    payload,
+                    last_sent: None,
+                });
+            }
+        }
+    }
+
+    /// Queues a message for every connected peer.
+    pub fn broadcast(&mut self, payload: &[u8], delivery: Delivery) {
+        let addrs = self.connections().collect::<Vec<_>>();
+        for addr in addrs {
+            self.send(addr, payload.to_vec(), delivery);
+        }
+    }
+
+    /// Reads every pending datagram and drops timed-out connections.
+    pub fn poll(&mut self) -> Result<Vec<NetEvent>, NetError> {
+        let mut buf = [0u8; MAX_PACKET_SIZE];
+        loop {
+            match self.socket.recv_from(&mut buf) {
+                Ok((len, from)) => {
+                    // Malformed datagrams are ignored; they are most likely stray traffic.
+                    let _ = self.handle_packet(&buf[..len], from);
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                // Windows reports ICMP port unreachable from a previous send here.
+                Err(e) if e.kind() == ErrorKind::ConnectionReset => continue,
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        let now = Instant::now();
+        let timeout = self.config.timeout;
+        let timed_out = self
+            .connections
+            .iter()
+            .filter(|(_, connection)| now.duration_since(connection.last_received) > timeout)
+            .map(|(addr, _)| *addr)
+            .collect::<Vec<_>>();
+        for addr in timed_out {
+            if let Some(connection) = self.connections.remove(&addr)
+                && connection.state == ConnectionState::Connected
+            {
+                self.events.push(NetEvent::Disconnected(addr));
+            }
+        }
+
+        Ok(std::mem::take(&mut self.events))
+    }
+
+    /// Sends queued messages, reliable resends, connect requests and heartbeats.
+    pub fn flush(&mut self) -> Result<(), NetError> {
+        let now = Instant::now();
+        for (addr, connection) in self.connections.iter_mut() {
+            let packet = match connection.state {
+                ConnectionState::Connecting => {
+                    if now.duration_since(connection.last_sent) < self.config.resend_interval {
+                        continue;
+                    }
+                    connection.last_sent = now;
+                    let mut writer = ByteWriter::new();
+                    connection.write_header(
+                        &mut writer,
+                        self.config.protocol_id,
+                        PacketKind::Connect,
+                    );
+                    writer.into_inner()
+                }
+                ConnectionState::Connected => match connection.build_payload(&self.config, now) {
+                    Some(packet) => packet,
+                    None => continue,
+                },
+            };
+
+            match self.socket.send_to(&packet, addr) {
+                Ok(_) => {}
+                Err(e) if e.kind() == ErrorKind::WouldBlock => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Ok(())
+    }
+
+    fn handle_packet(&mut self, packet: &[u8], from: SocketAddr) -> Result<(), NetError> {
+        let mut reader = ByteReader::new(packet);
+        if reader.read_u32()? != self.config.protocol_id {
+            return Err(NetError::InvalidPacket);
+        }
+        let kind = PacketKind::from_u8(reader.read_u8()?)?;
+        let sequence = reader.read_u16()?;
+        let ack = reader.read_u16()?;
+        let ack_bits = reader.read_u32()?;
+        let now = Instant::now();
+
+        if kind == PacketKind::Connect {
+            if !self.listening {
+                return Ok(());
+            }
+            if !self.connections.contains_key(&from) {
+                if self.connections.len() >= self.config.max_connections {
+                    return Ok(());
+                }
+                self.connections
+                    .insert(from, Connection::new(ConnectionState::Connected, now));
+                self.events.push(NetEvent::Connected(from));
+            }
+            // Answer every connect so a lost accept is recovered by the client's resend.
+            let connection = self.connections.get_mut(&from).unwrap();
+            connection.last_received = now;
+            let mut writer = ByteWriter::new();
+            connection.write_header(&mut writer, self.config.protocol_id, PacketKind::Accept);
+            connection.last_sent = now;
+            self.socket.send_to(writer.as_slice(), from)?;
+            return Ok(());
+        }
+
+        let Some(connection) = self.connections.get_mut(&from) else {
+            return Ok(());
+        };
+        connection.last_received = now;
+
+        match kind {
+            PacketKind::Connect => unreachable!(),
+            PacketKind::Accept => {
+                if connection.state == ConnectionState::Connecting {
+                    connection.state = ConnectionState::Connected;
+                    connection.last_sent = now - self.config.heartbeat_interval;
+                    self.events.push(NetEvent::Connected(from));
+                }
+            }
+            PacketKind::Disconnect => {
+                let was_connected = connection.state == ConnectionState::Connected;
+                self.connections.remove(&from);
+                if was_connected {
+                    self.events.push(NetEvent::Disconnected(from));
+                }
+            }
+            PacketKind::Payload => {
+                if connection.state != ConnectionState::Connected {
+                    return Ok(());
+                }
+                connection.record_received(sequence);
+                connection.process_acks(ack, ack_bits);
+
+                let count = reader.read_u8()?;
+                for _ in 0..count {
+                    let flags = reader.read_u8()?;
+                    let deliver = if flags & FLAG_RELIABLE != 0 {
+                        let id = reader.read_u16()?;
+                        connection.accept_reliable(id)
+                    } else {
+                        true
+                    };
+                    let payload = reader.read_bytes()?;
+                    if deliver {
+                        self.events.push(NetEvent::Message {
+                            from,
+                            payload: payload.to_vec(),
+                        });
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Wrapping comparison for 16-bit sequence numbers.
+fn sequence_greater_than(a: u16, b: u16) -> bool {
+    (a > b && a - b <= 32768) || (a < b && b - a > 32768)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pump(a: &mut Endpoint, b: &mut Endpoint) -> (Vec<NetEvent>, Vec<NetEvent>) {
+        let mut a_events = Vec::new();
+        let mut b_events = Vec::new();
+        for _ in 0..50 {
+            a.flush().unwrap();
+            b.flush().unwrap();
+            std::thread::sleep(Duration::from_millis(2));
+            a_events.extend(a.poll().unwrap());
+            b_events.extend(b.poll().unwrap());
+        }
+        (a_events, b_events)
+    }
+
+    #[test]
+    fn sequence_comparison_wraps() {
+        assert!(sequence_greater_than(1, 0));
+        assert!(sequence_greater_than(0, u16::MAX));
+        assert!(!sequence_greater_than(u16::MAX, 0));
+    }
+
+    #[test]
+    fn loopback_connect_and_reliable_message() {
+        let config = TransportConfig {
+            resend_interval: Duration::from_millis(5),
+            ..Default::default()
+        };
+        let mut server = Endpoint::server("127.0.0.1:0", config.clone()).unwrap();
+        let mut client = Endpoint::client(config).unwrap();
+        let server_addr = server.local_addr().unwrap();
+
+        client.connect(server_addr);
+        let (_, client_events) = pump(&mut server, &mut client);
+        assert!(
+            client_events
+                .iter()
+                .any(|e| matches!(e, NetEvent::Connected(addr) if *addr == server_addr))
+        );
+
+        client.send(server_addr, b"hello".to_vec(), Delivery::Reliable);
+        let (server_events, _) = pump(&mut server, &mut client);
+        let messages = server_events
+            .iter()
+            .filter(|e| matches!(e, NetEvent::Message { payload, .. } if payload == b"hello"))
+            .count();
+        assert_eq!(messages, 1);
+    }
+}
@@ -10,6 +10,7 @@ impl Guid {
         Self(uuid::Uuid::new_v4())
     }
 
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(s: &str) -> Option<Self> {
         uuid::Uuid::parse_str(s).ok().map(Self)
     }
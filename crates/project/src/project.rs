@@ -54,8 +54,10 @@ impl Project {
     /// resolved relative to the directory containing the `.eproj`.
     pub fn load(path: impl AsRef<Path>) -> Result<Self, ProjectError> {
         let path = path.as_ref();
+        // A bare file name has an empty parent, which cannot be canonicalized.
         let root = path
             .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
             .unwrap_or(Path::new("."))
             .to_path_buf()
             .canonicalize()?;
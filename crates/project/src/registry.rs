@@ -1,15 +1,18 @@
 use crate::AssetMeta;
 use common::Guid;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fmt;
 use std::path::{Path, PathBuf};
 
 /// Index of every known asset in the project content directory.
+#[derive(Default)]
 pub struct AssetRegistry {
     records: HashMap<Guid, AssetRecord>,
     /// Reverse index: source_path (relative to content dir) → GUID.
     path_index: HashMap<PathBuf, Guid>,
+    /// Reverse dependency index: GUID → assets that reference it directly.
+    dependents: HashMap<Guid, Vec<Guid>>,
 }
 
 pub struct AssetRecord {
@@ -21,7 +24,11 @@ pub struct AssetRecord {
     pub source_hash: u64,
     /// FNV-1a hash of the import settings at last scan.
     pub import_hash: u64,
+    /// The `[import]` table of the asset's `.meta` sidecar, for its conditioner.
+    pub import: toml::Table,
     pub status: AssetStatus,
+    /// Assets this one references by GUID, read from the source at last scan.
+    pub dependencies: Vec<Guid>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -32,6 +39,11 @@ pub enum AssetType {
     Material,
     Shader,
     ShaderManifest,
+    StringTable,
+    BehaviorTree,
+    /// A level's asset manifest: TOML whose GUID strings name the meshes, materials
+    /// and other assets it places. Loaded by game code; preloading uses its references.
+    Scene,
     Unknown,
 }
 
@@ -144,9 +156,10 @@ impl AssetRegistry {
             let asset_type =
                 AssetType::from_extension(path.extension().and_then(|e| e.to_str()).unwrap_or(""));
             let source_hash = hash_file(path).unwrap_or(0);
-            let import_hash = hash_table(&meta.import);
+            let import_hash = hash_table(&meta.import) ^ asset_type.cook_version();
 
             let source_path = path.strip_prefix(content_dir).unwrap_or(path).to_path_buf();
+            let dependencies = source_dependencies(path, asset_type);
 
             let status = resolve_status(
                 cache_dir,
@@ -165,19 +178,14 @@ impl AssetRegistry {
                     asset_type,
                     source_hash,
                     import_hash,
+                    import: meta.import,
                     status,
+                    dependencies,
                 },
             );
         }
 
-        let path_index = records
-            .iter()
-            .map(|(g, r)| (r.source_path.clone(), *g))
-            .collect();
-        Ok(Self {
-            records,
-            path_index,
-        })
+        Ok(Self::from_records(records))
     }
 
     /// Loads a previously saved registry from `.cache/.assetdb`, restoring
@@ -231,6 +239,65 @@ impl AssetRegistry {
             .filter(|r| r.status != AssetStatus::Fresh)
     }
 
+    /// Assets `guid` references directly.
+    pub fn dependencies(&self, guid: &Guid) -> &[Guid] {
+        self.records
+            .get(guid)
+            .map_or(&[], |r| r.dependencies.as_slice())
+    }
+
+    /// Assets that reference `guid` directly.
+    pub fn dependents(&self, guid: &Guid) -> &[Guid] {
+        self.dependents.get(guid).map_or(&[], Vec::as_slice)
+    }
+
+    /// `roots` and everything they depend on, transitively, each listed after all of
+    /// its dependencies. Unknown GUIDs are skipped and cycles are broken arbitrarily.
+    pub fn load_order(&self, roots: &[Guid]) -> Vec<Guid> {
+        fn visit(
+            registry: &AssetRegistry,
+            guid: Guid,
+            visited: &mut HashSet<Guid>,
+            order: &mut Vec<Guid>,
+        ) {
+            if !registry.records.contains_key(&guid) || !visited.insert(guid) {
+                return;
+            }
+            for &dependency in registry.dependencies(&guid) {
+                visit(registry, dependency, visited, order);
+            }
+            order.push(guid);
+        }
+
+        let mut visited = HashSet::new();
+        let mut order = Vec::new();
+        for &root in roots {
+            visit(self, root, &mut visited, &mut order);
+        }
+        order
+    }
+
+    /// `changed` and every asset that depends on it, transitively: what must be rebuilt
+    /// when `changed` is reimported, in an order where each asset comes after the
+    /// affected assets it depends on.
+    pub fn invalidation_order(&self, changed: &Guid) -> Vec<Guid> {
+        let mut affected = vec![*changed];
+        let mut seen: HashSet<Guid> = affected.iter().copied().collect();
+        let mut next = 0;
+        while let Some(&guid) = affected.get(next) {
+            next += 1;
+            for &dependent in self.dependents(&guid) {
+                if seen.insert(dependent) {
+                    affected.push(dependent);
+                }
+            }
+        }
+        self.load_order(&affected)
+            .into_iter()
+            .filter(|guid| seen.contains(guid))
+            .collect()
+    }
+
     pub fn all(&self) -> impl Iterator<Item = &AssetRecord> {
         self.records.values()
     }
@@ -263,20 +330,34 @@ impl AssetRegistry {
                         asset_type: rec.asset_type,
                         source_hash,
                         import_hash,
+                        import: toml::Table::new(),
                         status,
+                        // Only used as the `previous` of a scan, which re-reads them.
+                        dependencies: Vec::new(),
                     },
                 ))
             })
             .collect();
 
+        Ok(Self::from_records(records))
+    }
+
+    fn from_records(records: HashMap<Guid, AssetRecord>) -> Self {
         let path_index = records
             .iter()
             .map(|(g, r)| (r.source_path.clone(), *g))
             .collect();
-        Ok(Self {
+        let mut dependents: HashMap<Guid, Vec<Guid>> = HashMap::new();
+        for record in records.values() {
+            for dependency in &record.dependencies {
+                dependents.entry(*dependency).or_default().push(record.guid);
+            }
+        }
+        Self {
             records,
             path_index,
-        })
+            dependents,
+        }
     }
 }
 
@@ -284,21 +365,43 @@ impl AssetType {
     fn from_extension(ext: &str) -> Self {
         match ext {
             "gltf" | "glb" | "obj" => AssetType::Mesh,
-            "png" | "jpg" | "jpeg" | "hdr" | "exr" => AssetType::Texture,
+            "png" | "jpg" | "jpeg" | "hdr" | "exr" | "ktx2" => AssetType::Texture,
             "emat" => AssetType::Material,
             "glsl" | "vert" | "frag" | "comp" => AssetType::Shader,
             "shader" => AssetType::ShaderManifest,
+            "strings" => AssetType::StringTable,
+            "btree" => AssetType::BehaviorTree,
+            "scene" => AssetType::Scene,
             _ => AssetType::Unknown,
         }
     }
 
+    /// Bumped when the cooked format of this asset type changes. Folded into the import
+    /// hash, so outputs cooked by an older pipeline are marked dirty and recooked.
+    fn cook_version(self) -> u64 {
+        match self {
+            // 1: tangents added to the vertex format.
+            // 2: vertex colors and a second UV set moved to an optional extras stream.
+            // 3: optional skin stream with joint indices and weights.
+            AssetType::Mesh => 3,
+            // 1: flags word with color space and compression.
+            AssetType::Texture => 1,
+            _ => 0,
+        }
+    }
+
     /// File extension used for this asset's cooked output, if any.
     pub fn cooked_extension(self) -> Option<&'static str> {
         match self {
             AssetType::Mesh => Some("emesh"),
             AssetType::Texture => Some("etex"),
             AssetType::Shader => Some("spv"),
-            AssetType::Material | AssetType::ShaderManifest | AssetType::Unknown => None,
+            AssetType::Material
+            | AssetType::ShaderManifest
+            | AssetType::StringTable
+            | AssetType::BehaviorTree
+            | AssetType::Scene
+            | AssetType::Unknown => None,
         }
     }
 }
@@ -361,6 +464,39 @@ fn cooked_status(cache_dir: &Path, guid: &Guid, asset_type: AssetType) -> AssetS
     }
 }
 
+/// GUIDs referenced by a source file. Materials and scenes are TOML, and any string
+/// value in them that parses as a GUID is treated as a reference. Other types reference
+/// nothing.
+fn source_dependencies(path: &Path, asset_type: AssetType) -> Vec<Guid> {
+    fn collect(value: &toml::Value, out: &mut Vec<Guid>) {
+        match value {
+            toml::Value::String(s) => {
+                if let Some(guid) = Guid::from_str(s) {
+                    if !out.contains(&guid) {
+                        out.push(guid);
+                    }
+                }
+            }
+            toml::Value::Array(values) => values.iter().for_each(|v| collect(v, out)),
+            toml::Value::Table(table) => table.values().for_each(|v| collect(v, out)),
+            _ => {}
+        }
+    }
+
+    if !matches!(asset_type, AssetType::Material | AssetType::Scene) {
+        return Vec::new();
+    }
+    let Some(table) = std::fs::read_to_string(path)
+        .ok()
+        .and_then(|text| text.parse::<toml::Table>().ok())
+    else {
+        return Vec::new();
+    };
+    let mut dependencies = Vec::new();
+    collect(&toml::Value::Table(table), &mut dependencies);
+    dependencies
+}
+
 fn parse_hash(s: &str) -> u64 {
     let hex = s.trim_start_matches("0x");
     u64::from_str_radix(hex, 16).unwrap_or(0)
@@ -404,6 +540,7 @@ pub fn resolve_cooked_path(cache_dir: &Path, guid: &Guid, extension: &str) -> Pa
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::Project;
 
     #[test]
     fn asset_type_from_extension() {
@@ -416,6 +553,9 @@ mod tests {
             AssetType::from_extension("shader"),
             AssetType::ShaderManifest
         );
+        assert_eq!(AssetType::from_extension("strings"), AssetType::StringTable);
+        assert_eq!(AssetType::from_extension("btree"), AssetType::BehaviorTree);
+        assert_eq!(AssetType::from_extension("scene"), AssetType::Scene);
         assert_eq!(AssetType::from_extension("xyz"), AssetType::Unknown);
     }
 
@@ -478,4 +618,45 @@ mod tests {
 
         std::fs::remove_dir_all(&dir).unwrap();
     }
+
+    #[test]
+    fn dependencies_are_read_from_materials_and_scenes() {
+        let dir = std::env::temp_dir().join(format!("reg_deps_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let texture = Guid::from_uuid(uuid::Uuid::new_v4());
+        let material = Guid::from_uuid(uuid::Uuid::new_v4());
+        let scene = Guid::from_uuid(uuid::Uuid::new_v4());
+        let write = |name: &str, guid: Guid, content: String| {
+            let path = dir.join(name);
+            std::fs::write(&path, content).unwrap();
+            std::fs::write(
+                AssetMeta::meta_path_for(&path),
+                format!("guid = \"{guid}\"\n"),
+            )
+            .unwrap();
+        };
+        write("wall.png", texture, "px".into());
+        write(
+            "wall.emat",
+            material,
+            format!("type = \"pbr\"\n[params.albedo]\ntexture = \"{texture}\"\n"),
+        );
+        write(
+            "level.scene",
+            scene,
+            format!("[[entities]]\nmaterial = \"{material}\"\n"),
+        );
+
+        let registry = AssetRegistry::scan(&dir.join(".cache"), &dir, None).unwrap();
+        assert_eq!(registry.dependencies(&material), [texture]);
+        assert_eq!(registry.dependents(&texture), [material]);
+        assert_eq!(registry.load_order(&[scene]), [texture, material, scene]);
+        assert_eq!(
+            registry.invalidation_order(&texture),
+            [texture, material, scene]
+        );
+        assert_eq!(registry.invalidation_order(&material), [material, scene]);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
 }
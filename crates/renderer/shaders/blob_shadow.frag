@@ -0,0 +1,50 @@
+#version 450
+
+// Darkens the scene surface inside a blob shadow's box by a soft ellipse that fades
+// with the distance below the entity. Blends multiplicatively over the lit image.
+
+struct BlobShadow {
+    // xyz: world-space location of the entity, w: how far below it the blob reaches
+    vec4 positionReach;
+    // xyz: direction of the ellipse's length, flat on the ground, w: opacity
+    vec4 forwardOpacity;
+    // x: half width, y: half length
+    vec4 halfSize;
+};
+
+layout(set = 0, binding = 0) uniform BlobShadowUniforms {
+    mat4 viewProj;
+    mat4 inverseViewProj;
+} ubo;
+
+layout(std430, set = 0, binding = 1) readonly buffer BlobShadows {
+    BlobShadow blobs[];
+};
+
+layout(set = 0, binding = 2) uniform sampler2D depthTexture;
+
+layout(location = 0) flat in uint blobIndex;
+
+layout(location = 0) out vec4 outColor;
+
+void main() {
+    float depth = texelFetch(depthTexture, ivec2(gl_FragCoord.xy), 0).r;
+    if (depth >= 1.0)
+        discard;
+    vec2 uv = gl_FragCoord.xy / vec2(textureSize(depthTexture, 0));
+    vec4 world = ubo.inverseViewProj * vec4(uv * 2.0 - 1.0, depth, 1.0);
+    world /= world.w;
+
+    BlobShadow blob = blobs[blobIndex];
+    vec3 offset = world.xyz - blob.positionReach.xyz;
+    vec3 forward = blob.forwardOpacity.xyz;
+    vec3 right = cross(vec3(0.0, 1.0, 0.0), forward);
+    vec2 local = vec2(dot(offset, right), dot(offset, forward)) / blob.halfSize.xy;
+    float radial = 1.0 - dot(local, local);
+    float below = -offset.y / max(blob.positionReach.w, 1e-4);
+    if (radial <= 0.0 || below >= 1.0)
+        discard;
+
+    float falloff = smoothstep(0.0, 1.0, radial) * (1.0 - clamp(below, 0.0, 1.0));
+    outColor = vec4(0.0, 0.0, 0.0, blob.forwardOpacity.w * falloff);
+}
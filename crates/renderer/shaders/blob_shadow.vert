@@ -0,0 +1,51 @@
+#version 450
+
+// Boxes around the volume each blob shadow projects into, 36 vertices per blob. The
+// fragment shader finds the scene surface inside the box and darkens it.
+
+struct BlobShadow {
+    // xyz: world-space location of the entity, w: how far below it the blob reaches
+    vec4 positionReach;
+    // xyz: direction of the ellipse's length, flat on the ground, w: opacity
+    vec4 forwardOpacity;
+    // x: half width, y: half length
+    vec4 halfSize;
+};
+
+layout(set = 0, binding = 0) uniform BlobShadowUniforms {
+    mat4 viewProj;
+    mat4 inverseViewProj;
+} ubo;
+
+layout(std430, set = 0, binding = 1) readonly buffer BlobShadows {
+    BlobShadow blobs[];
+};
+
+layout(location = 0) flat out uint blobIndex;
+
+// Corner bits of a cube's triangles, counter-clockwise seen from outside.
+const uint CUBE[36] = uint[](
+    4, 6, 2, 4, 2, 0, 1, 3, 7, 1, 7, 5,
+    0, 1, 5, 0, 5, 4, 6, 7, 3, 6, 3, 2,
+    2, 3, 1, 2, 1, 0, 4, 5, 7, 4, 7, 6
+);
+
+// Fraction of the reach the box extends above the entity, so ground that rises a
+// little around it still receives the blob.
+const float ABOVE = 0.1;
+
+void main() {
+    blobIndex = uint(gl_VertexIndex) / 36;
+    BlobShadow blob = blobs[blobIndex];
+    uint bits = CUBE[uint(gl_VertexIndex) % 36];
+    vec3 corner = vec3(bits & 1, (bits >> 1) & 1, (bits >> 2) & 1);
+
+    vec3 forward = blob.forwardOpacity.xyz;
+    vec3 right = cross(vec3(0.0, 1.0, 0.0), forward);
+    float reach = blob.positionReach.w;
+    vec3 position = blob.positionReach.xyz
+        + right * (corner.x * 2.0 - 1.0) * blob.halfSize.x
+        + vec3(0.0, mix(-reach, reach * ABOVE, corner.y), 0.0)
+        + forward * (corner.z * 2.0 - 1.0) * blob.halfSize.y;
+    gl_Position = ubo.viewProj * vec4(position, 1.0);
+}
@@ -1,4 +1,8 @@
 C:\VulkanSDK\1.3.290.0\Bin\glslc.exe shader.vert -o vert.spv
+C:\VulkanSDK\1.3.290.0\Bin\glslc.exe shader.vert -DHAS_VERTEX_EXTRAS -o vert.HAS_VERTEX_EXTRAS.spv
+C:\VulkanSDK\1.3.290.0\Bin\glslc.exe shader.vert -DHAS_VERTEX_EXTRAS -DHAS_WIND -o vert.HAS_VERTEX_EXTRAS.HAS_WIND.spv
+C:\VulkanSDK\1.3.290.0\Bin\glslc.exe shader.vert -DHAS_SKINNING -o vert.HAS_SKINNING.spv
+C:\VulkanSDK\1.3.290.0\Bin\glslc.exe shader.vert -DHAS_SKINNING -DHAS_VERTEX_EXTRAS -o vert.HAS_SKINNING.HAS_VERTEX_EXTRAS.spv
 C:\VulkanSDK\1.3.290.0\Bin\glslc.exe shader.frag -o pbr.frag.spv
 C:\VulkanSDK\1.3.290.0\Bin\glslc.exe shader.frag -DHAS_COLOR_TEXTURE -o pbr.frag.HAS_COLOR_TEXTURE.spv
 C:\VulkanSDK\1.3.290.0\Bin\glslc.exe shader.frag -DHAS_NORMAL_TEXTURE -o pbr.frag.HAS_NORMAL_TEXTURE.spv
@@ -9,9 +13,34 @@ C:\VulkanSDK\1.3.290.0\Bin\glslc.exe shader.frag -DHAS_NORMAL_TEXTURE -DHAS_ORM_
 C:\VulkanSDK\1.3.290.0\Bin\glslc.exe shader.frag -DHAS_COLOR_TEXTURE -DHAS_NORMAL_TEXTURE -DHAS_ORM_TEXTURE -o pbr.frag.HAS_COLOR_TEXTURE.HAS_NORMAL_TEXTURE.HAS_ORM_TEXTURE.spv
 
 C:\VulkanSDK\1.3.290.0\Bin\glslc.exe shadow.vert -o shadow.spv
+C:\VulkanSDK\1.3.290.0\Bin\glslc.exe shadow.vert -DHAS_SKINNING -o shadow.HAS_SKINNING.spv
 C:\VulkanSDK\1.3.290.0\Bin\glslc.exe lighting.frag -o lighting.spv
+C:\VulkanSDK\1.3.290.0\Bin\glslc.exe light_clusters.comp -o light_clusters.spv
+C:\VulkanSDK\1.3.290.0\Bin\glslc.exe gpu_culling.comp -o gpu_culling.spv
+C:\VulkanSDK\1.3.290.0\Bin\glslc.exe ibl_irradiance.comp -o ibl_irradiance.spv
+C:\VulkanSDK\1.3.290.0\Bin\glslc.exe ibl_specular.comp -o ibl_specular.spv
+C:\VulkanSDK\1.3.290.0\Bin\glslc.exe brdf_lut.comp -o brdf_lut.spv
 C:\VulkanSDK\1.3.290.0\Bin\glslc.exe quad.vert -o quad.spv
 C:\VulkanSDK\1.3.290.0\Bin\glslc.exe line_debug.vert -o line_debug_vert.spv
 C:\VulkanSDK\1.3.290.0\Bin\glslc.exe line_debug.frag -o line_debug_frag.spv
+C:\VulkanSDK\1.3.290.0\Bin\glslc.exe ui.vert -o ui_vert.spv
+C:\VulkanSDK\1.3.290.0\Bin\glslc.exe ui.frag -o ui_frag.spv
+C:\VulkanSDK\1.3.290.0\Bin\glslc.exe draw2d.vert -o draw2d_vert.spv
+C:\VulkanSDK\1.3.290.0\Bin\glslc.exe draw2d.frag -o draw2d_frag.spv
+C:\VulkanSDK\1.3.290.0\Bin\glslc.exe output.frag -o output.spv
+C:\VulkanSDK\1.3.290.0\Bin\glslc.exe dof_coc.frag -o dof_coc.spv
+C:\VulkanSDK\1.3.290.0\Bin\glslc.exe dof_blur.frag -o dof_blur.spv
+C:\VulkanSDK\1.3.290.0\Bin\glslc.exe dof_composite.frag -o dof_composite.spv
+C:\VulkanSDK\1.3.290.0\Bin\glslc.exe sky.frag -o sky.spv
+C:\VulkanSDK\1.3.290.0\Bin\glslc.exe blob_shadow.vert -o blob_shadow_vert.spv
+C:\VulkanSDK\1.3.290.0\Bin\glslc.exe blob_shadow.frag -o blob_shadow_frag.spv
+C:\VulkanSDK\1.3.290.0\Bin\glslc.exe particle_emit.comp -o particle_emit.spv
+C:\VulkanSDK\1.3.290.0\Bin\glslc.exe particle_simulate.comp -o particle_simulate.spv
+C:\VulkanSDK\1.3.290.0\Bin\glslc.exe particle_finalize.comp -o particle_finalize.spv
+C:\VulkanSDK\1.3.290.0\Bin\glslc.exe particle.vert -o particle_vert.spv
+C:\VulkanSDK\1.3.290.0\Bin\glslc.exe particle.frag -o particle_frag.spv
+C:\VulkanSDK\1.3.290.0\Bin\glslc.exe trail.vert -o trail_vert.spv
+C:\VulkanSDK\1.3.290.0\Bin\glslc.exe trail.frag -o trail_frag.spv
+C:\VulkanSDK\1.3.290.0\Bin\glslc.exe reflection_probe_capture.comp -o reflection_probe_capture.spv
 
 pause
\ No newline at end of file
@@ -0,0 +1,46 @@
+#version 450
+
+// One axis of the separable bokeh blur, at half resolution. A neighbor contributes when its
+// own circle of confusion reaches this pixel, so in-focus pixels stay sharp and blurred
+// foreground spreads over what is behind it. Alpha carries the CoC on, lowered to the
+// nearest foreground CoC that reached the pixel so the composite blends that spread in.
+
+layout(set = 0, binding = 0) uniform sampler2D sourceTexture;
+
+layout(push_constant) uniform DofParams {
+    mat4 inverseProj;
+    // x: focus distance, y: CoC scale in half-res pixels, z: largest CoC in half-res pixels
+    vec4 lens;
+    // xy: one half-res texel along the blur axis, in UV
+    vec4 direction;
+} params;
+
+layout(location = 0) in vec2 fragTexCoord;
+layout(location = 0) out vec4 outColor;
+
+// Taps on each side of the center.
+const int TAPS = 8;
+
+void main() {
+    vec4 center = texture(sourceTexture, fragTexCoord);
+    float maxRadius = params.lens.z;
+
+    vec3 sum = vec3(0.0);
+    float weightSum = 0.0;
+    float coc = center.a;
+    for (int i = -TAPS; i <= TAPS; i++) {
+        float offset = float(i) / float(TAPS) * maxRadius;
+        vec4 tap = texture(sourceTexture, fragTexCoord + params.direction.xy * offset);
+
+        // What is behind this pixel blurs over it no more than this pixel is blurred.
+        float radius = tap.a > center.a ? min(abs(tap.a), abs(center.a)) : abs(tap.a);
+        float weight = clamp(radius - abs(offset) + 1.0, 0.0, 1.0);
+        sum += tap.rgb * weight;
+        weightSum += weight;
+        if (weight > 0.0) {
+            coc = min(coc, tap.a);
+        }
+    }
+
+    outColor = vec4(sum / weightSum, coc);
+}
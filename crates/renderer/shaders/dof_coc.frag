@@ -0,0 +1,42 @@
+#version 450
+
+// Downsamples the lit scene to half resolution and stores each pixel's circle of
+// confusion in alpha: its blur radius in half-resolution pixels, negative in front of the
+// focus plane.
+
+layout(set = 0, binding = 0) uniform sampler2D sceneTexture;
+layout(set = 0, binding = 1) uniform sampler2D depthTexture;
+
+layout(push_constant) uniform DofParams {
+    mat4 inverseProj;
+    // x: focus distance, y: CoC scale in half-res pixels, z: largest CoC in half-res pixels
+    vec4 lens;
+    // xy: one half-res texel along the blur axis, in UV
+    vec4 direction;
+} params;
+
+layout(location = 0) in vec2 fragTexCoord;
+layout(location = 0) out vec4 outColor;
+
+float circleOfConfusion(float depth) {
+    vec4 view = params.inverseProj * vec4(0.0, 0.0, depth, 1.0);
+    float distance = -view.z / view.w;
+    float coc = params.lens.y * (distance - params.lens.x) / distance;
+    return clamp(coc, -params.lens.z, params.lens.z);
+}
+
+void main() {
+    ivec2 lastTexel = textureSize(depthTexture, 0) - 1;
+    ivec2 base = ivec2(gl_FragCoord.xy) * 2;
+
+    // The nearest of the four depths, so foreground edges keep their blur.
+    float depth = 1.0;
+    for (int i = 0; i < 4; i++) {
+        ivec2 texel = min(base + ivec2(i & 1, i >> 1), lastTexel);
+        depth = min(depth, texelFetch(depthTexture, texel, 0).r);
+    }
+
+    // Bilinear at the center of the 2x2 block averages it.
+    vec3 color = texture(sceneTexture, fragTexCoord).rgb;
+    outColor = vec4(color, circleOfConfusion(depth));
+}
@@ -0,0 +1,34 @@
+#version 450
+
+// Blends the blurred half-resolution scene over the sharp one by each pixel's circle of
+// confusion, before the image is encoded for the display.
+
+layout(set = 0, binding = 0) uniform sampler2D blurredTexture;
+layout(set = 0, binding = 1) uniform sampler2D depthTexture;
+
+layout(push_constant) uniform DofParams {
+    mat4 inverseProj;
+    // x: focus distance, y: CoC scale in half-res pixels, z: largest CoC in half-res pixels
+    vec4 lens;
+    // xy: one half-res texel along the blur axis, in UV
+    vec4 direction;
+} params;
+
+layout(location = 0) in vec2 fragTexCoord;
+layout(location = 0) out vec4 outColor;
+
+float circleOfConfusion(float depth) {
+    vec4 view = params.inverseProj * vec4(0.0, 0.0, depth, 1.0);
+    float distance = -view.z / view.w;
+    float coc = params.lens.y * (distance - params.lens.x) / distance;
+    return clamp(coc, -params.lens.z, params.lens.z);
+}
+
+void main() {
+    float depth = texelFetch(depthTexture, ivec2(gl_FragCoord.xy), 0).r;
+    vec4 blurred = texture(blurredTexture, fragTexCoord);
+
+    // Foreground blur spreads over sharp pixels next to it.
+    float radius = max(abs(circleOfConfusion(depth)), -min(blurred.a, 0.0));
+    outColor = vec4(blurred.rgb, smoothstep(0.5, 1.5, radius));
+}
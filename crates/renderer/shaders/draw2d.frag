@@ -0,0 +1,13 @@
+#version 450
+
+layout(location = 0) in vec2 fragUv;
+layout(location = 1) in vec4 fragColor;
+
+// A white texel for untextured shapes.
+layout(set = 0, binding = 0) uniform sampler2D shapeTexture;
+
+layout(location = 0) out vec4 outColor;
+
+void main() {
+    outColor = texture(shapeTexture, fragUv) * fragColor;
+}
@@ -0,0 +1,20 @@
+#version 450
+
+// Immediate-mode 2D shapes. Positions arrive in window pixels, origin top-left.
+layout(location = 0) in vec2 inPosition;
+layout(location = 1) in vec2 inUv;
+layout(location = 2) in vec4 inColor;
+
+layout(location = 0) out vec2 fragUv;
+layout(location = 1) out vec4 fragColor;
+
+layout(push_constant) uniform Draw2DPushConstants {
+    vec2 viewportSize;
+} pc;
+
+void main() {
+    // Vulkan clip space already has +y pointing down.
+    gl_Position = vec4(inPosition / pc.viewportSize * 2.0 - 1.0, 0.0, 1.0);
+    fragUv = inUv;
+    fragColor = inColor;
+}
@@ -4,6 +4,44 @@
 layout(set = 0, binding = 1) uniform sampler2D albedoTexture;
 layout(set = 0, binding = 2) uniform sampler2D normalTexture;
 layout(set = 0, binding = 3) uniform sampler2D depthTexture;
+layout(set = 0, binding = 14) uniform sampler2D emissiveTexture;
+// Equirectangular panorama behind all geometry
+layout(set = 0, binding = 15) uniform sampler2D skyboxTexture;
+// Skybox prefiltered by the image-based lighting compute passes
+layout(set = 0, binding = 16) uniform samplerCube irradianceMap;
+layout(set = 0, binding = 17) uniform samplerCube specularMap;
+layout(set = 0, binding = 18) uniform sampler2D brdfLut;
+// Prefiltered reflection probes, like specularMap. Slots past the probe count are unused.
+#define MAX_REFLECTION_PROBES 4
+layout(set = 0, binding = 19) uniform samplerCube reflectionProbe0;
+layout(set = 0, binding = 20) uniform samplerCube reflectionProbe1;
+layout(set = 0, binding = 21) uniform samplerCube reflectionProbe2;
+layout(set = 0, binding = 22) uniform samplerCube reflectionProbe3;
+
+layout(std140, set = 0, binding = 23) uniform ReflectionProbes {
+    // xyz: capture position, w: intensity
+    vec4 positionIntensity[MAX_REFLECTION_PROBES];
+    // xyz: box minimum, w: blend distance
+    vec4 boxMin[MAX_REFLECTION_PROBES];
+    // xyz: box maximum
+    vec4 boxMax[MAX_REFLECTION_PROBES];
+    // x: probe count
+    vec4 params;
+} probes;
+
+#define MAX_AREA_LIGHTS 16
+layout(std140, set = 0, binding = 24) uniform AreaLights {
+    // xyz: center, w: range
+    vec4 centerRange[MAX_AREA_LIGHTS];
+    // xyz: center to the middle of the right edge, w: 1 when two-sided
+    vec4 halfRight[MAX_AREA_LIGHTS];
+    // xyz: center to the middle of the top edge
+    vec4 halfUp[MAX_AREA_LIGHTS];
+    // rgb: color, w: intensity
+    vec4 colorIntensity[MAX_AREA_LIGHTS];
+    // x: light count
+    vec4 params;
+} areaLights;
 
 // Shadow pass depths
 // TODO: Replace with single uniform
@@ -12,6 +50,12 @@ layout(set = 0, binding = 5) uniform sampler2DShadow shadowMapCascade1;
 layout(set = 0, binding = 6) uniform sampler2DShadow shadowMapCascade2;
 layout(set = 0, binding = 9) uniform sampler2DShadow shadowMapCascade3;
 
+// Raw cascade depths for the PCSS blocker search
+layout(set = 0, binding = 10) uniform sampler2D shadowDepthCascade0;
+layout(set = 0, binding = 11) uniform sampler2D shadowDepthCascade1;
+layout(set = 0, binding = 12) uniform sampler2D shadowDepthCascade2;
+layout(set = 0, binding = 13) uniform sampler2D shadowDepthCascade3;
+
 // Directionallight cascade view projections
 layout(std140, set = 0, binding = 7) uniform Cascade {
     mat4[4] cascadeViewProjMat;
@@ -29,11 +73,66 @@ layout(std140, set = 0, binding = 0) uniform Lighting {
     vec4 lightColor;
     vec4 ambiantLight;
     vec4 cascadeDepths;
+    vec4 cascadeResolutions;
+    // x: active cascade count, y: unused, z: cascade blend fraction
+    vec4 shadowParams;
+    // x: receiver bias, y: normal offset in texels, z: PCSS light size, w: PCSS enabled
+    vec4 shadowBias;
+    // rgb: fog color, w: fog mode (0 off, 1 linear, 2 exponential, 3 exponential squared)
+    vec4 fogColor;
+    // x: density, y: linear start, z: linear end
+    vec4 fogParams;
+    // x: 1 when a skybox is bound, y: last mip of the specular map
+    vec4 skyParams;
 } lighting;
 
+// Half-width of the PCF kernel in texels. Set per pipeline so the kernel loops unroll.
+layout(constant_id = 0) const int PCF_RADIUS = 1;
+
+// Clustered point lights, written by light_clusters.comp
+#define MAX_LIGHTS_PER_CLUSTER 63
+
+layout(std140, set = 1, binding = 0) uniform ClusterParams {
+    mat4 view;
+    mat4 inverseProj;
+    uint gridX;
+    uint gridY;
+    uint gridZ;
+    uint lightCount;
+    // x: near, y: far, z: slice scale, w: slice bias
+    vec4 depthSlicing;
+    // x: light count heatmap enabled
+    vec4 debug;
+} clusters;
+
+struct PointLight {
+    // xyz: world position, w: range
+    vec4 positionRange;
+    // rgb: color, w: intensity
+    vec4 colorIntensity;
+};
+
+layout(std430, set = 1, binding = 1) readonly buffer Lights {
+    PointLight lights[];
+};
+
+// Per cluster: light count, then MAX_LIGHTS_PER_CLUSTER light indices.
+layout(std430, set = 1, binding = 2) readonly buffer ClusterLights {
+    uint clusterLights[];
+};
+
 layout(location = 0) in vec2 fragTexCoord;
 layout(location = 0) out vec4 fragColor;
 
+// Inverse of the geometry pass octahedral encoding.
+vec3 octDecode(vec2 f) {
+    vec3 n = vec3(f, 1.0 - abs(f.x) - abs(f.y));
+    float t = max(-n.z, 0.0);
+    n.x += n.x >= 0.0 ? -t : t;
+    n.y += n.y >= 0.0 ? -t : t;
+    return normalize(n);
+}
+
 vec3 reconstructWorldPosition(vec2 fragTexCoord, float depth) {
     vec4 ndcPos = vec4(fragTexCoord * 2.0 - vec2(1.0), depth, 1.0);
     vec4 viewSpacePos = inverse(ubo.proj) * ndcPos;
@@ -42,18 +141,139 @@ vec3 reconstructWorldPosition(vec2 fragTexCoord, float depth) {
     return worldPos.xyz;
 }
 
-float pcfSample(sampler2DShadow shadowMap, vec2 uv, float compareZ) {
+// Skybox texel along the view ray through `fragTexCoord`.
+vec3 skyColor(vec2 fragTexCoord) {
+    vec3 cameraPos = inverse(ubo.view)[3].xyz;
+    vec3 dir = normalize(reconstructWorldPosition(fragTexCoord, 1.0) - cameraPos);
+    const float PI = 3.14159265;
+    vec2 uv = vec2(atan(dir.z, dir.x) / (2.0 * PI) + 0.5, acos(clamp(dir.y, -1.0, 1.0)) / PI);
+    return texture(skyboxTexture, uv).rgb;
+}
+
+vec3 sampleReflectionProbe(int index, vec3 dir, float lod) {
+    if (index == 0) {
+        return textureLod(reflectionProbe0, dir, lod).rgb;
+    } else if (index == 1) {
+        return textureLod(reflectionProbe1, dir, lod).rgb;
+    } else if (index == 2) {
+        return textureLod(reflectionProbe2, dir, lod).rgb;
+    }
+    return textureLod(reflectionProbe3, dir, lod).rgb;
+}
+
+// Reflections of the probes whose box contains `worldPos` along `reflected`, box
+// projected: the reflected ray is traced to the box's walls and the probe sampled towards
+// that point from its capture position. rgb: weighted reflections, a: total weight, at
+// most 1. Earlier probes take precedence where boxes overlap.
+vec4 probeReflections(vec3 worldPos, vec3 reflected, float lod) {
+    vec4 result = vec4(0.0);
+    int count = int(probes.params.x);
+    for (int i = 0; i < count; ++i) {
+        vec3 boxMin = probes.boxMin[i].xyz;
+        vec3 boxMax = probes.boxMax[i].xyz;
+        vec3 inside = min(worldPos - boxMin, boxMax - worldPos);
+        float edgeDistance = min(inside.x, min(inside.y, inside.z));
+        if (edgeDistance <= 0.0) {
+            continue;
+        }
+        float weight = clamp(edgeDistance / max(probes.boxMin[i].w, 1e-4), 0.0, 1.0);
+        weight = min(weight, 1.0 - result.a);
+
+        vec3 exits = max((boxMax - worldPos) / reflected, (boxMin - worldPos) / reflected);
+        float t = min(exits.x, min(exits.y, exits.z));
+        vec3 dir = worldPos + reflected * t - probes.positionIntensity[i].xyz;
+        result.rgb += sampleReflectionProbe(i, dir, lod) * probes.positionIntensity[i].w * weight;
+        result.a += weight;
+    }
+    return result;
+}
+
+// Ambient light reflected by the surface, including its albedo: diffuse irradiance plus
+// split-sum specular reflections. Both come from the prefiltered skybox if there is one,
+// scaled by `ambientScale`; without one the diffuse term is `ambientScale` alone. The
+// reflection probes around `worldPos` replace the skybox's reflections; they captured the
+// lit scene, so only `occlusion` darkens them.
+vec3 ambientLighting(vec3 albedo, vec3 normal, vec3 viewDir, float roughness, float metallic,
+                     vec3 worldPos, vec3 ambientScale, float occlusion) {
+    bool skybox = lighting.skyParams.x > 0.5;
+    float nDotV = max(dot(normal, viewDir), 1e-4);
+    vec3 f0 = mix(vec3(0.04), albedo, metallic);
+    // Schlick's Fresnel, damped on rough surfaces
+    vec3 fresnel = f0 + (max(vec3(1.0 - roughness), f0) - f0) * pow(1.0 - nDotV, 5.0);
+    vec3 kD = (1.0 - fresnel) * (1.0 - metallic);
+
+    vec3 irradiance = skybox ? texture(irradianceMap, normal).rgb * kD : vec3(1.0);
+    vec3 diffuse = irradiance * albedo * ambientScale;
+    vec3 reflected = reflect(-viewDir, normal);
+    float lod = roughness * lighting.skyParams.y;
+    vec3 prefiltered = skybox ? textureLod(specularMap, reflected, lod).rgb * ambientScale
+                              : vec3(0.0);
+    vec4 local = probeReflections(worldPos, reflected, lod);
+    prefiltered = prefiltered * (1.0 - local.a) + local.rgb * occlusion;
+    vec2 brdf = texture(brdfLut, vec2(nDotV, roughness)).rg;
+    vec3 specular = prefiltered * (fresnel * brdf.x + brdf.y);
+    return diffuse + specular;
+}
+
+// Blends `color` towards the fog color over `distance` from the camera.
+vec3 applyFog(vec3 color, float distance) {
+    int mode = int(lighting.fogColor.w);
+    float density = lighting.fogParams.x;
+    float visibility;
+    if (mode == 1) {
+        float start = lighting.fogParams.y;
+        float end = lighting.fogParams.z;
+        visibility = clamp((end - distance) / max(end - start, 1e-4), 0.0, 1.0);
+    } else if (mode == 2) {
+        visibility = exp(-density * distance);
+    } else if (mode == 3) {
+        float d = density * distance;
+        visibility = exp(-d * d);
+    } else {
+        return color;
+    }
+    return mix(lighting.fogColor.rgb, color, visibility);
+}
+
+// Samples a (2r+1)^2 kernel with `spacing` UV units between taps.
+float pcfSample(sampler2DShadow shadowMap, vec2 uv, float compareZ, float spacing) {
     float shadow = 0.0;
-    shadow += textureOffset(shadowMap, vec3(uv, compareZ), ivec2(-1, -1));
-    shadow += textureOffset(shadowMap, vec3(uv, compareZ), ivec2( 0, -1));
-    shadow += textureOffset(shadowMap, vec3(uv, compareZ), ivec2( 1, -1));
-    shadow += textureOffset(shadowMap, vec3(uv, compareZ), ivec2(-1,  0));
-    shadow += textureOffset(shadowMap, vec3(uv, compareZ), ivec2( 0,  0));
-    shadow += textureOffset(shadowMap, vec3(uv, compareZ), ivec2( 1,  0));
-    shadow += textureOffset(shadowMap, vec3(uv, compareZ), ivec2(-1,  1));
-    shadow += textureOffset(shadowMap, vec3(uv, compareZ), ivec2( 0,  1));
-    shadow += textureOffset(shadowMap, vec3(uv, compareZ), ivec2( 1,  1));
-    return shadow / 9.0;
+    for (int x = -PCF_RADIUS; x <= PCF_RADIUS; ++x) {
+        for (int y = -PCF_RADIUS; y <= PCF_RADIUS; ++y) {
+            shadow += texture(shadowMap, vec3(uv + vec2(x, y) * spacing, compareZ));
+        }
+    }
+    float kernelWidth = float(2 * PCF_RADIUS + 1);
+    return shadow / (kernelWidth * kernelWidth);
+}
+
+// Average depth of occluders within `searchRadius`, or -1.0 if nothing blocks the receiver.
+float findBlockerDepth(sampler2D depthMap, vec2 uv, float receiverZ, float searchRadius) {
+    const int SEARCH_RADIUS = 2;
+    float step = searchRadius / float(SEARCH_RADIUS);
+    float blockerSum = 0.0;
+    float blockerCount = 0.0;
+    for (int x = -SEARCH_RADIUS; x <= SEARCH_RADIUS; ++x) {
+        for (int y = -SEARCH_RADIUS; y <= SEARCH_RADIUS; ++y) {
+            float depth = texture(depthMap, uv + vec2(x, y) * step).r;
+            if (depth < receiverZ) {
+                blockerSum += depth;
+                blockerCount += 1.0;
+            }
+        }
+    }
+    return blockerCount > 0.0 ? blockerSum / blockerCount : -1.0;
+}
+
+float blockerDepth(int cascadeIndex, vec2 uv, float receiverZ, float searchRadius) {
+    if (cascadeIndex == 0) {
+        return findBlockerDepth(shadowDepthCascade0, uv, receiverZ, searchRadius);
+    } else if (cascadeIndex == 1) {
+        return findBlockerDepth(shadowDepthCascade1, uv, receiverZ, searchRadius);
+    } else if (cascadeIndex == 2) {
+        return findBlockerDepth(shadowDepthCascade2, uv, receiverZ, searchRadius);
+    }
+    return findBlockerDepth(shadowDepthCascade3, uv, receiverZ, searchRadius);
 }
 
 float calculateShadow(int cascadeIndex, vec3 worldPos, vec3 normal) {
@@ -64,9 +284,9 @@ float calculateShadow(int cascadeIndex, vec3 worldPos, vec3 normal) {
     float cosTheta = max(dot(normal, lightDir), 0.0);
     float sinTheta = sqrt(1.0 - cosTheta * cosTheta);
     float depthScale = abs(lightViewProj[2][2]);
-    float cascadeRes = (cascadeIndex < 2) ? 2048.0 : 1024.0;
+    float cascadeRes = lighting.cascadeResolutions[cascadeIndex];
     float texelWorldSize = 1.0 / (cascadeRes * depthScale);
-    vec3 offsetPos = worldPos + normal * texelWorldSize * max(sinTheta, 0.1) * 2.0;
+    vec3 offsetPos = worldPos + normal * texelWorldSize * max(sinTheta, 0.1) * lighting.shadowBias.y;
 
     // Convert offset position to light space
     vec4 lightSpacePos = lightViewProj * vec4(offsetPos, 1.0);
@@ -80,31 +300,136 @@ float calculateShadow(int cascadeIndex, vec3 worldPos, vec3 normal) {
         return 0.0;
     }
 
-    float bias = 0.002 * depthScale;
+    float bias = lighting.shadowBias.x * depthScale;
 
     float z = lightSpacePos.z - bias;
+    float texelSize = 1.0 / cascadeRes;
+    float spacing = texelSize;
+
+    // PCSS: widen the kernel with the receiver-blocker distance for contact hardening
+    if (lighting.shadowBias.w > 0.5) {
+        float lightSize = lighting.shadowBias.z;
+        float blocker = blockerDepth(cascadeIndex, uv, z, lightSize);
+        if (blocker < 0.0) {
+            return 0.0;
+        }
+        float penumbra = (z - blocker) / max(depthScale, 1e-4) * lightSize;
+        float radius = max(float(PCF_RADIUS), 1.0);
+        spacing = max(texelSize, penumbra / radius);
+    }
+
     float shadow;
     if (cascadeIndex == 0) {
-        shadow = pcfSample(shadowMapCascade0, uv, z);
+        shadow = pcfSample(shadowMapCascade0, uv, z, spacing);
     } else if (cascadeIndex == 1) {
-        shadow = pcfSample(shadowMapCascade1, uv, z);
+        shadow = pcfSample(shadowMapCascade1, uv, z, spacing);
     } else if (cascadeIndex == 2) {
-        shadow = pcfSample(shadowMapCascade2, uv, z);
+        shadow = pcfSample(shadowMapCascade2, uv, z, spacing);
     } else {
-        shadow = pcfSample(shadowMapCascade3, uv, z);
+        shadow = pcfSample(shadowMapCascade3, uv, z, spacing);
     }
 
     float shadowFade = smoothstep(0.0, 0.1, cosTheta);
     return mix(1.0, 1.0 - shadow, shadowFade);
 }
 
+// Offset of the light list of the cluster containing this fragment.
+uint clusterBase(vec2 uv, float viewDepth) {
+    uvec2 tile = min(uvec2(uv * vec2(clusters.gridX, clusters.gridY)),
+                     uvec2(clusters.gridX - 1, clusters.gridY - 1));
+    float slice = log(viewDepth) * clusters.depthSlicing.z + clusters.depthSlicing.w;
+    uint z = uint(clamp(slice, 0.0, float(clusters.gridZ - 1)));
+    return ((z * clusters.gridY + tile.y) * clusters.gridX + tile.x) * (MAX_LIGHTS_PER_CLUSTER + 1);
+}
+
+// Diffuse light from the point lights in the cluster starting at `base`.
+vec3 pointLighting(uint base, vec3 worldPos, vec3 normal) {
+    vec3 result = vec3(0.0);
+    uint count = clusterLights[base];
+    for (uint i = 0; i < count; ++i) {
+        PointLight light = lights[clusterLights[base + 1 + i]];
+        vec3 toLight = light.positionRange.xyz - worldPos;
+        float distance = length(toLight);
+        float range = light.positionRange.w;
+        // Inverse square, windowed to reach zero at the light's range.
+        float window = clamp(1.0 - pow(distance / range, 4.0), 0.0, 1.0);
+        float attenuation = window * window / (distance * distance + 1.0);
+        float diff = max(dot(normal, toLight / max(distance, 1e-4)), 0.0);
+        result += light.colorIntensity.rgb * light.colorIntensity.w * diff * attenuation;
+    }
+    return result;
+}
+
+// Form factor of the edge from `a` to `b`, both unit directions from the shaded point.
+vec3 edgeFormFactor(vec3 a, vec3 b) {
+    float c = clamp(dot(a, b), -1.0, 1.0);
+    float s = sqrt(1.0 - c * c);
+    // theta / sin(theta) keeps short edges stable where the cross product vanishes.
+    return cross(a, b) * (s > 1e-4 ? acos(c) / s : 1.0);
+}
+
+// Diffuse light from the rectangular area lights. This is the cosine LTC: each
+// rectangle's irradiance comes exactly from its vector form factor, approximating
+// only the part that dips below the surface's horizon.
+vec3 areaLighting(vec3 worldPos, vec3 normal) {
+    const float TWO_PI = 6.28318531;
+    vec3 result = vec3(0.0);
+    uint count = uint(areaLights.params.x);
+    for (uint i = 0; i < count; ++i) {
+        vec3 center = areaLights.centerRange[i].xyz;
+        vec3 right = areaLights.halfRight[i].xyz;
+        vec3 up = areaLights.halfUp[i].xyz;
+        // Lights shine along their forward axis, -Z of the rectangle's plane.
+        vec3 forward = normalize(cross(up, right));
+        vec3 fromCenter = worldPos - center;
+        bool twoSided = areaLights.halfRight[i].w > 0.5;
+        if (!twoSided && dot(fromCenter, forward) <= 0.0) {
+            continue;
+        }
+
+        vec3 p0 = normalize(center - right - up - worldPos);
+        vec3 p1 = normalize(center - right + up - worldPos);
+        vec3 p2 = normalize(center + right + up - worldPos);
+        vec3 p3 = normalize(center + right - up - worldPos);
+        vec3 formFactor = edgeFormFactor(p0, p1) + edgeFormFactor(p1, p2)
+                        + edgeFormFactor(p2, p3) + edgeFormFactor(p3, p0);
+        // Point the vector at the light whichever way the corners wind from here.
+        if (dot(formFactor, fromCenter) > 0.0) {
+            formFactor = -formFactor;
+        }
+        float irradiance = max(dot(formFactor, normal), 0.0) / TWO_PI;
+
+        // Windowed like the point lights so each light has a bounded reach.
+        float range = areaLights.centerRange[i].w;
+        float window = clamp(1.0 - pow(length(fromCenter) / range, 4.0), 0.0, 1.0);
+        vec4 colorIntensity = areaLights.colorIntensity[i];
+        result += colorIntensity.rgb * colorIntensity.w * irradiance * window * window;
+    }
+    return result;
+}
+
+// Blue through green to red as the cluster fills up.
+vec3 heatmap(float t) {
+    return t < 0.5 ? mix(vec3(0.0, 0.0, 1.0), vec3(0.0, 1.0, 0.0), t * 2.0)
+                   : mix(vec3(0.0, 1.0, 0.0), vec3(1.0, 0.0, 0.0), t * 2.0 - 1.0);
+}
+
 void main() {
-    vec3 albedo = texture(albedoTexture, fragTexCoord).rgb;
-    vec3 normal = texture(normalTexture, fragTexCoord).rgb;
+    vec4 albedoOcclusion = texture(albedoTexture, fragTexCoord);
+    vec3 albedo = albedoOcclusion.rgb;
+    // Material and baked ambient occlusion; only darkens the ambient term.
+    float occlusion = albedoOcclusion.a;
+    vec4 normalRoughnessMetallic = texture(normalTexture, fragTexCoord);
+    vec3 normal = octDecode(normalRoughnessMetallic.xy);
     float depth = texture(depthTexture, fragTexCoord).r;
 
-    if (depth == 1)
-        discard;
+    // Background: the skybox if there is one, otherwise the clear color stays.
+    if (depth == 1) {
+        if (lighting.skyParams.x < 0.5)
+            discard;
+        fragColor = vec4(skyColor(fragTexCoord), 1.0);
+        return;
+    }
 
     vec4 ndcPos = vec4(fragTexCoord * 2.0 - vec2(1.0), depth, 1.0);
     vec4 viewSpacePos = inverse(ubo.proj) * ndcPos;
@@ -115,8 +440,9 @@ void main() {
     vec3 viewPos =  (ubo.view * vec4(worldPos, 1.0)).xyz;
     float viewDepth = viewPos.z;
 
+    int cascadeCount = int(lighting.shadowParams.x);
     int cascadeIndex = 0;
-    for (int i = 0; i < 4 - 1; ++i) {
+    for (int i = 0; i < cascadeCount - 1; ++i) {
         if (viewDepth < -lighting.cascadeDepths[i]) {
             cascadeIndex = i + 1;
         }
@@ -130,16 +456,46 @@ void main() {
     vec3 lightColor = lighting.lightColor.xyz * cascadeColor;
     vec3 diffuse = diff * lightColor * lighting.lightColor.w;
 
-    // apply shadow to diffuse
-    float shadow = calculateShadow(cascadeIndex, worldPos, normal);
+    vec4 emissive = texture(emissiveTexture, fragTexCoord);
+
+    // apply shadow to diffuse; without cascades shadow maps are off
+    float shadow = cascadeCount > 0 ? calculateShadow(cascadeIndex, worldPos, normal) : 0.0;
+
+    // Blend into the next cascade near the split to hide the resolution seam
+    float blendFraction = lighting.shadowParams.z;
+    if (blendFraction > 0.0 && cascadeIndex < cascadeCount - 1) {
+        float splitFar = lighting.cascadeDepths[cascadeIndex];
+        float splitNear = (cascadeIndex == 0) ? 0.0 : lighting.cascadeDepths[cascadeIndex - 1];
+        float blendStart = splitFar - (splitFar - splitNear) * blendFraction;
+        float fragDepth = -viewDepth;
+        if (fragDepth > blendStart) {
+            float t = (fragDepth - blendStart) / (splitFar - blendStart);
+            shadow = mix(shadow, calculateShadow(cascadeIndex + 1, worldPos, normal), t);
+        }
+    }
+    // Emissive alpha marks meshes that do not receive shadows.
+    if (emissive.a > 0.5)
+        shadow = 0.0;
     diffuse = diffuse * (1.0 - shadow);
 
-    // add ambient to diffuse
-    vec3 ambient = lighting.ambiantLight.rgb * lighting.ambiantLight.w;
-    vec3 lightingResult = ambient + diffuse;
+    uint base = clusterBase(fragTexCoord, -viewDepth);
+    diffuse += pointLighting(base, worldPos, normal);
+    diffuse += areaLighting(worldPos, normal);
 
-    // combine with albedo
-    vec3 finalColor = albedo * lightingResult;
+    // Ambient: the skybox's reflected light if there is one, otherwise a constant term.
+    // Either way scaled by the environment's ambient color and intensity.
+    vec3 ambientScale = lighting.ambiantLight.rgb * lighting.ambiantLight.w * occlusion;
+    vec3 finalColor = albedo * diffuse;
+    vec3 viewDir = normalize(inverse(ubo.view)[3].xyz - worldPos);
+    finalColor += ambientLighting(albedo, normal, viewDir, normalRoughnessMetallic.z,
+                                  normalRoughnessMetallic.w, worldPos, ambientScale, occlusion);
+    finalColor += emissive.rgb;
+    finalColor = applyFog(finalColor, length(viewPos));
+
+    if (clusters.debug.x > 0.5) {
+        float fill = float(clusterLights[base]) / float(MAX_LIGHTS_PER_CLUSTER);
+        finalColor = clusterLights[base] == 0 ? finalColor * 0.25 : heatmap(sqrt(fill));
+    }
 
     fragColor = vec4(finalColor, 1.0);
 }
\ No newline at end of file
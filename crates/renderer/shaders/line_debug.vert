@@ -2,7 +2,6 @@
 
 layout(location = 0) in vec3 inPosition;
 layout(location = 1) in vec3 inColor;
-layout(location = 2) in vec2 inTexCoord;
 
 layout(location = 0) out vec3 fragColor;
 
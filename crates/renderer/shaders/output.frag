@@ -0,0 +1,51 @@
+#version 450
+
+// Encodes the linear scene for an HDR swapchain. SDR output skips this pass.
+
+layout(set = 0, binding = 0) uniform sampler2D sceneTexture;
+
+layout(push_constant) uniform OutputParams {
+    uint mode;            // 1: HDR10 (PQ, Rec. 2020), 2: scRGB (linear Rec. 709)
+    float paperWhiteNits; // Luminance of scene value 1.0
+    float peakNits;       // Brightest luminance the display is sent
+} params;
+
+layout(location = 0) in vec2 fragTexCoord;
+layout(location = 0) out vec4 outColor;
+
+const uint MODE_HDR10 = 1;
+
+// Column-major: converts linear Rec. 709 to linear Rec. 2020.
+const mat3 REC709_TO_REC2020 = mat3(
+    0.6274, 0.0691, 0.0164,
+    0.3293, 0.9195, 0.0880,
+    0.0433, 0.0114, 0.8956
+);
+
+// SMPTE ST 2084 inverse EOTF; `normalized` is luminance / 10000 nits.
+vec3 pqEncode(vec3 normalized) {
+    const float m1 = 0.1593017578125;
+    const float m2 = 78.84375;
+    const float c1 = 0.8359375;
+    const float c2 = 18.8515625;
+    const float c3 = 18.6875;
+    vec3 y = pow(clamp(normalized, 0.0, 1.0), vec3(m1));
+    return pow((c1 + c2 * y) / (1.0 + c3 * y), vec3(m2));
+}
+
+void main() {
+    vec3 color = max(texture(sceneTexture, fragTexCoord).rgb, vec3(0.0));
+    vec3 nits = color * params.paperWhiteNits;
+
+    // Scale down as a whole rather than per channel, so highlights keep their hue.
+    float brightest = max(nits.r, max(nits.g, nits.b));
+    if (brightest > params.peakNits) {
+        nits *= params.peakNits / brightest;
+    }
+
+    if (params.mode == MODE_HDR10) {
+        outColor = vec4(pqEncode(REC709_TO_REC2020 * nits / 10000.0), 1.0);
+    } else {
+        outColor = vec4(nits / 80.0, 1.0);
+    }
+}
@@ -0,0 +1,53 @@
+#version 450
+
+// Camera-facing quads, six vertices per alive particle. Reads the particle pool and the
+// alive list the simulation wrote, or the CPU path's uploads.
+
+struct Particle {
+    // xyz: world-space position, w: age in seconds
+    vec4 positionAge;
+    // xyz: velocity, w: lifetime in seconds
+    vec4 velocityLife;
+};
+
+layout(std430, set = 0, binding = 0) readonly buffer Particles {
+    Particle particles[];
+};
+
+layout(std430, set = 0, binding = 1) readonly buffer AliveList {
+    uint alive[];
+};
+
+layout(set = 0, binding = 4) uniform Camera {
+    mat4 view;
+    mat4 proj;
+} camera;
+
+layout(push_constant) uniform Push {
+    vec4 startColor;
+    vec4 endColor;
+    // x: size at birth, y: size at death
+    vec2 size;
+    // First entry of the alive list to draw
+    uint aliveOffset;
+} push;
+
+layout(location = 0) out vec4 fragColor;
+layout(location = 1) out vec2 fragCorner;
+
+const vec2 CORNERS[6] = vec2[](
+    vec2(-1.0, -1.0), vec2(1.0, -1.0), vec2(1.0, 1.0),
+    vec2(-1.0, -1.0), vec2(1.0, 1.0), vec2(-1.0, 1.0)
+);
+
+void main() {
+    Particle p = particles[alive[push.aliveOffset + gl_VertexIndex / 6]];
+    float t = clamp(p.positionAge.w / p.velocityLife.w, 0.0, 1.0);
+    vec2 corner = CORNERS[gl_VertexIndex % 6];
+
+    vec4 viewPosition = camera.view * vec4(p.positionAge.xyz, 1.0);
+    viewPosition.xy += corner * 0.5 * mix(push.size.x, push.size.y, t);
+    gl_Position = camera.proj * viewPosition;
+    fragColor = mix(push.startColor, push.endColor, t);
+    fragCorner = corner;
+}
@@ -3,14 +3,48 @@
 
 #extension GL_ARB_separate_shader_objects: enable
 
-layout(location = 0) out vec4 outColor;
+// G-buffer layout:
+//   0: RGBA8   albedo.rgb, occlusion
+//   1: RGBA16F octahedral normal.xy, roughness, metallic
+//   2: RGBA16F emissive.rgb, 1 where the surface ignores shadows
+//   3: R32UI   entity index plus one, only bound with GPU picking enabled
+// World position is reconstructed from depth in the lighting pass.
+layout(location = 0) out vec4 outAlbedo;
 layout(location = 1) out vec4 outNormal;
-layout(location = 2) out vec4 outOrm;
+layout(location = 2) out vec4 outEmissive;
+layout(location = 3) out uint outEntityId;
 
-layout(location = 0) in vec3 fragColor;
+// Vertex color, white for meshes without one.
+layout(location = 0) in vec4 fragColor;
 layout(location = 1) in vec2 fragTexCoord;
 layout(location = 3) in vec3 inNormal;
 layout(location = 2) in vec3 inPos;
+layout(location = 4) flat in vec4 inTint;
+// xy: UV offset (already applied to fragTexCoord), z: emissive strength,
+// w: lightmap intensity (negative: no lightmap)
+layout(location = 5) flat in vec4 inMaterialParams;
+// xyz: world-space tangent, w: bitangent sign
+layout(location = 6) in vec4 inTangent;
+// Lightmap UVs, already remapped into the mesh's lightmap region.
+layout(location = 7) in vec2 fragTexCoord1;
+layout(location = 8) flat in uint inEntityId;
+layout(location = 9) flat in uint inInstanceFlags;
+layout(location = 10) flat in uint inInstanceSlot;
+
+// Match INSTANCE_* in frame_data.rs
+#define INSTANCE_NO_RECEIVE_SHADOWS 1u
+#define INSTANCE_PROBE_LIT 2u
+
+// Light probe lighting per transform slot, matching InstanceProbe in frame_data.rs.
+// rgb: order-2 spherical harmonics of the baked light, convolved with the cosine lobe.
+// w of the first coefficient: sky visibility. Only read for INSTANCE_PROBE_LIT.
+struct InstanceProbe {
+    vec4 sh[9];
+};
+
+layout(std430, set = 0, binding = 4) readonly buffer Probes {
+    InstanceProbe probes[];
+};
 
 #ifdef HAS_COLOR_TEXTURE
 layout(set = 1, binding = 0) uniform sampler2D baseColor;
@@ -24,6 +58,10 @@ layout(set = 1, binding = 1) uniform sampler2D normal;
 layout(set = 1, binding = 2) uniform sampler2D orm;
 #endif
 
+// Baked lighting: rgb indirect light, a ambient occlusion. Only sampled when
+// inMaterialParams.w (the lightmap intensity) is not negative.
+layout(set = 2, binding = 0) uniform sampler2D lightmap;
+
 layout(push_constant) uniform MaterialConstants {
     layout(offset = 16) vec4 baseColor;
     vec4 normal;
@@ -35,22 +73,73 @@ layout(push_constant) uniform MaterialConstants {
 
 
 
+// Baked light a probe gives a surface facing `n`. The basis matches light_probe.rs.
+vec3 probeLight(InstanceProbe probe, vec3 n) {
+    vec3 light = probe.sh[0].rgb * 0.282095
+        + probe.sh[1].rgb * (0.488603 * n.y)
+        + probe.sh[2].rgb * (0.488603 * n.z)
+        + probe.sh[3].rgb * (0.488603 * n.x)
+        + probe.sh[4].rgb * (1.092548 * n.x * n.y)
+        + probe.sh[5].rgb * (1.092548 * n.y * n.z)
+        + probe.sh[6].rgb * (0.315392 * (3.0 * n.z * n.z - 1.0))
+        + probe.sh[7].rgb * (1.092548 * n.x * n.z)
+        + probe.sh[8].rgb * (0.546274 * (n.x * n.x - n.y * n.y));
+    return max(light, vec3(0.0));
+}
+
+// Maps a unit vector onto the [-1, 1] square of an octahedron.
+vec2 octEncode(vec3 n) {
+    n /= abs(n.x) + abs(n.y) + abs(n.z);
+    if (n.z < 0.0) {
+        vec2 signs = vec2(n.x >= 0.0 ? 1.0 : -1.0, n.y >= 0.0 ? 1.0 : -1.0);
+        n.xy = (1.0 - abs(n.yx)) * signs;
+    }
+    return n.xy;
+}
+
 void main() {
     #ifdef HAS_COLOR_TEXTURE
-    outColor = texture(baseColor, fragTexCoord);
+    vec3 albedo = texture(baseColor, fragTexCoord).rgb;
     #else
-    outColor = pc.baseColor;
+    vec3 albedo = pc.baseColor.rgb;
     #endif
 
     #ifdef HAS_NORMAL_TEXTURE
-    outNormal = texture(normal, fragTexCoord);
+    // Tangent-space normal map; re-orthogonalize the interpolated frame before use.
+    // Only xy is read and z rebuilt, so two-channel BC5 normal maps work too.
+    vec2 tangentXY = texture(normal, fragTexCoord).rg * 2.0 - 1.0;
+    vec3 tangentNormal = vec3(tangentXY, sqrt(max(1.0 - dot(tangentXY, tangentXY), 0.0)));
+    vec3 N = normalize(inNormal);
+    vec3 T = normalize(inTangent.xyz - N * dot(N, inTangent.xyz));
+    vec3 B = cross(N, T) * inTangent.w;
+    vec3 n = mat3(T, B, N) * tangentNormal;
     #else
-    outNormal = vec4(inNormal, 0);
+    vec3 n = inNormal;
     #endif
 
     #ifdef HAS_ORM_TEXTURE
-    outOrm = texture(orm, fragTexCoord);
+    vec3 orm = texture(orm, fragTexCoord).rgb;
     #else
-    outOrm = vec4(pc.roughness, pc.metallic, pc.occlusion, pc.speccular);
+    vec3 orm = vec3(pc.occlusion, pc.roughness, pc.metallic);
     #endif
+
+    albedo *= inTint.rgb * fragColor.rgb;
+
+    float occlusion = orm.r;
+    vec3 bakedLight = vec3(0.0);
+    if (inMaterialParams.w >= 0.0) {
+        vec4 baked = texture(lightmap, fragTexCoord1);
+        occlusion *= baked.a;
+        bakedLight = baked.rgb * inMaterialParams.w;
+    } else if ((inInstanceFlags & INSTANCE_PROBE_LIT) != 0u) {
+        InstanceProbe probe = probes[inInstanceSlot];
+        occlusion *= probe.sh[0].w;
+        bakedLight = probeLight(probe, normalize(n));
+    }
+
+    outAlbedo = vec4(albedo, occlusion);
+    outNormal = vec4(octEncode(normalize(n)), orm.g, orm.b);
+    float shadowFree = (inInstanceFlags & INSTANCE_NO_RECEIVE_SHADOWS) != 0u ? 1.0 : 0.0;
+    outEmissive = vec4(albedo * (inMaterialParams.z + bakedLight), shadowFree);
+    outEntityId = inEntityId;
 }
\ No newline at end of file
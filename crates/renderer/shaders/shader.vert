@@ -6,38 +6,149 @@ layout(binding = 0) uniform UniformBufferObject {
     mat4 proj;
 } ubo;
 
-layout(std430, binding = 1) readonly buffer Transforms {
-    mat4 model[];
+// Per-instance data, one entry per transform slot.
+struct InstanceData {
+    mat4 model;
+    vec4 tint;
+    // xy: UV offset, z: emissive strength, w: lightmap intensity (negative: no lightmap)
+    vec4 materialParams;
+    // xy: scale, zw: offset of the lightmap UVs
+    vec4 lightmapScaleOffset;
+    // Entity index plus one, for the entity ID buffer
+    uint entityId;
+    // INSTANCE_* bits
+    uint flags;
+    // x: branch sway, y: leaf flutter of vegetation
+    vec2 vegetation;
 };
 
+layout(std430, binding = 1) readonly buffer Instances {
+    InstanceData instances[];
+};
+
+#ifdef HAS_WIND
+layout(binding = 3) uniform Wind {
+    // xyz: horizontal direction the wind blows towards, w: strength
+    vec4 directionStrength;
+    // x: time in seconds, y: gust strength, z: gust frequency, w: gust size
+    vec4 gust;
+} wind;
+#endif
+
+#ifdef HAS_SKINNING
+// Joint palettes of all skinned meshes; each mesh's starts at push.joint_offset.
+layout(std430, binding = 2) readonly buffer Joints {
+    mat4 joints[];
+};
+#endif
+
+// Direct draws push the transform slot and draw instance 0; GPU-culled indirect draws
+// push 0 and pass the slot as firstInstance.
 layout(push_constant) uniform Push {
     uint object_index;
+    uint joint_offset;
 } push;
 
 layout(location = 0) in vec3 inPosition;
-layout(location = 1) in vec3 inColor;
-layout(location = 2) in vec2 inTexCoord;
-layout(location = 3) in vec3 inNormal;
+layout(location = 1) in vec2 inTexCoord;
+layout(location = 2) in vec3 inNormal;
+// xyz: tangent, w: bitangent sign
+layout(location = 3) in vec4 inTangent;
+#ifdef HAS_VERTEX_EXTRAS
+// Optional second stream: second UV set and vertex color.
+layout(location = 4) in vec2 inTexCoord1;
+layout(location = 5) in vec4 inColor;
+#endif
+#ifdef HAS_SKINNING
+layout(location = 6) in uvec4 inJoints;
+layout(location = 7) in vec4 inWeights;
+#endif
 
-
-layout(location = 0) out vec3 fragColor;
+layout(location = 0) out vec4 fragColor;
 layout(location = 1) out vec2 fragTexCoord;
 layout(location = 2) out vec3 worldPos;
 layout(location = 3) out vec3 fragNormal;
+layout(location = 4) flat out vec4 fragTint;
+layout(location = 5) flat out vec4 fragMaterialParams;
+layout(location = 6) out vec4 fragTangent;
+layout(location = 7) out vec2 fragTexCoord1;
+layout(location = 8) flat out uint fragEntityId;
+layout(location = 9) flat out uint fragInstanceFlags;
+// Transform slot, indexing the light probe buffer
+layout(location = 10) flat out uint fragInstanceSlot;
 
 out gl_PerVertex {
     vec4 gl_Position;
 };
 
+#ifdef HAS_WIND
+float hash(vec2 p) {
+    return fract(sin(dot(p, vec2(127.1, 311.7))) * 43758.5453);
+}
+
+// Smooth value noise in [0, 1].
+float valueNoise(vec2 p) {
+    vec2 i = floor(p);
+    vec2 f = fract(p);
+    vec2 u = f * f * (3.0 - 2.0 * f);
+    return mix(mix(hash(i), hash(i + vec2(1.0, 0.0)), u.x),
+               mix(hash(i + vec2(0.0, 1.0)), hash(i + vec2(1.0, 1.0)), u.x), u.y);
+}
+
+// World-space displacement of a vegetation vertex. `root` is the instance's origin, so
+// a whole plant sees the same gust; the color channels weight branch sway (r) and leaf
+// flutter (g), with b offsetting each leaf's phase.
+vec3 windOffset(vec3 root, vec3 normal, vec4 weights, vec2 amount) {
+    float time = wind.gust.x;
+    vec3 direction = wind.directionStrength.xyz;
+    // Gusts are noise scrolled across the ground along the wind.
+    vec2 gustUv = (root.xz - direction.xz * time * wind.gust.z * wind.gust.w) / wind.gust.w;
+    float gust = 1.0 + wind.gust.y * (valueNoise(gustUv) * 2.0 - 1.0);
+    float strength = wind.directionStrength.w * gust;
+
+    float phase = dot(root, vec3(0.7, 0.0, 1.3));
+    float sway = strength * (1.0 + 0.2 * sin(time * 1.7 + phase));
+    vec3 branch = direction * sway * amount.x * weights.r;
+    float flutter = sin(time * 9.0 + phase + weights.b * 6.2832);
+    vec3 leaf = normal * flutter * strength * amount.y * weights.g;
+    return branch + leaf;
+}
+#endif
+
 void main() {
-    mat4 modelMat = model[push.object_index];
+    uint slot = push.object_index + gl_InstanceIndex;
+    InstanceData instance = instances[slot];
+    mat4 modelMat = instance.model;
+#ifdef HAS_SKINNING
+    modelMat = modelMat * (
+        inWeights.x * joints[push.joint_offset + inJoints.x] +
+        inWeights.y * joints[push.joint_offset + inJoints.y] +
+        inWeights.z * joints[push.joint_offset + inJoints.z] +
+        inWeights.w * joints[push.joint_offset + inJoints.w]);
+#endif
 
     mat3 normalMatrix = transpose(mat3(inverse(modelMat)));
     fragNormal = normalize(normalMatrix * inNormal);
+    fragTangent = vec4(normalize(mat3(modelMat) * inTangent.xyz), inTangent.w);
 
     vec4 worldPosition = modelMat * vec4(inPosition, 1.0);
+#ifdef HAS_WIND
+    worldPosition.xyz += windOffset(modelMat[3].xyz, fragNormal, inColor, instance.vegetation);
+#endif
     worldPos = worldPosition.xyz;
-    gl_Position = ubo.proj * ubo.view * modelMat * vec4(inPosition, 1.0);
+    gl_Position = ubo.proj * ubo.view * worldPosition;
+#ifdef HAS_VERTEX_EXTRAS
     fragColor = inColor;
-    fragTexCoord = inTexCoord;
+    vec2 lightmapUv = inTexCoord1;
+#else
+    fragColor = vec4(1.0);
+    vec2 lightmapUv = inTexCoord;
+#endif
+    fragTexCoord1 = lightmapUv * instance.lightmapScaleOffset.xy + instance.lightmapScaleOffset.zw;
+    fragTexCoord = inTexCoord + instance.materialParams.xy;
+    fragTint = instance.tint;
+    fragMaterialParams = instance.materialParams;
+    fragEntityId = instance.entityId;
+    fragInstanceFlags = instance.flags;
+    fragInstanceSlot = slot;
 }
\ No newline at end of file
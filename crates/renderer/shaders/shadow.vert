@@ -5,21 +5,45 @@ layout(set = 0, binding = 0) uniform UBO {
     mat4[SHADOW_MAP_CASCADE_COUNT] cascadeViewProjMat;
 } ubo;
 
-layout(std430, set = 0, binding = 1) readonly buffer Transforms {
-    mat4 model[];
+struct InstanceData {
+    mat4 model;
+    vec4 tint;
+    vec4 materialParams;
+    vec4 lightmapScaleOffset;
+    uint entityId;
+    uint flags;
 };
 
+layout(std430, set = 0, binding = 1) readonly buffer Instances {
+    InstanceData instances[];
+};
+
+#ifdef HAS_SKINNING
+layout(std430, set = 0, binding = 2) readonly buffer Joints {
+    mat4 joints[];
+};
+#endif
+
 layout(push_constant) uniform PushConsts {
     uint objectIndex;
     uint cascadeIndex;
+    uint jointOffset;
 } pushConsts;
 
 layout(location = 0) in vec3 inPosition;
-layout(location = 1) in vec3 inColor;
-layout(location = 2) in vec2 inTexCoord;
-layout(location = 3) in vec3 inNormal;
+#ifdef HAS_SKINNING
+layout(location = 6) in uvec4 inJoints;
+layout(location = 7) in vec4 inWeights;
+#endif
 
 void main() {
-    mat4 modelMat = model[pushConsts.objectIndex];
+    mat4 modelMat = instances[pushConsts.objectIndex].model;
+#ifdef HAS_SKINNING
+    modelMat = modelMat * (
+        inWeights.x * joints[pushConsts.jointOffset + inJoints.x] +
+        inWeights.y * joints[pushConsts.jointOffset + inJoints.y] +
+        inWeights.z * joints[pushConsts.jointOffset + inJoints.z] +
+        inWeights.w * joints[pushConsts.jointOffset + inJoints.w]);
+#endif
     gl_Position = ubo.cascadeViewProjMat[pushConsts.cascadeIndex] * modelMat * vec4(inPosition, 1);
 }
@@ -0,0 +1,70 @@
+#version 450
+
+// Preetham analytic daylight sky, drawn where the lighting pass left the background.
+
+layout(std140, set = 0, binding = 0) uniform Sky {
+    mat4 inverseViewProj;
+    vec4 cameraPosition;
+    // xyz: direction towards the sun
+    vec4 sunDirection;
+    // rgb: radiance of the sun disk
+    vec4 sunColor;
+    // Perez coefficients A to D for luminance Y and chromaticities x and y
+    vec4 perezY;
+    vec4 perezX;
+    vec4 perezChromaY;
+    // Perez coefficient E for Y, x and y
+    vec4 perezE;
+    // xyz: zenith Y, x and y divided by the Perez function at the zenith, w: exposure
+    vec4 zenith;
+} sky;
+
+layout(set = 0, binding = 1) uniform sampler2D depthTexture;
+
+layout(location = 0) in vec2 fragTexCoord;
+layout(location = 0) out vec4 outColor;
+
+// Cosine of the sun disk's angular radius, about half a degree.
+const float SUN_DISK_COS = 0.99996;
+
+float perez(vec4 abcd, float e, float cosTheta, float gamma, float cosGamma) {
+    return (1.0 + abcd.x * exp(abcd.y / cosTheta))
+         * (1.0 + abcd.z * exp(abcd.w * gamma) + e * cosGamma * cosGamma);
+}
+
+vec3 xyYToLinearSrgb(vec3 xyY) {
+    float Y = xyY.z;
+    float X = xyY.x * Y / xyY.y;
+    float Z = (1.0 - xyY.x - xyY.y) * Y / xyY.y;
+    return vec3(
+         3.2406 * X - 1.5372 * Y - 0.4986 * Z,
+        -0.9689 * X + 1.8758 * Y + 0.0415 * Z,
+         0.0557 * X - 0.2040 * Y + 1.0570 * Z
+    );
+}
+
+void main() {
+    if (texelFetch(depthTexture, ivec2(gl_FragCoord.xy), 0).r < 1.0)
+        discard;
+
+    vec4 farPoint = sky.inverseViewProj * vec4(fragTexCoord * 2.0 - 1.0, 1.0, 1.0);
+    vec3 direction = normalize(farPoint.xyz / farPoint.w - sky.cameraPosition.xyz);
+    vec3 sunDirection = normalize(sky.sunDirection.xyz);
+
+    // Below the horizon the sky repeats its horizon color.
+    float cosTheta = max(direction.y, 0.001);
+    float cosGamma = clamp(dot(direction, sunDirection), -1.0, 1.0);
+    float gamma = acos(cosGamma);
+
+    vec3 xyY = vec3(
+        sky.zenith.y * perez(sky.perezX, sky.perezE.y, cosTheta, gamma, cosGamma),
+        sky.zenith.z * perez(sky.perezChromaY, sky.perezE.z, cosTheta, gamma, cosGamma),
+        sky.zenith.x * perez(sky.perezY, sky.perezE.x, cosTheta, gamma, cosGamma)
+    );
+    vec3 color = max(xyYToLinearSrgb(xyY), vec3(0.0)) * sky.zenith.w;
+
+    if (cosGamma > SUN_DISK_COS && direction.y > 0.0) {
+        color += sky.sunColor.rgb;
+    }
+    outColor = vec4(color, 1.0);
+}
@@ -0,0 +1,11 @@
+#version 450
+
+// Flat ribbon color; the blend state decides between additive and alpha blending.
+
+layout(location = 0) in vec4 fragColor;
+
+layout(location = 0) out vec4 outColor;
+
+void main() {
+    outColor = fragColor;
+}
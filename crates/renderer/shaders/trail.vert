@@ -0,0 +1,40 @@
+#version 450
+
+// Camera-facing ribbons drawn as triangle strips, two vertices per trail point. The
+// strip's first vertex selects the trail's first point.
+
+struct TrailPoint {
+    // xyz: world-space position, w: half width
+    vec4 positionHalfWidth;
+    // xyz: world-space direction along the trail
+    vec4 tangent;
+    vec4 color;
+};
+
+layout(std430, set = 0, binding = 0) readonly buffer Points {
+    TrailPoint points[];
+};
+
+layout(set = 0, binding = 1) uniform Camera {
+    mat4 view;
+    mat4 proj;
+} camera;
+
+layout(location = 0) out vec4 fragColor;
+
+void main() {
+    TrailPoint p = points[gl_VertexIndex / 2];
+    float side = (gl_VertexIndex & 1) == 0 ? -1.0 : 1.0;
+
+    // Widen across the trail and perpendicular to the view ray, so the ribbon faces the
+    // camera while following its path.
+    vec3 viewPosition = (camera.view * vec4(p.positionHalfWidth.xyz, 1.0)).xyz;
+    vec3 viewTangent = mat3(camera.view) * p.tangent.xyz;
+    vec3 across = cross(viewTangent, viewPosition);
+    float len = length(across);
+    across = len > 1e-6 ? across / len : vec3(1.0, 0.0, 0.0);
+
+    viewPosition += across * side * p.positionHalfWidth.w;
+    gl_Position = camera.proj * vec4(viewPosition, 1.0);
+    fragColor = p.color;
+}
@@ -0,0 +1,17 @@
+#version 450
+
+// Screen-space UI quads. Positions arrive in window pixels, origin top-left.
+layout(location = 0) in vec2 inPosition;
+layout(location = 1) in vec4 inColor;
+
+layout(location = 0) out vec4 fragColor;
+
+layout(push_constant) uniform UiPushConstants {
+    vec2 viewportSize;
+} pc;
+
+void main() {
+    // Vulkan clip space already has +y pointing down.
+    gl_Position = vec4(inPosition / pc.viewportSize * 2.0 - 1.0, 0.0, 1.0);
+    fragColor = inColor;
+}
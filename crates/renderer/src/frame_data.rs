@@ -1,11 +1,15 @@
-use nalgebra_glm::Mat4;
+use crate::frame_ring::{FrameRing, RingWrites};
+use crate::render_data::{InstanceUpdate, ProbeUpdate};
+use config::config::{ShadowQuality, ShadowSettings, MAX_SHADOW_CASCADES};
+use nalgebra_glm::{Mat4, Vec2, Vec4};
 use rendering_backend::backend_impl::vulkan_backend::VulkanBackend;
 use rendering_backend::buffer::{BufferDesc, BufferHandle, BufferUsageFlags};
 use rendering_backend::camera::CameraMvpUbo;
 use rendering_backend::descriptor::{
     DescriptorBinding, DescriptorLayoutDesc, DescriptorLayoutHandle, DescriptorSetHandle,
-    DescriptorType, DescriptorValue, DescriptorWriteDesc, ShaderStage,
+    DescriptorType, DescriptorValue, DescriptorWriteDesc, SampledImageInfo, ShaderStage,
 };
+use rendering_backend::gpu_layout::GpuStruct;
 use rendering_backend::image::{
     GpuImageHandle, ImageAspect, ImageDesc, ImageUsageFlags, TextureFormat,
 };
@@ -16,156 +20,298 @@ use rendering_backend::sampler::{Filter, SamplerAddressMode, SamplerDesc, Sample
 pub struct FrameImages {
     pub gbuffer_albedo: GpuImageHandle,
     pub gbuffer_normal: GpuImageHandle,
+    pub gbuffer_emissive: GpuImageHandle,
     pub gbuffer_depth: GpuImageHandle,
     pub draw_image: GpuImageHandle,
     pub shadow_cascades: Vec<GpuImageHandle>,
+    /// Entity index plus one per pixel, 0 where no mesh was drawn. Only created with
+    /// GPU picking enabled.
+    pub entity_ids: Option<GpuImageHandle>,
+    /// Every image above sized to the render resolution, with the description it was
+    /// created from.
+    render_targets: Vec<(GpuImageHandle, ImageDesc)>,
 }
 
 impl FrameImages {
     pub fn new(
         vulkan_backend: &mut VulkanBackend,
         resolution_settings: ResolutionSettings,
+        shadow_settings: &ShadowSettings,
+        entity_ids: bool,
     ) -> Self {
-        let window_resolution = resolution_settings.window_resolution;
-        let gbuffer_albedo = vulkan_backend.create_image(ImageDesc {
-            width: window_resolution.width,
-            height: window_resolution.height,
-            depth: 1,
-            format: TextureFormat::R16g16b16a16Float,
-            clear_value: None,
-            array_layers: 1,
-            is_cubemap: false,
-            mip_levels: 1,
-            aspect: ImageAspect::Color,
-            usage: ImageUsageFlags::COLOR_ATTACHMENT
-                | ImageUsageFlags::TRANSFER_SRC
-                | ImageUsageFlags::TRANSFER_DST
-                | ImageUsageFlags::SAMPLED
-                | ImageUsageFlags::STORAGE,
-        });
-        let gbuffer_normal = vulkan_backend.create_image(ImageDesc {
-            width: window_resolution.width,
-            height: window_resolution.height,
-            depth: 1,
-            format: TextureFormat::R16g16b16a16Float,
-            clear_value: None,
-            array_layers: 1,
-            is_cubemap: false,
-            mip_levels: 1,
-            aspect: ImageAspect::Color,
-            usage: ImageUsageFlags::COLOR_ATTACHMENT
-                | ImageUsageFlags::TRANSFER_SRC
-                | ImageUsageFlags::TRANSFER_DST
-                | ImageUsageFlags::SAMPLED
-                | ImageUsageFlags::STORAGE,
-        });
-
-        let gbuffer_depth = vulkan_backend.create_image(ImageDesc {
-            width: window_resolution.width,
-            height: window_resolution.height,
-            depth: 1,
-            format: TextureFormat::D32Float,
-            clear_value: None,
-            array_layers: 1,
-            is_cubemap: false,
-            mip_levels: 1,
-            aspect: ImageAspect::Depth,
-            usage: ImageUsageFlags::DEPTH_ATTACHMENT
-                | ImageUsageFlags::TRANSFER_SRC
-                | ImageUsageFlags::TRANSFER_DST
-                | ImageUsageFlags::SAMPLED
-                | ImageUsageFlags::STORAGE,
-        });
+        let resolution = resolution_settings.window_resolution;
+        let mut render_targets = Vec::new();
+        let mut create_target = |format, aspect, usage| {
+            let desc = render_target_desc(resolution, format, aspect, usage);
+            let image = vulkan_backend.create_image(desc);
+            render_targets.push((image, desc));
+            image
+        };
+        let sampled_target = ImageUsageFlags::TRANSFER_SRC
+            | ImageUsageFlags::TRANSFER_DST
+            | ImageUsageFlags::SAMPLED
+            | ImageUsageFlags::STORAGE;
 
-        let draw_image = vulkan_backend.create_image(ImageDesc {
-            width: window_resolution.width,
-            height: window_resolution.height,
-            depth: 1,
-            format: TextureFormat::R16g16b16a16Float,
-            clear_value: None,
-            array_layers: 1,
-            is_cubemap: false,
-            mip_levels: 1,
-            aspect: ImageAspect::Color,
-            usage: ImageUsageFlags::COLOR_ATTACHMENT
-                | ImageUsageFlags::TRANSFER_SRC
-                | ImageUsageFlags::TRANSFER_DST
-                | ImageUsageFlags::SAMPLED
-                | ImageUsageFlags::STORAGE,
+        // Albedo is LDR, occlusion rides in alpha.
+        let gbuffer_albedo = create_target(
+            TextureFormat::R8g8b8a8Unorm,
+            ImageAspect::Color,
+            ImageUsageFlags::COLOR_ATTACHMENT | sampled_target,
+        );
+        // Octahedral normal in RG, roughness and metallic in BA.
+        let gbuffer_normal = create_target(
+            TextureFormat::R16g16b16a16Float,
+            ImageAspect::Color,
+            ImageUsageFlags::COLOR_ATTACHMENT | sampled_target,
+        );
+        // HDR emissive radiance, added on top of the lit result.
+        let gbuffer_emissive = create_target(
+            TextureFormat::R16g16b16a16Float,
+            ImageAspect::Color,
+            ImageUsageFlags::COLOR_ATTACHMENT | sampled_target,
+        );
+        let gbuffer_depth = create_target(
+            TextureFormat::D32Float,
+            ImageAspect::Depth,
+            ImageUsageFlags::DEPTH_ATTACHMENT | sampled_target,
+        );
+        let draw_image = create_target(
+            TextureFormat::R16g16b16a16Float,
+            ImageAspect::Color,
+            ImageUsageFlags::COLOR_ATTACHMENT | sampled_target,
+        );
+        // Cleared to 0, which no entity uses.
+        let entity_ids = entity_ids.then(|| {
+            create_target(
+                TextureFormat::R32Uint,
+                ImageAspect::Color,
+                ImageUsageFlags::COLOR_ATTACHMENT | ImageUsageFlags::TRANSFER_SRC,
+            )
         });
 
-        let shadow_cascades = resolution_settings
-            .shadow_resolutions
-            .iter()
-            .map(|res| {
-                vulkan_backend.create_image(ImageDesc {
-                    width: res.width,
-                    height: res.height,
-                    depth: 1,
-                    format: TextureFormat::D32Float,
-                    clear_value: None,
-                    array_layers: 1,
-                    is_cubemap: false,
-                    mip_levels: 1,
-                    aspect: ImageAspect::Depth,
-                    usage: ImageUsageFlags::DEPTH_ATTACHMENT
-                        | ImageUsageFlags::TRANSFER_SRC
-                        | ImageUsageFlags::TRANSFER_DST
-                        | ImageUsageFlags::SAMPLED
-                        | ImageUsageFlags::STORAGE,
-                })
+        let shadow_cascades = (0..MAX_SHADOW_CASCADES)
+            .map(|index| {
+                let res = shadow_cascade_resolution(shadow_settings, index);
+                vulkan_backend.create_image(shadow_cascade_desc(res))
             })
             .collect();
 
         Self {
             gbuffer_albedo,
             gbuffer_normal,
+            gbuffer_emissive,
             gbuffer_depth,
             draw_image,
             shadow_cascades,
+            entity_ids,
+            render_targets,
+        }
+    }
+
+    /// Width and height of the images sized to the render resolution.
+    pub fn resolution(&self) -> Resolution {
+        let (_, desc) = &self.render_targets[0];
+        Resolution {
+            width: desc.width,
+            height: desc.height,
+        }
+    }
+
+    /// Reallocates the images sized to the render resolution at `resolution`, from the
+    /// descriptions they were created with. Handles are preserved, so only the returned
+    /// images' descriptor sets need rewriting afterwards.
+    pub fn resize(
+        &mut self,
+        vulkan_backend: &mut VulkanBackend,
+        resolution: Resolution,
+    ) -> Vec<GpuImageHandle> {
+        self.render_targets
+            .iter_mut()
+            .map(|(image, desc)| {
+                desc.width = resolution.width;
+                desc.height = resolution.height;
+                vulkan_backend.recreate_image(*image, *desc);
+                *image
+            })
+            .collect()
+    }
+
+    /// Color attachments of the G-buffer pass in fragment output order, ending with the
+    /// entity ID buffer when there is one.
+    pub fn gbuffer_color_attachments(&self) -> Vec<GpuImageHandle> {
+        let mut attachments = vec![
+            self.gbuffer_albedo,
+            self.gbuffer_normal,
+            self.gbuffer_emissive,
+        ];
+        attachments.extend(self.entity_ids);
+        attachments
+    }
+
+    /// Reallocates every shadow cascade image to match `shadow_settings`.
+    /// Handles are preserved, so only descriptor sets need rewriting afterwards.
+    pub fn recreate_shadow_cascades(
+        &mut self,
+        vulkan_backend: &mut VulkanBackend,
+        shadow_settings: &ShadowSettings,
+    ) {
+        for (index, &image) in self.shadow_cascades.iter().enumerate() {
+            let res = shadow_cascade_resolution(shadow_settings, index as u32);
+            vulkan_backend.recreate_image(image, shadow_cascade_desc(res));
         }
     }
 }
 
+/// Square resolution of shadow cascade `index`. Inactive cascades, and every cascade
+/// with shadows off, keep a 1x1 placeholder so the lighting descriptor set always has a
+/// valid image bound.
+pub fn shadow_cascade_resolution(shadow_settings: &ShadowSettings, index: u32) -> u32 {
+    if shadow_settings.quality == ShadowQuality::Cascaded
+        && index < shadow_settings.active_cascades()
+    {
+        shadow_settings.cascade_resolution(index)
+    } else {
+        1
+    }
+}
+
+fn render_target_desc(
+    resolution: Resolution,
+    format: TextureFormat,
+    aspect: ImageAspect,
+    usage: ImageUsageFlags,
+) -> ImageDesc {
+    ImageDesc {
+        width: resolution.width,
+        height: resolution.height,
+        depth: 1,
+        format,
+        clear_value: None,
+        array_layers: 1,
+        is_cubemap: false,
+        mip_levels: 1,
+        aspect,
+        usage,
+    }
+}
+
+fn shadow_cascade_desc(resolution: u32) -> ImageDesc {
+    ImageDesc {
+        width: resolution,
+        height: resolution,
+        depth: 1,
+        format: TextureFormat::D32Float,
+        clear_value: None,
+        array_layers: 1,
+        is_cubemap: false,
+        mip_levels: 1,
+        aspect: ImageAspect::Depth,
+        usage: ImageUsageFlags::DEPTH_ATTACHMENT
+            | ImageUsageFlags::TRANSFER_SRC
+            | ImageUsageFlags::TRANSFER_DST
+            | ImageUsageFlags::SAMPLED
+            | ImageUsageFlags::STORAGE,
+    }
+}
+
+/// One entry of the instance storage buffer, indexed by a mesh's transform slot.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, GpuStruct)]
+#[gpu(std430)]
+pub struct InstanceData {
+    pub model: Mat4,
+    /// Multiplies the material's albedo.
+    pub tint: Vec4,
+    /// xy: UV offset, z: emissive strength, w: lightmap intensity, negative without a
+    /// lightmap.
+    pub material_params: Vec4,
+    /// xy: scale, zw: offset applied to the lightmap UVs.
+    pub lightmap_scale_offset: Vec4,
+    /// Index of the entity drawn with this instance plus one, written to the entity ID
+    /// buffer.
+    pub entity_id: u32,
+    /// `INSTANCE_*` bits.
+    pub flags: u32,
+    /// x: branch sway, y: leaf flutter of vegetation. Zero for other meshes.
+    pub vegetation: Vec2,
+}
+
+/// The instance's surfaces are lit as if unshadowed.
+pub const INSTANCE_NO_RECEIVE_SHADOWS: u32 = 1;
+/// The instance has no lightmap and takes its baked light from its [`InstanceProbe`].
+pub const INSTANCE_PROBE_LIT: u32 = 2;
+
+/// Light probe lighting of one instance slot, set 0 binding 4 of the geometry pass. Only
+/// read for instances with [`INSTANCE_PROBE_LIT`].
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, GpuStruct)]
+#[gpu(std430)]
+pub struct InstanceProbe {
+    /// rgb: the probe's spherical harmonics convolved with the cosine lobe and scaled to
+    /// the baked light term, which the shader evaluates at the surface normal. w of the
+    /// first coefficient: sky visibility.
+    pub sh: [Vec4; 9],
+}
+
+/// Wind read by the vegetation vertex shader, set 0 binding 3 of the geometry pass.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, GpuStruct)]
+pub struct WindUbo {
+    /// xyz: normalized horizontal direction the wind blows towards, w: strength.
+    pub direction_strength: Vec4,
+    /// x: seconds since startup, y: gust strength, z: gust frequency, w: gust size.
+    pub gust: Vec4,
+}
+
+/// Buffers the CPU writes every frame and the frame-level descriptor set binding them.
+/// There is one copy per frame in flight.
+pub struct FrameBuffers {
+    pub camera_buffer: BufferHandle,
+    pub instance_buffer: BufferHandle,
+    /// Joint palettes of skinned meshes, one `Mat4` per joint.
+    pub joint_buffer: BufferHandle,
+    pub wind_buffer: BufferHandle,
+    /// Light probe lighting, one [`InstanceProbe`] per instance slot.
+    pub probe_buffer: BufferHandle,
+    pub descriptor_handle: DescriptorSetHandle,
+}
+
 /// Per-frame GPU resources shared across the geometry and debug passes:
-/// camera/model data buffers, the frame-level descriptor set, and the basic sampler.
+/// camera/instance data buffers, the frame-level descriptor set, and the basic sampler.
 /// Shadow and lighting resources live in LightingRenderer.
 pub struct FrameData {
     pub frame_images: FrameImages,
-    pub camera_buffer: BufferHandle,
-    pub model_storage_buffer: BufferHandle,
+    pub buffers: FrameRing<FrameBuffers>,
+    /// Instance slots are only written when they change, so each copy of the instance
+    /// buffer catches up on the writes made while other frames were recorded.
+    instance_writes: RingWrites<InstanceData>,
+    /// Probe slots are only written when they change, like instance slots.
+    probe_writes: RingWrites<InstanceProbe>,
     pub descriptor_layout_handle: DescriptorLayoutHandle,
-    pub descriptor_handle: DescriptorSetHandle,
     pub basic_sampler: SamplerHandle,
+    /// Layout of the geometry pass's per-mesh lightmap set (set 2).
+    pub lightmap_layout_handle: DescriptorLayoutHandle,
+    /// Clamped, so lightmap atlas regions don't bleed into each other at the edges.
+    pub lightmap_sampler: SamplerHandle,
+    /// Bound for meshes without a lightmap; the shader skips sampling it for them.
+    pub default_lightmap_set: DescriptorSetHandle,
 }
 
 impl FrameData {
     pub fn new(
         vulkan_backend: &mut VulkanBackend,
         resolution_settings: ResolutionSettings,
+        shadow_settings: &ShadowSettings,
         max_meshes: usize,
+        max_joints: usize,
+        entity_ids: bool,
     ) -> Self {
-        let frame_images = FrameImages::new(vulkan_backend, resolution_settings);
-        let buffer_size = size_of::<CameraMvpUbo>();
-
-        let camera_buffer = vulkan_backend.create_buffer::<CameraMvpUbo>(
-            BufferDesc {
-                size: buffer_size,
-                usage: BufferUsageFlags::UNIFORM,
-                memory_hint: MemoryHint::CPUWritable,
-            },
-            None,
+        let frame_images = FrameImages::new(
+            vulkan_backend,
+            resolution_settings,
+            shadow_settings,
+            entity_ids,
         );
-
-        let model_storage_buffer = vulkan_backend.create_buffer::<Mat4>(
-            BufferDesc {
-                size: size_of::<Mat4>() * max_meshes,
-                memory_hint: MemoryHint::CPUWritable,
-                usage: BufferUsageFlags::STORAGE,
-            },
-            None,
-        );
-
         let basic_sampler = vulkan_backend.create_sampler(SamplerDesc {
             mag_filter: Filter::Linear,
             min_filter: Filter::Linear,
@@ -190,12 +336,176 @@ impl FrameData {
                     count: 1,
                     stages: ShaderStage::VERTEX,
                 },
+                DescriptorBinding {
+                    binding: 2,
+                    descriptor_type: DescriptorType::StorageBuffer,
+                    count: 1,
+                    stages: ShaderStage::VERTEX,
+                },
+                DescriptorBinding {
+                    binding: 3,
+                    descriptor_type: DescriptorType::UniformBuffer,
+                    count: 1,
+                    stages: ShaderStage::VERTEX,
+                },
+                DescriptorBinding {
+                    binding: 4,
+                    descriptor_type: DescriptorType::StorageBuffer,
+                    count: 1,
+                    stages: ShaderStage::FRAGMENT,
+                },
             ],
         };
 
         let descriptor_layout_handle = vulkan_backend.create_descriptor_layout(frame_layout_desc);
-        let descriptor_handle = vulkan_backend.allocate_descriptor_set(descriptor_layout_handle);
+        let buffers = FrameRing::new(vulkan_backend, |vulkan_backend, _| {
+            FrameBuffers::new(vulkan_backend, descriptor_layout_handle, max_meshes, max_joints)
+        });
+        let instance_writes = RingWrites::new(vulkan_backend.frames_in_flight());
+        let probe_writes = RingWrites::new(vulkan_backend.frames_in_flight());
+
+        let lightmap_sampler = vulkan_backend.create_sampler(SamplerDesc {
+            mag_filter: Filter::Linear,
+            min_filter: Filter::Linear,
+            address_u: SamplerAddressMode::ClampToEdge,
+            address_v: SamplerAddressMode::ClampToEdge,
+            address_w: SamplerAddressMode::ClampToEdge,
+            compare_enable: false,
+            compare_op: None,
+        });
+        let lightmap_layout_handle =
+            vulkan_backend.create_descriptor_layout(DescriptorLayoutDesc {
+                bindings: vec![DescriptorBinding {
+                    binding: 0,
+                    descriptor_type: DescriptorType::CombinedImageSampler,
+                    count: 1,
+                    stages: ShaderStage::FRAGMENT,
+                }],
+            });
+
+        // White and unoccluded, so it is harmless even if sampled.
+        let default_lightmap = vulkan_backend.create_image(ImageDesc {
+            width: 1,
+            height: 1,
+            depth: 1,
+            format: TextureFormat::R8g8b8a8Unorm,
+            clear_value: None,
+            array_layers: 0,
+            is_cubemap: false,
+            mip_levels: 0,
+            aspect: ImageAspect::Color,
+            usage: ImageUsageFlags::SAMPLED | ImageUsageFlags::TRANSFER_DST,
+        });
+        vulkan_backend.update_image_data(default_lightmap, &[255, 255, 255, 255]);
+        let default_lightmap_set = vulkan_backend.allocate_descriptor_set(lightmap_layout_handle);
+        vulkan_backend.update_descriptor_set(
+            default_lightmap_set,
+            &[DescriptorWriteDesc {
+                binding: 0,
+                value: DescriptorValue::SampledImage(SampledImageInfo {
+                    image: default_lightmap,
+                    sampler: lightmap_sampler,
+                }),
+            }],
+        );
+
+        Self {
+            frame_images,
+            buffers,
+            instance_writes,
+            probe_writes,
+            descriptor_layout_handle,
+            basic_sampler,
+            lightmap_layout_handle,
+            lightmap_sampler,
+            default_lightmap_set,
+        }
+    }
+
+    /// Writes `updates` to the instance buffer of the frame being recorded, after the
+    /// updates it missed while other frames were recorded. Call every frame.
+    pub fn update_instances(
+        &mut self,
+        vulkan_backend: &mut VulkanBackend,
+        updates: &[InstanceUpdate],
+    ) {
+        let slot = vulkan_backend.frame_slot();
+        let writes = updates
+            .iter()
+            .map(|update| (update.slot as usize, update.data));
+        let instance_buffer = self.buffers.get(slot).instance_buffer;
+        for (index, data) in self.instance_writes.take(slot, writes) {
+            vulkan_backend.update_buffer_at(instance_buffer, index, &[data]);
+        }
+    }
+
+    /// Writes `updates` to the probe buffer of the frame being recorded, after the
+    /// updates it missed while other frames were recorded. Call every frame.
+    pub fn update_probes(&mut self, vulkan_backend: &mut VulkanBackend, updates: &[ProbeUpdate]) {
+        let slot = vulkan_backend.frame_slot();
+        let writes = updates
+            .iter()
+            .map(|update| (update.slot as usize, update.probe));
+        let probe_buffer = self.buffers.get(slot).probe_buffer;
+        for (index, probe) in self.probe_writes.take(slot, writes) {
+            vulkan_backend.update_buffer_at(probe_buffer, index, &[probe]);
+        }
+    }
+}
+
+impl FrameBuffers {
+    fn new(
+        vulkan_backend: &mut VulkanBackend,
+        descriptor_layout_handle: DescriptorLayoutHandle,
+        max_meshes: usize,
+        max_joints: usize,
+    ) -> Self {
+        let camera_buffer = vulkan_backend.create_buffer::<CameraMvpUbo>(
+            BufferDesc {
+                size: size_of::<CameraMvpUbo>(),
+                usage: BufferUsageFlags::UNIFORM,
+                memory_hint: MemoryHint::CPUWritable,
+            },
+            None,
+        );
+
+        let instance_buffer = vulkan_backend.create_buffer::<InstanceData>(
+            BufferDesc {
+                size: size_of::<InstanceData>() * max_meshes,
+                memory_hint: MemoryHint::CPUWritable,
+                usage: BufferUsageFlags::STORAGE,
+            },
+            None,
+        );
 
+        let joint_buffer = vulkan_backend.create_buffer::<Mat4>(
+            BufferDesc {
+                size: size_of::<Mat4>() * max_joints,
+                memory_hint: MemoryHint::CPUWritable,
+                usage: BufferUsageFlags::STORAGE,
+            },
+            None,
+        );
+
+        let wind_buffer = vulkan_backend.create_buffer::<WindUbo>(
+            BufferDesc {
+                size: size_of::<WindUbo>(),
+                usage: BufferUsageFlags::UNIFORM,
+                memory_hint: MemoryHint::CPUWritable,
+            },
+            Some(&[WindUbo::default()]),
+        );
+
+        let probe_buffer = vulkan_backend.create_buffer::<InstanceProbe>(
+            BufferDesc {
+                size: size_of::<InstanceProbe>() * max_meshes,
+                memory_hint: MemoryHint::CPUWritable,
+                usage: BufferUsageFlags::STORAGE,
+            },
+            None,
+        );
+
+        let descriptor_handle = vulkan_backend.allocate_descriptor_set(descriptor_layout_handle);
         vulkan_backend.update_descriptor_set(
             descriptor_handle,
             &[
@@ -205,27 +515,39 @@ impl FrameData {
                 },
                 DescriptorWriteDesc {
                     binding: 1,
-                    value: DescriptorValue::StorageBuffer(model_storage_buffer),
+                    value: DescriptorValue::StorageBuffer(instance_buffer),
+                },
+                DescriptorWriteDesc {
+                    binding: 2,
+                    value: DescriptorValue::StorageBuffer(joint_buffer),
+                },
+                DescriptorWriteDesc {
+                    binding: 3,
+                    value: DescriptorValue::UniformBuffer(wind_buffer),
+                },
+                DescriptorWriteDesc {
+                    binding: 4,
+                    value: DescriptorValue::StorageBuffer(probe_buffer),
                 },
             ],
         );
 
         Self {
-            frame_images,
             camera_buffer,
-            model_storage_buffer,
-            descriptor_layout_handle,
+            instance_buffer,
+            joint_buffer,
+            wind_buffer,
+            probe_buffer,
             descriptor_handle,
-            basic_sampler,
         }
     }
 }
 
 pub struct ResolutionSettings {
     pub window_resolution: Resolution,
-    pub shadow_resolutions: Vec<Resolution>,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct Resolution {
     pub width: u32,
     pub height: u32,
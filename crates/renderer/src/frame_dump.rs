@@ -0,0 +1,180 @@
+//! CPU-side dump of one frame's draw list, for diagnosing batching and visibility bugs
+//! without a GPU debugger. Requested with
+//! [`Renderer::dump_frame`](crate::renderer::Renderer::dump_frame).
+//!
+//! Draws are listed in scene order. Meshes merged into a GPU culling batch are drawn
+//! with that batch's indirect call, after the direct draws, and only if the cull pass
+//! finds them visible; `batch` names the call.
+
+use crate::passes::gpu_culling::GpuCulling;
+use crate::render_scene::RenderScene;
+use assets::AssetStore;
+use common::{Guid, MeshData};
+use material::material_manager::MaterialManager;
+use rendering_backend::pipeline::PipelineHandle;
+use serde::Serialize;
+use std::io;
+use std::path::Path;
+
+#[derive(Debug, Serialize)]
+pub(crate) struct FrameDump {
+    frame: u64,
+    /// Pass markers pushed while recording the frame, in order.
+    passes: Vec<String>,
+    draws: Vec<DrawRecord>,
+}
+
+#[derive(Debug, Serialize)]
+struct DrawRecord {
+    order: usize,
+    /// Entity index in the world.
+    entity: usize,
+    mesh: u64,
+    mesh_guid: Option<Guid>,
+    material: u64,
+    material_guid: Option<Guid>,
+    /// Backend pipeline handle of the geometry pass permutation, or of the placeholder
+    /// drawn while it compiles. Absent if the mesh was skipped for lack of either.
+    pipeline: Option<usize>,
+    defines: Vec<String>,
+    transform_slot: u32,
+    skinned: bool,
+    index_count: usize,
+    instance_count: u32,
+    /// GPU culling batch the mesh was merged into; absent for direct draws.
+    batch: Option<usize>,
+}
+
+impl FrameDump {
+    /// `pipelines` holds the pipeline each mesh of `scene` was drawn with.
+    pub(crate) fn new(
+        frame: u64,
+        passes: &[String],
+        scene: &RenderScene,
+        pipelines: &[Option<PipelineHandle>],
+        culling: &GpuCulling,
+        asset_store: &AssetStore,
+        material_manager: &MaterialManager,
+    ) -> Self {
+        let draws = scene
+            .meshes
+            .iter()
+            .zip(pipelines)
+            .enumerate()
+            .map(|(order, (mesh, pipeline))| DrawRecord {
+                order,
+                entity: mesh.entity.index(),
+                mesh: mesh.mesh_handle.raw(),
+                mesh_guid: asset_store.guid_of::<MeshData>(mesh.mesh_handle),
+                material: mesh.material_handle.raw(),
+                material_guid: material_manager.guid_of(mesh.material_handle),
+                pipeline: pipeline.map(|pipeline| pipeline.0),
+                defines: mesh.material_data.shader_variant.active_defines.clone(),
+                transform_slot: mesh.transform_slot,
+                skinned: mesh.joint_offset.is_some(),
+                index_count: mesh.mesh_data.index_count,
+                instance_count: 1,
+                batch: culling.batch_of(order),
+            })
+            .collect();
+        Self {
+            frame,
+            passes: passes.to_vec(),
+            draws,
+        }
+    }
+
+    pub(crate) fn write(&self, path: &Path) -> io::Result<()> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(self)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render_scene::MeshRenderData;
+    use common::SubMesh;
+    use core::environment::WorldEnvironment;
+    use material::ShaderRef;
+    use serde_json::{json, Value};
+
+    #[test]
+    fn dump_lists_each_draw_with_its_assets_and_batch() {
+        let mut asset_store = AssetStore::new();
+        let cube = Guid::from_u128(7);
+        let handle = asset_store.insert_mesh(
+            cube,
+            MeshData {
+                vertices: Vec::new(),
+                vertex_encoding: Default::default(),
+                indices: Vec::new(),
+                extras: None,
+                skin: None,
+                submeshes: vec![SubMesh {
+                    index_offset: 0,
+                    index_count: 0,
+                }],
+            },
+        );
+        let batched = MeshRenderData::built_in(1, handle.raw(), 3);
+        let mut direct = MeshRenderData::built_in(1, handle.raw(), 3);
+        direct.material_data.shader_variant.vertex_shader = ShaderRef::Asset(Guid::from_u128(9));
+        direct.joint_offset = Some(4);
+        let scene = RenderScene {
+            meshes: vec![batched, direct],
+            camera_data: None,
+            directional_light: None,
+            point_lights: Vec::new(),
+            area_lights: Vec::new(),
+            particle_emitters: Vec::new(),
+            trails: Vec::new(),
+            blob_shadows: Vec::new(),
+            reflection_probes: Vec::new(),
+            environment: WorldEnvironment::default(),
+            skybox: None,
+        };
+        let mut culling = GpuCulling::without_gpu(8);
+        culling.build_batches(&scene.meshes);
+
+        let dump = FrameDump::new(
+            12,
+            &["Geometry".to_string(), "Lighting".to_string()],
+            &scene,
+            &[Some(PipelineHandle(5)), None],
+            &culling,
+            &asset_store,
+            &MaterialManager::new(),
+        );
+        let dump = serde_json::to_value(&dump).unwrap();
+
+        assert_eq!(dump["frame"], 12);
+        assert_eq!(dump["passes"], json!(["Geometry", "Lighting"]));
+        let draws = dump["draws"].as_array().unwrap();
+        assert_eq!(draws.len(), 2);
+        assert_eq!(
+            draws[0],
+            json!({
+                "order": 0,
+                "entity": 0,
+                "mesh": handle.raw(),
+                "mesh_guid": serde_json::to_value(cube).unwrap(),
+                "material": 3,
+                "material_guid": null,
+                "pipeline": 5,
+                "defines": [],
+                "transform_slot": 0,
+                "skinned": false,
+                "index_count": 36,
+                "instance_count": 1,
+                "batch": 0,
+            })
+        );
+        assert_eq!(draws[1]["order"], 1);
+        assert_eq!(draws[1]["pipeline"], Value::Null);
+        assert_eq!(draws[1]["skinned"], true);
+        assert_eq!(draws[1]["batch"], Value::Null);
+    }
+}
@@ -0,0 +1,108 @@
+//! Per-frame copies of the resources the CPU writes every frame.
+//!
+//! With several frames in flight, the GPU may still read a buffer from an earlier frame
+//! while the CPU records the next one. Such buffers, and the descriptor sets binding
+//! them, keep one copy per frame slot in a [`FrameRing`].
+
+use rendering_backend::backend_impl::vulkan_backend::VulkanBackend;
+use std::collections::BTreeMap;
+
+/// One `T` per frame in flight, indexed by [`VulkanBackend::frame_slot`].
+pub struct FrameRing<T> {
+    slots: Vec<T>,
+}
+
+impl<T> FrameRing<T> {
+    /// Creates the copy of each frame slot with `create`, which gets the slot index.
+    pub fn new(
+        vulkan_backend: &mut VulkanBackend,
+        mut create: impl FnMut(&mut VulkanBackend, usize) -> T,
+    ) -> Self {
+        let slots = (0..vulkan_backend.frames_in_flight())
+            .map(|slot| create(vulkan_backend, slot))
+            .collect();
+        Self { slots }
+    }
+
+    /// A ring of one copy, for tests that never record a frame.
+    #[cfg(test)]
+    pub fn single(item: T) -> Self {
+        Self { slots: vec![item] }
+    }
+
+    /// The copy of the frame being recorded. The GPU is done with it, since the first
+    /// `frame_slot` call of a frame waits for the slot.
+    pub fn current(&self, vulkan_backend: &mut VulkanBackend) -> &T {
+        &self.slots[vulkan_backend.frame_slot()]
+    }
+
+    pub fn current_mut(&mut self, vulkan_backend: &mut VulkanBackend) -> &mut T {
+        &mut self.slots[vulkan_backend.frame_slot()]
+    }
+
+    /// The copy of frame slot `slot`.
+    pub fn get(&self, slot: usize) -> &T {
+        &self.slots[slot]
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.slots.iter()
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut T> {
+        self.slots.iter_mut()
+    }
+}
+
+/// Element writes to a ringed buffer that is only partly rewritten each frame. A write
+/// goes to the current frame's copy and is replayed into every other copy the next
+/// time its slot comes round, so all copies end up with the same contents.
+pub struct RingWrites<T> {
+    /// Writes each slot has missed, by element index.
+    pending: Vec<BTreeMap<usize, T>>,
+}
+
+impl<T: Copy> RingWrites<T> {
+    pub fn new(frames_in_flight: usize) -> Self {
+        Self {
+            pending: (0..frames_in_flight).map(|_| BTreeMap::new()).collect(),
+        }
+    }
+
+    /// The elements to write to the copy of `slot` this frame: the writes it missed, then
+    /// `writes`, the latest write to an index winning. `writes` are queued for the other
+    /// slots. Call every frame, even without writes.
+    pub fn take(
+        &mut self,
+        slot: usize,
+        writes: impl IntoIterator<Item = (usize, T)>,
+    ) -> BTreeMap<usize, T> {
+        let mut elements = std::mem::take(&mut self.pending[slot]);
+        for (index, value) in writes {
+            elements.insert(index, value);
+            for (other, pending) in self.pending.iter_mut().enumerate() {
+                if other != slot {
+                    pending.insert(index, value);
+                }
+            }
+        }
+        elements
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_copy_receives_the_writes_it_missed() {
+        let mut writes = RingWrites::new(2);
+
+        assert_eq!(writes.take(0, [(0, 'a'), (3, 'b')]), BTreeMap::from([(0, 'a'), (3, 'b')]));
+        // Slot 1 catches up on both writes, with its own write to 3 replacing the older one.
+        assert_eq!(writes.take(1, [(3, 'c')]), BTreeMap::from([(0, 'a'), (3, 'c')]));
+        assert_eq!(writes.take(0, []), BTreeMap::from([(3, 'c')]));
+        assert!(writes.take(1, []).is_empty());
+        assert!(writes.take(0, []).is_empty());
+    }
+}
@@ -1,7 +1,12 @@
 pub mod frame_data;
+mod frame_dump;
+mod frame_ring;
+mod lightmap_gpu_cache;
 mod material_gpu_cache;
 mod passes;
 pub mod render_data;
 mod render_scene;
 pub mod renderer;
 mod shader_loader;
+mod shadows;
+mod texture_streaming;
@@ -0,0 +1,68 @@
+use crate::frame_data::FrameData;
+use assets::AssetStore;
+use common::ImageHandle;
+use rendering_backend::backend_impl::resource_manager::ResourceManager;
+use rendering_backend::backend_impl::vulkan_backend::VulkanBackend;
+use rendering_backend::descriptor::{
+    DescriptorSetHandle, DescriptorValue, DescriptorWriteDesc, SampledImageInfo,
+};
+use std::collections::HashMap;
+
+/// Maps lightmap images to descriptor sets for set 2 of the geometry pass.
+/// Meshes sharing a lightmap atlas share one set.
+pub struct LightmapGpuCache {
+    descriptor_cache: HashMap<ImageHandle, DescriptorSetHandle>,
+}
+
+impl LightmapGpuCache {
+    pub fn new() -> Self {
+        Self {
+            descriptor_cache: HashMap::new(),
+        }
+    }
+
+    /// Returns the set to bind for a mesh, falling back to the frame's default set for
+    /// meshes without a lightmap.
+    pub fn get_or_create(
+        &mut self,
+        vulkan_backend: &mut VulkanBackend,
+        lightmap: Option<ImageHandle>,
+        frame_data: &FrameData,
+        resource_manager: &mut ResourceManager,
+        asset_store: &AssetStore,
+    ) -> DescriptorSetHandle {
+        let Some(lightmap) = lightmap else {
+            return frame_data.default_lightmap_set;
+        };
+        if let Some(&set_handle) = self.descriptor_cache.get(&lightmap) {
+            return set_handle;
+        }
+
+        let image_asset = asset_store
+            .get(lightmap)
+            .unwrap_or_else(|| panic!("No asset found for lightmap: {}", lightmap.raw()));
+        let gpu_image = resource_manager.get_or_create_image(vulkan_backend, lightmap, image_asset);
+
+        let set_handle = vulkan_backend.allocate_descriptor_set(frame_data.lightmap_layout_handle);
+        vulkan_backend.update_descriptor_set(
+            set_handle,
+            &[DescriptorWriteDesc {
+                binding: 0,
+                value: DescriptorValue::SampledImage(SampledImageInfo {
+                    image: gpu_image,
+                    sampler: frame_data.lightmap_sampler,
+                }),
+            }],
+        );
+        self.descriptor_cache.insert(lightmap, set_handle);
+
+        set_handle
+    }
+
+    /// Frees the set of a lightmap that was unloaded.
+    pub fn release(&mut self, vulkan_backend: &mut VulkanBackend, lightmap: ImageHandle) {
+        if let Some(set_handle) = self.descriptor_cache.remove(&lightmap) {
+            vulkan_backend.release_descriptor_set(set_handle);
+        }
+    }
+}
@@ -27,6 +27,7 @@ impl MaterialGpuCache {
 
     /// Returns `(descriptor_set, layout)` for the given material.
     /// GPU resources are allocated and written only on the first call for each `material_handle`.
+    #[allow(clippy::too_many_arguments)]
     pub fn get_or_create(
         &mut self,
         vulkan_backend: &mut VulkanBackend,
@@ -81,6 +82,14 @@ impl MaterialGpuCache {
         (set_handle, layout_handle)
     }
 
+    /// Frees the descriptor set of a material that was unloaded. Layouts are shared per
+    /// variant and kept.
+    pub fn release(&mut self, vulkan_backend: &mut VulkanBackend, material_handle: MaterialHandle) {
+        if let Some(set_handle) = self.descriptor_cache.remove(&material_handle) {
+            vulkan_backend.release_descriptor_set(set_handle);
+        }
+    }
+
     fn get_or_create_layout(
         &mut self,
         vulkan_backend: &mut VulkanBackend,
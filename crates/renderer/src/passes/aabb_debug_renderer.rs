@@ -1,7 +1,9 @@
 use crate::frame_data::FrameData;
+use crate::frame_ring::FrameRing;
 use crate::shader_loader::ShaderCache;
+use common::Color;
 use material::ShaderRef;
-use nalgebra_glm::{Mat4, Vec2, Vec3};
+use nalgebra_glm::{Mat4, Vec3};
 use rendering_backend::backend_impl::vulkan_backend::VulkanBackend;
 use rendering_backend::buffer::{BufferDesc, BufferHandle, BufferUsageFlags};
 use rendering_backend::camera::CameraMvpUbo;
@@ -13,43 +15,69 @@ use rendering_backend::memory::MemoryHint;
 use rendering_backend::pipeline::{
     BlendAttachmentDesc, BlendFactor, BlendOp, BlendStateDesc, ColorWriteMask, CompareOp, CullMode,
     DepthStencilDesc, FrontFace, PipelineDesc, PipelineHandle, PolygonMode, PrimitiveTopology,
-    RasterizationStateDesc, VertexInputDesc,
+    RasterizationStateDesc, SpecializationConstants, VertexAttributeDesc, VertexBindingDesc,
+    VertexFormat, VertexInputDesc, VertexInputRate,
 };
-use common::Vertex;
+use std::mem::offset_of;
+
+/// Vertex layout read by `line_debug.vert`.
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+struct LineVertex {
+    pos: Vec3,
+    color: Vec3,
+}
 
 /// Axis-aligned bounding box passed to the debug renderer for wireframe drawing.
 pub struct DebugBox {
     pub min: Vec3,
     pub max: Vec3,
+    pub color: Color,
 }
 
-/// Renders wireframe AABB overlays on the final draw image. Toggled at runtime
+/// World-space line segment passed to the debug renderer, e.g. a piece of a spline.
+pub struct DebugLine {
+    pub from: Vec3,
+    pub to: Vec3,
+    pub color: Color,
+}
+
+/// Renders wireframe AABB and line overlays on the final draw image. Toggled at runtime
 /// with `toggle()`. When disabled, `draw_frame` is a no-op.
 ///
-/// The pipeline and descriptor set are created lazily on the first draw call.
-/// `camera_buffer` and `model_buffer` are pre-allocated in `new()`. The model
+/// The pipeline and descriptor sets are created lazily on the first draw call.
+/// The camera buffers and `model_buffer` are pre-allocated in `new()`. The model
 /// matrix is always identity because AABBs are already in world space.
 pub struct AabbDebugRenderer {
     pub enabled: bool,
     pipeline: Option<PipelineHandle>,
-    vertex_buffer: Option<BufferHandle>,
-    camera_buffer: BufferHandle,
+    frames: FrameRing<LineFrame>,
     model_buffer: BufferHandle,
     descriptor_layout: Option<DescriptorLayoutHandle>,
-    descriptor_set: Option<DescriptorSetHandle>,
+    /// One per frame in flight, binding its camera buffer.
+    descriptor_sets: Option<FrameRing<DescriptorSetHandle>>,
+}
+
+/// The buffers one frame in flight writes.
+struct LineFrame {
+    camera_buffer: BufferHandle,
+    vertex_buffer: Option<BufferHandle>,
 }
 
 impl AabbDebugRenderer {
     /// Creates the renderer and pre-allocates the camera and model uniform buffers.
     pub fn new(vulkan_backend: &mut VulkanBackend) -> Self {
-        let camera_buffer = vulkan_backend.create_buffer::<CameraMvpUbo>(
-            BufferDesc {
-                size: size_of::<CameraMvpUbo>(),
-                usage: BufferUsageFlags::UNIFORM,
-                memory_hint: MemoryHint::CPUWritable,
-            },
-            None,
-        );
+        let frames = FrameRing::new(vulkan_backend, |vulkan_backend, _| LineFrame {
+            camera_buffer: vulkan_backend.create_buffer::<CameraMvpUbo>(
+                BufferDesc {
+                    size: size_of::<CameraMvpUbo>(),
+                    usage: BufferUsageFlags::UNIFORM,
+                    memory_hint: MemoryHint::CPUWritable,
+                },
+                None,
+            ),
+            vertex_buffer: None,
+        });
 
         let identity = Mat4::identity();
         let model_buffer = vulkan_backend.create_buffer::<Mat4>(
@@ -64,11 +92,10 @@ impl AabbDebugRenderer {
         Self {
             enabled: false,
             pipeline: None,
-            vertex_buffer: None,
-            camera_buffer,
+            frames,
             model_buffer,
             descriptor_layout: None,
-            descriptor_set: None,
+            descriptor_sets: None,
         }
     }
 
@@ -77,34 +104,44 @@ impl AabbDebugRenderer {
         self.enabled = !self.enabled;
     }
 
-    /// Draws wireframe boxes for each entry in `aabbs` on top of the current
-    /// draw image. Does nothing if the renderer is disabled or `aabbs` is empty.
+    /// Draws wireframe boxes for each entry in `aabbs`, and `lines`, on top of the
+    /// current draw image. Does nothing if the renderer is disabled or there is nothing
+    /// to draw.
     pub fn draw_frame(
         &mut self,
         vulkan_backend: &mut VulkanBackend,
         aabbs: &[DebugBox],
+        lines: &[DebugLine],
         frame_data: &FrameData,
         camera: CameraMvpUbo,
         shader_cache: &mut ShaderCache,
     ) {
-        if !self.enabled || aabbs.is_empty() {
+        if !self.enabled || (aabbs.is_empty() && lines.is_empty()) {
             return;
         }
 
-        vulkan_backend.update_buffer(self.camera_buffer, &[camera]);
-
         let (pipeline, descriptor_set) =
             self.get_or_create_pipeline(vulkan_backend, frame_data, shader_cache);
+        let frame = self.frames.current_mut(vulkan_backend);
+        vulkan_backend.update_buffer(frame.camera_buffer, &[camera]);
 
-        let vertices = aabb_to_line_vertices(aabbs);
+        let mut vertices = aabb_to_line_vertices(aabbs);
+        for line in lines {
+            let color = line.color.to_vec3();
+            vertices.push(LineVertex { pos: line.from, color });
+            vertices.push(LineVertex { pos: line.to, color });
+        }
         let vertex_count = vertices.len() as u32;
 
-        let needed_size = size_of::<Vertex>() * vertices.len();
-        let needs_new_buffer = self.vertex_buffer.is_none()
-            || vulkan_backend.buffer_size(self.vertex_buffer.unwrap()) < needed_size;
+        let needed_size = size_of::<LineVertex>() * vertices.len();
+        let needs_new_buffer = frame.vertex_buffer.is_none()
+            || vulkan_backend.buffer_size(frame.vertex_buffer.unwrap()) < needed_size;
 
         if needs_new_buffer {
-            let vb = vulkan_backend.create_buffer::<Vertex>(
+            if let Some(old) = frame.vertex_buffer {
+                vulkan_backend.release_buffer(old);
+            }
+            let vb = vulkan_backend.create_buffer::<LineVertex>(
                 BufferDesc {
                     size: needed_size,
                     usage: BufferUsageFlags::VERTEX_BUFFER,
@@ -112,17 +149,19 @@ impl AabbDebugRenderer {
                 },
                 Some(&vertices),
             );
-            self.vertex_buffer = Some(vb);
+            frame.vertex_buffer = Some(vb);
         } else {
-            vulkan_backend.update_buffer(self.vertex_buffer.unwrap(), &vertices);
+            vulkan_backend.update_buffer(frame.vertex_buffer.unwrap(), &vertices);
         }
 
+        vulkan_backend.push_pass_marker("AABB debug");
         vulkan_backend.begin_rendering_load(&[frame_data.frame_images.draw_image]);
         vulkan_backend.bind_pipeline(pipeline);
         vulkan_backend.bind_descriptor_sets(&[descriptor_set], pipeline);
-        vulkan_backend.bind_vertex_buffer(self.vertex_buffer.unwrap());
-        vulkan_backend.draw(vertex_count);
+        vulkan_backend.bind_vertex_buffer(frame.vertex_buffer.unwrap());
+        vulkan_backend.draw(vertex_count, 0);
         vulkan_backend.end_rendering();
+        vulkan_backend.pop_pass_marker();
     }
 
     fn get_or_create_pipeline(
@@ -131,8 +170,8 @@ impl AabbDebugRenderer {
         frame_data: &FrameData,
         shader_cache: &mut ShaderCache,
     ) -> (PipelineHandle, DescriptorSetHandle) {
-        if let (Some(pipeline), Some(descriptor_set)) = (self.pipeline, self.descriptor_set) {
-            return (pipeline, descriptor_set);
+        if let (Some(pipeline), Some(descriptor_sets)) = (self.pipeline, &self.descriptor_sets) {
+            return (pipeline, *descriptor_sets.current(vulkan_backend));
         }
 
         let layout = vulkan_backend.create_descriptor_layout(DescriptorLayoutDesc {
@@ -152,20 +191,24 @@ impl AabbDebugRenderer {
             ],
         });
 
-        let descriptor_set = vulkan_backend.allocate_descriptor_set(layout);
-        vulkan_backend.update_descriptor_set(
-            descriptor_set,
-            &[
-                DescriptorWriteDesc {
-                    binding: 0,
-                    value: DescriptorValue::UniformBuffer(self.camera_buffer),
-                },
-                DescriptorWriteDesc {
-                    binding: 1,
-                    value: DescriptorValue::UniformBuffer(self.model_buffer),
-                },
-            ],
-        );
+        let descriptor_sets = FrameRing::new(vulkan_backend, |vulkan_backend, slot| {
+            let descriptor_set = vulkan_backend.allocate_descriptor_set(layout);
+            vulkan_backend.update_descriptor_set(
+                descriptor_set,
+                &[
+                    DescriptorWriteDesc {
+                        binding: 0,
+                        value: DescriptorValue::UniformBuffer(self.frames.get(slot).camera_buffer),
+                    },
+                    DescriptorWriteDesc {
+                        binding: 1,
+                        value: DescriptorValue::UniformBuffer(self.model_buffer),
+                    },
+                ],
+            );
+            descriptor_set
+        });
+        let descriptor_set = *descriptor_sets.current(vulkan_backend);
 
         let vert_bytes = shader_cache.load(&ShaderRef::BuiltIn("line_debug_vert".into()), &[]);
         let frag_bytes = shader_cache.load(&ShaderRef::BuiltIn("line_debug_frag".into()), &[]);
@@ -174,6 +217,7 @@ impl AabbDebugRenderer {
             vertex_shader: vert_bytes,
             fragment_shader: Some(frag_bytes),
             topology: PrimitiveTopology::LineList,
+            specialization: SpecializationConstants::default(),
             color_attachments: vec![frame_data.frame_images.draw_image],
             depth_attachment: None,
             depth_stencil: DepthStencilDesc {
@@ -207,28 +251,42 @@ impl AabbDebugRenderer {
             layout: vec![layout],
             push_constant_ranges: vec![],
             vertex_input: VertexInputDesc {
-                bindings: vec![],
-                attributes: vec![],
+                bindings: vec![VertexBindingDesc {
+                    binding: 0,
+                    stride: size_of::<LineVertex>() as u32,
+                    input_rate: VertexInputRate::Vertex,
+                }],
+                attributes: vec![
+                    VertexAttributeDesc {
+                        location: 0,
+                        binding: 0,
+                        format: VertexFormat::Float32x3,
+                        offset: offset_of!(LineVertex, pos) as u32,
+                    },
+                    VertexAttributeDesc {
+                        location: 1,
+                        binding: 0,
+                        format: VertexFormat::Float32x3,
+                        offset: offset_of!(LineVertex, color) as u32,
+                    },
+                ],
             },
         };
 
         let pipeline = vulkan_backend.create_graphics_pipeline(pipeline_desc);
 
         self.descriptor_layout = Some(layout);
-        self.descriptor_set = Some(descriptor_set);
+        self.descriptor_sets = Some(descriptor_sets);
         self.pipeline = Some(pipeline);
 
         (pipeline, descriptor_set)
     }
 }
 
-fn aabb_to_line_vertices(aabbs: &[DebugBox]) -> Vec<Vertex> {
+fn aabb_to_line_vertices(aabbs: &[DebugBox]) -> Vec<LineVertex> {
     let mut vertices = Vec::with_capacity(aabbs.len() * 24);
-    let green = Vec3::new(0.0f32, 1.0, 0.0);
-    let tex = Vec2::new(0.0f32, 0.0);
-    let normal = Vec3::new(0.0f32, 0.0, 0.0);
-
     for aabb in aabbs {
+        let color = aabb.color.to_vec3();
         let bounds_min = aabb.min;
         let bounds_max = aabb.max;
 
@@ -250,8 +308,8 @@ fn aabb_to_line_vertices(aabbs: &[DebugBox]) -> Vec<Vertex> {
         ];
 
         for (a, b) in edges {
-            vertices.push(Vertex { pos: c[a], color: green, tex_coord: tex, normal, texture_index: 0 });
-            vertices.push(Vertex { pos: c[b], color: green, tex_coord: tex, normal, texture_index: 0 });
+            vertices.push(LineVertex { pos: c[a], color });
+            vertices.push(LineVertex { pos: c[b], color });
         }
     }
 
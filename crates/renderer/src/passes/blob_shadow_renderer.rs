@@ -0,0 +1,282 @@
+use crate::frame_data::FrameData;
+use crate::frame_ring::FrameRing;
+use crate::render_data::BlobShadowData;
+use crate::render_scene::RenderScene;
+use crate::shader_loader::ShaderCache;
+use material::ShaderRef;
+use nalgebra_glm::{Mat4, Vec4};
+use rendering_backend::backend_impl::vulkan_backend::VulkanBackend;
+use rendering_backend::buffer::{BufferDesc, BufferHandle, BufferUsageFlags};
+use rendering_backend::descriptor::{
+    DescriptorBinding, DescriptorLayoutDesc, DescriptorSetHandle, DescriptorType, DescriptorValue,
+    DescriptorWriteDesc, SampledImageInfo, ShaderStage,
+};
+use rendering_backend::gpu_layout::GpuStruct;
+use rendering_backend::memory::MemoryHint;
+use rendering_backend::pipeline::{
+    BlendAttachmentDesc, BlendFactor, BlendOp, BlendStateDesc, ColorWriteMask, CompareOp, CullMode,
+    DepthStencilDesc, FrontFace, PipelineDesc, PipelineHandle, PolygonMode, PrimitiveTopology,
+    RasterizationStateDesc, SpecializationConstants, VertexInputDesc,
+};
+use rendering_backend::sampler::{Filter, SamplerAddressMode, SamplerDesc};
+use rendering_backend::sync::ResourceState;
+
+/// Blobs the buffer holds at first; it doubles whenever a frame needs more.
+const INITIAL_CAPACITY: usize = 64;
+/// Vertices of one blob's box in `blob_shadow.vert`.
+const VERTICES_PER_BLOB: u32 = 36;
+
+#[repr(C)]
+#[derive(Clone, Copy, GpuStruct)]
+pub(crate) struct BlobShadowUbo {
+    view_proj: Mat4,
+    inverse_view_proj: Mat4,
+}
+
+/// One blob shadow as the shaders read it.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, GpuStruct)]
+#[gpu(std430)]
+struct GpuBlobShadow {
+    /// xyz: world-space location of the entity, w: how far below it the blob reaches.
+    position_reach: Vec4,
+    /// xyz: direction of the ellipse's length, w: opacity.
+    forward_opacity: Vec4,
+    /// x: half width, y: half length.
+    half_size: Vec4,
+}
+
+impl From<&BlobShadowData> for GpuBlobShadow {
+    fn from(data: &BlobShadowData) -> Self {
+        let shadow = &data.shadow;
+        let half_size = shadow.size.map(|extent| extent.max(1e-3) * 0.5);
+        Self {
+            position_reach: data.position.push(shadow.max_distance.max(1e-3)),
+            forward_opacity: data.forward.push(shadow.opacity.clamp(0.0, 1.0)),
+            half_size: Vec4::new(half_size.x, half_size.y, 0.0, 0.0),
+        }
+    }
+}
+
+/// Darkens the lit image under every [`BlobShadowData`] while shadow maps are off.
+///
+/// Each blob draws the back faces of a box around the volume it projects into, so it
+/// still shows with the camera inside. The fragment shader reconstructs the scene surface
+/// from the depth buffer and multiplies it by a soft ellipse. Resources are created on
+/// the first frame a blob is shown.
+pub struct BlobShadowRenderer {
+    resources: Option<BlobShadowResources>,
+}
+
+struct BlobShadowResources {
+    pipeline: PipelineHandle,
+    frames: FrameRing<BlobShadowFrame>,
+}
+
+/// The buffers of one frame in flight and the set binding them.
+struct BlobShadowFrame {
+    uniform_buffer: BufferHandle,
+    blob_buffer: BufferHandle,
+    /// Blobs `blob_buffer` holds.
+    capacity: usize,
+    descriptor_set: DescriptorSetHandle,
+}
+
+impl BlobShadowRenderer {
+    pub fn new() -> Self {
+        Self { resources: None }
+    }
+
+    pub fn draw_frame(
+        &mut self,
+        vulkan_backend: &mut VulkanBackend,
+        render_scene: &RenderScene,
+        frame_data: &FrameData,
+        shader_cache: &mut ShaderCache,
+    ) {
+        let (blobs, Some(camera)) = (&render_scene.blob_shadows, &render_scene.camera_data) else {
+            return;
+        };
+        if blobs.is_empty() {
+            return;
+        }
+        let view_proj = camera.proj * camera.view;
+        let ubo = BlobShadowUbo {
+            view_proj,
+            inverse_view_proj: view_proj.try_inverse().unwrap_or_else(Mat4::identity),
+        };
+        let gpu_blobs: Vec<GpuBlobShadow> = blobs.iter().map(GpuBlobShadow::from).collect();
+
+        let resources = self.get_or_create_resources(vulkan_backend, frame_data, shader_cache);
+        let pipeline = resources.pipeline;
+        let frame = resources.frames.current_mut(vulkan_backend);
+        frame.reserve(vulkan_backend, gpu_blobs.len());
+        vulkan_backend.update_buffer(frame.uniform_buffer, &[ubo]);
+        vulkan_backend.update_buffer(frame.blob_buffer, &gpu_blobs);
+        let descriptor_set = frame.descriptor_set;
+
+        let images = &frame_data.frame_images;
+        vulkan_backend.push_pass_marker("Blob shadows");
+        vulkan_backend.transition_image(images.gbuffer_depth, ResourceState::FragmentShaderRead);
+        vulkan_backend.begin_rendering_load(&[images.draw_image]);
+        vulkan_backend.bind_pipeline(pipeline);
+        vulkan_backend.bind_descriptor_sets(&[descriptor_set], pipeline);
+        vulkan_backend.draw(gpu_blobs.len() as u32 * VERTICES_PER_BLOB, 0);
+        vulkan_backend.end_rendering();
+        vulkan_backend.pop_pass_marker();
+    }
+
+    fn get_or_create_resources(
+        &mut self,
+        vulkan_backend: &mut VulkanBackend,
+        frame_data: &FrameData,
+        shader_cache: &mut ShaderCache,
+    ) -> &mut BlobShadowResources {
+        self.resources.get_or_insert_with(|| {
+            let images = &frame_data.frame_images;
+            let sampler = vulkan_backend.create_sampler(SamplerDesc {
+                mag_filter: Filter::Nearest,
+                min_filter: Filter::Nearest,
+                address_u: SamplerAddressMode::ClampToEdge,
+                address_v: SamplerAddressMode::ClampToEdge,
+                address_w: SamplerAddressMode::ClampToEdge,
+                compare_enable: false,
+                compare_op: None,
+            });
+            let layout = vulkan_backend.create_descriptor_layout(DescriptorLayoutDesc {
+                bindings: vec![
+                    DescriptorBinding {
+                        binding: 0,
+                        descriptor_type: DescriptorType::UniformBuffer,
+                        count: 1,
+                        stages: ShaderStage::VERTEX | ShaderStage::FRAGMENT,
+                    },
+                    DescriptorBinding {
+                        binding: 1,
+                        descriptor_type: DescriptorType::StorageBuffer,
+                        count: 1,
+                        stages: ShaderStage::VERTEX | ShaderStage::FRAGMENT,
+                    },
+                    DescriptorBinding {
+                        binding: 2,
+                        descriptor_type: DescriptorType::CombinedImageSampler,
+                        count: 1,
+                        stages: ShaderStage::FRAGMENT,
+                    },
+                ],
+            });
+            let frames = FrameRing::new(vulkan_backend, |vulkan_backend, _| {
+                let uniform_buffer = vulkan_backend.create_buffer::<BlobShadowUbo>(
+                    BufferDesc {
+                        size: size_of::<BlobShadowUbo>(),
+                        usage: BufferUsageFlags::UNIFORM,
+                        memory_hint: MemoryHint::CPUWritable,
+                    },
+                    None,
+                );
+                let blob_buffer = create_blob_buffer(vulkan_backend, INITIAL_CAPACITY);
+                let descriptor_set = vulkan_backend.allocate_descriptor_set(layout);
+                vulkan_backend.update_descriptor_set(
+                    descriptor_set,
+                    &[
+                        DescriptorWriteDesc {
+                            binding: 0,
+                            value: DescriptorValue::UniformBuffer(uniform_buffer),
+                        },
+                        DescriptorWriteDesc {
+                            binding: 1,
+                            value: DescriptorValue::StorageBuffer(blob_buffer),
+                        },
+                        DescriptorWriteDesc {
+                            binding: 2,
+                            value: DescriptorValue::SampledImage(SampledImageInfo {
+                                image: images.gbuffer_depth,
+                                sampler,
+                            }),
+                        },
+                    ],
+                );
+                BlobShadowFrame {
+                    uniform_buffer,
+                    blob_buffer,
+                    capacity: INITIAL_CAPACITY,
+                    descriptor_set,
+                }
+            });
+
+            let vert = shader_cache.load(&ShaderRef::BuiltIn("blob_shadow_vert".into()), &[]);
+            let frag = shader_cache.load(&ShaderRef::BuiltIn("blob_shadow_frag".into()), &[]);
+            let pipeline = vulkan_backend.create_graphics_pipeline(PipelineDesc {
+                vertex_shader: vert,
+                fragment_shader: Some(frag),
+                layout: vec![layout],
+                vertex_input: VertexInputDesc::default(),
+                rasterization: RasterizationStateDesc {
+                    cull_mode: CullMode::Front,
+                    depth_bias_enable: false,
+                    depth_clamp_enable: false,
+                    discard_enable: false,
+                    front_face: FrontFace::CounterClockwise,
+                    polygon_mode: PolygonMode::Fill,
+                },
+                // Multiplies the lit color by one minus the blob's darkness.
+                blend: Some(BlendStateDesc {
+                    logic_op_enable: false,
+                    attachments: vec![BlendAttachmentDesc {
+                        blend_enable: true,
+                        src_color_blend: BlendFactor::Zero,
+                        dst_color_blend: BlendFactor::OneMinusSrcAlpha,
+                        color_blend_op: BlendOp::Add,
+                        src_alpha_blend: BlendFactor::Zero,
+                        dst_alpha_blend: BlendFactor::One,
+                        alpha_blend_op: BlendOp::Add,
+                        color_write_mask: ColorWriteMask::ALL,
+                    }],
+                }),
+                depth_stencil: DepthStencilDesc {
+                    depth_test_enable: false,
+                    depth_write_enable: false,
+                    depth_compare_op: CompareOp::Always,
+                    depth_bounds_test_enable: false,
+                    stencil_test_enable: false,
+                },
+                color_attachments: vec![images.draw_image],
+                depth_attachment: None,
+                push_constant_ranges: vec![],
+                topology: PrimitiveTopology::TriangleList,
+                specialization: SpecializationConstants::default(),
+            });
+            BlobShadowResources { pipeline, frames }
+        })
+    }
+}
+
+impl BlobShadowFrame {
+    /// Grows the blob buffer to hold at least `blobs`.
+    fn reserve(&mut self, vulkan_backend: &mut VulkanBackend, blobs: usize) {
+        if blobs <= self.capacity {
+            return;
+        }
+        vulkan_backend.release_buffer(self.blob_buffer);
+        self.capacity = blobs.next_power_of_two();
+        self.blob_buffer = create_blob_buffer(vulkan_backend, self.capacity);
+        vulkan_backend.update_descriptor_set(
+            self.descriptor_set,
+            &[DescriptorWriteDesc {
+                binding: 1,
+                value: DescriptorValue::StorageBuffer(self.blob_buffer),
+            }],
+        );
+    }
+}
+
+fn create_blob_buffer(vulkan_backend: &mut VulkanBackend, capacity: usize) -> BufferHandle {
+    vulkan_backend.create_buffer::<GpuBlobShadow>(
+        BufferDesc {
+            size: size_of::<GpuBlobShadow>() * capacity,
+            usage: BufferUsageFlags::STORAGE,
+            memory_hint: MemoryHint::CPUWritable,
+        },
+        None,
+    )
+}
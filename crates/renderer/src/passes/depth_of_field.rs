@@ -0,0 +1,289 @@
+use crate::frame_data::{FrameData, Resolution};
+use crate::passes::create_fullscreen_pipeline;
+use crate::render_scene::RenderScene;
+use crate::shader_loader::ShaderCache;
+use core::post_process::DepthOfField;
+use material::ShaderRef;
+use nalgebra_glm::{Mat4, Vec4};
+use rendering_backend::backend_impl::vulkan_backend::VulkanBackend;
+use rendering_backend::descriptor::{
+    DescriptorBinding, DescriptorLayoutDesc, DescriptorSetHandle, DescriptorType, DescriptorValue,
+    DescriptorWriteDesc, SampledImageInfo, ShaderStage,
+};
+use rendering_backend::gpu_layout::GpuStruct;
+use rendering_backend::image::{
+    GpuImageHandle, ImageAspect, ImageDesc, ImageUsageFlags, TextureFormat,
+};
+use rendering_backend::pipeline::{
+    BlendAttachmentDesc, BlendFactor, BlendOp, BlendStateDesc, ColorWriteMask, PipelineHandle,
+    PushConstantDesc,
+};
+use rendering_backend::sampler::{Filter, SamplerAddressMode, SamplerDesc};
+use rendering_backend::sync::ResourceState;
+
+/// Shared by the three depth-of-field shaders.
+#[repr(C)]
+#[derive(Clone, Copy, GpuStruct)]
+#[gpu(std430)]
+pub(crate) struct DofPushConstants {
+    inverse_proj: Mat4,
+    /// x: focus distance, y: CoC scale in half-res pixels, z: largest CoC in half-res
+    /// pixels.
+    lens: Vec4,
+    /// xy: one half-res texel along the blur axis, in UV.
+    direction: Vec4,
+}
+
+/// Depth of field between lighting and the output encode. The scene is downsampled to
+/// half resolution with each pixel's circle of confusion, blurred horizontally then
+/// vertically, and blended back over the full-resolution image by the CoC. Resources are
+/// created on the first frame it is enabled.
+pub struct DepthOfFieldRenderer {
+    resources: Option<DofResources>,
+}
+
+struct DofResources {
+    coc_pipeline: PipelineHandle,
+    blur_pipeline: PipelineHandle,
+    composite_pipeline: PipelineHandle,
+    /// Half-resolution scene with the CoC in alpha, then the two blur results.
+    coc_image: GpuImageHandle,
+    blur_images: [GpuImageHandle; 2],
+    coc_set: DescriptorSetHandle,
+    blur_sets: [DescriptorSetHandle; 2],
+    composite_set: DescriptorSetHandle,
+}
+
+impl DepthOfFieldRenderer {
+    pub fn new() -> Self {
+        Self { resources: None }
+    }
+
+    pub fn draw_frame(
+        &mut self,
+        vulkan_backend: &mut VulkanBackend,
+        render_scene: &RenderScene,
+        frame_data: &FrameData,
+        shader_cache: &mut ShaderCache,
+        settings: &DepthOfField,
+    ) {
+        let Some(camera) = &render_scene.camera_data else {
+            return;
+        };
+        let images = &frame_data.frame_images;
+        let (_, height) = vulkan_backend.image_size(images.draw_image);
+        let coc_scale = settings.coc_scale(height as f32) / 2.0;
+        if !settings.enabled || coc_scale <= 0.0 {
+            return;
+        }
+        let resources = self.get_or_create_resources(vulkan_backend, frame_data, shader_cache);
+        let (half_width, half_height) = vulkan_backend.image_size(resources.coc_image);
+
+        let mut push = DofPushConstants {
+            inverse_proj: camera.proj.try_inverse().unwrap_or_else(Mat4::identity),
+            lens: Vec4::new(
+                settings.focus_distance,
+                coc_scale,
+                settings.max_blur_radius / 2.0,
+                0.0,
+            ),
+            direction: Vec4::zeros(),
+        };
+
+        vulkan_backend.push_pass_marker("Depth of field");
+        vulkan_backend.transition_image(images.draw_image, ResourceState::FragmentShaderRead);
+        vulkan_backend.transition_image(images.gbuffer_depth, ResourceState::FragmentShaderRead);
+        Self::fullscreen_pass(
+            vulkan_backend,
+            resources.coc_pipeline,
+            resources.coc_set,
+            resources.coc_image,
+            &push,
+        );
+
+        let directions = [
+            Vec4::new(1.0 / half_width as f32, 0.0, 0.0, 0.0),
+            Vec4::new(0.0, 1.0 / half_height as f32, 0.0, 0.0),
+        ];
+        let sources = [resources.coc_image, resources.blur_images[0]];
+        for axis in 0..2 {
+            push.direction = directions[axis];
+            vulkan_backend.transition_image(sources[axis], ResourceState::FragmentShaderRead);
+            Self::fullscreen_pass(
+                vulkan_backend,
+                resources.blur_pipeline,
+                resources.blur_sets[axis],
+                resources.blur_images[axis],
+                &push,
+            );
+        }
+
+        vulkan_backend
+            .transition_image(resources.blur_images[1], ResourceState::FragmentShaderRead);
+        vulkan_backend.begin_rendering_load(&[images.draw_image]);
+        vulkan_backend.bind_pipeline(resources.composite_pipeline);
+        vulkan_backend
+            .bind_descriptor_sets(&[resources.composite_set], resources.composite_pipeline);
+        vulkan_backend.update_push_constants(
+            resources.composite_pipeline,
+            ShaderStage::FRAGMENT,
+            &[push],
+        );
+        vulkan_backend.draw(3, 0);
+        vulkan_backend.end_rendering();
+        vulkan_backend.pop_pass_marker();
+    }
+
+    /// Draws a fullscreen triangle into the half-resolution `target`.
+    fn fullscreen_pass(
+        vulkan_backend: &mut VulkanBackend,
+        pipeline: PipelineHandle,
+        descriptor_set: DescriptorSetHandle,
+        target: GpuImageHandle,
+        push: &DofPushConstants,
+    ) {
+        let (width, height) = vulkan_backend.image_size(target);
+        vulkan_backend.begin_rendering_with_extent(&[target], None, width, height);
+        vulkan_backend.bind_pipeline(pipeline);
+        vulkan_backend.bind_descriptor_sets(&[descriptor_set], pipeline);
+        vulkan_backend.update_push_constants(pipeline, ShaderStage::FRAGMENT, &[*push]);
+        vulkan_backend.draw(3, 0);
+        vulkan_backend.end_rendering();
+    }
+
+    /// Reallocates the half-resolution images for a new render `resolution`, if they were
+    /// created. Returns the images whose descriptor sets need rewriting.
+    pub fn resize(
+        &mut self,
+        vulkan_backend: &mut VulkanBackend,
+        resolution: Resolution,
+    ) -> Vec<GpuImageHandle> {
+        let Some(resources) = &self.resources else {
+            return Vec::new();
+        };
+        let images = [
+            resources.coc_image,
+            resources.blur_images[0],
+            resources.blur_images[1],
+        ];
+        for image in images {
+            vulkan_backend.recreate_image(image, half_image_desc(resolution));
+        }
+        images.to_vec()
+    }
+
+    fn get_or_create_resources(
+        &mut self,
+        vulkan_backend: &mut VulkanBackend,
+        frame_data: &FrameData,
+        shader_cache: &mut ShaderCache,
+    ) -> &DofResources {
+        self.resources.get_or_insert_with(|| {
+            let images = &frame_data.frame_images;
+            let (width, height) = vulkan_backend.image_size(images.draw_image);
+            let mut half_image =
+                || vulkan_backend.create_image(half_image_desc(Resolution { width, height }));
+            let coc_image = half_image();
+            let blur_images = [half_image(), half_image()];
+
+            let sampler = vulkan_backend.create_sampler(SamplerDesc {
+                mag_filter: Filter::Linear,
+                min_filter: Filter::Linear,
+                address_u: SamplerAddressMode::ClampToEdge,
+                address_v: SamplerAddressMode::ClampToEdge,
+                address_w: SamplerAddressMode::ClampToEdge,
+                compare_enable: false,
+                compare_op: None,
+            });
+            // Binding 0 is the color source, binding 1 the depth buffer; the blur passes
+            // leave depth unused.
+            let layout = vulkan_backend.create_descriptor_layout(DescriptorLayoutDesc {
+                bindings: (0..2)
+                    .map(|binding| DescriptorBinding {
+                        binding,
+                        descriptor_type: DescriptorType::CombinedImageSampler,
+                        count: 1,
+                        stages: ShaderStage::FRAGMENT,
+                    })
+                    .collect(),
+            });
+            let mut descriptor_set = |color: GpuImageHandle| {
+                let set = vulkan_backend.allocate_descriptor_set(layout);
+                let writes = [color, images.gbuffer_depth].map(|image| {
+                    DescriptorValue::SampledImage(SampledImageInfo { image, sampler })
+                });
+                vulkan_backend.update_descriptor_set(
+                    set,
+                    &writes
+                        .into_iter()
+                        .enumerate()
+                        .map(|(binding, value)| DescriptorWriteDesc { binding, value })
+                        .collect::<Vec<_>>(),
+                );
+                set
+            };
+            let coc_set = descriptor_set(images.draw_image);
+            let blur_sets = [descriptor_set(coc_image), descriptor_set(blur_images[0])];
+            let composite_set = descriptor_set(blur_images[1]);
+
+            let blend = BlendStateDesc {
+                logic_op_enable: false,
+                attachments: vec![BlendAttachmentDesc {
+                    blend_enable: true,
+                    src_color_blend: BlendFactor::SrcAlpha,
+                    dst_color_blend: BlendFactor::OneMinusSrcAlpha,
+                    color_blend_op: BlendOp::Add,
+                    src_alpha_blend: BlendFactor::Zero,
+                    dst_alpha_blend: BlendFactor::One,
+                    alpha_blend_op: BlendOp::Add,
+                    color_write_mask: ColorWriteMask::R | ColorWriteMask::G | ColorWriteMask::B,
+                }],
+            };
+            let mut pipeline = |shader: &str, target: GpuImageHandle, blend: Option<_>| {
+                let quad_vert = shader_cache.load(&ShaderRef::BuiltIn("quad".into()), &[]);
+                let frag = shader_cache.load(&ShaderRef::BuiltIn(shader.into()), &[]);
+                let push_constants = PushConstantDesc {
+                    stages: ShaderStage::FRAGMENT,
+                    offset: 0,
+                    size: size_of::<DofPushConstants>(),
+                };
+                create_fullscreen_pipeline(
+                    vulkan_backend,
+                    quad_vert,
+                    frag,
+                    layout,
+                    Some(push_constants),
+                    target,
+                    blend,
+                )
+            };
+
+            DofResources {
+                coc_pipeline: pipeline("dof_coc", coc_image, None),
+                blur_pipeline: pipeline("dof_blur", blur_images[0], None),
+                composite_pipeline: pipeline("dof_composite", images.draw_image, Some(blend)),
+                coc_image,
+                blur_images,
+                coc_set,
+                blur_sets,
+                composite_set,
+            }
+        })
+    }
+}
+
+/// Half of `resolution`, rounded up, for the CoC and blur images.
+fn half_image_desc(resolution: Resolution) -> ImageDesc {
+    ImageDesc {
+        width: resolution.width.div_ceil(2),
+        height: resolution.height.div_ceil(2),
+        depth: 1,
+        format: TextureFormat::R16g16b16a16Float,
+        clear_value: None,
+        array_layers: 1,
+        is_cubemap: false,
+        mip_levels: 1,
+        aspect: ImageAspect::Color,
+        usage: ImageUsageFlags::COLOR_ATTACHMENT | ImageUsageFlags::SAMPLED,
+    }
+}
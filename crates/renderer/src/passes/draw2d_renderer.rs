@@ -0,0 +1,259 @@
+use crate::frame_data::FrameData;
+use crate::shader_loader::ShaderCache;
+use assets::AssetStore;
+use common::ImageHandle;
+use core::draw2d::{Draw2D, Draw2DVertex};
+use material::ShaderRef;
+use nalgebra_glm::{Vec2, Vec4};
+use rendering_backend::backend_impl::resource_manager::ResourceManager;
+use rendering_backend::backend_impl::vulkan_backend::VulkanBackend;
+use rendering_backend::buffer::{BufferDesc, BufferHandle, BufferUsageFlags};
+use rendering_backend::descriptor::{
+    DescriptorSetHandle, DescriptorValue, DescriptorWriteDesc, SampledImageInfo, ShaderStage,
+};
+use rendering_backend::memory::MemoryHint;
+use rendering_backend::pipeline::{
+    BlendAttachmentDesc, BlendFactor, BlendOp, BlendStateDesc, ColorWriteMask, CompareOp, CullMode,
+    DepthStencilDesc, FrontFace, PipelineDesc, PipelineHandle, PolygonMode, PrimitiveTopology,
+    PushConstantDesc, RasterizationStateDesc, SpecializationConstants, VertexAttributeDesc,
+    VertexBindingDesc, VertexFormat, VertexInputDesc, VertexInputRate,
+};
+use std::collections::HashMap;
+use std::mem::offset_of;
+
+/// Vertex layout read by `draw2d.vert`.
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+struct Draw2DGpuVertex {
+    pos: Vec2,
+    uv: Vec2,
+    color: Vec4,
+}
+
+impl From<&Draw2DVertex> for Draw2DGpuVertex {
+    fn from(vertex: &Draw2DVertex) -> Self {
+        Self {
+            pos: vertex.position,
+            uv: vertex.uv,
+            color: vertex.color,
+        }
+    }
+}
+
+/// Draws the frame's [`Draw2D`] shapes over the final image, alpha blended, with one
+/// pipeline and one draw per batch. Each batch binds its texture through the lightmap
+/// layout, a single sampled image; untextured batches bind the white default lightmap.
+/// The pipeline is created on the first frame with shapes to draw.
+pub struct Draw2DRenderer {
+    pipeline: Option<PipelineHandle>,
+    /// Indexed by frame slot, so a frame never overwrites vertices the GPU still reads.
+    vertex_buffers: Vec<Option<BufferHandle>>,
+    texture_sets: HashMap<ImageHandle, DescriptorSetHandle>,
+}
+
+impl Draw2DRenderer {
+    pub fn new() -> Self {
+        Self {
+            pipeline: None,
+            vertex_buffers: Vec::new(),
+            texture_sets: HashMap::new(),
+        }
+    }
+
+    /// `viewport` is the window size in logical pixels the shapes were placed in.
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw_frame(
+        &mut self,
+        vulkan_backend: &mut VulkanBackend,
+        draw2d: &Draw2D,
+        viewport: Vec2,
+        frame_data: &FrameData,
+        shader_cache: &mut ShaderCache,
+        resource_manager: &mut ResourceManager,
+        asset_store: &AssetStore,
+    ) {
+        if draw2d.is_empty() || viewport.x <= 0.0 || viewport.y <= 0.0 {
+            return;
+        }
+
+        let vertices: Vec<Draw2DGpuVertex> = draw2d.vertices().iter().map(Into::into).collect();
+        let needed_size = size_of::<Draw2DGpuVertex>() * vertices.len();
+        let slot = vulkan_backend.frame_slot();
+        self.vertex_buffers.resize(vulkan_backend.frames_in_flight(), None);
+        let vertex_buffer = &mut self.vertex_buffers[slot];
+        match *vertex_buffer {
+            Some(vb) if vulkan_backend.buffer_size(vb) >= needed_size => {
+                vulkan_backend.update_buffer(vb, &vertices);
+            }
+            old => {
+                if let Some(old) = old {
+                    vulkan_backend.release_buffer(old);
+                }
+                let vb = vulkan_backend.create_buffer::<Draw2DGpuVertex>(
+                    BufferDesc {
+                        size: needed_size,
+                        usage: BufferUsageFlags::VERTEX_BUFFER,
+                        memory_hint: MemoryHint::CPUWritable,
+                    },
+                    Some(&vertices),
+                );
+                *vertex_buffer = Some(vb);
+            }
+        }
+        let vertex_buffer = vertex_buffer.expect("filled above");
+
+        let pipeline = self.get_or_create_pipeline(vulkan_backend, frame_data, shader_cache);
+        let sets = draw2d
+            .batches()
+            .iter()
+            .map(|batch| match batch.texture {
+                Some(texture) => self.texture_set(
+                    vulkan_backend,
+                    texture,
+                    frame_data,
+                    resource_manager,
+                    asset_store,
+                ),
+                None => frame_data.default_lightmap_set,
+            })
+            .collect::<Vec<_>>();
+
+        vulkan_backend.push_pass_marker("Draw2D");
+        vulkan_backend.begin_rendering_load(&[frame_data.frame_images.draw_image]);
+        vulkan_backend.bind_pipeline(pipeline);
+        vulkan_backend.update_push_constants(pipeline, ShaderStage::VERTEX, &[viewport]);
+        vulkan_backend.bind_vertex_buffer(vertex_buffer);
+        for (batch, set) in draw2d.batches().iter().zip(sets) {
+            vulkan_backend.bind_descriptor_sets(&[set], pipeline);
+            vulkan_backend.draw(batch.vertex_count, batch.first_vertex);
+        }
+        vulkan_backend.end_rendering();
+        vulkan_backend.pop_pass_marker();
+    }
+
+    /// Frees the set of a texture that was unloaded.
+    pub fn release(&mut self, vulkan_backend: &mut VulkanBackend, texture: ImageHandle) {
+        if let Some(set_handle) = self.texture_sets.remove(&texture) {
+            vulkan_backend.release_descriptor_set(set_handle);
+        }
+    }
+
+    fn texture_set(
+        &mut self,
+        vulkan_backend: &mut VulkanBackend,
+        texture: ImageHandle,
+        frame_data: &FrameData,
+        resource_manager: &mut ResourceManager,
+        asset_store: &AssetStore,
+    ) -> DescriptorSetHandle {
+        if let Some(&set_handle) = self.texture_sets.get(&texture) {
+            return set_handle;
+        }
+
+        let image_asset = asset_store
+            .get(texture)
+            .unwrap_or_else(|| panic!("No asset found for Draw2D texture: {}", texture.raw()));
+        let gpu_image = resource_manager.get_or_create_image(vulkan_backend, texture, image_asset);
+
+        let set_handle = vulkan_backend.allocate_descriptor_set(frame_data.lightmap_layout_handle);
+        vulkan_backend.update_descriptor_set(
+            set_handle,
+            &[DescriptorWriteDesc {
+                binding: 0,
+                value: DescriptorValue::SampledImage(SampledImageInfo {
+                    image: gpu_image,
+                    sampler: frame_data.lightmap_sampler,
+                }),
+            }],
+        );
+        self.texture_sets.insert(texture, set_handle);
+
+        set_handle
+    }
+
+    fn get_or_create_pipeline(
+        &mut self,
+        vulkan_backend: &mut VulkanBackend,
+        frame_data: &FrameData,
+        shader_cache: &mut ShaderCache,
+    ) -> PipelineHandle {
+        if let Some(pipeline) = self.pipeline {
+            return pipeline;
+        }
+
+        let vert_bytes = shader_cache.load(&ShaderRef::BuiltIn("draw2d_vert".into()), &[]);
+        let frag_bytes = shader_cache.load(&ShaderRef::BuiltIn("draw2d_frag".into()), &[]);
+
+        let pipeline = vulkan_backend.create_graphics_pipeline(PipelineDesc {
+            vertex_shader: vert_bytes,
+            fragment_shader: Some(frag_bytes),
+            topology: PrimitiveTopology::TriangleList,
+            specialization: SpecializationConstants::default(),
+            color_attachments: vec![frame_data.frame_images.draw_image],
+            depth_attachment: None,
+            depth_stencil: DepthStencilDesc {
+                depth_test_enable: false,
+                depth_write_enable: false,
+                depth_compare_op: CompareOp::Always,
+                depth_bounds_test_enable: false,
+                stencil_test_enable: false,
+            },
+            rasterization: RasterizationStateDesc {
+                polygon_mode: PolygonMode::Fill,
+                cull_mode: CullMode::None,
+                front_face: FrontFace::CounterClockwise,
+                depth_clamp_enable: false,
+                depth_bias_enable: false,
+                discard_enable: false,
+            },
+            blend: Some(BlendStateDesc {
+                logic_op_enable: false,
+                attachments: vec![BlendAttachmentDesc {
+                    blend_enable: true,
+                    src_color_blend: BlendFactor::SrcAlpha,
+                    dst_color_blend: BlendFactor::OneMinusSrcAlpha,
+                    color_blend_op: BlendOp::Add,
+                    src_alpha_blend: BlendFactor::One,
+                    dst_alpha_blend: BlendFactor::OneMinusSrcAlpha,
+                    alpha_blend_op: BlendOp::Add,
+                    color_write_mask: ColorWriteMask::ALL,
+                }],
+            }),
+            layout: vec![frame_data.lightmap_layout_handle],
+            push_constant_ranges: vec![PushConstantDesc {
+                stages: ShaderStage::VERTEX,
+                offset: 0,
+                size: size_of::<Vec2>(),
+            }],
+            vertex_input: VertexInputDesc {
+                bindings: vec![VertexBindingDesc {
+                    binding: 0,
+                    stride: size_of::<Draw2DGpuVertex>() as u32,
+                    input_rate: VertexInputRate::Vertex,
+                }],
+                attributes: vec![
+                    VertexAttributeDesc {
+                        location: 0,
+                        binding: 0,
+                        format: VertexFormat::Float32x2,
+                        offset: offset_of!(Draw2DGpuVertex, pos) as u32,
+                    },
+                    VertexAttributeDesc {
+                        location: 1,
+                        binding: 0,
+                        format: VertexFormat::Float32x2,
+                        offset: offset_of!(Draw2DGpuVertex, uv) as u32,
+                    },
+                    VertexAttributeDesc {
+                        location: 2,
+                        binding: 0,
+                        format: VertexFormat::Float32x4,
+                        offset: offset_of!(Draw2DGpuVertex, color) as u32,
+                    },
+                ],
+            },
+        });
+        self.pipeline = Some(pipeline);
+        pipeline
+    }
+}
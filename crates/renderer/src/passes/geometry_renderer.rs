@@ -1,177 +1,520 @@
 use crate::frame_data::FrameData;
-use crate::render_scene::{MaterialData, RenderScene};
+use crate::passes::gpu_culling::GpuCulling;
+use crate::render_scene::{MeshRenderData, RenderScene};
 use crate::shader_loader::ShaderCache;
+use common::VertexEncoding;
 use material::material_manager::MaterialVariant;
+use material::{ShaderFeatures, ShaderRef};
 use rendering_backend::backend_impl::vulkan_backend::VulkanBackend;
-use rendering_backend::descriptor::ShaderStage;
+use rendering_backend::buffer::BufferHandle;
+use rendering_backend::descriptor::{DescriptorLayoutHandle, ShaderStage};
+use rendering_backend::gpu_layout::has_output_location;
 use rendering_backend::pipeline::{
     BlendAttachmentDesc, BlendFactor, BlendOp, BlendStateDesc, ColorWriteMask, CompareOp, CullMode,
-    DepthStencilDesc, FrontFace, PipelineDesc, PipelineHandle, PolygonMode, PrimitiveTopology,
-    PushConstantDesc, RasterizationStateDesc, VertexInputDesc,
+    DepthStencilDesc, FrontFace, PendingPipelineHandle, PipelineCompileError, PipelineDesc,
+    PipelineHandle, PolygonMode, PrimitiveTopology, PushConstantDesc, RasterizationStateDesc,
+    SpecializationConstants, VertexInputDesc, MESH_SKIN_BINDING, MESH_VERTEX_BINDING,
 };
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 /// Byte offset of the fragment push constant block. The vertex block occupies
-/// bytes 0-7 (one u64 mesh index), but Vulkan push constant ranges must be
+/// bytes 0-7 (transform slot and joint offset), but Vulkan push constant ranges must be
 /// 16-byte aligned, so the fragment block starts here.
 const FRAGMENT_PUSH_CONSTANT_OFFSET: u32 = 16;
 
+/// Vertex features the built-in vertex shaders have permutations for; wind only together
+/// with vertex color. They read per-object data by instance index for every draw, so
+/// batched and direct draws share a pipeline.
+pub(crate) const BUILTIN_VERTEX_FEATURES: ShaderFeatures = ShaderFeatures::SKINNED
+    .union(ShaderFeatures::VERTEX_COLOR)
+    .union(ShaderFeatures::WIND);
+
+/// Fragment output location of the entity ID attachment. Fragment shaders that declare
+/// no output there leave the buffer untouched.
+pub(crate) const ENTITY_ID_LOCATION: u32 = 3;
+
+/// Pipeline permutation: the material's shaders and defines, the features they are
+/// compiled for, and the vertex encoding.
+type PipelineKey = (MaterialVariant, ShaderFeatures, VertexEncoding);
+
+/// What a pipeline must share with a permutation to draw its meshes in its place: the
+/// vertex features and encoding, the material set layout and the fragment push constant
+/// size.
+type PlaceholderKey = (ShaderFeatures, VertexEncoding, DescriptorLayoutHandle, usize);
+
+/// Geometry pass. Pipelines compile in the background the first time a permutation is
+/// drawn; until then its meshes are drawn with the last compiled permutation sharing
+/// their vertex layout and material set layout, usually the material's previous variant,
+/// or skipped if there is none. [`Self::prewarm`] compiles permutations ahead of time.
+/// A permutation whose compile fails keeps its placeholder and is not compiled again.
 pub struct GeometryRenderer {
-    pub pipeline_cache: HashMap<MaterialVariant, PipelineHandle>,
+    pub pipeline_cache: HashMap<PipelineKey, PipelineHandle>,
+    compiling: HashMap<PipelineKey, (PlaceholderKey, PendingPipelineHandle)>,
+    failed: HashSet<PipelineKey>,
+    placeholders: HashMap<PlaceholderKey, PipelineHandle>,
 }
 
 impl GeometryRenderer {
     pub fn new() -> Self {
         Self {
             pipeline_cache: HashMap::new(),
+            compiling: HashMap::new(),
+            failed: HashSet::new(),
+            placeholders: HashMap::new(),
+        }
+    }
+
+    /// Starts compiling the permutation `mesh_data` is drawn with, if it isn't compiled.
+    pub fn prewarm(
+        &mut self,
+        vulkan_backend: &mut VulkanBackend,
+        frame_data: &FrameData,
+        mesh_data: &MeshRenderData,
+        shader_cache: &mut ShaderCache,
+    ) {
+        for batched in [false, true] {
+            let features = shader_features(mesh_data, batched);
+            self.get_pipeline(vulkan_backend, frame_data, mesh_data, features, shader_cache);
         }
     }
 
+    /// Permutations still compiling.
+    pub fn compiling_count(&self) -> usize {
+        self.compiling.len()
+    }
+
+    /// Moves finished compiles into the cache.
+    pub fn poll_compiles(&mut self, vulkan_backend: &mut VulkanBackend) {
+        self.receive_compiles(|pending| vulkan_backend.compiled_pipeline(pending));
+    }
+
+    /// Moves the compiles `compiled` reports done into the cache, or into `failed`.
+    fn receive_compiles(
+        &mut self,
+        mut compiled: impl FnMut(
+            PendingPipelineHandle,
+        ) -> Option<Result<PipelineHandle, PipelineCompileError>>,
+    ) {
+        self.compiling.retain(|key, &mut (placeholder_key, pending)| {
+            match compiled(pending) {
+                None => return true,
+                Some(Ok(pipeline)) => {
+                    self.pipeline_cache.insert(key.clone(), pipeline);
+                    self.placeholders.insert(placeholder_key, pipeline);
+                }
+                Some(Err(error)) => {
+                    eprintln!(
+                        "Geometry pipeline for {:?} failed, drawing with a placeholder: {}",
+                        key.0.fragment_shader, error
+                    );
+                    self.failed.insert(key.clone());
+                }
+            }
+            false
+        });
+    }
+
     pub fn draw_frame(
         &mut self,
         vulkan_backend: &mut VulkanBackend,
         render_scene: &RenderScene,
         frame_data: &FrameData,
         shader_cache: &mut ShaderCache,
+        culling: &GpuCulling,
+        pipelines_used: Option<&mut Vec<Option<PipelineHandle>>>,
     ) {
+        self.poll_compiles(vulkan_backend);
+        vulkan_backend.push_pass_marker("GBuffer");
         vulkan_backend.begin_rendering(
-            &[frame_data.frame_images.gbuffer_albedo, frame_data.frame_images.gbuffer_normal],
+            &frame_data.frame_images.gbuffer_color_attachments(),
             Some(&frame_data.frame_images.gbuffer_depth),
         );
 
+        // Meshes the cull pass batched are drawn per batch below.
         for (index, mesh_data) in render_scene.meshes.iter().enumerate() {
-            let pipeline = self.get_or_create_pipeline(
+            if culling.batch_of(index).is_some() {
+                continue;
+            }
+            let pipeline = self.bind_mesh(
                 vulkan_backend,
                 frame_data,
-                &mesh_data.material_data,
+                mesh_data,
+                Some(mesh_data.transform_slot),
                 shader_cache,
             );
-
-            vulkan_backend.bind_pipeline(pipeline);
-            vulkan_backend.bind_descriptor_sets(
-                &[
-                    frame_data.descriptor_handle,
-                    mesh_data.material_data.descriptor_set_handle,
-                ],
-                pipeline,
+            if pipeline.is_none() {
+                continue;
+            }
+            vulkan_backend.draw_indexed(
+                mesh_data.mesh_data.index_count as u32,
+                mesh_data.mesh_data.first_index,
+                mesh_data.mesh_data.vertex_offset,
             );
+        }
 
-            vulkan_backend.update_push_constants(pipeline, ShaderStage::VERTEX, &[index]);
-
-            if !mesh_data.material_data.push_constant_data.is_empty() {
-                vulkan_backend.update_push_constants_raw(
-                    pipeline,
-                    ShaderStage::FRAGMENT,
-                    mesh_data.material_data.push_constant_data.as_slice(),
-                    FRAGMENT_PUSH_CONSTANT_OFFSET,
-                );
+        let mut batch_pipelines = Vec::with_capacity(culling.batches().len());
+        for (index, batch) in culling.batches().iter().enumerate() {
+            // The transform slot comes from each command's first instance.
+            let mesh_data = &render_scene.meshes[batch.mesh];
+            let pipeline =
+                self.bind_mesh(vulkan_backend, frame_data, mesh_data, None, shader_cache);
+            if pipeline.is_some() {
+                culling.draw_batch(vulkan_backend, index);
             }
+            batch_pipelines.push(pipeline);
+        }
 
-            vulkan_backend.bind_vertex_buffer(mesh_data.mesh_data.vertex_buffer);
-            vulkan_backend.bind_index_buffer(mesh_data.mesh_data.index_buffer);
-            vulkan_backend.draw_indexed(mesh_data.mesh_data.index_count as u32, 0);
+        if let Some(pipelines_used) = pipelines_used {
+            for (index, mesh_data) in render_scene.meshes.iter().enumerate() {
+                let pipeline = match culling.batch_of(index) {
+                    Some(batch) => batch_pipelines[batch],
+                    None => {
+                        let features = shader_features(mesh_data, false);
+                        self.get_pipeline(
+                            vulkan_backend,
+                            frame_data,
+                            mesh_data,
+                            features,
+                            shader_cache,
+                        )
+                    }
+                };
+                pipelines_used.push(pipeline);
+            }
         }
 
         vulkan_backend.end_rendering();
+        vulkan_backend.pop_pass_marker();
     }
 
-    fn get_or_create_pipeline(
+    /// Binds the pipeline, descriptor sets, push constants and buffers of `mesh_data`.
+    /// A direct draw finds its transform at `transform_slot`; batched draws, with `None`,
+    /// at the instance index of each command. Binds nothing and returns `None` while no
+    /// pipeline can draw the mesh.
+    fn bind_mesh(
         &mut self,
         vulkan_backend: &mut VulkanBackend,
         frame_data: &FrameData,
-        material_data: &MaterialData,
+        mesh_data: &MeshRenderData,
+        transform_slot: Option<u32>,
         shader_cache: &mut ShaderCache,
-    ) -> PipelineHandle {
-        if let Some(&pipeline) = self.pipeline_cache.get(&material_data.shader_variant) {
-            return pipeline;
-        }
+    ) -> Option<PipelineHandle> {
+        let features = shader_features(mesh_data, transform_slot.is_none());
+        let (extra_buffer, skin) = mesh_streams(mesh_data, features);
+        let pipeline =
+            self.get_pipeline(vulkan_backend, frame_data, mesh_data, features, shader_cache)?;
+        // Added to the draw's instance index to find the transform.
+        let object_index = transform_slot.unwrap_or(0);
 
-        let vert_bytes = shader_cache.load(
-            &material_data.shader_variant.vertex_shader,
-            &[], // vertex shader permutations are encoded in the name for built-ins
+        let frame_set = frame_data.buffers.current(vulkan_backend).descriptor_handle;
+        vulkan_backend.bind_pipeline(pipeline);
+        vulkan_backend.bind_descriptor_sets(
+            &[
+                frame_set,
+                mesh_data.material_data.descriptor_set_handle,
+                mesh_data.lightmap_set,
+            ],
+            pipeline,
         );
-        let frag_bytes = shader_cache.load(
-            &material_data.shader_variant.fragment_shader,
-            &material_data.shader_variant.active_defines,
+
+        vulkan_backend.update_push_constants(
+            pipeline,
+            ShaderStage::VERTEX,
+            // One element: only `size_of::<T>()` bytes are pushed.
+            &[[object_index, skin.map_or(0, |(_, offset)| offset)]],
         );
 
-        let mut push_constant_ranges = vec![PushConstantDesc {
-            offset: 0,
-            stages: ShaderStage::VERTEX,
-            size: size_of::<u64>(),
-        }];
-        if material_data.shader_variant.push_constant_size > 0 {
-            push_constant_ranges.push(PushConstantDesc {
-                offset: FRAGMENT_PUSH_CONSTANT_OFFSET,
-                stages: ShaderStage::FRAGMENT,
-                size: material_data.shader_variant.push_constant_size,
-            });
+        if !mesh_data.material_data.push_constant_data.is_empty() {
+            vulkan_backend.update_push_constants_raw(
+                pipeline,
+                ShaderStage::FRAGMENT,
+                mesh_data.material_data.push_constant_data.as_slice(),
+                FRAGMENT_PUSH_CONSTANT_OFFSET,
+            );
         }
 
-        let pipeline_desc = PipelineDesc {
-            vertex_shader: vert_bytes,
-            fragment_shader: Some(frag_bytes),
-            color_attachments: vec![
-                frame_data.frame_images.gbuffer_albedo,
-                frame_data.frame_images.gbuffer_normal,
-            ],
-            depth_attachment: Some(frame_data.frame_images.gbuffer_depth),
-            layout: vec![
-                frame_data.descriptor_layout_handle,
-                material_data.descriptor_layout_handle,
-            ],
-            depth_stencil: DepthStencilDesc {
-                depth_test_enable: true,
-                depth_write_enable: true,
-                depth_compare_op: CompareOp::Less,
-                depth_bounds_test_enable: false,
-                stencil_test_enable: false,
-            },
-            push_constant_ranges,
-            blend: Some(BlendStateDesc {
-                logic_op_enable: false,
-                attachments: vec![
-                    BlendAttachmentDesc {
-                        color_write_mask: ColorWriteMask::ALL,
-                        blend_enable: false,
-                        src_color_blend: BlendFactor::One,
-                        dst_color_blend: BlendFactor::Zero,
-                        color_blend_op: BlendOp::Add,
-                        src_alpha_blend: BlendFactor::One,
-                        dst_alpha_blend: BlendFactor::Zero,
-                        alpha_blend_op: BlendOp::Add,
-                    },
-                    BlendAttachmentDesc {
-                        color_write_mask: ColorWriteMask::ALL,
-                        blend_enable: false,
-                        src_color_blend: BlendFactor::One,
-                        dst_color_blend: BlendFactor::Zero,
-                        color_blend_op: BlendOp::Add,
-                        src_alpha_blend: BlendFactor::One,
-                        dst_alpha_blend: BlendFactor::Zero,
-                        alpha_blend_op: BlendOp::Add,
-                    },
-                ],
-            }),
-            rasterization: RasterizationStateDesc {
-                depth_clamp_enable: false,
-                depth_bias_enable: false,
-                discard_enable: false,
-                polygon_mode: PolygonMode::Fill,
-                cull_mode: CullMode::Back,
-                front_face: FrontFace::CounterClockwise,
-            },
-            vertex_input: VertexInputDesc {
-                bindings: vec![],
-                attributes: vec![],
+        match extra_buffer {
+            Some(extra_buffer) => vulkan_backend.bind_vertex_buffers(
+                MESH_VERTEX_BINDING,
+                &[mesh_data.mesh_data.vertex_buffer, extra_buffer],
+            ),
+            None => vulkan_backend.bind_vertex_buffer(mesh_data.mesh_data.vertex_buffer),
+        }
+        if let Some((skin_buffer, _)) = skin {
+            vulkan_backend.bind_vertex_buffers(MESH_SKIN_BINDING, &[skin_buffer]);
+        }
+        vulkan_backend.bind_index_buffer(mesh_data.mesh_data.index_buffer);
+        Some(pipeline)
+    }
+
+    /// The pipeline of `mesh_data`'s permutation with `features`, or while that compiles,
+    /// a compatible placeholder if there is one. Starts the compile on first use.
+    fn get_pipeline(
+        &mut self,
+        vulkan_backend: &mut VulkanBackend,
+        frame_data: &FrameData,
+        mesh_data: &MeshRenderData,
+        features: ShaderFeatures,
+        shader_cache: &mut ShaderCache,
+    ) -> Option<PipelineHandle> {
+        let material_data = &mesh_data.material_data;
+        let vertex_encoding = mesh_data.mesh_data.vertex_encoding;
+        let key = (material_data.shader_variant.clone(), features, vertex_encoding);
+        let placeholder_key = (
+            features & ShaderFeatures::VERTEX_STAGE,
+            vertex_encoding,
+            material_data.descriptor_layout_handle,
+            material_data.shader_variant.push_constant_size,
+        );
+        self.pipeline_or_placeholder(key, placeholder_key, || {
+            let pipeline_desc = pipeline_desc(frame_data, mesh_data, features, shader_cache);
+            vulkan_backend.compile_graphics_pipeline(pipeline_desc)
+        })
+    }
+
+    /// The pipeline of `key`, or its placeholder while it compiles or after its compile
+    /// failed. Calls `compile` to start the compile on first use.
+    fn pipeline_or_placeholder(
+        &mut self,
+        key: PipelineKey,
+        placeholder_key: PlaceholderKey,
+        compile: impl FnOnce() -> PendingPipelineHandle,
+    ) -> Option<PipelineHandle> {
+        if let Some(&pipeline) = self.pipeline_cache.get(&key) {
+            return Some(pipeline);
+        }
+        if !self.failed.contains(&key) {
+            self.compiling
+                .entry(key)
+                .or_insert_with(|| (placeholder_key, compile()));
+        }
+        self.placeholders.get(&placeholder_key).copied()
+    }
+}
+
+/// Geometry pass pipeline for `mesh_data`'s permutation with `features`. The vertex
+/// shader is selected by the vertex features; the fragment shader by the material's
+/// defines, which carry its fragment features along with any custom ones.
+fn pipeline_desc(
+    frame_data: &FrameData,
+    mesh_data: &MeshRenderData,
+    features: ShaderFeatures,
+    shader_cache: &mut ShaderCache,
+) -> PipelineDesc {
+    let material_data = &mesh_data.material_data;
+    let mut vertex_input = VertexInputDesc::encoded_mesh(mesh_data.mesh_data.vertex_encoding);
+    if features.contains(ShaderFeatures::VERTEX_COLOR) {
+        vertex_input = vertex_input.with_extras();
+    }
+    if features.contains(ShaderFeatures::SKINNED) {
+        vertex_input = vertex_input.with_skin();
+    }
+    let vertex_defines = (features & ShaderFeatures::VERTEX_STAGE).defines();
+    let vert_bytes =
+        shader_cache.load(&material_data.shader_variant.vertex_shader, &vertex_defines);
+    let frag_bytes = shader_cache.load(
+        &material_data.shader_variant.fragment_shader,
+        &material_data.shader_variant.active_defines,
+    );
+
+    let mut push_constant_ranges = vec![PushConstantDesc {
+        offset: 0,
+        stages: ShaderStage::VERTEX,
+        size: size_of::<[u32; 2]>(),
+    }];
+    if material_data.shader_variant.push_constant_size > 0 {
+        push_constant_ranges.push(PushConstantDesc {
+            offset: FRAGMENT_PUSH_CONSTANT_OFFSET,
+            stages: ShaderStage::FRAGMENT,
+            size: material_data.shader_variant.push_constant_size,
+        });
+    }
+
+    let opaque = BlendAttachmentDesc {
+        color_write_mask: ColorWriteMask::ALL,
+        blend_enable: false,
+        src_color_blend: BlendFactor::One,
+        dst_color_blend: BlendFactor::Zero,
+        color_blend_op: BlendOp::Add,
+        src_alpha_blend: BlendFactor::One,
+        dst_alpha_blend: BlendFactor::Zero,
+        alpha_blend_op: BlendOp::Add,
+    };
+    let mut blend_attachments = vec![opaque.clone(); 3];
+    if frame_data.frame_images.entity_ids.is_some() {
+        let writes_ids = has_output_location(&frag_bytes, ENTITY_ID_LOCATION)
+            .expect("fragment shader is not valid SPIR-V");
+        blend_attachments.push(BlendAttachmentDesc {
+            color_write_mask: if writes_ids {
+                ColorWriteMask::ALL
+            } else {
+                ColorWriteMask::empty()
             },
-            topology: PrimitiveTopology::TriangleList,
+            ..opaque
+        });
+    }
+
+    PipelineDesc {
+        vertex_shader: vert_bytes,
+        fragment_shader: Some(frag_bytes),
+        color_attachments: frame_data.frame_images.gbuffer_color_attachments(),
+        depth_attachment: Some(frame_data.frame_images.gbuffer_depth),
+        layout: vec![
+            frame_data.descriptor_layout_handle,
+            material_data.descriptor_layout_handle,
+            frame_data.lightmap_layout_handle,
+        ],
+        depth_stencil: DepthStencilDesc {
+            depth_test_enable: true,
+            depth_write_enable: true,
+            depth_compare_op: CompareOp::Less,
+            depth_bounds_test_enable: false,
+            stencil_test_enable: false,
+        },
+        push_constant_ranges,
+        blend: Some(BlendStateDesc {
+            logic_op_enable: false,
+            attachments: blend_attachments,
+        }),
+        rasterization: RasterizationStateDesc {
+            depth_clamp_enable: false,
+            depth_bias_enable: false,
+            discard_enable: false,
+            polygon_mode: PolygonMode::Fill,
+            cull_mode: CullMode::Back,
+            front_face: FrontFace::CounterClockwise,
+        },
+        vertex_input,
+        topology: PrimitiveTopology::TriangleList,
+        specialization: SpecializationConstants::default(),
+    }
+}
+
+/// Features `mesh_data` is drawn with, batched or not: its material's, and those of
+/// the mesh the vertex shader has permutations for. Only built-in vertex shaders have
+/// vertex permutations; custom ones get the plain mesh layout.
+fn shader_features(mesh_data: &MeshRenderData, batched: bool) -> ShaderFeatures {
+    let variant = &mesh_data.material_data.shader_variant;
+    let mut features =
+        ShaderFeatures::from_defines(&variant.active_defines) & ShaderFeatures::FRAGMENT_STAGE;
+    let mesh = &mesh_data.mesh_data;
+    let skinned = mesh.skin_buffer.is_some() && mesh_data.joint_offset.is_some();
+    features.set(ShaderFeatures::VERTEX_COLOR, mesh.extra_buffer.is_some());
+    features.set(ShaderFeatures::SKINNED, skinned);
+    // The sway weights are vertex colors, which come with the extras stream.
+    let wind = mesh_data.vegetation && mesh.extra_buffer.is_some() && !skinned;
+    features.set(ShaderFeatures::WIND, wind);
+    features.set(ShaderFeatures::INSTANCED, batched);
+
+    let vertex_features = match variant.vertex_shader {
+        ShaderRef::BuiltIn(_) => BUILTIN_VERTEX_FEATURES,
+        ShaderRef::Asset(_) => ShaderFeatures::empty(),
+    };
+    features & (vertex_features | ShaderFeatures::FRAGMENT_STAGE)
+}
+
+/// The extras stream and the skin stream with its joint offset that `mesh_data` is drawn
+/// with under `features`. Streams the permutation doesn't read are left unbound.
+fn mesh_streams(
+    mesh_data: &MeshRenderData,
+    features: ShaderFeatures,
+) -> (Option<BufferHandle>, Option<(BufferHandle, u32)>) {
+    let extra_buffer = mesh_data
+        .mesh_data
+        .extra_buffer
+        .filter(|_| features.contains(ShaderFeatures::VERTEX_COLOR));
+    let skin = mesh_data
+        .mesh_data
+        .skin_buffer
+        .zip(mesh_data.joint_offset)
+        .filter(|_| features.contains(ShaderFeatures::SKINNED));
+    (extra_buffer, skin)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(define: &str) -> PipelineKey {
+        let variant = MaterialVariant {
+            vertex_shader: ShaderRef::BuiltIn("vert".to_string()),
+            fragment_shader: ShaderRef::BuiltIn("pbr.frag".to_string()),
+            active_defines: vec![define.to_string()],
+            push_constant_size: 0,
+            binding_info: Vec::new(),
         };
+        (variant, ShaderFeatures::empty(), VertexEncoding::Full)
+    }
 
-        let pipeline_handle = vulkan_backend.create_graphics_pipeline(pipeline_desc);
-        self.pipeline_cache
-            .insert(material_data.shader_variant.clone(), pipeline_handle);
+    const PLACEHOLDER: PlaceholderKey = (
+        ShaderFeatures::empty(),
+        VertexEncoding::Full,
+        DescriptorLayoutHandle(0),
+        0,
+    );
 
-        pipeline_handle
+    fn pipeline(renderer: &mut GeometryRenderer, define: &str) -> Option<usize> {
+        renderer
+            .pipeline_or_placeholder(key(define), PLACEHOLDER, || {
+                panic!("'{define}' compiled again")
+            })
+            .map(|pipeline| pipeline.0)
     }
-}
\ No newline at end of file
+
+    /// Reports `pending` done with `result`, and every other compile still running.
+    fn finish(
+        renderer: &mut GeometryRenderer,
+        pending: usize,
+        result: Result<PipelineHandle, PipelineCompileError>,
+    ) {
+        let mut result = Some(result);
+        renderer.receive_compiles(|handle| {
+            if handle.0 == pending {
+                result.take()
+            } else {
+                None
+            }
+        });
+    }
+
+    #[test]
+    fn permutations_draw_with_a_placeholder_until_their_compile_is_done() {
+        let mut renderer = GeometryRenderer::new();
+        let first = renderer.pipeline_or_placeholder(key("A"), PLACEHOLDER, || {
+            PendingPipelineHandle(0)
+        });
+        // Nothing compatible has compiled yet, so the mesh is skipped.
+        assert!(first.is_none());
+        renderer.receive_compiles(|_| None);
+        assert_eq!(renderer.compiling_count(), 1);
+        assert_eq!(pipeline(&mut renderer, "A"), None);
+
+        finish(&mut renderer, 0, Ok(PipelineHandle(10)));
+        assert_eq!(renderer.compiling_count(), 0);
+        assert_eq!(pipeline(&mut renderer, "A"), Some(10));
+
+        let second = renderer.pipeline_or_placeholder(key("B"), PLACEHOLDER, || {
+            PendingPipelineHandle(1)
+        });
+        assert_eq!(second.map(|pipeline| pipeline.0), Some(10));
+        assert_eq!(pipeline(&mut renderer, "B"), Some(10));
+
+        finish(&mut renderer, 1, Ok(PipelineHandle(11)));
+        assert_eq!(pipeline(&mut renderer, "B"), Some(11));
+        assert_eq!(pipeline(&mut renderer, "A"), Some(10));
+    }
+
+    #[test]
+    fn failed_compiles_keep_the_placeholder_and_are_not_retried() {
+        let mut renderer = GeometryRenderer::new();
+        renderer.pipeline_or_placeholder(key("A"), PLACEHOLDER, || PendingPipelineHandle(0));
+        finish(&mut renderer, 0, Ok(PipelineHandle(10)));
+        renderer.pipeline_or_placeholder(key("B"), PLACEHOLDER, || PendingPipelineHandle(1));
+
+        let error = PipelineCompileError::Panicked("driver error".to_string());
+        finish(&mut renderer, 1, Err(error));
+        assert_eq!(renderer.compiling_count(), 0);
+        assert_eq!(pipeline(&mut renderer, "B"), Some(10));
+        assert!(!renderer.pipeline_cache.contains_key(&key("B")));
+    }
+}
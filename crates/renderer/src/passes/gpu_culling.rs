@@ -0,0 +1,476 @@
+use crate::frame_data::FrameData;
+use crate::frame_ring::FrameRing;
+use crate::render_scene::{MeshRenderData, RenderScene};
+use crate::shader_loader::ShaderCache;
+use core::types::frustum::{ClipDepth, Frustum};
+use material::ShaderRef;
+use nalgebra_glm::Vec4;
+use rendering_backend::backend_impl::vulkan_backend::VulkanBackend;
+use rendering_backend::buffer::{BufferDesc, BufferHandle, BufferUsageFlags};
+use rendering_backend::descriptor::{
+    DescriptorBinding, DescriptorLayoutDesc, DescriptorLayoutHandle, DescriptorSetHandle,
+    DescriptorType, DescriptorValue, DescriptorWriteDesc, ShaderStage,
+};
+use rendering_backend::gpu_layout::{GpuStruct, Padding};
+use rendering_backend::memory::MemoryHint;
+use rendering_backend::pipeline::{
+    ComputePipelineDesc, PipelineHandle, PushConstantDesc, SpecializationConstants,
+};
+use std::collections::HashMap;
+
+/// Invocations per workgroup of `gpu_culling.comp`.
+const WORKGROUP_SIZE: u32 = 64;
+
+/// One object to cull: the instance whose model matrix places it, and its draw.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, GpuStruct)]
+#[gpu(std430)]
+pub(crate) struct GpuCullObject {
+    transform_slot: u32,
+    draw: u32,
+}
+
+/// The draw shared by every object of one mesh within a batch.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, GpuStruct)]
+#[gpu(std430)]
+pub(crate) struct GpuCullDraw {
+    /// xyz: mesh-space center, w: radius of the mesh's bounding sphere.
+    bounds: Vec4,
+    index_count: u32,
+    first_index: u32,
+    vertex_offset: i32,
+    batch: u32,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, GpuStruct)]
+#[gpu(std430)]
+pub(crate) struct CullPushConstants {
+    /// World-space frustum planes, xyz: inward normal, w: distance.
+    planes: [Vec4; 6],
+    object_count: u32,
+    _padding: Padding<12>,
+}
+
+/// A range of indirect commands drawn with one call. Every command uses the buffers and
+/// pipeline of `mesh`, an index into the scene's meshes.
+#[derive(Clone, Copy, Debug)]
+pub struct CullBatch {
+    pub mesh: usize,
+    pub first_command: u32,
+    pub max_draws: u32,
+}
+
+/// GPU frustum culling for the geometry pass. Meshes drawn with a built-in, unskinned
+/// vertex shader are grouped into batches sharing vertex and index buffers, material,
+/// lightmap and vertex animation, so pooled meshes of one block share a batch. A
+/// compute pass tests each object's bounding sphere against the camera frustum and
+/// appends the visible ones to their batch's command range, counting them per batch.
+/// The geometry pass then issues one `vkCmdDrawIndexedIndirectCount` per batch, so its
+/// CPU cost follows the number of batches rather than objects.
+///
+/// Objects past the buffer capacity and meshes the pass cannot batch are drawn directly,
+/// and so is everything on devices without `drawIndirectCount`.
+pub struct GpuCulling {
+    /// False if the device cannot draw with a GPU-written count; nothing is batched then.
+    enabled: bool,
+    pipeline: PipelineHandle,
+    buffers: FrameRing<CullBuffers>,
+    capacity: usize,
+    batches: Vec<CullBatch>,
+    /// Batch of each scene mesh, `None` for meshes drawn directly.
+    mesh_batches: Vec<Option<usize>>,
+}
+
+/// The buffers of one frame's cull pass and the set binding them.
+struct CullBuffers {
+    descriptor_set: DescriptorSetHandle,
+    object_buffer: BufferHandle,
+    draw_buffer: BufferHandle,
+    batch_buffer: BufferHandle,
+    command_buffer: BufferHandle,
+    count_buffer: BufferHandle,
+}
+
+impl GpuCulling {
+    /// `capacity` bounds the objects and batches culled per frame.
+    pub fn new(
+        vulkan_backend: &mut VulkanBackend,
+        frame_data: &FrameData,
+        shader_cache: &mut ShaderCache,
+        capacity: usize,
+    ) -> Self {
+        let storage = |binding| DescriptorBinding {
+            binding,
+            descriptor_type: DescriptorType::StorageBuffer,
+            count: 1,
+            stages: ShaderStage::COMPUTE,
+        };
+        let descriptor_layout = vulkan_backend.create_descriptor_layout(DescriptorLayoutDesc {
+            bindings: (0..6).map(storage).collect(),
+        });
+        let buffers = FrameRing::new(vulkan_backend, |vulkan_backend, slot| {
+            let instance_buffer = frame_data.buffers.get(slot).instance_buffer;
+            CullBuffers::new(vulkan_backend, descriptor_layout, instance_buffer, capacity)
+        });
+
+        let pipeline = vulkan_backend.create_compute_pipeline(ComputePipelineDesc {
+            shader: shader_cache.load(&ShaderRef::BuiltIn("gpu_culling".into()), &[]),
+            layout: vec![descriptor_layout],
+            push_constant_ranges: vec![PushConstantDesc {
+                stages: ShaderStage::COMPUTE,
+                offset: 0,
+                size: size_of::<CullPushConstants>(),
+            }],
+            specialization: SpecializationConstants::default(),
+        });
+
+        Self {
+            enabled: vulkan_backend.capabilities().draw_indirect_count,
+            pipeline,
+            buffers,
+            capacity,
+            batches: Vec::new(),
+            mesh_batches: Vec::new(),
+        }
+    }
+
+    /// Batches of the last [`Self::cull`], in the order of their command ranges.
+    pub fn batches(&self) -> &[CullBatch] {
+        &self.batches
+    }
+
+    /// Batch the scene mesh at `mesh` was merged into, or `None` if it is drawn directly.
+    pub fn batch_of(&self, mesh: usize) -> Option<usize> {
+        self.mesh_batches.get(mesh).copied().flatten()
+    }
+
+    /// Groups the scene's meshes into batches, uploads them and records the cull pass.
+    /// Call between `begin_compute` and `submit_compute`; the frame submission waits for
+    /// it. Without a camera, or if the pass is disabled, nothing is batched.
+    pub fn cull(&mut self, vulkan_backend: &mut VulkanBackend, render_scene: &RenderScene) {
+        let camera = render_scene.camera_data.as_ref().filter(|_| self.enabled);
+        let Some(camera) = camera else {
+            self.batches.clear();
+            self.mesh_batches.clear();
+            return;
+        };
+        let (objects, draws) = self.build_batches(&render_scene.meshes);
+        if objects.is_empty() {
+            return;
+        }
+
+        let batches = self
+            .batches
+            .iter()
+            .map(|batch| batch.first_command)
+            .collect::<Vec<_>>();
+        let buffers = self.buffers.current(vulkan_backend);
+        vulkan_backend.update_buffer(buffers.object_buffer, &objects);
+        vulkan_backend.update_buffer(buffers.draw_buffer, &draws);
+        vulkan_backend.update_buffer(buffers.batch_buffer, &batches);
+        vulkan_backend.update_buffer(buffers.count_buffer, &vec![0u32; batches.len()]);
+
+        let frustum =
+            Frustum::from_view_proj(&(camera.proj * camera.view), ClipDepth::NegativeOneToOne);
+        let push_constants = CullPushConstants {
+            planes: frustum
+                .planes()
+                .map(|plane| plane.normal.push(plane.distance)),
+            object_count: objects.len() as u32,
+            _padding: Padding::default(),
+        };
+
+        vulkan_backend.push_pass_marker("GPU culling");
+        vulkan_backend.bind_pipeline(self.pipeline);
+        vulkan_backend.bind_descriptor_sets(&[buffers.descriptor_set], self.pipeline);
+        vulkan_backend.update_push_constants(
+            self.pipeline,
+            ShaderStage::COMPUTE,
+            &[push_constants],
+        );
+        vulkan_backend.dispatch((objects.len() as u32).div_ceil(WORKGROUP_SIZE), 1, 1);
+        vulkan_backend.pop_pass_marker();
+    }
+
+    /// Records the indirect draw of the visible objects of `batch`. The batch's mesh
+    /// bindings and pipeline must already be bound.
+    pub fn draw_batch(&self, vulkan_backend: &mut VulkanBackend, batch: usize) {
+        let CullBatch {
+            first_command,
+            max_draws,
+            ..
+        } = self.batches[batch];
+        let buffers = self.buffers.current(vulkan_backend);
+        vulkan_backend.draw_indexed_indirect_count(
+            buffers.command_buffer,
+            first_command,
+            buffers.count_buffer,
+            batch as u32,
+            max_draws,
+        );
+    }
+
+    /// Batching state with placeholder GPU handles, for tests that never record the pass.
+    #[cfg(test)]
+    pub(crate) fn without_gpu(capacity: usize) -> Self {
+        let buffer = BufferHandle(0);
+        Self {
+            enabled: true,
+            pipeline: PipelineHandle(0),
+            buffers: FrameRing::single(CullBuffers {
+                descriptor_set: DescriptorSetHandle(0),
+                object_buffer: buffer,
+                draw_buffer: buffer,
+                batch_buffer: buffer,
+                command_buffer: buffer,
+                count_buffer: buffer,
+            }),
+            capacity,
+            batches: Vec::new(),
+            mesh_batches: Vec::new(),
+        }
+    }
+
+    /// Fills `batches` and `mesh_batches` for `meshes` and returns the objects to cull
+    /// with their draws, each batch's objects given consecutive commands.
+    pub(crate) fn build_batches(
+        &mut self,
+        meshes: &[MeshRenderData],
+    ) -> (Vec<GpuCullObject>, Vec<GpuCullDraw>) {
+        self.batches.clear();
+        self.mesh_batches.clear();
+        let mut batch_keys = HashMap::new();
+        let mut draw_keys = HashMap::new();
+        let mut objects = Vec::new();
+        let mut draws = Vec::new();
+        for (index, mesh) in meshes.iter().enumerate() {
+            if !can_cull(mesh) || objects.len() == self.capacity {
+                self.mesh_batches.push(None);
+                continue;
+            }
+            let gpu_mesh = &mesh.mesh_data;
+            let key = (
+                gpu_mesh.vertex_buffer.0,
+                gpu_mesh.index_buffer.0,
+                gpu_mesh.extra_buffer.map(|buffer| buffer.0),
+                mesh.material_handle,
+                mesh.lightmap_set,
+                mesh.vegetation,
+            );
+            let batch = *batch_keys.entry(key).or_insert_with(|| {
+                self.batches.push(CullBatch {
+                    mesh: index,
+                    first_command: 0,
+                    max_draws: 0,
+                });
+                self.batches.len() - 1
+            });
+            let draw = *draw_keys
+                .entry((batch, mesh.mesh_handle))
+                .or_insert_with(|| {
+                    draws.push(GpuCullDraw {
+                        bounds: gpu_mesh.bounds,
+                        index_count: gpu_mesh.index_count as u32,
+                        first_index: gpu_mesh.first_index,
+                        vertex_offset: gpu_mesh.vertex_offset,
+                        batch: batch as u32,
+                    });
+                    draws.len() as u32 - 1
+                });
+            self.batches[batch].max_draws += 1;
+            self.mesh_batches.push(Some(batch));
+            objects.push(GpuCullObject {
+                transform_slot: mesh.transform_slot,
+                draw,
+            });
+        }
+
+        let mut first_command = 0;
+        for batch in &mut self.batches {
+            batch.first_command = first_command;
+            first_command += batch.max_draws;
+        }
+        (objects, draws)
+    }
+}
+
+impl CullBuffers {
+    fn new(
+        vulkan_backend: &mut VulkanBackend,
+        descriptor_layout: DescriptorLayoutHandle,
+        instance_buffer: BufferHandle,
+        capacity: usize,
+    ) -> Self {
+        let object_buffer = vulkan_backend.create_buffer::<GpuCullObject>(
+            BufferDesc {
+                size: size_of::<GpuCullObject>() * capacity,
+                usage: BufferUsageFlags::STORAGE,
+                memory_hint: MemoryHint::CPUWritable,
+            },
+            None,
+        );
+        let draw_buffer = vulkan_backend.create_buffer::<GpuCullDraw>(
+            BufferDesc {
+                size: size_of::<GpuCullDraw>() * capacity,
+                usage: BufferUsageFlags::STORAGE,
+                memory_hint: MemoryHint::CPUWritable,
+            },
+            None,
+        );
+        let batch_buffer = vulkan_backend.create_buffer::<u32>(
+            BufferDesc {
+                size: size_of::<u32>() * capacity,
+                usage: BufferUsageFlags::STORAGE,
+                memory_hint: MemoryHint::CPUWritable,
+            },
+            None,
+        );
+        // One `VkDrawIndexedIndirectCommand`, five 32-bit values, per object.
+        let command_buffer = vulkan_backend.create_buffer::<u8>(
+            BufferDesc {
+                size: size_of::<[u32; 5]>() * capacity,
+                usage: BufferUsageFlags::STORAGE | BufferUsageFlags::INDIRECT,
+                memory_hint: MemoryHint::GPUOnly,
+            },
+            None,
+        );
+        // Cleared from the CPU before each cull pass recorded in this frame slot.
+        let count_buffer = vulkan_backend.create_buffer::<u32>(
+            BufferDesc {
+                size: size_of::<u32>() * capacity,
+                usage: BufferUsageFlags::STORAGE | BufferUsageFlags::INDIRECT,
+                memory_hint: MemoryHint::CPUWritable,
+            },
+            None,
+        );
+
+        let descriptor_set = vulkan_backend.allocate_descriptor_set(descriptor_layout);
+        let buffers = [
+            instance_buffer,
+            object_buffer,
+            draw_buffer,
+            batch_buffer,
+            command_buffer,
+            count_buffer,
+        ];
+        let writes = buffers
+            .into_iter()
+            .enumerate()
+            .map(|(binding, buffer)| DescriptorWriteDesc {
+                binding,
+                value: DescriptorValue::StorageBuffer(buffer),
+            })
+            .collect::<Vec<_>>();
+        vulkan_backend.update_descriptor_set(descriptor_set, &writes);
+
+        Self {
+            descriptor_set,
+            object_buffer,
+            draw_buffer,
+            batch_buffer,
+            command_buffer,
+            count_buffer,
+        }
+    }
+}
+
+/// Only built-in vertex shaders take the transform slot from the draw's first instance,
+/// and skinned meshes need a per-object joint offset, so both are drawn directly.
+fn can_cull(mesh: &MeshRenderData) -> bool {
+    let skinned = mesh.mesh_data.skin_buffer.is_some() && mesh.joint_offset.is_some();
+    matches!(
+        mesh.material_data.shader_variant.vertex_shader,
+        ShaderRef::BuiltIn(_)
+    ) && !skinned
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn culling(capacity: usize) -> GpuCulling {
+        GpuCulling::without_gpu(capacity)
+    }
+
+    fn mesh(vertex_buffer: usize, mesh: u64, material: u64) -> MeshRenderData {
+        MeshRenderData::built_in(vertex_buffer, mesh, material)
+    }
+
+    #[test]
+    fn batches_are_keyed_by_buffers_and_material_and_share_draws_per_mesh() {
+        let meshes = [
+            mesh(1, 10, 100),
+            mesh(2, 20, 100),
+            // Another mesh pooled into the first mesh's buffers.
+            mesh(1, 11, 100),
+            mesh(1, 10, 101),
+            mesh(1, 10, 100),
+        ];
+        let mut culling = culling(64);
+        let (objects, draws) = culling.build_batches(&meshes);
+
+        assert_eq!(culling.batches().len(), 3);
+        let batches = (0..meshes.len())
+            .map(|mesh| culling.batch_of(mesh))
+            .collect::<Vec<_>>();
+        assert_eq!(batches, [Some(0), Some(1), Some(0), Some(2), Some(0)]);
+        assert_eq!(culling.batches()[2].mesh, 3);
+
+        // Objects of one mesh handle within a batch reuse its draw.
+        assert_eq!(draws.len(), 4);
+        let object_draws = objects.iter().map(|object| object.draw).collect::<Vec<_>>();
+        assert_eq!(object_draws, [0, 1, 2, 3, 0]);
+        assert_eq!(draws[2].first_index, 11 * 36);
+        assert_eq!(draws[3].batch, 2);
+    }
+
+    #[test]
+    fn command_ranges_follow_each_other_in_batch_order() {
+        let meshes = [
+            mesh(1, 10, 100),
+            mesh(2, 20, 100),
+            mesh(1, 10, 100),
+            mesh(3, 30, 100),
+            mesh(1, 10, 100),
+            mesh(2, 20, 100),
+        ];
+        let mut culling = culling(64);
+        culling.build_batches(&meshes);
+
+        let ranges = culling
+            .batches()
+            .iter()
+            .map(|batch| (batch.first_command, batch.max_draws))
+            .collect::<Vec<_>>();
+        assert_eq!(ranges, [(0, 3), (3, 2), (5, 1)]);
+    }
+
+    #[test]
+    fn meshes_past_capacity_or_not_cullable_are_drawn_directly() {
+        let mut custom = mesh(1, 10, 100);
+        custom.material_data.shader_variant.vertex_shader =
+            ShaderRef::Asset(common::Guid::from_u128(1));
+        let mut skinned = mesh(1, 10, 100);
+        skinned.mesh_data.skin_buffer = Some(BufferHandle(9));
+        skinned.joint_offset = Some(0);
+        let meshes = [
+            custom,
+            mesh(1, 10, 100),
+            skinned,
+            mesh(2, 20, 100),
+            mesh(1, 10, 100),
+        ];
+        let mut culling = culling(2);
+        let (objects, _) = culling.build_batches(&meshes);
+
+        assert_eq!(objects.len(), 2);
+        let batches = (0..meshes.len())
+            .map(|mesh| culling.batch_of(mesh))
+            .collect::<Vec<_>>();
+        assert_eq!(batches, [None, Some(0), None, Some(1), None]);
+        let draws = culling.batches().iter().map(|batch| batch.max_draws).sum::<u32>();
+        assert_eq!(draws, 2);
+    }
+}
@@ -0,0 +1,260 @@
+use crate::shader_loader::ShaderCache;
+use material::ShaderRef;
+use rendering_backend::backend_impl::vulkan_backend::VulkanBackend;
+use rendering_backend::descriptor::{
+    DescriptorBinding, DescriptorLayoutDesc, DescriptorSetHandle, DescriptorType, DescriptorValue,
+    DescriptorWriteDesc, SampledImageInfo, ShaderStage, StorageImageInfo,
+};
+use rendering_backend::image::{
+    GpuImageHandle, ImageAspect, ImageDesc, ImageUsageFlags, TextureFormat,
+};
+use rendering_backend::pipeline::{
+    ComputePipelineDesc, PipelineHandle, PushConstantDesc, SpecializationConstants,
+};
+use rendering_backend::sampler::{Filter, SamplerAddressMode, SamplerDesc, SamplerHandle};
+use rendering_backend::sync::ResourceState;
+
+/// Face size of the irradiance cube map. Irradiance varies slowly, so it can be tiny.
+const IRRADIANCE_SIZE: u32 = 32;
+/// Face size of mip 0 of the specular cube map.
+pub(crate) const SPECULAR_SIZE: u32 = 128;
+/// Mips of the specular cube map, roughness 0 to 1 in even steps.
+pub const SPECULAR_MIPS: u32 = 5;
+const BRDF_LUT_SIZE: u32 = 256;
+/// Invocations along each axis of a workgroup of the prefilter shaders.
+pub(crate) const WORKGROUP_SIZE: u32 = 8;
+
+/// Image-based lighting from the environment's skybox. When the skybox changes, compute
+/// passes prefilter it into a diffuse irradiance cube map and a specular cube map whose
+/// mips hold increasingly rough reflections. A split-sum BRDF table, independent of the
+/// skybox, is computed on the first frame. The lighting pass samples all three.
+pub struct ImageBasedLighting {
+    irradiance_pipeline: PipelineHandle,
+    specular_pipeline: PipelineHandle,
+    brdf_pipeline: PipelineHandle,
+    irradiance_set: DescriptorSetHandle,
+    /// One set per specular mip, each writing that mip.
+    specular_sets: Vec<DescriptorSetHandle>,
+    brdf_set: DescriptorSetHandle,
+    irradiance_map: GpuImageHandle,
+    specular_map: GpuImageHandle,
+    brdf_lut: GpuImageHandle,
+    sampler: SamplerHandle,
+    /// Skybox the cube maps were last prefiltered from.
+    source: Option<GpuImageHandle>,
+    brdf_baked: bool,
+}
+
+impl ImageBasedLighting {
+    pub fn new(vulkan_backend: &mut VulkanBackend, shader_cache: &mut ShaderCache) -> Self {
+        let cube_map = |vulkan_backend: &mut VulkanBackend, size, mip_levels| {
+            vulkan_backend.create_image(ImageDesc {
+                width: size,
+                height: size,
+                depth: 1,
+                mip_levels,
+                array_layers: 6,
+                is_cubemap: true,
+                format: TextureFormat::R16g16b16a16Float,
+                aspect: ImageAspect::Color,
+                usage: ImageUsageFlags::SAMPLED | ImageUsageFlags::STORAGE,
+                clear_value: None,
+            })
+        };
+        let irradiance_map = cube_map(vulkan_backend, IRRADIANCE_SIZE, 1);
+        let specular_map = cube_map(vulkan_backend, SPECULAR_SIZE, SPECULAR_MIPS);
+        let brdf_lut = vulkan_backend.create_image(ImageDesc {
+            width: BRDF_LUT_SIZE,
+            height: BRDF_LUT_SIZE,
+            depth: 1,
+            mip_levels: 1,
+            array_layers: 1,
+            is_cubemap: false,
+            format: TextureFormat::R16g16b16a16Float,
+            aspect: ImageAspect::Color,
+            usage: ImageUsageFlags::SAMPLED | ImageUsageFlags::STORAGE,
+            clear_value: None,
+        });
+
+        let sampler = vulkan_backend.create_sampler(SamplerDesc {
+            mag_filter: Filter::Linear,
+            min_filter: Filter::Linear,
+            address_u: SamplerAddressMode::ClampToEdge,
+            address_v: SamplerAddressMode::ClampToEdge,
+            address_w: SamplerAddressMode::ClampToEdge,
+            compare_enable: false,
+            compare_op: None,
+        });
+
+        let binding = |binding, descriptor_type| DescriptorBinding {
+            binding,
+            descriptor_type,
+            count: 1,
+            stages: ShaderStage::COMPUTE,
+        };
+        let prefilter_layout = vulkan_backend.create_descriptor_layout(DescriptorLayoutDesc {
+            bindings: vec![
+                binding(0, DescriptorType::CombinedImageSampler),
+                binding(1, DescriptorType::StorageImage),
+            ],
+        });
+        let brdf_layout = vulkan_backend.create_descriptor_layout(DescriptorLayoutDesc {
+            bindings: vec![binding(0, DescriptorType::StorageImage)],
+        });
+
+        let prefilter_set = |vulkan_backend: &mut VulkanBackend, image, mip_level| {
+            let set = vulkan_backend.allocate_descriptor_set(prefilter_layout);
+            vulkan_backend.update_descriptor_set(
+                set,
+                &[DescriptorWriteDesc {
+                    binding: 1,
+                    value: DescriptorValue::StorageImage(StorageImageInfo { image, mip_level }),
+                }],
+            );
+            set
+        };
+        let irradiance_set = prefilter_set(vulkan_backend, irradiance_map, 0);
+        let specular_sets = (0..SPECULAR_MIPS)
+            .map(|mip| prefilter_set(vulkan_backend, specular_map, mip))
+            .collect();
+        let brdf_set = vulkan_backend.allocate_descriptor_set(brdf_layout);
+        vulkan_backend.update_descriptor_set(
+            brdf_set,
+            &[DescriptorWriteDesc {
+                binding: 0,
+                value: DescriptorValue::StorageImage(StorageImageInfo {
+                    image: brdf_lut,
+                    mip_level: 0,
+                }),
+            }],
+        );
+
+        let mut compute_pipeline = |shader: &str, layout, push_constant_size| {
+            vulkan_backend.create_compute_pipeline(ComputePipelineDesc {
+                shader: shader_cache.load(&ShaderRef::BuiltIn(shader.into()), &[]),
+                layout: vec![layout],
+                push_constant_ranges: (push_constant_size > 0)
+                    .then_some(PushConstantDesc {
+                        stages: ShaderStage::COMPUTE,
+                        offset: 0,
+                        size: push_constant_size,
+                    })
+                    .into_iter()
+                    .collect(),
+                specialization: SpecializationConstants::default(),
+            })
+        };
+        let irradiance_pipeline = compute_pipeline("ibl_irradiance", prefilter_layout, 0);
+        let specular_pipeline =
+            compute_pipeline("ibl_specular", prefilter_layout, size_of::<f32>());
+        let brdf_pipeline = compute_pipeline("brdf_lut", brdf_layout, 0);
+
+        Self {
+            irradiance_pipeline,
+            specular_pipeline,
+            brdf_pipeline,
+            irradiance_set,
+            specular_sets,
+            brdf_set,
+            irradiance_map,
+            specular_map,
+            brdf_lut,
+            sampler,
+            source: None,
+            brdf_baked: false,
+        }
+    }
+
+    /// Descriptor writes binding the irradiance map, specular map and BRDF table, in that
+    /// order, at `first_binding` and the two bindings after it.
+    pub fn descriptor_writes(&self, first_binding: usize) -> Vec<DescriptorWriteDesc> {
+        [self.irradiance_map, self.specular_map, self.brdf_lut]
+            .into_iter()
+            .enumerate()
+            .map(|(index, image)| DescriptorWriteDesc {
+                binding: first_binding + index,
+                value: DescriptorValue::SampledImage(SampledImageInfo {
+                    image,
+                    sampler: self.sampler,
+                }),
+            })
+            .collect()
+    }
+
+    /// The prefiltered specular cube map, sampled by fragment shaders after the first
+    /// `update`.
+    pub fn specular_map(&self) -> GpuImageHandle {
+        self.specular_map
+    }
+
+    /// Bakes the BRDF table on the first call and prefilters `skybox` whenever it differs
+    /// from the last one. Records into the frame, so call it outside of rendering and
+    /// before the lighting pass; afterwards all three images can be sampled by fragment
+    /// shaders. Without a skybox the cube maps keep their contents.
+    pub fn update(&mut self, vulkan_backend: &mut VulkanBackend, skybox: Option<GpuImageHandle>) {
+        let changed = skybox.map(|image| image.0) != self.source.map(|image| image.0);
+        self.source = skybox;
+        if self.brdf_baked && !(changed && skybox.is_some()) {
+            return;
+        }
+
+        vulkan_backend.push_pass_marker("Image-based lighting");
+        if !self.brdf_baked {
+            vulkan_backend.transition_image(self.brdf_lut, ResourceState::ComputeShaderWrite);
+            vulkan_backend.bind_pipeline(self.brdf_pipeline);
+            vulkan_backend.bind_descriptor_sets(&[self.brdf_set], self.brdf_pipeline);
+            let groups = BRDF_LUT_SIZE.div_ceil(WORKGROUP_SIZE);
+            vulkan_backend.dispatch(groups, groups, 1);
+            vulkan_backend.transition_image(self.brdf_lut, ResourceState::FragmentShaderRead);
+            self.brdf_baked = true;
+        }
+
+        for cube_map in [self.irradiance_map, self.specular_map] {
+            vulkan_backend.transition_image(cube_map, ResourceState::ComputeShaderWrite);
+        }
+        if let Some(skybox) = skybox.filter(|_| changed) {
+            self.prefilter(vulkan_backend, skybox);
+        }
+        // Also gives the cube maps a sampled layout before the first skybox arrives.
+        for cube_map in [self.irradiance_map, self.specular_map] {
+            vulkan_backend.transition_image(cube_map, ResourceState::FragmentShaderRead);
+        }
+        vulkan_backend.pop_pass_marker();
+    }
+
+    fn prefilter(&self, vulkan_backend: &mut VulkanBackend, skybox: GpuImageHandle) {
+        let source = || DescriptorWriteDesc {
+            binding: 0,
+            value: DescriptorValue::SampledImage(SampledImageInfo {
+                image: skybox,
+                sampler: self.sampler,
+            }),
+        };
+        // Waits for the frames in flight that bound these sets; the skybox rarely changes.
+        vulkan_backend.update_descriptor_set(self.irradiance_set, &[source()]);
+        for &set in &self.specular_sets {
+            vulkan_backend.update_descriptor_set(set, &[source()]);
+        }
+        vulkan_backend.transition_image(skybox, ResourceState::ComputeShaderRead);
+
+        let groups = IRRADIANCE_SIZE.div_ceil(WORKGROUP_SIZE);
+        vulkan_backend.bind_pipeline(self.irradiance_pipeline);
+        vulkan_backend.bind_descriptor_sets(&[self.irradiance_set], self.irradiance_pipeline);
+        vulkan_backend.dispatch(groups, groups, 6);
+
+        vulkan_backend.bind_pipeline(self.specular_pipeline);
+        for (mip, &set) in self.specular_sets.iter().enumerate() {
+            let roughness = mip as f32 / (SPECULAR_MIPS - 1) as f32;
+            let groups = (SPECULAR_SIZE >> mip).div_ceil(WORKGROUP_SIZE);
+            vulkan_backend.bind_descriptor_sets(&[set], self.specular_pipeline);
+            vulkan_backend.update_push_constants(
+                self.specular_pipeline,
+                ShaderStage::COMPUTE,
+                &[roughness],
+            );
+            vulkan_backend.dispatch(groups, groups, 6);
+        }
+
+        vulkan_backend.transition_image(skybox, ResourceState::FragmentShaderRead);
+    }
+}
@@ -0,0 +1,265 @@
+use crate::frame_ring::FrameRing;
+use crate::render_data::{CameraRenderData, PointLightData};
+use crate::shader_loader::ShaderCache;
+use material::ShaderRef;
+use nalgebra_glm::{Mat4, Vec4};
+use rendering_backend::backend_impl::vulkan_backend::VulkanBackend;
+use rendering_backend::buffer::{BufferDesc, BufferHandle, BufferUsageFlags};
+use rendering_backend::descriptor::{
+    DescriptorBinding, DescriptorLayoutDesc, DescriptorLayoutHandle, DescriptorSetHandle,
+    DescriptorType, DescriptorValue, DescriptorWriteDesc, ShaderStage,
+};
+use rendering_backend::gpu_layout::GpuStruct;
+use rendering_backend::memory::MemoryHint;
+use rendering_backend::pipeline::{ComputePipelineDesc, PipelineHandle, SpecializationConstants};
+
+/// Froxel grid: screen tiles across, tiles down, and depth slices.
+pub const CLUSTER_GRID: [u32; 3] = [16, 9, 24];
+/// Lights beyond this count are ignored.
+pub const MAX_POINT_LIGHTS: usize = 1024;
+/// Each cluster's list holds its light count followed by up to this many light indices.
+pub const MAX_LIGHTS_PER_CLUSTER: u32 = 63;
+/// Invocations per workgroup along each axis of `light_clusters.comp`.
+const WORKGROUP_SIZE: u32 = 4;
+
+/// One entry of the point light storage buffer.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, GpuStruct)]
+#[gpu(std430)]
+pub struct GpuPointLight {
+    /// xyz: world-space position, w: range.
+    pub position_range: Vec4,
+    /// rgb: color, w: intensity.
+    pub color_intensity: Vec4,
+}
+
+/// Grid parameters, shared by the assignment pass and every pass that shades from the
+/// cluster lists.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, GpuStruct)]
+pub struct ClusterUbo {
+    pub view: Mat4,
+    pub inverse_proj: Mat4,
+    pub grid_x: u32,
+    pub grid_y: u32,
+    pub grid_z: u32,
+    pub light_count: u32,
+    /// x: near, y: far, z: slice scale, w: slice bias. A view depth `d` falls in slice
+    /// `log(d) * scale + bias`.
+    pub depth_slicing: Vec4,
+    /// x: 1 to replace shading with a per-cluster light count heatmap.
+    pub debug: Vec4,
+}
+
+/// Clustered light assignment. Each frame, a compute pass splits the view frustum into a
+/// [`CLUSTER_GRID`] of froxels (exponential depth slices) and writes the list of point
+/// lights touching each one.
+///
+/// The results live in one descriptor set per frame in flight: binding 0 is the
+/// [`ClusterUbo`], binding 1 the light buffer and binding 2 the per-cluster lists. Passes
+/// that shade point lights bind it with [`Self::descriptor_layout`] and
+/// [`Self::descriptor_set`].
+pub struct LightClusters {
+    pub debug_view: bool,
+    pipeline: PipelineHandle,
+    descriptor_layout: DescriptorLayoutHandle,
+    frames: FrameRing<ClusterFrame>,
+}
+
+/// The inputs and lists of one frame in flight. The lists are written on the compute
+/// queue, so each frame gets its own and assignment only waits for the frame that last
+/// used the same slot.
+struct ClusterFrame {
+    descriptor_set: DescriptorSetHandle,
+    params_buffer: BufferHandle,
+    light_buffer: BufferHandle,
+}
+
+impl LightClusters {
+    pub fn new(vulkan_backend: &mut VulkanBackend, shader_cache: &mut ShaderCache) -> Self {
+        let cluster_count = CLUSTER_GRID.iter().product::<u32>() as usize;
+
+        let stages = ShaderStage::COMPUTE | ShaderStage::FRAGMENT;
+        let descriptor_layout = vulkan_backend.create_descriptor_layout(DescriptorLayoutDesc {
+            bindings: vec![
+                DescriptorBinding {
+                    binding: 0,
+                    descriptor_type: DescriptorType::UniformBuffer,
+                    count: 1,
+                    stages,
+                },
+                DescriptorBinding {
+                    binding: 1,
+                    descriptor_type: DescriptorType::StorageBuffer,
+                    count: 1,
+                    stages,
+                },
+                DescriptorBinding {
+                    binding: 2,
+                    descriptor_type: DescriptorType::StorageBuffer,
+                    count: 1,
+                    stages,
+                },
+            ],
+        });
+        let frames = FrameRing::new(vulkan_backend, |vulkan_backend, _| {
+            let list_buffer = vulkan_backend.create_buffer::<u32>(
+                BufferDesc {
+                    size: size_of::<u32>()
+                        * cluster_count
+                        * (MAX_LIGHTS_PER_CLUSTER as usize + 1),
+                    usage: BufferUsageFlags::STORAGE,
+                    memory_hint: MemoryHint::GPUOnly,
+                },
+                None,
+            );
+            let params_buffer = vulkan_backend.create_buffer::<ClusterUbo>(
+                BufferDesc {
+                    size: size_of::<ClusterUbo>(),
+                    usage: BufferUsageFlags::UNIFORM,
+                    memory_hint: MemoryHint::CPUWritable,
+                },
+                None,
+            );
+            let light_buffer = vulkan_backend.create_buffer::<GpuPointLight>(
+                BufferDesc {
+                    size: size_of::<GpuPointLight>() * MAX_POINT_LIGHTS,
+                    usage: BufferUsageFlags::STORAGE,
+                    memory_hint: MemoryHint::CPUWritable,
+                },
+                None,
+            );
+            let descriptor_set = vulkan_backend.allocate_descriptor_set(descriptor_layout);
+            vulkan_backend.update_descriptor_set(
+                descriptor_set,
+                &[
+                    DescriptorWriteDesc {
+                        binding: 0,
+                        value: DescriptorValue::UniformBuffer(params_buffer),
+                    },
+                    DescriptorWriteDesc {
+                        binding: 1,
+                        value: DescriptorValue::StorageBuffer(light_buffer),
+                    },
+                    DescriptorWriteDesc {
+                        binding: 2,
+                        value: DescriptorValue::StorageBuffer(list_buffer),
+                    },
+                ],
+            );
+            ClusterFrame {
+                descriptor_set,
+                params_buffer,
+                light_buffer,
+            }
+        });
+
+        let pipeline = vulkan_backend.create_compute_pipeline(ComputePipelineDesc {
+            shader: shader_cache.load(&ShaderRef::BuiltIn("light_clusters".into()), &[]),
+            layout: vec![descriptor_layout],
+            push_constant_ranges: vec![],
+            specialization: SpecializationConstants::default(),
+        });
+
+        Self {
+            debug_view: false,
+            pipeline,
+            descriptor_layout,
+            frames,
+        }
+    }
+
+    pub fn descriptor_layout(&self) -> DescriptorLayoutHandle {
+        self.descriptor_layout
+    }
+
+    /// The set of the frame being recorded.
+    pub fn descriptor_set(&self, vulkan_backend: &mut VulkanBackend) -> DescriptorSetHandle {
+        self.frames.current(vulkan_backend).descriptor_set
+    }
+
+    /// Uploads the lights and records the assignment pass. Call between `begin_compute`
+    /// and `submit_compute`; the frame submission waits for it.
+    pub fn assign(
+        &self,
+        vulkan_backend: &mut VulkanBackend,
+        camera: &CameraRenderData,
+        point_lights: &[PointLightData],
+    ) {
+        let lights: Vec<GpuPointLight> = point_lights
+            .iter()
+            .take(MAX_POINT_LIGHTS)
+            .map(|light| GpuPointLight {
+                position_range: Vec4::new(
+                    light.position.x,
+                    light.position.y,
+                    light.position.z,
+                    light.range,
+                ),
+                color_intensity: Vec4::new(
+                    light.color.x,
+                    light.color.y,
+                    light.color.z,
+                    light.intensity,
+                ),
+            })
+            .collect();
+        let frame = self.frames.current(vulkan_backend);
+        let (descriptor_set, light_buffer, params_buffer) =
+            (frame.descriptor_set, frame.light_buffer, frame.params_buffer);
+        vulkan_backend.update_buffer(light_buffer, &lights);
+
+        let params = cluster_params(camera, lights.len() as u32, self.debug_view);
+        vulkan_backend.update_buffer(params_buffer, &[params]);
+
+        vulkan_backend.push_pass_marker("Light clusters");
+        vulkan_backend.bind_pipeline(self.pipeline);
+        vulkan_backend.bind_descriptor_sets(&[descriptor_set], self.pipeline);
+        let [x, y, z] = CLUSTER_GRID.map(|size| size.div_ceil(WORKGROUP_SIZE));
+        vulkan_backend.dispatch(x, y, z);
+        vulkan_backend.pop_pass_marker();
+    }
+}
+
+/// Grid parameters for `camera`, with depth slices spaced exponentially between its
+/// near and far planes so near clusters stay small.
+pub fn cluster_params(camera: &CameraRenderData, light_count: u32, debug_view: bool) -> ClusterUbo {
+    let [grid_x, grid_y, grid_z] = CLUSTER_GRID;
+    let (near, far) = (camera.near_clip, camera.far_clip);
+    let log_ratio = (far / near).ln();
+    let scale = grid_z as f32 / log_ratio;
+    ClusterUbo {
+        view: camera.view,
+        inverse_proj: nalgebra_glm::inverse(&camera.proj),
+        grid_x,
+        grid_y,
+        grid_z,
+        light_count,
+        depth_slicing: Vec4::new(near, far, scale, -near.ln() * scale),
+        debug: Vec4::new(if debug_view { 1.0 } else { 0.0 }, 0.0, 0.0, 0.0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn depth_slices_span_near_to_far() {
+        let camera = CameraRenderData {
+            view: Mat4::identity(),
+            proj: Mat4::identity(),
+            near_clip: 0.1,
+            far_clip: 1000.0,
+            fov: 60.0,
+            aspect_ratio: 1.0,
+        };
+        let params = cluster_params(&camera, 0, false);
+        let slice = |depth: f32| depth.ln() * params.depth_slicing.z + params.depth_slicing.w;
+
+        assert!(slice(0.1).abs() < 1e-4);
+        assert!((slice(1000.0) - CLUSTER_GRID[2] as f32).abs() < 1e-3);
+        // Exponential: each slice covers the same depth ratio.
+        assert!((slice(1.0) - slice(0.1) - (slice(100.0) - slice(10.0))).abs() < 1e-3);
+    }
+}
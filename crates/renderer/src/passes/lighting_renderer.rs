@@ -1,67 +1,164 @@
-use crate::frame_data::FrameData;
-use crate::render_data::CameraRenderData;
+use crate::frame_data::{shadow_cascade_resolution, FrameData};
+use crate::frame_ring::FrameRing;
+use crate::passes::image_based_lighting::{ImageBasedLighting, SPECULAR_MIPS};
+use crate::passes::reflection_probes::{ReflectionProbes, MAX_REFLECTION_PROBES};
+use crate::render_data::AreaLightData;
 use crate::render_scene::RenderScene;
+use crate::shadows::CascadeShadows;
 use crate::shader_loader::ShaderCache;
-use material::ShaderRef;
-use nalgebra_glm::{self as glm, Mat4, Vec3, Vec4};
+use common::VertexEncoding;
+use config::config::{ShadowQuality, ShadowSettings, MAX_SHADOW_CASCADES};
+use core::environment::FogMode;
+use material::{ShaderFeatures, ShaderRef};
+use nalgebra_glm::{Mat4, Vec3, Vec4};
 use rendering_backend::backend_impl::vulkan_backend::VulkanBackend;
 use rendering_backend::buffer::{BufferDesc, BufferHandle, BufferUsageFlags};
 use rendering_backend::descriptor::{
-    DescriptorBinding, DescriptorLayoutDesc, DescriptorSetHandle,
+    DescriptorBinding, DescriptorLayoutDesc, DescriptorLayoutHandle, DescriptorSetHandle,
     DescriptorType, DescriptorValue, DescriptorWriteDesc, SampledImageInfo, ShaderStage,
 };
+use rendering_backend::gpu_layout::GpuStruct;
+use rendering_backend::image::{
+    ClearValue, GpuImageHandle, ImageAspect, ImageDesc, ImageUsageFlags, TextureFormat,
+};
 use rendering_backend::memory::MemoryHint;
 use rendering_backend::pipeline::{
     CompareOp, CullMode, DepthStencilDesc, FrontFace, PipelineDesc, PipelineHandle, PolygonMode,
-    PrimitiveTopology, PushConstantDesc, RasterizationStateDesc, VertexInputDesc,
+    PrimitiveTopology, PushConstantDesc, RasterizationStateDesc, SpecializationConstants,
+    VertexInputDesc, MESH_SKIN_BINDING,
 };
 use rendering_backend::sampler::{Filter, SamplerAddressMode, SamplerDesc, SamplerHandle};
-
-const CASCADE_COUNT: usize = 4;
-const CASCADE_RESOLUTIONS: [u32; CASCADE_COUNT] = [2048, 2048, 1024, 1024];
-const SHADOW_DISTANCE: f32 = 100.0;
+use rendering_backend::sync::ResourceState;
+use std::collections::HashMap;
+
+/// Cascade slots allocated in the cascade buffer and lighting descriptor set.
+const CASCADE_SLOTS: usize = MAX_SHADOW_CASCADES as usize;
+/// `constant_id` of `PCF_RADIUS` in lighting.frag.
+const PCF_RADIUS_CONSTANT_ID: u32 = 0;
+/// First lighting set binding of the reflection probe cube maps, followed by their
+/// uniform buffer.
+const REFLECTION_PROBE_BINDING: usize = 19;
+/// Lighting set binding of the [`AreaLightUbo`].
+const AREA_LIGHT_BINDING: usize = 24;
+/// Area lights beyond this count are ignored.
+pub const MAX_AREA_LIGHTS: usize = 16;
 
 #[repr(C)]
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, GpuStruct)]
 pub struct LightingUbo {
     pub light_direction: Vec4,
     pub light_color: Vec4,
     pub ambient_light: Vec4,
     pub cascade_depths: Vec4,
+    pub cascade_resolutions: Vec4,
+    /// x: active cascade count, y: unused, z: cascade blend fraction. The PCF radius is
+    /// baked into the lighting pipeline.
+    pub shadow_params: Vec4,
+    /// x: receiver bias, y: normal-offset bias in texels, z: PCSS light size, w: PCSS enabled.
+    pub shadow_bias: Vec4,
+    /// rgb: fog color, w: fog mode (0 off, 1 linear, 2 exponential, 3 exponential squared).
+    pub fog_color: Vec4,
+    /// x: density, y: linear start, z: linear end.
+    pub fog_params: Vec4,
+    /// x: 1 when a skybox is bound, y: last mip of the prefiltered specular map.
+    pub sky_params: Vec4,
 }
 
-#[derive(Clone, Copy)]
-pub struct Cascade {
-    pub view_proj: Mat4,
-    pub depth: f32,
+/// Rectangular area lights, shaded by every lighting pass fragment they reach. Diffuse
+/// only: there is no GGX LTC specular term or lookup table yet.
+#[repr(C)]
+#[derive(Clone, Copy, GpuStruct)]
+pub struct AreaLightUbo {
+    /// xyz: center, w: range.
+    pub center_range: [Vec4; MAX_AREA_LIGHTS],
+    /// xyz: from the center to the middle of the right edge, w: 1 when two-sided.
+    pub half_right: [Vec4; MAX_AREA_LIGHTS],
+    /// xyz: from the center to the middle of the top edge.
+    pub half_up: [Vec4; MAX_AREA_LIGHTS],
+    /// rgb: color, w: intensity.
+    pub color_intensity: [Vec4; MAX_AREA_LIGHTS],
+    /// x: light count.
+    pub params: Vec4,
+}
+
+impl AreaLightUbo {
+    fn new(lights: &[AreaLightData]) -> Self {
+        let mut ubo = Self {
+            center_range: [Vec4::zeros(); MAX_AREA_LIGHTS],
+            half_right: [Vec4::zeros(); MAX_AREA_LIGHTS],
+            half_up: [Vec4::zeros(); MAX_AREA_LIGHTS],
+            color_intensity: [Vec4::zeros(); MAX_AREA_LIGHTS],
+            params: Vec4::zeros(),
+        };
+        let lights = &lights[..lights.len().min(MAX_AREA_LIGHTS)];
+        for (index, light) in lights.iter().enumerate() {
+            let two_sided = if light.two_sided { 1.0 } else { 0.0 };
+            ubo.center_range[index] = light.center.push(light.range.max(1e-4));
+            ubo.half_right[index] = light.half_right.push(two_sided);
+            ubo.half_up[index] = light.half_up.push(0.0);
+            ubo.color_intensity[index] = light.color.push(light.intensity);
+        }
+        ubo.params.x = lights.len() as f32;
+        ubo
+    }
 }
 
 #[repr(C)]
-#[derive(Clone, Copy)]
-struct ShadowPushConstants {
+#[derive(Clone, Copy, GpuStruct)]
+#[gpu(std430)]
+pub(crate) struct ShadowPushConstants {
     object_index: u32,
     cascade_index: u32,
+    /// First joint of the mesh's palette; ignored by the static pipeline.
+    joint_offset: u32,
 }
 
 pub struct LightingRenderer {
-    shadow_pipeline: PipelineHandle,
+    /// Indexed by `VertexEncoding as usize`.
+    shadow_pipelines: [PipelineHandle; 2],
+    /// Deforms skinned meshes with the frame's joint palette before projecting them.
+    skinned_shadow_pipelines: [PipelineHandle; 2],
+    /// Lighting pipeline for the current PCF radius.
     lighting_pipeline: PipelineHandle,
+    /// Lighting pipelines by the PCF radius baked into them, compiled on first use.
+    lighting_pipelines: HashMap<u32, PipelineHandle>,
+    lighting_pipeline_desc: PipelineDesc,
+    frames: FrameRing<LightingFrame>,
+    shadow_sampler: SamplerHandle,
+    /// Non-comparison sampler used by the PCSS blocker search to read raw depth.
+    shadow_depth_sampler: SamplerHandle,
+    /// Bound to the skybox slot while the environment has no skybox.
+    default_skybox: GpuImageHandle,
+    /// Prefiltered skybox lighting, bound at 16-18 and used while a skybox is bound.
+    image_based_lighting: ImageBasedLighting,
+    /// Local reflections replacing the skybox's, bound from `REFLECTION_PROBE_BINDING`.
+    reflection_probes: ReflectionProbes,
+    shadow_settings: ShadowSettings,
+    cascade_shadows: CascadeShadows,
+}
+
+/// The uniforms of one frame in flight and the sets binding them.
+struct LightingFrame {
     cascade_buffer: BufferHandle,
     lighting_buffer: BufferHandle,
-    shadow_sampler: SamplerHandle,
+    area_light_buffer: BufferHandle,
     shadow_descriptor_set: DescriptorSetHandle,
     lighting_descriptor_set: DescriptorSetHandle,
+    /// Skybox in `lighting_descriptor_set`.
+    skybox: Option<GpuImageHandle>,
 }
 
-impl LightingRenderer {
-    pub fn new(
+impl LightingFrame {
+    fn new(
         vulkan_backend: &mut VulkanBackend,
         frame_data: &FrameData,
-        shader_cache: &mut ShaderCache,
+        slot: usize,
+        shadow_layout: DescriptorLayoutHandle,
+        lighting_layout: DescriptorLayoutHandle,
     ) -> Self {
         let cascade_buffer = vulkan_backend.create_buffer::<Mat4>(
             BufferDesc {
-                size: size_of::<Mat4>() * CASCADE_COUNT,
+                size: size_of::<Mat4>() * CASCADE_SLOTS,
                 usage: BufferUsageFlags::UNIFORM,
                 memory_hint: MemoryHint::CPUWritable,
             },
@@ -77,6 +174,54 @@ impl LightingRenderer {
             None,
         );
 
+        let area_light_buffer = vulkan_backend.create_buffer::<AreaLightUbo>(
+            BufferDesc {
+                size: size_of::<AreaLightUbo>(),
+                usage: BufferUsageFlags::UNIFORM,
+                memory_hint: MemoryHint::CPUWritable,
+            },
+            None,
+        );
+
+        let frame_buffers = frame_data.buffers.get(slot);
+        let shadow_descriptor_set = vulkan_backend.allocate_descriptor_set(shadow_layout);
+        vulkan_backend.update_descriptor_set(
+            shadow_descriptor_set,
+            &[
+                DescriptorWriteDesc {
+                    binding: 0,
+                    value: DescriptorValue::UniformBuffer(cascade_buffer),
+                },
+                DescriptorWriteDesc {
+                    binding: 1,
+                    value: DescriptorValue::StorageBuffer(frame_buffers.instance_buffer),
+                },
+                DescriptorWriteDesc {
+                    binding: 2,
+                    value: DescriptorValue::StorageBuffer(frame_buffers.joint_buffer),
+                },
+            ],
+        );
+
+        Self {
+            cascade_buffer,
+            lighting_buffer,
+            area_light_buffer,
+            shadow_descriptor_set,
+            lighting_descriptor_set: vulkan_backend.allocate_descriptor_set(lighting_layout),
+            skybox: None,
+        }
+    }
+}
+
+impl LightingRenderer {
+    pub fn new(
+        vulkan_backend: &mut VulkanBackend,
+        frame_data: &FrameData,
+        shader_cache: &mut ShaderCache,
+        shadow_settings: ShadowSettings,
+        cluster_layout: DescriptorLayoutHandle,
+    ) -> Self {
         let shadow_sampler = vulkan_backend.create_sampler(SamplerDesc {
             mag_filter: Filter::Linear,
             min_filter: Filter::Linear,
@@ -87,6 +232,16 @@ impl LightingRenderer {
             compare_op: Some(CompareOp::Less),
         });
 
+        let shadow_depth_sampler = vulkan_backend.create_sampler(SamplerDesc {
+            mag_filter: Filter::Nearest,
+            min_filter: Filter::Nearest,
+            address_u: SamplerAddressMode::ClampToEdge,
+            address_v: SamplerAddressMode::ClampToEdge,
+            address_w: SamplerAddressMode::ClampToEdge,
+            compare_enable: false,
+            compare_op: None,
+        });
+
         let shadow_descriptor_layout =
             vulkan_backend.create_descriptor_layout(DescriptorLayoutDesc {
                 bindings: vec![
@@ -102,26 +257,15 @@ impl LightingRenderer {
                         count: 1,
                         stages: ShaderStage::VERTEX,
                     },
+                    DescriptorBinding {
+                        binding: 2,
+                        descriptor_type: DescriptorType::StorageBuffer,
+                        count: 1,
+                        stages: ShaderStage::VERTEX,
+                    },
                 ],
             });
 
-        let shadow_descriptor_set =
-            vulkan_backend.allocate_descriptor_set(shadow_descriptor_layout);
-
-        vulkan_backend.update_descriptor_set(
-            shadow_descriptor_set,
-            &[
-                DescriptorWriteDesc {
-                    binding: 0,
-                    value: DescriptorValue::UniformBuffer(cascade_buffer),
-                },
-                DescriptorWriteDesc {
-                    binding: 1,
-                    value: DescriptorValue::StorageBuffer(frame_data.model_storage_buffer),
-                },
-            ],
-        );
-
         let lighting_descriptor_layout =
             vulkan_backend.create_descriptor_layout(DescriptorLayoutDesc {
                 bindings: vec![
@@ -185,18 +329,121 @@ impl LightingRenderer {
                         count: 1,
                         stages: ShaderStage::FRAGMENT,
                     },
-                ],
+                    DescriptorBinding {
+                        binding: 10,
+                        descriptor_type: DescriptorType::CombinedImageSampler,
+                        count: 1,
+                        stages: ShaderStage::FRAGMENT,
+                    },
+                    DescriptorBinding {
+                        binding: 11,
+                        descriptor_type: DescriptorType::CombinedImageSampler,
+                        count: 1,
+                        stages: ShaderStage::FRAGMENT,
+                    },
+                    DescriptorBinding {
+                        binding: 12,
+                        descriptor_type: DescriptorType::CombinedImageSampler,
+                        count: 1,
+                        stages: ShaderStage::FRAGMENT,
+                    },
+                    DescriptorBinding {
+                        binding: 13,
+                        descriptor_type: DescriptorType::CombinedImageSampler,
+                        count: 1,
+                        stages: ShaderStage::FRAGMENT,
+                    },
+                    DescriptorBinding {
+                        binding: 14,
+                        descriptor_type: DescriptorType::CombinedImageSampler,
+                        count: 1,
+                        stages: ShaderStage::FRAGMENT,
+                    },
+                    DescriptorBinding {
+                        binding: 15,
+                        descriptor_type: DescriptorType::CombinedImageSampler,
+                        count: 1,
+                        stages: ShaderStage::FRAGMENT,
+                    },
+                    DescriptorBinding {
+                        binding: 16,
+                        descriptor_type: DescriptorType::CombinedImageSampler,
+                        count: 1,
+                        stages: ShaderStage::FRAGMENT,
+                    },
+                    DescriptorBinding {
+                        binding: 17,
+                        descriptor_type: DescriptorType::CombinedImageSampler,
+                        count: 1,
+                        stages: ShaderStage::FRAGMENT,
+                    },
+                    DescriptorBinding {
+                        binding: 18,
+                        descriptor_type: DescriptorType::CombinedImageSampler,
+                        count: 1,
+                        stages: ShaderStage::FRAGMENT,
+                    },
+                ]
+                .into_iter()
+                .chain((0..MAX_REFLECTION_PROBES).map(|slot| DescriptorBinding {
+                    binding: (REFLECTION_PROBE_BINDING + slot) as u32,
+                    descriptor_type: DescriptorType::CombinedImageSampler,
+                    count: 1,
+                    stages: ShaderStage::FRAGMENT,
+                }))
+                .chain([
+                    DescriptorBinding {
+                        binding: (REFLECTION_PROBE_BINDING + MAX_REFLECTION_PROBES) as u32,
+                        descriptor_type: DescriptorType::UniformBuffer,
+                        count: 1,
+                        stages: ShaderStage::FRAGMENT,
+                    },
+                    DescriptorBinding {
+                        binding: AREA_LIGHT_BINDING as u32,
+                        descriptor_type: DescriptorType::UniformBuffer,
+                        count: 1,
+                        stages: ShaderStage::FRAGMENT,
+                    },
+                ])
+                .collect(),
             });
 
-        let lighting_descriptor_set =
-            vulkan_backend.allocate_descriptor_set(lighting_descriptor_layout);
+        let frames = FrameRing::new(vulkan_backend, |vulkan_backend, slot| {
+            LightingFrame::new(
+                vulkan_backend,
+                frame_data,
+                slot,
+                shadow_descriptor_layout,
+                lighting_descriptor_layout,
+            )
+        });
+        // Never sampled; the shader checks for a skybox first.
+        let default_skybox = vulkan_backend.create_image(ImageDesc {
+            width: 1,
+            height: 1,
+            depth: 1,
+            format: TextureFormat::R8g8b8a8Unorm,
+            clear_value: None,
+            array_layers: 0,
+            is_cubemap: false,
+            mip_levels: 0,
+            aspect: ImageAspect::Color,
+            usage: ImageUsageFlags::SAMPLED | ImageUsageFlags::TRANSFER_DST,
+        });
+        vulkan_backend.update_image_data(default_skybox, &[0, 0, 0, 255]);
+        let image_based_lighting = ImageBasedLighting::new(vulkan_backend, shader_cache);
+        let reflection_probes = ReflectionProbes::new(vulkan_backend, frame_data, shader_cache);
 
         let shadow_vert = shader_cache.load(&ShaderRef::BuiltIn("shadow".into()), &[]);
+        let skinned_shadow_vert = shader_cache.load(
+            &ShaderRef::BuiltIn("shadow".into()),
+            &ShaderFeatures::SKINNED.defines(),
+        );
         let quad_vert = shader_cache.load(&ShaderRef::BuiltIn("quad".into()), &[]);
         let lighting_frag = shader_cache.load(&ShaderRef::BuiltIn("lighting".into()), &[]);
 
-        let shadow_pipeline = vulkan_backend.create_graphics_pipeline(PipelineDesc {
-            vertex_shader: shadow_vert,
+        let shadow_pipeline_desc = |vertex_shader, vertex_input| PipelineDesc {
+            vertex_shader,
             fragment_shader: None,
             push_constant_ranges: vec![PushConstantDesc {
                 stages: ShaderStage::VERTEX,
@@ -222,24 +469,34 @@ impl LightingRenderer {
             },
             rasterization: RasterizationStateDesc {
                 cull_mode: CullMode::Front,
-                depth_bias_enable: false,
+                depth_bias_enable: true,
                 depth_clamp_enable: true,
                 discard_enable: false,
                 front_face: FrontFace::CounterClockwise,
                 polygon_mode: PolygonMode::Fill,
             },
-            vertex_input: VertexInputDesc {
-                bindings: vec![],
-                attributes: vec![],
-            },
+            vertex_input,
             topology: PrimitiveTopology::TriangleList,
+            specialization: SpecializationConstants::default(),
+        };
+        let shadow_pipelines = VertexEncoding::ALL.map(|encoding| {
+            let vertex_input = VertexInputDesc::encoded_mesh(encoding);
+            vulkan_backend
+                .create_graphics_pipeline(shadow_pipeline_desc(shadow_vert.clone(), vertex_input))
+        });
+        let skinned_shadow_pipelines = VertexEncoding::ALL.map(|encoding| {
+            let vertex_input = VertexInputDesc::encoded_mesh(encoding).with_skin();
+            vulkan_backend.create_graphics_pipeline(shadow_pipeline_desc(
+                skinned_shadow_vert.clone(),
+                vertex_input,
+            ))
         });
 
-        let lighting_pipeline = vulkan_backend.create_graphics_pipeline(PipelineDesc {
+        let lighting_pipeline_desc = PipelineDesc {
             vertex_shader: quad_vert,
             fragment_shader: Some(lighting_frag),
             push_constant_ranges: vec![],
-            layout: vec![lighting_descriptor_layout],
+            layout: vec![lighting_descriptor_layout, cluster_layout],
             color_attachments: vec![frame_data.frame_images.draw_image],
             depth_attachment: None,
             blend: None,
@@ -263,55 +520,120 @@ impl LightingRenderer {
                 attributes: vec![],
             },
             topology: PrimitiveTopology::TriangleList,
-        });
+            specialization: SpecializationConstants::default(),
+        };
+
+        let pcf_radius = shadow_settings.pcf_radius();
+        let lighting_pipeline =
+            create_lighting_pipeline(vulkan_backend, &lighting_pipeline_desc, pcf_radius);
 
         let renderer = Self {
-            shadow_pipeline,
+            shadow_pipelines,
+            skinned_shadow_pipelines,
             lighting_pipeline,
-            cascade_buffer,
-            lighting_buffer,
+            lighting_pipelines: HashMap::from([(pcf_radius, lighting_pipeline)]),
+            lighting_pipeline_desc,
+            frames,
             shadow_sampler,
-            shadow_descriptor_set,
-            lighting_descriptor_set,
+            shadow_depth_sampler,
+            default_skybox,
+            image_based_lighting,
+            reflection_probes,
+            shadow_settings,
+            cascade_shadows: CascadeShadows::default(),
         };
 
         renderer.update_lighting_descriptors(vulkan_backend, frame_data);
         renderer
     }
 
+    pub fn shadow_settings(&self) -> &ShadowSettings {
+        &self.shadow_settings
+    }
+
+    pub fn reflection_probes_mut(&mut self) -> &mut ReflectionProbes {
+        &mut self.reflection_probes
+    }
+
+    /// Applies new shadow settings. When cascade resolutions change the cascade
+    /// images are reallocated and the lighting descriptors rewritten; a new kernel size
+    /// selects the lighting pipeline compiled for it. Blending and split parameters only
+    /// feed the per-frame uniforms.
+    pub fn set_shadow_settings(
+        &mut self,
+        vulkan_backend: &mut VulkanBackend,
+        frame_data: &mut FrameData,
+        shadow_settings: ShadowSettings,
+    ) {
+        let resolutions_changed = (0..MAX_SHADOW_CASCADES).any(|index| {
+            shadow_cascade_resolution(&self.shadow_settings, index)
+                != shadow_cascade_resolution(&shadow_settings, index)
+        });
+        self.shadow_settings = shadow_settings;
+        self.select_lighting_pipeline(vulkan_backend);
+
+        if resolutions_changed {
+            // The lighting sets of the frames in flight still sample the old cascades.
+            vulkan_backend.wait_idle();
+            frame_data
+                .frame_images
+                .recreate_shadow_cascades(vulkan_backend, &self.shadow_settings);
+            self.update_lighting_descriptors(vulkan_backend, frame_data);
+        }
+    }
+
     pub fn draw_frame(
-        &self,
+        &mut self,
         vulkan_backend: &mut VulkanBackend,
         render_scene: &RenderScene,
         frame_data: &FrameData,
+        cluster_set: DescriptorSetHandle,
     ) {
-        let light = match &render_scene.directional_light {
-            Some(l) => l,
-            None => return,
-        };
         let camera = match &render_scene.camera_data {
             Some(c) => c,
             None => return,
         };
+        // Without a sun, point lights are the only light and nothing needs shadowing.
+        let light = render_scene.directional_light.as_ref();
+        let cascades = match light {
+            Some(light) if self.shadow_settings.quality == ShadowQuality::Cascaded => {
+                self.cascade_shadows
+                    .update(camera, &light.direction, &self.shadow_settings)
+            }
+            _ => Vec::new(),
+        };
+        let light_direction = light.map_or(Vec3::y(), |light| light.direction);
+        let (light_color, light_intensity) =
+            light.map_or((Vec3::zeros(), 0.0), |light| (light.color, light.intensity));
+        let environment = &render_scene.environment;
+        let ambient_color = environment.ambient_color.to_vec3();
+        let (fog_mode, fog_start, fog_end) = match environment.fog.mode {
+            FogMode::Off => (0.0, 0.0, 0.0),
+            FogMode::Linear { start, end } => (1.0, start, end),
+            FogMode::Exponential => (2.0, 0.0, 0.0),
+            FogMode::ExponentialSquared => (3.0, 0.0, 0.0),
+        };
+        let fog_color = environment.fog.color.to_vec3();
+        let skybox_bound = render_scene.skybox.map_or(0.0, |_| 1.0);
 
-        let cascades = self.compute_cascades(camera, &light.direction);
-
+        let slot = vulkan_backend.frame_slot();
+        let frame = self.frames.get(slot);
         let cascade_matrices: Vec<Mat4> = cascades.iter().map(|c| c.view_proj).collect();
-        vulkan_backend.update_buffer(self.cascade_buffer, cascade_matrices.as_slice());
+        vulkan_backend.update_buffer(frame.cascade_buffer, cascade_matrices.as_slice());
 
         let lighting_ubo = LightingUbo {
             light_direction: Vec4::new(
-                light.direction.x,
-                light.direction.y,
-                light.direction.z,
+                light_direction.x,
+                light_direction.y,
+                light_direction.z,
                 0.0,
             ),
-            light_color: Vec4::new(light.color.x, light.color.y, light.color.z, light.intensity),
+            light_color: Vec4::new(light_color.x, light_color.y, light_color.z, light_intensity),
             ambient_light: Vec4::new(
-                light.ambient_color.x,
-                light.ambient_color.y,
-                light.ambient_color.z,
-                light.ambient_intensity,
+                ambient_color.x,
+                ambient_color.y,
+                ambient_color.z,
+                environment.ambient_intensity,
             ),
             cascade_depths: Vec4::new(
                 cascades.first().map_or(0.0, |c| c.depth),
@@ -319,53 +641,155 @@ impl LightingRenderer {
                 cascades.get(2).map_or(0.0, |c| c.depth),
                 cascades.get(3).map_or(0.0, |c| c.depth),
             ),
+            cascade_resolutions: Vec4::new(
+                shadow_cascade_resolution(&self.shadow_settings, 0) as f32,
+                shadow_cascade_resolution(&self.shadow_settings, 1) as f32,
+                shadow_cascade_resolution(&self.shadow_settings, 2) as f32,
+                shadow_cascade_resolution(&self.shadow_settings, 3) as f32,
+            ),
+            shadow_params: Vec4::new(
+                cascades.len() as f32,
+                0.0,
+                self.shadow_settings.cascade_blend.clamp(0.0, 1.0),
+                0.0,
+            ),
+            shadow_bias: Vec4::new(
+                self.shadow_settings.receiver_bias,
+                self.shadow_settings.normal_offset_bias,
+                self.shadow_settings.pcss_light_size,
+                if self.shadow_settings.pcss { 1.0 } else { 0.0 },
+            ),
+            fog_color: Vec4::new(fog_color.x, fog_color.y, fog_color.z, fog_mode),
+            fog_params: Vec4::new(environment.fog.density, fog_start, fog_end, 0.0),
+            sky_params: Vec4::new(skybox_bound, (SPECULAR_MIPS - 1) as f32, 0.0, 0.0),
         };
-        vulkan_backend.update_buffer(self.lighting_buffer, &[lighting_ubo]);
+        vulkan_backend.update_buffer(frame.lighting_buffer, &[lighting_ubo]);
+        vulkan_backend.update_buffer(
+            frame.area_light_buffer,
+            &[AreaLightUbo::new(&render_scene.area_lights)],
+        );
 
-        #[allow(clippy::needless_range_loop)]
-        for cascade_idx in 0..CASCADE_COUNT {
+        for cascade_idx in 0..cascades.len() {
             let shadow_image = &frame_data.frame_images.shadow_cascades[cascade_idx];
-            let res = CASCADE_RESOLUTIONS[cascade_idx];
+            let res = shadow_cascade_resolution(&self.shadow_settings, cascade_idx as u32);
+            vulkan_backend.push_pass_marker(&format!("Shadow cascade {cascade_idx}"));
             vulkan_backend.begin_rendering_with_extent(&[], Some(shadow_image), res, res);
 
-            vulkan_backend.bind_pipeline(self.shadow_pipeline);
-            vulkan_backend
-                .bind_descriptor_sets(&[self.shadow_descriptor_set], self.shadow_pipeline);
-
-            for (object_index, mesh_data) in render_scene.meshes.iter().enumerate() {
-                let push = ShadowPushConstants {
-                    object_index: object_index as u32,
-                    cascade_index: cascade_idx as u32,
+            // Static meshes first, then skinned ones, each by vertex encoding, so each
+            // pipeline is bound once.
+            let passes = [false, true]
+                .into_iter()
+                .flat_map(|skinned| VertexEncoding::ALL.map(|encoding| (skinned, encoding)));
+            for (skinned, encoding) in passes {
+                let pipeline = if skinned {
+                    self.skinned_shadow_pipelines[encoding as usize]
+                } else {
+                    self.shadow_pipelines[encoding as usize]
                 };
-                vulkan_backend.update_push_constants(
-                    self.shadow_pipeline,
-                    ShaderStage::VERTEX,
-                    &[push],
-                );
-
-                vulkan_backend.bind_vertex_buffer(mesh_data.mesh_data.vertex_buffer);
-                vulkan_backend.bind_index_buffer(mesh_data.mesh_data.index_buffer);
-                vulkan_backend.draw_indexed(mesh_data.mesh_data.index_count as u32, 0);
+                let mut bound = false;
+
+                for mesh_data in &render_scene.meshes {
+                    let skin = mesh_data.mesh_data.skin_buffer.zip(mesh_data.joint_offset);
+                    if skin.is_some() != skinned
+                        || mesh_data.mesh_data.vertex_encoding != encoding
+                        || !mesh_data.cast_shadows
+                    {
+                        continue;
+                    }
+                    if !bound {
+                        vulkan_backend.bind_pipeline(pipeline);
+                        vulkan_backend.set_depth_bias(
+                            self.shadow_settings.depth_bias_constant,
+                            self.shadow_settings.depth_bias_slope,
+                        );
+                        vulkan_backend
+                            .bind_descriptor_sets(&[frame.shadow_descriptor_set], pipeline);
+                        bound = true;
+                    }
+
+                    let push = ShadowPushConstants {
+                        object_index: mesh_data.transform_slot,
+                        cascade_index: cascade_idx as u32,
+                        joint_offset: skin.map_or(0, |(_, offset)| offset),
+                    };
+                    vulkan_backend.update_push_constants(pipeline, ShaderStage::VERTEX, &[push]);
+
+                    vulkan_backend.bind_vertex_buffer(mesh_data.mesh_data.vertex_buffer);
+                    if let Some((skin_buffer, _)) = skin {
+                        vulkan_backend.bind_vertex_buffers(MESH_SKIN_BINDING, &[skin_buffer]);
+                    }
+                    vulkan_backend.bind_index_buffer(mesh_data.mesh_data.index_buffer);
+                    vulkan_backend.draw_indexed(
+                        mesh_data.mesh_data.index_count as u32,
+                        mesh_data.mesh_data.first_index,
+                        mesh_data.mesh_data.vertex_offset,
+                    );
+                }
             }
 
             vulkan_backend.end_rendering();
+            vulkan_backend.pop_pass_marker();
         }
 
-        for cascade_idx in 0..CASCADE_COUNT {
-            vulkan_backend
-                .transition_image(frame_data.frame_images.shadow_cascades[cascade_idx], true);
+        // Inactive cascades are still bound to the lighting set, so they need a valid layout too.
+        for &shadow_image in &frame_data.frame_images.shadow_cascades {
+            vulkan_backend.transition_image(shadow_image, ResourceState::FragmentShaderRead);
+        }
+
+        let images = &frame_data.frame_images;
+        for gbuffer_image in [
+            images.gbuffer_albedo,
+            images.gbuffer_normal,
+            images.gbuffer_emissive,
+            images.gbuffer_depth,
+        ] {
+            vulkan_backend.transition_image(gbuffer_image, ResourceState::FragmentShaderRead);
         }
 
-        vulkan_backend.transition_image(frame_data.frame_images.gbuffer_albedo, false);
-        vulkan_backend.transition_image(frame_data.frame_images.gbuffer_normal, false);
-        vulkan_backend.transition_image(frame_data.frame_images.gbuffer_depth, true);
+        self.image_based_lighting.update(vulkan_backend, render_scene.skybox);
+        self.reflection_probes.update(
+            vulkan_backend,
+            &render_scene.reflection_probes,
+            camera.view.try_inverse().map_or(Vec3::zeros(), |world| world.column(3).xyz()),
+            frame.lighting_descriptor_set,
+            REFLECTION_PROBE_BINDING,
+            self.image_based_lighting.specular_map(),
+        );
+
+        // The frame that last used this slot's lighting set has finished, so it can be
+        // rewritten.
+        let skybox_changed =
+            render_scene.skybox.map(|image| image.0) != frame.skybox.map(|image| image.0);
+        let lighting_set = frame.lighting_descriptor_set;
+        if skybox_changed {
+            let write = self.skybox_write(render_scene.skybox, frame_data);
+            vulkan_backend.update_descriptor_set(lighting_set, &[write]);
+            self.frames.current_mut(vulkan_backend).skybox = render_scene.skybox;
+        }
+        vulkan_backend.set_clear_value(
+            frame_data.frame_images.draw_image,
+            ClearValue::Color(environment.clear_color),
+        );
 
+        vulkan_backend.push_pass_marker("Lighting");
         vulkan_backend.begin_rendering(&[frame_data.frame_images.draw_image], None);
         vulkan_backend.bind_pipeline(self.lighting_pipeline);
-        vulkan_backend
-            .bind_descriptor_sets(&[self.lighting_descriptor_set], self.lighting_pipeline);
-        vulkan_backend.draw(3);
+        vulkan_backend.bind_descriptor_sets(
+            &[lighting_set, cluster_set],
+            self.lighting_pipeline,
+        );
+        vulkan_backend.draw(3, 0);
         vulkan_backend.end_rendering();
+        vulkan_backend.pop_pass_marker();
+    }
+
+    fn select_lighting_pipeline(&mut self, vulkan_backend: &mut VulkanBackend) {
+        let radius = self.shadow_settings.pcf_radius();
+        let desc = &self.lighting_pipeline_desc;
+        self.lighting_pipeline = *self
+            .lighting_pipelines
+            .entry(radius)
+            .or_insert_with(|| create_lighting_pipeline(vulkan_backend, desc, radius));
     }
 
     fn update_lighting_descriptors(
@@ -373,10 +797,23 @@ impl LightingRenderer {
         vulkan_backend: &mut VulkanBackend,
         frame_data: &FrameData,
     ) {
-        let writes = vec![
+        for (slot, frame) in self.frames.iter().enumerate() {
+            let writes = self.lighting_descriptor_writes(frame_data, slot, frame);
+            vulkan_backend.update_descriptor_set(frame.lighting_descriptor_set, &writes);
+        }
+    }
+
+    fn lighting_descriptor_writes(
+        &self,
+        frame_data: &FrameData,
+        slot: usize,
+        frame: &LightingFrame,
+    ) -> Vec<DescriptorWriteDesc> {
+        let camera_buffer = frame_data.buffers.get(slot).camera_buffer;
+        let mut writes = vec![
             DescriptorWriteDesc {
                 binding: 0,
-                value: DescriptorValue::UniformBuffer(self.lighting_buffer),
+                value: DescriptorValue::UniformBuffer(frame.lighting_buffer),
             },
             DescriptorWriteDesc {
                 binding: 1,
@@ -422,11 +859,11 @@ impl LightingRenderer {
             },
             DescriptorWriteDesc {
                 binding: 7,
-                value: DescriptorValue::UniformBuffer(self.cascade_buffer),
+                value: DescriptorValue::UniformBuffer(frame.cascade_buffer),
             },
             DescriptorWriteDesc {
                 binding: 8,
-                value: DescriptorValue::UniformBuffer(frame_data.camera_buffer),
+                value: DescriptorValue::UniformBuffer(camera_buffer),
             },
             DescriptorWriteDesc {
                 binding: 9,
@@ -435,103 +872,63 @@ impl LightingRenderer {
                     sampler: self.shadow_sampler,
                 }),
             },
+            DescriptorWriteDesc {
+                binding: 14,
+                value: DescriptorValue::SampledImage(SampledImageInfo {
+                    image: frame_data.frame_images.gbuffer_emissive,
+                    sampler: frame_data.basic_sampler,
+                }),
+            },
+            DescriptorWriteDesc {
+                binding: AREA_LIGHT_BINDING,
+                value: DescriptorValue::UniformBuffer(frame.area_light_buffer),
+            },
+            self.skybox_write(frame.skybox, frame_data),
         ];
 
-        vulkan_backend.update_descriptor_set(self.lighting_descriptor_set, &writes);
+        // Raw depth views of the cascades for the PCSS blocker search (bindings 10-13).
+        writes.extend(frame_data.frame_images.shadow_cascades.iter().enumerate().map(
+            |(index, &image)| DescriptorWriteDesc {
+                binding: 10 + index,
+                value: DescriptorValue::SampledImage(SampledImageInfo {
+                    image,
+                    sampler: self.shadow_depth_sampler,
+                }),
+            },
+        ));
+        writes.extend(self.image_based_lighting.descriptor_writes(16));
+        writes.extend(self.reflection_probes.descriptor_writes(
+            slot,
+            REFLECTION_PROBE_BINDING,
+            self.image_based_lighting.specular_map(),
+        ));
+        writes
     }
 
-    fn compute_cascades(&self, camera: &CameraRenderData, light_dir: &Vec3) -> Vec<Cascade> {
-        let near = camera.near_clip;
-        let far = camera.far_clip.min(SHADOW_DISTANCE);
-        let lambda = 0.9f32;
-
-        let mut splits = [0.0f32; CASCADE_COUNT + 1];
-        splits[0] = near;
-        splits[CASCADE_COUNT] = far;
-
-        #[allow(clippy::needless_range_loop)]
-        for i in 1..CASCADE_COUNT {
-            let idm = i as f32 / CASCADE_COUNT as f32;
-            let log = near * (far / near).powf(idm);
-            let uniform = near + (far - near) * idm;
-            splits[i] = log * lambda + uniform * (1.0 - lambda);
-        }
-
-        let mut cascades = Vec::with_capacity(CASCADE_COUNT);
-
-        for i in 0..CASCADE_COUNT {
-            let split_near = splits[i];
-            let split_far = splits[i + 1];
-
-            let sub_proj = glm::perspective_rh_zo(
-                camera.aspect_ratio,
-                camera.fov.to_radians(),
-                split_near,
-                split_far,
-            );
-
-            let sub_inv_vp = glm::inverse(&(sub_proj * camera.view));
-
-            let ndc_corners = [
-                Vec4::new(-1.0, -1.0, 0.0, 1.0),
-                Vec4::new(1.0, -1.0, 0.0, 1.0),
-                Vec4::new(1.0, 1.0, 0.0, 1.0),
-                Vec4::new(-1.0, 1.0, 0.0, 1.0),
-                Vec4::new(-1.0, -1.0, 1.0, 1.0),
-                Vec4::new(1.0, -1.0, 1.0, 1.0),
-                Vec4::new(1.0, 1.0, 1.0, 1.0),
-                Vec4::new(-1.0, 1.0, 1.0, 1.0),
-            ];
-
-            let mut world_corners = [Vec3::zeros(); 8];
-            for (j, ndc) in ndc_corners.iter().enumerate() {
-                let world = sub_inv_vp * ndc;
-                world_corners[j] =
-                    Vec3::new(world.x / world.w, world.y / world.w, world.z / world.w);
-            }
-
-            let mut center = Vec3::zeros();
-            for corner in &world_corners {
-                center += corner;
-            }
-            center /= 8.0;
-
-            let mut radius = 0.0f32;
-            for corner in &world_corners {
-                let dist = glm::length(&(corner - center));
-                radius = radius.max(dist);
-            }
-            radius = (radius * 16.0).ceil() / 16.0;
-
-            let light_dir_norm = glm::normalize(light_dir);
-            let light_view = glm::look_at(
-                &(center + light_dir_norm * radius),
-                &center,
-                &Vec3::new(0.0, 1.0, 0.0),
-            );
-            let light_proj = glm::ortho_rh_zo(-radius, radius, -radius, radius, 0.0, 2.0 * radius);
-
-            let mut shadow_vp = light_proj * light_view;
-
-            let snap_res = CASCADE_RESOLUTIONS[i] as f32;
-            let shadow_origin = shadow_vp * Vec4::new(0.0, 0.0, 0.0, 1.0);
-            let shadow_origin_snapped = Vec4::new(
-                (shadow_origin.x * snap_res / 2.0).round() / (snap_res / 2.0),
-                (shadow_origin.y * snap_res / 2.0).round() / (snap_res / 2.0),
-                shadow_origin.z,
-                shadow_origin.w,
-            );
-            let offset = shadow_origin_snapped - shadow_origin;
-
-            shadow_vp[(0, 3)] += offset.x;
-            shadow_vp[(1, 3)] += offset.y;
-
-            cascades.push(Cascade {
-                view_proj: shadow_vp,
-                depth: split_far,
-            });
+    fn skybox_write(
+        &self,
+        skybox: Option<GpuImageHandle>,
+        frame_data: &FrameData,
+    ) -> DescriptorWriteDesc {
+        DescriptorWriteDesc {
+            binding: 15,
+            value: DescriptorValue::SampledImage(SampledImageInfo {
+                image: skybox.unwrap_or(self.default_skybox),
+                sampler: frame_data.basic_sampler,
+            }),
         }
-
-        cascades
     }
-}
\ No newline at end of file
+}
+
+/// Builds the lighting pipeline with a `radius` texel PCF kernel baked in.
+fn create_lighting_pipeline(
+    vulkan_backend: &mut VulkanBackend,
+    desc: &PipelineDesc,
+    radius: u32,
+) -> PipelineHandle {
+    vulkan_backend.create_graphics_pipeline(PipelineDesc {
+        specialization: SpecializationConstants::default()
+            .with_i32(PCF_RADIUS_CONSTANT_ID, radius as i32),
+        ..desc.clone()
+    })
+}
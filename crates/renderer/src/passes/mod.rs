@@ -1,3 +1,67 @@
 pub mod aabb_debug_renderer;
+pub mod blob_shadow_renderer;
+pub mod depth_of_field;
+pub mod draw2d_renderer;
 pub mod geometry_renderer;
+pub mod gpu_culling;
+pub mod image_based_lighting;
+pub mod light_clusters;
 pub mod lighting_renderer;
+pub mod output_renderer;
+pub mod particle_renderer;
+pub mod reflection_probes;
+pub mod sky_renderer;
+pub mod trail_renderer;
+pub mod ui_renderer;
+
+use rendering_backend::backend_impl::vulkan_backend::VulkanBackend;
+use rendering_backend::descriptor::DescriptorLayoutHandle;
+use rendering_backend::image::GpuImageHandle;
+use rendering_backend::pipeline::{
+    BlendStateDesc, CompareOp, CullMode, DepthStencilDesc, FrontFace, PipelineDesc, PipelineHandle,
+    PolygonMode, PrimitiveTopology, PushConstantDesc, RasterizationStateDesc,
+    SpecializationConstants, VertexInputDesc,
+};
+
+/// Pipeline for a fullscreen triangle from the `quad` vertex shader into `target`, with
+/// no depth test.
+pub(crate) fn create_fullscreen_pipeline(
+    vulkan_backend: &mut VulkanBackend,
+    vertex_shader: Vec<u8>,
+    fragment_shader: Vec<u8>,
+    layout: DescriptorLayoutHandle,
+    push_constants: Option<PushConstantDesc>,
+    target: GpuImageHandle,
+    blend: Option<BlendStateDesc>,
+) -> PipelineHandle {
+    vulkan_backend.create_graphics_pipeline(PipelineDesc {
+        vertex_shader,
+        fragment_shader: Some(fragment_shader),
+        push_constant_ranges: push_constants.into_iter().collect(),
+        layout: vec![layout],
+        color_attachments: vec![target],
+        depth_attachment: None,
+        blend,
+        depth_stencil: DepthStencilDesc {
+            depth_test_enable: false,
+            depth_write_enable: false,
+            depth_compare_op: CompareOp::Always,
+            depth_bounds_test_enable: false,
+            stencil_test_enable: false,
+        },
+        rasterization: RasterizationStateDesc {
+            cull_mode: CullMode::None,
+            depth_bias_enable: false,
+            depth_clamp_enable: false,
+            discard_enable: false,
+            front_face: FrontFace::CounterClockwise,
+            polygon_mode: PolygonMode::Fill,
+        },
+        vertex_input: VertexInputDesc {
+            bindings: vec![],
+            attributes: vec![],
+        },
+        topology: PrimitiveTopology::TriangleList,
+        specialization: SpecializationConstants::default(),
+    })
+}
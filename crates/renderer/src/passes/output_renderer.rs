@@ -0,0 +1,206 @@
+use crate::frame_data::{FrameData, Resolution};
+use crate::shader_loader::ShaderCache;
+use common::{OutputMode, OutputSettings};
+use material::ShaderRef;
+use rendering_backend::backend_impl::vulkan_backend::VulkanBackend;
+use rendering_backend::descriptor::{
+    DescriptorBinding, DescriptorLayoutDesc, DescriptorSetHandle, DescriptorType, DescriptorValue,
+    DescriptorWriteDesc, SampledImageInfo, ShaderStage,
+};
+use rendering_backend::gpu_layout::GpuStruct;
+use rendering_backend::image::{
+    GpuImageHandle, ImageAspect, ImageDesc, ImageUsageFlags, TextureFormat,
+};
+use rendering_backend::pipeline::{
+    CompareOp, CullMode, DepthStencilDesc, FrontFace, PipelineDesc, PipelineHandle, PolygonMode,
+    PrimitiveTopology, PushConstantDesc, RasterizationStateDesc, SpecializationConstants,
+    VertexInputDesc,
+};
+use rendering_backend::sampler::{Filter, SamplerAddressMode, SamplerDesc};
+use rendering_backend::sync::ResourceState;
+
+/// `mode` values understood by `output.frag`.
+const MODE_HDR10: u32 = 1;
+const MODE_SCRGB: u32 = 2;
+
+#[repr(C)]
+#[derive(Clone, Copy, GpuStruct)]
+#[gpu(std430)]
+pub(crate) struct OutputPushConstants {
+    mode: u32,
+    paper_white_nits: f32,
+    peak_nits: f32,
+}
+
+/// Encodes the final image for an HDR swapchain. Scene values are scaled to nits and
+/// kept under the display peak, then written as PQ (HDR10) or linear scRGB into an
+/// RGBA16F image that is blitted to the swapchain. SDR output presents the draw image
+/// directly. Resources are created on the first HDR frame.
+pub struct OutputRenderer {
+    resources: Option<OutputResources>,
+}
+
+struct OutputResources {
+    pipeline: PipelineHandle,
+    descriptor_set: DescriptorSetHandle,
+    image: GpuImageHandle,
+}
+
+impl OutputRenderer {
+    pub fn new() -> Self {
+        Self { resources: None }
+    }
+
+    /// Records the encode pass for the swapchain's current mode and returns the image to
+    /// present.
+    pub fn draw_frame(
+        &mut self,
+        vulkan_backend: &mut VulkanBackend,
+        frame_data: &FrameData,
+        shader_cache: &mut ShaderCache,
+        settings: &OutputSettings,
+    ) -> GpuImageHandle {
+        let draw_image = frame_data.frame_images.draw_image;
+        let mode = match vulkan_backend.output_mode() {
+            OutputMode::Sdr => return draw_image,
+            OutputMode::Hdr10 => MODE_HDR10,
+            OutputMode::ScRgb => MODE_SCRGB,
+        };
+        let resources = self.get_or_create_resources(vulkan_backend, frame_data, shader_cache);
+
+        vulkan_backend.transition_image(draw_image, ResourceState::FragmentShaderRead);
+        vulkan_backend.push_pass_marker("HDR output");
+        vulkan_backend.begin_rendering(&[resources.image], None);
+        vulkan_backend.bind_pipeline(resources.pipeline);
+        vulkan_backend.bind_descriptor_sets(&[resources.descriptor_set], resources.pipeline);
+        vulkan_backend.update_push_constants(
+            resources.pipeline,
+            ShaderStage::FRAGMENT,
+            &[OutputPushConstants {
+                mode,
+                paper_white_nits: settings.paper_white_nits,
+                peak_nits: settings.peak_nits,
+            }],
+        );
+        vulkan_backend.draw(3, 0);
+        vulkan_backend.end_rendering();
+        vulkan_backend.pop_pass_marker();
+
+        resources.image
+    }
+
+    /// Reallocates the encoded image for a new render `resolution`, if it was created.
+    /// Returns the images whose descriptor sets need rewriting.
+    pub fn resize(
+        &mut self,
+        vulkan_backend: &mut VulkanBackend,
+        resolution: Resolution,
+    ) -> Vec<GpuImageHandle> {
+        let Some(resources) = &self.resources else {
+            return Vec::new();
+        };
+        vulkan_backend.recreate_image(resources.image, encoded_image_desc(resolution));
+        vec![resources.image]
+    }
+
+    fn get_or_create_resources(
+        &mut self,
+        vulkan_backend: &mut VulkanBackend,
+        frame_data: &FrameData,
+        shader_cache: &mut ShaderCache,
+    ) -> &OutputResources {
+        self.resources.get_or_insert_with(|| {
+            let draw_image = frame_data.frame_images.draw_image;
+            let (width, height) = vulkan_backend.image_size(draw_image);
+            let image =
+                vulkan_backend.create_image(encoded_image_desc(Resolution { width, height }));
+
+            let sampler = vulkan_backend.create_sampler(SamplerDesc {
+                mag_filter: Filter::Nearest,
+                min_filter: Filter::Nearest,
+                address_u: SamplerAddressMode::ClampToEdge,
+                address_v: SamplerAddressMode::ClampToEdge,
+                address_w: SamplerAddressMode::ClampToEdge,
+                compare_enable: false,
+                compare_op: None,
+            });
+            let layout = vulkan_backend.create_descriptor_layout(DescriptorLayoutDesc {
+                bindings: vec![DescriptorBinding {
+                    binding: 0,
+                    descriptor_type: DescriptorType::CombinedImageSampler,
+                    count: 1,
+                    stages: ShaderStage::FRAGMENT,
+                }],
+            });
+            let descriptor_set = vulkan_backend.allocate_descriptor_set(layout);
+            vulkan_backend.update_descriptor_set(
+                descriptor_set,
+                &[DescriptorWriteDesc {
+                    binding: 0,
+                    value: DescriptorValue::SampledImage(SampledImageInfo {
+                        image: draw_image,
+                        sampler,
+                    }),
+                }],
+            );
+
+            let quad_vert = shader_cache.load(&ShaderRef::BuiltIn("quad".into()), &[]);
+            let output_frag = shader_cache.load(&ShaderRef::BuiltIn("output".into()), &[]);
+            let pipeline = vulkan_backend.create_graphics_pipeline(PipelineDesc {
+                vertex_shader: quad_vert,
+                fragment_shader: Some(output_frag),
+                push_constant_ranges: vec![PushConstantDesc {
+                    stages: ShaderStage::FRAGMENT,
+                    offset: 0,
+                    size: size_of::<OutputPushConstants>(),
+                }],
+                layout: vec![layout],
+                color_attachments: vec![image],
+                depth_attachment: None,
+                blend: None,
+                depth_stencil: DepthStencilDesc {
+                    depth_test_enable: false,
+                    depth_write_enable: false,
+                    depth_compare_op: CompareOp::Always,
+                    depth_bounds_test_enable: false,
+                    stencil_test_enable: false,
+                },
+                rasterization: RasterizationStateDesc {
+                    cull_mode: CullMode::None,
+                    depth_bias_enable: false,
+                    depth_clamp_enable: false,
+                    discard_enable: false,
+                    front_face: FrontFace::CounterClockwise,
+                    polygon_mode: PolygonMode::Fill,
+                },
+                vertex_input: VertexInputDesc {
+                    bindings: vec![],
+                    attributes: vec![],
+                },
+                topology: PrimitiveTopology::TriangleList,
+                specialization: SpecializationConstants::default(),
+            });
+
+            OutputResources {
+                pipeline,
+                descriptor_set,
+                image,
+            }
+        })
+    }
+}
+
+fn encoded_image_desc(resolution: Resolution) -> ImageDesc {
+    ImageDesc {
+        width: resolution.width,
+        height: resolution.height,
+        depth: 1,
+        format: TextureFormat::R16g16b16a16Float,
+        clear_value: None,
+        array_layers: 1,
+        is_cubemap: false,
+        mip_levels: 1,
+        aspect: ImageAspect::Color,
+        usage: ImageUsageFlags::COLOR_ATTACHMENT | ImageUsageFlags::TRANSFER_SRC,
+    }
+}
@@ -0,0 +1,676 @@
+use crate::frame_data::FrameData;
+use crate::frame_ring::FrameRing;
+use crate::render_data::ParticleEmitterData;
+use crate::render_scene::RenderScene;
+use crate::shader_loader::ShaderCache;
+use core::particles::ParticleBlendMode;
+use ecs::entity::Entity;
+use material::ShaderRef;
+use nalgebra_glm::{Vec2, Vec3, Vec4};
+use rendering_backend::backend_impl::vulkan_backend::VulkanBackend;
+use rendering_backend::buffer::{BufferDesc, BufferHandle, BufferUsageFlags};
+use rendering_backend::descriptor::{
+    DescriptorBinding, DescriptorLayoutDesc, DescriptorLayoutHandle, DescriptorSetHandle,
+    DescriptorType, DescriptorValue, DescriptorWriteDesc, SampledImageInfo, ShaderStage,
+};
+use rendering_backend::gpu_layout::{GpuStruct, Padding};
+use rendering_backend::memory::MemoryHint;
+use rendering_backend::pipeline::{
+    BlendAttachmentDesc, BlendFactor, BlendOp, BlendStateDesc, ColorWriteMask, CompareOp,
+    ComputePipelineDesc, CullMode, DepthStencilDesc, FrontFace, PipelineDesc, PipelineHandle,
+    PolygonMode, PrimitiveTopology, PushConstantDesc, RasterizationStateDesc,
+    SpecializationConstants, VertexInputDesc,
+};
+use rendering_backend::sampler::{Filter, SamplerAddressMode, SamplerDesc, SamplerHandle};
+use rendering_backend::sync::ResourceState;
+use std::collections::HashMap;
+use std::f32::consts::{PI, TAU};
+
+/// Invocations per workgroup of the particle compute shaders.
+const WORKGROUP_SIZE: u32 = 64;
+/// Vertices of one particle's quad in `particle.vert`.
+const VERTICES_PER_PARTICLE: u32 = 6;
+
+/// One entry of an emitter's particle pool.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, GpuStruct)]
+#[gpu(std430)]
+struct GpuParticle {
+    /// xyz: world-space position, w: age in seconds.
+    position_age: Vec4,
+    /// xyz: velocity, w: lifetime in seconds.
+    velocity_life: Vec4,
+}
+
+/// Indirect draw arguments and pool bookkeeping of one emitter, written by the compute
+/// passes.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, GpuStruct)]
+#[gpu(std430)]
+pub(crate) struct ParticleCounters {
+    vertex_count: u32,
+    instance_count: u32,
+    first_vertex: u32,
+    first_instance: u32,
+    /// Entries on the dead stack.
+    dead_count: i32,
+    /// Length of each of the two alive lists.
+    alive_count: [u32; 2],
+    _padding: Padding<4>,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, GpuStruct)]
+#[gpu(std430)]
+pub(crate) struct ParticleSimPushConstants {
+    /// xyz: emitter position, w: cosine of the cone half-angle.
+    emitter_position: Vec4,
+    /// xyz: cone axis, w: initial speed.
+    emitter_direction: Vec4,
+    /// xyz: gravity, w: drag.
+    gravity_drag: Vec4,
+    /// x: delta time, y: lifetime, z: bounce, negative without collision.
+    params: Vec4,
+    emit_count: u32,
+    pool_size: u32,
+    /// Alive list the simulation reads this frame.
+    parity: u32,
+    seed: u32,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, GpuStruct)]
+#[gpu(std430)]
+pub(crate) struct ParticleDrawPushConstants {
+    start_color: Vec4,
+    end_color: Vec4,
+    /// x: size at birth, y: size at death.
+    size: Vec2,
+    /// First entry of the alive list to draw.
+    alive_offset: u32,
+    _padding: Padding<4>,
+}
+
+/// Simulates and draws the particles of every [`ParticleEmitterData`], after the sky and
+/// before post-processing, depth tested against the scene.
+///
+/// On the GPU path each emitter owns a pool in storage buffers with a stack of free slots
+/// and two alive lists. Every frame an emit pass pops free slots for new particles, a
+/// simulate pass ages, moves and collides the particles of one list with the depth
+/// buffer and compacts the survivors into the other, and a finalize pass writes their
+/// count as indirect draw arguments. The CPU path simulates the same way without
+/// collision and uploads the alive particles; it is used when compute is unavailable on
+/// the graphics queue or GPU particles are disabled.
+pub struct ParticleRenderer {
+    gpu: bool,
+    resources: Option<ParticleResources>,
+    emitters: HashMap<Entity, EmitterState>,
+    frame: u32,
+}
+
+struct ParticleResources {
+    layout: DescriptorLayoutHandle,
+    sampler: SamplerHandle,
+    additive_pipeline: PipelineHandle,
+    alpha_pipeline: PipelineHandle,
+    /// Emit, simulate and finalize; `None` on the CPU path.
+    compute: Option<[PipelineHandle; 3]>,
+}
+
+/// The particle pool of one emitter.
+struct EmitterState {
+    pool_size: u32,
+    /// The pool on the GPU path; one copy per frame slot on the CPU path, which rewrites
+    /// it every frame.
+    particles: Vec<BufferHandle>,
+    alive: BufferHandle,
+    dead: BufferHandle,
+    counters: BufferHandle,
+    /// Binds the frame slot's particles and camera.
+    descriptor_sets: FrameRing<DescriptorSetHandle>,
+    /// Fraction of a particle carried over to the next frame's emission.
+    emission_carry: f32,
+    /// Alive list the next simulation step reads; flips every GPU frame.
+    parity: u32,
+    /// The simulated particles on the CPU path.
+    cpu_particles: Vec<GpuParticle>,
+}
+
+impl ParticleRenderer {
+    /// `gpu` selects the compute path; callers pass false when the graphics queue cannot
+    /// run compute.
+    pub fn new(gpu: bool) -> Self {
+        Self {
+            gpu,
+            resources: None,
+            emitters: HashMap::new(),
+            frame: 0,
+        }
+    }
+
+    /// Advances every emitter by `delta` seconds and draws its particles into the draw
+    /// image.
+    pub fn draw_frame(
+        &mut self,
+        vulkan_backend: &mut VulkanBackend,
+        render_scene: &RenderScene,
+        frame_data: &FrameData,
+        shader_cache: &mut ShaderCache,
+        delta: f32,
+    ) {
+        let emitters = &render_scene.particle_emitters;
+        // Pools of despawned emitters, or of emitters whose capacity changed.
+        self.emitters.retain(|entity, state| {
+            let keep = emitters
+                .iter()
+                .any(|emitter| emitter.entity == *entity && pool_size(emitter) == state.pool_size);
+            if !keep {
+                state.release(vulkan_backend);
+            }
+            keep
+        });
+        if emitters.is_empty() {
+            return;
+        }
+        self.frame = self.frame.wrapping_add(1);
+        let gpu = self.gpu;
+        let resources = Self::get_or_create_resources(
+            &mut self.resources,
+            gpu,
+            vulkan_backend,
+            frame_data,
+            shader_cache,
+        );
+        for data in emitters {
+            self.emitters.entry(data.entity).or_insert_with(|| {
+                EmitterState::new(vulkan_backend, frame_data, resources, data, gpu)
+            });
+        }
+
+        let images = &frame_data.frame_images;
+        vulkan_backend.push_pass_marker("Particles");
+        if let Some(compute) = resources.compute {
+            vulkan_backend.transition_image(images.gbuffer_depth, ResourceState::ComputeShaderRead);
+            vulkan_backend
+                .transition_image(images.gbuffer_normal, ResourceState::ComputeShaderRead);
+            // Last frame's draw may still be reading the buffers.
+            vulkan_backend.draw_to_compute_barrier();
+            for data in emitters {
+                let state = self.emitters.get_mut(&data.entity).expect("created above");
+                let seed = hash(self.frame ^ hash(data.entity.index() as u32));
+                state.simulate_gpu(vulkan_backend, compute, data, seed, delta);
+            }
+            vulkan_backend.compute_to_draw_barrier();
+        } else {
+            for data in emitters {
+                let state = self.emitters.get_mut(&data.entity).expect("created above");
+                let seed = hash(self.frame ^ hash(data.entity.index() as u32));
+                let emit = state.emission_count(data, delta);
+                simulate_cpu(&mut state.cpu_particles, data, emit, seed, delta);
+                let particles = state.particles[vulkan_backend.frame_slot()];
+                vulkan_backend.update_buffer(particles, &state.cpu_particles);
+            }
+        }
+
+        vulkan_backend
+            .begin_rendering_load_with_depth(&[images.draw_image], Some(&images.gbuffer_depth));
+        for data in emitters {
+            let state = &self.emitters[&data.entity];
+            let pipeline = match data.emitter.blend {
+                ParticleBlendMode::Additive => resources.additive_pipeline,
+                ParticleBlendMode::AlphaBlend => resources.alpha_pipeline,
+            };
+            let emitter = &data.emitter;
+            let alive_offset = if gpu {
+                // The simulation flipped the parity to the list it compacted into.
+                state.parity * state.pool_size
+            } else {
+                0
+            };
+            let push_constants = ParticleDrawPushConstants {
+                start_color: emitter.start_color.to_vec4(),
+                end_color: emitter.end_color.to_vec4(),
+                size: Vec2::new(emitter.start_size, emitter.end_size),
+                alive_offset,
+                _padding: Padding::default(),
+            };
+            let descriptor_set = *state.descriptor_sets.current(vulkan_backend);
+            vulkan_backend.bind_pipeline(pipeline);
+            vulkan_backend.bind_descriptor_sets(&[descriptor_set], pipeline);
+            vulkan_backend.update_push_constants(pipeline, ShaderStage::VERTEX, &[push_constants]);
+            if gpu {
+                vulkan_backend.draw_indirect(state.counters, 0);
+            } else if !state.cpu_particles.is_empty() {
+                let vertices = state.cpu_particles.len() as u32 * VERTICES_PER_PARTICLE;
+                vulkan_backend.draw(vertices, 0);
+            }
+        }
+        vulkan_backend.end_rendering();
+        vulkan_backend.pop_pass_marker();
+    }
+
+    fn get_or_create_resources<'a>(
+        resources: &'a mut Option<ParticleResources>,
+        gpu: bool,
+        vulkan_backend: &mut VulkanBackend,
+        frame_data: &FrameData,
+        shader_cache: &mut ShaderCache,
+    ) -> &'a ParticleResources {
+        resources.get_or_insert_with(|| {
+            let buffer_stages = ShaderStage::COMPUTE | ShaderStage::VERTEX;
+            let binding = |binding, descriptor_type, stages| DescriptorBinding {
+                binding,
+                descriptor_type,
+                count: 1,
+                stages,
+            };
+            let layout = vulkan_backend.create_descriptor_layout(DescriptorLayoutDesc {
+                bindings: vec![
+                    binding(0, DescriptorType::StorageBuffer, buffer_stages),
+                    binding(1, DescriptorType::StorageBuffer, buffer_stages),
+                    binding(2, DescriptorType::StorageBuffer, ShaderStage::COMPUTE),
+                    binding(3, DescriptorType::StorageBuffer, ShaderStage::COMPUTE),
+                    binding(4, DescriptorType::UniformBuffer, buffer_stages),
+                    binding(
+                        5,
+                        DescriptorType::CombinedImageSampler,
+                        ShaderStage::COMPUTE,
+                    ),
+                    binding(
+                        6,
+                        DescriptorType::CombinedImageSampler,
+                        ShaderStage::COMPUTE,
+                    ),
+                ],
+            });
+            let sampler = vulkan_backend.create_sampler(SamplerDesc {
+                mag_filter: Filter::Nearest,
+                min_filter: Filter::Nearest,
+                address_u: SamplerAddressMode::ClampToEdge,
+                address_v: SamplerAddressMode::ClampToEdge,
+                address_w: SamplerAddressMode::ClampToEdge,
+                compare_enable: false,
+                compare_op: None,
+            });
+
+            let vert = shader_cache.load(&ShaderRef::BuiltIn("particle_vert".into()), &[]);
+            let frag = shader_cache.load(&ShaderRef::BuiltIn("particle_frag".into()), &[]);
+            let mut draw_pipeline = |dst_color_blend| {
+                Self::create_draw_pipeline(
+                    vulkan_backend,
+                    vert.clone(),
+                    frag.clone(),
+                    layout,
+                    frame_data,
+                    dst_color_blend,
+                )
+            };
+            let additive_pipeline = draw_pipeline(BlendFactor::One);
+            let alpha_pipeline = draw_pipeline(BlendFactor::OneMinusSrcAlpha);
+
+            let compute = gpu.then(|| {
+                ["particle_emit", "particle_simulate", "particle_finalize"].map(|name| {
+                    vulkan_backend.create_compute_pipeline(ComputePipelineDesc {
+                        shader: shader_cache.load(&ShaderRef::BuiltIn(name.into()), &[]),
+                        layout: vec![layout],
+                        push_constant_ranges: vec![PushConstantDesc {
+                            stages: ShaderStage::COMPUTE,
+                            offset: 0,
+                            size: size_of::<ParticleSimPushConstants>(),
+                        }],
+                        specialization: SpecializationConstants::default(),
+                    })
+                })
+            });
+            ParticleResources {
+                layout,
+                sampler,
+                additive_pipeline,
+                alpha_pipeline,
+                compute,
+            }
+        })
+    }
+
+    /// Camera-facing quads into the HDR draw image, depth tested but not written.
+    fn create_draw_pipeline(
+        vulkan_backend: &mut VulkanBackend,
+        vertex_shader: Vec<u8>,
+        fragment_shader: Vec<u8>,
+        layout: DescriptorLayoutHandle,
+        frame_data: &FrameData,
+        dst_color_blend: BlendFactor,
+    ) -> PipelineHandle {
+        let images = &frame_data.frame_images;
+        vulkan_backend.create_graphics_pipeline(PipelineDesc {
+            vertex_shader,
+            fragment_shader: Some(fragment_shader),
+            layout: vec![layout],
+            vertex_input: VertexInputDesc::default(),
+            rasterization: RasterizationStateDesc {
+                cull_mode: CullMode::None,
+                depth_bias_enable: false,
+                depth_clamp_enable: false,
+                discard_enable: false,
+                front_face: FrontFace::CounterClockwise,
+                polygon_mode: PolygonMode::Fill,
+            },
+            blend: Some(BlendStateDesc {
+                logic_op_enable: false,
+                attachments: vec![BlendAttachmentDesc {
+                    blend_enable: true,
+                    src_color_blend: BlendFactor::SrcAlpha,
+                    dst_color_blend,
+                    color_blend_op: BlendOp::Add,
+                    src_alpha_blend: BlendFactor::Zero,
+                    dst_alpha_blend: BlendFactor::One,
+                    alpha_blend_op: BlendOp::Add,
+                    color_write_mask: ColorWriteMask::ALL,
+                }],
+            }),
+            depth_stencil: DepthStencilDesc {
+                depth_test_enable: true,
+                depth_write_enable: false,
+                depth_compare_op: CompareOp::LessOrEqual,
+                depth_bounds_test_enable: false,
+                stencil_test_enable: false,
+            },
+            color_attachments: vec![images.draw_image],
+            depth_attachment: Some(images.gbuffer_depth),
+            push_constant_ranges: vec![PushConstantDesc {
+                stages: ShaderStage::VERTEX,
+                offset: 0,
+                size: size_of::<ParticleDrawPushConstants>(),
+            }],
+            topology: PrimitiveTopology::TriangleList,
+            specialization: SpecializationConstants::default(),
+        })
+    }
+}
+
+impl EmitterState {
+    fn new(
+        vulkan_backend: &mut VulkanBackend,
+        frame_data: &FrameData,
+        resources: &ParticleResources,
+        data: &ParticleEmitterData,
+        gpu: bool,
+    ) -> Self {
+        let pool_size = pool_size(data);
+        let pool = pool_size as usize;
+        // The CPU path writes particles every frame; the GPU path never reads them back.
+        let memory_hint = || {
+            if gpu {
+                MemoryHint::GPUOnly
+            } else {
+                MemoryHint::CPUWritable
+            }
+        };
+        let copies = if gpu {
+            1
+        } else {
+            vulkan_backend.frames_in_flight()
+        };
+        let particles: Vec<BufferHandle> = (0..copies)
+            .map(|_| {
+                vulkan_backend.create_buffer::<GpuParticle>(
+                    BufferDesc {
+                        size: size_of::<GpuParticle>() * pool,
+                        usage: BufferUsageFlags::STORAGE,
+                        memory_hint: memory_hint(),
+                    },
+                    None,
+                )
+            })
+            .collect();
+        // The CPU path keeps its particles compacted, so its first list is the identity.
+        let identity: Vec<u32> = (0..pool_size).chain(0..pool_size).collect();
+        let alive = vulkan_backend.create_buffer(
+            BufferDesc {
+                size: size_of::<u32>() * pool * 2,
+                usage: BufferUsageFlags::STORAGE,
+                memory_hint: memory_hint(),
+            },
+            Some(identity.as_slice()),
+        );
+        let dead = vulkan_backend.create_buffer(
+            BufferDesc {
+                size: size_of::<u32>() * pool,
+                usage: BufferUsageFlags::STORAGE,
+                memory_hint: MemoryHint::GPUOnly,
+            },
+            Some(&identity[..pool]),
+        );
+        let counters = vulkan_backend.create_buffer(
+            BufferDesc {
+                size: size_of::<ParticleCounters>(),
+                usage: BufferUsageFlags::STORAGE | BufferUsageFlags::INDIRECT,
+                memory_hint: MemoryHint::GPUOnly,
+            },
+            Some(&[ParticleCounters {
+                vertex_count: 0,
+                instance_count: 1,
+                first_vertex: 0,
+                first_instance: 0,
+                dead_count: pool_size as i32,
+                alive_count: [0; 2],
+                _padding: Padding::default(),
+            }]),
+        );
+
+        let images = &frame_data.frame_images;
+        let sampled = |image| {
+            DescriptorValue::SampledImage(SampledImageInfo {
+                image,
+                sampler: resources.sampler,
+            })
+        };
+        let descriptor_sets = FrameRing::new(vulkan_backend, |vulkan_backend, slot| {
+            let descriptor_set = vulkan_backend.allocate_descriptor_set(resources.layout);
+            let writes = [
+                DescriptorValue::StorageBuffer(particles[slot.min(particles.len() - 1)]),
+                DescriptorValue::StorageBuffer(alive),
+                DescriptorValue::StorageBuffer(dead),
+                DescriptorValue::StorageBuffer(counters),
+                DescriptorValue::UniformBuffer(frame_data.buffers.get(slot).camera_buffer),
+                sampled(images.gbuffer_depth),
+                sampled(images.gbuffer_normal),
+            ];
+            let writes: Vec<DescriptorWriteDesc> = writes
+                .into_iter()
+                .enumerate()
+                .map(|(binding, value)| DescriptorWriteDesc { binding, value })
+                .collect();
+            vulkan_backend.update_descriptor_set(descriptor_set, &writes);
+            descriptor_set
+        });
+
+        Self {
+            pool_size,
+            particles,
+            alive,
+            dead,
+            counters,
+            descriptor_sets,
+            emission_carry: 0.0,
+            parity: 0,
+            cpu_particles: Vec::new(),
+        }
+    }
+
+    /// Particles to spawn this frame. Fractions carry over, so low rates still emit.
+    fn emission_count(&mut self, data: &ParticleEmitterData, delta: f32) -> u32 {
+        let emitter = &data.emitter;
+        if !emitter.emitting {
+            self.emission_carry = 0.0;
+            return 0;
+        }
+        let total = self.emission_carry + emitter.emission_rate.max(0.0) * delta;
+        let count = total.floor();
+        self.emission_carry = total - count;
+        (count as u32).min(self.pool_size)
+    }
+
+    /// Records the emit, simulate and finalize passes.
+    fn simulate_gpu(
+        &mut self,
+        vulkan_backend: &mut VulkanBackend,
+        [emit, simulate, finalize]: [PipelineHandle; 3],
+        data: &ParticleEmitterData,
+        seed: u32,
+        delta: f32,
+    ) {
+        let emitter = &data.emitter;
+        let emit_count = self.emission_count(data, delta);
+        let bounce = if emitter.collide {
+            emitter.bounce.max(0.0)
+        } else {
+            -1.0
+        };
+        let descriptor_set = *self.descriptor_sets.current(vulkan_backend);
+        let push_constants = ParticleSimPushConstants {
+            emitter_position: data.position.push(emitter.spread.clamp(0.0, PI).cos()),
+            emitter_direction: data.direction.normalize().push(emitter.speed),
+            gravity_drag: emitter.gravity.push(emitter.drag),
+            params: Vec4::new(delta, emitter.lifetime.max(f32::EPSILON), bounce, 0.0),
+            emit_count,
+            pool_size: self.pool_size,
+            parity: self.parity,
+            seed,
+        };
+        let passes = [
+            (emit, emit_count.div_ceil(WORKGROUP_SIZE)),
+            (simulate, self.pool_size.div_ceil(WORKGROUP_SIZE)),
+            (finalize, 1),
+        ];
+        for (i, (pipeline, groups)) in passes.into_iter().enumerate() {
+            if i > 0 {
+                vulkan_backend.compute_barrier();
+            }
+            if groups == 0 {
+                continue;
+            }
+            vulkan_backend.bind_pipeline(pipeline);
+            vulkan_backend.bind_descriptor_sets(&[descriptor_set], pipeline);
+            vulkan_backend.update_push_constants(pipeline, ShaderStage::COMPUTE, &[push_constants]);
+            vulkan_backend.dispatch(groups, 1, 1);
+        }
+        self.parity = 1 - self.parity;
+    }
+
+    fn release(&self, vulkan_backend: &mut VulkanBackend) {
+        for &buffer in self.particles.iter().chain([&self.alive, &self.dead, &self.counters]) {
+            vulkan_backend.release_buffer(buffer);
+        }
+        for &descriptor_set in self.descriptor_sets.iter() {
+            vulkan_backend.release_descriptor_set(descriptor_set);
+        }
+    }
+}
+
+/// Spawns `emit` particles, as far as the pool allows, then advances every particle by
+/// `delta` seconds. Mirrors the emit and simulate shaders, without collision.
+fn simulate_cpu(
+    particles: &mut Vec<GpuParticle>,
+    data: &ParticleEmitterData,
+    emit: u32,
+    seed: u32,
+    delta: f32,
+) {
+    let emitter = &data.emitter;
+    let free = (pool_size(data) as usize).saturating_sub(particles.len());
+    let axis = data.direction.normalize();
+    let cos_spread = emitter.spread.clamp(0.0, PI).cos();
+    let lifetime = emitter.lifetime.max(f32::EPSILON);
+    particles.extend((0..emit.min(free as u32)).map(|i| {
+        let direction = cone_direction(&axis, cos_spread, hash(seed ^ hash(i)));
+        GpuParticle {
+            position_age: data.position.push(0.0),
+            velocity_life: (direction * emitter.speed).push(lifetime),
+        }
+    }));
+
+    let damping = (1.0 - emitter.drag * delta).max(0.0);
+    particles.retain_mut(|particle| {
+        particle.position_age.w += delta;
+        if particle.position_age.w >= particle.velocity_life.w {
+            return false;
+        }
+        let velocity = (particle.velocity_life.xyz() + emitter.gravity * delta) * damping;
+        let position = particle.position_age.xyz() + velocity * delta;
+        particle.position_age = position.push(particle.position_age.w);
+        particle.velocity_life = velocity.push(particle.velocity_life.w);
+        true
+    });
+}
+
+fn pool_size(data: &ParticleEmitterData) -> u32 {
+    data.emitter.max_particles.max(1)
+}
+
+/// PCG hash, matching `hash` in the particle shaders.
+fn hash(v: u32) -> u32 {
+    let state = v.wrapping_mul(747796405).wrapping_add(2891336453);
+    let word = ((state >> ((state >> 28) + 4)) ^ state).wrapping_mul(277803737);
+    (word >> 22) ^ word
+}
+
+fn unit_float(v: u32) -> f32 {
+    (v >> 8) as f32 / 16777216.0
+}
+
+/// Uniformly distributed direction within `cos_spread` of `axis`.
+fn cone_direction(axis: &Vec3, cos_spread: f32, random: u32) -> Vec3 {
+    let second = hash(random);
+    let cos_theta = 1.0 + (cos_spread - 1.0) * unit_float(random);
+    let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+    let phi = TAU * unit_float(second);
+    let helper = if axis.y.abs() < 0.999 {
+        Vec3::y()
+    } else {
+        Vec3::x()
+    };
+    let tangent = helper.cross(axis).normalize();
+    let bitangent = axis.cross(&tangent);
+    (tangent * phi.cos() + bitangent * phi.sin()) * sin_theta + axis * cos_theta
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::particles::ParticleEmitterComponent;
+
+    fn emitter_data(emitter: ParticleEmitterComponent) -> ParticleEmitterData {
+        ParticleEmitterData {
+            entity: Entity(0),
+            position: Vec3::new(1.0, 2.0, 3.0),
+            direction: Vec3::z(),
+            emitter,
+        }
+    }
+
+    #[test]
+    fn cpu_particles_spawn_in_the_cone_and_expire() {
+        let data = emitter_data(ParticleEmitterComponent {
+            max_particles: 8,
+            lifetime: 1.0,
+            spread: 0.2,
+            gravity: Vec3::zeros(),
+            drag: 0.0,
+            ..ParticleEmitterComponent::default()
+        });
+        let mut particles = Vec::new();
+        simulate_cpu(&mut particles, &data, 20, 7, 0.5);
+        // Capped by the pool.
+        assert_eq!(particles.len(), 8);
+        for particle in &particles {
+            let direction = particle.velocity_life.xyz().normalize();
+            assert!(direction.dot(&Vec3::z()) >= 0.2f32.cos() - 1e-5);
+            let travelled = particle.position_age.xyz() - data.position;
+            assert!((travelled.norm() - data.emitter.speed * 0.5).abs() < 1e-4);
+        }
+
+        simulate_cpu(&mut particles, &data, 0, 8, 0.5);
+        assert!(particles.is_empty());
+    }
+}
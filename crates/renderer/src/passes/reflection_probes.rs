@@ -0,0 +1,544 @@
+use crate::frame_data::FrameData;
+use crate::frame_ring::FrameRing;
+use crate::passes::image_based_lighting::{SPECULAR_MIPS, SPECULAR_SIZE, WORKGROUP_SIZE};
+use crate::render_data::{CameraRenderData, ReflectionProbeData};
+use crate::shader_loader::ShaderCache;
+use ecs::entity::Entity;
+use material::ShaderRef;
+use nalgebra_glm::{self as glm, Mat4, Vec3, Vec4};
+use rendering_backend::backend_impl::vulkan_backend::VulkanBackend;
+use rendering_backend::buffer::{BufferDesc, BufferHandle, BufferUsageFlags};
+use rendering_backend::descriptor::{
+    DescriptorBinding, DescriptorLayoutDesc, DescriptorLayoutHandle, DescriptorSetHandle,
+    DescriptorType, DescriptorValue, DescriptorWriteDesc, SampledImageInfo, ShaderStage,
+    StorageImageInfo,
+};
+use rendering_backend::gpu_layout::{GpuStruct, Padding};
+use rendering_backend::image::{
+    GpuImageHandle, ImageAspect, ImageDesc, ImageUsageFlags, TextureFormat,
+};
+use rendering_backend::memory::MemoryHint;
+use rendering_backend::pipeline::{
+    ComputePipelineDesc, PipelineHandle, PushConstantDesc, SpecializationConstants,
+};
+use rendering_backend::sampler::{Filter, SamplerAddressMode, SamplerDesc, SamplerHandle};
+use rendering_backend::sync::ResourceState;
+use std::collections::HashMap;
+
+/// Probes the lighting pass samples at once.
+pub const MAX_REFLECTION_PROBES: usize = 4;
+/// Size of the equirectangular panorama the faces of a capture are gathered in.
+const PANORAMA_WIDTH: u32 = 512;
+const PANORAMA_HEIGHT: u32 = 256;
+/// Near clip plane of the face cameras, close enough for probes placed near a wall.
+const CAPTURE_NEAR_CLIP: f32 = 0.05;
+
+/// Look direction and up vector of each face camera, in Vulkan cube face order.
+const FACES: [(Vec3, Vec3); 6] = [
+    (Vec3::new(1.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0)),
+    (Vec3::new(-1.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0)),
+    (Vec3::new(0.0, 1.0, 0.0), Vec3::new(0.0, 0.0, 1.0)),
+    (Vec3::new(0.0, -1.0, 0.0), Vec3::new(0.0, 0.0, 1.0)),
+    (Vec3::new(0.0, 0.0, 1.0), Vec3::new(0.0, 1.0, 0.0)),
+    (Vec3::new(0.0, 0.0, -1.0), Vec3::new(0.0, 1.0, 0.0)),
+];
+
+/// Boxes and capture positions of the probes bound to the lighting set, in slot order.
+#[repr(C)]
+#[derive(Clone, Copy, GpuStruct)]
+pub(crate) struct ReflectionProbeUbo {
+    /// xyz: capture position, w: intensity.
+    position_intensity: [Vec4; MAX_REFLECTION_PROBES],
+    /// xyz: box minimum, w: blend distance.
+    box_min: [Vec4; MAX_REFLECTION_PROBES],
+    box_max: [Vec4; MAX_REFLECTION_PROBES],
+    /// x: probe count.
+    params: Vec4,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, GpuStruct)]
+#[gpu(std430)]
+pub(crate) struct CapturePushConstants {
+    /// The face camera's projection times its view without the translation.
+    direction_view_proj: Mat4,
+    face: i32,
+    _padding: Padding<12>,
+}
+
+/// Static reflection probes: cube maps captured at each [`ReflectionProbeData`]'s
+/// position and prefiltered like the skybox's specular map. The lighting pass samples the
+/// probes closest to the camera, box projected.
+///
+/// A capture renders the scene from the probe over six frames, one cube face each, with
+/// the face camera standing in for the active camera. After the sky pass each face is
+/// copied into a shared panorama; after the last one the panorama is prefiltered into
+/// the probe's cube map. One probe is captured at a time.
+pub struct ReflectionProbes {
+    capture_pipeline: PipelineHandle,
+    specular_pipeline: PipelineHandle,
+    prefilter_layout: DescriptorLayoutHandle,
+    capture_set: DescriptorSetHandle,
+    panorama: GpuImageHandle,
+    sampler: SamplerHandle,
+    /// Uniforms of the probes bound by each frame slot's lighting set.
+    probe_buffers: FrameRing<BufferHandle>,
+    probes: HashMap<Entity, Probe>,
+    capture: Option<Capture>,
+    /// Probes in each frame slot's lighting set probe slots, in slot order.
+    bound: Vec<Vec<Entity>>,
+}
+
+struct Probe {
+    specular_map: GpuImageHandle,
+    /// One set per specular mip, prefiltering the panorama into that mip.
+    specular_sets: Vec<DescriptorSetHandle>,
+    /// Capture generation the cube map holds; `None` before the first capture finished.
+    captured: Option<u32>,
+}
+
+struct Capture {
+    entity: Entity,
+    generation: u32,
+    position: Vec3,
+    /// Face rendered this frame.
+    face: usize,
+    /// The face camera's projection times its view without the translation.
+    direction_view_proj: Mat4,
+}
+
+impl ReflectionProbes {
+    pub fn new(
+        vulkan_backend: &mut VulkanBackend,
+        frame_data: &FrameData,
+        shader_cache: &mut ShaderCache,
+    ) -> Self {
+        let panorama = vulkan_backend.create_image(ImageDesc {
+            width: PANORAMA_WIDTH,
+            height: PANORAMA_HEIGHT,
+            depth: 1,
+            mip_levels: 1,
+            array_layers: 1,
+            is_cubemap: false,
+            format: TextureFormat::R16g16b16a16Float,
+            aspect: ImageAspect::Color,
+            usage: ImageUsageFlags::SAMPLED | ImageUsageFlags::STORAGE,
+            clear_value: None,
+        });
+        let sampler = vulkan_backend.create_sampler(SamplerDesc {
+            mag_filter: Filter::Linear,
+            min_filter: Filter::Linear,
+            address_u: SamplerAddressMode::ClampToEdge,
+            address_v: SamplerAddressMode::ClampToEdge,
+            address_w: SamplerAddressMode::ClampToEdge,
+            compare_enable: false,
+            compare_op: None,
+        });
+        let probe_buffers = FrameRing::new(vulkan_backend, |vulkan_backend, _| {
+            vulkan_backend.create_buffer::<ReflectionProbeUbo>(
+                BufferDesc {
+                    size: size_of::<ReflectionProbeUbo>(),
+                    usage: BufferUsageFlags::UNIFORM,
+                    memory_hint: MemoryHint::CPUWritable,
+                },
+                None,
+            )
+        });
+        let bound = vec![Vec::new(); vulkan_backend.frames_in_flight()];
+
+        // Sampled source at binding 0 and written image at binding 1, like the
+        // image-based lighting prefilter passes.
+        let binding = |binding, descriptor_type| DescriptorBinding {
+            binding,
+            descriptor_type,
+            count: 1,
+            stages: ShaderStage::COMPUTE,
+        };
+        let prefilter_layout = vulkan_backend.create_descriptor_layout(DescriptorLayoutDesc {
+            bindings: vec![
+                binding(0, DescriptorType::CombinedImageSampler),
+                binding(1, DescriptorType::StorageImage),
+            ],
+        });
+        let capture_set = vulkan_backend.allocate_descriptor_set(prefilter_layout);
+        vulkan_backend.update_descriptor_set(
+            capture_set,
+            &[
+                DescriptorWriteDesc {
+                    binding: 0,
+                    value: DescriptorValue::SampledImage(SampledImageInfo {
+                        image: frame_data.frame_images.draw_image,
+                        sampler,
+                    }),
+                },
+                DescriptorWriteDesc {
+                    binding: 1,
+                    value: DescriptorValue::StorageImage(StorageImageInfo {
+                        image: panorama,
+                        mip_level: 0,
+                    }),
+                },
+            ],
+        );
+
+        let mut compute_pipeline = |shader: &str, push_constant_size| {
+            vulkan_backend.create_compute_pipeline(ComputePipelineDesc {
+                shader: shader_cache.load(&ShaderRef::BuiltIn(shader.into()), &[]),
+                layout: vec![prefilter_layout],
+                push_constant_ranges: vec![PushConstantDesc {
+                    stages: ShaderStage::COMPUTE,
+                    offset: 0,
+                    size: push_constant_size,
+                }],
+                specialization: SpecializationConstants::default(),
+            })
+        };
+        let capture_pipeline = compute_pipeline(
+            "reflection_probe_capture",
+            size_of::<CapturePushConstants>(),
+        );
+        let specular_pipeline = compute_pipeline("ibl_specular", size_of::<f32>());
+
+        Self {
+            capture_pipeline,
+            specular_pipeline,
+            prefilter_layout,
+            capture_set,
+            panorama,
+            sampler,
+            probe_buffers,
+            probes: HashMap::new(),
+            capture: None,
+            bound,
+        }
+    }
+
+    /// Descriptor writes binding frame slot `frame_slot`'s probe cube maps at
+    /// `first_binding` and the [`MAX_REFLECTION_PROBES`] - 1 bindings after it, then its
+    /// probe uniform buffer. Unused slots get `placeholder`, a cube map in a sampled
+    /// layout.
+    pub fn descriptor_writes(
+        &self,
+        frame_slot: usize,
+        first_binding: usize,
+        placeholder: GpuImageHandle,
+    ) -> Vec<DescriptorWriteDesc> {
+        let bound = &self.bound[frame_slot];
+        let mut writes: Vec<DescriptorWriteDesc> = (0..MAX_REFLECTION_PROBES)
+            .map(|slot| {
+                // A probe removed since the frame slot last ran leaves its slot unused.
+                let image = bound
+                    .get(slot)
+                    .and_then(|entity| self.probes.get(entity))
+                    .map_or(placeholder, |probe| probe.specular_map);
+                DescriptorWriteDesc {
+                    binding: first_binding + slot,
+                    value: DescriptorValue::SampledImage(SampledImageInfo {
+                        image,
+                        sampler: self.sampler,
+                    }),
+                }
+            })
+            .collect();
+        writes.push(DescriptorWriteDesc {
+            binding: first_binding + MAX_REFLECTION_PROBES,
+            value: DescriptorValue::UniformBuffer(*self.probe_buffers.get(frame_slot)),
+        });
+        writes
+    }
+
+    /// Creates cube maps for new probes and frees those of removed ones, then continues
+    /// the capture in progress or starts one for a probe that is new or asked to
+    /// `recapture`. Returns the camera to render this frame with while capturing; call
+    /// before the frame's camera is uploaded.
+    pub fn begin_frame(
+        &mut self,
+        vulkan_backend: &mut VulkanBackend,
+        probes: &[ReflectionProbeData],
+        camera: &CameraRenderData,
+    ) -> Option<CameraRenderData> {
+        let removed: Vec<Entity> = self
+            .probes
+            .keys()
+            .filter(|&&entity| !probes.iter().any(|probe| probe.entity == entity))
+            .copied()
+            .collect();
+        for entity in removed {
+            let probe = self.probes.remove(&entity).expect("listed above");
+            vulkan_backend.release_image(probe.specular_map);
+            for set in probe.specular_sets {
+                vulkan_backend.release_descriptor_set(set);
+            }
+            if self
+                .capture
+                .as_ref()
+                .is_some_and(|capture| capture.entity == entity)
+            {
+                self.capture = None;
+            }
+        }
+        for probe in probes {
+            if !self.probes.contains_key(&probe.entity) {
+                let created = self.create_probe(vulkan_backend);
+                self.probes.insert(probe.entity, created);
+            }
+        }
+
+        if self.capture.is_none() {
+            self.capture = probes
+                .iter()
+                .find(|probe| {
+                    self.probes[&probe.entity].captured != Some(probe.probe.capture_generation())
+                })
+                .map(|probe| Capture {
+                    entity: probe.entity,
+                    generation: probe.probe.capture_generation(),
+                    position: probe.position,
+                    face: 0,
+                    direction_view_proj: Mat4::identity(),
+                });
+        }
+        let capture = self.capture.as_mut()?;
+        let (face_camera, direction_view_proj) =
+            face_camera(capture.position, capture.face, camera);
+        capture.direction_view_proj = direction_view_proj;
+        Some(face_camera)
+    }
+
+    /// Copies the face captured this frame from the lit draw image into the panorama,
+    /// and after the last face prefilters it into the probe's cube map. Records into the
+    /// frame; call outside of rendering, after the sky pass.
+    pub fn capture_face(&mut self, vulkan_backend: &mut VulkanBackend, frame_data: &FrameData) {
+        let Some(capture) = &mut self.capture else {
+            return;
+        };
+        let draw_image = frame_data.frame_images.draw_image;
+
+        vulkan_backend.push_pass_marker("Reflection probe capture");
+        vulkan_backend.transition_image(draw_image, ResourceState::ComputeShaderRead);
+        vulkan_backend.transition_image(self.panorama, ResourceState::ComputeShaderWrite);
+        vulkan_backend.bind_pipeline(self.capture_pipeline);
+        vulkan_backend.bind_descriptor_sets(&[self.capture_set], self.capture_pipeline);
+        vulkan_backend.update_push_constants(
+            self.capture_pipeline,
+            ShaderStage::COMPUTE,
+            &[CapturePushConstants {
+                direction_view_proj: capture.direction_view_proj,
+                face: capture.face as i32,
+                _padding: Padding::default(),
+            }],
+        );
+        vulkan_backend.dispatch(
+            PANORAMA_WIDTH.div_ceil(WORKGROUP_SIZE),
+            PANORAMA_HEIGHT.div_ceil(WORKGROUP_SIZE),
+            1,
+        );
+
+        capture.face += 1;
+        if capture.face == FACES.len() {
+            let probe = self
+                .probes
+                .get_mut(&capture.entity)
+                .expect("probes being captured exist");
+            vulkan_backend.transition_image(self.panorama, ResourceState::ComputeShaderRead);
+            vulkan_backend.transition_image(probe.specular_map, ResourceState::ComputeShaderWrite);
+            vulkan_backend.bind_pipeline(self.specular_pipeline);
+            for (mip, &set) in probe.specular_sets.iter().enumerate() {
+                let roughness = mip as f32 / (SPECULAR_MIPS - 1) as f32;
+                let groups = (SPECULAR_SIZE >> mip).div_ceil(WORKGROUP_SIZE);
+                vulkan_backend.bind_descriptor_sets(&[set], self.specular_pipeline);
+                vulkan_backend.update_push_constants(
+                    self.specular_pipeline,
+                    ShaderStage::COMPUTE,
+                    &[roughness],
+                );
+                vulkan_backend.dispatch(groups, groups, 6);
+            }
+            vulkan_backend.transition_image(probe.specular_map, ResourceState::FragmentShaderRead);
+            probe.captured = Some(capture.generation);
+            self.capture = None;
+        }
+        vulkan_backend.pop_pass_marker();
+    }
+
+    /// Binds the captured probes closest to `camera_position` to the lighting set's probe
+    /// slots from `first_binding` on, rewriting them if the selection changed, and
+    /// uploads their boxes. The probe being captured is left out. Call before the
+    /// lighting pass with the current frame slot's lighting set.
+    pub fn update(
+        &mut self,
+        vulkan_backend: &mut VulkanBackend,
+        probes: &[ReflectionProbeData],
+        camera_position: Vec3,
+        lighting_set: DescriptorSetHandle,
+        first_binding: usize,
+        placeholder: GpuImageHandle,
+    ) {
+        let capturing = self.capture.as_ref().map(|capture| capture.entity);
+        let usable: Vec<&ReflectionProbeData> = probes
+            .iter()
+            .filter(|probe| {
+                Some(probe.entity) != capturing
+                    && self
+                        .probes
+                        .get(&probe.entity)
+                        .is_some_and(|p| p.captured.is_some())
+            })
+            .collect();
+        let selected = select_probes(&usable, camera_position);
+        let frame_slot = vulkan_backend.frame_slot();
+
+        let bound: Vec<Entity> = selected.iter().map(|probe| probe.entity).collect();
+        if bound != self.bound[frame_slot] {
+            self.bound[frame_slot] = bound;
+            vulkan_backend.update_descriptor_set(
+                lighting_set,
+                &self.descriptor_writes(frame_slot, first_binding, placeholder),
+            );
+        }
+
+        let mut ubo = ReflectionProbeUbo {
+            position_intensity: [Vec4::zeros(); MAX_REFLECTION_PROBES],
+            box_min: [Vec4::zeros(); MAX_REFLECTION_PROBES],
+            box_max: [Vec4::zeros(); MAX_REFLECTION_PROBES],
+            params: Vec4::new(selected.len() as f32, 0.0, 0.0, 0.0),
+        };
+        for (slot, probe) in selected.iter().enumerate() {
+            let (min, max) = probe_box(probe);
+            let settings = &probe.probe;
+            ubo.position_intensity[slot] = probe.position.push(settings.intensity.max(0.0));
+            ubo.box_min[slot] = min.push(settings.blend_distance.max(0.0));
+            ubo.box_max[slot] = max.push(0.0);
+        }
+        vulkan_backend.update_buffer(*self.probe_buffers.get(frame_slot), &[ubo]);
+    }
+
+    fn create_probe(&self, vulkan_backend: &mut VulkanBackend) -> Probe {
+        let specular_map = vulkan_backend.create_image(ImageDesc {
+            width: SPECULAR_SIZE,
+            height: SPECULAR_SIZE,
+            depth: 1,
+            mip_levels: SPECULAR_MIPS,
+            array_layers: 6,
+            is_cubemap: true,
+            format: TextureFormat::R16g16b16a16Float,
+            aspect: ImageAspect::Color,
+            usage: ImageUsageFlags::SAMPLED | ImageUsageFlags::STORAGE,
+            clear_value: None,
+        });
+        let specular_sets = (0..SPECULAR_MIPS)
+            .map(|mip_level| {
+                let set = vulkan_backend.allocate_descriptor_set(self.prefilter_layout);
+                vulkan_backend.update_descriptor_set(
+                    set,
+                    &[
+                        DescriptorWriteDesc {
+                            binding: 0,
+                            value: DescriptorValue::SampledImage(SampledImageInfo {
+                                image: self.panorama,
+                                sampler: self.sampler,
+                            }),
+                        },
+                        DescriptorWriteDesc {
+                            binding: 1,
+                            value: DescriptorValue::StorageImage(StorageImageInfo {
+                                image: specular_map,
+                                mip_level,
+                            }),
+                        },
+                    ],
+                );
+                set
+            })
+            .collect();
+        Probe {
+            specular_map,
+            specular_sets,
+            captured: None,
+        }
+    }
+}
+
+/// The world-space box a probe affects.
+fn probe_box(probe: &ReflectionProbeData) -> (Vec3, Vec3) {
+    let half_size = probe.probe.size.abs() * 0.5;
+    (probe.position - half_size, probe.position + half_size)
+}
+
+/// Up to [`MAX_REFLECTION_PROBES`] of `probes`, those whose box is closest to
+/// `camera_position`, ordered smallest box first so that nested probes take precedence
+/// over the ones around them.
+fn select_probes<'a>(
+    probes: &[&'a ReflectionProbeData],
+    camera_position: Vec3,
+) -> Vec<&'a ReflectionProbeData> {
+    let box_distance = |probe: &ReflectionProbeData| {
+        let (min, max) = probe_box(probe);
+        let nearest = glm::clamp_vec(&camera_position, &min, &max);
+        glm::distance(&camera_position, &nearest)
+    };
+    let volume = |probe: &ReflectionProbeData| probe.probe.size.abs().product();
+
+    let mut selected = probes.to_vec();
+    selected.sort_by(|a, b| box_distance(a).total_cmp(&box_distance(b)));
+    selected.truncate(MAX_REFLECTION_PROBES);
+    selected.sort_by(|a, b| volume(a).total_cmp(&volume(b)));
+    selected
+}
+
+/// Camera rendering cube face `face` from `position`, with the aspect ratio and far
+/// plane of `camera`, and the face camera's projection times its view without the
+/// translation. Its field of view is widened past 90 degrees where the aspect ratio
+/// needs it, so the frustum always contains the whole face.
+fn face_camera(position: Vec3, face: usize, camera: &CameraRenderData) -> (CameraRenderData, Mat4) {
+    let (forward, up) = FACES[face];
+    let aspect_ratio = camera.aspect_ratio.max(f32::EPSILON);
+    let fov = 90f32.max((2.0 * (1.0 / aspect_ratio).atan()).to_degrees());
+    let far_clip = camera.far_clip.max(CAPTURE_NEAR_CLIP * 2.0);
+    let mut proj = glm::perspective(aspect_ratio, fov.to_radians(), CAPTURE_NEAR_CLIP, far_clip);
+    proj[(1, 1)] *= -1.0; // Vulkan Y-flip
+    let view = glm::look_at(&position, &(position + forward), &up);
+    let rotation = glm::look_at(&Vec3::zeros(), &forward, &up);
+    let camera = CameraRenderData {
+        view,
+        proj,
+        near_clip: CAPTURE_NEAR_CLIP,
+        far_clip,
+        fov,
+        aspect_ratio,
+    };
+    (camera, proj * rotation)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn face_cameras_contain_their_whole_face() {
+        for aspect_ratio in [16.0 / 9.0, 1.0, 9.0 / 16.0] {
+            let camera = CameraRenderData {
+                view: Mat4::identity(),
+                proj: Mat4::identity(),
+                near_clip: 0.1,
+                far_clip: 100.0,
+                fov: 60.0,
+                aspect_ratio,
+            };
+            for (face, &(forward, up)) in FACES.iter().enumerate() {
+                let (_, direction_view_proj) = face_camera(Vec3::new(1.0, 2.0, 3.0), face, &camera);
+                let right = forward.cross(&up);
+                let project = |dir: Vec3| {
+                    let clip = direction_view_proj * dir.push(1.0);
+                    assert!(clip.w > 0.0);
+                    clip.xy() / clip.w
+                };
+                assert!(project(forward).norm() < 1e-5);
+                for (x, y) in [(-1.0, -1.0), (-1.0, 1.0), (1.0, -1.0), (1.0, 1.0)] {
+                    let ndc = project(forward + right * x + up * y);
+                    assert!(ndc.abs().max() <= 1.0 + 1e-4, "face {face} corner at {ndc}");
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,306 @@
+use crate::frame_data::FrameData;
+use crate::frame_ring::FrameRing;
+use crate::passes::create_fullscreen_pipeline;
+use crate::render_scene::RenderScene;
+use crate::shader_loader::ShaderCache;
+use core::environment::ProceduralSky;
+use material::ShaderRef;
+use nalgebra_glm::{Mat4, Vec3, Vec4};
+use rendering_backend::backend_impl::vulkan_backend::VulkanBackend;
+use rendering_backend::buffer::{BufferDesc, BufferHandle, BufferUsageFlags};
+use rendering_backend::descriptor::{
+    DescriptorBinding, DescriptorLayoutDesc, DescriptorSetHandle, DescriptorType, DescriptorValue,
+    DescriptorWriteDesc, SampledImageInfo, ShaderStage,
+};
+use rendering_backend::gpu_layout::GpuStruct;
+use rendering_backend::memory::MemoryHint;
+use rendering_backend::pipeline::PipelineHandle;
+use rendering_backend::sampler::{Filter, SamplerAddressMode, SamplerDesc};
+use rendering_backend::sync::ResourceState;
+
+/// Sun zenith angles beyond this leave the Preetham model's valid range; lower suns keep
+/// the horizon colors and only fade out.
+const MAX_SUN_ZENITH: f32 = 89.0 * std::f32::consts::PI / 180.0;
+
+#[repr(C)]
+#[derive(Clone, Copy, GpuStruct)]
+pub(crate) struct SkyUbo {
+    inverse_view_proj: Mat4,
+    camera_position: Vec4,
+    /// xyz: direction towards the sun.
+    sun_direction: Vec4,
+    /// rgb: radiance of the sun disk.
+    sun_color: Vec4,
+    /// Perez coefficients A to D for luminance, then the two chromaticities.
+    perez_luminance: Vec4,
+    perez_x: Vec4,
+    perez_y: Vec4,
+    /// Perez coefficient E for luminance, x and y.
+    perez_e: Vec4,
+    /// xyz: zenith luminance and chromaticities over the Perez function at the zenith,
+    /// w: exposure.
+    zenith: Vec4,
+}
+
+/// Preetham et al., "A Practical Analytic Model for Daylight": coefficients A to E of
+/// the Perez sky function for luminance and the x, y chromaticities, plus their values
+/// at the zenith.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct PreethamSky {
+    perez: [[f32; 5]; 3],
+    /// Luminance in kcd/m² and chromaticities straight up.
+    zenith: [f32; 3],
+}
+
+impl PreethamSky {
+    /// `sun_zenith` is the angle between the sun and straight up, in radians.
+    fn new(turbidity: f32, sun_zenith: f32) -> Self {
+        let t = turbidity;
+        let perez = [
+            [
+                0.1787 * t - 1.4630,
+                -0.3554 * t + 0.4275,
+                -0.0227 * t + 5.3251,
+                0.1206 * t - 2.5771,
+                -0.0670 * t + 0.3703,
+            ],
+            [
+                -0.0193 * t - 0.2592,
+                -0.0665 * t + 0.0008,
+                -0.0004 * t + 0.2125,
+                -0.0641 * t - 0.8989,
+                -0.0033 * t + 0.0452,
+            ],
+            [
+                -0.0167 * t - 0.2608,
+                -0.0950 * t + 0.0092,
+                -0.0079 * t + 0.2102,
+                -0.0441 * t - 1.6537,
+                -0.0109 * t + 0.0529,
+            ],
+        ];
+
+        let theta = sun_zenith;
+        let chi = (4.0 / 9.0 - t / 120.0) * (std::f32::consts::PI - 2.0 * theta);
+        let luminance = (4.0453 * t - 4.9710) * chi.tan() - 0.2155 * t + 2.4192;
+        let chromaticity = |m: [[f32; 4]; 3]| {
+            let cubic = |c: [f32; 4]| ((c[0] * theta + c[1]) * theta + c[2]) * theta + c[3];
+            t * t * cubic(m[0]) + t * cubic(m[1]) + cubic(m[2])
+        };
+        let x = chromaticity([
+            [0.00166, -0.00375, 0.00209, 0.0],
+            [-0.02903, 0.06377, -0.03202, 0.00394],
+            [0.11693, -0.21196, 0.06052, 0.25886],
+        ]);
+        let y = chromaticity([
+            [0.00275, -0.00610, 0.00317, 0.0],
+            [-0.04214, 0.08970, -0.04153, 0.00516],
+            [0.15346, -0.26756, 0.06670, 0.26688],
+        ]);
+
+        Self {
+            perez,
+            zenith: [luminance, x, y],
+        }
+    }
+
+    /// Zenith values over the Perez function at the zenith, the factor the sky shader
+    /// scales the Perez function by.
+    fn normalized_zenith(&self, sun_zenith: f32) -> [f32; 3] {
+        std::array::from_fn(|i| self.zenith[i] / perez(self.perez[i], 0.0, sun_zenith))
+    }
+
+    /// Luminance, x and y `theta` from the zenith and `gamma` from the sun; both in
+    /// radians.
+    #[cfg(test)]
+    fn evaluate(&self, theta: f32, gamma: f32, sun_zenith: f32) -> [f32; 3] {
+        let zenith = self.normalized_zenith(sun_zenith);
+        std::array::from_fn(|i| zenith[i] * perez(self.perez[i], theta, gamma))
+    }
+}
+
+fn perez([a, b, c, d, e]: [f32; 5], theta: f32, gamma: f32) -> f32 {
+    (1.0 + a * (b / theta.cos()).exp()) * (1.0 + c * (d * gamma).exp() + e * gamma.cos().powi(2))
+}
+
+/// Draws a [`ProceduralSky`] over the background pixels after lighting. Resources are
+/// created on the first frame a sky is shown.
+pub struct SkyRenderer {
+    resources: Option<SkyResources>,
+}
+
+struct SkyResources {
+    pipeline: PipelineHandle,
+    /// Each frame in flight's uniforms and the set binding them.
+    frames: FrameRing<(BufferHandle, DescriptorSetHandle)>,
+}
+
+impl SkyRenderer {
+    pub fn new() -> Self {
+        Self { resources: None }
+    }
+
+    pub fn draw_frame(
+        &mut self,
+        vulkan_backend: &mut VulkanBackend,
+        render_scene: &RenderScene,
+        frame_data: &FrameData,
+        shader_cache: &mut ShaderCache,
+    ) {
+        let environment = &render_scene.environment;
+        let (Some(sky), None, Some(camera)) = (
+            &environment.procedural_sky,
+            environment.skybox,
+            &render_scene.camera_data,
+        ) else {
+            return;
+        };
+        let ubo = Self::sky_ubo(sky, render_scene, camera.view, camera.proj);
+        let resources = self.get_or_create_resources(vulkan_backend, frame_data, shader_cache);
+        let &(uniform_buffer, descriptor_set) = resources.frames.current(vulkan_backend);
+        vulkan_backend.update_buffer(uniform_buffer, &[ubo]);
+
+        let images = &frame_data.frame_images;
+        vulkan_backend.push_pass_marker("Sky");
+        vulkan_backend.transition_image(images.gbuffer_depth, ResourceState::FragmentShaderRead);
+        vulkan_backend.begin_rendering_load(&[images.draw_image]);
+        vulkan_backend.bind_pipeline(resources.pipeline);
+        vulkan_backend.bind_descriptor_sets(&[descriptor_set], resources.pipeline);
+        vulkan_backend.draw(3, 0);
+        vulkan_backend.end_rendering();
+        vulkan_backend.pop_pass_marker();
+    }
+
+    fn sky_ubo(sky: &ProceduralSky, render_scene: &RenderScene, view: Mat4, proj: Mat4) -> SkyUbo {
+        let light = render_scene.directional_light.as_ref();
+        let sun = light.map_or_else(Vec3::y, |light| light.direction.normalize());
+        let sun_color = light.map_or_else(Vec3::zeros, |light| {
+            light.color * light.intensity * sky.sun_disk_intensity
+        });
+
+        let sun_zenith = sun.y.clamp(-1.0, 1.0).acos().min(MAX_SUN_ZENITH);
+        let model = PreethamSky::new(sky.turbidity, sun_zenith);
+        let zenith = model.normalized_zenith(sun_zenith);
+        // Dims the sky through dusk, down to black once the sun is well below the horizon.
+        let t = ((sun.y + 0.1) / 0.25).clamp(0.0, 1.0);
+        let exposure = sky.exposure * t * t * (3.0 - 2.0 * t);
+
+        let coefficients = |i: usize| Vec4::from_column_slice(&model.perez[i][..4]);
+        let camera_position = view
+            .try_inverse()
+            .unwrap_or_else(Mat4::identity)
+            .column(3)
+            .into();
+        SkyUbo {
+            inverse_view_proj: (proj * view).try_inverse().unwrap_or_else(Mat4::identity),
+            camera_position,
+            sun_direction: sun.push(0.0),
+            sun_color: sun_color.push(0.0),
+            perez_luminance: coefficients(0),
+            perez_x: coefficients(1),
+            perez_y: coefficients(2),
+            perez_e: Vec4::new(model.perez[0][4], model.perez[1][4], model.perez[2][4], 0.0),
+            zenith: Vec4::new(zenith[0], zenith[1], zenith[2], exposure),
+        }
+    }
+
+    fn get_or_create_resources(
+        &mut self,
+        vulkan_backend: &mut VulkanBackend,
+        frame_data: &FrameData,
+        shader_cache: &mut ShaderCache,
+    ) -> &SkyResources {
+        self.resources.get_or_insert_with(|| {
+            let images = &frame_data.frame_images;
+            let sampler = vulkan_backend.create_sampler(SamplerDesc {
+                mag_filter: Filter::Nearest,
+                min_filter: Filter::Nearest,
+                address_u: SamplerAddressMode::ClampToEdge,
+                address_v: SamplerAddressMode::ClampToEdge,
+                address_w: SamplerAddressMode::ClampToEdge,
+                compare_enable: false,
+                compare_op: None,
+            });
+            let layout = vulkan_backend.create_descriptor_layout(DescriptorLayoutDesc {
+                bindings: vec![
+                    DescriptorBinding {
+                        binding: 0,
+                        descriptor_type: DescriptorType::UniformBuffer,
+                        count: 1,
+                        stages: ShaderStage::FRAGMENT,
+                    },
+                    DescriptorBinding {
+                        binding: 1,
+                        descriptor_type: DescriptorType::CombinedImageSampler,
+                        count: 1,
+                        stages: ShaderStage::FRAGMENT,
+                    },
+                ],
+            });
+            let frames = FrameRing::new(vulkan_backend, |vulkan_backend, _| {
+                let uniform_buffer = vulkan_backend.create_buffer::<SkyUbo>(
+                    BufferDesc {
+                        size: size_of::<SkyUbo>(),
+                        usage: BufferUsageFlags::UNIFORM,
+                        memory_hint: MemoryHint::CPUWritable,
+                    },
+                    None,
+                );
+                let descriptor_set = vulkan_backend.allocate_descriptor_set(layout);
+                vulkan_backend.update_descriptor_set(
+                    descriptor_set,
+                    &[
+                        DescriptorWriteDesc {
+                            binding: 0,
+                            value: DescriptorValue::UniformBuffer(uniform_buffer),
+                        },
+                        DescriptorWriteDesc {
+                            binding: 1,
+                            value: DescriptorValue::SampledImage(SampledImageInfo {
+                                image: images.gbuffer_depth,
+                                sampler,
+                            }),
+                        },
+                    ],
+                );
+                (uniform_buffer, descriptor_set)
+            });
+
+            let quad_vert = shader_cache.load(&ShaderRef::BuiltIn("quad".into()), &[]);
+            let frag = shader_cache.load(&ShaderRef::BuiltIn("sky".into()), &[]);
+            let pipeline = create_fullscreen_pipeline(
+                vulkan_backend,
+                quad_vert,
+                frag,
+                layout,
+                None,
+                images.draw_image,
+                None,
+            );
+            SkyResources { pipeline, frames }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn preetham_sky_is_brightest_towards_the_sun() {
+        let sun_zenith = 60f32.to_radians();
+        let sky = PreethamSky::new(2.5, sun_zenith);
+        // Zenith luminance from the paper's fit, and the bluish white of a clear sky.
+        assert!((sky.zenith[0] - 4.32).abs() < 0.05, "{:?}", sky.zenith);
+        for chromaticity in &sky.zenith[1..] {
+            assert!((0.2..0.35).contains(chromaticity), "{:?}", sky.zenith);
+        }
+
+        let zenith = sky.evaluate(0.0, sun_zenith, sun_zenith);
+        assert!((zenith[0] - sky.zenith[0]).abs() < 1e-4);
+        let horizon = 85f32.to_radians();
+        let towards_sun = sky.evaluate(horizon, horizon - sun_zenith, sun_zenith);
+        let away_from_sun = sky.evaluate(horizon, horizon + sun_zenith, sun_zenith);
+        assert!(towards_sun[0] > 2.0 * away_from_sun[0]);
+    }
+}
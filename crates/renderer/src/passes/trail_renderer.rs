@@ -0,0 +1,319 @@
+use crate::frame_data::FrameData;
+use crate::frame_ring::FrameRing;
+use crate::render_scene::RenderScene;
+use crate::shader_loader::ShaderCache;
+use core::particles::ParticleBlendMode;
+use core::trails::TrailComponent;
+use material::ShaderRef;
+use nalgebra_glm::{Vec3, Vec4};
+use rendering_backend::backend_impl::vulkan_backend::VulkanBackend;
+use rendering_backend::buffer::{BufferDesc, BufferHandle, BufferUsageFlags};
+use rendering_backend::descriptor::{
+    DescriptorBinding, DescriptorLayoutDesc, DescriptorLayoutHandle, DescriptorSetHandle,
+    DescriptorType, DescriptorValue, DescriptorWriteDesc, ShaderStage,
+};
+use rendering_backend::gpu_layout::GpuStruct;
+use rendering_backend::memory::MemoryHint;
+use rendering_backend::pipeline::{
+    BlendAttachmentDesc, BlendFactor, BlendOp, BlendStateDesc, ColorWriteMask, CompareOp, CullMode,
+    DepthStencilDesc, FrontFace, PipelineDesc, PipelineHandle, PolygonMode, PrimitiveTopology,
+    RasterizationStateDesc, SpecializationConstants, VertexInputDesc,
+};
+
+/// Points the buffer holds at first; it doubles whenever a frame needs more.
+const INITIAL_CAPACITY: usize = 256;
+
+/// One point of a trail as `trail.vert` reads it.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, GpuStruct)]
+#[gpu(std430)]
+struct GpuTrailPoint {
+    /// xyz: world-space position, w: half the width.
+    position_half_width: Vec4,
+    /// xyz: world-space direction along the trail.
+    tangent: Vec4,
+    color: Vec4,
+}
+
+/// Draws every trail in the scene as a camera-facing triangle strip, after the particles
+/// and with the same blending, depth tested against the scene.
+///
+/// The points of all trails are uploaded to one storage buffer each frame, with a copy
+/// per frame in flight; the vertex shader widens each point across the trail and the
+/// view ray.
+pub struct TrailRenderer {
+    resources: Option<TrailResources>,
+    /// Reused between frames.
+    points: Vec<GpuTrailPoint>,
+}
+
+struct TrailResources {
+    additive_pipeline: PipelineHandle,
+    alpha_pipeline: PipelineHandle,
+    frames: FrameRing<TrailFrame>,
+}
+
+/// The point buffer of one frame in flight and the set binding it with the frame's
+/// camera.
+struct TrailFrame {
+    buffer: BufferHandle,
+    /// Points `buffer` holds.
+    capacity: usize,
+    descriptor_set: DescriptorSetHandle,
+    camera_buffer: BufferHandle,
+}
+
+impl TrailRenderer {
+    pub fn new() -> Self {
+        Self {
+            resources: None,
+            points: Vec::new(),
+        }
+    }
+
+    pub fn draw_frame(
+        &mut self,
+        vulkan_backend: &mut VulkanBackend,
+        render_scene: &RenderScene,
+        frame_data: &FrameData,
+        shader_cache: &mut ShaderCache,
+    ) {
+        let trails = &render_scene.trails;
+        if trails.is_empty() {
+            return;
+        }
+        self.points.clear();
+        let ranges: Vec<(usize, usize)> = trails
+            .iter()
+            .map(|trail| {
+                let first = self.points.len();
+                trail_points(trail, &mut self.points);
+                (first, self.points.len() - first)
+            })
+            .collect();
+
+        let resources = Self::get_or_create_resources(
+            &mut self.resources,
+            vulkan_backend,
+            frame_data,
+            shader_cache,
+        );
+        let frame = resources.frames.current_mut(vulkan_backend);
+        frame.reserve(vulkan_backend, self.points.len());
+        vulkan_backend.update_buffer(frame.buffer, &self.points);
+        let descriptor_set = frame.descriptor_set;
+
+        let images = &frame_data.frame_images;
+        vulkan_backend.push_pass_marker("Trails");
+        vulkan_backend
+            .begin_rendering_load_with_depth(&[images.draw_image], Some(&images.gbuffer_depth));
+        for (trail, (first, count)) in trails.iter().zip(ranges) {
+            let pipeline = match trail.blend {
+                ParticleBlendMode::Additive => resources.additive_pipeline,
+                ParticleBlendMode::AlphaBlend => resources.alpha_pipeline,
+            };
+            vulkan_backend.bind_pipeline(pipeline);
+            vulkan_backend.bind_descriptor_sets(&[descriptor_set], pipeline);
+            vulkan_backend.draw(count as u32 * 2, first as u32 * 2);
+        }
+        vulkan_backend.end_rendering();
+        vulkan_backend.pop_pass_marker();
+    }
+
+    fn get_or_create_resources<'a>(
+        resources: &'a mut Option<TrailResources>,
+        vulkan_backend: &mut VulkanBackend,
+        frame_data: &FrameData,
+        shader_cache: &mut ShaderCache,
+    ) -> &'a mut TrailResources {
+        resources.get_or_insert_with(|| {
+            let layout = vulkan_backend.create_descriptor_layout(DescriptorLayoutDesc {
+                bindings: vec![
+                    DescriptorBinding {
+                        binding: 0,
+                        descriptor_type: DescriptorType::StorageBuffer,
+                        count: 1,
+                        stages: ShaderStage::VERTEX,
+                    },
+                    DescriptorBinding {
+                        binding: 1,
+                        descriptor_type: DescriptorType::UniformBuffer,
+                        count: 1,
+                        stages: ShaderStage::VERTEX,
+                    },
+                ],
+            });
+            let vert = shader_cache.load(&ShaderRef::BuiltIn("trail_vert".into()), &[]);
+            let frag = shader_cache.load(&ShaderRef::BuiltIn("trail_frag".into()), &[]);
+            let mut draw_pipeline = |dst_color_blend| {
+                Self::create_draw_pipeline(
+                    vulkan_backend,
+                    vert.clone(),
+                    frag.clone(),
+                    layout,
+                    frame_data,
+                    dst_color_blend,
+                )
+            };
+            let additive_pipeline = draw_pipeline(BlendFactor::One);
+            let alpha_pipeline = draw_pipeline(BlendFactor::OneMinusSrcAlpha);
+
+            let frames = FrameRing::new(vulkan_backend, |vulkan_backend, slot| {
+                let frame = TrailFrame {
+                    buffer: create_point_buffer(vulkan_backend, INITIAL_CAPACITY),
+                    capacity: INITIAL_CAPACITY,
+                    descriptor_set: vulkan_backend.allocate_descriptor_set(layout),
+                    camera_buffer: frame_data.buffers.get(slot).camera_buffer,
+                };
+                frame.write_descriptor_set(vulkan_backend);
+                frame
+            });
+            TrailResources {
+                additive_pipeline,
+                alpha_pipeline,
+                frames,
+            }
+        })
+    }
+
+    /// Triangle strips into the HDR draw image, depth tested but not written.
+    fn create_draw_pipeline(
+        vulkan_backend: &mut VulkanBackend,
+        vertex_shader: Vec<u8>,
+        fragment_shader: Vec<u8>,
+        layout: DescriptorLayoutHandle,
+        frame_data: &FrameData,
+        dst_color_blend: BlendFactor,
+    ) -> PipelineHandle {
+        let images = &frame_data.frame_images;
+        vulkan_backend.create_graphics_pipeline(PipelineDesc {
+            vertex_shader,
+            fragment_shader: Some(fragment_shader),
+            layout: vec![layout],
+            vertex_input: VertexInputDesc::default(),
+            rasterization: RasterizationStateDesc {
+                cull_mode: CullMode::None,
+                depth_bias_enable: false,
+                depth_clamp_enable: false,
+                discard_enable: false,
+                front_face: FrontFace::CounterClockwise,
+                polygon_mode: PolygonMode::Fill,
+            },
+            blend: Some(BlendStateDesc {
+                logic_op_enable: false,
+                attachments: vec![BlendAttachmentDesc {
+                    blend_enable: true,
+                    src_color_blend: BlendFactor::SrcAlpha,
+                    dst_color_blend,
+                    color_blend_op: BlendOp::Add,
+                    src_alpha_blend: BlendFactor::Zero,
+                    dst_alpha_blend: BlendFactor::One,
+                    alpha_blend_op: BlendOp::Add,
+                    color_write_mask: ColorWriteMask::ALL,
+                }],
+            }),
+            depth_stencil: DepthStencilDesc {
+                depth_test_enable: true,
+                depth_write_enable: false,
+                depth_compare_op: CompareOp::LessOrEqual,
+                depth_bounds_test_enable: false,
+                stencil_test_enable: false,
+            },
+            color_attachments: vec![images.draw_image],
+            depth_attachment: Some(images.gbuffer_depth),
+            push_constant_ranges: vec![],
+            topology: PrimitiveTopology::TriangleStrip,
+            specialization: SpecializationConstants::default(),
+        })
+    }
+}
+
+impl Default for TrailRenderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TrailFrame {
+    /// Grows the point buffer to hold at least `points`.
+    fn reserve(&mut self, vulkan_backend: &mut VulkanBackend, points: usize) {
+        if points <= self.capacity {
+            return;
+        }
+        vulkan_backend.release_buffer(self.buffer);
+        self.capacity = points.next_power_of_two();
+        self.buffer = create_point_buffer(vulkan_backend, self.capacity);
+        self.write_descriptor_set(vulkan_backend);
+    }
+
+    fn write_descriptor_set(&self, vulkan_backend: &mut VulkanBackend) {
+        vulkan_backend.update_descriptor_set(
+            self.descriptor_set,
+            &[
+                DescriptorWriteDesc {
+                    binding: 0,
+                    value: DescriptorValue::StorageBuffer(self.buffer),
+                },
+                DescriptorWriteDesc {
+                    binding: 1,
+                    value: DescriptorValue::UniformBuffer(self.camera_buffer),
+                },
+            ],
+        );
+    }
+}
+
+fn create_point_buffer(vulkan_backend: &mut VulkanBackend, capacity: usize) -> BufferHandle {
+    vulkan_backend.create_buffer::<GpuTrailPoint>(
+        BufferDesc {
+            size: size_of::<GpuTrailPoint>() * capacity,
+            usage: BufferUsageFlags::STORAGE,
+            memory_hint: MemoryHint::CPUWritable,
+        },
+        None,
+    )
+}
+
+/// Appends the points of `trail`, newest first, with width and color sampled at their
+/// normalized age.
+fn trail_points(trail: &TrailComponent, out: &mut Vec<GpuTrailPoint>) {
+    let points: Vec<Vec3> = trail.points().map(|point| point.position).collect();
+    let lifetime = trail.lifetime.max(f32::EPSILON);
+    for (i, point) in trail.points().enumerate() {
+        let previous = points[i.saturating_sub(1)];
+        let next = points[(i + 1).min(points.len() - 1)];
+        let t = (point.age / lifetime).clamp(0.0, 1.0);
+        out.push(GpuTrailPoint {
+            position_half_width: point.position.push(trail.width.sample(t).max(0.0) * 0.5),
+            tangent: (previous - next).push(0.0),
+            color: trail.color.sample(t).to_vec4(),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::curve::Curve;
+
+    #[test]
+    fn points_sample_the_curves_by_age() {
+        let mut trail = TrailComponent::default();
+        trail.lifetime = 1.0;
+        trail.min_vertex_distance = 0.5;
+        trail.width = Curve::linear(2.0, 0.0);
+        for x in [0.0, 1.0, 2.0] {
+            trail.update(Vec3::new(x, 0.0, 0.0), 0.25);
+        }
+        let mut points = Vec::new();
+        trail_points(&trail, &mut points);
+
+        assert_eq!(points.len(), 3);
+        let half_widths: Vec<f32> = points.iter().map(|p| p.position_half_width.w).collect();
+        assert_eq!(half_widths, [1.0, 0.75, 0.5]);
+        // Tangents span the neighbors, pointing towards the head.
+        assert_eq!(points[1].tangent.xyz(), Vec3::new(2.0, 0.0, 0.0));
+        assert_eq!(points[2].tangent.xyz(), Vec3::new(1.0, 0.0, 0.0));
+        assert!(points[2].color.w < points[0].color.w);
+    }
+}
@@ -0,0 +1,188 @@
+use crate::frame_data::FrameData;
+use crate::shader_loader::ShaderCache;
+use core::ui::UiLayout;
+use material::ShaderRef;
+use nalgebra_glm::{Vec2, Vec4};
+use rendering_backend::backend_impl::vulkan_backend::VulkanBackend;
+use rendering_backend::buffer::{BufferDesc, BufferHandle, BufferUsageFlags};
+use rendering_backend::descriptor::ShaderStage;
+use rendering_backend::memory::MemoryHint;
+use rendering_backend::pipeline::{
+    BlendAttachmentDesc, BlendFactor, BlendOp, BlendStateDesc, ColorWriteMask, CompareOp, CullMode,
+    DepthStencilDesc, FrontFace, PipelineDesc, PipelineHandle, PolygonMode, PrimitiveTopology,
+    PushConstantDesc, RasterizationStateDesc, SpecializationConstants, VertexAttributeDesc,
+    VertexBindingDesc, VertexFormat, VertexInputDesc, VertexInputRate,
+};
+use std::mem::offset_of;
+
+/// Vertex layout read by `ui.vert`.
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+struct UiVertex {
+    pos: Vec2,
+    color: Vec4,
+}
+
+/// Draws the retained UI's filled rects over the final image, alpha blended, in the
+/// layout's draw order. The pipeline is created on the first frame with UI to draw.
+pub struct UiRenderer {
+    pipeline: Option<PipelineHandle>,
+    /// Indexed by frame slot, so a frame never overwrites vertices the GPU still reads.
+    vertex_buffers: Vec<Option<BufferHandle>>,
+}
+
+impl UiRenderer {
+    pub fn new() -> Self {
+        Self {
+            pipeline: None,
+            vertex_buffers: Vec::new(),
+        }
+    }
+
+    pub fn draw_frame(
+        &mut self,
+        vulkan_backend: &mut VulkanBackend,
+        ui: &UiLayout,
+        frame_data: &FrameData,
+        shader_cache: &mut ShaderCache,
+    ) {
+        let viewport = ui.viewport();
+        if ui.draw_list().is_empty() || viewport.x <= 0.0 || viewport.y <= 0.0 {
+            return;
+        }
+
+        let vertices: Vec<UiVertex> = ui
+            .draw_list()
+            .iter()
+            .flat_map(|quad| {
+                let (min, max) = (quad.rect.min, quad.rect.max);
+                let color = quad.color.to_vec4();
+                [
+                    Vec2::new(min.x, min.y),
+                    Vec2::new(max.x, min.y),
+                    Vec2::new(max.x, max.y),
+                    Vec2::new(min.x, min.y),
+                    Vec2::new(max.x, max.y),
+                    Vec2::new(min.x, max.y),
+                ]
+                .map(|pos| UiVertex { pos, color })
+            })
+            .collect();
+
+        let needed_size = size_of::<UiVertex>() * vertices.len();
+        let slot = vulkan_backend.frame_slot();
+        self.vertex_buffers.resize(vulkan_backend.frames_in_flight(), None);
+        let vertex_buffer = &mut self.vertex_buffers[slot];
+        match *vertex_buffer {
+            Some(vb) if vulkan_backend.buffer_size(vb) >= needed_size => {
+                vulkan_backend.update_buffer(vb, &vertices);
+            }
+            old => {
+                if let Some(old) = old {
+                    vulkan_backend.release_buffer(old);
+                }
+                let vb = vulkan_backend.create_buffer::<UiVertex>(
+                    BufferDesc {
+                        size: needed_size,
+                        usage: BufferUsageFlags::VERTEX_BUFFER,
+                        memory_hint: MemoryHint::CPUWritable,
+                    },
+                    Some(&vertices),
+                );
+                *vertex_buffer = Some(vb);
+            }
+        }
+        let vertex_buffer = vertex_buffer.expect("filled above");
+
+        let pipeline = self.get_or_create_pipeline(vulkan_backend, frame_data, shader_cache);
+
+        vulkan_backend.push_pass_marker("UI");
+        vulkan_backend.begin_rendering_load(&[frame_data.frame_images.draw_image]);
+        vulkan_backend.bind_pipeline(pipeline);
+        vulkan_backend.update_push_constants(pipeline, ShaderStage::VERTEX, &[viewport]);
+        vulkan_backend.bind_vertex_buffer(vertex_buffer);
+        vulkan_backend.draw(vertices.len() as u32, 0);
+        vulkan_backend.end_rendering();
+        vulkan_backend.pop_pass_marker();
+    }
+
+    fn get_or_create_pipeline(
+        &mut self,
+        vulkan_backend: &mut VulkanBackend,
+        frame_data: &FrameData,
+        shader_cache: &mut ShaderCache,
+    ) -> PipelineHandle {
+        if let Some(pipeline) = self.pipeline {
+            return pipeline;
+        }
+
+        let vert_bytes = shader_cache.load(&ShaderRef::BuiltIn("ui_vert".into()), &[]);
+        let frag_bytes = shader_cache.load(&ShaderRef::BuiltIn("ui_frag".into()), &[]);
+
+        let pipeline = vulkan_backend.create_graphics_pipeline(PipelineDesc {
+            vertex_shader: vert_bytes,
+            fragment_shader: Some(frag_bytes),
+            topology: PrimitiveTopology::TriangleList,
+            specialization: SpecializationConstants::default(),
+            color_attachments: vec![frame_data.frame_images.draw_image],
+            depth_attachment: None,
+            depth_stencil: DepthStencilDesc {
+                depth_test_enable: false,
+                depth_write_enable: false,
+                depth_compare_op: CompareOp::Always,
+                depth_bounds_test_enable: false,
+                stencil_test_enable: false,
+            },
+            rasterization: RasterizationStateDesc {
+                polygon_mode: PolygonMode::Fill,
+                cull_mode: CullMode::None,
+                front_face: FrontFace::CounterClockwise,
+                depth_clamp_enable: false,
+                depth_bias_enable: false,
+                discard_enable: false,
+            },
+            blend: Some(BlendStateDesc {
+                logic_op_enable: false,
+                attachments: vec![BlendAttachmentDesc {
+                    blend_enable: true,
+                    src_color_blend: BlendFactor::SrcAlpha,
+                    dst_color_blend: BlendFactor::OneMinusSrcAlpha,
+                    color_blend_op: BlendOp::Add,
+                    src_alpha_blend: BlendFactor::One,
+                    dst_alpha_blend: BlendFactor::OneMinusSrcAlpha,
+                    alpha_blend_op: BlendOp::Add,
+                    color_write_mask: ColorWriteMask::ALL,
+                }],
+            }),
+            layout: vec![],
+            push_constant_ranges: vec![PushConstantDesc {
+                stages: ShaderStage::VERTEX,
+                offset: 0,
+                size: size_of::<Vec2>(),
+            }],
+            vertex_input: VertexInputDesc {
+                bindings: vec![VertexBindingDesc {
+                    binding: 0,
+                    stride: size_of::<UiVertex>() as u32,
+                    input_rate: VertexInputRate::Vertex,
+                }],
+                attributes: vec![
+                    VertexAttributeDesc {
+                        location: 0,
+                        binding: 0,
+                        format: VertexFormat::Float32x2,
+                        offset: offset_of!(UiVertex, pos) as u32,
+                    },
+                    VertexAttributeDesc {
+                        location: 1,
+                        binding: 0,
+                        format: VertexFormat::Float32x4,
+                        offset: offset_of!(UiVertex, color) as u32,
+                    },
+                ],
+            },
+        });
+        self.pipeline = Some(pipeline);
+        pipeline
+    }
+}
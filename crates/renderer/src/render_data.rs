@@ -1,19 +1,55 @@
-use core::types::transform::Transform;
+use crate::frame_data::{
+    InstanceData, InstanceProbe, INSTANCE_NO_RECEIVE_SHADOWS, INSTANCE_PROBE_LIT,
+};
+use config::config::LightShadowSettings;
+use core::particles::ParticleEmitterComponent;
+use core::trails::TrailComponent;
 use core::{
-    CameraComponent, DirectionalLightComponent, MaterialComponent, MeshComponent,
-    TransformComponent,
+    AreaLightComponent, BlobShadowComponent, CameraComponent, DirectionalLightComponent,
+    EditorOnly, GlobalTransformComponent, LightProbeGridComponent, LightmapComponent,
+    MaterialComponent, MaterialOverrideComponent, MeshComponent, PointLightComponent,
+    ReflectionProbeComponent, RenderLayers, SkinnedMeshComponent, TransformComponent,
+    VegetationComponent, VisibilityComponent,
 };
+use ecs::entity::Entity;
 use ecs::world::World;
 use material::material_manager::MaterialHandle;
-use nalgebra_glm::{Mat4, Vec3};
-use common::MeshHandle;
+use nalgebra_glm::{Mat4, Vec2, Vec3, Vec4};
+use common::{ImageHandle, LightProbe, MeshHandle};
+use std::collections::HashMap;
+use std::f32::consts::PI;
 
 /// A request to render a mesh with a specific transform and material.
 #[derive(Clone)]
 pub struct MeshRenderRequest {
+    pub entity: Entity,
     pub mesh_handle: MeshHandle,
     pub material_handle: MaterialHandle,
-    pub transform: Transform,
+    /// Index of the mesh's entry in the instance storage buffer.
+    pub transform_slot: u32,
+    /// World transform, for the screen size texture streaming requests mips by.
+    pub model: Mat4,
+    pub lightmap: Option<ImageHandle>,
+    /// Offset of the entity's palette in [`RenderDataCollector::joint_matrices`], for
+    /// skinned meshes.
+    pub joint_offset: Option<u32>,
+    pub cast_shadows: bool,
+    /// Whether the mesh sways with the vegetation vertex shader.
+    pub vegetation: bool,
+}
+
+/// Instance data that changed this frame and must be written to the GPU.
+#[derive(Clone, Copy)]
+pub struct InstanceUpdate {
+    pub slot: u32,
+    pub data: InstanceData,
+}
+
+/// Light probe lighting of an instance slot that changed this frame.
+#[derive(Clone, Copy)]
+pub struct ProbeUpdate {
+    pub slot: u32,
+    pub probe: InstanceProbe,
 }
 
 #[derive(Clone)]
@@ -27,96 +63,409 @@ pub struct CameraRenderData {
 }
 
 pub struct DirectionalLightData {
+    /// Normalized world-space direction from the surface towards the light.
     pub direction: Vec3,
     pub color: Vec3,
     pub intensity: f32,
-    pub ambient_color: Vec3,
-    pub ambient_intensity: f32,
+    pub shadow: LightShadowSettings,
+}
+
+#[derive(Clone, Copy)]
+pub struct PointLightData {
+    pub position: Vec3,
+    pub color: Vec3,
+    pub intensity: f32,
+    pub range: f32,
+}
+
+/// A rectangular area light with its world-space placement.
+#[derive(Clone, Copy)]
+pub struct AreaLightData {
+    pub center: Vec3,
+    /// From the center to the middle of the right edge.
+    pub half_right: Vec3,
+    /// From the center to the middle of the top edge.
+    pub half_up: Vec3,
+    pub color: Vec3,
+    pub intensity: f32,
+    pub two_sided: bool,
+    pub range: f32,
+}
+
+/// A particle emitter with its world-space placement.
+#[derive(Clone, Debug)]
+pub struct ParticleEmitterData {
+    /// Keys the emitter's particle pool across frames.
+    pub entity: Entity,
+    pub position: Vec3,
+    /// Axis of the emission cone.
+    pub direction: Vec3,
+    pub emitter: ParticleEmitterComponent,
+}
+
+/// A blob shadow with its world-space placement.
+#[derive(Clone, Debug)]
+pub struct BlobShadowData {
+    pub position: Vec3,
+    /// Direction of the ellipse's length, flattened onto the ground.
+    pub forward: Vec3,
+    pub shadow: BlobShadowComponent,
+}
+
+/// A reflection probe with its world-space placement.
+#[derive(Clone, Debug)]
+pub struct ReflectionProbeData {
+    /// Keys the probe's captured cube map across frames.
+    pub entity: Entity,
+    pub position: Vec3,
+    pub probe: ReflectionProbeComponent,
 }
 
 /// Collects render data from the ECS World.
 /// Designed to be extensible for future render types (lights, particles, etc.)
+///
+/// Keep one collector for the lifetime of the renderer: it owns the transform slot
+/// allocation, so `instance_updates` only lists instances that changed since last frame.
 pub struct RenderDataCollector {
     pub mesh_requests: Vec<MeshRenderRequest>,
+    /// Dirty list of instance data to upload this frame.
+    pub instance_updates: Vec<InstanceUpdate>,
+    /// Joint palettes of every skinned mesh, concatenated. Re-uploaded whole each frame.
+    pub joint_matrices: Vec<Mat4>,
     pub camera: Option<CameraRenderData>,
     pub directional_light: Option<DirectionalLightData>,
+    pub point_lights: Vec<PointLightData>,
+    pub area_lights: Vec<AreaLightData>,
+    pub particle_emitters: Vec<ParticleEmitterData>,
+    /// Trails with at least two recorded points.
+    pub trails: Vec<TrailComponent>,
+    pub blob_shadows: Vec<BlobShadowData>,
+    pub reflection_probes: Vec<ReflectionProbeData>,
+    /// Dirty list of light probe lighting to upload this frame.
+    pub probe_updates: Vec<ProbeUpdate>,
+    /// Baked light probe grids and their centers, gathered before the meshes they light.
+    light_probe_grids: Vec<(Vec3, LightProbeGridComponent)>,
+    transform_slots: TransformSlots,
+    /// Stand-ins for meshes spawned without a `GlobalTransformComponent`, kept while the
+    /// entity is collected.
+    fallback_globals: HashMap<Entity, GlobalTransformComponent>,
 }
 
 impl RenderDataCollector {
     pub fn new() -> Self {
         Self {
             mesh_requests: Vec::new(),
+            instance_updates: Vec::new(),
+            joint_matrices: Vec::new(),
             camera: None,
             directional_light: None,
+            point_lights: Vec::new(),
+            area_lights: Vec::new(),
+            particle_emitters: Vec::new(),
+            trails: Vec::new(),
+            blob_shadows: Vec::new(),
+            reflection_probes: Vec::new(),
+            probe_updates: Vec::new(),
+            light_probe_grids: Vec::new(),
+            transform_slots: TransformSlots::default(),
+            fallback_globals: HashMap::new(),
         }
     }
 
     /// Collects all render data from the World by querying for renderable entities.
     pub fn collect_from_world(&mut self, world: &mut World, aspect_ratio: f32) {
         self.mesh_requests.clear();
+        self.instance_updates.clear();
+        self.probe_updates.clear();
+        self.joint_matrices.clear();
         self.camera = None;
         self.directional_light = None;
-        self.collect_meshes(world);
-        self.collect_camera(world, aspect_ratio);
+        self.point_lights.clear();
+        self.area_lights.clear();
+        self.particle_emitters.clear();
+        self.trails.clear();
+        self.blob_shadows.clear();
+        self.reflection_probes.clear();
+        let camera_layers = self.collect_camera(world, aspect_ratio);
+        self.collect_light_probe_grids(world);
+        self.collect_meshes(world, camera_layers);
         self.collect_directional_light(world);
+        self.collect_point_lights(world);
+        self.collect_area_lights(world);
+        self.collect_particle_emitters(world);
+        self.collect_trails(world);
+        self.collect_blob_shadows(world);
+        self.collect_reflection_probes(world);
     }
 
-    fn collect_meshes(&mut self, world: &mut World) {
+    /// Meshes outside `camera_layers` keep their instance slot and data up to date but
+    /// are not requested for drawing.
+    fn collect_meshes(&mut self, world: &mut World, camera_layers: RenderLayers) {
         let mut query = world.query::<(
+            Entity,
             &mut TransformComponent,
+            Option<&mut GlobalTransformComponent>,
             &mut MeshComponent,
             &mut MaterialComponent,
+            Option<&mut MaterialOverrideComponent>,
+            Option<&mut LightmapComponent>,
+            Option<&mut SkinnedMeshComponent>,
+            Option<&mut RenderLayers>,
+            Option<&mut VisibilityComponent>,
+            Option<&mut EditorOnly>,
+            Option<&mut VegetationComponent>,
         )>();
 
-        for (transform, mesh, material) in query.iter() {
+        self.transform_slots.begin_frame();
+        let mut fallbacks = std::mem::take(&mut self.fallback_globals);
+        for (
+            entity,
+            transform,
+            global,
+            mesh,
+            material,
+            material_override,
+            lightmap,
+            skin,
+            layers,
+            visibility,
+            editor_only,
+            vegetation,
+        ) in query.iter()
+        {
+            let mut fallback = None;
+            let global = match global {
+                Some(global) => global,
+                None => fallback.insert(fallbacks.remove(&entity).unwrap_or_default()),
+            };
+            let moved = global.sync(&transform.0);
+            let slot = match global.gpu_slot {
+                Some(slot) if self.transform_slots.claim(slot) => slot,
+                // New entity, or a slot copied along with a cloned component.
+                _ => self.transform_slots.allocate(),
+            };
+            global.gpu_slot = Some(slot);
+            let model = *global.model();
+            if let Some(fallback) = fallback {
+                self.fallback_globals.insert(entity, fallback);
+            }
+
+            let visibility = visibility.map_or(VisibilityComponent::default(), |v| *v);
+            let mut overrides = InstanceOverrides::from_components(
+                material_override.as_deref(),
+                lightmap.as_deref(),
+                vegetation.as_deref(),
+                visibility,
+            );
+            if lightmap.is_none() {
+                self.apply_light_probes(slot, &model, &mut overrides);
+            }
+            let overrides_changed = self.transform_slots.swap_overrides(slot, overrides);
+            if moved || overrides_changed {
+                self.instance_updates.push(InstanceUpdate {
+                    slot,
+                    data: InstanceData {
+                        model,
+                        tint: overrides.tint,
+                        material_params: overrides.material_params,
+                        lightmap_scale_offset: overrides.lightmap_scale_offset,
+                        entity_id: entity.index() as u32 + 1,
+                        flags: overrides.flags,
+                        vegetation: overrides.vegetation,
+                    },
+                });
+            }
+            let layers = layers.map_or(RenderLayers::DEFAULT, |layers| *layers);
+            let hidden = !visibility.visible || (editor_only.is_some() && !EditorOnly::DRAWN);
+            if hidden || !layers.intersects(camera_layers) {
+                continue;
+            }
+            let joint_offset = skin.map(|skin| {
+                let offset = self.joint_matrices.len() as u32;
+                self.joint_matrices.extend_from_slice(&skin.joint_matrices);
+                offset
+            });
             self.mesh_requests.push(MeshRenderRequest {
+                entity,
                 mesh_handle: mesh.mesh_handle,
                 material_handle: material.material_handle,
-                transform: transform.0,
+                transform_slot: slot,
+                model,
+                lightmap: lightmap.map(|lightmap| lightmap.lightmap),
+                joint_offset,
+                cast_shadows: visibility.cast_shadows,
+                vegetation: vegetation.is_some(),
             });
         }
+        self.transform_slots.release_unclaimed();
     }
 
-    fn collect_camera(&mut self, world: &mut World, aspect_ratio: f32) {
-        let mut query = world.query::<(&mut TransformComponent, &mut CameraComponent)>();
-        if let Some((transform, camera)) = query.iter().find(|(_, cam)| cam.active) {
-            let view = transform.0.get_view_matrix();
-            let mut proj = nalgebra_glm::perspective(
-                aspect_ratio,
-                camera.fov.to_radians(),
-                camera.near_clip,
-                camera.far_clip,
-            );
-            proj[(1, 1)] *= -1.0; // Vulkan Y-flip
-            self.camera = Some(CameraRenderData {
-                view,
-                proj,
-                near_clip: camera.near_clip,
-                far_clip: camera.far_clip,
-                fov: camera.fov,
-                aspect_ratio,
-            });
+    fn collect_light_probe_grids(&mut self, world: &mut World) {
+        self.light_probe_grids.clear();
+        let mut query = world.query::<(&mut TransformComponent, &mut LightProbeGridComponent)>();
+        for (transform, grid) in query.iter() {
+            if grid.is_baked() {
+                self.light_probe_grids.push((transform.location, grid.clone()));
+            }
         }
     }
 
+    /// Lights an instance without a lightmap with the probes around its origin, if a
+    /// baked grid covers it, queueing the slot's probe for upload when it changed.
+    fn apply_light_probes(&mut self, slot: u32, model: &Mat4, overrides: &mut InstanceOverrides) {
+        let origin = model.column(3).xyz();
+        let Some((probe, intensity)) = self
+            .light_probe_grids
+            .iter()
+            .find_map(|(center, grid)| Some((grid.sample(center, &origin)?, grid.intensity)))
+        else {
+            return;
+        };
+
+        overrides.flags |= INSTANCE_PROBE_LIT;
+        let probe = instance_probe(&probe, intensity);
+        if self.transform_slots.swap_probe(slot, probe) {
+            self.probe_updates.push(ProbeUpdate { slot, probe });
+        }
+    }
+
+    /// Returns the layers the active camera renders.
+    fn collect_camera(&mut self, world: &mut World, aspect_ratio: f32) -> RenderLayers {
+        let mut query = world.query::<(
+            &mut TransformComponent,
+            &mut CameraComponent,
+            Option<&mut RenderLayers>,
+        )>();
+        let Some((transform, camera, layers)) = query.iter().find(|(_, cam, _)| cam.active) else {
+            return RenderLayers::DEFAULT;
+        };
+        let view = transform.0.get_view_matrix();
+        let mut proj = nalgebra_glm::perspective(
+            aspect_ratio,
+            camera.fov.to_radians(),
+            camera.near_clip,
+            camera.far_clip,
+        );
+        proj[(1, 1)] *= -1.0; // Vulkan Y-flip
+        self.camera = Some(CameraRenderData {
+            view,
+            proj,
+            near_clip: camera.near_clip,
+            far_clip: camera.far_clip,
+            fov: camera.fov,
+            aspect_ratio,
+        });
+        layers.map_or(RenderLayers::DEFAULT, |layers| *layers)
+    }
+
     fn collect_directional_light(&mut self, world: &mut World) {
         let mut query =
             world.query::<(&mut TransformComponent, &mut DirectionalLightComponent)>();
         if let Some((transform, light)) = query.iter().next() {
-            // Compute forward direction from Euler angles (rotation = pitch, yaw, roll)
-            let pitch = transform.rotation.x;
-            let yaw = transform.rotation.y;
-            let direction = Vec3::new(
-                yaw.cos() * pitch.cos(),
-                pitch.sin(),
-                yaw.sin() * pitch.cos(),
-            );
+            // The light shines along the transform's forward axis; the lighting pass
+            // expects the direction pointing back towards the light.
             self.directional_light = Some(DirectionalLightData {
-                direction: nalgebra_glm::normalize(&direction),
-                color: light.color,
+                direction: -transform.forward(),
+                color: light.color.to_vec3(),
+                intensity: light.intensity,
+                shadow: light.shadow.clone(),
+            });
+        }
+    }
+
+    fn collect_point_lights(&mut self, world: &mut World) {
+        let mut query = world.query::<(&mut TransformComponent, &mut PointLightComponent)>();
+        for (transform, light) in query.iter() {
+            self.point_lights.push(PointLightData {
+                position: transform.location,
+                color: light.color.to_vec3(),
+                intensity: light.intensity,
+                range: light.range,
+            });
+        }
+    }
+
+    fn collect_area_lights(&mut self, world: &mut World) {
+        let mut query = world.query::<(&mut TransformComponent, &mut AreaLightComponent)>();
+        for (transform, light) in query.iter() {
+            // The rectangle spans the local X and Y axes, unaffected by scale.
+            let model = transform.get_model_matrix();
+            let right = model.column(0).xyz().normalize();
+            let up = model.column(1).xyz().normalize();
+            self.area_lights.push(AreaLightData {
+                center: transform.location,
+                half_right: right * light.width.abs() * 0.5,
+                half_up: up * light.height.abs() * 0.5,
+                color: light.color.to_vec3(),
                 intensity: light.intensity,
-                ambient_color: light.ambient_color,
-                ambient_intensity: light.ambient_intensity,
+                two_sided: light.two_sided,
+                range: light.range,
+            });
+        }
+    }
+
+    fn collect_particle_emitters(&mut self, world: &mut World) {
+        let mut query = world.query::<(
+            Entity,
+            &mut TransformComponent,
+            &mut ParticleEmitterComponent,
+        )>();
+        for (entity, transform, emitter) in query.iter() {
+            self.particle_emitters.push(ParticleEmitterData {
+                entity,
+                position: transform.location,
+                direction: transform.forward(),
+                emitter: emitter.clone(),
+            });
+        }
+    }
+
+    fn collect_trails(&mut self, world: &mut World) {
+        let mut query = world.query::<&mut TrailComponent>();
+        for trail in query.iter() {
+            if trail.points().len() >= 2 {
+                self.trails.push(trail.clone());
+            }
+        }
+    }
+
+    fn collect_blob_shadows(&mut self, world: &mut World) {
+        let mut query = world.query::<(
+            &mut TransformComponent,
+            &mut BlobShadowComponent,
+            Option<&mut VisibilityComponent>,
+        )>();
+        for (transform, shadow, visibility) in query.iter() {
+            if visibility.is_some_and(|v| !v.visible || !v.cast_shadows) {
+                continue;
+            }
+            let forward = transform.forward();
+            let flat = Vec3::new(forward.x, 0.0, forward.z);
+            let forward = if flat.norm_squared() > 1e-6 {
+                flat.normalize()
+            } else {
+                // Looking straight up or down; any heading will do.
+                Vec3::z()
+            };
+            self.blob_shadows.push(BlobShadowData {
+                position: transform.location,
+                forward,
+                shadow: *shadow,
+            });
+        }
+    }
+
+    fn collect_reflection_probes(&mut self, world: &mut World) {
+        let mut query = world.query::<(
+            Entity,
+            &mut TransformComponent,
+            &mut ReflectionProbeComponent,
+        )>();
+        for (entity, transform, probe) in query.iter() {
+            self.reflection_probes.push(ReflectionProbeData {
+                entity,
+                position: transform.location,
+                probe: probe.clone(),
             });
         }
     }
@@ -127,3 +476,384 @@ impl Default for RenderDataCollector {
         Self::new()
     }
 }
+
+/// The material override, lightmap, vegetation and visibility part of [`InstanceData`],
+/// as last uploaded for a slot.
+#[derive(Clone, Copy, PartialEq)]
+struct InstanceOverrides {
+    tint: Vec4,
+    material_params: Vec4,
+    lightmap_scale_offset: Vec4,
+    flags: u32,
+    vegetation: Vec2,
+}
+
+impl InstanceOverrides {
+    fn from_components(
+        material_override: Option<&MaterialOverrideComponent>,
+        lightmap: Option<&LightmapComponent>,
+        vegetation: Option<&VegetationComponent>,
+        visibility: VisibilityComponent,
+    ) -> Self {
+        let material_override = material_override.cloned().unwrap_or_default();
+        let (lightmap_intensity, lightmap_scale_offset) = lightmap.map_or(
+            (-1.0, Vec4::new(1.0, 1.0, 0.0, 0.0)),
+            |lightmap| (lightmap.intensity, lightmap.scale_offset),
+        );
+        Self {
+            tint: material_override.tint,
+            material_params: Vec4::new(
+                material_override.uv_offset.x,
+                material_override.uv_offset.y,
+                material_override.emissive_strength,
+                lightmap_intensity,
+            ),
+            lightmap_scale_offset,
+            flags: if visibility.receive_shadows {
+                0
+            } else {
+                INSTANCE_NO_RECEIVE_SHADOWS
+            },
+            vegetation: vegetation.map_or(Vec2::zeros(), |vegetation| {
+                Vec2::new(vegetation.branch_sway, vegetation.leaf_flutter)
+            }),
+        }
+    }
+}
+
+/// Packs a probe for the geometry shader, which evaluates it at the surface normal.
+/// Shading multiplies the baked light by the albedo, so it is irradiance over π, scaled
+/// by the grid's intensity. The sky visibility rides in the first coefficient's alpha.
+fn instance_probe(probe: &LightProbe, intensity: f32) -> InstanceProbe {
+    let scale = intensity / PI;
+    let mut sh = probe
+        .bounce
+        .convolved()
+        .map(|coefficient| (coefficient * scale).push(0.0));
+    sh[0].w = probe.sky_visibility;
+    InstanceProbe { sh }
+}
+
+/// Slot allocation for the instance storage buffer. Entities have no despawn hook, so a
+/// slot is freed when no entity claims it during a frame's extraction.
+#[derive(Default)]
+struct TransformSlots {
+    /// Frame each slot was last claimed in; `None` for free slots.
+    claimed: Vec<Option<u64>>,
+    /// Overrides last uploaded for each slot; `None` until the slot is first written.
+    overrides: Vec<Option<InstanceOverrides>>,
+    /// Probe lighting last uploaded for each slot; `None` until the slot is first lit by
+    /// a probe grid.
+    probes: Vec<Option<InstanceProbe>>,
+    free: Vec<u32>,
+    frame: u64,
+}
+
+impl TransformSlots {
+    fn begin_frame(&mut self) {
+        self.frame += 1;
+    }
+
+    /// Marks `slot` as in use this frame. Fails if it is free or already claimed this
+    /// frame by another entity.
+    fn claim(&mut self, slot: u32) -> bool {
+        match self.claimed.get_mut(slot as usize) {
+            Some(Some(frame)) if *frame != self.frame => {
+                *frame = self.frame;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Hands out a free slot. Its overrides and probe are reset so the first extraction
+    /// uploads the whole instance.
+    fn allocate(&mut self) -> u32 {
+        let slot = self.free.pop().unwrap_or_else(|| {
+            self.claimed.push(None);
+            self.overrides.push(None);
+            self.probes.push(None);
+            (self.claimed.len() - 1) as u32
+        });
+        self.claimed[slot as usize] = Some(self.frame);
+        self.overrides[slot as usize] = None;
+        self.probes[slot as usize] = None;
+        slot
+    }
+
+    /// Records `overrides` for `slot`, returning whether they differ from the last upload.
+    fn swap_overrides(&mut self, slot: u32, overrides: InstanceOverrides) -> bool {
+        self.overrides[slot as usize].replace(overrides) != Some(overrides)
+    }
+
+    /// Records `probe` for `slot`, returning whether it differs from the last upload.
+    fn swap_probe(&mut self, slot: u32, probe: InstanceProbe) -> bool {
+        self.probes[slot as usize].replace(probe) != Some(probe)
+    }
+
+    fn release_unclaimed(&mut self) {
+        for (slot, claimed) in self.claimed.iter_mut().enumerate() {
+            if claimed.is_some_and(|frame| frame != self.frame) {
+                *claimed = None;
+                self.free.push(slot as u32);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::types::transform::Transform;
+
+    #[test]
+    fn slots_are_reused_after_release() {
+        let mut slots = TransformSlots::default();
+        slots.begin_frame();
+        let a = slots.allocate();
+        let b = slots.allocate();
+        slots.release_unclaimed();
+
+        // `a` is no longer claimed by anything and is reclaimed.
+        slots.begin_frame();
+        assert!(slots.claim(b));
+        assert!(!slots.claim(b));
+        slots.release_unclaimed();
+        assert!(!slots.claim(a));
+        assert_eq!(slots.allocate(), a);
+    }
+
+    #[test]
+    fn meshes_without_a_global_transform_keep_their_slot() {
+        let mut world = World::new();
+        let mesh = world.create_entity((
+            TransformComponent(Transform::default().with_location(Vec3::new(1.0, 2.0, 3.0))),
+            MeshComponent::new(MeshHandle::new(1)),
+            MaterialComponent::new(MaterialHandle::new(0)),
+        ));
+        world.create_entity((
+            TransformComponent::default(),
+            CameraComponent {
+                near_clip: 0.1,
+                far_clip: 100.0,
+                fov: 60.0,
+                active: true,
+            },
+        ));
+
+        let mut collector = RenderDataCollector::new();
+        collector.collect_from_world(&mut world, 1.0);
+        let request = &collector.mesh_requests[0];
+        assert_eq!(request.entity, mesh);
+        assert_eq!(request.model.column(3).xyz(), Vec3::new(1.0, 2.0, 3.0));
+        let slot = request.transform_slot;
+        assert_eq!(collector.instance_updates.len(), 1);
+
+        // Unchanged, so nothing is re-uploaded and the slot stays put.
+        collector.collect_from_world(&mut world, 1.0);
+        assert_eq!(collector.mesh_requests[0].transform_slot, slot);
+        assert!(collector.instance_updates.is_empty());
+    }
+
+    #[test]
+    fn meshes_outside_the_camera_layers_are_not_requested() {
+        let mut world = World::new();
+        let mut entities = Vec::new();
+        for (id, layers) in [
+            (1, RenderLayers::DEFAULT),
+            (2, RenderLayers::layer(1)),
+            (3, RenderLayers::NONE),
+        ] {
+            entities.push(world.create_entity((
+                TransformComponent::default(),
+                GlobalTransformComponent::default(),
+                MeshComponent::new(MeshHandle::new(id)),
+                MaterialComponent::new(MaterialHandle::new(0)),
+                layers,
+            )));
+        }
+        let camera = CameraComponent {
+            near_clip: 0.1,
+            far_clip: 100.0,
+            fov: 60.0,
+            active: true,
+        };
+        world.create_entity((
+            TransformComponent::default(),
+            camera,
+            RenderLayers::DEFAULT.with(1),
+        ));
+
+        let mut collector = RenderDataCollector::new();
+        collector.collect_from_world(&mut world, 1.0);
+        let mut drawn: Vec<(u64, Entity)> = collector
+            .mesh_requests
+            .iter()
+            .map(|r| (r.mesh_handle.raw(), r.entity))
+            .collect();
+        drawn.sort_unstable_by_key(|(mesh, _)| *mesh);
+        assert_eq!(drawn, [(1, entities[0]), (2, entities[1])]);
+        // Hidden meshes still keep their instance data current.
+        assert_eq!(collector.instance_updates.len(), 3);
+    }
+
+    #[test]
+    fn visibility_flags_reach_requests_and_instances() {
+        let mut world = World::new();
+        let unshadowed = VisibilityComponent {
+            visible: true,
+            cast_shadows: false,
+            receive_shadows: false,
+        };
+        for (id, visibility) in [
+            (1, VisibilityComponent::HIDDEN),
+            (2, unshadowed),
+            (4, VisibilityComponent::default()),
+        ] {
+            world.create_entity((
+                TransformComponent::default(),
+                GlobalTransformComponent::default(),
+                MeshComponent::new(MeshHandle::new(id)),
+                MaterialComponent::new(MaterialHandle::new(0)),
+                visibility,
+            ));
+        }
+        world.create_entity((
+            TransformComponent::default(),
+            GlobalTransformComponent::default(),
+            MeshComponent::new(MeshHandle::new(3)),
+            MaterialComponent::new(MaterialHandle::new(0)),
+            EditorOnly,
+        ));
+
+        let mut collector = RenderDataCollector::new();
+        collector.collect_from_world(&mut world, 1.0);
+        let mut drawn: Vec<(u64, bool)> = collector
+            .mesh_requests
+            .iter()
+            .map(|r| (r.mesh_handle.raw(), r.cast_shadows))
+            .collect();
+        drawn.sort_unstable();
+        let mut expected = vec![(2, false), (4, true)];
+        if EditorOnly::DRAWN {
+            expected.insert(1, (3, true));
+        }
+        assert_eq!(drawn, expected);
+
+        let unshadowed_slot = collector
+            .mesh_requests
+            .iter()
+            .find(|r| r.mesh_handle.raw() == 2)
+            .map(|r| r.transform_slot)
+            .unwrap();
+        let flags = collector
+            .instance_updates
+            .iter()
+            .find(|update| update.slot == unshadowed_slot)
+            .map(|update| update.data.flags);
+        assert_eq!(flags, Some(INSTANCE_NO_RECEIVE_SHADOWS));
+    }
+
+    #[test]
+    fn vegetation_reaches_requests_and_instances() {
+        let mut world = World::new();
+        let vegetation = VegetationComponent {
+            branch_sway: 0.5,
+            leaf_flutter: 0.1,
+        };
+        world.create_entity((
+            TransformComponent::default(),
+            GlobalTransformComponent::default(),
+            MeshComponent::new(MeshHandle::new(1)),
+            MaterialComponent::new(MaterialHandle::new(0)),
+            vegetation,
+        ));
+        world.create_entity((
+            TransformComponent::default(),
+            GlobalTransformComponent::default(),
+            MeshComponent::new(MeshHandle::new(2)),
+            MaterialComponent::new(MaterialHandle::new(0)),
+        ));
+
+        let mut collector = RenderDataCollector::new();
+        collector.collect_from_world(&mut world, 1.0);
+        for request in &collector.mesh_requests {
+            let foliage = request.mesh_handle.raw() == 1;
+            assert_eq!(request.vegetation, foliage);
+            let instance = collector
+                .instance_updates
+                .iter()
+                .find(|update| update.slot == request.transform_slot)
+                .unwrap();
+            let expected = if foliage {
+                Vec2::new(0.5, 0.1)
+            } else {
+                Vec2::zeros()
+            };
+            assert_eq!(instance.data.vegetation, expected);
+        }
+    }
+
+    #[test]
+    fn light_probe_grids_light_meshes_inside_them() {
+        let mut world = World::new();
+        let mut probe = LightProbe {
+            sky_visibility: 0.5,
+            ..Default::default()
+        };
+        // One sample weighted by the whole sphere averages to its radiance.
+        probe
+            .bounce
+            .add_sample(&Vec3::y(), &Vec3::new(2.0, 1.0, 0.5), 4.0 * PI);
+        let grid = LightProbeGridComponent {
+            counts: [2, 1, 2],
+            spacing: Vec3::repeat(4.0),
+            intensity: 1.5,
+            probes: vec![probe; 4],
+        };
+        world.create_entity((TransformComponent::default(), grid));
+        for (id, x) in [(1, 1.0), (2, 50.0)] {
+            let transform = Transform {
+                location: Vec3::new(x, 0.0, 0.0),
+                ..Default::default()
+            };
+            world.create_entity((
+                TransformComponent(transform),
+                GlobalTransformComponent::default(),
+                MeshComponent::new(MeshHandle::new(id)),
+                MaterialComponent::new(MaterialHandle::new(0)),
+            ));
+        }
+
+        let mut collector = RenderDataCollector::new();
+        collector.collect_from_world(&mut world, 1.0);
+        for request in &collector.mesh_requests {
+            let inside = request.mesh_handle.raw() == 1;
+            let slot = request.transform_slot;
+            let instance = collector
+                .instance_updates
+                .iter()
+                .find(|update| update.slot == slot)
+                .unwrap();
+            assert_eq!(instance.data.flags & INSTANCE_PROBE_LIT != 0, inside);
+            assert_eq!(instance.data.material_params.w, -1.0);
+            let probe = collector
+                .probe_updates
+                .iter()
+                .find(|update| update.slot == slot);
+            assert_eq!(probe.is_some(), inside);
+            if let Some(ProbeUpdate { probe, .. }) = probe {
+                // The constant band gives the average light, times the grid's intensity.
+                let average = probe.sh[0].xyz() * 0.282_095;
+                assert!((average - Vec3::new(3.0, 1.5, 0.75)).norm() < 1e-3);
+                assert_eq!(probe.sh[0].w, 0.5);
+                // Light from above, so surfaces facing up receive more of it.
+                assert!(probe.sh[1].x > 0.0);
+            }
+        }
+
+        // Nothing changed, so no probe is uploaded again.
+        collector.collect_from_world(&mut world, 1.0);
+        assert!(collector.probe_updates.is_empty());
+    }
+}
@@ -1,17 +1,96 @@
-use crate::render_data::{CameraRenderData, DirectionalLightData};
-use material::material_manager::MaterialVariant;
+use crate::render_data::{
+    AreaLightData, BlobShadowData, CameraRenderData, DirectionalLightData, ParticleEmitterData,
+    PointLightData, ReflectionProbeData,
+};
+use common::MeshHandle;
+use core::environment::WorldEnvironment;
+use core::trails::TrailComponent;
+use ecs::entity::Entity;
+use material::material_manager::{MaterialHandle, MaterialVariant};
 use rendering_backend::backend_impl::resource_manager::GpuMeshData;
 use rendering_backend::descriptor::{DescriptorLayoutHandle, DescriptorSetHandle};
+use rendering_backend::image::GpuImageHandle;
 
 pub struct RenderScene {
     pub meshes: Vec<MeshRenderData>,
     pub camera_data: Option<CameraRenderData>,
     pub directional_light: Option<DirectionalLightData>,
+    pub point_lights: Vec<PointLightData>,
+    pub area_lights: Vec<AreaLightData>,
+    pub particle_emitters: Vec<ParticleEmitterData>,
+    pub trails: Vec<TrailComponent>,
+    pub blob_shadows: Vec<BlobShadowData>,
+    pub reflection_probes: Vec<ReflectionProbeData>,
+    pub environment: WorldEnvironment,
+    /// The environment's skybox, uploaded. `None` without one or while its asset is not
+    /// loaded.
+    pub skybox: Option<GpuImageHandle>,
 }
 
 pub struct MeshRenderData {
+    pub entity: Entity,
+    pub mesh_handle: MeshHandle,
+    pub material_handle: MaterialHandle,
     pub mesh_data: GpuMeshData,
+    /// Index into the model matrix storage buffer.
+    pub transform_slot: u32,
+    /// First joint matrix of the mesh's palette in the joint storage buffer, for meshes
+    /// drawn with GPU skinning.
+    pub joint_offset: Option<u32>,
     pub material_data: MaterialData,
+    /// Set 2 of the geometry pass: the mesh's lightmap, or the default one.
+    pub lightmap_set: DescriptorSetHandle,
+    /// Whether the mesh is drawn into the shadow cascades.
+    pub cast_shadows: bool,
+    /// Whether the mesh sways with the vegetation vertex shader.
+    pub vegetation: bool,
+}
+
+#[cfg(test)]
+impl MeshRenderData {
+    /// Mesh `mesh` at its own index range of `vertex_buffer`, drawn with `material` and
+    /// the built-in shaders, for tests that never touch the GPU.
+    pub(crate) fn built_in(vertex_buffer: usize, mesh: u64, material: u64) -> Self {
+        use common::{Handle, VertexEncoding};
+        use material::ShaderRef;
+        use nalgebra_glm::Vec4;
+        use rendering_backend::buffer::BufferHandle;
+
+        Self {
+            entity: Entity(0),
+            mesh_handle: Handle::new(mesh),
+            material_handle: Handle::new(material),
+            mesh_data: GpuMeshData {
+                vertex_buffer: BufferHandle(vertex_buffer),
+                index_buffer: BufferHandle(vertex_buffer),
+                index_count: 36,
+                first_index: mesh as u32 * 36,
+                vertex_offset: 0,
+                pool_allocation: None,
+                extra_buffer: None,
+                skin_buffer: None,
+                vertex_encoding: VertexEncoding::Full,
+                bounds: Vec4::new(0.0, 0.0, 0.0, 1.0),
+            },
+            transform_slot: 0,
+            joint_offset: None,
+            material_data: MaterialData {
+                shader_variant: MaterialVariant {
+                    vertex_shader: ShaderRef::BuiltIn("vert".to_string()),
+                    fragment_shader: ShaderRef::BuiltIn("pbr.frag".to_string()),
+                    active_defines: Vec::new(),
+                    push_constant_size: 0,
+                    binding_info: Vec::new(),
+                },
+                descriptor_set_handle: DescriptorSetHandle(0),
+                descriptor_layout_handle: DescriptorLayoutHandle(0),
+                push_constant_data: Vec::new(),
+            },
+            lightmap_set: DescriptorSetHandle(0),
+            cast_shadows: true,
+            vegetation: false,
+        }
+    }
 }
 
 pub struct MaterialData {
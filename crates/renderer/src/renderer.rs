@@ -1,51 +1,192 @@
-use crate::frame_data::{FrameData, ResolutionSettings};
+use crate::frame_data::{FrameData, Resolution, ResolutionSettings, WindUbo};
+use crate::frame_dump::FrameDump;
+use crate::lightmap_gpu_cache::LightmapGpuCache;
 use crate::material_gpu_cache::MaterialGpuCache;
 use crate::passes::aabb_debug_renderer::AabbDebugRenderer;
+use crate::passes::blob_shadow_renderer::BlobShadowRenderer;
+use crate::passes::depth_of_field::DepthOfFieldRenderer;
+use crate::passes::draw2d_renderer::Draw2DRenderer;
 use crate::passes::geometry_renderer::GeometryRenderer;
+use crate::passes::gpu_culling::GpuCulling;
+use crate::passes::light_clusters::LightClusters;
 use crate::passes::lighting_renderer::LightingRenderer;
-use crate::render_data::{CameraRenderData, DirectionalLightData, MeshRenderRequest};
+use crate::passes::output_renderer::OutputRenderer;
+use crate::passes::particle_renderer::ParticleRenderer;
+use crate::passes::sky_renderer::SkyRenderer;
+use crate::passes::trail_renderer::TrailRenderer;
+use crate::passes::ui_renderer::UiRenderer;
+use crate::render_data::{
+    AreaLightData, BlobShadowData, CameraRenderData, DirectionalLightData, InstanceUpdate,
+    MeshRenderRequest, ParticleEmitterData, PointLightData, ReflectionProbeData,
+    RenderDataCollector,
+};
 use crate::render_scene::{MaterialData, MeshRenderData, RenderScene};
 use crate::shader_loader::ShaderCache;
+use crate::texture_streaming::{projected_size, TextureStreamer};
 use assets::AssetStore;
-use common::MeshData;
+use common::half::f16_to_f32;
+use common::{Color, ColorSpace, ImageData, MeshData, OutputMode, OutputSettings};
+use config::config::{ShadowQuality, ShadowSettings};
+use core::asset_gc::AssetId;
+use core::draw2d::Draw2D;
+use core::environment::WorldEnvironment;
+use core::post_process::PostProcessSettings;
+use core::trails::TrailComponent;
+use core::ui::UiLayout;
+use core::wind::Wind;
+use ecs::entity::Entity;
 use material::material_manager::MaterialManager;
+use material::MaterialParameterBindingData;
+use nalgebra_glm::{Mat4, Vec3, Vec4};
 use rendering_backend::backend_impl::resource_manager::ResourceManager;
-use rendering_backend::backend_impl::vulkan_backend::VulkanBackend;
+use rendering_backend::backend_impl::vulkan_backend::{BackendConfig, VulkanBackend};
 use rendering_backend::camera::CameraMvpUbo;
+use rendering_backend::image::GpuImageHandle;
+use rendering_backend::memory::GpuMemoryStats;
 use std::path::PathBuf;
+use std::time::Duration;
+use winit::window::Window;
 
-pub use crate::passes::aabb_debug_renderer::DebugBox;
+pub use crate::passes::aabb_debug_renderer::{DebugBox, DebugLine};
+
+/// Capacity of the transform storage buffer; transform slots must stay below this.
+const MAX_MESHES: usize = 1000;
+/// Capacity of the joint storage buffer, shared by the palettes of all skinned meshes.
+const MAX_JOINTS: usize = 4096;
+/// Upper bound of `RendererConfig::frames_in_flight`. Every CPU-written buffer has a
+/// copy per frame in flight.
+const MAX_FRAMES_IN_FLIGHT: usize = 3;
 
 pub struct RendererConfig {
+    pub vsync: bool,
+    /// Frames the CPU may record ahead of the GPU, clamped to 1..=3.
+    pub frames_in_flight: usize,
+    /// Use a dedicated compute queue when available.
+    pub async_compute: bool,
+    /// Leave GPU crash breadcrumbs around render passes.
+    pub gpu_diagnostics: bool,
+    /// Write entity IDs in the G-buffer pass for [`Renderer::pick_gpu`].
+    pub gpu_picking: bool,
+    /// Simulate particles in compute shaders when the device allows it.
+    pub gpu_particles: bool,
     pub resolution_settings: ResolutionSettings,
+    pub shadow_settings: ShadowSettings,
+    /// VRAM budget of streamed material texture mips in MiB; 0 uploads every mip up
+    /// front.
+    pub texture_budget_mb: u32,
     /// Directory containing cook-time asset shaders from the project cache.
     pub asset_cache_dir: PathBuf,
 }
 
+/// Owns the Vulkan backend and orchestrates each frame: uploads, then the geometry,
+/// lighting, post-processing and debug passes. Callers hand it collected render data and never see
+/// backend handles.
 pub struct Renderer {
     frame_data: FrameData,
     material_gpu_cache: MaterialGpuCache,
+    /// `None` when every material texture mip is uploaded up front.
+    texture_streamer: Option<TextureStreamer>,
+    lightmap_gpu_cache: LightmapGpuCache,
     geometry_renderer: GeometryRenderer,
+    gpu_culling: GpuCulling,
+    light_clusters: LightClusters,
     lighting_renderer: LightingRenderer,
+    blob_shadow_renderer: BlobShadowRenderer,
+    sky_renderer: SkyRenderer,
+    particle_renderer: ParticleRenderer,
+    /// Seconds particles advance in the next frame.
+    particle_delta: f32,
+    trail_renderer: TrailRenderer,
+    depth_of_field: DepthOfFieldRenderer,
     aabb_debug_renderer: AabbDebugRenderer,
+    ui_renderer: UiRenderer,
+    draw2d_renderer: Draw2DRenderer,
+    output_renderer: OutputRenderer,
+    output_settings: OutputSettings,
+    post_process: PostProcessSettings,
     shader_cache: ShaderCache,
+    /// The surface was resized; the swapchain and render targets are rebuilt before the
+    /// next frame.
+    swapchain_dirty: bool,
+    /// Where to write the next rendered frame's draw list.
+    frame_dump: Option<PathBuf>,
+    /// Image copied to the swapchain by the last `draw_frame`; `None` if it rendered
+    /// nothing.
+    presented_image: Option<GpuImageHandle>,
+    // Dropped last, in this order, once `Drop` has waited for the GPU.
+    resource_manager: ResourceManager,
+    vulkan_backend: VulkanBackend,
 }
 
 impl Renderer {
-    pub fn new(vulkan_backend: &mut VulkanBackend, config: RendererConfig) -> Self {
-        let frame_data = FrameData::new(vulkan_backend, config.resolution_settings, 1000);
+    /// Creates the Vulkan backend for `window` and all render passes.
+    pub fn new(window: &Window, config: RendererConfig) -> Self {
+        let mut vulkan_backend = VulkanBackend::new(
+            window,
+            BackendConfig {
+                vsync: config.vsync,
+                async_compute: config.async_compute,
+                gpu_diagnostics: config.gpu_diagnostics,
+                frames_in_flight: config.frames_in_flight.clamp(1, MAX_FRAMES_IN_FLIGHT),
+            },
+        )
+        .expect("Failed to initialize Vulkan backend");
+
+        let frame_data = FrameData::new(
+            &mut vulkan_backend,
+            config.resolution_settings,
+            &config.shadow_settings,
+            MAX_MESHES,
+            MAX_JOINTS,
+            config.gpu_picking,
+        );
         let geometry_renderer = GeometryRenderer::new();
-        let aabb_debug_renderer = AabbDebugRenderer::new(vulkan_backend);
+        let gpu_particles = config.gpu_particles && vulkan_backend.capabilities().graphics_compute;
+        let aabb_debug_renderer = AabbDebugRenderer::new(&mut vulkan_backend);
         let mut shader_cache = ShaderCache::new(config.asset_cache_dir);
-        let lighting_renderer =
-            LightingRenderer::new(vulkan_backend, &frame_data, &mut shader_cache);
+        let gpu_culling = GpuCulling::new(
+            &mut vulkan_backend,
+            &frame_data,
+            &mut shader_cache,
+            MAX_MESHES,
+        );
+        let light_clusters = LightClusters::new(&mut vulkan_backend, &mut shader_cache);
+        let lighting_renderer = LightingRenderer::new(
+            &mut vulkan_backend,
+            &frame_data,
+            &mut shader_cache,
+            config.shadow_settings,
+            light_clusters.descriptor_layout(),
+        );
         Self {
             frame_data,
             material_gpu_cache: MaterialGpuCache::new(),
+            texture_streamer: (config.texture_budget_mb > 0).then(|| {
+                TextureStreamer::new(u64::from(config.texture_budget_mb) * 1024 * 1024)
+            }),
+            lightmap_gpu_cache: LightmapGpuCache::new(),
             geometry_renderer,
+            gpu_culling,
+            light_clusters,
             lighting_renderer,
+            blob_shadow_renderer: BlobShadowRenderer::new(),
+            sky_renderer: SkyRenderer::new(),
+            particle_renderer: ParticleRenderer::new(gpu_particles),
+            particle_delta: 0.0,
+            trail_renderer: TrailRenderer::new(),
+            depth_of_field: DepthOfFieldRenderer::new(),
             aabb_debug_renderer,
+            ui_renderer: UiRenderer::new(),
+            draw2d_renderer: Draw2DRenderer::new(),
+            output_renderer: OutputRenderer::new(),
+            output_settings: OutputSettings::default(),
+            post_process: PostProcessSettings::default(),
             shader_cache,
+            swapchain_dirty: false,
+            frame_dump: None,
+            presented_image: None,
+            resource_manager: ResourceManager::new(),
+            vulkan_backend,
         }
     }
 
@@ -53,115 +194,573 @@ impl Renderer {
         self.aabb_debug_renderer.toggle();
     }
 
+    /// Whether the AABB and line overlay is drawn. Lines are only worth gathering then.
+    pub fn is_aabb_debug_enabled(&self) -> bool {
+        self.aabb_debug_renderer.enabled
+    }
+
+    /// Switches the lighting output to a heatmap of the point lights per cluster.
+    pub fn toggle_cluster_debug(&mut self) {
+        self.light_clusters.debug_view = !self.light_clusters.debug_view;
+    }
+
+    /// Captures the next rendered frame with RenderDoc. Returns false if the process was
+    /// not launched from RenderDoc.
+    pub fn trigger_capture(&mut self) -> bool {
+        self.vulkan_backend.trigger_capture()
+    }
+
+    /// Writes the draw list and pass list of the next rendered frame to `path` as JSON:
+    /// entity, mesh, material and pipeline of every draw, in submission order.
+    pub fn dump_frame(&mut self, path: impl Into<PathBuf>) {
+        self.frame_dump = Some(path.into());
+    }
+
+    /// The last rendered frame as 8-bit sRGB, as shown in SDR output. Blocks until the GPU
+    /// has finished it. `None` if the last `draw_frame` rendered nothing.
+    pub fn read_frame(&mut self) -> Option<ImageData> {
+        let image = self.presented_image?;
+        let (width, height) = self.vulkan_backend.image_size(image);
+        let texels = self.vulkan_backend.read_image(image);
+        let pixels = texels
+            .chunks_exact(8)
+            .flat_map(|texel| {
+                let channel = |i: usize| f16_to_f32(u16::from_ne_bytes([texel[i], texel[i + 1]]));
+                Color::linear_rgba(channel(0), channel(2), channel(4), channel(6)).to_srgba_u8()
+            })
+            .collect();
+        Some(ImageData::rgba8(pixels, width, height, ColorSpace::Srgb))
+    }
+
+    /// Device memory held by the renderer's images and buffers.
+    pub fn gpu_memory(&self) -> GpuMemoryStats {
+        self.vulkan_backend.memory_stats()
+    }
+
+    /// Bytes of material texture mips resident under the streaming budget, `None` when
+    /// texture streaming is off.
+    pub fn streamed_texture_memory(&self) -> Option<u64> {
+        self.texture_streamer
+            .as_ref()
+            .map(TextureStreamer::resident_bytes)
+    }
+
+    /// Starts compiling the geometry pipelines `mesh_requests` are drawn with, uploading
+    /// their meshes and materials. Needs no camera and draws nothing, so a scene can be
+    /// warmed up behind a loading screen. Returns [`Self::compiling_pipelines`].
+    pub fn prewarm_pipelines(
+        &mut self,
+        mesh_requests: &[MeshRenderRequest],
+        material_manager: &mut MaterialManager,
+        asset_store: &AssetStore,
+    ) -> usize {
+        for request in mesh_requests {
+            let mesh_data = self.mesh_render_data(request, material_manager, asset_store, None);
+            self.geometry_renderer.prewarm(
+                &mut self.vulkan_backend,
+                &self.frame_data,
+                &mesh_data,
+                &mut self.shader_cache,
+            );
+        }
+        self.compiling_pipelines()
+    }
+
+    /// Geometry pipelines still compiling in the background. Their meshes are drawn with
+    /// a placeholder pipeline, or not at all, until they are done.
+    pub fn compiling_pipelines(&mut self) -> usize {
+        self.geometry_renderer.poll_compiles(&mut self.vulkan_backend);
+        self.geometry_renderer.compiling_count()
+    }
+
+    /// Entity drawn at window coordinates `position` in the last rendered frame, read
+    /// from the entity ID buffer. Blocks until the GPU has finished that frame. `None`
+    /// over the background, outside the frame, or without `gpu_picking`.
+    pub fn pick_gpu(&mut self, position: [f32; 2]) -> Option<Entity> {
+        let image = self.frame_data.frame_images.entity_ids?;
+        let (width, height) = self.vulkan_backend.image_size(image);
+        let [x, y] = position.map(|coordinate| coordinate.floor());
+        if x < 0.0 || y < 0.0 || x >= width as f32 || y >= height as f32 {
+            return None;
+        }
+        let texel = self.vulkan_backend.read_texel(image, x as u32, y as u32);
+        let id = u32::from_ne_bytes(texel.try_into().expect("entity IDs are 32-bit"));
+        id.checked_sub(1).map(|index| Entity(index as usize))
+    }
+
+    /// Frees the GPU resources of assets the asset GC unloaded. Call between frames,
+    /// before the next `draw_frame`.
+    pub fn release_assets(&mut self, released: &[AssetId]) {
+        let backend = &mut self.vulkan_backend;
+        for &asset in released {
+            match asset {
+                AssetId::Mesh(mesh) => self.resource_manager.release_mesh(backend, mesh),
+                AssetId::Texture(texture) => {
+                    if let Some(streamer) = &mut self.texture_streamer {
+                        streamer.release(texture);
+                    }
+                    self.lightmap_gpu_cache.release(backend, texture);
+                    self.draw2d_renderer.release(backend, texture);
+                    self.resource_manager.release_image(backend, texture);
+                }
+                AssetId::Material(material) => self.material_gpu_cache.release(backend, material),
+            }
+        }
+    }
+
+    /// Marks the swapchain for recreation before the next frame.
+    pub fn on_resized(&mut self) {
+        self.swapchain_dirty = true;
+    }
+
+    /// Resets the surface if the swapchain is stale, whether from a resize or an output
+    /// mode change. Returns false if no frame can be rendered at this surface size.
+    pub fn prepare_surface(&mut self, width: u32, height: u32) -> bool {
+        if self.swapchain_dirty || self.vulkan_backend.swapchain_out_of_date() {
+            if !self.reset_surface(width, height) {
+                return false;
+            }
+            self.swapchain_dirty = false;
+        }
+        true
+    }
+
+    /// Rebuilds everything that depends on the surface: the swapchain, then each image
+    /// sized to the render resolution, from the description it was created with. The
+    /// descriptor sets binding those images are rewritten; handles and settings carry
+    /// over. Returns false, changing nothing, while the surface has a zero extent.
+    fn reset_surface(&mut self, width: u32, height: u32) -> bool {
+        // Waits for the GPU, so no frame still uses the images replaced below.
+        if !self.vulkan_backend.recreate_swapchain(width, height) {
+            return false;
+        }
+        let resolution = Resolution { width, height };
+        if self.frame_data.frame_images.resolution() == resolution {
+            return true;
+        }
+        let backend = &mut self.vulkan_backend;
+        let mut resized = self.frame_data.frame_images.resize(backend, resolution);
+        resized.extend(self.depth_of_field.resize(backend, resolution));
+        resized.extend(self.output_renderer.resize(backend, resolution));
+        backend.refresh_descriptor_sets(&resized);
+        // Its contents did not survive the resize.
+        self.presented_image = None;
+        true
+    }
+
+    /// Blocks until the last rendered frame is on screen, or at least finished on the GPU
+    /// when the device cannot wait on presentation.
+    pub fn wait_for_presented_frame(&mut self) {
+        self.vulkan_backend.wait_for_presented_frame();
+    }
+
+    /// Times every frame on the GPU, for `gpu_frame_time`.
+    pub fn set_gpu_frame_timing(&mut self, enabled: bool) {
+        self.vulkan_backend.set_gpu_frame_timing(enabled);
+    }
+
+    /// GPU time of the last finished frame, while frame timing is on and the device
+    /// supports timestamps.
+    pub fn gpu_frame_time(&self) -> Option<Duration> {
+        self.vulkan_backend.gpu_frame_time()
+    }
+
+    /// Applies shadow quality settings, recreating cascade images if needed.
+    /// Cheap to call every frame; does nothing when the settings are unchanged.
+    pub fn set_shadow_settings(&mut self, shadow_settings: &ShadowSettings) {
+        if self.lighting_renderer.shadow_settings() == shadow_settings {
+            return;
+        }
+        self.lighting_renderer.set_shadow_settings(
+            &mut self.vulkan_backend,
+            &mut self.frame_data,
+            shadow_settings.clone(),
+        );
+    }
+
+    /// Requests a swapchain output mode and the brightness used to encode HDR output.
+    /// A mode change recreates the swapchain before the next frame; modes the display
+    /// does not support fall back to SDR. Cheap to call every frame.
+    pub fn set_output_settings(&mut self, output_settings: &OutputSettings) {
+        self.vulkan_backend.set_output_mode(output_settings.mode);
+        self.output_settings = *output_settings;
+    }
+
+    /// Uploads the wind vegetation sways in, `time` seconds into its animation. Called
+    /// every frame.
+    pub fn set_wind(&mut self, wind: &Wind, time: f32) {
+        let ubo = WindUbo {
+            direction_strength: wind.horizontal_direction().push(wind.strength),
+            gust: Vec4::new(
+                time,
+                wind.gust_strength,
+                wind.gust_frequency,
+                wind.gust_size.max(f32::EPSILON),
+            ),
+        };
+        let backend = &mut self.vulkan_backend;
+        let wind_buffer = self.frame_data.buffers.current(backend).wind_buffer;
+        backend.update_buffer(wind_buffer, &[ubo]);
+    }
+
+    /// Seconds particles advance in the next frame; 0 freezes them. Called every frame.
+    pub fn set_particle_delta(&mut self, delta: f32) {
+        self.particle_delta = delta;
+    }
+
+    /// Applies the post-processing effects drawn from the next frame on. Cheap to call
+    /// every frame.
+    pub fn set_post_process_settings(&mut self, post_process: &PostProcessSettings) {
+        self.post_process = *post_process;
+    }
+
+    /// Output mode the swapchain actually uses.
+    pub fn output_mode(&self) -> OutputMode {
+        self.vulkan_backend.output_mode()
+    }
+
+    /// Uploads this frame's changes from `render_data` and records all passes, with the
+    /// UI and `draw2d` shapes drawn last. `aabbs` and `lines` are drawn while the AABB
+    /// overlay is on. `environment` sets the background, ambient light
+    /// and fog. Panics if the world has no active camera.
     #[allow(clippy::too_many_arguments)]
     pub fn draw_frame(
         &mut self,
-        vulkan_backend: &mut VulkanBackend,
-        mesh_requests: &[MeshRenderRequest],
+        render_data: &mut RenderDataCollector,
         material_manager: &mut MaterialManager,
         asset_store: &AssetStore,
-        resource_manager: &mut ResourceManager,
-        camera: CameraMvpUbo,
-        camera_render_data: Option<CameraRenderData>,
-        directional_light: Option<DirectionalLightData>,
         aabbs: &[DebugBox],
+        lines: &[DebugLine],
+        ui: &UiLayout,
+        draw2d: &Draw2D,
+        environment: &WorldEnvironment,
     ) {
+        let mut camera_render_data = render_data.camera.take();
+        // While a reflection probe is captured, its face cameras stand in for the camera.
+        if let Some(camera) = &camera_render_data {
+            let face_camera = self.lighting_renderer.reflection_probes_mut().begin_frame(
+                &mut self.vulkan_backend,
+                &render_data.reflection_probes,
+                camera,
+            );
+            camera_render_data = face_camera.or(camera_render_data);
+        }
+        let camera = camera_render_data
+            .as_ref()
+            .map(|c| CameraMvpUbo { view: c.view, proj: c.proj })
+            .expect("No active camera in world");
+
+        self.frame_data
+            .update_probes(&mut self.vulkan_backend, &render_data.probe_updates);
         let render_scene = self.create_render_scene(
-            vulkan_backend,
-            mesh_requests,
+            &render_data.mesh_requests,
+            &render_data.instance_updates,
+            &render_data.joint_matrices,
             material_manager,
             asset_store,
-            resource_manager,
             camera,
             camera_render_data,
-            directional_light,
+            render_data.directional_light.take(),
+            std::mem::take(&mut render_data.point_lights),
+            std::mem::take(&mut render_data.area_lights),
+            std::mem::take(&mut render_data.particle_emitters),
+            std::mem::take(&mut render_data.trails),
+            std::mem::take(&mut render_data.blob_shadows),
+            std::mem::take(&mut render_data.reflection_probes),
+            environment,
         );
-        vulkan_backend.begin_frame();
+        let vulkan_backend = &mut self.vulkan_backend;
+        self.presented_image = None;
+        if !vulkan_backend.begin_frame() {
+            return;
+        }
+        // Rewriting the sets binding streamed textures waits for the frames in flight
+        // that bound them.
+        if let Some(streamer) = &mut self.texture_streamer {
+            let mut streamed = Vec::new();
+            for (texture, first_mip) in streamer.end_frame() {
+                let Some(data) = asset_store.get(texture) else {
+                    continue;
+                };
+                streamed.extend(self.resource_manager.reupload_image(
+                    vulkan_backend,
+                    texture,
+                    data,
+                    first_mip,
+                ));
+            }
+            vulkan_backend.refresh_descriptor_sets(&streamed);
+        }
 
+        vulkan_backend.begin_compute();
+        if let Some(camera) = &render_scene.camera_data {
+            self.light_clusters
+                .assign(vulkan_backend, camera, &render_scene.point_lights);
+        }
+        self.gpu_culling.cull(vulkan_backend, &render_scene);
+        vulkan_backend.submit_compute(&[]);
+
+        let mut pipelines_used = self.frame_dump.is_some().then(Vec::new);
         self.geometry_renderer.draw_frame(
             vulkan_backend,
             &render_scene,
             &self.frame_data,
             &mut self.shader_cache,
+            &self.gpu_culling,
+            pipelines_used.as_mut(),
+        );
+        let cluster_set = self.light_clusters.descriptor_set(vulkan_backend);
+        self.lighting_renderer.draw_frame(
+            vulkan_backend,
+            &render_scene,
+            &self.frame_data,
+            cluster_set,
+        );
+        // Blob shadows stand in for the shadow maps on low-end settings.
+        if self.lighting_renderer.shadow_settings().quality == ShadowQuality::Off {
+            self.blob_shadow_renderer.draw_frame(
+                vulkan_backend,
+                &render_scene,
+                &self.frame_data,
+                &mut self.shader_cache,
+            );
+        }
+        self.sky_renderer.draw_frame(
+            vulkan_backend,
+            &render_scene,
+            &self.frame_data,
+            &mut self.shader_cache,
+        );
+        self.lighting_renderer
+            .reflection_probes_mut()
+            .capture_face(vulkan_backend, &self.frame_data);
+        self.particle_renderer.draw_frame(
+            vulkan_backend,
+            &render_scene,
+            &self.frame_data,
+            &mut self.shader_cache,
+            self.particle_delta,
+        );
+        self.trail_renderer.draw_frame(
+            vulkan_backend,
+            &render_scene,
+            &self.frame_data,
+            &mut self.shader_cache,
+        );
+        self.depth_of_field.draw_frame(
+            vulkan_backend,
+            &render_scene,
+            &self.frame_data,
+            &mut self.shader_cache,
+            &self.post_process.depth_of_field,
         );
-        self.lighting_renderer.draw_frame(vulkan_backend, &render_scene, &self.frame_data);
         self.aabb_debug_renderer.draw_frame(
             vulkan_backend,
             aabbs,
+            lines,
             &self.frame_data,
             camera,
             &mut self.shader_cache,
         );
+        self.ui_renderer
+            .draw_frame(vulkan_backend, ui, &self.frame_data, &mut self.shader_cache);
+        self.draw2d_renderer.draw_frame(
+            vulkan_backend,
+            draw2d,
+            ui.viewport(),
+            &self.frame_data,
+            &mut self.shader_cache,
+            &mut self.resource_manager,
+            asset_store,
+        );
+
+        let final_image = self.output_renderer.draw_frame(
+            vulkan_backend,
+            &self.frame_data,
+            &mut self.shader_cache,
+            &self.output_settings,
+        );
+        vulkan_backend.end_frame(final_image);
+        self.presented_image = Some(final_image);
 
-        vulkan_backend.end_frame(self.frame_data.frame_images.draw_image);
+        if let (Some(path), Some(pipelines)) = (self.frame_dump.take(), pipelines_used) {
+            let dump = FrameDump::new(
+                vulkan_backend.frame_number(),
+                vulkan_backend.recorded_passes(),
+                &render_scene,
+                &pipelines,
+                &self.gpu_culling,
+                asset_store,
+                material_manager,
+            );
+            match dump.write(&path) {
+                Ok(()) => println!("Frame dump written to '{}'", path.display()),
+                Err(e) => eprintln!("failed to write frame dump '{}': {}", path.display(), e),
+            }
+        }
     }
 
     #[allow(clippy::too_many_arguments)]
     fn create_render_scene(
         &mut self,
-        vulkan_backend: &mut VulkanBackend,
         mesh_requests: &[MeshRenderRequest],
+        instance_updates: &[InstanceUpdate],
+        joint_matrices: &[Mat4],
         material_manager: &mut MaterialManager,
         asset_store: &AssetStore,
-        resource_manager: &mut ResourceManager,
         camera: CameraMvpUbo,
         camera_render_data: Option<CameraRenderData>,
         directional_light: Option<DirectionalLightData>,
+        point_lights: Vec<PointLightData>,
+        area_lights: Vec<AreaLightData>,
+        particle_emitters: Vec<ParticleEmitterData>,
+        trails: Vec<TrailComponent>,
+        blob_shadows: Vec<BlobShadowData>,
+        reflection_probes: Vec<ReflectionProbeData>,
+        environment: &WorldEnvironment,
     ) -> RenderScene {
-        let mut meshes = vec![];
-        let mut model_matrices = vec![];
+        // Camera position and focal length in pixels, for the screen size of each mesh.
+        let projection = camera_render_data.as_ref().map(|camera| {
+            let camera_position = camera
+                .view
+                .try_inverse()
+                .map_or(Vec3::zeros(), |world| world.column(3).xyz());
+            let height = self.frame_data.frame_images.resolution().height as f32;
+            let focal_length = height / (2.0 * (camera.fov.to_radians() / 2.0).tan());
+            (camera_position, focal_length)
+        });
 
-        let basic_sampler = self.frame_data.basic_sampler;
+        let meshes: Vec<_> = mesh_requests
+            .iter()
+            .map(|request| {
+                self.mesh_render_data(request, material_manager, asset_store, projection)
+            })
+            .collect();
 
-        for request in mesh_requests {
-            let mesh_data = asset_store
-                .get::<MeshData>(request.mesh_handle)
-                .unwrap_or_else(|| {
-                    panic!("No asset found for mesh_handle: {}", request.mesh_handle)
-                });
+        let vulkan_backend = &mut self.vulkan_backend;
+        let resource_manager = &mut self.resource_manager;
+        for update in instance_updates {
+            assert!(
+                (update.slot as usize) < MAX_MESHES,
+                "more than {MAX_MESHES} meshes in the world"
+            );
+        }
+        self.frame_data.update_instances(vulkan_backend, instance_updates);
+        let buffers = self.frame_data.buffers.current(vulkan_backend);
+        if !joint_matrices.is_empty() {
+            assert!(
+                joint_matrices.len() <= MAX_JOINTS,
+                "more than {MAX_JOINTS} skinning joints in the world"
+            );
+            vulkan_backend.update_buffer(buffers.joint_buffer, joint_matrices);
+        }
+        vulkan_backend.update_buffer(buffers.camera_buffer, &[camera]);
 
-            model_matrices.push(request.transform.get_model_matrix());
+        let skybox = environment.skybox.and_then(|skybox| {
+            let image_asset = asset_store.get(skybox)?;
+            Some(resource_manager.get_or_create_image(vulkan_backend, skybox, image_asset))
+        });
 
-            let gpu_mesh_data =
-                resource_manager.get_or_create_mesh(vulkan_backend, request.mesh_handle, mesh_data);
+        RenderScene {
+            meshes,
+            camera_data: camera_render_data,
+            directional_light,
+            point_lights,
+            area_lights,
+            particle_emitters,
+            trails,
+            blob_shadows,
+            reflection_probes,
+            environment: environment.clone(),
+            skybox,
+        }
+    }
+    /// Uploads the mesh and material of `request` if needed and gathers what its draws
+    /// bind. With the camera position and focal length in `projection`, requests the
+    /// material's texture mips for the mesh's screen size.
+    fn mesh_render_data(
+        &mut self,
+        request: &MeshRenderRequest,
+        material_manager: &mut MaterialManager,
+        asset_store: &AssetStore,
+        projection: Option<(Vec3, f32)>,
+    ) -> MeshRenderData {
+        let vulkan_backend = &mut self.vulkan_backend;
+        let resource_manager = &mut self.resource_manager;
+        let basic_sampler = self.frame_data.basic_sampler;
+        let mesh_data = asset_store
+            .get::<MeshData>(request.mesh_handle)
+            .unwrap_or_else(|| panic!("No asset found for mesh_handle: {}", request.mesh_handle));
 
-            let material_bindings =
-                material_manager.get_bindings(request.material_handle).to_vec();
-            let shader_variant = material_manager.get_variant(request.material_handle).clone();
-            let push_constant_data =
-                material_manager.get_push_constants(request.material_handle).to_vec();
+        let gpu_mesh_data =
+            resource_manager.get_or_create_mesh(vulkan_backend, request.mesh_handle, mesh_data);
 
-            let (set_handle, layout_handle) = self.material_gpu_cache.get_or_create(
-                vulkan_backend,
-                request.material_handle,
-                material_bindings,
-                &shader_variant,
-                resource_manager,
-                asset_store,
-                basic_sampler,
-            );
+        let material_bindings = material_manager.get_bindings(request.material_handle).to_vec();
+        let shader_variant = material_manager.get_variant(request.material_handle).clone();
+        let push_constant_data =
+            material_manager.get_push_constants(request.material_handle).to_vec();
 
-            meshes.push(MeshRenderData {
-                mesh_data: gpu_mesh_data,
-                material_data: MaterialData {
-                    shader_variant,
-                    descriptor_set_handle: set_handle,
-                    descriptor_layout_handle: layout_handle,
-                    push_constant_data,
-                },
+        if let Some(streamer) = &mut self.texture_streamer {
+            let screen_size = projection.map_or(0.0, |(position, focal_length)| {
+                projected_size(position, focal_length, &request.model, gpu_mesh_data.bounds)
             });
+            for binding in &material_bindings {
+                let MaterialParameterBindingData::Texture(texture) = binding.data else {
+                    continue;
+                };
+                if !streamer.is_registered(texture) {
+                    let Some(data) = asset_store.get(texture) else {
+                        continue;
+                    };
+                    let first_mip = streamer.register(texture, data);
+                    resource_manager
+                        .get_or_create_image_from_mip(vulkan_backend, texture, data, first_mip);
+                }
+                streamer.request(texture, screen_size);
+            }
         }
 
-        vulkan_backend.update_buffer(
-            self.frame_data.model_storage_buffer,
-            model_matrices.as_slice(),
+        let (set_handle, layout_handle) = self.material_gpu_cache.get_or_create(
+            vulkan_backend,
+            request.material_handle,
+            material_bindings,
+            &shader_variant,
+            resource_manager,
+            asset_store,
+            basic_sampler,
         );
-        vulkan_backend.update_buffer(self.frame_data.camera_buffer, &[camera]);
 
-        RenderScene {
-            meshes,
-            camera_data: camera_render_data,
-            directional_light,
+        let lightmap_set = self.lightmap_gpu_cache.get_or_create(
+            vulkan_backend,
+            request.lightmap,
+            &self.frame_data,
+            resource_manager,
+            asset_store,
+        );
+
+        MeshRenderData {
+            entity: request.entity,
+            mesh_handle: request.mesh_handle,
+            material_handle: request.material_handle,
+            mesh_data: gpu_mesh_data,
+            transform_slot: request.transform_slot,
+            joint_offset: request.joint_offset,
+            material_data: MaterialData {
+                shader_variant,
+                descriptor_set_handle: set_handle,
+                descriptor_layout_handle: layout_handle,
+                push_constant_data,
+            },
+            lightmap_set,
+            cast_shadows: request.cast_shadows,
+            vegetation: request.vegetation,
         }
     }
-}
\ No newline at end of file
+}
+
+impl Drop for Renderer {
+    fn drop(&mut self) {
+        // Nothing may still be executing when the resource registry and device are freed.
+        self.vulkan_backend.wait_idle();
+    }
+}
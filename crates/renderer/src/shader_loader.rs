@@ -1,6 +1,6 @@
 use material::ShaderRef;
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 /// Reads SPV bytecode on demand for user asset shaders, caching each file after first load.
 /// Engine built-in shaders are served from bytes embedded at compile time.
@@ -42,7 +42,7 @@ impl ShaderCache {
     }
 }
 
-fn resolve_asset_path(cache_dir: &PathBuf, base: &str, active_defines: &[String]) -> PathBuf {
+fn resolve_asset_path(cache_dir: &Path, base: &str, active_defines: &[String]) -> PathBuf {
     let mut sorted = active_defines.to_vec();
     sorted.sort_unstable();
     if sorted.is_empty() {
@@ -65,11 +65,48 @@ fn resolve_name(base: &str, active_defines: &[String]) -> String {
 fn builtin_bytes(name: &str) -> &'static [u8] {
     match name {
         "vert"             => include_bytes!("../shaders/vert.spv"),
+        "vert.HAS_VERTEX_EXTRAS"
+            => include_bytes!("../shaders/vert.HAS_VERTEX_EXTRAS.spv"),
+        "vert.HAS_VERTEX_EXTRAS.HAS_WIND"
+            => include_bytes!("../shaders/vert.HAS_VERTEX_EXTRAS.HAS_WIND.spv"),
+        "vert.HAS_SKINNING"
+            => include_bytes!("../shaders/vert.HAS_SKINNING.spv"),
+        "vert.HAS_SKINNING.HAS_VERTEX_EXTRAS"
+            => include_bytes!("../shaders/vert.HAS_SKINNING.HAS_VERTEX_EXTRAS.spv"),
         "shadow"           => include_bytes!("../shaders/shadow.spv"),
+        "shadow.HAS_SKINNING"
+            => include_bytes!("../shaders/shadow.HAS_SKINNING.spv"),
         "quad"             => include_bytes!("../shaders/quad.spv"),
         "lighting"         => include_bytes!("../shaders/lighting.spv"),
+        "light_clusters"   => include_bytes!("../shaders/light_clusters.spv"),
+        "gpu_culling"      => include_bytes!("../shaders/gpu_culling.spv"),
+        "ibl_irradiance"   => include_bytes!("../shaders/ibl_irradiance.spv"),
+        "ibl_specular"     => include_bytes!("../shaders/ibl_specular.spv"),
+        "brdf_lut"         => include_bytes!("../shaders/brdf_lut.spv"),
+        "reflection_probe_capture"
+            => include_bytes!("../shaders/reflection_probe_capture.spv"),
         "line_debug_vert"  => include_bytes!("../shaders/line_debug_vert.spv"),
         "line_debug_frag"  => include_bytes!("../shaders/line_debug_frag.spv"),
+        "ui_vert"          => include_bytes!("../shaders/ui_vert.spv"),
+        "ui_frag"          => include_bytes!("../shaders/ui_frag.spv"),
+        "draw2d_vert"      => include_bytes!("../shaders/draw2d_vert.spv"),
+        "draw2d_frag"      => include_bytes!("../shaders/draw2d_frag.spv"),
+        "output"           => include_bytes!("../shaders/output.spv"),
+        "dof_coc"          => include_bytes!("../shaders/dof_coc.spv"),
+        "dof_blur"         => include_bytes!("../shaders/dof_blur.spv"),
+        "dof_composite"    => include_bytes!("../shaders/dof_composite.spv"),
+        "sky"              => include_bytes!("../shaders/sky.spv"),
+        "blob_shadow_vert" => include_bytes!("../shaders/blob_shadow_vert.spv"),
+        "blob_shadow_frag" => include_bytes!("../shaders/blob_shadow_frag.spv"),
+        "particle_emit"    => include_bytes!("../shaders/particle_emit.spv"),
+        "particle_simulate"
+            => include_bytes!("../shaders/particle_simulate.spv"),
+        "particle_finalize"
+            => include_bytes!("../shaders/particle_finalize.spv"),
+        "particle_vert"    => include_bytes!("../shaders/particle_vert.spv"),
+        "particle_frag"    => include_bytes!("../shaders/particle_frag.spv"),
+        "trail_vert"       => include_bytes!("../shaders/trail_vert.spv"),
+        "trail_frag"       => include_bytes!("../shaders/trail_frag.spv"),
         "pbr.frag"         => include_bytes!("../shaders/pbr.frag.spv"),
         "pbr.frag.HAS_COLOR_TEXTURE"
             => include_bytes!("../shaders/pbr.frag.HAS_COLOR_TEXTURE.spv"),
@@ -88,3 +125,125 @@ fn builtin_bytes(name: &str) -> &'static [u8] {
         other => panic!("unknown built-in shader: '{other}'"),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{builtin_bytes, resolve_name};
+    use crate::frame_data::WindUbo;
+    use crate::passes::blob_shadow_renderer::BlobShadowUbo;
+    use crate::passes::depth_of_field::DofPushConstants;
+    use crate::passes::geometry_renderer::{BUILTIN_VERTEX_FEATURES, ENTITY_ID_LOCATION};
+    use crate::passes::gpu_culling::CullPushConstants;
+    use crate::passes::light_clusters::ClusterUbo;
+    use crate::passes::lighting_renderer::{AreaLightUbo, LightingUbo, ShadowPushConstants};
+    use crate::passes::output_renderer::OutputPushConstants;
+    use crate::passes::particle_renderer::{
+        ParticleCounters, ParticleDrawPushConstants, ParticleSimPushConstants,
+    };
+    use crate::passes::reflection_probes::{CapturePushConstants, ReflectionProbeUbo};
+    use crate::passes::sky_renderer::SkyUbo;
+    use material::ShaderFeatures;
+    use rendering_backend::camera::CameraMvpUbo;
+    use rendering_backend::gpu_layout::{has_output_location, validate_block, BlockBinding};
+
+    #[test]
+    fn builtin_blocks_match_rust_layouts() {
+        let camera = BlockBinding::Descriptor { set: 0, binding: 0 };
+        validate_block::<CameraMvpUbo>(builtin_bytes("vert"), camera).unwrap();
+        validate_block::<CameraMvpUbo>(builtin_bytes("vert.HAS_VERTEX_EXTRAS"), camera).unwrap();
+        let wind = BlockBinding::Descriptor { set: 0, binding: 3 };
+        validate_block::<WindUbo>(builtin_bytes("vert.HAS_VERTEX_EXTRAS.HAS_WIND"), wind).unwrap();
+
+        let lighting = BlockBinding::Descriptor { set: 0, binding: 0 };
+        validate_block::<LightingUbo>(builtin_bytes("lighting"), lighting).unwrap();
+        let lighting_camera = BlockBinding::Descriptor { set: 0, binding: 8 };
+        validate_block::<CameraMvpUbo>(builtin_bytes("lighting"), lighting_camera).unwrap();
+        let area_lights = BlockBinding::Descriptor { set: 0, binding: 24 };
+        validate_block::<AreaLightUbo>(builtin_bytes("lighting"), area_lights).unwrap();
+
+        let clusters = BlockBinding::Descriptor { set: 0, binding: 0 };
+        validate_block::<ClusterUbo>(builtin_bytes("light_clusters"), clusters).unwrap();
+        let lighting_clusters = BlockBinding::Descriptor { set: 1, binding: 0 };
+        validate_block::<ClusterUbo>(builtin_bytes("lighting"), lighting_clusters).unwrap();
+        let reflection_probes = BlockBinding::Descriptor { set: 0, binding: 23 };
+        validate_block::<ReflectionProbeUbo>(builtin_bytes("lighting"), reflection_probes)
+            .unwrap();
+        validate_block::<CapturePushConstants>(
+            builtin_bytes("reflection_probe_capture"),
+            BlockBinding::PushConstant,
+        )
+        .unwrap();
+
+        for shadow in ["shadow", "shadow.HAS_SKINNING"] {
+            validate_block::<ShadowPushConstants>(builtin_bytes(shadow), BlockBinding::PushConstant)
+                .unwrap();
+        }
+        validate_block::<OutputPushConstants>(builtin_bytes("output"), BlockBinding::PushConstant)
+            .unwrap();
+        for dof in ["dof_coc", "dof_blur", "dof_composite"] {
+            validate_block::<DofPushConstants>(builtin_bytes(dof), BlockBinding::PushConstant)
+                .unwrap();
+        }
+        let sky = BlockBinding::Descriptor { set: 0, binding: 0 };
+        validate_block::<SkyUbo>(builtin_bytes("sky"), sky).unwrap();
+        let blob_shadow = BlockBinding::Descriptor { set: 0, binding: 0 };
+        for stage in ["blob_shadow_vert", "blob_shadow_frag"] {
+            validate_block::<BlobShadowUbo>(builtin_bytes(stage), blob_shadow).unwrap();
+        }
+        validate_block::<CullPushConstants>(
+            builtin_bytes("gpu_culling"),
+            BlockBinding::PushConstant,
+        )
+        .unwrap();
+
+        let counters = BlockBinding::Descriptor { set: 0, binding: 3 };
+        for particles in ["particle_emit", "particle_simulate", "particle_finalize"] {
+            let spirv = builtin_bytes(particles);
+            validate_block::<ParticleSimPushConstants>(spirv, BlockBinding::PushConstant).unwrap();
+            validate_block::<ParticleCounters>(spirv, counters).unwrap();
+        }
+        let particle_camera = BlockBinding::Descriptor { set: 0, binding: 4 };
+        validate_block::<CameraMvpUbo>(builtin_bytes("particle_simulate"), particle_camera)
+            .unwrap();
+        validate_block::<CameraMvpUbo>(builtin_bytes("particle_vert"), particle_camera).unwrap();
+        validate_block::<ParticleDrawPushConstants>(
+            builtin_bytes("particle_vert"),
+            BlockBinding::PushConstant,
+        )
+        .unwrap();
+        let trail_camera = BlockBinding::Descriptor { set: 0, binding: 1 };
+        validate_block::<CameraMvpUbo>(builtin_bytes("trail_vert"), trail_camera).unwrap();
+    }
+
+    #[test]
+    fn builtin_gbuffer_shader_writes_entity_ids() {
+        assert!(has_output_location(builtin_bytes("pbr.frag"), ENTITY_ID_LOCATION).unwrap());
+        assert!(!has_output_location(builtin_bytes("lighting"), ENTITY_ID_LOCATION).unwrap());
+    }
+
+    #[test]
+    fn builtin_permutations_cover_their_features() {
+        let pbr_features = ShaderFeatures::COLOR_TEXTURE
+            | ShaderFeatures::NORMAL_MAP
+            | ShaderFeatures::ORM_TEXTURE;
+        for features in subsets(pbr_features) {
+            builtin_bytes(&resolve_name("pbr.frag", &features.defines()));
+        }
+        for features in subsets(BUILTIN_VERTEX_FEATURES) {
+            let wind = features.contains(ShaderFeatures::WIND);
+            if wind
+                && (!features.contains(ShaderFeatures::VERTEX_COLOR)
+                    || features.contains(ShaderFeatures::SKINNED))
+            {
+                continue;
+            }
+            builtin_bytes(&resolve_name("vert", &features.defines()));
+        }
+    }
+
+    fn subsets(features: ShaderFeatures) -> impl Iterator<Item = ShaderFeatures> {
+        (0..=features.bits())
+            .filter_map(ShaderFeatures::from_bits)
+            .filter(move |subset| features.contains(*subset))
+    }
+}
@@ -0,0 +1,104 @@
+use crate::frame_data::shadow_cascade_resolution;
+use crate::render_data::CameraRenderData;
+use config::config::{ShadowSettings, MAX_SHADOW_CASCADES};
+use core::types::frustum::Frustum;
+use nalgebra_glm::{self as glm, Mat4, Vec3, Vec4};
+
+#[derive(Clone, Copy)]
+pub struct Cascade {
+    pub view_proj: Mat4,
+    pub depth: f32,
+}
+
+/// Radius of one cascade, reused while its split planes and projection are unchanged.
+#[derive(Clone, Copy)]
+struct CachedRadius {
+    key: [u32; 4],
+    radius: f32,
+}
+
+/// Fits directional-light shadow cascades to the camera each frame.
+///
+/// Each cascade bounds a slice of the camera frustum with a sphere, so its size does not
+/// depend on where the camera looks. The radius is cached per cascade and only recomputed
+/// when the slice changes, and the light-space origin is snapped to whole texels, so
+/// moving or turning the camera does not make shadow edges shimmer.
+#[derive(Default)]
+pub struct CascadeShadows {
+    radii: [Option<CachedRadius>; MAX_SHADOW_CASCADES as usize],
+}
+
+impl CascadeShadows {
+    pub fn update(
+        &mut self,
+        camera: &CameraRenderData,
+        light_dir: &Vec3,
+        settings: &ShadowSettings,
+    ) -> Vec<Cascade> {
+        let far = camera.far_clip.min(settings.shadow_distance);
+        let splits = cascade_splits(
+            camera.near_clip,
+            far,
+            settings.active_cascades() as usize,
+            settings.split_lambda,
+        );
+
+        let light_dir = glm::normalize(light_dir);
+        // `look_at` degenerates when the light points straight up or down.
+        let up = if light_dir.y.abs() > 0.99 { Vec3::z() } else { Vec3::y() };
+
+        splits
+            .windows(2)
+            .enumerate()
+            .map(|(i, split)| {
+                let (split_near, split_far) = (split[0], split[1]);
+                let (center, radius) = Frustum::from_perspective(
+                    &camera.view,
+                    camera.aspect_ratio,
+                    camera.fov,
+                    split_near,
+                    split_far,
+                )
+                .bounding_sphere();
+
+                let key = [split_near, split_far, camera.fov, camera.aspect_ratio].map(f32::to_bits);
+                let radius = match self.radii[i] {
+                    Some(cached) if cached.key == key => cached.radius,
+                    _ => {
+                        let radius = (radius * 16.0).ceil() / 16.0;
+                        self.radii[i] = Some(CachedRadius { key, radius });
+                        radius
+                    }
+                };
+
+                let light_view = glm::look_at(&(center + light_dir * radius), &center, &up);
+                let light_proj = glm::ortho_rh_zo(-radius, radius, -radius, radius, 0.0, 2.0 * radius);
+                let mut view_proj = light_proj * light_view;
+
+                let snap_res = shadow_cascade_resolution(settings, i as u32) as f32 / 2.0;
+                let origin = view_proj * Vec4::new(0.0, 0.0, 0.0, 1.0);
+                view_proj[(0, 3)] += (origin.x * snap_res).round() / snap_res - origin.x;
+                view_proj[(1, 3)] += (origin.y * snap_res).round() / snap_res - origin.y;
+
+                Cascade {
+                    view_proj,
+                    depth: split_far,
+                }
+            })
+            .collect()
+    }
+}
+
+/// Split distances from `near` to `far` for `count` cascades, blending uniform (`lambda`
+/// 0) and logarithmic (`lambda` 1) distribution. Returns `count + 1` distances.
+pub fn cascade_splits(near: f32, far: f32, count: usize, lambda: f32) -> Vec<f32> {
+    let lambda = lambda.clamp(0.0, 1.0);
+    (0..=count)
+        .map(|i| {
+            let idm = i as f32 / count as f32;
+            let log = near * (far / near).powf(idm);
+            let uniform = near + (far - near) * idm;
+            log * lambda + uniform * (1.0 - lambda)
+        })
+        .collect()
+}
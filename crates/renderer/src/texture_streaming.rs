@@ -0,0 +1,251 @@
+use common::{ImageData, ImageHandle, PixelFormat};
+use nalgebra_glm::{Mat4, Vec3, Vec4};
+use std::collections::BTreeMap;
+
+/// Largest side of the mips a texture gets before it is seen on screen.
+const INITIAL_MAX_SIZE: u32 = 256;
+/// Frames a texture keeps finer mips after it last needed them, so detail doesn't
+/// flicker in and out as objects move.
+const STREAM_OUT_DELAY: u64 = 120;
+/// Textures streamed in per frame. Each is a full re-upload, so this bounds the hitch.
+const MAX_STREAM_INS_PER_FRAME: usize = 4;
+
+struct StreamedTexture {
+    width: u32,
+    height: u32,
+    mip_levels: u32,
+    format: PixelFormat,
+    /// First mip on the GPU; the smaller ones are resident too.
+    resident_mip: u32,
+    initial_mip: u32,
+    /// Finest mip asked for this frame.
+    requested_mip: Option<u32>,
+    /// Last frame a request needed `resident_mip` or finer.
+    last_needed: u64,
+}
+
+impl StreamedTexture {
+    /// Bytes of the mips from `first_mip` down.
+    fn cost(&self, first_mip: u32) -> u64 {
+        (first_mip..self.mip_levels)
+            .map(|level| {
+                let width = (self.width >> level).max(1);
+                let height = (self.height >> level).max(1);
+                self.format.level_len(width, height) as u64
+            })
+            .sum()
+    }
+}
+
+/// Decides which mips of each material texture stay in VRAM. Textures start with mips
+/// no larger than 256 pixels; the renderer then requests finer mips from the screen
+/// size of the meshes using them, and [`end_frame`](Self::end_frame) streams them in
+/// until `budget` bytes are resident, coarsening the largest textures first when over.
+/// Mips are swapped by re-uploading the resident chain; sparse residency is not used.
+pub struct TextureStreamer {
+    textures: BTreeMap<ImageHandle, StreamedTexture>,
+    budget: u64,
+    frame: u64,
+}
+
+impl TextureStreamer {
+    pub fn new(budget: u64) -> Self {
+        Self {
+            textures: BTreeMap::new(),
+            budget,
+            frame: 0,
+        }
+    }
+
+    /// Starts tracking a texture about to be uploaded and returns the first mip to upload.
+    pub fn register(&mut self, handle: ImageHandle, data: &ImageData) -> u32 {
+        let mut initial_mip = 0;
+        while initial_mip + 1 < data.mip_levels
+            && (data.width.max(data.height) >> initial_mip) > INITIAL_MAX_SIZE
+        {
+            initial_mip += 1;
+        }
+        self.textures.insert(
+            handle,
+            StreamedTexture {
+                width: data.width,
+                height: data.height,
+                mip_levels: data.mip_levels,
+                format: data.format,
+                resident_mip: initial_mip,
+                initial_mip,
+                requested_mip: None,
+                last_needed: self.frame,
+            },
+        );
+        initial_mip
+    }
+
+    pub fn is_registered(&self, handle: ImageHandle) -> bool {
+        self.textures.contains_key(&handle)
+    }
+
+    /// Stops tracking a texture that was unloaded.
+    pub fn release(&mut self, handle: ImageHandle) {
+        self.textures.remove(&handle);
+    }
+
+    /// Requests the mip that maps about one texel to a pixel when the whole texture
+    /// covers `screen_size` pixels across.
+    pub fn request(&mut self, handle: ImageHandle, screen_size: f32) {
+        let Some(texture) = self.textures.get_mut(&handle) else {
+            return;
+        };
+        let texels_per_pixel = texture.width.max(texture.height) as f32 / screen_size.max(1.0);
+        let mip = (texels_per_pixel.log2().floor().max(0.0) as u32).min(texture.mip_levels - 1);
+        texture.requested_mip = Some(texture.requested_mip.map_or(mip, |other| other.min(mip)));
+    }
+
+    /// Bytes of the mips currently resident.
+    pub fn resident_bytes(&self) -> u64 {
+        self.textures
+            .values()
+            .map(|texture| texture.cost(texture.resident_mip))
+            .sum()
+    }
+
+    /// Settles this frame's requests into the textures whose resident mip changes, with
+    /// their new first mip. Clears the requests.
+    pub fn end_frame(&mut self) -> Vec<(ImageHandle, u32)> {
+        self.frame += 1;
+        let frame = self.frame;
+
+        let mut targets: BTreeMap<ImageHandle, u32> = self
+            .textures
+            .iter_mut()
+            .map(|(&handle, texture)| {
+                let requested = texture.requested_mip.take();
+                if requested.is_some_and(|mip| mip <= texture.resident_mip) {
+                    texture.last_needed = frame;
+                }
+                let target = match requested {
+                    Some(mip) if mip < texture.resident_mip => mip,
+                    _ if frame - texture.last_needed > STREAM_OUT_DELAY => {
+                        requested.unwrap_or(texture.initial_mip)
+                    }
+                    _ => texture.resident_mip,
+                };
+                (handle, target)
+            })
+            .collect();
+
+        // Over budget, drop a mip from whichever texture that frees the most.
+        let mut total: u64 = targets
+            .iter()
+            .map(|(handle, &mip)| self.textures[handle].cost(mip))
+            .sum();
+        while total > self.budget {
+            let largest = targets
+                .iter()
+                .filter(|&(handle, &mip)| mip + 1 < self.textures[handle].mip_levels)
+                .max_by_key(|&(handle, &mip)| {
+                    let texture = &self.textures[handle];
+                    texture.cost(mip) - texture.cost(mip + 1)
+                })
+                .map(|(&handle, _)| handle);
+            let Some(handle) = largest else {
+                break;
+            };
+            let texture = &self.textures[&handle];
+            let mip = targets.get_mut(&handle).expect("target of every texture");
+            total -= texture.cost(*mip) - texture.cost(*mip + 1);
+            *mip += 1;
+        }
+
+        // Every stream-out, freeing memory first, then the stream-ins gaining the most.
+        let resident = |handle: &ImageHandle| self.textures[handle].resident_mip;
+        let (mut stream_ins, stream_outs): (Vec<_>, Vec<_>) = targets
+            .into_iter()
+            .filter(|(handle, mip)| *mip != resident(handle))
+            .partition(|(handle, mip)| *mip < resident(handle));
+        stream_ins.sort_by_key(|(handle, mip)| *mip as i64 - resident(handle) as i64);
+        stream_ins.truncate(MAX_STREAM_INS_PER_FRAME);
+        let changes: Vec<_> = stream_outs.into_iter().chain(stream_ins).collect();
+
+        for (handle, mip) in &changes {
+            self.textures
+                .get_mut(handle)
+                .expect("changes come from tracked textures")
+                .resident_mip = *mip;
+        }
+        changes
+    }
+}
+
+/// Pixels across the bounding sphere `bounds` (xyz: mesh-space center, w: radius) of a
+/// mesh placed by `model`, for a camera at `camera_position` with `focal_length` in
+/// pixels. Unbounded when the camera is inside the sphere.
+pub fn projected_size(camera_position: Vec3, focal_length: f32, model: &Mat4, bounds: Vec4) -> f32 {
+    let center = (model * Vec4::new(bounds.x, bounds.y, bounds.z, 1.0)).xyz();
+    let scale = (0..3)
+        .map(|axis| model.column(axis).xyz().norm())
+        .fold(0.0, f32::max);
+    let radius = bounds.w * scale;
+    let distance = (center - camera_position).norm();
+    if distance <= radius {
+        return f32::INFINITY;
+    }
+    2.0 * radius * focal_length / distance
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::ColorSpace;
+
+    fn texture(size: u32) -> ImageData {
+        let mut data = ImageData::rgba8(Vec::new(), size, size, ColorSpace::Srgb);
+        data.mip_levels = common::full_mip_count(size, size);
+        data
+    }
+
+    #[test]
+    fn textures_start_small_and_stream_in_when_close() {
+        let mut streamer = TextureStreamer::new(u64::MAX);
+        let handle = ImageHandle::new(1);
+        // 2048 down to 256 is three levels.
+        assert_eq!(streamer.register(handle, &texture(2048)), 3);
+
+        streamer.request(handle, 100.0);
+        assert!(streamer.end_frame().is_empty());
+
+        // 2048 texels over 600 pixels: mip 1 is the first under two texels a pixel.
+        streamer.request(handle, 600.0);
+        assert_eq!(streamer.end_frame(), [(handle, 1)]);
+    }
+
+    #[test]
+    fn unneeded_mips_stream_out_after_a_delay() {
+        let mut streamer = TextureStreamer::new(u64::MAX);
+        let handle = ImageHandle::new(1);
+        streamer.register(handle, &texture(1024));
+        streamer.request(handle, 1024.0);
+        assert_eq!(streamer.end_frame(), [(handle, 0)]);
+
+        for _ in 0..STREAM_OUT_DELAY {
+            assert!(streamer.end_frame().is_empty());
+        }
+        assert_eq!(streamer.end_frame(), [(handle, 2)]);
+    }
+
+    #[test]
+    fn the_budget_coarsens_the_largest_textures_first() {
+        let (large, small) = (ImageHandle::new(1), ImageHandle::new(2));
+        let mut streamer = TextureStreamer::new(0);
+        streamer.register(large, &texture(256));
+        streamer.register(small, &texture(64));
+        let budget = streamer.textures[&large].cost(1) + streamer.textures[&small].cost(0);
+        streamer.budget = budget;
+
+        streamer.request(large, 256.0);
+        streamer.request(small, 64.0);
+        // Both are at their finest mip already, so only the budget moves them.
+        assert_eq!(streamer.end_frame(), [(large, 1)]);
+        assert_eq!(streamer.resident_bytes(), budget);
+    }
+}
@@ -1,11 +0,0 @@
-#[derive(Copy, Clone, Debug)]
-pub struct ImageHandle(u32);
-
-#[derive(Copy, Clone, Debug)]
-pub struct PipelineHandle(u32);
-
-#[derive(Copy, Clone, Debug)]
-pub struct DescriptorHandle(u32);
-
-#[derive(Copy, Clone, Debug)]
-pub struct MeshHandle(u32);
\ No newline at end of file
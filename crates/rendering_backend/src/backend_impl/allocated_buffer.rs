@@ -5,7 +5,6 @@ use crate::buffer::{BufferDesc, BufferUsageFlags};
 use crate::memory::MemoryHint;
 use ash::{vk, Instance};
 use std::ffi::c_void;
-use std::slice;
 
 pub struct AllocatedBuffer {
     pub buffer: vk::Buffer,
@@ -15,6 +14,21 @@ pub struct AllocatedBuffer {
 }
 
 impl AllocatedBuffer {
+    /// Placeholder left in a registry slot whose buffer was released. Destroying it is a
+    /// no-op.
+    pub(crate) fn released() -> Self {
+        Self {
+            buffer: vk::Buffer::null(),
+            buffer_memory: vk::DeviceMemory::null(),
+            buffer_size: 0,
+            mapped_buffer: None,
+        }
+    }
+
+    pub(crate) fn is_released(&self) -> bool {
+        self.buffer == vk::Buffer::null()
+    }
+
     pub fn new<T>(
         device_info: &DeviceInfo,
         instance: &Instance,
@@ -67,6 +81,49 @@ impl AllocatedBuffer {
         }
     }
 
+    pub fn update_buffer_at<T>(&mut self, first: usize, data: &[T]) {
+        assert!(
+            ((first + data.len()) * size_of::<T>()) as vk::DeviceSize <= self.buffer_size,
+            "buffer write out of bounds"
+        );
+        if let Some(mapped) = self.mapped_buffer {
+            unsafe {
+                (mapped as *mut T)
+                    .add(first)
+                    .copy_from_nonoverlapping(data.as_ptr(), data.len());
+            }
+        }
+    }
+
+    /// Copies `data` to element `first` through a staging buffer and waits for the copy.
+    /// For device-local buffers, which are not mapped.
+    pub fn upload_at<T>(
+        &self,
+        device_info: &DeviceInfo,
+        instance: &Instance,
+        first: usize,
+        data: &[T],
+    ) {
+        let size = size_of_val(data);
+        let offset = first * size_of::<T>();
+        assert!(
+            (offset + size) as vk::DeviceSize <= self.buffer_size,
+            "buffer upload out of bounds"
+        );
+        if size == 0 {
+            return;
+        }
+        let staging_desc = BufferDesc {
+            size,
+            usage: BufferUsageFlags::TRANSFER_SRC,
+            memory_hint: MemoryHint::CPUToGPU,
+        };
+        let (staging_buffer, staging_memory) =
+            Self::create_host_visible_buffer(device_info, instance, &staging_desc, Some(data));
+        Self::copy_buffer_region(device_info, staging_buffer, self.buffer, offset, size);
+        Self::destroy_buffer(staging_buffer, staging_memory, &device_info.logical_device);
+    }
+
     pub fn flush_mapped_memory_ranges(
         &mut self,
         device: &ash::Device,
@@ -86,17 +143,7 @@ impl AllocatedBuffer {
         usage: vk::BufferUsageFlags,
         memory_property_flags: vk::MemoryPropertyFlags,
     ) -> (vk::Buffer, vk::DeviceMemory) {
-        let buffer_create_info = vk::BufferCreateInfo::default()
-            .size(size)
-            .usage(usage)
-            .sharing_mode(vk::SharingMode::EXCLUSIVE);
-
-        let buffer = unsafe {
-            device_info
-                .logical_device
-                .create_buffer(&buffer_create_info, None)
-                .expect("failed to create buffer")
-        };
+        let buffer = Self::create_vk_buffer(device_info, size, usage);
 
         let mem_requirements = unsafe {
             device_info
@@ -130,24 +177,38 @@ impl AllocatedBuffer {
         (buffer, buffer_memory)
     }
 
+    /// Storage buffers are shared concurrently with the async compute family, if any.
+    fn create_vk_buffer(
+        device_info: &DeviceInfo,
+        size: vk::DeviceSize,
+        usage: vk::BufferUsageFlags,
+    ) -> vk::Buffer {
+        let sharing_families = device_info.queue_info.storage_sharing_families();
+        let mut buffer_create_info = vk::BufferCreateInfo::default()
+            .size(size)
+            .usage(usage)
+            .sharing_mode(vk::SharingMode::EXCLUSIVE);
+        if usage.contains(vk::BufferUsageFlags::STORAGE_BUFFER) && !sharing_families.is_empty() {
+            buffer_create_info = buffer_create_info
+                .sharing_mode(vk::SharingMode::CONCURRENT)
+                .queue_family_indices(&sharing_families);
+        }
+
+        unsafe {
+            device_info
+                .logical_device
+                .create_buffer(&buffer_create_info, None)
+                .expect("failed to create buffer")
+        }
+    }
+
     fn create_empty_buffer(
         device_info: &DeviceInfo,
         instance: &Instance,
         desc: &BufferDesc,
     ) -> (vk::Buffer, vk::DeviceMemory) {
-        let buffer_info = vk::BufferCreateInfo {
-            size: desc.size as u64,
-            usage: map_usage_flags(desc.usage),
-            sharing_mode: vk::SharingMode::EXCLUSIVE,
-            ..Default::default()
-        };
-
-        let buffer = unsafe {
-            device_info
-                .logical_device
-                .create_buffer(&buffer_info, None)
-                .unwrap()
-        };
+        let buffer =
+            Self::create_vk_buffer(device_info, desc.size as u64, map_usage_flags(desc.usage));
 
         let mem_requirements = unsafe {
             device_info
@@ -181,26 +242,15 @@ impl AllocatedBuffer {
         (buffer, memory)
     }
 
-    fn create_host_visible_buffer<T>(
+    pub(crate) fn create_host_visible_buffer<T>(
         device_info: &DeviceInfo,
         instance: &Instance,
         desc: &BufferDesc,
         initial_data: Option<&[T]>,
     ) -> (vk::Buffer, vk::DeviceMemory) {
         // 1. Create VkBuffer
-        let buffer_info = vk::BufferCreateInfo {
-            size: desc.size as u64,
-            usage: map_usage_flags(desc.usage), // usage flags mapped
-            sharing_mode: vk::SharingMode::EXCLUSIVE,
-            ..Default::default()
-        };
-
-        let buffer = unsafe {
-            device_info
-                .logical_device
-                .create_buffer(&buffer_info, None)
-                .unwrap()
-        };
+        let buffer =
+            Self::create_vk_buffer(device_info, desc.size as u64, map_usage_flags(desc.usage));
 
         // 2. Allocate host-visible memory
         let mem_requirements = unsafe {
@@ -282,11 +332,22 @@ impl AllocatedBuffer {
     }
 
     pub fn copy_buffer(device_info: &DeviceInfo, src: vk::Buffer, dst: vk::Buffer, size: usize) {
+        Self::copy_buffer_region(device_info, src, dst, 0, size);
+    }
+
+    /// Copies the first `size` bytes of `src` to byte `dst_offset` of `dst`.
+    pub fn copy_buffer_region(
+        device_info: &DeviceInfo,
+        src: vk::Buffer,
+        dst: vk::Buffer,
+        dst_offset: usize,
+        size: usize,
+    ) {
         let command_buffer = Self::begin_single_time_command(device_info);
 
         let copy_region = vk::BufferCopy {
             src_offset: 0,
-            dst_offset: 0,
+            dst_offset: dst_offset as u64,
             size: size as u64,
         };
 
@@ -333,21 +394,21 @@ impl AllocatedBuffer {
                 .expect("Failed to end command buffer!");
         };
 
-        let submit_info =
-            vk::SubmitInfo::default().command_buffers(slice::from_ref(&command_buffer));
+        let timelines = &device_info.timelines;
+        let uploaded = timelines
+            .graphics
+            .submit(
+                &device_info.logical_device,
+                device_info.queue_info.graphics_queue,
+                &[command_buffer],
+                &[],
+                &[],
+            )
+            .expect("Unable to submit command buffer");
+        timelines
+            .wait(&device_info.logical_device, &[uploaded])
+            .expect("Failed to wait for buffer upload");
         unsafe {
-            device_info
-                .logical_device
-                .queue_submit(
-                    device_info.queue_info.graphics_queue,
-                    &[submit_info],
-                    vk::Fence::null(),
-                )
-                .expect("Failed to submit queue!");
-            device_info
-                .logical_device
-                .queue_wait_idle(device_info.queue_info.graphics_queue)
-                .expect("Failed to wait on queue!");
             device_info
                 .logical_device
                 .free_command_buffers(device_info.command_pool, &[command_buffer]);
@@ -386,6 +447,9 @@ fn map_usage_flags(usage: BufferUsageFlags) -> vk::BufferUsageFlags {
     if usage.contains(BufferUsageFlags::TRANSFER_DST) {
         flags |= vk::BufferUsageFlags::TRANSFER_DST;
     }
+    if usage.contains(BufferUsageFlags::INDIRECT) {
+        flags |= vk::BufferUsageFlags::INDIRECT_BUFFER;
+    }
     flags
 }
 
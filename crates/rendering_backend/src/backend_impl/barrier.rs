@@ -0,0 +1,207 @@
+use crate::sync::ResourceState;
+use ash::vk;
+
+/// Stages, accesses and layout of one side of a barrier.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) struct StateScope {
+    pub stages: vk::PipelineStageFlags2,
+    pub access: vk::AccessFlags2,
+    pub layout: vk::ImageLayout,
+}
+
+impl ResourceState {
+    /// Scope of the accesses made in this state, i.e. the work a barrier leaving the
+    /// state must wait for.
+    pub(crate) fn src_scope(self) -> StateScope {
+        match self {
+            // Swapchain images come back from presentation with discarded contents. The
+            // acquire semaphore is waited on at COLOR_ATTACHMENT_OUTPUT, so the barrier
+            // starts there to chain with it.
+            ResourceState::Present => StateScope {
+                stages: vk::PipelineStageFlags2::COLOR_ATTACHMENT_OUTPUT,
+                access: vk::AccessFlags2::NONE,
+                layout: vk::ImageLayout::UNDEFINED,
+            },
+            // Reads need no memory availability, only execution ordering.
+            state if state.is_read_only() => StateScope {
+                access: vk::AccessFlags2::NONE,
+                ..state.dst_scope()
+            },
+            state => state.dst_scope(),
+        }
+    }
+
+    /// Scope of the accesses about to be made in this state, i.e. the work a barrier
+    /// entering the state must block.
+    pub(crate) fn dst_scope(self) -> StateScope {
+        let (stages, access, layout) = match self {
+            ResourceState::Undefined => (
+                vk::PipelineStageFlags2::NONE,
+                vk::AccessFlags2::NONE,
+                vk::ImageLayout::UNDEFINED,
+            ),
+            ResourceState::ColorAttachment => (
+                vk::PipelineStageFlags2::COLOR_ATTACHMENT_OUTPUT,
+                vk::AccessFlags2::COLOR_ATTACHMENT_READ | vk::AccessFlags2::COLOR_ATTACHMENT_WRITE,
+                vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+            ),
+            ResourceState::DepthAttachment => (
+                vk::PipelineStageFlags2::EARLY_FRAGMENT_TESTS
+                    | vk::PipelineStageFlags2::LATE_FRAGMENT_TESTS,
+                vk::AccessFlags2::DEPTH_STENCIL_ATTACHMENT_READ
+                    | vk::AccessFlags2::DEPTH_STENCIL_ATTACHMENT_WRITE,
+                vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL,
+            ),
+            ResourceState::FragmentShaderRead => (
+                vk::PipelineStageFlags2::FRAGMENT_SHADER,
+                vk::AccessFlags2::SHADER_SAMPLED_READ,
+                vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+            ),
+            ResourceState::ComputeShaderRead => (
+                vk::PipelineStageFlags2::COMPUTE_SHADER,
+                vk::AccessFlags2::SHADER_SAMPLED_READ,
+                vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+            ),
+            ResourceState::ComputeShaderWrite => (
+                vk::PipelineStageFlags2::COMPUTE_SHADER,
+                vk::AccessFlags2::SHADER_STORAGE_WRITE,
+                vk::ImageLayout::GENERAL,
+            ),
+            ResourceState::TransferSrc => (
+                vk::PipelineStageFlags2::ALL_TRANSFER,
+                vk::AccessFlags2::TRANSFER_READ,
+                vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+            ),
+            ResourceState::TransferDst => (
+                vk::PipelineStageFlags2::ALL_TRANSFER,
+                vk::AccessFlags2::TRANSFER_WRITE,
+                vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+            ),
+            // vkQueuePresentKHR makes the image visible itself; the submission's signal
+            // semaphore orders it after all prior work.
+            ResourceState::Present => (
+                vk::PipelineStageFlags2::NONE,
+                vk::AccessFlags2::NONE,
+                vk::ImageLayout::PRESENT_SRC_KHR,
+            ),
+        };
+        StateScope {
+            stages,
+            access,
+            layout,
+        }
+    }
+}
+
+/// A layout transition and memory dependency for a whole image, all mips and layers.
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct ImageBarrier {
+    image: vk::Image,
+    aspect: vk::ImageAspectFlags,
+    from: ResourceState,
+    to: ResourceState,
+}
+
+impl ImageBarrier {
+    pub(crate) fn new(
+        image: vk::Image,
+        aspect: vk::ImageAspectFlags,
+        from: ResourceState,
+        to: ResourceState,
+    ) -> Self {
+        Self {
+            image,
+            aspect,
+            from,
+            to,
+        }
+    }
+
+    /// False when the image stays in a read-only state, where neither a layout change nor
+    /// a memory dependency is needed.
+    pub(crate) fn is_needed(&self) -> bool {
+        self.from != self.to || !self.from.is_read_only()
+    }
+
+    fn to_vk(self) -> vk::ImageMemoryBarrier2<'static> {
+        let src = self.from.src_scope();
+        let dst = self.to.dst_scope();
+        vk::ImageMemoryBarrier2::default()
+            .src_stage_mask(src.stages)
+            .src_access_mask(src.access)
+            .dst_stage_mask(dst.stages)
+            .dst_access_mask(dst.access)
+            .old_layout(src.layout)
+            .new_layout(dst.layout)
+            .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+            .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+            .image(self.image)
+            .subresource_range(
+                vk::ImageSubresourceRange::default()
+                    .aspect_mask(self.aspect)
+                    .base_mip_level(0)
+                    .level_count(vk::REMAINING_MIP_LEVELS)
+                    .base_array_layer(0)
+                    .layer_count(vk::REMAINING_ARRAY_LAYERS),
+            )
+    }
+}
+
+/// Records `barriers` as one dependency, skipping those that are not needed.
+pub(crate) fn record_image_barriers(
+    device: &ash::Device,
+    command_buffer: vk::CommandBuffer,
+    barriers: &[ImageBarrier],
+) {
+    let barriers = barriers
+        .iter()
+        .filter(|barrier| barrier.is_needed())
+        .map(|barrier| barrier.to_vk())
+        .collect::<Vec<_>>();
+    if barriers.is_empty() {
+        return;
+    }
+    let dependency_info = vk::DependencyInfo::default().image_memory_barriers(&barriers);
+    unsafe { device.cmd_pipeline_barrier2(command_buffer, &dependency_info) };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn derives_precise_scopes() {
+        let image = vk::Image::null();
+        let color = vk::ImageAspectFlags::COLOR;
+
+        let barrier = ImageBarrier::new(
+            image,
+            color,
+            ResourceState::ColorAttachment,
+            ResourceState::FragmentShaderRead,
+        )
+        .to_vk();
+        assert_eq!(
+            barrier.src_stage_mask,
+            vk::PipelineStageFlags2::COLOR_ATTACHMENT_OUTPUT
+        );
+        assert_eq!(
+            barrier.src_access_mask,
+            vk::AccessFlags2::COLOR_ATTACHMENT_READ | vk::AccessFlags2::COLOR_ATTACHMENT_WRITE
+        );
+        assert_eq!(
+            barrier.dst_stage_mask,
+            vk::PipelineStageFlags2::FRAGMENT_SHADER
+        );
+        assert_eq!(
+            barrier.new_layout,
+            vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL
+        );
+        assert_eq!(barrier.src_queue_family_index, vk::QUEUE_FAMILY_IGNORED);
+
+        let read = ResourceState::FragmentShaderRead;
+        assert!(!ImageBarrier::new(image, color, read, read).is_needed());
+        let write = ResourceState::ColorAttachment;
+        assert!(ImageBarrier::new(image, color, write, write).is_needed());
+    }
+}
@@ -0,0 +1,159 @@
+use crate::pipeline::PipelineCompileError;
+use std::any::Any;
+use std::mem;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread::{self, JoinHandle};
+
+enum Slot<T> {
+    Free,
+    Compiling,
+    Done(Result<T, PipelineCompileError>),
+}
+
+/// Jobs run one at a time on a single worker thread, each tracked in a slot that is
+/// reused once its result is taken. A job that panics yields
+/// [`PipelineCompileError::Panicked`] and the worker moves on to the next one.
+pub(crate) struct CompileQueue<J, T> {
+    /// `None` once `finish` has stopped the worker.
+    jobs: Option<Sender<(usize, J)>>,
+    results: Receiver<(usize, Result<T, PipelineCompileError>)>,
+    worker: Option<JoinHandle<()>>,
+    slots: Vec<Slot<T>>,
+    free: Vec<usize>,
+}
+
+impl<J: Send + 'static, T: Send + 'static> CompileQueue<J, T> {
+    pub(crate) fn new(name: &str, compile: impl Fn(J) -> T + Send + 'static) -> Self {
+        let (jobs, job_receiver) = mpsc::channel::<(usize, J)>();
+        let (result_sender, results) = mpsc::channel();
+        let worker = thread::Builder::new()
+            .name(name.to_string())
+            .spawn(move || {
+                for (slot, job) in job_receiver {
+                    let result = panic::catch_unwind(AssertUnwindSafe(|| compile(job)))
+                        .map_err(|payload| PipelineCompileError::Panicked(panic_message(payload)));
+                    if result_sender.send((slot, result)).is_err() {
+                        return;
+                    }
+                }
+            })
+            .expect("failed to spawn compile thread");
+        Self {
+            jobs: Some(jobs),
+            results,
+            worker: Some(worker),
+            slots: Vec::new(),
+            free: Vec::new(),
+        }
+    }
+
+    /// Queues `job` and returns its slot.
+    pub(crate) fn submit(&mut self, job: J) -> usize {
+        let slot = match self.free.pop() {
+            Some(slot) => {
+                self.slots[slot] = Slot::Compiling;
+                slot
+            }
+            None => {
+                self.slots.push(Slot::Compiling);
+                self.slots.len() - 1
+            }
+        };
+        self.jobs
+            .as_ref()
+            .expect("compile queue already finished")
+            .send((slot, job))
+            .expect("compile thread exited");
+        slot
+    }
+
+    /// The result of the job in `slot` once it is done. Frees the slot, so each result
+    /// is returned once and the slot may then be handed out again.
+    pub(crate) fn take(&mut self, slot: usize) -> Option<Result<T, PipelineCompileError>> {
+        self.receive();
+        if !matches!(self.slots[slot], Slot::Done(_)) {
+            return None;
+        }
+        let Slot::Done(result) = mem::replace(&mut self.slots[slot], Slot::Free) else {
+            unreachable!();
+        };
+        self.free.push(slot);
+        Some(result)
+    }
+
+    /// Waits for the queued jobs and stops the worker. Returns every successful result
+    /// not taken yet.
+    pub(crate) fn finish(&mut self) -> Vec<T> {
+        self.jobs = None;
+        if let Some(worker) = self.worker.take() {
+            // Job panics are caught, so the worker itself only ends by returning.
+            let _ = worker.join();
+        }
+        self.receive();
+        self.free.clear();
+        mem::take(&mut self.slots)
+            .into_iter()
+            .filter_map(|slot| match slot {
+                Slot::Done(Ok(result)) => Some(result),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn receive(&mut self) {
+        for (slot, result) in self.results.try_iter() {
+            self.slots[slot] = Slot::Done(result);
+        }
+    }
+}
+
+fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    match payload.downcast::<String>() {
+        Ok(message) => *message,
+        Err(payload) => payload
+            .downcast_ref::<&str>()
+            .map_or("unknown panic".to_string(), |message| message.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, Instant};
+
+    type Queue = CompileQueue<u32, u32>;
+
+    fn wait(queue: &mut Queue, slot: usize) -> Result<u32, PipelineCompileError> {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        loop {
+            if let Some(result) = queue.take(slot) {
+                return result;
+            }
+            assert!(Instant::now() < deadline, "job {slot} never finished");
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    #[test]
+    fn panicking_jobs_fail_without_stopping_the_worker_and_slots_are_reused() {
+        let mut queue = CompileQueue::new("test compile", |job: u32| {
+            assert!(job != 0, "cannot compile job 0");
+            job * 2
+        });
+        let failing = queue.submit(0);
+        let working = queue.submit(4);
+
+        match wait(&mut queue, failing) {
+            Err(PipelineCompileError::Panicked(message)) => {
+                assert_eq!(message, "cannot compile job 0");
+            }
+            Ok(_) => panic!("job 0 should fail"),
+        }
+        assert_eq!(wait(&mut queue, working).unwrap(), 8);
+
+        // Both slots are free again.
+        assert!(queue.submit(5) < 2);
+        assert_eq!(queue.finish(), [10]);
+    }
+}
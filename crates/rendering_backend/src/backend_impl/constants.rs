@@ -1,7 +0,0 @@
-pub const WINDOW_WIDTH: u32 = 800;
-pub const WINDOW_HEIGHT: u32 = 600;
-#[allow(dead_code)]
-pub const MAX_FRAMES_IN_FLIGHT: u32 = 3;
-
-#[allow(dead_code)]
-pub const SHADOW_CASCADE_COUNT: u32 = 3;
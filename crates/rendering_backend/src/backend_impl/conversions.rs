@@ -1,6 +1,8 @@
 use crate::descriptor::ShaderStage;
+use crate::image::ClearValue;
 use crate::pipeline::{
     BlendFactor, BlendOp, ColorWriteMask, CompareOp, CullMode, FrontFace, PolygonMode, VertexFormat,
+    VertexInputRate,
 };
 use crate::sampler::{Filter, SamplerAddressMode};
 use ash::vk;
@@ -17,6 +19,34 @@ impl From<VertexFormat> for vk::Format {
             VertexFormat::Uint32x2 => vk::Format::R32G32_UINT,
             VertexFormat::Uint32x3 => vk::Format::R32G32B32_UINT,
             VertexFormat::Uint32x4 => vk::Format::R32G32B32A32_UINT,
+
+            VertexFormat::Float16x2 => vk::Format::R16G16_SFLOAT,
+            VertexFormat::Float16x4 => vk::Format::R16G16B16A16_SFLOAT,
+            VertexFormat::Snorm10_10_10_2 => vk::Format::A2B10G10R10_SNORM_PACK32,
+        }
+    }
+}
+
+impl From<ClearValue> for vk::ClearValue {
+    fn from(value: ClearValue) -> Self {
+        match value {
+            ClearValue::Color(color) => vk::ClearValue {
+                color: vk::ClearColorValue {
+                    float32: color.into(),
+                },
+            },
+            ClearValue::DepthStencil { depth, stencil } => vk::ClearValue {
+                depth_stencil: vk::ClearDepthStencilValue { depth, stencil },
+            },
+        }
+    }
+}
+
+impl From<VertexInputRate> for vk::VertexInputRate {
+    fn from(rate: VertexInputRate) -> Self {
+        match rate {
+            VertexInputRate::Vertex => vk::VertexInputRate::VERTEX,
+            VertexInputRate::Instance => vk::VertexInputRate::INSTANCE,
         }
     }
 }
@@ -1,8 +1,12 @@
 use std::{collections::HashSet, ffi::CStr};
 
 use ash::vk;
+use common::crash_context;
 
 use super::surface::SurfaceInfo;
+use super::timeline::Timelines;
+use super::vulkan_backend::BackendConfig;
+use crate::capabilities::DeviceCapabilities;
 
 const DEVICE_EXTENSIONS: [&CStr; 4] = [
     vk::KHR_SWAPCHAIN_NAME,
@@ -16,22 +20,55 @@ pub struct DeviceInfo {
     pub logical_device: ash::Device,
     pub queue_info: QueueInfo,
     pub command_pool: vk::CommandPool,
+    /// Pool for the compute queue family. Separate from `command_pool` even when the
+    /// families match, so compute recording never contends with graphics.
+    pub compute_command_pool: vk::CommandPool,
+    pub timelines: Timelines,
     pub swapchain_support_details: SwapChainSupportDetails,
     pub min_ubo_alignment: u64,
+    /// Most sampler objects the device allows to exist at once.
+    pub max_sampler_count: u32,
+    pub diagnostic_extensions: DiagnosticExtensions,
+    pub capabilities: DeviceCapabilities,
+    /// Nanoseconds per timestamp tick, if graphics and compute queues can write
+    /// timestamps and query pools can be reset from the host.
+    pub timestamp_period: Option<f32>,
+}
+
+/// Vendor crash-breadcrumb extensions enabled on the device. Only requested when
+/// `BackendConfig::gpu_diagnostics` is set, since they add overhead to every marker.
+#[derive(Default, Clone, Copy)]
+pub struct DiagnosticExtensions {
+    /// `VK_NV_device_diagnostic_checkpoints`
+    pub checkpoints: bool,
+    /// `VK_AMD_buffer_marker`
+    pub buffer_marker: bool,
 }
 
 impl DeviceInfo {
-    pub fn new(instance: &ash::Instance, surface_info: &SurfaceInfo) -> DeviceInfo {
+    /// With `async_compute`, a compute-only queue family is used for compute submissions
+    /// when the device has one; otherwise compute shares the graphics queue.
+    pub fn new(
+        instance: &ash::Instance,
+        surface_info: &SurfaceInfo,
+        config: &BackendConfig,
+    ) -> DeviceInfo {
         let physical_device = Self::pick_physical_device(instance, surface_info);
         let swapchain_support_details =
             Self::query_swap_chain_support(physical_device, surface_info);
         // We can safely unwrap because
         let queue_indices =
             Self::find_queue_family(instance, physical_device, surface_info).unwrap();
+        let compute_queue_index = config
+            .async_compute
+            .then(|| Self::find_async_compute_family(instance, physical_device))
+            .flatten()
+            .unwrap_or(queue_indices.graphics_queue_index);
 
         let mut unique_queue_families = HashSet::new();
         unique_queue_families.insert(queue_indices.graphics_queue_index);
         unique_queue_families.insert(queue_indices.present_queue_index);
+        unique_queue_families.insert(compute_queue_index);
 
         let queue_priorities = [1.0_f32];
         let mut queue_create_infos = vec![];
@@ -44,9 +81,23 @@ impl DeviceInfo {
             queue_create_infos.push(queue_create_info);
         }
 
+        let supported_features = unsafe { instance.get_physical_device_features(physical_device) };
+        let capabilities = DeviceCapabilities {
+            texture_compression_bc: supported_features.texture_compression_bc == vk::TRUE,
+            texture_compression_astc: supported_features.texture_compression_astc_ldr == vk::TRUE,
+            graphics_compute: Self::supports_graphics_compute(
+                instance,
+                physical_device,
+                queue_indices.graphics_queue_index,
+            ),
+            present_wait: Self::supports_present_wait(instance, physical_device),
+            draw_indirect_count: Self::supports_draw_indirect_count(instance, physical_device),
+        };
         let physical_device_features = vk::PhysicalDeviceFeatures::default()
             .sampler_anisotropy(true)
-            .depth_clamp(true);
+            .depth_clamp(true)
+            .texture_compression_bc(capabilities.texture_compression_bc)
+            .texture_compression_astc_ldr(capabilities.texture_compression_astc);
 
         let mut vulkan_13_features = vk::PhysicalDeviceVulkan13Features::default()
             .dynamic_rendering(true)
@@ -55,15 +106,48 @@ impl DeviceInfo {
         let mut vulkan_12_features = vk::PhysicalDeviceVulkan12Features::default()
             .shader_sampled_image_array_non_uniform_indexing(true)
             .descriptor_binding_partially_bound(true)
-            .runtime_descriptor_array(true);
+            .runtime_descriptor_array(true)
+            .timeline_semaphore(true)
+            .draw_indirect_count(capabilities.draw_indirect_count);
+
+        let properties = unsafe { instance.get_physical_device_properties(physical_device) };
+        let timestamp_period = (properties.limits.timestamp_compute_and_graphics == vk::TRUE
+            && Self::supports_host_query_reset(instance, physical_device))
+        .then_some(properties.limits.timestamp_period);
+        vulkan_12_features = vulkan_12_features.host_query_reset(timestamp_period.is_some());
+
+        let diagnostic_extensions = if config.gpu_diagnostics {
+            Self::find_diagnostic_extensions(instance, physical_device)
+        } else {
+            DiagnosticExtensions::default()
+        };
 
-        let binding = DEVICE_EXTENSIONS.map(|name| name.as_ptr());
-        let create_info = vk::DeviceCreateInfo::default()
+        let mut binding = DEVICE_EXTENSIONS.map(|name| name.as_ptr()).to_vec();
+        if diagnostic_extensions.checkpoints {
+            binding.push(vk::NV_DEVICE_DIAGNOSTIC_CHECKPOINTS_NAME.as_ptr());
+        }
+        if diagnostic_extensions.buffer_marker {
+            binding.push(vk::AMD_BUFFER_MARKER_NAME.as_ptr());
+        }
+        let mut present_id_features =
+            vk::PhysicalDevicePresentIdFeaturesKHR::default().present_id(true);
+        let mut present_wait_features =
+            vk::PhysicalDevicePresentWaitFeaturesKHR::default().present_wait(true);
+        if capabilities.present_wait {
+            binding.push(vk::KHR_PRESENT_ID_NAME.as_ptr());
+            binding.push(vk::KHR_PRESENT_WAIT_NAME.as_ptr());
+        }
+        let mut create_info = vk::DeviceCreateInfo::default()
             .push_next(&mut vulkan_13_features)
             .push_next(&mut vulkan_12_features)
             .queue_create_infos(queue_create_infos.as_slice())
             .enabled_features(&physical_device_features)
             .enabled_extension_names(binding.as_slice());
+        if capabilities.present_wait {
+            create_info = create_info
+                .push_next(&mut present_id_features)
+                .push_next(&mut present_wait_features);
+        }
 
         let logical_device: ash::Device = unsafe {
             instance
@@ -76,12 +160,17 @@ impl DeviceInfo {
         let present_queue =
             unsafe { logical_device.get_device_queue(queue_indices.present_queue_index, 0) };
 
-        let command_pool = Self::create_command_pool(&logical_device, &queue_indices);
+        let compute_queue = unsafe { logical_device.get_device_queue(compute_queue_index, 0) };
 
-        let min_ubo_alignment = unsafe {
-            let xc = instance.get_physical_device_properties(physical_device);
-            xc.limits.min_uniform_buffer_offset_alignment
-        };
+        let command_pool =
+            Self::create_command_pool(&logical_device, queue_indices.graphics_queue_index);
+        let compute_command_pool = Self::create_command_pool(&logical_device, compute_queue_index);
+
+        let timelines = Timelines::new(&logical_device);
+
+        let min_ubo_alignment = properties.limits.min_uniform_buffer_offset_alignment;
+        let max_sampler_count = properties.limits.max_sampler_allocation_count;
+        crash_context::set_device(describe_device(&properties));
 
         Self {
             logical_device,
@@ -89,13 +178,70 @@ impl DeviceInfo {
             queue_info: QueueInfo {
                 graphics_queue,
                 present_queue,
+                compute_queue,
                 graphics_queue_index: queue_indices.graphics_queue_index,
                 present_queue_index: queue_indices.present_queue_index,
+                compute_queue_index,
             },
             swapchain_support_details,
             command_pool,
+            compute_command_pool,
+            timelines,
             min_ubo_alignment,
+            max_sampler_count,
+            diagnostic_extensions,
+            capabilities,
+            timestamp_period,
+        }
+    }
+
+    fn supports_host_query_reset(
+        instance: &ash::Instance,
+        physical_device: vk::PhysicalDevice,
+    ) -> bool {
+        let mut vulkan_12_features = vk::PhysicalDeviceVulkan12Features::default();
+        let mut features =
+            vk::PhysicalDeviceFeatures2::default().push_next(&mut vulkan_12_features);
+        unsafe { instance.get_physical_device_features2(physical_device, &mut features) };
+        vulkan_12_features.host_query_reset == vk::TRUE
+    }
+
+    fn supports_draw_indirect_count(
+        instance: &ash::Instance,
+        physical_device: vk::PhysicalDevice,
+    ) -> bool {
+        let mut vulkan_12_features = vk::PhysicalDeviceVulkan12Features::default();
+        let mut features =
+            vk::PhysicalDeviceFeatures2::default().push_next(&mut vulkan_12_features);
+        unsafe { instance.get_physical_device_features2(physical_device, &mut features) };
+        vulkan_12_features.draw_indirect_count == vk::TRUE
+    }
+
+    fn supports_present_wait(
+        instance: &ash::Instance,
+        physical_device: vk::PhysicalDevice,
+    ) -> bool {
+        let extensions = unsafe {
+            instance
+                .enumerate_device_extension_properties(physical_device)
+                .unwrap_or_default()
+        };
+        let supported = |name: &CStr| {
+            extensions
+                .iter()
+                .any(|ex| ex.extension_name_as_c_str() == Ok(name))
+        };
+        if !supported(vk::KHR_PRESENT_ID_NAME) || !supported(vk::KHR_PRESENT_WAIT_NAME) {
+            return false;
         }
+
+        let mut present_id = vk::PhysicalDevicePresentIdFeaturesKHR::default();
+        let mut present_wait = vk::PhysicalDevicePresentWaitFeaturesKHR::default();
+        let mut features = vk::PhysicalDeviceFeatures2::default()
+            .push_next(&mut present_id)
+            .push_next(&mut present_wait);
+        unsafe { instance.get_physical_device_features2(physical_device, &mut features) };
+        present_id.present_id == vk::TRUE && present_wait.present_wait == vk::TRUE
     }
 
     pub fn update_swapchain_capabilities(&mut self, surface_info: &SurfaceInfo) {
@@ -202,6 +348,58 @@ impl DeviceInfo {
         })
     }
 
+    fn supports_graphics_compute(
+        instance: &ash::Instance,
+        physical_device: vk::PhysicalDevice,
+        graphics_queue_index: u32,
+    ) -> bool {
+        let queue_families =
+            unsafe { instance.get_physical_device_queue_family_properties(physical_device) };
+        queue_families
+            .get(graphics_queue_index as usize)
+            .is_some_and(|family| family.queue_flags.contains(vk::QueueFlags::COMPUTE))
+    }
+
+    /// A queue family that supports compute but not graphics, so its work can overlap
+    /// with the graphics queue.
+    fn find_async_compute_family(
+        instance: &ash::Instance,
+        physical_device: vk::PhysicalDevice,
+    ) -> Option<u32> {
+        let queue_families =
+            unsafe { instance.get_physical_device_queue_family_properties(physical_device) };
+
+        queue_families
+            .iter()
+            .position(|family| {
+                family.queue_count > 0
+                    && family.queue_flags.contains(vk::QueueFlags::COMPUTE)
+                    && !family.queue_flags.contains(vk::QueueFlags::GRAPHICS)
+            })
+            .map(|i| i as u32)
+    }
+
+    fn find_diagnostic_extensions(
+        instance: &ash::Instance,
+        physical_device: vk::PhysicalDevice,
+    ) -> DiagnosticExtensions {
+        let extensions = unsafe {
+            instance
+                .enumerate_device_extension_properties(physical_device)
+                .unwrap_or_default()
+        };
+        let supported = |name: &CStr| {
+            extensions
+                .iter()
+                .any(|ex| ex.extension_name_as_c_str() == Ok(name))
+        };
+
+        DiagnosticExtensions {
+            checkpoints: supported(vk::NV_DEVICE_DIAGNOSTIC_CHECKPOINTS_NAME),
+            buffer_marker: supported(vk::AMD_BUFFER_MARKER_NAME),
+        }
+    }
+
     fn check_device_extension_support(
         instance: &ash::Instance,
         physical_device: vk::PhysicalDevice,
@@ -252,10 +450,10 @@ impl DeviceInfo {
 
     fn create_command_pool(
         logical_device: &ash::Device,
-        queue_family_indeces: &QueueFamiliyIndices,
+        queue_family_index: u32,
     ) -> ash::vk::CommandPool {
         let command_pool_create_info = ash::vk::CommandPoolCreateInfo::default()
-            .queue_family_index(queue_family_indeces.graphics_queue_index)
+            .queue_family_index(queue_family_index)
             .flags(vk::CommandPoolCreateFlags::RESET_COMMAND_BUFFER);
 
         unsafe {
@@ -266,11 +464,49 @@ impl DeviceInfo {
     }
 }
 
+/// One line for crash reports, e.g. `NVIDIA GeForce RTX 3070 (vendor 0x10de, driver
+/// 0x86a64000, Vulkan 1.3.277)`.
+fn describe_device(properties: &vk::PhysicalDeviceProperties) -> String {
+    let name = properties
+        .device_name_as_c_str()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|_| "unknown device".to_string());
+    let api = properties.api_version;
+    format!(
+        "{} (vendor {:#06x}, driver {:#x}, Vulkan {}.{}.{})",
+        name,
+        properties.vendor_id,
+        properties.driver_version,
+        vk::api_version_major(api),
+        vk::api_version_minor(api),
+        vk::api_version_patch(api)
+    )
+}
+
 pub struct QueueInfo {
     pub graphics_queue_index: u32,
     pub present_queue_index: u32,
+    /// Equal to `graphics_queue_index` when async compute is disabled or unavailable.
+    pub compute_queue_index: u32,
     pub graphics_queue: vk::Queue,
     pub present_queue: vk::Queue,
+    pub compute_queue: vk::Queue,
+}
+
+impl QueueInfo {
+    pub fn has_async_compute(&self) -> bool {
+        self.compute_queue_index != self.graphics_queue_index
+    }
+
+    /// Families that storage resources are shared between. Empty when compute runs on
+    /// the graphics family and exclusive ownership is enough.
+    pub fn storage_sharing_families(&self) -> Vec<u32> {
+        if self.has_async_compute() {
+            vec![self.graphics_queue_index, self.compute_queue_index]
+        } else {
+            vec![]
+        }
+    }
 }
 
 #[derive(Default)]
@@ -0,0 +1,388 @@
+use super::allocated_buffer::AllocatedBuffer;
+use super::destroyable::Destroyable;
+use super::device::DeviceInfo;
+use crate::buffer::{BufferDesc, BufferUsageFlags};
+use crate::memory::MemoryHint;
+use ash::{amd, ext, nv, vk};
+use common::{crash_context, trace};
+use std::ffi::{c_void, CString};
+use std::fmt::Write;
+use std::time::{Duration, Instant};
+
+/// Debug labels and crash breadcrumbs around GPU passes.
+///
+/// Labels are always emitted when `VK_EXT_debug_utils` is available so RenderDoc and
+/// Nsight captures are grouped by pass. With GPU diagnostics enabled, each pass also
+/// leaves NV checkpoints or AMD buffer markers, which the device-lost report uses to
+/// show how far the GPU got. While a trace session is active or frame timing is on,
+/// passes are also timed with timestamp queries: recorded on the trace's GPU track, and
+/// summed up into the frame's GPU time. Each frame in flight times into its own query
+/// pool, read back once the GPU has finished it.
+pub struct GpuDiagnostics {
+    debug_utils: Option<ext::debug_utils::Device>,
+    checkpoints: Option<nv::device_diagnostic_checkpoints::Device>,
+    buffer_marker: Option<BufferMarker>,
+    /// One per frame slot; empty when the device cannot write timestamps.
+    timers: Vec<PassTimer>,
+    /// Frame slot being recorded.
+    slot: usize,
+    /// Passes begun in the last recorded frame, in order. Kept until the next frame
+    /// begins, so a loss detected while waiting on the frame can still name them.
+    passes: Vec<String>,
+    open: Vec<u32>,
+    frame: u64,
+    frame_timing: bool,
+    /// GPU time from the first timed pass's start to the last one's end, last frame.
+    gpu_frame_time: Option<Duration>,
+}
+
+/// Two u32 slots: the last pass whose commands started, and the last that finished.
+struct BufferMarker {
+    loader: amd::buffer_marker::Device,
+    buffer: AllocatedBuffer,
+}
+
+const MARKER_STARTED: vk::DeviceSize = 0;
+const MARKER_FINISHED: vk::DeviceSize = 4;
+
+/// Passes timed per frame; later ones are left out of the trace.
+const MAX_TIMED_PASSES: u32 = 128;
+
+/// A begin and end timestamp query per timed pass.
+struct PassTimer {
+    pool: vk::QueryPool,
+    /// Nanoseconds per tick.
+    period: f32,
+    /// Index into `passes` and name of each pass timed this frame; its queries are
+    /// `2 * i` and `2 * i + 1`.
+    timed: Vec<(usize, String)>,
+    closed: usize,
+    /// When the frame was submitted, used to place its passes on the CPU timeline.
+    submitted: Option<Instant>,
+}
+
+impl PassTimer {
+    fn new(device: &ash::Device, period: f32) -> Self {
+        let create_info = vk::QueryPoolCreateInfo::default()
+            .query_type(vk::QueryType::TIMESTAMP)
+            .query_count(2 * MAX_TIMED_PASSES);
+        let pool = unsafe {
+            let pool = device
+                .create_query_pool(&create_info, None)
+                .expect("Failed to create timestamp query pool");
+            device.reset_query_pool(pool, 0, 2 * MAX_TIMED_PASSES);
+            pool
+        };
+        Self {
+            pool,
+            period,
+            timed: Vec::new(),
+            closed: 0,
+            submitted: None,
+        }
+    }
+
+    /// Records the previous frame's timings and returns the time its passes spanned. The
+    /// GPU must be done with that frame.
+    fn collect(&mut self, device: &ash::Device) -> Option<Duration> {
+        let count = 2 * self.timed.len() as u32;
+        if count == 0 {
+            return None;
+        }
+        let mut ticks = vec![0u64; count as usize];
+        let results = unsafe {
+            device.get_query_pool_results(self.pool, 0, &mut ticks, vk::QueryResultFlags::TYPE_64)
+        };
+        let mut frame_time = None;
+        if let (Ok(()), Some(submitted), true) =
+            (results, self.submitted, self.closed == self.timed.len())
+        {
+            let first = ticks.iter().step_by(2).copied().min().unwrap_or(0);
+            let last = ticks.iter().skip(1).step_by(2).copied().max().unwrap_or(0);
+            let to_duration =
+                |ticks: u64| Duration::from_nanos((ticks as f64 * self.period as f64) as u64);
+            frame_time = Some(to_duration(last.saturating_sub(first)));
+            if trace::is_active() {
+                for ((_, name), span) in self.timed.iter().zip(ticks.chunks_exact(2)) {
+                    trace::record_gpu(
+                        name,
+                        submitted + to_duration(span[0].saturating_sub(first)),
+                        to_duration(span[1].saturating_sub(span[0])),
+                    );
+                }
+            }
+        }
+
+        unsafe { device.reset_query_pool(self.pool, 0, count) };
+        self.timed.clear();
+        self.closed = 0;
+        self.submitted = None;
+        frame_time
+    }
+
+    /// Writes the begin or end timestamp of the `index`th timed pass.
+    fn write(
+        &self,
+        device: &ash::Device,
+        command_buffer: vk::CommandBuffer,
+        index: usize,
+        end: bool,
+    ) {
+        let stage = if end {
+            vk::PipelineStageFlags::BOTTOM_OF_PIPE
+        } else {
+            vk::PipelineStageFlags::TOP_OF_PIPE
+        };
+        let query = 2 * index as u32 + end as u32;
+        unsafe { device.cmd_write_timestamp(command_buffer, stage, self.pool, query) };
+    }
+}
+
+impl GpuDiagnostics {
+    pub fn new(
+        instance: &ash::Instance,
+        device_info: &DeviceInfo,
+        debug_utils: bool,
+        frames_in_flight: usize,
+    ) -> Self {
+        let device = &device_info.logical_device;
+        let extensions = &device_info.diagnostic_extensions;
+
+        let buffer_marker = extensions.buffer_marker.then(|| BufferMarker {
+            loader: amd::buffer_marker::Device::new(instance, device),
+            buffer: AllocatedBuffer::new::<u32>(
+                device_info,
+                instance,
+                BufferDesc {
+                    size: 2 * size_of::<u32>(),
+                    usage: BufferUsageFlags::TRANSFER_DST,
+                    memory_hint: MemoryHint::CPUWritable,
+                },
+                None,
+            ),
+        });
+
+        Self {
+            debug_utils: debug_utils.then(|| ext::debug_utils::Device::new(instance, device)),
+            checkpoints: extensions
+                .checkpoints
+                .then(|| nv::device_diagnostic_checkpoints::Device::new(instance, device)),
+            buffer_marker,
+            timers: device_info
+                .timestamp_period
+                .map_or_else(Vec::new, |period| {
+                    (0..frames_in_flight).map(|_| PassTimer::new(device, period)).collect()
+                }),
+            slot: 0,
+            passes: Vec::new(),
+            open: Vec::new(),
+            frame: 0,
+            frame_timing: false,
+            gpu_frame_time: None,
+        }
+    }
+
+    /// Starts a new frame in frame slot `slot`. The GPU must have finished the frame
+    /// that last used the slot.
+    pub fn begin_frame(&mut self, device: &ash::Device, slot: usize) {
+        self.slot = slot;
+        if let Some(timer) = self.timers.get_mut(slot) {
+            self.gpu_frame_time = timer.collect(device);
+        }
+        self.frame += 1;
+        self.passes.clear();
+        crash_context::begin_frame(self.frame);
+        self.open.clear();
+        if let Some(marker) = &mut self.buffer_marker {
+            marker.buffer.update_buffer(&[0u32, 0u32]);
+        }
+    }
+
+    pub fn begin_pass(
+        &mut self,
+        device: &ash::Device,
+        command_buffer: vk::CommandBuffer,
+        name: &str,
+    ) {
+        self.passes.push(name.to_string());
+        crash_context::record_pass(name);
+        let id = self.passes.len() as u32;
+        self.open.push(id);
+
+        if let Some(timer) = self.timers.get_mut(self.slot) {
+            let timing = trace::is_active() || self.frame_timing;
+            if timing && timer.timed.len() < MAX_TIMED_PASSES as usize {
+                timer.write(device, command_buffer, timer.timed.len(), false);
+                timer.timed.push((id as usize - 1, name.to_string()));
+            }
+        }
+
+        unsafe {
+            if let Some(debug_utils) = &self.debug_utils {
+                let label_name = CString::new(name).unwrap_or_default();
+                let label = vk::DebugUtilsLabelEXT::default().label_name(&label_name);
+                debug_utils.cmd_begin_debug_utils_label(command_buffer, &label);
+            }
+            if let Some(checkpoints) = &self.checkpoints {
+                checkpoints.cmd_set_checkpoint(command_buffer, checkpoint_marker(id, false));
+            }
+            if let Some(marker) = &self.buffer_marker {
+                marker.loader.cmd_write_buffer_marker(
+                    command_buffer,
+                    vk::PipelineStageFlags::TOP_OF_PIPE,
+                    marker.buffer.buffer,
+                    MARKER_STARTED,
+                    id,
+                );
+            }
+        }
+    }
+
+    pub fn end_pass(&mut self, device: &ash::Device, command_buffer: vk::CommandBuffer) {
+        let Some(id) = self.open.pop() else {
+            debug_assert!(false, "end_pass without begin_pass");
+            return;
+        };
+
+        if let Some(timer) = self.timers.get_mut(self.slot) {
+            let pass = id as usize - 1;
+            if let Some(i) = timer.timed.iter().rposition(|(timed, _)| *timed == pass) {
+                timer.write(device, command_buffer, i, true);
+                timer.closed += 1;
+            }
+        }
+
+        unsafe {
+            if let Some(marker) = &self.buffer_marker {
+                marker.loader.cmd_write_buffer_marker(
+                    command_buffer,
+                    vk::PipelineStageFlags::BOTTOM_OF_PIPE,
+                    marker.buffer.buffer,
+                    MARKER_FINISHED,
+                    id,
+                );
+            }
+            if let Some(checkpoints) = &self.checkpoints {
+                checkpoints.cmd_set_checkpoint(command_buffer, checkpoint_marker(id, true));
+            }
+            if let Some(debug_utils) = &self.debug_utils {
+                debug_utils.cmd_end_debug_utils_label(command_buffer);
+            }
+        }
+    }
+
+    /// Marks the frame's commands as submitted.
+    pub fn frame_submitted(&mut self) {
+        if let Some(timer) = self.timers.get_mut(self.slot) {
+            timer.submitted = Some(Instant::now());
+        }
+    }
+
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+
+    /// Times passes outside trace sessions too, for `gpu_frame_time`.
+    pub fn set_frame_timing(&mut self, enabled: bool) {
+        self.frame_timing = enabled;
+    }
+
+    /// GPU time of the last finished frame, if its passes were timed. `None` when the
+    /// device cannot write timestamps.
+    pub fn gpu_frame_time(&self) -> Option<Duration> {
+        self.gpu_frame_time
+    }
+
+    /// Passes begun in the current frame, or the last one until the next begins.
+    pub fn passes(&self) -> &[String] {
+        &self.passes
+    }
+
+    /// Describes what the GPU was doing when the device was lost.
+    pub fn device_lost_report(&self, queues: &[(&str, vk::Queue)]) -> String {
+        let mut report = format!("=== GPU device lost (frame {}) ===\n", self.frame);
+
+        if self.passes.is_empty() {
+            report.push_str("No passes were recorded this frame.\n");
+        } else {
+            report.push_str("Passes recorded this frame:\n");
+            for (i, pass) in self.passes.iter().enumerate() {
+                let _ = writeln!(report, "  #{} {}", i + 1, pass);
+            }
+        }
+
+        if let Some(marker) = &self.buffer_marker {
+            if let Some(mapped) = marker.buffer.mapped_buffer {
+                let slots = mapped as *const u32;
+                let (started, finished) =
+                    unsafe { (slots.read_volatile(), slots.add(1).read_volatile()) };
+                let _ = writeln!(
+                    report,
+                    "Buffer markers: last started {}, last finished {}",
+                    self.pass_name(started),
+                    self.pass_name(finished)
+                );
+            }
+        }
+
+        if let Some(checkpoints) = &self.checkpoints {
+            for &(queue_name, queue) in queues {
+                let data = unsafe {
+                    let len = checkpoints.get_queue_checkpoint_data_len(queue);
+                    let mut data = vec![vk::CheckpointDataNV::default(); len];
+                    checkpoints.get_queue_checkpoint_data(queue, &mut data);
+                    data
+                };
+                let _ = writeln!(report, "{queue_name} queue checkpoints:");
+                for checkpoint in data {
+                    let (id, end) = decode_checkpoint(checkpoint.p_checkpoint_marker);
+                    let _ = writeln!(
+                        report,
+                        "  {:?}: {} {}",
+                        checkpoint.stage,
+                        if end { "end" } else { "begin" },
+                        self.pass_name(id)
+                    );
+                }
+            }
+        }
+
+        if self.checkpoints.is_none() && self.buffer_marker.is_none() {
+            report.push_str(
+                "Enable `gpu_diagnostics` in the graphics settings for checkpoint or buffer \
+                 marker data (NVIDIA/AMD only).\n",
+            );
+        }
+
+        report
+    }
+
+    fn pass_name(&self, id: u32) -> String {
+        match id {
+            0 => "<none>".to_string(),
+            id => match self.passes.get(id as usize - 1) {
+                Some(name) => format!("#{id} {name}"),
+                None => format!("#{id}"),
+            },
+        }
+    }
+
+    pub fn destroy(&self, device: &ash::Device) {
+        if let Some(marker) = &self.buffer_marker {
+            marker.buffer.destroy(device);
+        }
+        for timer in &self.timers {
+            unsafe { device.destroy_query_pool(timer.pool, None) };
+        }
+    }
+}
+
+/// Checkpoint markers are opaque pointers; pack the pass id and begin/end into one.
+fn checkpoint_marker(id: u32, end: bool) -> *const c_void {
+    (((id as usize) << 1) | end as usize) as *const c_void
+}
+
+fn decode_checkpoint(marker: *mut c_void) -> (u32, bool) {
+    let bits = marker as usize;
+    ((bits >> 1) as u32, bits & 1 == 1)
+}
@@ -1,16 +1,33 @@
+use crate::backend_impl::allocated_buffer::AllocatedBuffer;
+use crate::backend_impl::barrier::{self, ImageBarrier};
 use crate::backend_impl::destroyable::Destroyable;
 use crate::backend_impl::device::DeviceInfo;
 use crate::backend_impl::utils;
-use crate::image::{ImageAspect, ImageDesc, ImageUsageFlags, TextureFormat};
+use crate::buffer::{BufferDesc, BufferUsageFlags};
+use crate::image::{ClearValue, ImageAspect, ImageDesc, ImageUsageFlags, TextureFormat};
+use crate::memory::MemoryHint;
+use crate::sync::ResourceState;
 use ash::{vk, Device, Instance};
+use std::ops::Range;
 
 pub struct AllocatedImage {
     pub image: vk::Image,
     pub image_view: vk::ImageView,
+    /// One view per mip level, bound as storage images. Only created for storage images
+    /// with several mips or layers; the others are bound through `image_view`.
+    pub mip_views: Vec<vk::ImageView>,
     pub image_memory: vk::DeviceMemory,
+    /// Size of `image_memory` in bytes, as required by the driver.
+    pub memory_size: vk::DeviceSize,
     pub image_extent: vk::Extent3D,
+    pub mip_levels: u32,
     pub image_format: vk::Format,
-    pub image_layout: vk::ImageLayout,
+    pub aspect: vk::ImageAspectFlags,
+    /// State the last recorded barrier left the image in.
+    pub state: ResourceState,
+    /// Value the attachment is cleared to when rendering begins. `None` clears color to
+    /// opaque black and depth to 1.
+    pub clear_value: Option<ClearValue>,
 }
 
 #[allow(dead_code)]
@@ -20,6 +37,127 @@ pub enum AllocatedImageType {
 }
 
 impl AllocatedImage {
+    /// Placeholder left in a registry slot whose image was released. Destroying it is a
+    /// no-op.
+    pub(crate) fn released() -> Self {
+        Self {
+            image: vk::Image::null(),
+            image_view: vk::ImageView::null(),
+            mip_views: Vec::new(),
+            image_memory: vk::DeviceMemory::null(),
+            memory_size: 0,
+            image_extent: vk::Extent3D::default(),
+            mip_levels: 0,
+            image_format: vk::Format::UNDEFINED,
+            aspect: vk::ImageAspectFlags::empty(),
+            state: ResourceState::Undefined,
+            clear_value: None,
+        }
+    }
+
+    pub(crate) fn is_released(&self) -> bool {
+        self.image == vk::Image::null()
+    }
+
+    /// Records a barrier on `command_buffer` that moves the image into `state`.
+    pub(crate) fn transition(
+        &mut self,
+        device: &Device,
+        command_buffer: vk::CommandBuffer,
+        state: ResourceState,
+    ) {
+        barrier::record_image_barriers(
+            device,
+            command_buffer,
+            &[ImageBarrier::new(
+                self.image,
+                self.aspect,
+                self.state,
+                state,
+            )],
+        );
+        self.state = state;
+    }
+
+    /// Copies a `width` x `height` region at (`x`, `y`) of the first mip and layer back
+    /// to the CPU and returns its texels, tightly packed row by row. The copy is queued
+    /// behind all submitted graphics work and blocks until it has finished.
+    pub(crate) fn read_region(
+        &mut self,
+        device_info: &DeviceInfo,
+        instance: &Instance,
+        (x, y): (u32, u32),
+        (width, height): (u32, u32),
+    ) -> Vec<u8> {
+        assert!(
+            x + width <= self.image_extent.width && y + height <= self.image_extent.height,
+            "region ({x}, {y}) + {width}x{height} is outside the image"
+        );
+        let size = texel_size(self.image_format) * width as usize * height as usize;
+        let (buffer, memory) = AllocatedBuffer::create_host_visible_buffer::<u8>(
+            device_info,
+            instance,
+            &BufferDesc {
+                size,
+                usage: BufferUsageFlags::TRANSFER_DST,
+                memory_hint: MemoryHint::CPUToGPU,
+            },
+            None,
+        );
+
+        let device = &device_info.logical_device;
+        let command_buffer = AllocatedBuffer::begin_single_time_command(device_info);
+        self.transition(device, command_buffer, ResourceState::TransferSrc);
+        let region = vk::BufferImageCopy::default()
+            .image_subresource(vk::ImageSubresourceLayers {
+                aspect_mask: self.aspect,
+                mip_level: 0,
+                base_array_layer: 0,
+                layer_count: 1,
+            })
+            .image_offset(vk::Offset3D {
+                x: x as i32,
+                y: y as i32,
+                z: 0,
+            })
+            .image_extent(vk::Extent3D {
+                width,
+                height,
+                depth: 1,
+            });
+        // Makes the copied texels visible to the host once the submission has finished.
+        let host_read = [vk::MemoryBarrier2::default()
+            .src_stage_mask(vk::PipelineStageFlags2::COPY)
+            .src_access_mask(vk::AccessFlags2::TRANSFER_WRITE)
+            .dst_stage_mask(vk::PipelineStageFlags2::HOST)
+            .dst_access_mask(vk::AccessFlags2::HOST_READ)];
+        unsafe {
+            device.cmd_copy_image_to_buffer(
+                command_buffer,
+                self.image,
+                vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                buffer,
+                &[region],
+            );
+            device.cmd_pipeline_barrier2(
+                command_buffer,
+                &vk::DependencyInfo::default().memory_barriers(&host_read),
+            );
+        }
+        AllocatedBuffer::end_single_time_command(device_info, command_buffer);
+
+        let mut texels = vec![0u8; size];
+        unsafe {
+            let ptr = device
+                .map_memory(memory, 0, size as u64, vk::MemoryMapFlags::empty())
+                .expect("Failed to map readback memory") as *const u8;
+            ptr.copy_to_nonoverlapping(texels.as_mut_ptr(), size);
+            device.unmap_memory(memory);
+        }
+        AllocatedBuffer::destroy_buffer(buffer, memory, device);
+        texels
+    }
+
     pub fn new(
         image_desc: ImageDesc,
         device_info: &DeviceInfo,
@@ -36,23 +174,70 @@ impl AllocatedImage {
         let aspect_flags = map_aspect(image_desc.aspect);
         let usage_flags = map_usage_flags(image_desc.usage);
 
+        // Storage images may be written by the async compute queue.
+        let sharing_families = if usage_flags.contains(vk::ImageUsageFlags::STORAGE) {
+            device_info.queue_info.storage_sharing_families()
+        } else {
+            vec![]
+        };
+        let shape = ImageShape::of(&image_desc);
         let image = Self::create_image(
             &device_info.logical_device,
             format,
             vk::ImageTiling::OPTIMAL,
             usage_flags,
             extent,
+            shape,
+            &sharing_families,
         );
-        let image_memory = Self::allocate_image(device_info, instance, &image, mem_properties);
-        let image_view = Self::create_image_view(device_info, &image, format, aspect_flags);
+        let (image_memory, memory_size) =
+            Self::allocate_image(device_info, instance, &image, mem_properties);
+        let image_view = Self::create_image_view(
+            device_info,
+            &image,
+            format,
+            aspect_flags,
+            shape.view_type(),
+            0..shape.mip_levels,
+            shape.array_layers,
+        );
+        let mip_views = if usage_flags.contains(vk::ImageUsageFlags::STORAGE)
+            && (shape.mip_levels > 1 || shape.array_layers > 1)
+        {
+            let view_type = if shape.array_layers > 1 {
+                vk::ImageViewType::TYPE_2D_ARRAY
+            } else {
+                vk::ImageViewType::TYPE_2D
+            };
+            (0..shape.mip_levels)
+                .map(|mip| {
+                    Self::create_image_view(
+                        device_info,
+                        &image,
+                        format,
+                        aspect_flags,
+                        view_type,
+                        mip..mip + 1,
+                        shape.array_layers,
+                    )
+                })
+                .collect()
+        } else {
+            Vec::new()
+        };
 
         Self {
             image,
             image_view,
+            mip_views,
             image_memory,
+            memory_size,
             image_format: format,
             image_extent: extent,
-            image_layout: vk::ImageLayout::UNDEFINED,
+            mip_levels: shape.mip_levels,
+            aspect: aspect_flags,
+            state: ResourceState::Undefined,
+            clear_value: image_desc.clear_value,
         }
     }
 
@@ -62,19 +247,31 @@ impl AllocatedImage {
         tiling: vk::ImageTiling,
         usage: vk::ImageUsageFlags,
         extent: vk::Extent3D,
+        shape: ImageShape,
+        sharing_families: &[u32],
     ) -> vk::Image {
-        let image_create_info = vk::ImageCreateInfo::default()
+        let flags = if shape.cube {
+            vk::ImageCreateFlags::CUBE_COMPATIBLE
+        } else {
+            vk::ImageCreateFlags::empty()
+        };
+        let mut image_create_info = vk::ImageCreateInfo::default()
             .image_type(vk::ImageType::TYPE_2D)
             .extent(extent)
-            .mip_levels(1)
-            .array_layers(1)
+            .mip_levels(shape.mip_levels)
+            .array_layers(shape.array_layers)
             .format(format)
             .tiling(tiling)
             .initial_layout(vk::ImageLayout::UNDEFINED)
             .usage(usage)
             .sharing_mode(vk::SharingMode::EXCLUSIVE)
             .samples(vk::SampleCountFlags::TYPE_1)
-            .flags(vk::ImageCreateFlags::empty());
+            .flags(flags);
+        if !sharing_families.is_empty() {
+            image_create_info = image_create_info
+                .sharing_mode(vk::SharingMode::CONCURRENT)
+                .queue_family_indices(sharing_families);
+        }
 
         unsafe {
             device
@@ -88,7 +285,7 @@ impl AllocatedImage {
         instance: &Instance,
         image: &vk::Image,
         mem_properties: vk::MemoryPropertyFlags,
-    ) -> vk::DeviceMemory {
+    ) -> (vk::DeviceMemory, vk::DeviceSize) {
         let mem_requirements = unsafe {
             device_info
                 .logical_device
@@ -120,26 +317,30 @@ impl AllocatedImage {
                 .expect("failed to bind image memory");
         }
 
-        allocated_memory
+        (allocated_memory, mem_requirements.size)
     }
 
+    /// A view of the mips in `mips` and the first `layer_count` layers.
     pub fn create_image_view(
         device_info: &DeviceInfo,
         image: &vk::Image,
         format: vk::Format,
         image_aspect_flags: vk::ImageAspectFlags,
+        view_type: vk::ImageViewType,
+        mips: Range<u32>,
+        layer_count: u32,
     ) -> vk::ImageView {
         let view_info = vk::ImageViewCreateInfo::default()
             .image(*image)
-            .view_type(vk::ImageViewType::TYPE_2D)
+            .view_type(view_type)
             .format(format)
             .subresource_range(
                 vk::ImageSubresourceRange::default()
                     .aspect_mask(image_aspect_flags)
-                    .base_mip_level(0)
-                    .level_count(1)
+                    .base_mip_level(mips.start)
+                    .level_count(mips.len() as u32)
                     .base_array_layer(0)
-                    .layer_count(1),
+                    .layer_count(layer_count),
             );
 
         unsafe {
@@ -151,6 +352,34 @@ impl AllocatedImage {
     }
 }
 
+/// Mip and layer counts of an image, and whether its six layers form a cube map.
+#[derive(Copy, Clone, Debug)]
+pub struct ImageShape {
+    pub mip_levels: u32,
+    pub array_layers: u32,
+    pub cube: bool,
+}
+
+impl ImageShape {
+    /// Cube maps always have six layers; other images have one, as array images are not
+    /// supported yet. A mip count of 0 means a single mip.
+    fn of(image_desc: &ImageDesc) -> Self {
+        Self {
+            mip_levels: image_desc.mip_levels.max(1),
+            array_layers: if image_desc.is_cubemap { 6 } else { 1 },
+            cube: image_desc.is_cubemap,
+        }
+    }
+
+    fn view_type(self) -> vk::ImageViewType {
+        if self.cube {
+            vk::ImageViewType::CUBE
+        } else {
+            vk::ImageViewType::TYPE_2D
+        }
+    }
+}
+
 pub fn copy_image_to_image(
     device: &Device,
     command_buffer: &vk::CommandBuffer,
@@ -194,55 +423,6 @@ pub fn copy_image_to_image(
     unsafe { device.cmd_blit_image2(*command_buffer, &blit_info) }
 }
 
-pub fn transition_image_layout(
-    device_info: &DeviceInfo,
-    command_buffer: &vk::CommandBuffer,
-    image: vk::Image,
-    old_layout: vk::ImageLayout,
-    new_layout: vk::ImageLayout,
-    depth: bool,
-) {
-    let mut aspect_mask = vk::ImageAspectFlags::COLOR;
-    if depth {
-        aspect_mask = vk::ImageAspectFlags::DEPTH;
-    }
-
-    let src_access_mask = vk::AccessFlags::MEMORY_WRITE;
-    let dst_access_mask = vk::AccessFlags::MEMORY_READ | vk::AccessFlags::MEMORY_WRITE;
-
-    let source_stage = vk::PipelineStageFlags::ALL_COMMANDS;
-    let destination_stage = vk::PipelineStageFlags::ALL_COMMANDS;
-
-    let barrier = vk::ImageMemoryBarrier::default()
-        .src_access_mask(src_access_mask)
-        .dst_access_mask(dst_access_mask)
-        .old_layout(old_layout)
-        .new_layout(new_layout)
-        .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
-        .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
-        .image(image)
-        .subresource_range(
-            vk::ImageSubresourceRange::default()
-                .aspect_mask(aspect_mask)
-                .base_mip_level(0)
-                .level_count(1)
-                .base_array_layer(0)
-                .layer_count(1),
-        );
-
-    unsafe {
-        device_info.logical_device.cmd_pipeline_barrier(
-            *command_buffer,
-            source_stage,
-            destination_stage,
-            vk::DependencyFlags::empty(),
-            &[],
-            &[],
-            &[barrier],
-        )
-    }
-}
-
 #[allow(dead_code, clippy::too_many_arguments)]
 pub fn create_image(
     device_info: &DeviceInfo,
@@ -311,8 +491,41 @@ pub fn create_image(
 fn map_texture_format(texture_format: TextureFormat) -> vk::Format {
     match texture_format {
         TextureFormat::R8g8b8a8Unorm => vk::Format::R8G8B8A8_UNORM,
+        TextureFormat::R8g8b8a8Srgb => vk::Format::R8G8B8A8_SRGB,
         TextureFormat::D32Float => vk::Format::D32_SFLOAT,
         TextureFormat::R16g16b16a16Float => vk::Format::R16G16B16A16_SFLOAT,
+        TextureFormat::R32Uint => vk::Format::R32_UINT,
+        TextureFormat::Bc5Unorm => vk::Format::BC5_UNORM_BLOCK,
+        TextureFormat::Bc7Unorm => vk::Format::BC7_UNORM_BLOCK,
+        TextureFormat::Bc7Srgb => vk::Format::BC7_SRGB_BLOCK,
+        TextureFormat::Astc4x4Unorm => vk::Format::ASTC_4X4_UNORM_BLOCK,
+        TextureFormat::Astc4x4Srgb => vk::Format::ASTC_4X4_SRGB_BLOCK,
+    }
+}
+
+/// Bytes of one `width` x `height` mip level in `format`, as laid out for uploads.
+pub(crate) fn level_size(format: vk::Format, width: u32, height: u32) -> usize {
+    match format {
+        vk::Format::BC5_UNORM_BLOCK
+        | vk::Format::BC7_UNORM_BLOCK
+        | vk::Format::BC7_SRGB_BLOCK
+        | vk::Format::ASTC_4X4_UNORM_BLOCK
+        | vk::Format::ASTC_4X4_SRGB_BLOCK => {
+            width.div_ceil(4) as usize * height.div_ceil(4) as usize * 16
+        }
+        _ => width as usize * height as usize * texel_size(format),
+    }
+}
+
+/// Bytes per texel of the formats `map_texture_format` produces.
+fn texel_size(format: vk::Format) -> usize {
+    match format {
+        vk::Format::R16G16B16A16_SFLOAT => 8,
+        vk::Format::R8G8B8A8_UNORM
+        | vk::Format::R8G8B8A8_SRGB
+        | vk::Format::D32_SFLOAT
+        | vk::Format::R32_UINT => 4,
+        other => panic!("no texel size for {other:?}"),
     }
 }
 
@@ -357,6 +570,9 @@ impl Destroyable for AllocatedImage {
     fn destroy(&self, device: &ash::Device) {
         unsafe {
             device.destroy_image_view(self.image_view, None);
+            for &view in &self.mip_views {
+                device.destroy_image_view(view, None);
+            }
             device.destroy_image(self.image, None);
             device.free_memory(self.image_memory, None);
         }
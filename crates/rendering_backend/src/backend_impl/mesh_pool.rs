@@ -0,0 +1,248 @@
+use crate::backend_impl::vulkan_backend::VulkanBackend;
+use crate::buffer::{BufferDesc, BufferHandle, BufferUsageFlags};
+use crate::memory::MemoryHint;
+use crate::sync::TimelinePoint;
+use common::Vertex;
+
+/// Vertices in each pooled vertex buffer, about 13 MB.
+const BLOCK_VERTICES: u32 = 1 << 18;
+/// Indices in each pooled index buffer, 4 MB.
+const BLOCK_INDICES: u32 = 1 << 20;
+
+/// Where a pooled mesh lives: element ranges of one block's vertex and index buffers.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct PoolAllocation {
+    block: usize,
+    first_vertex: u32,
+    vertex_count: u32,
+    first_index: u32,
+    index_count: u32,
+}
+
+impl PoolAllocation {
+    pub fn first_index(&self) -> u32 {
+        self.first_index
+    }
+
+    /// Added to the mesh's indices when drawing.
+    pub fn vertex_offset(&self) -> i32 {
+        self.first_vertex as i32
+    }
+}
+
+/// Suballocates static meshes from large shared vertex and index buffers, so meshes in
+/// one block draw with a single bind and can share an indirect batch. A block is added
+/// when none has room; meshes larger than a block are not pooled. Freed ranges are
+/// reused once the frame that last drew them has finished.
+#[derive(Default)]
+pub struct MeshBufferPool {
+    blocks: Vec<PoolBlock>,
+    pending_frees: Vec<(TimelinePoint, PoolAllocation)>,
+}
+
+struct PoolBlock {
+    vertex_buffer: BufferHandle,
+    index_buffer: BufferHandle,
+    vertices: RangeAllocator,
+    indices: RangeAllocator,
+}
+
+impl MeshBufferPool {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Copies a mesh into the pool and returns its allocation with the vertex and index
+    /// buffers holding it, or `None` if the mesh is empty or larger than a block.
+    pub fn allocate(
+        &mut self,
+        vulkan_backend: &mut VulkanBackend,
+        vertices: &[Vertex],
+        indices: &[u32],
+    ) -> Option<(PoolAllocation, BufferHandle, BufferHandle)> {
+        let vertex_count = pooled_len(vertices.len(), BLOCK_VERTICES)?;
+        let index_count = pooled_len(indices.len(), BLOCK_INDICES)?;
+        self.reclaim(vulkan_backend);
+
+        let allocation = self
+            .blocks
+            .iter_mut()
+            .enumerate()
+            .find_map(|(index, block)| block.allocate(index, vertex_count, index_count));
+        let allocation = match allocation {
+            Some(allocation) => allocation,
+            None => {
+                let index = self.blocks.len();
+                let mut block = PoolBlock::new(vulkan_backend);
+                let allocation = block
+                    .allocate(index, vertex_count, index_count)
+                    .expect("an empty block fits any pooled mesh");
+                self.blocks.push(block);
+                allocation
+            }
+        };
+
+        let block = &self.blocks[allocation.block];
+        vulkan_backend.upload_buffer_at(
+            block.vertex_buffer,
+            allocation.first_vertex as usize,
+            vertices,
+        );
+        vulkan_backend.upload_buffer_at(
+            block.index_buffer,
+            allocation.first_index as usize,
+            indices,
+        );
+        Some((allocation, block.vertex_buffer, block.index_buffer))
+    }
+
+    /// Returns a mesh's ranges to the pool once the last submitted frame has finished.
+    pub fn free(&mut self, vulkan_backend: &VulkanBackend, allocation: PoolAllocation) {
+        self.pending_frees
+            .push((vulkan_backend.last_frame_point(), allocation));
+    }
+
+    fn reclaim(&mut self, vulkan_backend: &VulkanBackend) {
+        let blocks = &mut self.blocks;
+        self.pending_frees.retain(|&(point, allocation)| {
+            if !vulkan_backend.is_reached(point) {
+                return true;
+            }
+            let block = &mut blocks[allocation.block];
+            block
+                .vertices
+                .free(allocation.first_vertex, allocation.vertex_count);
+            block
+                .indices
+                .free(allocation.first_index, allocation.index_count);
+            false
+        });
+    }
+}
+
+impl PoolBlock {
+    fn new(vulkan_backend: &mut VulkanBackend) -> Self {
+        let vertex_buffer = vulkan_backend.create_buffer::<Vertex>(
+            BufferDesc {
+                size: size_of::<Vertex>() * BLOCK_VERTICES as usize,
+                usage: BufferUsageFlags::VERTEX_BUFFER | BufferUsageFlags::TRANSFER_DST,
+                memory_hint: MemoryHint::GPUOnly,
+            },
+            None,
+        );
+        let index_buffer = vulkan_backend.create_buffer::<u32>(
+            BufferDesc {
+                size: size_of::<u32>() * BLOCK_INDICES as usize,
+                usage: BufferUsageFlags::INDEX_BUFFER | BufferUsageFlags::TRANSFER_DST,
+                memory_hint: MemoryHint::GPUOnly,
+            },
+            None,
+        );
+        Self {
+            vertex_buffer,
+            index_buffer,
+            vertices: RangeAllocator::new(BLOCK_VERTICES),
+            indices: RangeAllocator::new(BLOCK_INDICES),
+        }
+    }
+
+    fn allocate(
+        &mut self,
+        block: usize,
+        vertex_count: u32,
+        index_count: u32,
+    ) -> Option<PoolAllocation> {
+        let first_vertex = self.vertices.allocate(vertex_count)?;
+        let Some(first_index) = self.indices.allocate(index_count) else {
+            self.vertices.free(first_vertex, vertex_count);
+            return None;
+        };
+        Some(PoolAllocation {
+            block,
+            first_vertex,
+            vertex_count,
+            first_index,
+            index_count,
+        })
+    }
+}
+
+/// `len` as a range length, if it is non-empty and fits in a block of `capacity`.
+fn pooled_len(len: usize, capacity: u32) -> Option<u32> {
+    u32::try_from(len)
+        .ok()
+        .filter(|&len| len > 0 && len <= capacity)
+}
+
+/// First-fit allocator over element ranges of one buffer.
+#[derive(Debug)]
+struct RangeAllocator {
+    /// Free ranges as (start, length), sorted by start, never empty or adjacent.
+    free: Vec<(u32, u32)>,
+}
+
+impl RangeAllocator {
+    fn new(capacity: u32) -> Self {
+        Self {
+            free: vec![(0, capacity)],
+        }
+    }
+
+    fn allocate(&mut self, len: u32) -> Option<u32> {
+        let index = self
+            .free
+            .iter()
+            .position(|&(_, free_len)| free_len >= len)?;
+        let (start, free_len) = self.free[index];
+        if free_len == len {
+            self.free.remove(index);
+        } else {
+            self.free[index] = (start + len, free_len - len);
+        }
+        Some(start)
+    }
+
+    fn free(&mut self, start: u32, len: u32) {
+        let index = self
+            .free
+            .partition_point(|&(free_start, _)| free_start < start);
+        self.free.insert(index, (start, len));
+        // Merge with the following range, then the preceding one.
+        if let Some(&(next_start, next_len)) = self.free.get(index + 1) {
+            if start + len == next_start {
+                self.free[index].1 += next_len;
+                self.free.remove(index + 1);
+            }
+        }
+        if index > 0 {
+            let (prev_start, prev_len) = self.free[index - 1];
+            if prev_start + prev_len == start {
+                self.free[index - 1].1 += self.free[index].1;
+                self.free.remove(index);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reuses_and_merges_freed_ranges() {
+        let mut ranges = RangeAllocator::new(100);
+        let a = ranges.allocate(30).unwrap();
+        let b = ranges.allocate(30).unwrap();
+        let c = ranges.allocate(30).unwrap();
+        assert_eq!((a, b, c), (0, 30, 60));
+        assert_eq!(ranges.allocate(20), None);
+
+        ranges.free(a, 30);
+        ranges.free(c, 30);
+        assert_eq!(ranges.free, vec![(0, 30), (60, 40)]);
+        // Freeing the middle range joins all three.
+        ranges.free(b, 30);
+        assert_eq!(ranges.free, vec![(0, 100)]);
+        assert_eq!(ranges.allocate(100), Some(0));
+    }
+}
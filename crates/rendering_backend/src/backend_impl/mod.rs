@@ -1,18 +1,19 @@
 mod allocated_buffer;
+mod barrier;
+mod compile_queue;
 mod destroyable;
-mod camera;
-mod constants;
 mod conversions;
 mod descriptor_info;
+mod diagnostics;
 mod device;
 mod image_util;
+pub mod mesh_pool;
 mod pipeline_info;
+mod renderdoc;
 pub mod resource_manager;
 mod resource_registry;
-mod scene;
-mod structs;
 mod surface;
 mod swapchain;
+mod timeline;
 mod utils;
-mod vk_vertex_info;
 pub mod vulkan_backend;
@@ -0,0 +1,109 @@
+use ash::vk::{self, Handle};
+use std::ffi::{c_int, c_void};
+
+/// `eRENDERDOC_API_Version_1_1_2`, the oldest version with everything used here.
+const API_VERSION_1_1_2: c_int = 10102;
+
+type GetApiFn = unsafe extern "C" fn(version: c_int, out_api: *mut *mut c_void) -> c_int;
+type StartFrameCaptureFn = unsafe extern "C" fn(device: *mut c_void, window: *mut c_void);
+type EndFrameCaptureFn = unsafe extern "C" fn(device: *mut c_void, window: *mut c_void) -> u32;
+
+/// Prefix of `RENDERDOC_API_1_1_2`. Only the entry points up to `EndFrameCapture` are
+/// declared; the rest of the table is never read.
+#[repr(C)]
+struct RenderDocApi {
+    _get_api_version: *const c_void,
+    _set_capture_option_u32: *const c_void,
+    _set_capture_option_f32: *const c_void,
+    _get_capture_option_u32: *const c_void,
+    _get_capture_option_f32: *const c_void,
+    _set_focus_toggle_keys: *const c_void,
+    _set_capture_keys: *const c_void,
+    _get_overlay_bits: *const c_void,
+    _mask_overlay_bits: *const c_void,
+    _remove_hooks: *const c_void,
+    _unload_crash_handler: *const c_void,
+    _set_capture_file_path_template: *const c_void,
+    _get_capture_file_path_template: *const c_void,
+    _get_num_captures: *const c_void,
+    _get_capture: *const c_void,
+    _trigger_capture: *const c_void,
+    _is_target_control_connected: *const c_void,
+    _launch_replay_ui: *const c_void,
+    _set_active_window: *const c_void,
+    start_frame_capture: StartFrameCaptureFn,
+    _is_frame_capturing: *const c_void,
+    end_frame_capture: EndFrameCaptureFn,
+}
+
+/// The in-application RenderDoc API. Only available when the process was launched or
+/// injected by RenderDoc; the library is never loaded on its own.
+pub struct RenderDoc {
+    // Keeps the module reference alive for as long as `api` is used.
+    _library: libloading::Library,
+    api: *const RenderDocApi,
+}
+
+impl RenderDoc {
+    pub fn attach() -> Option<Self> {
+        let library = Self::loaded_library()?;
+        let api = unsafe {
+            let get_api = library.get::<GetApiFn>(b"RENDERDOC_GetAPI\0").ok()?;
+            let mut api = std::ptr::null_mut();
+            if get_api(API_VERSION_1_1_2, &mut api) != 1 || api.is_null() {
+                return None;
+            }
+            api as *const RenderDocApi
+        };
+
+        Some(Self {
+            _library: library,
+            api,
+        })
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    fn loaded_library() -> Option<libloading::Library> {
+        use libloading::os::unix::{Library, RTLD_NOW};
+        // Not exported by libloading; same value on glibc, musl and bionic.
+        const RTLD_NOLOAD: std::ffi::c_int = 0x4;
+        let name = if cfg!(target_os = "android") {
+            "libVkLayer_GLES_RenderDoc.so"
+        } else {
+            "librenderdoc.so"
+        };
+        unsafe { Library::open(Some(name), RTLD_NOW | RTLD_NOLOAD) }
+            .ok()
+            .map(Into::into)
+    }
+
+    #[cfg(windows)]
+    fn loaded_library() -> Option<libloading::Library> {
+        libloading::os::windows::Library::open_already_loaded("renderdoc.dll")
+            .ok()
+            .map(Into::into)
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "android", windows)))]
+    fn loaded_library() -> Option<libloading::Library> {
+        None
+    }
+
+    /// Starts capturing all work on `instance`'s device until `end_frame_capture`.
+    pub fn start_frame_capture(&self, instance: vk::Instance) {
+        unsafe { ((*self.api).start_frame_capture)(device_pointer(instance), std::ptr::null_mut()) }
+    }
+
+    /// Returns false if the capture failed.
+    pub fn end_frame_capture(&self, instance: vk::Instance) -> bool {
+        unsafe {
+            ((*self.api).end_frame_capture)(device_pointer(instance), std::ptr::null_mut()) == 1
+        }
+    }
+}
+
+/// `RENDERDOC_DEVICEPOINTER_FROM_VKINSTANCE`: the dispatch table pointer the instance
+/// handle points at.
+fn device_pointer(instance: vk::Instance) -> *mut c_void {
+    unsafe { *(instance.as_raw() as *const *mut c_void) }
+}
@@ -1,8 +1,13 @@
+use crate::backend_impl::mesh_pool::{MeshBufferPool, PoolAllocation};
 use crate::backend_impl::vulkan_backend::VulkanBackend;
 use crate::buffer::{BufferDesc, BufferHandle, BufferUsageFlags};
 use crate::image::{GpuImageHandle, ImageAspect, ImageDesc, ImageUsageFlags, TextureFormat};
 use crate::memory::MemoryHint;
-use common::{ImageData, ImageHandle, MeshData, MeshHandle, Vertex};
+use common::{
+    block_compression, ColorSpace, ImageData, ImageHandle, MeshData, MeshHandle, PackedVertex,
+    PixelFormat, Vertex, VertexEncoding,
+};
+use nalgebra_glm::Vec4;
 use std::collections::HashMap;
 use std::mem;
 
@@ -11,11 +16,26 @@ pub struct GpuMeshData {
     pub vertex_buffer: BufferHandle,
     pub index_buffer: BufferHandle,
     pub index_count: usize,
+    /// First index of the mesh in `index_buffer`.
+    pub first_index: u32,
+    /// Added to the mesh's indices to address `vertex_buffer`.
+    pub vertex_offset: i32,
+    /// Set when the vertex and index buffers are shared pool buffers.
+    pub pool_allocation: Option<PoolAllocation>,
+    /// `VertexExtra` stream, for meshes that have one.
+    pub extra_buffer: Option<BufferHandle>,
+    /// `VertexSkin` stream, for meshes bound to a skeleton.
+    pub skin_buffer: Option<BufferHandle>,
+    /// Layout of `vertex_buffer`; pipelines drawing the mesh must match it.
+    pub vertex_encoding: VertexEncoding,
+    /// Mesh-space bounding sphere; xyz: center, w: radius.
+    pub bounds: Vec4,
 }
 
 pub struct ResourceManager {
     pub mesh_data: HashMap<MeshHandle, GpuMeshData>,
     pub images: HashMap<ImageHandle, GpuImageHandle>,
+    mesh_pool: MeshBufferPool,
 }
 
 #[derive(Clone, Debug)]
@@ -29,6 +49,7 @@ impl ResourceManager {
         Self {
             mesh_data: HashMap::new(),
             images: HashMap::new(),
+            mesh_pool: MeshBufferPool::new(),
         }
     }
 }
@@ -63,46 +84,206 @@ impl ResourceManager {
         let vertices = mesh.vertices.as_slice();
         let indices = mesh.indices.as_slice();
 
-        let vertex_buffer_size = mem::size_of_val(vertices);
-        let index_buffer_size = mem::size_of_val(indices);
-        let vertex_buffer_handle = vulkan_backend.create_buffer(
-            BufferDesc {
-                usage: BufferUsageFlags::VERTEX_BUFFER,
-                memory_hint: MemoryHint::GPUOnly,
-                size: vertex_buffer_size,
-            },
-            Some(vertices),
-        );
-        let index_buffer_handle = vulkan_backend.create_buffer(
-            BufferDesc {
-                usage: BufferUsageFlags::INDEX_BUFFER,
-                memory_hint: MemoryHint::GPUOnly,
-                size: index_buffer_size,
-            },
-            Some(indices),
-        );
+        // Extra streams are addressed with the same vertex offset as the vertices, so
+        // meshes with them keep dedicated buffers. The pool holds full vertices only.
+        let pooled = if mesh.extras.is_none()
+            && mesh.skin.is_none()
+            && mesh.vertex_encoding == VertexEncoding::Full
+        {
+            self.mesh_pool.allocate(vulkan_backend, vertices, indices)
+        } else {
+            None
+        };
+        let (vertex_buffer_handle, index_buffer_handle, pool_allocation) = match pooled {
+            Some((allocation, vertex_buffer, index_buffer)) => {
+                (vertex_buffer, index_buffer, Some(allocation))
+            }
+            None => {
+                let vertex_desc = |size| BufferDesc {
+                    usage: BufferUsageFlags::VERTEX_BUFFER,
+                    memory_hint: MemoryHint::GPUOnly,
+                    size,
+                };
+                let index_buffer_size = mem::size_of_val(indices);
+                let vertex_buffer_handle = match mesh.vertex_encoding {
+                    VertexEncoding::Full => vulkan_backend
+                        .create_buffer(vertex_desc(mem::size_of_val(vertices)), Some(vertices)),
+                    VertexEncoding::Packed => {
+                        let packed: Vec<PackedVertex> =
+                            vertices.iter().map(PackedVertex::pack).collect();
+                        vulkan_backend
+                            .create_buffer(vertex_desc(mem::size_of_val(&*packed)), Some(&packed))
+                    }
+                };
+                let index_buffer_handle = vulkan_backend.create_buffer(
+                    BufferDesc {
+                        usage: BufferUsageFlags::INDEX_BUFFER,
+                        memory_hint: MemoryHint::GPUOnly,
+                        size: index_buffer_size,
+                    },
+                    Some(indices),
+                );
+                (vertex_buffer_handle, index_buffer_handle, None)
+            }
+        };
+
+        let extra_buffer = mesh.extras.as_deref().map(|extras| {
+            vulkan_backend.create_buffer(
+                BufferDesc {
+                    usage: BufferUsageFlags::VERTEX_BUFFER,
+                    memory_hint: MemoryHint::GPUOnly,
+                    size: mem::size_of_val(extras),
+                },
+                Some(extras),
+            )
+        });
 
+        let skin_buffer = mesh.skin.as_deref().map(|skin| {
+            vulkan_backend.create_buffer(
+                BufferDesc {
+                    usage: BufferUsageFlags::VERTEX_BUFFER,
+                    memory_hint: MemoryHint::GPUOnly,
+                    size: mem::size_of_val(skin),
+                },
+                Some(skin),
+            )
+        });
+
+        let (center, radius) = mesh.bounding_sphere();
         let mesh_data = GpuMeshData {
             vertex_buffer: vertex_buffer_handle,
             index_buffer: index_buffer_handle,
             index_count: indices.len(),
+            first_index: pool_allocation.map_or(0, |allocation| allocation.first_index()),
+            vertex_offset: pool_allocation.map_or(0, |allocation| allocation.vertex_offset()),
+            pool_allocation,
+            extra_buffer,
+            skin_buffer,
+            vertex_encoding: mesh.vertex_encoding,
+            bounds: Vec4::new(center.x, center.y, center.z, radius),
         };
         self.mesh_data.insert(handle, mesh_data);
 
         mesh_data
     }
 
+    /// Frees the GPU buffers of a mesh, or returns its pool ranges. Does nothing if it was
+    /// never uploaded.
+    pub fn release_mesh(&mut self, vulkan_backend: &mut VulkanBackend, handle: MeshHandle) {
+        let Some(mesh_data) = self.mesh_data.remove(&handle) else {
+            return;
+        };
+        if let Some(allocation) = mesh_data.pool_allocation {
+            self.mesh_pool.free(vulkan_backend, allocation);
+            return;
+        }
+        vulkan_backend.release_buffer(mesh_data.vertex_buffer);
+        vulkan_backend.release_buffer(mesh_data.index_buffer);
+        for buffer in [mesh_data.extra_buffer, mesh_data.skin_buffer]
+            .into_iter()
+            .flatten()
+        {
+            vulkan_backend.release_buffer(buffer);
+        }
+    }
+
+    /// Frees the GPU copy of a texture. Does nothing if it was never uploaded.
+    pub fn release_image(&mut self, vulkan_backend: &mut VulkanBackend, handle: ImageHandle) {
+        if let Some(image) = self.images.remove(&handle) {
+            vulkan_backend.release_image(image);
+        }
+    }
+
+    /// Uploads a texture with all its mips on first use. Block-compressed textures the
+    /// device cannot sample are decoded to RGBA8; ASTC ones, which have no software
+    /// decoder, are replaced by a magenta texel.
     pub fn get_or_create_image(
         &mut self,
         vulkan_backend: &mut VulkanBackend,
         handle: ImageHandle,
         data: &ImageData,
     ) -> GpuImageHandle {
-        let images = self.images.get_mut(&handle);
-        if let Some(images) = images {
-            return *images;
+        self.get_or_create_image_from_mip(vulkan_backend, handle, data, 0)
+    }
+
+    /// Like [`get_or_create_image`](Self::get_or_create_image), but a texture uploaded
+    /// here only gets its mips from `first_mip` down.
+    pub fn get_or_create_image_from_mip(
+        &mut self,
+        vulkan_backend: &mut VulkanBackend,
+        handle: ImageHandle,
+        data: &ImageData,
+        first_mip: u32,
+    ) -> GpuImageHandle {
+        if let Some(&image) = self.images.get(&handle) {
+            return image;
         }
+        let image = Self::upload_image(vulkan_backend, handle, data, first_mip, None);
+        self.images.insert(handle, image);
+        image
+    }
+
+    /// Replaces an uploaded texture with its mips from `first_mip` down, under the same
+    /// handle. Descriptor sets binding it must be refreshed afterwards. Returns `None` if
+    /// the texture was never uploaded.
+    pub fn reupload_image(
+        &mut self,
+        vulkan_backend: &mut VulkanBackend,
+        handle: ImageHandle,
+        data: &ImageData,
+        first_mip: u32,
+    ) -> Option<GpuImageHandle> {
+        let image = *self.images.get(&handle)?;
+        Some(Self::upload_image(
+            vulkan_backend,
+            handle,
+            data,
+            first_mip,
+            Some(image),
+        ))
+    }
+
+    /// Uploads the mips of `data` from `first_mip` down into a new image, or in place of
+    /// `target`.
+    fn upload_image(
+        vulkan_backend: &mut VulkanBackend,
+        handle: ImageHandle,
+        data: &ImageData,
+        first_mip: u32,
+        target: Option<GpuImageHandle>,
+    ) -> GpuImageHandle {
+        let fallback;
+        let data = if vulkan_backend.capabilities().supports(data.format) {
+            data
+        } else {
+            fallback = block_compression::decompress(data).unwrap_or_else(|| {
+                eprintln!(
+                    "warning: the device cannot sample {:?} textures; texture {} is replaced",
+                    data.format,
+                    handle.raw()
+                );
+                ImageData::rgba8(vec![255, 0, 255, 255], 1, 1, ColorSpace::Linear)
+            });
+            &fallback
+        };
+        let tail;
+        let data = if first_mip > 0 {
+            tail = data.mip_tail(first_mip);
+            &tail
+        } else {
+            data
+        };
 
+        let srgb = data.color_space == ColorSpace::Srgb;
+        let format = match data.format {
+            PixelFormat::Rgba8 if srgb => TextureFormat::R8g8b8a8Srgb,
+            PixelFormat::Rgba8 => TextureFormat::R8g8b8a8Unorm,
+            PixelFormat::Bc5 => TextureFormat::Bc5Unorm,
+            PixelFormat::Bc7 if srgb => TextureFormat::Bc7Srgb,
+            PixelFormat::Bc7 => TextureFormat::Bc7Unorm,
+            PixelFormat::Astc4x4 if srgb => TextureFormat::Astc4x4Srgb,
+            PixelFormat::Astc4x4 => TextureFormat::Astc4x4Unorm,
+        };
         let image_desc = ImageDesc {
             width: data.width,
             height: data.height,
@@ -110,16 +291,20 @@ impl ResourceManager {
             aspect: ImageAspect::Color,
             array_layers: 0,
             is_cubemap: false,
-            mip_levels: 0,
-            format: TextureFormat::R8g8b8a8Unorm,
+            mip_levels: data.mip_levels,
+            format,
             clear_value: None,
             depth: 1,
         };
 
-        let image_handle = vulkan_backend.create_image(image_desc);
+        let image_handle = match target {
+            Some(image) => {
+                vulkan_backend.recreate_image(image, image_desc);
+                image
+            }
+            None => vulkan_backend.create_image(image_desc),
+        };
         vulkan_backend.update_image_data(image_handle, data.pixels.as_ref());
-        self.images.insert(handle, image_handle);
-
         image_handle
     }
 }
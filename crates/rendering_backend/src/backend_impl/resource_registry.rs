@@ -4,13 +4,20 @@ use crate::backend_impl::descriptor_info::{
 };
 use crate::backend_impl::destroyable::Destroyable;
 use crate::backend_impl::image_util::AllocatedImage;
-use crate::backend_impl::pipeline_info::PipelineInfo;
+use crate::backend_impl::pipeline_info::{PipelineInfo, PipelineLayoutKey};
 use crate::buffer::BufferHandle;
-use crate::descriptor::{DescriptorLayoutHandle, DescriptorSetHandle};
+use crate::descriptor::{
+    DescriptorLayoutDesc, DescriptorLayoutHandle, DescriptorSetHandle, DescriptorWriteDesc,
+};
 use crate::image::GpuImageHandle;
+use crate::memory::{mib, GpuMemoryStats};
 use crate::pipeline::PipelineHandle;
-use crate::sampler::SamplerHandle;
+use crate::sampler::{SamplerDesc, SamplerHandle};
 use ash::vk;
+use std::collections::{HashMap, VecDeque};
+
+/// Largest images listed individually in the shutdown report.
+const REPORTED_IMAGES: usize = 8;
 
 /// Thin wrapper so `vk::Sampler` (a plain handle) can implement `Destroyable`.
 struct OwnedSampler(vk::Sampler);
@@ -26,11 +33,25 @@ pub struct ResourceRegistry {
     pub buffers: Vec<AllocatedBuffer>,
     pub descriptor_pools: Vec<DescriptorPoolChunk>,
     pub descriptor_sets: Vec<AllocatedDescriptorSet>,
+    /// Last write to each binding of each set, indexed like `descriptor_sets`, so sets can
+    /// be rewritten once an image they bind is recreated.
+    descriptor_writes: Vec<Vec<DescriptorWriteDesc>>,
     pub descriptor_layouts: Vec<DescriptorLayoutInfo>,
     pub pipelines: Vec<PipelineInfo>,
     pub samplers: Vec<vk::Sampler>,
-    /// Resources waiting to be freed after the next GPU fence wait.
-    pending_destroy: Vec<Box<dyn Destroyable>>,
+    /// Layout created for each normalized description, so equal layouts are shared.
+    descriptor_layout_cache: HashMap<DescriptorLayoutDesc, DescriptorLayoutHandle>,
+    /// Pipeline layouts shared by all pipelines with the same sets and push constants.
+    pipeline_layouts: HashMap<PipelineLayoutKey, vk::PipelineLayout>,
+    sampler_cache: HashMap<SamplerDesc, SamplerHandle>,
+    /// Resources waiting to be freed, with the frame that was being recorded when they
+    /// were released. Oldest first.
+    pending_destroy: VecDeque<(u64, Box<dyn Destroyable>)>,
+    /// Number of the frame being recorded; frames count up from 0 as they are submitted.
+    frame: u64,
+    /// Frame that last bound each set, indexed like `descriptor_sets`.
+    descriptor_frames: Vec<Option<u64>>,
+    memory: GpuMemoryStats,
 }
 
 impl ResourceRegistry {
@@ -40,46 +61,177 @@ impl ResourceRegistry {
             buffers: vec![],
             descriptor_pools: vec![],
             descriptor_sets: vec![],
+            descriptor_writes: vec![],
             descriptor_layouts: vec![],
             pipelines: vec![],
             samplers: vec![],
-            pending_destroy: vec![],
+            descriptor_layout_cache: HashMap::new(),
+            pipeline_layouts: HashMap::new(),
+            sampler_cache: HashMap::new(),
+            pending_destroy: VecDeque::new(),
+            frame: 0,
+            descriptor_frames: vec![],
+            memory: GpuMemoryStats::default(),
         }
     }
 
     pub fn register_image(&mut self, image: AllocatedImage) -> GpuImageHandle {
         let id = self.images.len();
+        self.memory.images.allocate(image.memory_size);
         self.images.push(image);
         GpuImageHandle(id)
     }
 
     pub fn register_buffer(&mut self, buffer: AllocatedBuffer) -> BufferHandle {
         let id = self.buffers.len();
+        self.memory.buffers.allocate(buffer.buffer_size);
         self.buffers.push(buffer);
         BufferHandle(id)
     }
 
+    /// Swaps the image behind `handle` and queues the old one for destruction.
+    pub fn replace_image(&mut self, handle: GpuImageHandle, image: AllocatedImage) {
+        self.memory.images.allocate(image.memory_size);
+        let old = std::mem::replace(&mut self.images[handle.0], image);
+        self.memory.images.free(old.memory_size);
+        self.queue_destroy(Box::new(old));
+    }
+
+    /// Queues the image behind `handle` for destruction and leaves an empty slot, so
+    /// other handles stay valid. `handle` must not be used again.
+    pub fn release_image(&mut self, handle: GpuImageHandle) {
+        let old = std::mem::replace(&mut self.images[handle.0], AllocatedImage::released());
+        if old.is_released() {
+            return;
+        }
+        self.memory.images.free(old.memory_size);
+        self.queue_destroy(Box::new(old));
+    }
+
+    /// Buffer counterpart of [`release_image`](Self::release_image).
+    pub fn release_buffer(&mut self, handle: BufferHandle) {
+        let old = std::mem::replace(&mut self.buffers[handle.0], AllocatedBuffer::released());
+        if old.is_released() {
+            return;
+        }
+        self.memory.buffers.free(old.buffer_size);
+        self.queue_destroy(Box::new(old));
+    }
+
+    /// Queues the set for freeing and returns its slot to the pool it came from.
+    pub fn release_descriptor_set(&mut self, handle: DescriptorSetHandle) {
+        let set = &mut self.descriptor_sets[handle.0];
+        if set.descriptor_set == vk::DescriptorSet::null() {
+            return;
+        }
+        let old = AllocatedDescriptorSet {
+            descriptor_set: std::mem::replace(&mut set.descriptor_set, vk::DescriptorSet::null()),
+            pool: set.pool,
+        };
+        self.descriptor_writes[handle.0].clear();
+        if let Some(pool) = self
+            .descriptor_pools
+            .iter_mut()
+            .find(|p| p.pool == old.pool)
+        {
+            pool.used -= 1;
+        }
+        self.queue_destroy(Box::new(old));
+    }
+
     pub fn register_allocated_descriptor_set(
         &mut self,
         allocated_descriptor: AllocatedDescriptorSet,
     ) -> DescriptorSetHandle {
         let id = self.descriptor_sets.len();
         self.descriptor_sets.push(allocated_descriptor);
+        self.descriptor_writes.push(vec![]);
+        self.descriptor_frames.push(None);
         DescriptorSetHandle(id)
     }
 
+    /// Remembers `writes` to the set, replacing earlier writes to the same bindings.
+    pub fn record_descriptor_writes(
+        &mut self,
+        handle: DescriptorSetHandle,
+        writes: &[DescriptorWriteDesc],
+    ) {
+        let recorded = &mut self.descriptor_writes[handle.0];
+        for write in writes {
+            recorded.retain(|old| old.binding != write.binding);
+            recorded.push(*write);
+        }
+    }
+
+    /// Records that the frame being recorded binds the set.
+    pub fn mark_descriptor_set_bound(&mut self, handle: DescriptorSetHandle) {
+        self.descriptor_frames[handle.0] = Some(self.frame);
+    }
+
+    /// The last frame that bound the set, if any did.
+    pub fn descriptor_set_frame(&self, handle: DescriptorSetHandle) -> Option<u64> {
+        self.descriptor_frames[handle.0]
+    }
+
+    /// Every binding written to the set so far.
+    pub fn descriptor_writes(&self, handle: DescriptorSetHandle) -> &[DescriptorWriteDesc] {
+        &self.descriptor_writes[handle.0]
+    }
+
+    /// Sets with a binding that refers to one of `images`.
+    pub fn descriptor_sets_binding(&self, images: &[GpuImageHandle]) -> Vec<DescriptorSetHandle> {
+        self.descriptor_writes
+            .iter()
+            .enumerate()
+            .filter(|(_, writes)| {
+                writes.iter().any(|write| {
+                    write
+                        .value
+                        .image()
+                        .is_some_and(|image| images.iter().any(|other| other.0 == image.0))
+                })
+            })
+            .map(|(index, _)| DescriptorSetHandle(index))
+            .collect()
+    }
+
+    /// The layout already created for `desc`, which must be normalized.
+    pub fn find_descriptor_layout(
+        &self,
+        desc: &DescriptorLayoutDesc,
+    ) -> Option<DescriptorLayoutHandle> {
+        self.descriptor_layout_cache.get(desc).copied()
+    }
+
     pub fn register_descriptor_layout(
         &mut self,
+        desc: DescriptorLayoutDesc,
         layout_info: DescriptorLayoutInfo,
     ) -> DescriptorLayoutHandle {
         let id = self.descriptor_layouts.len();
         self.descriptor_layouts.push(layout_info);
+        self.descriptor_layout_cache
+            .insert(desc, DescriptorLayoutHandle(id));
         DescriptorLayoutHandle(id)
     }
 
-    pub fn register_sampler(&mut self, sampler: vk::Sampler) -> SamplerHandle {
+    pub fn pipeline_layout(&self, key: &PipelineLayoutKey) -> Option<vk::PipelineLayout> {
+        self.pipeline_layouts.get(key).copied()
+    }
+
+    pub fn register_pipeline_layout(&mut self, key: PipelineLayoutKey, layout: vk::PipelineLayout) {
+        self.pipeline_layouts.insert(key, layout);
+    }
+
+    /// The sampler already created for `desc`.
+    pub fn find_sampler(&self, desc: &SamplerDesc) -> Option<SamplerHandle> {
+        self.sampler_cache.get(desc).copied()
+    }
+
+    pub fn register_sampler(&mut self, desc: SamplerDesc, sampler: vk::Sampler) -> SamplerHandle {
         let id = self.samplers.len();
         self.samplers.push(sampler);
+        self.sampler_cache.insert(desc, SamplerHandle(id));
         SamplerHandle(id)
     }
 
@@ -89,46 +241,283 @@ impl ResourceRegistry {
         PipelineHandle(id)
     }
 
-    /// Queue any resource for deferred destruction. The resource will be freed
-    /// on the next call to `flush_pending`, which happens after the GPU fence wait.
-    #[allow(dead_code)]
+    /// Queue any resource for deferred destruction. The resource may still be used by
+    /// the frame being recorded and those before it, so `flush_pending` frees it once the
+    /// GPU has finished that frame.
     pub fn queue_destroy(&mut self, resource: Box<dyn Destroyable>) {
-        self.pending_destroy.push(resource);
+        self.pending_destroy.push_back((self.frame, resource));
+    }
+
+    /// Moves on to recording the next frame. Call once the current one is submitted.
+    pub fn frame_submitted(&mut self) {
+        self.frame += 1;
+    }
+
+    /// Current accounting of registered resources.
+    pub fn memory_stats(&self) -> GpuMemoryStats {
+        GpuMemoryStats {
+            pipelines: self.pipelines.len(),
+            pipeline_layouts: self.pipeline_layouts.len(),
+            descriptor_layouts: self.descriptor_layouts.len(),
+            descriptor_sets: self
+                .descriptor_sets
+                .iter()
+                .filter(|set| set.descriptor_set != vk::DescriptorSet::null())
+                .count(),
+            samplers: self.samplers.len(),
+            ..self.memory.clone()
+        }
     }
 
-    /// Free all queued resources. Call this immediately after the per-frame fence wait
-    /// to guarantee the GPU has finished using these resources.
-    pub fn flush_pending(&mut self, device: &ash::Device) {
-        for resource in self.pending_destroy.drain(..) {
+    /// Closes the per-frame allocation counters. Call once at the start of each frame.
+    pub fn end_frame_accounting(&mut self) {
+        self.memory.end_frame();
+    }
+
+    /// Frees the queued resources that no frame after `finished` uses. Call once the
+    /// GPU has finished frame `finished`.
+    pub fn flush_pending(&mut self, device: &ash::Device, finished: u64) {
+        for resource in self.take_pending(finished) {
             resource.destroy(device);
         }
     }
 
-    /// Free every live resource and flush the pending queue.
+    fn take_pending(&mut self, finished: u64) -> Vec<Box<dyn Destroyable>> {
+        let count = self
+            .pending_destroy
+            .iter()
+            .take_while(|(frame, _)| *frame <= finished)
+            .count();
+        self.pending_destroy
+            .drain(..count)
+            .map(|(_, resource)| resource)
+            .collect()
+    }
+
+    /// Free every live resource and everything still queued.
     /// Call this on shutdown after `device_wait_idle`.
     pub fn destroy_all(&mut self, device: &ash::Device) {
-        self.flush_pending(device);
-        // Free individual sets before destroying their pools.
-        for set in self.descriptor_sets.drain(..) {
+        if cfg!(debug_assertions) {
+            self.report_unreleased();
+        }
+        self.flush_pending(device, u64::MAX);
+        self.descriptor_writes.clear();
+        self.descriptor_frames.clear();
+        // Free individual sets before destroying their pools. Released slots are empty.
+        for set in self
+            .descriptor_sets
+            .drain(..)
+            .filter(|set| set.descriptor_set != vk::DescriptorSet::null())
+        {
             set.destroy(device);
         }
         for pool in self.descriptor_pools.drain(..) {
             pool.destroy(device);
         }
-        for layout in self.descriptor_layouts.drain(..) {
-            layout.destroy(device);
-        }
         for pipeline in self.pipelines.drain(..) {
             pipeline.destroy(device);
         }
+        for (_, layout) in self.pipeline_layouts.drain() {
+            unsafe { device.destroy_pipeline_layout(layout, None) };
+        }
+        self.descriptor_layout_cache.clear();
+        for layout in self.descriptor_layouts.drain(..) {
+            layout.destroy(device);
+        }
         for image in self.images.drain(..) {
             image.destroy(device);
         }
         for buffer in self.buffers.drain(..) {
             buffer.destroy(device);
         }
+        self.sampler_cache.clear();
         for sampler in self.samplers.drain(..) {
             OwnedSampler(sampler).destroy(device);
         }
     }
+
+    /// Lists what is still registered at shutdown, largest images first. Only assets the
+    /// asset GC unloads are released earlier, so growth here between runs points at
+    /// resources created every frame or every load instead of once.
+    fn report_unreleased(&self) {
+        let stats = self.memory_stats();
+        if stats.images.live_count == 0 && stats.buffers.live_count == 0 {
+            return;
+        }
+        println!("GPU resources still registered at shutdown:\n{}", stats);
+
+        let mut images = self
+            .images
+            .iter()
+            .filter(|image| !image.is_released())
+            .collect::<Vec<_>>();
+        images.sort_by_key(|image| std::cmp::Reverse(image.memory_size));
+        for image in images.iter().take(REPORTED_IMAGES) {
+            let extent = image.image_extent;
+            println!(
+                "  image {}x{}x{} {:?}: {:.2} MiB",
+                extent.width,
+                extent.height,
+                extent.depth,
+                image.image_format,
+                mib(image.memory_size)
+            );
+        }
+        if images.len() > REPORTED_IMAGES {
+            println!(
+                "  ... and {} smaller images",
+                images.len() - REPORTED_IMAGES
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::buffer::BufferHandle;
+    use crate::descriptor::{DescriptorValue, SampledImageInfo};
+
+    fn sampled(binding: usize, image: usize) -> DescriptorWriteDesc {
+        DescriptorWriteDesc {
+            binding,
+            value: DescriptorValue::SampledImage(SampledImageInfo {
+                image: GpuImageHandle(image),
+                sampler: SamplerHandle(0),
+            }),
+        }
+    }
+
+    fn empty_set() -> AllocatedDescriptorSet {
+        AllocatedDescriptorSet {
+            descriptor_set: vk::DescriptorSet::null(),
+            pool: vk::DescriptorPool::null(),
+        }
+    }
+
+    #[test]
+    fn sets_are_found_by_the_images_they_currently_bind() {
+        let mut registry = ResourceRegistry::new();
+        let first = registry.register_allocated_descriptor_set(empty_set());
+        let second = registry.register_allocated_descriptor_set(empty_set());
+        registry.record_descriptor_writes(
+            first,
+            &[
+                sampled(0, 1),
+                DescriptorWriteDesc {
+                    binding: 1,
+                    value: DescriptorValue::UniformBuffer(BufferHandle(1)),
+                },
+            ],
+        );
+        registry.record_descriptor_writes(second, &[sampled(0, 2)]);
+        // Rebinding replaces the earlier write to the binding.
+        registry.record_descriptor_writes(first, &[sampled(0, 3)]);
+
+        assert_eq!(registry.descriptor_writes(first).len(), 2);
+        let binding = |image| registry.descriptor_sets_binding(&[GpuImageHandle(image)]);
+        assert!(binding(1).is_empty());
+        assert_eq!(binding(2), [second]);
+        assert_eq!(binding(3), [first]);
+    }
+
+    #[test]
+    fn samplers_are_shared_only_by_equal_descriptions() {
+        use crate::pipeline::CompareOp;
+        use crate::sampler::{Filter, SamplerAddressMode};
+        use std::hash::{BuildHasher, RandomState};
+
+        let base = SamplerDesc {
+            mag_filter: Filter::Linear,
+            min_filter: Filter::Linear,
+            address_u: SamplerAddressMode::Repeat,
+            address_v: SamplerAddressMode::Repeat,
+            address_w: SamplerAddressMode::Repeat,
+            compare_enable: false,
+            compare_op: None,
+        };
+        // Each differs from `base` in exactly one field.
+        let variants = [
+            SamplerDesc {
+                mag_filter: Filter::Nearest,
+                ..base
+            },
+            SamplerDesc {
+                min_filter: Filter::Nearest,
+                ..base
+            },
+            SamplerDesc {
+                address_u: SamplerAddressMode::ClampToEdge,
+                ..base
+            },
+            SamplerDesc {
+                address_v: SamplerAddressMode::ClampToEdge,
+                ..base
+            },
+            SamplerDesc {
+                address_w: SamplerAddressMode::ClampToEdge,
+                ..base
+            },
+            SamplerDesc {
+                compare_enable: true,
+                ..base
+            },
+            SamplerDesc {
+                compare_op: Some(CompareOp::Less),
+                ..base
+            },
+        ];
+
+        let hasher = RandomState::new();
+        let copy = base;
+        assert_eq!(copy, base);
+        assert_eq!(hasher.hash_one(copy), hasher.hash_one(base));
+        for variant in &variants {
+            assert_ne!(*variant, base);
+        }
+
+        let mut registry = ResourceRegistry::new();
+        let handle = registry.register_sampler(base, vk::Sampler::null());
+        assert_eq!(registry.find_sampler(&copy).map(|h| h.0), Some(handle.0));
+        for (index, variant) in variants.iter().enumerate() {
+            assert!(registry.find_sampler(variant).is_none(), "variant {index}");
+            let created = registry.register_sampler(*variant, vk::Sampler::null());
+            assert_eq!(registry.find_sampler(variant).map(|h| h.0), Some(created.0));
+        }
+        let handles: std::collections::HashSet<usize> = std::iter::once(&base)
+            .chain(&variants)
+            .map(|desc| registry.find_sampler(desc).unwrap().0)
+            .collect();
+        assert_eq!(handles.len(), variants.len() + 1);
+    }
+
+    struct Released;
+
+    impl Destroyable for Released {
+        fn destroy(&self, _device: &ash::Device) {}
+    }
+
+    #[test]
+    fn queued_resources_wait_for_the_frame_they_were_released_in() {
+        let mut registry = ResourceRegistry::new();
+        registry.queue_destroy(Box::new(Released));
+        registry.frame_submitted();
+        registry.queue_destroy(Box::new(Released));
+        registry.queue_destroy(Box::new(Released));
+        registry.frame_submitted();
+
+        assert_eq!(registry.take_pending(0).len(), 1);
+        assert!(registry.take_pending(0).is_empty());
+        assert_eq!(registry.take_pending(1).len(), 2);
+    }
+
+    #[test]
+    fn bound_sets_remember_the_frame_that_bound_them() {
+        let mut registry = ResourceRegistry::new();
+        let set = registry.register_allocated_descriptor_set(empty_set());
+        assert_eq!(registry.descriptor_set_frame(set), None);
+        registry.frame_submitted();
+        registry.mark_descriptor_set_bound(set);
+        assert_eq!(registry.descriptor_set_frame(set), Some(1));
+    }
 }
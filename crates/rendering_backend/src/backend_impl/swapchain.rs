@@ -1,5 +1,6 @@
-use super::{constants, device, surface::SurfaceInfo};
+use super::{device, surface::SurfaceInfo};
 use ash::{khr, vk};
+use common::OutputMode;
 use std::ptr;
 
 pub struct SwapchainInfo {
@@ -10,6 +11,8 @@ pub struct SwapchainInfo {
     #[allow(dead_code)]
     pub(super) swapchain_image_format: vk::SurfaceFormatKHR,
     pub swapchain_extent: vk::Extent2D,
+    /// Encoding actually in use. SDR when the requested HDR mode is unsupported.
+    pub output_mode: OutputMode,
 }
 
 impl SwapchainInfo {
@@ -17,12 +20,22 @@ impl SwapchainInfo {
         instance: &ash::Instance,
         device_info: &device::DeviceInfo,
         surface_info: &SurfaceInfo,
+        vsync: bool,
+        output_mode: OutputMode,
+        window_extent: vk::Extent2D,
     ) -> SwapchainInfo {
-        let surface_format =
-            Self::choose_swapchain_format(&device_info.swapchain_support_details.formats);
-        let present_mode =
-            Self::choose_swap_present_mode(&device_info.swapchain_support_details.present_modes);
-        let extent = Self::chosse_swap_extent(&device_info.swapchain_support_details.capabilies);
+        let (surface_format, output_mode) = Self::choose_swapchain_format(
+            &device_info.swapchain_support_details.formats,
+            output_mode,
+        );
+        let present_mode = Self::choose_swap_present_mode(
+            &device_info.swapchain_support_details.present_modes,
+            vsync,
+        );
+        let extent = Self::chosse_swap_extent(
+            &device_info.swapchain_support_details.capabilies,
+            window_extent,
+        );
 
         let mut image_count = device_info
             .swapchain_support_details
@@ -99,45 +112,86 @@ impl SwapchainInfo {
             swapchain_images: swapchain_images.unwrap(),
             swapchain_image_format: surface_format,
             swapchain_extent: extent,
+            output_mode,
         }
     }
 
-    fn choose_swapchain_format(available_formats: &[vk::SurfaceFormatKHR]) -> vk::SurfaceFormatKHR {
+    /// Picks a surface format for `requested`, falling back to SDR when the surface
+    /// offers no matching HDR format and color space.
+    fn choose_swapchain_format(
+        available_formats: &[vk::SurfaceFormatKHR],
+        requested: OutputMode,
+    ) -> (vk::SurfaceFormatKHR, OutputMode) {
+        let hdr = match requested {
+            OutputMode::Sdr => None,
+            OutputMode::Hdr10 => available_formats.iter().find(|format| {
+                matches!(
+                    format.format,
+                    vk::Format::A2B10G10R10_UNORM_PACK32 | vk::Format::A2R10G10B10_UNORM_PACK32
+                ) && format.color_space == vk::ColorSpaceKHR::HDR10_ST2084_EXT
+            }),
+            OutputMode::ScRgb => available_formats.iter().find(|format| {
+                format.format == vk::Format::R16G16B16A16_SFLOAT
+                    && format.color_space == vk::ColorSpaceKHR::EXTENDED_SRGB_LINEAR_EXT
+            }),
+        };
+        if let Some(&format) = hdr {
+            return (format, requested);
+        }
+        if requested.is_hdr() {
+            eprintln!(
+                "{:?} output is not supported by this display, using SDR",
+                requested
+            );
+        }
+
         for &format in available_formats.iter() {
             if format.format == vk::Format::B8G8R8A8_SRGB
                 && format.color_space == vk::ColorSpaceKHR::SRGB_NONLINEAR
             {
-                return format;
+                return (format, OutputMode::Sdr);
             }
         }
 
-        *available_formats.first().unwrap()
+        (*available_formats.first().unwrap(), OutputMode::Sdr)
     }
 
+    /// FIFO is always available and locks presentation to the display refresh. Without
+    /// vsync, MAILBOX is preferred since it does not tear, then IMMEDIATE.
     fn choose_swap_present_mode(
         available_present_modes: &[vk::PresentModeKHR],
+        vsync: bool,
     ) -> vk::PresentModeKHR {
-        for &present_mode in available_present_modes.iter() {
-            if present_mode == vk::PresentModeKHR::MAILBOX {
-                return present_mode;
+        if vsync {
+            return vk::PresentModeKHR::FIFO;
+        }
+
+        for preferred in [vk::PresentModeKHR::MAILBOX, vk::PresentModeKHR::IMMEDIATE] {
+            if available_present_modes.contains(&preferred) {
+                return preferred;
             }
         }
 
         vk::PresentModeKHR::FIFO
     }
 
-    fn chosse_swap_extent(capabilities: &vk::SurfaceCapabilitiesKHR) -> vk::Extent2D {
+    /// Uses the surface's current extent, or the window size when the platform leaves
+    /// the extent up to the swapchain (reported as `u32::MAX`).
+    fn chosse_swap_extent(
+        capabilities: &vk::SurfaceCapabilitiesKHR,
+        window_extent: vk::Extent2D,
+    ) -> vk::Extent2D {
         if capabilities.current_extent.width != u32::MAX {
             capabilities.current_extent
         } else {
             vk::Extent2D {
                 width: num::clamp(
-                    constants::WINDOW_WIDTH,
+                    window_extent.width,
                     capabilities.min_image_extent.width,
                     capabilities.max_image_extent.width,
                 ),
                 height: num::clamp(
-                    constants::WINDOW_HEIGHT,
+                    window_extent.height,
                     capabilities.min_image_extent.height,
                     capabilities.max_image_extent.height,
                 ),
@@ -0,0 +1,145 @@
+use crate::sync::{GpuTimeline, TimelinePoint};
+use ash::prelude::VkResult;
+use ash::vk;
+use std::cell::Cell;
+
+/// A timeline semaphore plus the last value a submission was asked to signal.
+pub struct TimelineSemaphore {
+    pub semaphore: vk::Semaphore,
+    timeline: GpuTimeline,
+    last_submitted: Cell<u64>,
+}
+
+impl TimelineSemaphore {
+    fn new(device: &ash::Device, timeline: GpuTimeline) -> Self {
+        let mut type_info = vk::SemaphoreTypeCreateInfo::default()
+            .semaphore_type(vk::SemaphoreType::TIMELINE)
+            .initial_value(0);
+        let create_info = vk::SemaphoreCreateInfo::default().push_next(&mut type_info);
+        let semaphore = unsafe {
+            device
+                .create_semaphore(&create_info, None)
+                .expect("failed to create timeline semaphore")
+        };
+
+        Self {
+            semaphore,
+            timeline,
+            last_submitted: Cell::new(0),
+        }
+    }
+
+    /// The point the most recent submission on this timeline will signal.
+    pub fn last_submitted(&self) -> TimelinePoint {
+        self.point(self.last_submitted.get())
+    }
+
+    /// Submits `command_buffers` to `queue` and signals the next value on this timeline.
+    /// `waits` and `signals` may hold binary semaphores (swapchain) as well as timelines.
+    /// Errors, such as `ERROR_DEVICE_LOST`, are returned for the caller to report.
+    pub fn submit(
+        &self,
+        device: &ash::Device,
+        queue: vk::Queue,
+        command_buffers: &[vk::CommandBuffer],
+        waits: &[vk::SemaphoreSubmitInfo],
+        signals: &[vk::SemaphoreSubmitInfo],
+    ) -> VkResult<TimelinePoint> {
+        let value = self.last_submitted.get() + 1;
+
+        let command_buffer_infos = command_buffers
+            .iter()
+            .map(|&command_buffer| {
+                vk::CommandBufferSubmitInfo::default().command_buffer(command_buffer)
+            })
+            .collect::<Vec<_>>();
+        let mut signal_infos = signals.to_vec();
+        signal_infos.push(
+            vk::SemaphoreSubmitInfo::default()
+                .semaphore(self.semaphore)
+                .value(value)
+                .stage_mask(vk::PipelineStageFlags2::ALL_COMMANDS),
+        );
+
+        let submit_info = vk::SubmitInfo2::default()
+            .command_buffer_infos(&command_buffer_infos)
+            .wait_semaphore_infos(waits)
+            .signal_semaphore_infos(&signal_infos);
+
+        unsafe { device.queue_submit2(queue, &[submit_info], vk::Fence::null())? };
+
+        self.last_submitted.set(value);
+        Ok(self.point(value))
+    }
+
+    fn point(&self, value: u64) -> TimelinePoint {
+        TimelinePoint {
+            timeline: self.timeline,
+            value,
+        }
+    }
+}
+
+/// One timeline per queue the backend submits to.
+pub struct Timelines {
+    pub graphics: TimelineSemaphore,
+    pub compute: TimelineSemaphore,
+}
+
+impl Timelines {
+    pub fn new(device: &ash::Device) -> Self {
+        Self {
+            graphics: TimelineSemaphore::new(device, GpuTimeline::Graphics),
+            compute: TimelineSemaphore::new(device, GpuTimeline::Compute),
+        }
+    }
+
+    pub fn get(&self, timeline: GpuTimeline) -> &TimelineSemaphore {
+        match timeline {
+            GpuTimeline::Graphics => &self.graphics,
+            GpuTimeline::Compute => &self.compute,
+        }
+    }
+
+    /// A wait on `point` for a submission, blocking `stage` until it is reached.
+    pub fn wait_info(
+        &self,
+        point: TimelinePoint,
+        stage: vk::PipelineStageFlags2,
+    ) -> vk::SemaphoreSubmitInfo<'static> {
+        vk::SemaphoreSubmitInfo::default()
+            .semaphore(self.get(point.timeline).semaphore)
+            .value(point.value)
+            .stage_mask(stage)
+    }
+
+    /// Blocks the CPU until every point in `points` is reached.
+    pub fn wait(&self, device: &ash::Device, points: &[TimelinePoint]) -> VkResult<()> {
+        let semaphores = points
+            .iter()
+            .map(|point| self.get(point.timeline).semaphore)
+            .collect::<Vec<_>>();
+        let values = points.iter().map(|point| point.value).collect::<Vec<_>>();
+        let wait_info = vk::SemaphoreWaitInfo::default()
+            .semaphores(&semaphores)
+            .values(&values);
+
+        unsafe { device.wait_semaphores(&wait_info, u64::MAX) }
+    }
+
+    pub fn is_reached(&self, device: &ash::Device, point: TimelinePoint) -> bool {
+        let value = unsafe {
+            device
+                .get_semaphore_counter_value(self.get(point.timeline).semaphore)
+                .expect("Failed to read timeline semaphore")
+        };
+        value >= point.value
+    }
+
+    pub fn destroy(&self, device: &ash::Device) {
+        unsafe {
+            device.destroy_semaphore(self.graphics.semaphore, None);
+            device.destroy_semaphore(self.compute.semaphore, None);
+        }
+    }
+}
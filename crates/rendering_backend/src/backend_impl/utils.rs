@@ -68,25 +68,3 @@ pub fn create_texture_sampler(
             .expect("failed to create sampler")
     }
 }
-
-// pub fn load_texture<P>(path: P) -> ImageResource
-// where
-//     P: AsRef<Path>,
-// {
-//     let dyn_image = image::open(path).unwrap();
-//     let image_width = dyn_image.width();
-//     let image_height = dyn_image.height();
-//
-//     let image_data = match &dyn_image {
-//         image::DynamicImage::ImageLuma8(_) | image::DynamicImage::ImageRgb8(_) => {
-//             dyn_image.to_rgba8().into_raw()
-//         }
-//         _ => vec![],
-//     };
-//
-//     ImageResource {
-//         image_data,
-//         width: image_width,
-//         height: image_height,
-//     }
-// }
@@ -1,101 +1,259 @@
 use super::{device::DeviceInfo, image_util, surface::SurfaceInfo, swapchain::SwapchainInfo};
 
+use crate::backend_impl::diagnostics::GpuDiagnostics;
+use crate::backend_impl::renderdoc::RenderDoc;
+
 use crate::backend_impl::allocated_buffer::AllocatedBuffer;
+use crate::backend_impl::barrier::{self, ImageBarrier};
 use crate::backend_impl::descriptor_info::{
     AllocatedDescriptorSet, DescriptorLayoutInfo, DescriptorPoolChunk,
 };
 use crate::backend_impl::image_util::AllocatedImage;
 use crate::buffer::{BufferDesc, BufferHandle, BufferUsageFlags};
+use crate::capabilities::DeviceCapabilities;
 use crate::descriptor::{
     DescriptorLayoutDesc, DescriptorLayoutHandle, DescriptorSetHandle, DescriptorValue,
     DescriptorWriteDesc, ShaderStage,
 };
-use crate::image::{GpuImageHandle, ImageDesc};
+use crate::image::{ClearValue, GpuImageHandle, ImageDesc};
 
-use crate::backend_impl::pipeline_info::PipelineInfo;
+use crate::backend_impl::compile_queue::CompileQueue;
+use crate::backend_impl::pipeline_info::{GraphicsPipelineBuild, PipelineInfo};
 use crate::backend_impl::resource_registry::ResourceRegistry;
-use crate::memory::MemoryHint;
-use crate::pipeline::{PipelineDesc, PipelineHandle};
-use crate::sampler::{SamplerDesc, SamplerHandle};
+use crate::memory::{GpuMemoryStats, MemoryHint};
+use crate::pipeline::{
+    ComputePipelineDesc, PendingPipelineHandle, PipelineCompileError, PipelineDesc,
+    PipelineHandle,
+};
+use crate::sampler::{Filter, SamplerDesc, SamplerHandle};
+use crate::sync::{ResourceState, TimelinePoint};
+use ash::prelude::VkResult;
 use ash::vk::MemoryPropertyFlags;
 use ash::vk::{self};
 use ash::Instance;
-use std::{error::Error, ffi::CString, mem, ptr, slice};
+use common::{crash_context, Color, OutputMode};
+use std::{
+    error::Error,
+    ffi::{CStr, CString},
+    mem, ptr, slice,
+    time::Duration,
+};
 use winit::{raw_window_handle::HasDisplayHandle, window::Window};
 
+/// Longest `wait_for_presented_frame` waits on the display, in nanoseconds. Presents can
+/// stall indefinitely while the window is hidden.
+const PRESENT_WAIT_TIMEOUT: u64 = 100_000_000;
+
 pub struct VulkanBackend {
     _entry: ash::Entry,
     instance: Instance,
     device_info: DeviceInfo,
-    // Kept to own the Vulkan surface for its lifetime; not read after construction.
-    _surface_info: SurfaceInfo,
+    surface_info: SurfaceInfo,
     resource_registry: ResourceRegistry,
     swapchain_info: SwapchainInfo,
-    render_semaphore: vk::Semaphore,
-    swapchain_semaphore: vk::Semaphore,
-    render_fence: vk::Fence,
+    /// One per swapchain image, signalled by the frame that renders to it; present waits
+    /// on it. A present's wait is only known to be done once the image is acquired
+    /// again, so these cannot be reused per frame slot.
+    render_semaphores: Vec<vk::Semaphore>,
+    /// One per frame in flight.
+    frames: Vec<FrameSlot>,
+    /// Slot of the frame being recorded.
+    frame_slot: usize,
+    /// Set once the frame being recorded has waited for its slot.
+    frame_slot_ready: bool,
+    /// Frames submitted so far, which is also the number of the frame being recorded.
+    frames_submitted: u64,
+    /// Command buffer of the frame being recorded, from its slot.
     command_buffer: vk::CommandBuffer,
+    /// Vertex buffers bound in the frame command buffer, by binding, and the index
+    /// buffer, so draws from shared buffers skip redundant binds.
+    bound_vertex_buffers: Vec<vk::Buffer>,
+    bound_index_buffer: vk::Buffer,
+    /// Extra points the next frame submission waits on, from `wait_in_next_frame`.
+    frame_waits: Vec<TimelinePoint>,
+    compute: ComputeContext,
+    diagnostics: GpuDiagnostics,
+    renderdoc: Option<RenderDoc>,
+    capture: CaptureState,
     current_swapchain_image: u32,
+    vsync: bool,
+    /// Encoding requested with `set_output_mode`; the swapchain may have fallen back.
+    output_mode: OutputMode,
+    /// Set when acquire or present reports the swapchain no longer matches the surface.
+    swapchain_out_of_date: bool,
+    /// `VK_KHR_present_wait`, when the device supports it.
+    present_wait: Option<ash::khr::present_wait::Device>,
+    /// Present ID of the last frame presented to the current swapchain, 0 before the
+    /// first. IDs restart with each swapchain.
+    last_present_id: u64,
+    /// Graphics pipelines from `compile_graphics_pipeline`, by `PendingPipelineHandle`.
+    pipeline_compiles: CompileQueue<GraphicsPipelineBuild, PipelineInfo>,
+}
+
+/// Options fixed at backend creation.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BackendConfig {
+    /// FIFO presentation; otherwise MAILBOX is preferred.
+    pub vsync: bool,
+    /// Run compute work on a dedicated queue when the device has one.
+    pub async_compute: bool,
+    /// Enable NV checkpoints or AMD buffer markers around passes, so a device-lost
+    /// report can say which pass the GPU was in. Debug labels are emitted regardless.
+    /// Keeps one frame in flight, so the report's passes are those of the lost frame.
+    pub gpu_diagnostics: bool,
+    /// Frames the CPU may record while the GPU is still rendering earlier ones; 0
+    /// counts as 1. Each needs its own copy of the resources the CPU writes per frame,
+    /// indexed by [`VulkanBackend::frame_slot`].
+    pub frames_in_flight: usize,
+}
+
+/// What one frame in flight records into. Frames take the slots in turn, and a slot is
+/// reused once the GPU has finished the frame that last used it.
+struct FrameSlot {
+    command_buffer: vk::CommandBuffer,
+    compute_command_buffer: vk::CommandBuffer,
+    /// Signalled once the swapchain image is acquired; the frame submission waits on it.
+    acquire_semaphore: vk::Semaphore,
+    /// Number of the last frame submitted from this slot and the point it signals.
+    submitted: Option<(u64, TimelinePoint)>,
+    /// The last compute submission recorded in this slot.
+    compute_submitted: Option<TimelinePoint>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CaptureState {
+    Idle,
+    /// Requested; starts with the next `begin_frame` that acquires an image.
+    Pending,
+    Capturing,
+}
+
+/// Compute recording state. Work recorded between `begin_compute` and `submit_compute`
+/// goes to the compute queue and signals the compute timeline.
+struct ComputeContext {
+    /// Compute command buffer of the current frame slot, while recording.
+    command_buffer: vk::CommandBuffer,
+    recording: bool,
 }
 
 impl VulkanBackend {
-    pub fn new(window: &Window) -> Result<Self, Box<dyn Error>> {
+    /// Creates the instance, device and swapchain for `window`.
+    pub fn new(window: &Window, config: BackendConfig) -> Result<Self, Box<dyn Error>> {
         let entry = unsafe { ash::Entry::load()? };
-        let instance = Self::create_instance(&entry, window);
+        let debug_utils = Self::supports_instance_extension(&entry, vk::EXT_DEBUG_UTILS_NAME);
+        let hdr_color_spaces =
+            Self::supports_instance_extension(&entry, vk::EXT_SWAPCHAIN_COLORSPACE_NAME);
+        let instance = Self::create_instance(&entry, window, debug_utils, hdr_color_spaces);
         let surface_info = SurfaceInfo::new(&entry, &instance, window);
-        let device_info = DeviceInfo::new(&instance, &surface_info);
-        let swapchain_info = SwapchainInfo::new(&instance, &device_info, &surface_info);
-        let command_buffer = Self::create_command_buffers(&device_info);
-        let (swapchain_semaphore, render_semaphore, render_fence) =
-            Self::create_sync_objects(&device_info.logical_device);
+        let device_info = DeviceInfo::new(&instance, &surface_info, &config);
+        let size = window.inner_size();
+        let swapchain_info = SwapchainInfo::new(
+            &instance,
+            &device_info,
+            &surface_info,
+            config.vsync,
+            OutputMode::Sdr,
+            vk::Extent2D {
+                width: size.width,
+                height: size.height,
+            },
+        );
+        let frames_in_flight = if config.gpu_diagnostics {
+            1
+        } else {
+            config.frames_in_flight.max(1)
+        };
+        let frames = (0..frames_in_flight)
+            .map(|_| FrameSlot {
+                command_buffer: Self::create_command_buffers(
+                    &device_info,
+                    device_info.command_pool,
+                ),
+                compute_command_buffer: Self::create_command_buffers(
+                    &device_info,
+                    device_info.compute_command_pool,
+                ),
+                acquire_semaphore: Self::create_semaphore(&device_info.logical_device),
+                submitted: None,
+                compute_submitted: None,
+            })
+            .collect::<Vec<_>>();
+        let render_semaphores =
+            Self::create_render_semaphores(&device_info.logical_device, &swapchain_info);
+        let compute = ComputeContext {
+            command_buffer: frames[0].compute_command_buffer,
+            recording: false,
+        };
+        let diagnostics =
+            GpuDiagnostics::new(&instance, &device_info, debug_utils, frames_in_flight);
+        let present_wait = device_info.capabilities.present_wait.then(|| {
+            ash::khr::present_wait::Device::new(&instance, &device_info.logical_device)
+        });
+        let renderdoc = RenderDoc::attach();
+        if renderdoc.is_some() {
+            println!("RenderDoc attached, frame captures available.");
+        }
 
         Ok(Self {
             _entry: entry,
             instance,
             device_info,
-            _surface_info: surface_info,
+            surface_info,
             swapchain_info,
+            render_semaphores,
             resource_registry: ResourceRegistry::new(),
-            command_buffer,
-            swapchain_semaphore,
-            render_semaphore,
-            render_fence,
+            command_buffer: frames[0].command_buffer,
+            frames,
+            frame_slot: 0,
+            frame_slot_ready: false,
+            frames_submitted: 0,
+            bound_vertex_buffers: Vec::new(),
+            bound_index_buffer: vk::Buffer::null(),
+            frame_waits: Vec::new(),
+            compute,
+            diagnostics,
+            renderdoc,
+            capture: CaptureState::Idle,
             current_swapchain_image: 0,
+            vsync: config.vsync,
+            output_mode: OutputMode::Sdr,
+            swapchain_out_of_date: false,
+            present_wait,
+            last_present_id: 0,
+            pipeline_compiles: CompileQueue::new(
+                "pipeline compile",
+                GraphicsPipelineBuild::compile,
+            ),
         })
     }
 
-    fn create_sync_objects(device: &ash::Device) -> (vk::Semaphore, vk::Semaphore, vk::Fence) {
-        let semaphore_create_info = vk::SemaphoreCreateInfo {
-            s_type: vk::StructureType::SEMAPHORE_CREATE_INFO,
-            ..Default::default()
-        };
-
-        let fence_create_info = vk::FenceCreateInfo {
-            s_type: vk::StructureType::FENCE_CREATE_INFO,
-            flags: vk::FenceCreateFlags::SIGNALED,
-            ..Default::default()
-        };
-
+    /// Binary semaphore for swapchain acquire or present, which cannot use timelines.
+    /// Frame pacing goes through the graphics timeline.
+    fn create_semaphore(device: &ash::Device) -> vk::Semaphore {
         unsafe {
-            let swapchain_semaphore = device
-                .create_semaphore(&semaphore_create_info, None)
-                .expect("failed to create semaphore for swapchain");
-
-            let render_semaphore = device
-                .create_semaphore(&semaphore_create_info, None)
-                .expect("failed to create semaphore for render semaphore");
-
-            let render_fence = device
-                .create_fence(&fence_create_info, None)
-                .expect("failed to create fence for render fence");
-
-            (swapchain_semaphore, render_semaphore, render_fence)
+            device
+                .create_semaphore(&vk::SemaphoreCreateInfo::default(), None)
+                .expect("failed to create swapchain semaphore")
         }
     }
 
-    fn create_command_buffers(device_info: &DeviceInfo) -> vk::CommandBuffer {
+    fn create_render_semaphores(
+        device: &ash::Device,
+        swapchain_info: &SwapchainInfo,
+    ) -> Vec<vk::Semaphore> {
+        swapchain_info
+            .swapchain_images
+            .iter()
+            .map(|_| Self::create_semaphore(device))
+            .collect()
+    }
+
+    fn create_command_buffers(
+        device_info: &DeviceInfo,
+        command_pool: vk::CommandPool,
+    ) -> vk::CommandBuffer {
         let command_buffer_alloc_info = vk::CommandBufferAllocateInfo::default()
-            .command_pool(device_info.command_pool)
+            .command_pool(command_pool)
             .level(vk::CommandBufferLevel::PRIMARY)
             .command_buffer_count(1);
 
@@ -124,12 +282,52 @@ impl VulkanBackend {
         let pipeline = PipelineInfo::create_pipeline_from_desc(
             &self.device_info,
             desc,
-            &self.resource_registry,
+            &mut self.resource_registry,
         );
 
         self.resource_registry.register_pipeline(pipeline)
     }
 
+    /// Queues a graphics pipeline for creation on the pipeline compile thread, so the
+    /// driver's shader compilation doesn't stall the frame. Pipelines compile one at a
+    /// time in the order queued. The layout is created right away; poll
+    /// [`Self::compiled_pipeline`] for the pipeline.
+    pub fn compile_graphics_pipeline(&mut self, desc: PipelineDesc) -> PendingPipelineHandle {
+        let build = GraphicsPipelineBuild::new(&self.device_info, desc, &mut self.resource_registry);
+        PendingPipelineHandle(self.pipeline_compiles.submit(build))
+    }
+
+    /// The outcome of `pending` once its compile is done, returned only once: `pending`
+    /// is then released and may be handed out again for a later compile.
+    pub fn compiled_pipeline(
+        &mut self,
+        pending: PendingPipelineHandle,
+    ) -> Option<Result<PipelineHandle, PipelineCompileError>> {
+        let result = self.pipeline_compiles.take(pending.0)?;
+        Some(result.map(|pipeline| self.resource_registry.register_pipeline(pipeline)))
+    }
+
+    pub fn create_compute_pipeline(&mut self, desc: ComputePipelineDesc) -> PipelineHandle {
+        let pipeline = PipelineInfo::create_compute_pipeline_from_desc(
+            &self.device_info,
+            desc,
+            &mut self.resource_registry,
+        );
+
+        self.resource_registry.register_pipeline(pipeline)
+    }
+
+    /// True if compute submissions run on their own queue and can overlap graphics work.
+    /// When false they share the graphics queue, with the same API and ordering.
+    pub fn has_async_compute(&self) -> bool {
+        self.device_info.queue_info.has_async_compute()
+    }
+
+    /// Optional features the device supports and that were enabled on it.
+    pub fn capabilities(&self) -> DeviceCapabilities {
+        self.device_info.capabilities
+    }
+
     pub fn allocate_descriptor_set(
         &mut self,
         layout_handle: DescriptorLayoutHandle,
@@ -156,14 +354,20 @@ impl VulkanBackend {
             .register_allocated_descriptor_set(allocated_descriptor)
     }
 
+    /// Returns the layout for `layout_desc`, creating it on first use. Equal descriptions
+    /// share a handle, so sets allocated for one fit every pipeline built with the other.
     pub fn create_descriptor_layout(
         &mut self,
         layout_desc: DescriptorLayoutDesc,
     ) -> DescriptorLayoutHandle {
-        let layout_info = DescriptorLayoutInfo::new(&self.device_info, layout_desc);
+        let layout_desc = layout_desc.normalized();
+        if let Some(handle) = self.resource_registry.find_descriptor_layout(&layout_desc) {
+            return handle;
+        }
+        let layout_info = DescriptorLayoutInfo::new(&self.device_info, layout_desc.clone());
 
         self.resource_registry
-            .register_descriptor_layout(layout_info)
+            .register_descriptor_layout(layout_desc, layout_info)
     }
 
     pub fn create_image(&mut self, image_desc: ImageDesc) -> GpuImageHandle {
@@ -177,8 +381,58 @@ impl VulkanBackend {
         self.resource_registry.register_image(image)
     }
 
+    /// Replaces the image behind `image_handle` with a freshly allocated one.
+    /// The handle stays valid; the old image is freed once the frames in flight are done.
+    /// Descriptor sets that reference the handle must be rewritten by the caller, e.g.
+    /// with [`refresh_descriptor_sets`](Self::refresh_descriptor_sets).
+    pub fn recreate_image(&mut self, image_handle: GpuImageHandle, image_desc: ImageDesc) {
+        let image = AllocatedImage::new(
+            image_desc,
+            &self.device_info,
+            &self.instance,
+            MemoryPropertyFlags::DEVICE_LOCAL,
+        );
+
+        self.resource_registry.replace_image(image_handle, image);
+    }
+
+    /// Frees the image once the GPU is done with the frame being recorded. `image_handle`
+    /// must not be used again.
+    pub fn release_image(&mut self, image_handle: GpuImageHandle) {
+        self.resource_registry.release_image(image_handle);
+    }
+
+    /// Frees the buffer once the GPU is done with the frame being recorded.
+    /// `buffer_handle` must not be used again.
+    pub fn release_buffer(&mut self, buffer_handle: BufferHandle) {
+        self.resource_registry.release_buffer(buffer_handle);
+    }
+
+    /// Frees the descriptor set once the GPU is done with the frame being recorded. Call
+    /// between frames; `set_handle` must not be used again.
+    pub fn release_descriptor_set(&mut self, set_handle: DescriptorSetHandle) {
+        self.resource_registry.release_descriptor_set(set_handle);
+    }
+
+    /// Blocks until the GPU has finished all submitted work. Use before mutating
+    /// resources that an in-flight frame may still reference.
+    pub fn wait_idle(&self) {
+        unsafe {
+            self.device_info
+                .logical_device
+                .device_wait_idle()
+                .expect("device_wait_idle failed");
+        }
+    }
+
+    /// Replaces the contents of a sampled image. `data` holds every mip level, largest
+    /// first and tightly packed; block-compressed levels are whole 4x4 blocks. Waits for
+    /// the frames in flight first, unless the image has never been used.
     pub fn update_image_data(&mut self, image_handle: GpuImageHandle, data: &[u8]) {
-        let image = &self.resource_registry.images[image_handle.0];
+        if self.resource_registry.images[image_handle.0].state != ResourceState::Undefined {
+            self.wait_for(&[self.last_frame_point()]);
+        }
+
         let buffer_desc = BufferDesc {
             usage: BufferUsageFlags::TRANSFER_SRC,
             size: data.len(),
@@ -187,32 +441,27 @@ impl VulkanBackend {
         let buffer =
             AllocatedBuffer::new(&self.device_info, &self.instance, buffer_desc, Some(data));
 
+        // The old contents are overwritten, so they need not be preserved.
+        self.resource_registry.images[image_handle.0].state = ResourceState::Undefined;
         let command_buffer = self.begin_single_time_command();
-
-        image_util::transition_image_layout(
-            &self.device_info,
-            &command_buffer,
-            image.image,
-            vk::ImageLayout::UNDEFINED,
-            vk::ImageLayout::TRANSFER_DST_OPTIMAL,
-            false,
+        self.resource_registry.images[image_handle.0].transition(
+            &self.device_info.logical_device,
+            command_buffer,
+            ResourceState::TransferDst,
         );
-
         self.end_single_time_command(command_buffer);
 
-        self.copy_buffer_to_image(buffer.buffer, image);
+        self.copy_buffer_to_image(
+            buffer.buffer,
+            &self.resource_registry.images[image_handle.0],
+        );
 
         let command_buffer = self.begin_single_time_command();
-
-        image_util::transition_image_layout(
-            &self.device_info,
-            &command_buffer,
-            image.image,
-            vk::ImageLayout::TRANSFER_DST_OPTIMAL,
-            vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
-            false,
+        self.resource_registry.images[image_handle.0].transition(
+            &self.device_info.logical_device,
+            command_buffer,
+            ResourceState::FragmentShaderRead,
         );
-
         self.end_single_time_command(command_buffer);
 
         unsafe {
@@ -242,14 +491,57 @@ impl VulkanBackend {
         buffer.update_buffer(data);
     }
 
+    /// Writes `data` starting at element `first` of a host-visible buffer, leaving the rest
+    /// of the buffer untouched.
+    pub fn update_buffer_at<T>(&mut self, buffer_handle: BufferHandle, first: usize, data: &[T]) {
+        let buffer = &mut self.resource_registry.buffers[buffer_handle.0];
+
+        buffer.update_buffer_at(first, data);
+    }
+
+    /// Copies `data` to element `first` of a device-local buffer through a staging
+    /// buffer, blocking until the copy has finished. The buffer needs `TRANSFER_DST`.
+    pub fn upload_buffer_at<T>(&mut self, buffer_handle: BufferHandle, first: usize, data: &[T]) {
+        let buffer = &self.resource_registry.buffers[buffer_handle.0];
+
+        buffer.upload_at(&self.device_info, &self.instance, first, data);
+    }
+
     pub fn buffer_size(&self, buffer_handle: BufferHandle) -> usize {
         self.resource_registry.buffers[buffer_handle.0].buffer_size as usize
     }
 
+    /// Width and height of an image in texels.
+    pub fn image_size(&self, image_handle: GpuImageHandle) -> (u32, u32) {
+        let extent = self.resource_registry.images[image_handle.0].image_extent;
+        (extent.width, extent.height)
+    }
+
+    /// Returns the sampler for `desc`, creating it on first use. Equal descriptions share
+    /// one sampler, since devices cap how many can exist.
     pub fn create_sampler(&mut self, desc: SamplerDesc) -> SamplerHandle {
+        if let Some(handle) = self.resource_registry.find_sampler(&desc) {
+            return handle;
+        }
+        let count = self.resource_registry.samplers.len() as u32 + 1;
+        let limit = self.device_info.max_sampler_count;
+        if count == limit.saturating_mul(3) / 4 || count == limit {
+            eprintln!(
+                "{} distinct samplers created; the device allows at most {}",
+                count, limit
+            );
+        }
+
+        // Mips are blended like texels and are all available to sampling.
+        let mipmap_mode = match desc.min_filter {
+            Filter::Nearest => vk::SamplerMipmapMode::NEAREST,
+            Filter::Linear => vk::SamplerMipmapMode::LINEAR,
+        };
         let mut sampler_info = vk::SamplerCreateInfo::default()
             .mag_filter(desc.mag_filter.into())
             .min_filter(desc.min_filter.into())
+            .mipmap_mode(mipmap_mode)
+            .max_lod(vk::LOD_CLAMP_NONE)
             .address_mode_u(desc.address_u.into())
             .address_mode_v(desc.address_v.into())
             .address_mode_w(desc.address_w.into())
@@ -269,40 +561,141 @@ impl VulkanBackend {
                 .expect("Failed to create sampler")
         };
 
-        self.resource_registry.register_sampler(sampler)
+        self.resource_registry.register_sampler(desc, sampler)
     }
 
-    pub fn begin_frame(&mut self) {
-        let begin_info = vk::CommandBufferBeginInfo::default();
-        unsafe {
-            self.device_info
-                .logical_device
-                .wait_for_fences(&[self.render_fence], true, u64::MAX)
-                .expect("Failed to wait for fences");
-            self.device_info
-                .logical_device
-                .reset_fences(&[self.render_fence])
-                .expect("Failed to reset fences");
+    /// Requests a display encoding. The swapchain is recreated before the next frame if
+    /// it changed; check `output_mode` afterwards for the encoding actually in use.
+    pub fn set_output_mode(&mut self, output_mode: OutputMode) {
+        if self.output_mode != output_mode {
+            self.output_mode = output_mode;
+            self.swapchain_out_of_date = true;
         }
+    }
 
-        // GPU is idle after the fence wait — safe to free any queued resources.
-        self.resource_registry
-            .flush_pending(&self.device_info.logical_device);
+    /// Encoding of the current swapchain images.
+    pub fn output_mode(&self) -> OutputMode {
+        self.swapchain_info.output_mode
+    }
+
+    /// True when the swapchain must be recreated before the next frame, e.g. after a resize.
+    pub fn swapchain_out_of_date(&self) -> bool {
+        self.swapchain_out_of_date
+    }
+
+    /// Rebuilds the swapchain for the surface's current size. Returns false, leaving the
+    /// old swapchain in place, while the surface has a zero extent (minimized window).
+    pub fn recreate_swapchain(&mut self, width: u32, height: u32) -> bool {
+        if width == 0 || height == 0 {
+            return false;
+        }
+
+        self.wait_idle();
+        self.device_info
+            .update_swapchain_capabilities(&self.surface_info);
+        let current = self
+            .device_info
+            .swapchain_support_details
+            .capabilies
+            .current_extent;
+        if current.width == 0 || current.height == 0 {
+            return false;
+        }
 
         unsafe {
-            let (swapchain_image_index, _) = self
-                .swapchain_info
+            self.swapchain_info
                 .swapchain_device
-                .acquire_next_image(
-                    self.swapchain_info.swapchain,
-                    u64::MAX,
-                    self.swapchain_semaphore,
-                    vk::Fence::null(),
-                )
-                .expect("failed to acquire swapchain image");
+                .destroy_swapchain(self.swapchain_info.swapchain, None);
+        }
+        self.swapchain_info = SwapchainInfo::new(
+            &self.instance,
+            &self.device_info,
+            &self.surface_info,
+            self.vsync,
+            self.output_mode,
+            vk::Extent2D { width, height },
+        );
+        // Idle, so no present still waits on the old semaphores.
+        for semaphore in self.render_semaphores.drain(..) {
+            unsafe {
+                self.device_info
+                    .logical_device
+                    .destroy_semaphore(semaphore, None)
+            };
+        }
+        self.render_semaphores =
+            Self::create_render_semaphores(&self.device_info.logical_device, &self.swapchain_info);
+        self.swapchain_out_of_date = false;
+        self.last_present_id = 0;
+        true
+    }
 
-            self.current_swapchain_image = swapchain_image_index;
+    /// Frames the CPU may record ahead of the GPU, from `BackendConfig::frames_in_flight`.
+    pub fn frames_in_flight(&self) -> usize {
+        self.frames.len()
+    }
 
+    /// Slot of the frame being recorded, below `frames_in_flight`. Resources the CPU
+    /// writes every frame keep one copy per slot, indexed by it. The first call of a
+    /// frame, which may come before `begin_frame`, waits until the GPU has finished the
+    /// frame that last used the slot, so its copies can be written.
+    pub fn frame_slot(&mut self) -> usize {
+        if !self.frame_slot_ready {
+            let slot = &self.frames[self.frame_slot];
+            if let Some((frame, point)) = slot.submitted {
+                let waited = self
+                    .device_info
+                    .timelines
+                    .wait(&self.device_info.logical_device, &[point]);
+                self.check_device(waited, "wait for frame slot");
+                // Frames finish in order, so nothing queued up to this one is used anymore.
+                self.resource_registry
+                    .flush_pending(&self.device_info.logical_device, frame);
+            }
+            self.frame_slot_ready = true;
+        }
+        self.frame_slot
+    }
+
+    /// Waits for the frame slot, acquires a swapchain image and begins recording.
+    /// Returns false if the swapchain is out of date; skip the frame and recreate it.
+    pub fn begin_frame(&mut self) -> bool {
+        let begin_info = vk::CommandBufferBeginInfo::default();
+        let slot = self.frame_slot();
+        self.diagnostics
+            .begin_frame(&self.device_info.logical_device, slot);
+        self.resource_registry.end_frame_accounting();
+        self.command_buffer = self.frames[slot].command_buffer;
+
+        let acquired = unsafe {
+            self.swapchain_info.swapchain_device.acquire_next_image(
+                self.swapchain_info.swapchain,
+                u64::MAX,
+                self.frames[slot].acquire_semaphore,
+                vk::Fence::null(),
+            )
+        };
+        let swapchain_image_index = match acquired {
+            Ok((index, suboptimal)) => {
+                self.swapchain_out_of_date |= suboptimal;
+                index
+            }
+            Err(vk::Result::ERROR_OUT_OF_DATE_KHR) => {
+                self.swapchain_out_of_date = true;
+                return false;
+            }
+            Err(e) => self.check_device(Err(e), "acquire swapchain image"),
+        };
+        self.current_swapchain_image = swapchain_image_index;
+
+        if self.capture == CaptureState::Pending {
+            if let Some(renderdoc) = &self.renderdoc {
+                renderdoc.start_frame_capture(self.instance.handle());
+                self.capture = CaptureState::Capturing;
+            }
+        }
+
+        unsafe {
             self.device_info
                 .logical_device
                 .reset_command_buffer(self.command_buffer, vk::CommandBufferResetFlags::empty())
@@ -313,28 +706,42 @@ impl VulkanBackend {
                 .begin_command_buffer(self.command_buffer, &begin_info)
                 .expect("Begin command buffer failed");
         }
+        self.bound_vertex_buffers.clear();
+        self.bound_index_buffer = vk::Buffer::null();
+        true
     }
 
-    pub fn end_frame(&mut self, final_image_handle: GpuImageHandle) {
-        let final_image = &self.resource_registry.images[final_image_handle.0];
+    /// Records the copy to the swapchain, submits and presents. Returns the graphics
+    /// timeline point that is reached when the frame's GPU work has finished.
+    pub fn end_frame(&mut self, final_image_handle: GpuImageHandle) -> TimelinePoint {
         let swapchain_image =
             self.swapchain_info.swapchain_images[self.current_swapchain_image as usize];
-        image_util::transition_image_layout(
-            &self.device_info,
-            &self.command_buffer,
-            swapchain_image,
-            vk::ImageLayout::UNDEFINED,
-            vk::ImageLayout::TRANSFER_DST_OPTIMAL,
-            false,
+        self.diagnostics.begin_pass(
+            &self.device_info.logical_device,
+            self.command_buffer,
+            "Copy to swapchain",
         );
-        image_util::transition_image_layout(
-            &self.device_info,
-            &self.command_buffer,
-            final_image.image,
-            final_image.image_layout,
-            vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
-            false,
+        let final_image = &mut self.resource_registry.images[final_image_handle.0];
+        barrier::record_image_barriers(
+            &self.device_info.logical_device,
+            self.command_buffer,
+            &[
+                ImageBarrier::new(
+                    swapchain_image,
+                    vk::ImageAspectFlags::COLOR,
+                    ResourceState::Present,
+                    ResourceState::TransferDst,
+                ),
+                ImageBarrier::new(
+                    final_image.image,
+                    final_image.aspect,
+                    final_image.state,
+                    ResourceState::TransferSrc,
+                ),
+            ],
         );
+        // Left as a copy source; the next pass rendering to it transitions it back.
+        final_image.state = ResourceState::TransferSrc;
 
         let final_extend = vk::Extent2D {
             height: final_image.image_extent.height,
@@ -350,23 +757,18 @@ impl VulkanBackend {
             self.swapchain_info.swapchain_extent,
         );
 
-        image_util::transition_image_layout(
-            &self.device_info,
-            &self.command_buffer,
-            final_image.image,
-            vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
-            vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
-            false,
-        );
-
-        image_util::transition_image_layout(
-            &self.device_info,
-            &self.command_buffer,
-            swapchain_image,
-            vk::ImageLayout::TRANSFER_DST_OPTIMAL,
-            vk::ImageLayout::PRESENT_SRC_KHR,
-            false,
+        barrier::record_image_barriers(
+            &self.device_info.logical_device,
+            self.command_buffer,
+            &[ImageBarrier::new(
+                swapchain_image,
+                vk::ImageAspectFlags::COLOR,
+                ResourceState::TransferDst,
+                ResourceState::Present,
+            )],
         );
+        self.diagnostics
+            .end_pass(&self.device_info.logical_device, self.command_buffer);
 
         unsafe {
             self.device_info
@@ -375,47 +777,58 @@ impl VulkanBackend {
                 .expect("End command buffer failed");
         };
 
-        let command_buffer_submit_info = [vk::CommandBufferSubmitInfo::default()
-            .command_buffer(self.command_buffer)
-            .device_mask(0)];
-
-        let wait_info = [vk::SemaphoreSubmitInfo::default()
-            .semaphore(self.swapchain_semaphore)
-            .stage_mask(vk::PipelineStageFlags2::COLOR_ATTACHMENT_OUTPUT_KHR)
-            .device_index(0)
-            .value(1)];
+        let timelines = &self.device_info.timelines;
+        let slot = &self.frames[self.frame_slot];
+        // Compute submitted this frame is consumed by this frame; waiting on an already
+        // reached point is free.
+        let mut wait_info = vec![
+            vk::SemaphoreSubmitInfo::default()
+                .semaphore(slot.acquire_semaphore)
+                .stage_mask(vk::PipelineStageFlags2::COLOR_ATTACHMENT_OUTPUT_KHR),
+            timelines.wait_info(
+                timelines.compute.last_submitted(),
+                vk::PipelineStageFlags2::ALL_COMMANDS,
+            ),
+        ];
+        wait_info.extend(
+            self.frame_waits
+                .drain(..)
+                .map(|point| timelines.wait_info(point, vk::PipelineStageFlags2::ALL_COMMANDS)),
+        );
 
+        let render_semaphore = self.render_semaphores[self.current_swapchain_image as usize];
         let signal_info = [vk::SemaphoreSubmitInfo::default()
-            .semaphore(self.render_semaphore)
-            .stage_mask(vk::PipelineStageFlags2::ALL_GRAPHICS)
-            .device_index(0)
-            .value(1)];
-
-        let submit_info = vk::SubmitInfo2::default()
-            .command_buffer_infos(&command_buffer_submit_info)
-            .wait_semaphore_infos(&wait_info)
-            .signal_semaphore_infos(&signal_info);
+            .semaphore(render_semaphore)
+            .stage_mask(vk::PipelineStageFlags2::ALL_GRAPHICS)];
 
-        //let t = Instant::now();
-        unsafe {
-            self.device_info
-                .logical_device
-                .queue_submit2(
-                    self.device_info.queue_info.graphics_queue,
-                    &[submit_info],
-                    self.render_fence,
-                )
-                .expect("Unable to submit draw command buffer");
-        }
+        let submitted = timelines.graphics.submit(
+            &self.device_info.logical_device,
+            self.device_info.queue_info.graphics_queue,
+            &[self.command_buffer],
+            &wait_info,
+            &signal_info,
+        );
+        let frame_done = self.check_device(submitted, "submit frame");
+        self.diagnostics.frame_submitted();
+        let render_semaphores = [render_semaphore];
+        self.frames[self.frame_slot].submitted = Some((self.frames_submitted, frame_done));
+        self.frames_submitted += 1;
+        self.resource_registry.frame_submitted();
+        self.frame_slot = (self.frame_slot + 1) % self.frames.len();
+        self.frame_slot_ready = false;
 
-        let render_semaphores = [self.render_semaphore];
         let swapchains = [self.swapchain_info.swapchain];
         let image_indices = [self.current_swapchain_image];
 
-        let present_info = vk::PresentInfoKHR::default()
+        let present_ids = [self.last_present_id + 1];
+        let mut present_id = vk::PresentIdKHR::default().present_ids(&present_ids);
+        let mut present_info = vk::PresentInfoKHR::default()
             .wait_semaphores(&render_semaphores)
             .swapchains(&swapchains)
             .image_indices(&image_indices);
+        if self.present_wait.is_some() {
+            present_info = present_info.push_next(&mut present_id);
+        }
 
         let present_result = unsafe {
             self.swapchain_info
@@ -424,13 +837,22 @@ impl VulkanBackend {
         };
 
         match present_result {
-            Ok(_) => {}
-            Err(vk::Result::ERROR_OUT_OF_DATE_KHR) => {
-                // TODO: recreate swapchain on resize
-                println!("Error SWAPCHAIN");
-            }
-            Err(_) => panic!("Unexpected present error"),
+            Ok(suboptimal) => self.swapchain_out_of_date |= suboptimal,
+            Err(vk::Result::ERROR_OUT_OF_DATE_KHR) => self.swapchain_out_of_date = true,
+            Err(e) => self.check_device(Err(e), "present"),
         };
+        self.last_present_id = present_ids[0];
+
+        if self.capture == CaptureState::Capturing {
+            if let Some(renderdoc) = &self.renderdoc {
+                if !renderdoc.end_frame_capture(self.instance.handle()) {
+                    eprintln!("RenderDoc frame capture failed");
+                }
+            }
+            self.capture = CaptureState::Idle;
+        }
+
+        frame_done
     }
 
     pub fn begin_rendering(
@@ -443,17 +865,11 @@ impl VulkanBackend {
         for handle in color_image_handles {
             let img = &mut self.resource_registry.images[handle.0];
 
-            if img.image_layout != vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL {
-                image_util::transition_image_layout(
-                    &self.device_info,
-                    &self.command_buffer,
-                    img.image,
-                    img.image_layout,
-                    vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
-                    false,
-                );
-                img.image_layout = vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL;
-            }
+            img.transition(
+                &self.device_info.logical_device,
+                self.command_buffer,
+                ResourceState::ColorAttachment,
+            );
 
             color_infos.push(
                 vk::RenderingAttachmentInfo::default()
@@ -461,40 +877,36 @@ impl VulkanBackend {
                     .image_layout(vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL)
                     .load_op(vk::AttachmentLoadOp::CLEAR)
                     .store_op(vk::AttachmentStoreOp::STORE)
-                    .clear_value(vk::ClearValue {
-                        color: vk::ClearColorValue {
-                            float32: [0.0, 0.0, 0.0, 1.0],
-                        },
-                    }),
+                    .clear_value(
+                        img.clear_value
+                            .unwrap_or(ClearValue::Color(Color::BLACK))
+                            .into(),
+                    ),
             );
         }
 
         let depth_info = depth_image_handle.map(|handle| {
             let img = &mut self.resource_registry.images[handle.0];
 
-            if img.image_layout != vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL {
-                image_util::transition_image_layout(
-                    &self.device_info,
-                    &self.command_buffer,
-                    img.image,
-                    img.image_layout,
-                    vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL,
-                    true,
-                );
-                img.image_layout = vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL;
-            }
+            img.transition(
+                &self.device_info.logical_device,
+                self.command_buffer,
+                ResourceState::DepthAttachment,
+            );
 
             vk::RenderingAttachmentInfo::default()
                 .image_view(img.image_view)
                 .image_layout(vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL)
                 .load_op(vk::AttachmentLoadOp::CLEAR)
                 .store_op(vk::AttachmentStoreOp::STORE)
-                .clear_value(vk::ClearValue {
-                    depth_stencil: vk::ClearDepthStencilValue {
-                        depth: 1.0,
-                        stencil: 0,
-                    },
-                })
+                .clear_value(
+                    img.clear_value
+                        .unwrap_or(ClearValue::DepthStencil {
+                            depth: 1.0,
+                            stencil: 0,
+                        })
+                        .into(),
+                )
         });
 
         let mut begin_render_info = vk::RenderingInfo::default()
@@ -523,21 +935,25 @@ impl VulkanBackend {
     /// Like begin_rendering but preserves existing color contents (LOAD op).
     /// No depth attachment — suitable for debug overlays drawn on top of the scene.
     pub fn begin_rendering_load(&mut self, color_image_handles: &[GpuImageHandle]) {
+        self.begin_rendering_load_with_depth(color_image_handles, None);
+    }
+
+    /// Like begin_rendering_load, with an existing depth buffer loaded for depth testing
+    /// against the scene.
+    pub fn begin_rendering_load_with_depth(
+        &mut self,
+        color_image_handles: &[GpuImageHandle],
+        depth_image_handle: Option<&GpuImageHandle>,
+    ) {
         let mut color_infos: Vec<vk::RenderingAttachmentInfo> =
             Vec::with_capacity(color_image_handles.len());
         for handle in color_image_handles {
             let img = &mut self.resource_registry.images[handle.0];
-            if img.image_layout != vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL {
-                image_util::transition_image_layout(
-                    &self.device_info,
-                    &self.command_buffer,
-                    img.image,
-                    img.image_layout,
-                    vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
-                    false,
-                );
-                img.image_layout = vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL;
-            }
+            img.transition(
+                &self.device_info.logical_device,
+                self.command_buffer,
+                ResourceState::ColorAttachment,
+            );
             color_infos.push(
                 vk::RenderingAttachmentInfo::default()
                     .image_view(img.image_view)
@@ -546,13 +962,29 @@ impl VulkanBackend {
                     .store_op(vk::AttachmentStoreOp::STORE),
             );
         }
-        let begin_render_info = vk::RenderingInfo::default()
+        let depth_info = depth_image_handle.map(|handle| {
+            let img = &mut self.resource_registry.images[handle.0];
+            img.transition(
+                &self.device_info.logical_device,
+                self.command_buffer,
+                ResourceState::DepthAttachment,
+            );
+            vk::RenderingAttachmentInfo::default()
+                .image_view(img.image_view)
+                .image_layout(vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL)
+                .load_op(vk::AttachmentLoadOp::LOAD)
+                .store_op(vk::AttachmentStoreOp::STORE)
+        });
+        let mut begin_render_info = vk::RenderingInfo::default()
             .render_area(vk::Rect2D {
                 extent: self.swapchain_info.swapchain_extent,
                 offset: vk::Offset2D { x: 0, y: 0 },
             })
             .layer_count(1)
             .color_attachments(&color_infos);
+        if let Some(ref depth) = depth_info {
+            begin_render_info = begin_render_info.depth_attachment(depth);
+        }
         unsafe {
             self.device_info
                 .logical_device
@@ -575,17 +1007,11 @@ impl VulkanBackend {
         for handle in color_image_handles {
             let img = &mut self.resource_registry.images[handle.0];
 
-            if img.image_layout != vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL {
-                image_util::transition_image_layout(
-                    &self.device_info,
-                    &self.command_buffer,
-                    img.image,
-                    img.image_layout,
-                    vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
-                    false,
-                );
-                img.image_layout = vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL;
-            }
+            img.transition(
+                &self.device_info.logical_device,
+                self.command_buffer,
+                ResourceState::ColorAttachment,
+            );
 
             color_infos.push(
                 vk::RenderingAttachmentInfo::default()
@@ -593,40 +1019,36 @@ impl VulkanBackend {
                     .image_layout(vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL)
                     .load_op(vk::AttachmentLoadOp::CLEAR)
                     .store_op(vk::AttachmentStoreOp::STORE)
-                    .clear_value(vk::ClearValue {
-                        color: vk::ClearColorValue {
-                            float32: [0.0, 0.0, 0.0, 1.0],
-                        },
-                    }),
+                    .clear_value(
+                        img.clear_value
+                            .unwrap_or(ClearValue::Color(Color::BLACK))
+                            .into(),
+                    ),
             );
         }
 
         let depth_info = depth_image_handle.map(|handle| {
             let img = &mut self.resource_registry.images[handle.0];
 
-            if img.image_layout != vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL {
-                image_util::transition_image_layout(
-                    &self.device_info,
-                    &self.command_buffer,
-                    img.image,
-                    img.image_layout,
-                    vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL,
-                    true,
-                );
-                img.image_layout = vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL;
-            }
+            img.transition(
+                &self.device_info.logical_device,
+                self.command_buffer,
+                ResourceState::DepthAttachment,
+            );
 
             vk::RenderingAttachmentInfo::default()
                 .image_view(img.image_view)
                 .image_layout(vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL)
                 .load_op(vk::AttachmentLoadOp::CLEAR)
                 .store_op(vk::AttachmentStoreOp::STORE)
-                .clear_value(vk::ClearValue {
-                    depth_stencil: vk::ClearDepthStencilValue {
-                        depth: 1.0,
-                        stencil: 0,
-                    },
-                })
+                .clear_value(
+                    img.clear_value
+                        .unwrap_or(ClearValue::DepthStencil {
+                            depth: 1.0,
+                            stencil: 0,
+                        })
+                        .into(),
+                )
         });
 
         let extent = vk::Extent2D { width, height };
@@ -661,20 +1083,44 @@ impl VulkanBackend {
     }
 
     pub fn bind_vertex_buffer(&mut self, buffer: BufferHandle) {
-        let buf = self.resource_registry.buffers[buffer.0].buffer;
-        let offsets = [0u64];
+        self.bind_vertex_buffers(0, &[buffer]);
+    }
+
+    /// Binds `buffers` to consecutive vertex bindings starting at `first_binding`. Does
+    /// nothing if they are already bound there.
+    pub fn bind_vertex_buffers(&mut self, first_binding: u32, buffers: &[BufferHandle]) {
+        let bufs: Vec<vk::Buffer> = buffers
+            .iter()
+            .map(|buffer| self.resource_registry.buffers[buffer.0].buffer)
+            .collect();
+        let bindings = first_binding as usize..first_binding as usize + bufs.len();
+        if self.bound_vertex_buffers.get(bindings.clone()) == Some(bufs.as_slice()) {
+            return;
+        }
+        if self.bound_vertex_buffers.len() < bindings.end {
+            self.bound_vertex_buffers
+                .resize(bindings.end, vk::Buffer::null());
+        }
+        self.bound_vertex_buffers[bindings].copy_from_slice(&bufs);
+
+        let offsets = vec![0u64; bufs.len()];
         unsafe {
             self.device_info.logical_device.cmd_bind_vertex_buffers(
                 self.command_buffer,
-                0,
-                &[buf],
+                first_binding,
+                &bufs,
                 &offsets,
             );
         }
     }
 
+    /// Binds a `u32` index buffer. Does nothing if it is already bound.
     pub fn bind_index_buffer(&mut self, buffer: BufferHandle) {
         let buf = self.resource_registry.buffers[buffer.0].buffer;
+        if self.bound_index_buffer == buf {
+            return;
+        }
+        self.bound_index_buffer = buf;
         unsafe {
             self.device_info.logical_device.cmd_bind_index_buffer(
                 self.command_buffer,
@@ -686,28 +1132,31 @@ impl VulkanBackend {
     }
 
     pub fn bind_pipeline(&mut self, pipeline: PipelineHandle) {
-        let vk_pipeline = self.resource_registry.pipelines[pipeline.0].pipelines[0];
+        let pipeline_info = &self.resource_registry.pipelines[pipeline.0];
         unsafe {
             self.device_info.logical_device.cmd_bind_pipeline(
-                self.command_buffer,
-                vk::PipelineBindPoint::GRAPHICS,
-                vk_pipeline,
+                self.recording_command_buffer(),
+                pipeline_info.bind_point,
+                pipeline_info.pipelines[0],
             );
         }
     }
 
     pub fn bind_descriptor_sets(&mut self, sets: &[DescriptorSetHandle], pipeline: PipelineHandle) {
+        for &set in sets {
+            self.resource_registry.mark_descriptor_set_bound(set);
+        }
         let vk_sets = sets
             .iter()
             .map(|set| self.resource_registry.descriptor_sets[set.0].descriptor_set)
             .collect::<Vec<_>>();
-        let vk_pipeline_layout = self.resource_registry.pipelines[pipeline.0].pipeline_layout;
+        let pipeline_info = &self.resource_registry.pipelines[pipeline.0];
 
         unsafe {
             self.device_info.logical_device.cmd_bind_descriptor_sets(
-                self.command_buffer,
-                vk::PipelineBindPoint::GRAPHICS,
-                vk_pipeline_layout,
+                self.recording_command_buffer(),
+                pipeline_info.bind_point,
+                pipeline_info.pipeline_layout,
                 0,
                 vk_sets.as_slice(),
                 &[],
@@ -715,37 +1164,104 @@ impl VulkanBackend {
         }
     }
 
-    pub fn draw_indexed(&self, index_count: u32, first_index: u32) {
+    /// `vertex_offset` is added to every index, for meshes packed into shared buffers.
+    pub fn draw_indexed(&self, index_count: u32, first_index: u32, vertex_offset: i32) {
         unsafe {
             self.device_info.logical_device.cmd_draw_indexed(
                 self.command_buffer,
                 index_count,
                 1,
                 first_index,
-                0,
+                vertex_offset,
                 0,
             );
         }
     }
 
-    pub fn draw(&self, vertex_count: u32) {
+    /// Draws the indexed commands starting at command `first_command` of `commands`.
+    /// The number drawn is the `u32` at index `count_index` of `count_buffer`, capped at
+    /// `max_draw_count`; both buffers are usually written by a compute pass. Needs
+    /// [`DeviceCapabilities::draw_indirect_count`].
+    pub fn draw_indexed_indirect_count(
+        &self,
+        commands: BufferHandle,
+        first_command: u32,
+        count_buffer: BufferHandle,
+        count_index: u32,
+        max_draw_count: u32,
+    ) {
+        let stride = size_of::<vk::DrawIndexedIndirectCommand>() as u32;
         unsafe {
             self.device_info
                 .logical_device
-                .cmd_draw(self.command_buffer, vertex_count, 1, 0, 0);
+                .cmd_draw_indexed_indirect_count(
+                    self.command_buffer,
+                    self.resource_registry.buffers[commands.0].buffer,
+                    u64::from(first_command) * u64::from(stride),
+                    self.resource_registry.buffers[count_buffer.0].buffer,
+                    u64::from(count_index) * size_of::<u32>() as u64,
+                    max_draw_count,
+                    stride,
+                );
+        }
+    }
+
+    /// Draws one non-indexed command read from `offset` bytes into `args`, usually
+    /// written by a compute pass.
+    pub fn draw_indirect(&self, args: BufferHandle, offset: u64) {
+        unsafe {
+            self.device_info.logical_device.cmd_draw_indirect(
+                self.command_buffer,
+                self.resource_registry.buffers[args.0].buffer,
+                offset,
+                1,
+                size_of::<vk::DrawIndirectCommand>() as u32,
+            );
+        }
+    }
+
+    pub fn draw(&self, vertex_count: u32, first_vertex: u32) {
+        unsafe {
+            self.device_info.logical_device.cmd_draw(
+                self.command_buffer,
+                vertex_count,
+                1,
+                first_vertex,
+                0,
+            );
+        }
+    }
+
+    /// Rewrites every descriptor set that binds one of `images` with what it bound before,
+    /// picking up images swapped in by `recreate_image`. Call between frames.
+    pub fn refresh_descriptor_sets(&mut self, images: &[GpuImageHandle]) {
+        for set_handle in self.resource_registry.descriptor_sets_binding(images) {
+            let writes = self
+                .resource_registry
+                .descriptor_writes(set_handle)
+                .to_vec();
+            self.update_descriptor_set(set_handle, &writes);
         }
     }
 
+    /// Writes bindings of the set. If a frame still in flight bound it, waits for that
+    /// frame first; sets bound in the frame being recorded must not be rewritten.
     pub fn update_descriptor_set(
         &mut self,
         set_handle: DescriptorSetHandle,
         write_descs: &[DescriptorWriteDesc],
     ) {
+        if let Some(frame) = self.resource_registry.descriptor_set_frame(set_handle) {
+            self.wait_for_frame(frame);
+        }
+        self.resource_registry
+            .record_descriptor_writes(set_handle, write_descs);
         let set = self.resource_registry.descriptor_sets[set_handle.0].descriptor_set;
 
         let mut descriptor_uniform_buffer_infos = vec![];
         let mut descriptor_storage_buffer_infos = vec![];
         let mut descriptor_image_infos = vec![];
+        let mut descriptor_storage_image_infos = vec![];
 
         for write_desc in write_descs {
             match write_desc.value {
@@ -778,6 +1294,19 @@ impl VulkanBackend {
 
                     descriptor_image_infos.push((write_desc.binding, descriptor_image_info));
                 }
+                DescriptorValue::StorageImage(storage_image_info) => {
+                    let image = &self.resource_registry.images[storage_image_info.image.0];
+                    let image_view = match image.mip_views.as_slice() {
+                        [] => image.image_view,
+                        views => views[storage_image_info.mip_level as usize],
+                    };
+                    let descriptor_image_info = vk::DescriptorImageInfo::default()
+                        .image_view(image_view)
+                        .image_layout(vk::ImageLayout::GENERAL);
+
+                    descriptor_storage_image_infos
+                        .push((write_desc.binding, descriptor_image_info));
+                }
             }
         }
 
@@ -814,11 +1343,23 @@ impl VulkanBackend {
             })
             .collect::<Vec<_>>();
 
+        let mut storage_image_writes = descriptor_storage_image_infos
+            .iter()
+            .map(|(binding, info)| {
+                vk::WriteDescriptorSet::default()
+                    .dst_set(set)
+                    .dst_binding(*binding as u32)
+                    .descriptor_type(vk::DescriptorType::STORAGE_IMAGE)
+                    .image_info(slice::from_ref(info))
+            })
+            .collect::<Vec<_>>();
+
         let mut writes = vec![];
 
         writes.append(&mut uniform_writes);
         writes.append(&mut storage_writes);
         writes.append(&mut image_writes);
+        writes.append(&mut storage_image_writes);
         unsafe {
             self.device_info
                 .logical_device
@@ -826,17 +1367,44 @@ impl VulkanBackend {
         }
     }
 
-    pub fn transition_image(&mut self, image_handle: GpuImageHandle, is_depth: bool) {
-        let img = &mut self.resource_registry.images[image_handle.0];
-        image_util::transition_image_layout(
-            &self.device_info,
-            &self.command_buffer,
-            img.image,
-            img.image_layout,
-            vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
-            is_depth,
+    /// Records a barrier that moves the image into `state`, waiting only for the work
+    /// that accessed it in its previous state. Attachments are transitioned by
+    /// `begin_rendering`; call this before sampling an image that was rendered to.
+    pub fn transition_image(&mut self, image_handle: GpuImageHandle, state: ResourceState) {
+        self.resource_registry.images[image_handle.0].transition(
+            &self.device_info.logical_device,
+            self.command_buffer,
+            state,
         );
-        img.image_layout = vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL;
+    }
+
+    /// Changes the value `begin_rendering` clears an image to.
+    pub fn set_clear_value(&mut self, image_handle: GpuImageHandle, clear_value: ClearValue) {
+        self.resource_registry.images[image_handle.0].clear_value = Some(clear_value);
+    }
+
+    /// Reads the texel at (`x`, `y`) of an image back to the CPU. Blocks until the GPU has
+    /// finished all submitted frames, so call it between frames for one-off queries such
+    /// as picking. The image needs `TRANSFER_SRC`.
+    pub fn read_texel(&mut self, image_handle: GpuImageHandle, x: u32, y: u32) -> Vec<u8> {
+        self.resource_registry.images[image_handle.0].read_region(
+            &self.device_info,
+            &self.instance,
+            (x, y),
+            (1, 1),
+        )
+    }
+
+    /// Reads the whole first mip of an image back to the CPU, row by row, like
+    /// [`Self::read_texel`]. Blocks until the GPU has finished all submitted frames.
+    pub fn read_image(&mut self, image_handle: GpuImageHandle) -> Vec<u8> {
+        let size = self.image_size(image_handle);
+        self.resource_registry.images[image_handle.0].read_region(
+            &self.device_info,
+            &self.instance,
+            (0, 0),
+            size,
+        )
     }
 
     pub fn update_push_constants<T>(
@@ -848,7 +1416,7 @@ impl VulkanBackend {
         let pipeline_layout = self.resource_registry.pipelines[pipeline_handle.0].pipeline_layout;
         unsafe {
             self.device_info.logical_device.cmd_push_constants(
-                self.command_buffer,
+                self.recording_command_buffer(),
                 pipeline_layout,
                 stage.into(),
                 0,
@@ -857,6 +1425,19 @@ impl VulkanBackend {
         }
     }
 
+    /// Sets the dynamic depth bias for subsequent draws. Only valid for pipelines
+    /// created with `depth_bias_enable`. The bias is unclamped.
+    pub fn set_depth_bias(&self, constant_factor: f32, slope_factor: f32) {
+        unsafe {
+            self.device_info.logical_device.cmd_set_depth_bias(
+                self.command_buffer,
+                constant_factor,
+                0.0,
+                slope_factor,
+            );
+        }
+    }
+
     pub fn update_push_constants_raw(
         &mut self,
         pipeline_handle: PipelineHandle,
@@ -868,7 +1449,7 @@ impl VulkanBackend {
 
         unsafe {
             self.device_info.logical_device.cmd_push_constants(
-                self.command_buffer,
+                self.recording_command_buffer(),
                 pipeline_layout,
                 stage.into(),
                 offset,
@@ -877,6 +1458,286 @@ impl VulkanBackend {
         }
     }
 
+    /// Starts recording compute work. Until `submit_compute`, `bind_pipeline`,
+    /// `bind_descriptor_sets`, push constants, `dispatch` and `compute_barrier` record into
+    /// the compute command buffer of the frame slot; outside of it they record into the
+    /// frame. Blocks until the slot's previous compute submission has finished.
+    pub fn begin_compute(&mut self) {
+        assert!(!self.compute.recording, "begin_compute called twice");
+        let slot = self.frame_slot();
+        if let Some(point) = self.frames[slot].compute_submitted {
+            self.wait_for(&[point]);
+        }
+        self.compute.command_buffer = self.frames[slot].compute_command_buffer;
+        let device = &self.device_info.logical_device;
+        unsafe {
+            device
+                .reset_command_buffer(
+                    self.compute.command_buffer,
+                    vk::CommandBufferResetFlags::empty(),
+                )
+                .expect("Failed to reset compute command buffer");
+            device
+                .begin_command_buffer(
+                    self.compute.command_buffer,
+                    &vk::CommandBufferBeginInfo::default()
+                        .flags(vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT),
+                )
+                .expect("Begin compute command buffer failed");
+        }
+        self.compute.recording = true;
+    }
+
+    pub fn dispatch(&self, group_count_x: u32, group_count_y: u32, group_count_z: u32) {
+        unsafe {
+            self.device_info.logical_device.cmd_dispatch(
+                self.recording_command_buffer(),
+                group_count_x,
+                group_count_y,
+                group_count_z,
+            );
+        }
+    }
+
+    /// Makes shader writes of earlier dispatches visible to later ones.
+    pub fn compute_barrier(&self) {
+        self.memory_barrier(
+            vk::PipelineStageFlags2::COMPUTE_SHADER,
+            vk::AccessFlags2::SHADER_WRITE,
+            vk::PipelineStageFlags2::COMPUTE_SHADER,
+            vk::AccessFlags2::SHADER_READ | vk::AccessFlags2::SHADER_WRITE,
+        );
+    }
+
+    /// Makes shader writes of earlier dispatches visible to indirect draws and to vertex
+    /// shaders reading storage buffers.
+    pub fn compute_to_draw_barrier(&self) {
+        self.memory_barrier(
+            vk::PipelineStageFlags2::COMPUTE_SHADER,
+            vk::AccessFlags2::SHADER_WRITE,
+            vk::PipelineStageFlags2::DRAW_INDIRECT | vk::PipelineStageFlags2::VERTEX_SHADER,
+            vk::AccessFlags2::INDIRECT_COMMAND_READ | vk::AccessFlags2::SHADER_READ,
+        );
+    }
+
+    /// Orders later dispatches after earlier draws reading the same buffers, so compute
+    /// does not overwrite them while they are in use.
+    pub fn draw_to_compute_barrier(&self) {
+        self.memory_barrier(
+            vk::PipelineStageFlags2::DRAW_INDIRECT | vk::PipelineStageFlags2::VERTEX_SHADER,
+            vk::AccessFlags2::INDIRECT_COMMAND_READ | vk::AccessFlags2::SHADER_READ,
+            vk::PipelineStageFlags2::COMPUTE_SHADER,
+            vk::AccessFlags2::SHADER_READ | vk::AccessFlags2::SHADER_WRITE,
+        );
+    }
+
+    fn memory_barrier(
+        &self,
+        src_stage: vk::PipelineStageFlags2,
+        src_access: vk::AccessFlags2,
+        dst_stage: vk::PipelineStageFlags2,
+        dst_access: vk::AccessFlags2,
+    ) {
+        let barrier = [vk::MemoryBarrier2::default()
+            .src_stage_mask(src_stage)
+            .src_access_mask(src_access)
+            .dst_stage_mask(dst_stage)
+            .dst_access_mask(dst_access)];
+        let dependency_info = vk::DependencyInfo::default().memory_barriers(&barrier);
+        unsafe {
+            self.device_info
+                .logical_device
+                .cmd_pipeline_barrier2(self.recording_command_buffer(), &dependency_info);
+        }
+    }
+
+    /// Submits the recorded compute work once every point in `waits` is reached, and once
+    /// the frame that last used this frame slot has finished. Compute must only write
+    /// resources kept per frame slot, so later frames in flight never wait for it. The
+    /// next frame submission waits for it automatically; other work can wait on the
+    /// returned point.
+    pub fn submit_compute(&mut self, waits: &[TimelinePoint]) -> TimelinePoint {
+        assert!(self.compute.recording, "submit_compute without begin_compute");
+        self.compute.recording = false;
+
+        let device = &self.device_info.logical_device;
+        let timelines = &self.device_info.timelines;
+        unsafe {
+            device
+                .end_command_buffer(self.compute.command_buffer)
+                .expect("End compute command buffer failed");
+        }
+
+        let slot_free = self.frames[self.frame_slot].submitted.map(|(_, point)| point);
+        let wait_info = waits
+            .iter()
+            .chain(slot_free.as_ref())
+            .map(|&point| timelines.wait_info(point, vk::PipelineStageFlags2::COMPUTE_SHADER))
+            .collect::<Vec<_>>();
+
+        let submitted = timelines.compute.submit(
+            device,
+            self.device_info.queue_info.compute_queue,
+            &[self.compute.command_buffer],
+            &wait_info,
+            &[],
+        );
+        let point = self.check_device(submitted, "submit compute");
+        self.frames[self.frame_slot].compute_submitted = Some(point);
+        point
+    }
+
+    /// Captures the next rendered frame, from `begin_frame` to `end_frame`, with RenderDoc.
+    /// Returns false if the process was not launched from RenderDoc.
+    pub fn trigger_capture(&mut self) -> bool {
+        if self.renderdoc.is_none() {
+            return false;
+        }
+        if self.capture == CaptureState::Idle {
+            self.capture = CaptureState::Pending;
+        }
+        true
+    }
+
+    /// Makes the next frame submission wait on `point`, e.g. an upload or compute pass
+    /// submitted outside the frame.
+    pub fn wait_in_next_frame(&mut self, point: TimelinePoint) {
+        self.frame_waits.push(point);
+    }
+
+    /// Blocks the CPU until all `points` are reached.
+    pub fn wait_for(&self, points: &[TimelinePoint]) {
+        let waited = self
+            .device_info
+            .timelines
+            .wait(&self.device_info.logical_device, points);
+        self.check_device(waited, "wait for timeline");
+    }
+
+    /// Opens a named pass in the recording command buffer. Shows up as a debug label in
+    /// capture tools and as a breadcrumb in the device-lost report. Close with
+    /// `pop_pass_marker`; markers may nest.
+    pub fn push_pass_marker(&mut self, name: &str) {
+        let command_buffer = self.recording_command_buffer();
+        self.diagnostics
+            .begin_pass(&self.device_info.logical_device, command_buffer, name);
+    }
+
+    pub fn pop_pass_marker(&mut self) {
+        let command_buffer = self.recording_command_buffer();
+        self.diagnostics
+            .end_pass(&self.device_info.logical_device, command_buffer);
+    }
+
+    /// Unwraps a Vulkan result. On `ERROR_DEVICE_LOST` the pass breadcrumbs are printed
+    /// before panicking, since nothing can be recovered from a lost device.
+    fn check_device<T>(&self, result: VkResult<T>, action: &str) -> T {
+        match result {
+            Ok(value) => value,
+            Err(vk::Result::ERROR_DEVICE_LOST) => {
+                let queue_info = &self.device_info.queue_info;
+                let mut queues = vec![("graphics", queue_info.graphics_queue)];
+                if queue_info.has_async_compute() {
+                    queues.push(("compute", queue_info.compute_queue));
+                }
+                let report = self.diagnostics.device_lost_report(&queues);
+                eprintln!("{}", report);
+                crash_context::set_device_lost(report);
+                panic!("GPU device lost during {action}");
+            }
+            Err(e) => panic!("failed to {action}: {e}"),
+        }
+    }
+
+    /// Times every frame's passes on the GPU, not only during trace sessions.
+    pub fn set_gpu_frame_timing(&mut self, enabled: bool) {
+        self.diagnostics.set_frame_timing(enabled);
+    }
+
+    /// GPU time of the last finished frame's passes. Needs `set_gpu_frame_timing` or an
+    /// active trace session, and a device that can write timestamps.
+    pub fn gpu_frame_time(&self) -> Option<Duration> {
+        self.diagnostics.gpu_frame_time()
+    }
+
+    /// Number of the frame being recorded, or the last one after `end_frame`.
+    pub fn frame_number(&self) -> u64 {
+        self.diagnostics.frame()
+    }
+
+    /// Names of the pass markers pushed this frame, in order. Kept after `end_frame`
+    /// until the next frame begins.
+    pub fn recorded_passes(&self) -> &[String] {
+        self.diagnostics.passes()
+    }
+
+    /// Memory held by registered images and buffers, with high-water marks and the last
+    /// frame's allocations.
+    pub fn memory_stats(&self) -> GpuMemoryStats {
+        self.resource_registry.memory_stats()
+    }
+
+    /// Blocks until the last frame is on screen, or with no `VK_KHR_present_wait` until
+    /// it has finished on the GPU. The display wait gives up after `PRESENT_WAIT_TIMEOUT`.
+    pub fn wait_for_presented_frame(&mut self) {
+        let present_wait = self.present_wait.as_ref().filter(|_| self.last_present_id > 0);
+        let Some(present_wait) = present_wait else {
+            let timelines = &self.device_info.timelines;
+            let waited = timelines.wait(
+                &self.device_info.logical_device,
+                &[timelines.graphics.last_submitted()],
+            );
+            self.check_device(waited, "wait for frame");
+            return;
+        };
+
+        let waited = unsafe {
+            present_wait.wait_for_present(
+                self.swapchain_info.swapchain,
+                self.last_present_id,
+                PRESENT_WAIT_TIMEOUT,
+            )
+        };
+        match waited {
+            Ok(()) | Err(vk::Result::TIMEOUT) => {}
+            Err(vk::Result::SUBOPTIMAL_KHR | vk::Result::ERROR_OUT_OF_DATE_KHR) => {
+                self.swapchain_out_of_date = true;
+            }
+            Err(e) => self.check_device(Err(e), "wait for present"),
+        }
+    }
+
+    /// Graphics timeline point of the last submitted frame. Once it is reached, the GPU
+    /// no longer uses anything that frame referenced.
+    pub fn last_frame_point(&self) -> TimelinePoint {
+        self.device_info.timelines.graphics.last_submitted()
+    }
+
+    /// Non-blocking check whether the GPU has reached `point`.
+    pub fn is_reached(&self, point: TimelinePoint) -> bool {
+        self.device_info
+            .timelines
+            .is_reached(&self.device_info.logical_device, point)
+    }
+
+    /// Blocks until the GPU has finished frame number `frame`, if it has been submitted.
+    fn wait_for_frame(&self, frame: u64) {
+        let slot = &self.frames[(frame % self.frames.len() as u64) as usize];
+        // A slot only moves on to a newer frame after waiting for its last one.
+        if let Some((_, point)) = slot.submitted.filter(|&(last, _)| last == frame) {
+            self.wait_for(&[point]);
+        }
+    }
+
+    /// The command buffer that recording calls currently target.
+    fn recording_command_buffer(&self) -> vk::CommandBuffer {
+        if self.compute.recording {
+            self.compute.command_buffer
+        } else {
+            self.command_buffer
+        }
+    }
+
     fn begin_single_time_command(&self) -> vk::CommandBuffer {
         let command_buffer_allocate_info = vk::CommandBufferAllocateInfo::default()
             .level(vk::CommandBufferLevel::PRIMARY)
@@ -911,43 +1772,51 @@ impl VulkanBackend {
                 .expect("Failed to end command buffer!");
         };
 
-        let submit_info =
-            vk::SubmitInfo::default().command_buffers(slice::from_ref(&command_buffer));
+        let submitted = self.device_info.timelines.graphics.submit(
+            &self.device_info.logical_device,
+            self.device_info.queue_info.graphics_queue,
+            &[command_buffer],
+            &[],
+            &[],
+        );
+        let uploaded = self.check_device(submitted, "submit upload");
+        self.wait_for(&[uploaded]);
         unsafe {
-            self.device_info
-                .logical_device
-                .queue_submit(
-                    self.device_info.queue_info.graphics_queue,
-                    &[submit_info],
-                    vk::Fence::null(),
-                )
-                .expect("Failed to submit queue!");
-            self.device_info
-                .logical_device
-                .queue_wait_idle(self.device_info.queue_info.graphics_queue)
-                .expect("Failed to wait on queue!");
             self.device_info
                 .logical_device
                 .free_command_buffers(self.device_info.command_pool, &[command_buffer]);
         }
     }
 
+    /// Copies every mip level of `image`, packed largest first in `buffer`.
     fn copy_buffer_to_image(&self, buffer: vk::Buffer, image: &AllocatedImage) {
         let command_buffer = self.begin_single_time_command();
 
-        let region = vk::BufferImageCopy::default()
-            .buffer_offset(0)
-            .buffer_row_length(0)
-            .buffer_image_height(0)
-            .image_subresource(
-                vk::ImageSubresourceLayers::default()
-                    .aspect_mask(vk::ImageAspectFlags::COLOR)
-                    .mip_level(0)
-                    .base_array_layer(0)
-                    .layer_count(1),
-            )
-            .image_offset(vk::Offset3D { x: 0, y: 0, z: 0 })
-            .image_extent(image.image_extent);
+        let mut offset = 0;
+        let regions: Vec<_> = (0..image.mip_levels)
+            .map(|level| {
+                let extent = vk::Extent3D {
+                    width: (image.image_extent.width >> level).max(1),
+                    height: (image.image_extent.height >> level).max(1),
+                    depth: 1,
+                };
+                let region = vk::BufferImageCopy::default()
+                    .buffer_offset(offset as u64)
+                    .buffer_row_length(0)
+                    .buffer_image_height(0)
+                    .image_subresource(
+                        vk::ImageSubresourceLayers::default()
+                            .aspect_mask(vk::ImageAspectFlags::COLOR)
+                            .mip_level(level)
+                            .base_array_layer(0)
+                            .layer_count(1),
+                    )
+                    .image_offset(vk::Offset3D { x: 0, y: 0, z: 0 })
+                    .image_extent(extent);
+                offset += image_util::level_size(image.image_format, extent.width, extent.height);
+                region
+            })
+            .collect();
 
         unsafe {
             self.device_info.logical_device.cmd_copy_buffer_to_image(
@@ -955,281 +1824,32 @@ impl VulkanBackend {
                 buffer,
                 image.image,
                 vk::ImageLayout::TRANSFER_DST_OPTIMAL,
-                &[region],
+                &regions,
             );
         }
 
         self.end_single_time_command(command_buffer);
     }
 
-    // pub fn update_descriptor_set(
-    //     &mut self,
-    //     descriptor_set_handle: DescriptorSetHandle,
-    //     write_descriptor_set: &[DescriptorWrite],
-    // ) {
-    //     let mut writes = vec![];
-    //     let mut buffer_infos = vec![];
-    //     let mut image_infos = vec![];
-    //
-    //     let allocated_descriptor_set = &self.descriptor_sets[descriptor_set_handle.0];
-    //
-    //     for write_descriptor in write_descriptor_set {
-    //         match write_descriptor {
-    //             DescriptorWrite::UniformBuffer(dst_binding, buffer_handle) => {
-    //                 let allocated_buffer = &self.buffers[buffer_handle.0];
-    //
-    //                 buffer_infos.push(
-    //                     vk::DescriptorBufferInfo::default()
-    //                         .buffer(allocated_buffer.buffer)
-    //                         .offset(0)
-    //                         .range(allocated_buffer.buffer_size),
-    //                 );
-    //
-    //                 let info_ptr = buffer_infos.last().unwrap() as *const vk::DescriptorBufferInfo;
-    //
-    //                 let write = vk::WriteDescriptorSet {
-    //                     dst_set: allocated_descriptor_set.descriptor_set,
-    //                     dst_binding: *dst_binding,
-    //                     dst_array_element: 0,
-    //                     descriptor_count: 1,
-    //                     descriptor_type: vk::DescriptorType::UNIFORM_BUFFER,
-    //                     p_buffer_info: info_ptr,
-    //                     ..Default::default()
-    //                 };
-    //
-    //                 writes.push(write);
-    //             }
-    //             DescriptorWrite::CombinedImageSampler(
-    //                 dst_binding,
-    //                 image_handle,
-    //                 sampler_handle,
-    //             ) => {
-    //                 let allocated_image = &self.images[image_handle.0];
-    //                 let sampler = self.samplers[sampler_handle.0];
-    //
-    //                 image_infos.push(
-    //                     vk::DescriptorImageInfo::default()
-    //                         .image_view(allocated_image.image_view)
-    //                         .image_layout(vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL)
-    //                         .sampler(sampler),
-    //                 );
-    //
-    //                 let info_ptr = image_infos.last().unwrap() as *const vk::DescriptorImageInfo;
-    //
-    //                 let write = vk::WriteDescriptorSet {
-    //                     dst_set: allocated_descriptor_set.descriptor_set,
-    //                     dst_binding: *dst_binding,
-    //                     dst_array_element: 0,
-    //                     descriptor_count: 1,
-    //                     descriptor_type: vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
-    //                     p_image_info: info_ptr,
-    //                     ..Default::default()
-    //                 };
-    //
-    //                 writes.push(write);
-    //             }
-    //         }
-    //     }
-    //
-    //     unsafe {
-    //         self.device_info
-    //             .logical_device
-    //             .update_descriptor_sets(&writes, &[]);
-    //     }
-    // }
-
-    // fn create_buffer_old(
-    //     instance: &Instance,
-    //     device_info: &DeviceInfo,
-    //     size: vk::DeviceSize,
-    //     usage: vk::BufferUsageFlags,
-    //     memory_property_flags: MemoryPropertyFlags,
-    // ) -> (vk::Buffer, vk::DeviceMemory) {
-    //     let buffer_create_info = vk::BufferCreateInfo::default()
-    //         .size(size)
-    //         .usage(usage)
-    //         .sharing_mode(vk::SharingMode::EXCLUSIVE);
-    //
-    //     let buffer = unsafe {
-    //         device_info
-    //             .logical_device
-    //             .create_buffer(&buffer_create_info, None)
-    //             .expect("failed to create buffer")
-    //     };
-    //
-    //     let mem_requirements = unsafe {
-    //         device_info
-    //             .logical_device
-    //             .get_buffer_memory_requirements(buffer)
-    //     };
-    //     let memory_properties =
-    //         unsafe { instance.get_physical_device_memory_properties(device_info._physical_device) };
-    //     let memory_alloc_info = vk::MemoryAllocateInfo::default()
-    //         .allocation_size(mem_requirements.size)
-    //         .memory_type_index(utils::find_memory_type(
-    //             mem_requirements.memory_type_bits,
-    //             memory_property_flags,
-    //             memory_properties,
-    //         ));
-    //
-    //     let buffer_memory = unsafe {
-    //         device_info
-    //             .logical_device
-    //             .allocate_memory(&memory_alloc_info, None)
-    //             .expect("failed to allocate memory")
-    //     };
-    //
-    //     unsafe {
-    //         device_info
-    //             .logical_device
-    //             .bind_buffer_memory(buffer, buffer_memory, 0)
-    //             .expect("failed to bind buffer");
-    //     }
-    //
-    //     (buffer, buffer_memory)
-    // }
-
-    // pub fn copy_buffer(&self, src_buffer: vk::Buffer, dst_buffer: vk::Buffer, size: u64) {
-    //     let command_buffer = BufferInfo::begin_single_time_command(&self.device_info);
-    //
-    //     let copy_region = vk::BufferCopy {
-    //         src_offset: 0,
-    //         dst_offset: 0,
-    //         size,
-    //     };
-    //
-    //     unsafe {
-    //         self.device_info.logical_device.cmd_copy_buffer(
-    //             command_buffer,
-    //             src_buffer,
-    //             dst_buffer,
-    //             &[copy_region],
-    //         )
-    //     };
-    //
-    //     BufferInfo::end_single_time_command(&self.device_info, command_buffer);
-    // }
-
-    // fn create_index_buffer(
-    //     instance: &Instance,
-    //     device_info: &DeviceInfo,
-    //     indices: &[u32],
-    // ) -> AllocatedBuffer {
-    //     let buffer_size = mem::size_of_val(indices) as vk::DeviceSize;
-    //
-    //     let (x, y) = Self::create_buffer_old(
-    //         instance,
-    //         device_info,
-    //         buffer_size,
-    //         vk::BufferUsageFlags::TRANSFER_SRC,
-    //         MemoryPropertyFlags::HOST_VISIBLE | MemoryPropertyFlags::HOST_COHERENT,
-    //     );
-    //
-    //     unsafe {
-    //         let data = device_info
-    //             .logical_device
-    //             .map_memory(y, 0, buffer_size, vk::MemoryMapFlags::empty())
-    //             .expect("Failed to map memory") as *mut u32;
-    //
-    //         data.copy_from_nonoverlapping(indices.as_ptr(), indices.len());
-    //
-    //         device_info.logical_device.unmap_memory(y);
-    //     };
-    //
-    //     let (index_buffer, index_buffer_memory) = Self::create_buffer_old(
-    //         instance,
-    //         device_info,
-    //         buffer_size,
-    //         vk::BufferUsageFlags::TRANSFER_DST | vk::BufferUsageFlags::INDEX_BUFFER,
-    //         MemoryPropertyFlags::DEVICE_LOCAL,
-    //     );
-    //
-    //     /* Self::copy_buffer(x, index_buffer, buffer_size, device_info);*/
-    //
-    //     unsafe {
-    //         device_info.logical_device.destroy_buffer(x, None);
-    //         device_info.logical_device.free_memory(y, None);
-    //     }
-    //
-    //     AllocatedBuffer {
-    //         buffer: index_buffer,
-    //         buffer_memory: index_buffer_memory,
-    //         mapped_buffer: ptr::null_mut(),
-    //         buffer_size,
-    //     }
-    // }
-
-    // pub fn map_memory<T>(&self, buffer: &AllocatedBuffer, data: &[T]) {
-    //     unsafe {
-    //         let mapped_data = self
-    //             .device_info
-    //             .logical_device
-    //             .map_memory(
-    //                 buffer.buffer_memory,
-    //                 0,
-    //                 buffer.buffer_size,
-    //                 vk::MemoryMapFlags::empty(),
-    //             )
-    //             .expect("Failed to map memory") as *mut T;
-    //
-    //         mapped_data.copy_from_nonoverlapping(data.as_ptr(), data.len());
-    //
-    //         self.device_info
-    //             .logical_device
-    //             .unmap_memory(buffer.buffer_memory);
-    //     };
-    // }
-
-    // fn create_vertex_buffer(
-    //     instance: &Instance,
-    //     device_info: &DeviceInfo,
-    //     vertices: &[Vertex],
-    // ) -> AllocatedBuffer {
-    //     let buffer_size = mem::size_of_val(vertices) as u64;
-    //
-    //     let (x, y) = Self::create_buffer_old(
-    //         instance,
-    //         device_info,
-    //         buffer_size,
-    //         vk::BufferUsageFlags::TRANSFER_SRC,
-    //         MemoryPropertyFlags::HOST_VISIBLE | MemoryPropertyFlags::HOST_COHERENT,
-    //     );
-    //
-    //     unsafe {
-    //         let data = device_info
-    //             .logical_device
-    //             .map_memory(y, 0, buffer_size, vk::MemoryMapFlags::empty())
-    //             .expect("Failed to map memory") as *mut Vertex;
-    //
-    //         data.copy_from_nonoverlapping(vertices.as_ptr(), vertices.len());
-    //
-    //         device_info.logical_device.unmap_memory(y);
-    //     };
-    //
-    //     let (vertex_buffer, vertex_buffer_memory) = Self::create_buffer_old(
-    //         instance,
-    //         device_info,
-    //         buffer_size,
-    //         vk::BufferUsageFlags::TRANSFER_DST | vk::BufferUsageFlags::VERTEX_BUFFER,
-    //         MemoryPropertyFlags::DEVICE_LOCAL,
-    //     );
-    //
-    //     /*        Self::copy_buffer(x, vertex_buffer, buffer_size, device_info);
-    //      */
-    //     unsafe {
-    //         device_info.logical_device.destroy_buffer(x, None);
-    //         device_info.logical_device.free_memory(y, None);
-    //     }
-    //
-    //     AllocatedBuffer {
-    //         buffer: vertex_buffer,
-    //         buffer_memory: vertex_buffer_memory,
-    //         mapped_buffer: ptr::null_mut(),
-    //         buffer_size,
-    //     }
-    // }
-
-    fn create_instance(entry: &ash::Entry, window: &Window) -> Instance {
+    fn supports_instance_extension(entry: &ash::Entry, name: &CStr) -> bool {
+        let extensions = unsafe {
+            entry
+                .enumerate_instance_extension_properties(None)
+                .unwrap_or_default()
+        };
+        extensions
+            .iter()
+            .any(|ex| ex.extension_name_as_c_str() == Ok(name))
+    }
+
+    /// `hdr_color_spaces` enables the extension that lets surfaces report HDR color
+    /// spaces; without it only SDR formats are offered.
+    fn create_instance(
+        entry: &ash::Entry,
+        window: &Window,
+        debug_utils: bool,
+        hdr_color_spaces: bool,
+    ) -> Instance {
         let app_name = CString::new("Vulkan Application").unwrap();
         let engine_name = CString::new("No Engine").unwrap();
 
@@ -1246,6 +1866,12 @@ impl VulkanBackend {
         let mut extension_names = extension_names.to_vec();
 
         extension_names.push(vk::KHR_GET_PHYSICAL_DEVICE_PROPERTIES2_NAME.as_ptr());
+        if debug_utils {
+            extension_names.push(vk::EXT_DEBUG_UTILS_NAME.as_ptr());
+        }
+        if hdr_color_spaces {
+            extension_names.push(vk::EXT_SWAPCHAIN_COLORSPACE_NAME.as_ptr());
+        }
 
         let instance_create_info = vk::InstanceCreateInfo {
             s_type: vk::StructureType::INSTANCE_CREATE_INFO,
@@ -1261,1109 +1887,6 @@ impl VulkanBackend {
         unsafe { entry.create_instance(&instance_create_info, None).unwrap() }
     }
 
-    // fn create_image_views(
-    //     swapchain_info: &SwapchainInfo,
-    //     device_info: &DeviceInfo,
-    // ) -> Vec<ImageView> {
-    //     let mut image_views = vec![];
-    //
-    //     for swapchain_image in swapchain_info.swapchain_images.clone() {
-    //         let image_view = AllocatedImage::create_image_view(
-    //             device_info,
-    //             &swapchain_image,
-    //             swapchain_info.swapchain_image_format.format,
-    //             ImageAspectFlags::COLOR,
-    //         );
-    //         image_views.push(image_view);
-    //     }
-    //
-    //     image_views
-    // }
-
-    // fn recreate_swapchain(&mut self) {
-    //     unsafe { self.device_info.logical_device.device_wait_idle().unwrap() }
-    //
-    //     self.cleanup_swapchain();
-    //
-    //     self.device_info
-    //         .update_swapchain_capabilities(&self.surface_info);
-    //     self.swapchain_info =
-    //         SwapchainInfo::new(&self.instance, &self.device_info, &self.surface_info);
-    //     self.image_views = Self::create_image_views(&self.swapchain_info, &self.device_info);
-    // }
-
-    // pub fn create_command_buffers(device_info: &DeviceInfo) -> Vec<vk::CommandBuffer> {
-    //     let command_buffer_alloc_info = vk::CommandBufferAllocateInfo::default()
-    //         .command_pool(device_info.command_pool)
-    //         .level(vk::CommandBufferLevel::PRIMARY)
-    //         .command_buffer_count(MAX_FRAMES_IN_FLIGHT);
-    //
-    //     unsafe {
-    //         device_info
-    //             .logical_device
-    //             .allocate_command_buffers(&command_buffer_alloc_info)
-    //             .expect("failed to allocate command buffer")
-    //     }
-    // }
-
-    // fn cleanup_swapchain(&mut self) {
-    //     for image_view in self.image_views.iter() {
-    //         unsafe {
-    //             self.device_info
-    //                 .logical_device
-    //                 .destroy_image_view(*image_view, None)
-    //         }
-    //     }
-    //
-    //     unsafe {
-    //         self.swapchain_info
-    //             .swapchain_device
-    //             .destroy_swapchain(self.swapchain_info.swapchain, None)
-    //     }
-    // }
-
-    // pub fn get_cascade_frustum_corners(&self, cascade_matrix: &Mat4, color: Vec3) -> Vec<Vertex> {
-    //     let inverse_matrix = glm::inverse(cascade_matrix);
-    //
-    //     // NDC corners of the unit cube
-    //     let ndc_corners = [
-    //         vec3(-1.0, -1.0, -1.0),
-    //         vec3(1.0, -1.0, -1.0),
-    //         vec3(1.0, 1.0, -1.0),
-    //         vec3(-1.0, 1.0, -1.0),
-    //         vec3(-1.0, -1.0, 1.0),
-    //         vec3(1.0, -1.0, 1.0),
-    //         vec3(1.0, 1.0, 1.0),
-    //         vec3(-1.0, 1.0, 1.0),
-    //     ];
-    //
-    //     // Transform corners to world space
-    //     let frustum_corners_world: Vec<Vec3> = ndc_corners
-    //         .iter()
-    //         .map(|corner| {
-    //             let world_space = inverse_matrix * vec4(corner.x, corner.y, corner.z, 1.0);
-    //             vec3(world_space.x, world_space.y, world_space.z) / world_space.w
-    //         })
-    //         .collect();
-    //
-    //     // Create lines connecting the corners
-    //     let mut lines = Vec::new();
-    //
-    //     let line_pairs = [
-    //         // Near plane
-    //         (0, 1),
-    //         (1, 2),
-    //         (2, 3),
-    //         (3, 0),
-    //         // Far plane
-    //         (4, 5),
-    //         (5, 6),
-    //         (6, 7),
-    //         (7, 4),
-    //         // Connecting lines
-    //         (0, 4),
-    //         (1, 5),
-    //         (2, 6),
-    //         (3, 7),
-    //     ];
-    //
-    //     for &(start, end) in &line_pairs {
-    //         lines.push(Vertex {
-    //             pos: frustum_corners_world[start],
-    //             color,
-    //             tex_coord: vec2(0.0, 0.0),
-    //             normal: vec3(0.0, 0.0, 0.0),
-    //             ..Vertex::default()
-    //         });
-    //         lines.push(Vertex {
-    //             pos: frustum_corners_world[end],
-    //             color,
-    //             tex_coord: vec2(0.0, 0.0),
-    //             normal: vec3(0.0, 0.0, 0.0),
-    //             ..Vertex::default()
-    //         });
-    //     }
-    //
-    //     lines
-    // }
-
-    // pub fn update_cascades(&mut self) {
-    //     let lambda = 0.9;
-    //     let mut splits = [0.0; SHADOW_MAP_CASCADE_COUNT + 1];
-    //
-    //     for i in 0..=SHADOW_MAP_CASCADE_COUNT {
-    //         let near = self.camera.near_clip;
-    //         let far = self.camera.far_clip;
-    //         let idm = i as f32 / SHADOW_MAP_CASCADE_COUNT as f32;
-    //
-    //         let log = near * (far / near).powf(idm);
-    //         let uniform = near + (far - near) * idm;
-    //
-    //         splits[i] = log * lambda + uniform * (1.0 - lambda);
-    //     }
-    //
-    //     let shadow_map_resolution = 4096.0; // Shadow map resolution
-    //
-    //     for i in 0..SHADOW_MAP_CASCADE_COUNT {
-    //         let split_start = splits[i];
-    //         let split_end = splits[i + 1];
-    //
-
-    //
-    //         let mut corners_world = [Vec3::zeros(); 8];
-    //
-    //         let projection_matrix = self
-    //             .camera
-    //             .get_projection_matrix_with_splits(split_start, split_end);
-    //         let inv_projection_view =
-    //             glm::inverse(&(projection_matrix * self.camera.get_view_matrix()));
-    //         for (j, corner) in frustum_corners.iter().enumerate() {
-    //             let corner_world = inv_projection_view * vec4(corner.x, corner.y, corner.z, 1.0);
-    //             corners_world[j] = vec3(
-    //                 corner_world.x / corner_world.w,
-    //                 corner_world.y / corner_world.w,
-    //                 corner_world.z / corner_world.w,
-    //             );
-    //         }
-    //
-    //         let mut frustum_center = Vec3::zeros();
-    //         for corner in &corners_world {
-    //             frustum_center += *corner;
-    //         }
-    //         frustum_center /= corners_world.len() as f32;
-    //
-    //         let radius = corners_world
-    //             .iter()
-    //             .map(|corner| glm::distance(corner, &frustum_center))
-    //             .fold(0.0, f32::max);
-    //
-    //         let max_extents = vec3(radius, radius, radius);
-    //         let min_extents = -max_extents;
-    //
-    //         let texel_size = (max_extents.x - min_extents.x) / shadow_map_resolution;
-    //         let snapped_min_extents = glm::floor(&(min_extents / texel_size)) * texel_size;
-    //         let snapped_max_extents = glm::ceil(&(max_extents / texel_size)) * texel_size;
-    //
-    //         let light_dir = glm::normalize(&vec3(0.5, 1.0, 0.5)); // Assuming directional light
-    //
-    //         let light_position: Vec3 = frustum_center + light_dir * radius;
-    //
-    //         let mut up = vec3(0.0, 1.0, 0.0);
-    //         if glm::normalize(&light_position).y > 0.98 {
-    //             up = vec3(0.0, 1.0, 0.0);
-    //         }
-    //
-    //         let light_view = glm::look_at(&(light_position), &frustum_center, &up);
-    //
-    //         let mut light_projection = glm::ortho(
-    //             snapped_min_extents.x,
-    //             snapped_max_extents.x,
-    //             snapped_min_extents.y,
-    //             snapped_max_extents.y,
-    //             snapped_min_extents.z,
-    //             snapped_max_extents.z - snapped_min_extents.z,
-    //         );
-    //
-    //         light_projection[(1, 1)] *= -1.0;
-    //
-    //         let normalized_start = split_start;
-    //         let normalized_end = split_end;
-    //         let dist = normalized_end - normalized_start;
-    //
-    //         self.cascades[i] = Cascade {
-    //             cascade_view_proj: light_projection * light_view,
-    //             cascade_depth: normalized_start + dist,
-    //         }
-    //     }
-    // }
-
-    // fn create_texture_image(
-    //     device_info: &DeviceInfo,
-    //     instance: &Instance,
-    // ) -> (vk::Image, vk::DeviceMemory) {
-    //     let dyn_image = image::open(".\\resources\\textures\\texture.png").unwrap();
-    //     let image_width = dyn_image.width();
-    //     let image_height = dyn_image.height();
-    //
-    //     let image_size =
-    //         (mem::size_of::<u8>() as u32 * image_width * image_height * 4) as vk::DeviceSize;
-    //
-    //     let image_data = match &dyn_image {
-    //         image::DynamicImage::ImageLuma8(_) | image::DynamicImage::ImageRgb8(_) => {
-    //             dyn_image.to_rgba8().into_raw()
-    //         }
-    //         _ => vec![],
-    //     };
-    //
-    //     let image_buffer = BufferInfo::new(
-    //         instance,
-    //         device_info,
-    //         image_size,
-    //         MemoryPropertyFlags::HOST_VISIBLE | MemoryPropertyFlags::HOST_COHERENT,
-    //         vk::BufferUsageFlags::TRANSFER_SRC,
-    //     );
-    //     let image_buffer_mapped = unsafe {
-    //         device_info
-    //             .logical_device
-    //             .map_memory(
-    //                 image_buffer.buffer_memory,
-    //                 0,
-    //                 image_size,
-    //                 vk::MemoryMapFlags::empty(),
-    //             )
-    //             .expect("failed to map image buffer memory") as *mut u8
-    //     };
-    //
-    //     unsafe {
-    //         image_buffer_mapped.copy_from_nonoverlapping(image_data.as_ptr(), image_data.len());
-    //
-    //         device_info
-    //             .logical_device
-    //             .unmap_memory(image_buffer.buffer_memory);
-    //     }
-    //
-    //     let (image, device_memory) = image_util::create_image(
-    //         device_info,
-    //         instance,
-    //         dyn_image.width(),
-    //         dyn_image.height(),
-    //         vk::Format::R8G8B8A8_SRGB,
-    //         vk::ImageTiling::OPTIMAL,
-    //         vk::ImageUsageFlags::TRANSFER_DST | vk::ImageUsageFlags::SAMPLED,
-    //         MemoryPropertyFlags::DEVICE_LOCAL,
-    //     );
-    //
-    //     let cmd = BufferInfo::begin_single_time_command(device_info);
-    //
-    //     image_util::transition_image_layout(
-    //         device_info,
-    //         &cmd,
-    //         image,
-    //         vk::ImageLayout::UNDEFINED,
-    //         vk::ImageLayout::TRANSFER_DST_OPTIMAL,
-    //         false,
-    //     );
-    //
-    //     BufferInfo::end_single_time_command(device_info, cmd);
-    //
-    //     Self::copy_buffy_to_image(
-    //         device_info,
-    //         image_buffer.buffer,
-    //         image,
-    //         image_width,
-    //         image_height,
-    //     );
-    //     let cmd = BufferInfo::begin_single_time_command(device_info);
-    //
-    //     image_util::transition_image_layout(
-    //         device_info,
-    //         &cmd,
-    //         image,
-    //         vk::ImageLayout::TRANSFER_DST_OPTIMAL,
-    //         vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
-    //         false,
-    //     );
-    //
-    //     BufferInfo::end_single_time_command(device_info, cmd);
-    //
-    //     unsafe {
-    //         device_info
-    //             .logical_device
-    //             .destroy_buffer(image_buffer.buffer, None);
-    //         device_info
-    //             .logical_device
-    //             .free_memory(image_buffer.buffer_memory, None);
-    //     }
-    //
-    //     (image, device_memory)
-    // }
-
-    // fn copy_buffy_to_image(
-    //     device_info: &DeviceInfo,
-    //     buffer: vk::Buffer,
-    //     image: vk::Image,
-    //     width: u32,
-    //     height: u32,
-    // ) {
-    //     let command_buffer = BufferInfo::begin_single_time_command(device_info);
-    //
-    //     let region = vk::BufferImageCopy::default()
-    //         .buffer_offset(0)
-    //         .buffer_row_length(0)
-    //         .buffer_image_height(0)
-    //         .image_subresource(
-    //             vk::ImageSubresourceLayers::default()
-    //                 .aspect_mask(ImageAspectFlags::COLOR)
-    //                 .mip_level(0)
-    //                 .base_array_layer(0)
-    //                 .layer_count(1),
-    //         )
-    //         .image_offset(vk::Offset3D { x: 0, y: 0, z: 0 })
-    //         .image_extent(Extent3D {
-    //             height,
-    //             width,
-    //             depth: 1,
-    //         });
-    //
-    //     unsafe {
-    //         device_info.logical_device.cmd_copy_buffer_to_image(
-    //             command_buffer,
-    //             buffer,
-    //             image,
-    //             vk::ImageLayout::TRANSFER_DST_OPTIMAL,
-    //             &[region],
-    //         );
-    //     }
-    //
-    //     BufferInfo::end_single_time_command(device_info, command_buffer);
-    // }
-
-    // fn update_lighting_memory(&mut self) {
-    //     let current_frame = self.frame_manager.get_mut_current_frame();
-    //     current_frame.update_lighting_buffer(LightingUbo {
-    //         light_direction: vec3_to_vec4(&glm::normalize(&vec3(0.5, 1.0, 0.5))),
-    //
-    //         // w is intensity
-    //         light_color: vec4(1.0, 1.0, 1.0, 1.0),
-    //         ambient_light: vec4(0.1, 0.1, 0.1, 0.2),
-    //         cascade_depths: vec4(
-    //             self.cascades[0].cascade_depth,
-    //             self.cascades[1].cascade_depth,
-    //             self.cascades[2].cascade_depth,
-    //             1.0,
-    //         ),
-    //     })
-    // }
-    //
-    // fn update_cascade_memory(&mut self) {
-    //     let current_frame = self.frame_manager.get_mut_current_frame();
-    //
-    //     let shadow_cascades = self
-    //         .cascades
-    //         .iter()
-    //         .take(3)
-    //         .map(|casc| CascadeShadowUbo {
-    //             cascade_view_proj: casc.cascade_view_proj,
-    //         })
-    //         .collect::<Vec<_>>();
-    //
-    //     current_frame.update_shadow_map_buffer(shadow_cascades);
-    // }
-
-    // pub fn upload_meshes(
-    //     &mut self,
-    //     meshes: Vec<Rc<Mesh>>,
-    //     // Find a better way to pass the model information ...
-    //     model: Mat4,
-    // ) {
-    //     let mut mesh_data = vec![];
-    //     let mesh_count = meshes.len();
-    //     for mesh in meshes {
-    //         let vertices = &mesh.vertices;
-    //         let indices = &mesh.indices;
-    //
-    //         let vertex_buffer =
-    //             Self::create_vertex_buffer(&self.instance, &self.device_info, vertices);
-    //         let index_buffer =
-    //             Self::create_index_buffer(&self.instance, &self.device_info, indices);
-    //         println!("Created vertex buffer: vert_count: {}", vertices.len());
-    //
-    //         mesh_data.push(GPUMeshData {
-    //             vertex_buffer,
-    //             index_buffer,
-    //             index_count: indices.len() as u32,
-    //         });
-    //     }
-    //
-    //     self.gpu_mesh_data = mesh_data;
-    //
-    //     self.frame_manager.recreate_model_dynamic_buffer(
-    //         &self.device_info,
-    //         &self.instance,
-    //         mesh_count,
-    //     );
-    // }
-
-    // pub fn prepare_draw(&mut self) {
-    //     let current_frame = self.frame_manager.get_current_frame();
-    //
-    //     unsafe {
-    //         self.device_info
-    //             .logical_device
-    //             .wait_for_fences(&[current_frame.render_fence], true, u64::MAX)
-    //             .expect("Unable to wait for fence")
-    //     }
-    //
-    //     let image_result = unsafe {
-    //         self.swapchain_info.swapchain_device.acquire_next_image(
-    //             self.swapchain_info.swapchain,
-    //             u64::MAX,
-    //             current_frame.swapchain_semaphore,
-    //             vk::Fence::null(),
-    //         )
-    //     };
-    // }
-    //
-    // pub fn draw_frame(&mut self, _delta_time: f32) {
-    //     self.update_camera();
-    //     self.update_world();
-    //     self.update_cascades();
-    //     self.update_cascade_memory();
-    //     self.update_lighting_memory();
-    //
-    //     let current_frame = self.frame_manager.get_current_frame();
-    //     unsafe {
-    //         self.device_info
-    //             .logical_device
-    //             .wait_for_fences(&[current_frame.render_fence], true, u64::MAX)
-    //             .expect("Unable to wait for fence")
-    //     }
-    //
-    //     let image_result = unsafe {
-    //         self.swapchain_info.swapchain_device.acquire_next_image(
-    //             self.swapchain_info.swapchain,
-    //             u64::MAX,
-    //             current_frame.swapchain_semaphore,
-    //             vk::Fence::null(),
-    //         )
-    //     };
-    //
-    //     let (image_index, _is_sub_optimal) = match image_result {
-    //         Ok(result) => result,
-    //         Err(error_result) => match error_result {
-    //             vk::Result::ERROR_OU T_OF_DATE_KHR => {
-    //                 self.recreate_swapchain();
-    //                 println!("Error SWAPCHAIN");
-    //                 return;
-    //             }
-    //             _ => panic!(),
-    //         },
-    //     };
-    //
-    //     unsafe {
-    //         self.device_info
-    //             .logical_device
-    //             .reset_fences(&[current_frame.render_fence])
-    //             .expect("Unable to reset fence")
-    //     };
-    //
-    //     unsafe {
-    //         self.device_info
-    //             .logical_device
-    //             .reset_command_buffer(
-    //                 current_frame.command_buffer,
-    //                 vk::CommandBufferResetFlags::empty(),
-    //             )
-    //             .expect("Unable to reset command buffer");
-    //
-    //         let command_buffer_begin_info = vk::CommandBufferBeginInfo::default();
-    //
-    //         self.device_info
-    //             .logical_device
-    //             .begin_command_buffer(current_frame.command_buffer, &command_buffer_begin_info)
-    //             .expect("failed to begin command buffer")
-    //     }
-    //
-    //     image_util::transition_image_layout(
-    //         &self.device_info,
-    //         &current_frame.command_buffer,
-    //         current_frame.albedo_image.image,
-    //         vk::ImageLayout::UNDEFINED,
-    //         vk::ImageLayout::GENERAL,
-    //         false,
-    //     );
-    //
-    //     image_util::transition_image_layout(
-    //         &self.device_info,
-    //         &current_frame.command_buffer,
-    //         current_frame.pos_image.image,
-    //         vk::ImageLayout::UNDEFINED,
-    //         vk::ImageLayout::GENERAL,
-    //         false,
-    //     );
-    //
-    //     image_util::transition_image_layout(
-    //         &self.device_info,
-    //         &current_frame.command_buffer,
-    //         current_frame.normal_image.image,
-    //         vk::ImageLayout::UNDEFINED,
-    //         vk::ImageLayout::GENERAL,
-    //         false,
-    //     );
-    //
-    //     image_util::transition_image_layout(
-    //         &self.device_info,
-    //         &current_frame.command_buffer,
-    //         current_frame.depth_image.image,
-    //         vk::ImageLayout::UNDEFINED,
-    //         vk::ImageLayout::DEPTH_ATTACHMENT_OPTIMAL,
-    //         true,
-    //     );
-    //
-    //     image_util::transition_image_layout(
-    //         &self.device_info,
-    //         &current_frame.command_buffer,
-    //         current_frame.shadow_cascades[0].shadow_depth_image.image,
-    //         vk::ImageLayout::UNDEFINED,
-    //         vk::ImageLayout::DEPTH_ATTACHMENT_OPTIMAL,
-    //         true,
-    //     );
-    //
-    //     image_util::transition_image_layout(
-    //         &self.device_info,
-    //         &current_frame.command_buffer,
-    //         current_frame.shadow_cascades[1].shadow_depth_image.image,
-    //         vk::ImageLayout::UNDEFINED,
-    //         vk::ImageLayout::DEPTH_ATTACHMENT_OPTIMAL,
-    //         true,
-    //     );
-    //     image_util::transition_image_layout(
-    //         &self.device_info,
-    //         &current_frame.command_buffer,
-    //         current_frame.shadow_cascades[2].shadow_depth_image.image,
-    //         vk::ImageLayout::UNDEFINED,
-    //         vk::ImageLayout::DEPTH_ATTACHMENT_OPTIMAL,
-    //         true,
-    //     );
-    //
-    //     image_util::transition_image_layout(
-    //         &self.device_info,
-    //         &current_frame.command_buffer,
-    //         current_frame.draw_image.image,
-    //         vk::ImageLayout::UNDEFINED,
-    //         vk::ImageLayout::GENERAL,
-    //         false,
-    //     );
-    //
-    //     self.render_cascade_shadow_map();
-    //     self.render_scene();
-    //
-    //     image_util::transition_image_layout(
-    //         &self.device_info,
-    //         &current_frame.command_buffer,
-    //         current_frame.albedo_image.image,
-    //         vk::ImageLayout::GENERAL,
-    //         vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
-    //         false,
-    //     );
-    //
-    //     image_util::transition_image_layout(
-    //         &self.device_info,
-    //         &current_frame.command_buffer,
-    //         current_frame.pos_image.image,
-    //         vk::ImageLayout::GENERAL,
-    //         vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
-    //         false,
-    //     );
-    //
-    //     image_util::transition_image_layout(
-    //         &self.device_info,
-    //         &current_frame.command_buffer,
-    //         current_frame.normal_image.image,
-    //         vk::ImageLayout::GENERAL,
-    //         vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
-    //         false,
-    //     );
-    //
-    //     image_util::transition_image_layout(
-    //         &self.device_info,
-    //         &current_frame.command_buffer,
-    //         current_frame.depth_image.image,
-    //         vk::ImageLayout::DEPTH_ATTACHMENT_OPTIMAL,
-    //         vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
-    //         true,
-    //     );
-    //
-    //     image_util::transition_image_layout(
-    //         &self.device_info,
-    //         &current_frame.command_buffer,
-    //         current_frame.shadow_cascades[0].shadow_depth_image.image,
-    //         vk::ImageLayout::DEPTH_ATTACHMENT_OPTIMAL,
-    //         vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
-    //         true,
-    //     );
-    //
-    //     image_util::transition_image_layout(
-    //         &self.device_info,
-    //         &current_frame.command_buffer,
-    //         current_frame.shadow_cascades[1].shadow_depth_image.image,
-    //         vk::ImageLayout::DEPTH_ATTACHMENT_OPTIMAL,
-    //         vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
-    //         true,
-    //     );
-    //
-    //     image_util::transition_image_layout(
-    //         &self.device_info,
-    //         &current_frame.command_buffer,
-    //         current_frame.shadow_cascades[2].shadow_depth_image.image,
-    //         vk::ImageLayout::DEPTH_ATTACHMENT_OPTIMAL,
-    //         vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
-    //         true,
-    //     );
-    //
-    //     self.render_lighting();
-    //
-    //     unsafe {
-    //         image_util::transition_image_layout(
-    //             &self.device_info,
-    //             &current_frame.command_buffer,
-    //             current_frame.draw_image.image,
-    //             vk::ImageLayout::GENERAL,
-    //             vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
-    //             false,
-    //         );
-    //         image_util::transition_image_layout(
-    //             &self.device_info,
-    //             &current_frame.command_buffer,
-    //             self.swapchain_info.swapchain_images[image_index as usize],
-    //             vk::ImageLayout::UNDEFINED,
-    //             vk::ImageLayout::TRANSFER_DST_OPTIMAL,
-    //             false,
-    //         );
-    //
-    //         let draw_extend = Extent2D {
-    //             height: current_frame.draw_image.image_extent.height,
-    //             width: current_frame.draw_image.image_extent.width,
-    //         };
-    //
-    //         image_util::copy_image_to_image(
-    //             &self.device_info.logical_device,
-    //             &current_frame.command_buffer,
-    //             current_frame.draw_image.image,
-    //             self.swapchain_info.swapchain_images[image_index as usize],
-    //             draw_extend,
-    //             self.swapchain_info.swapchain_extent,
-    //         );
-    //         image_util::transition_image_layout(
-    //             &self.device_info,
-    //             &current_frame.command_buffer,
-    //             self.swapchain_info.swapchain_images[image_index as usize],
-    //             vk::ImageLayout::TRANSFER_DST_OPTIMAL,
-    //             vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
-    //             false,
-    //         );
-    //         image_util::transition_image_layout(
-    //             &self.device_info,
-    //             &current_frame.command_buffer,
-    //             self.swapchain_info.swapchain_images[image_index as usize],
-    //             vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
-    //             vk::ImageLayout::PRESENT_SRC_KHR,
-    //             false,
-    //         );
-    //
-    //         self.device_info
-    //             .logical_device
-    //             .end_command_buffer(current_frame.command_buffer)
-    //             .expect("failed to end command buffer");
-    //     }
-    //
-    //     let command_buffer_submit_info = [vk::CommandBufferSubmitInfo::default()
-    //         .command_buffer(current_frame.command_buffer)
-    //         .device_mask(0)];
-    //
-    //     let wait_info = [vk::SemaphoreSubmitInfo::default()
-    //         .semaphore(current_frame.swapchain_semaphore)
-    //         .stage_mask(vk::PipelineStageFlags2::COLOR_ATTACHMENT_OUTPUT_KHR)
-    //         .device_index(0)
-    //         .value(1)];
-    //
-    //     let signal_info = [vk::SemaphoreSubmitInfo::default()
-    //         .semaphore(current_frame.render_semaphore)
-    //         .stage_mask(vk::PipelineStageFlags2::ALL_GRAPHICS)
-    //         .device_index(0)
-    //         .value(1)];
-    //
-    //     let submit_info = vk::SubmitInfo2::default()
-    //         .command_buffer_infos(&command_buffer_submit_info)
-    //         .wait_semaphore_infos(&wait_info)
-    //         .signal_semaphore_infos(&signal_info);
-    //
-    //     unsafe {
-    //         self.device_info
-    //             .logical_device
-    //             .queue_submit2(
-    //                 self.device_info.queue_info.graphics_queue,
-    //                 &[submit_info],
-    //                 current_frame.render_fence,
-    //             )
-    //             .expect("Unable to submit draw command buffer");
-    //     }
-    //
-    //     let render_semaphores = [current_frame.render_semaphore];
-    //     let swapchains = [self.swapchain_info.swapchain];
-    //     let image_indices = [image_index];
-    //
-    //     let present_info = vk::PresentInfoKHR::default()
-    //         .wait_semaphores(&render_semaphores)
-    //         .swapchains(&swapchains)
-    //         .image_indices(&image_indices);
-    //
-    //     let present_result = unsafe {
-    //         self.swapchain_info
-    //             .swapchain_device
-    //             .queue_present(self.device_info.queue_info.present_queue, &present_info)
-    //     };
-    //
-    //     match present_result {
-    //         Ok(result) => result,
-    //         Err(error_result) => match error_result {
-    //             vk::Result::ERROR_OUT_OF_DATE_KHR => {
-    //                 self.recreate_swapchain();
-    //                 println!("Error SWAPCHAIN");
-    //                 return;
-    //             }
-    //             _ => panic!(),
-    //         },
-    //     };
-    //
-    //     self.frame_manager.advance_frame();
-    // }
-    //
-    // fn render_lighting(&self) {
-    //     let current_frame = self.frame_manager.get_current_frame();
-    //
-    //     let color_attachment = vk::RenderingAttachmentInfo::default()
-    //         .image_view(current_frame.draw_image.image_view)
-    //         .image_layout(vk::ImageLayout::GENERAL)
-    //         .load_op(vk::AttachmentLoadOp::CLEAR)
-    //         .store_op(vk::AttachmentStoreOp::STORE);
-    //
-    //     let color_attachments = [color_attachment];
-    //     let begin_render_info = vk::RenderingInfo::default()
-    //         .render_area(Rect2D {
-    //             extent: self.swapchain_info.swapchain_extent,
-    //             offset: vk::Offset2D { x: 0, y: 0 },
-    //         })
-    //         .layer_count(1)
-    //         .color_attachments(&color_attachments);
-    //
-    //     unsafe {
-    //         self.device_info
-    //             .logical_device
-    //             .cmd_begin_rendering(current_frame.command_buffer, &begin_render_info)
-    //     }
-    //
-    //     let width = self.swapchain_info.swapchain_extent.width as f32;
-    //     let height = self.swapchain_info.swapchain_extent.height as f32;
-    //     self.set_viewport_scissor(width, height);
-    //
-    //     unsafe {
-    //         self.device_info.logical_device.cmd_bind_pipeline(
-    //             current_frame.command_buffer,
-    //             PipelineBindPoint::GRAPHICS,
-    //             self.frame_manager.lighting_pipeline.pipelines[0],
-    //         );
-    //
-    //         self.device_info.logical_device.cmd_bind_descriptor_sets(
-    //             current_frame.command_buffer,
-    //             PipelineBindPoint::GRAPHICS,
-    //             self.frame_manager.lighting_pipeline.pipeline_layout,
-    //             0,
-    //             &[current_frame.descriptor_lighting_set],
-    //             &[],
-    //         );
-    //
-    //         self.device_info
-    //             .logical_device
-    //             .cmd_draw(current_frame.command_buffer, 3, 1, 0, 0);
-    //
-    //         self.device_info
-    //             .logical_device
-    //             .cmd_end_rendering(current_frame.command_buffer);
-    //     }
-    // }
-    //
-
-    //
-    // pub fn draw_indexed(
-    //     &self,
-    //     gpu_mesh: &GPUMeshData,
-    //     descriptor: &DescriptorInfo,
-    //     pipeline: &PipelineInfo,
-    //     model: Mat4,
-    //     index: u32,
-    //     alignment: u32,
-    // ) {
-    //     unsafe {
-    //         self.device_info.logical_device.cmd_bind_descriptor_sets(
-    //             self.command_buffer,
-    //             PipelineBindPoint::GRAPHICS,
-    //             pipeline.pipeline_layout,
-    //             0,
-    //             &[descriptor.descriptor_set],
-    //             &[0],
-    //         )
-    //     }
-    //
-    //     unsafe {
-    //         self.device_info.logical_device.cmd_bind_pipeline(
-    //             self.command_buffer,
-    //             PipelineBindPoint::GRAPHICS,
-    //             pipeline.pipelines[0],
-    //         );
-    //     }
-    //
-    //     let model_push_constant = ModelPushConstant { model };
-    //
-    //     unsafe {
-    //         self.device_info.logical_device.cmd_push_constants(
-    //             self.command_buffer,
-    //             pipeline.pipeline_layout,
-    //             ShaderStageFlags::VERTEX,
-    //             0,
-    //             std::slice::from_raw_parts(
-    //                 (&model_push_constant as *const ModelPushConstant) as *const u8,
-    //                 mem::size_of::<ModelPushConstant>(),
-    //             ),
-    //         )
-    //     }
-    //
-    //     unsafe {
-    //         self.device_info.logical_device.cmd_bind_index_buffer(
-    //             self.command_buffer,
-    //             gpu_mesh.index_buffer.buffer,
-    //             0,
-    //             vk::IndexType::UINT32,
-    //         );
-    //
-    //         self.device_info.logical_device.cmd_bind_vertex_buffers(
-    //             self.command_buffer,
-    //             0,
-    //             &[gpu_mesh.vertex_buffer.buffer],
-    //             &[0],
-    //         );
-    //
-    //         self.device_info.logical_device.cmd_draw_indexed(
-    //             self.command_buffer,
-    //             gpu_mesh.index_count,
-    //             1,
-    //             0,
-    //             0,
-    //             0,
-    //         );
-    //     }
-    //
-    //     unsafe {
-    //         self.device_info
-    //             .logical_device
-    //             .cmd_end_rendering(self.command_buffer);
-    //     }
-    // }
-    //
-    // fn render_scene(&self) {
-    //     let current_frame = self.frame_manager.get_current_frame();
-    //
-    //     let color_attachment = vk::RenderingAttachmentInfo::default()
-    //         .image_view(current_frame.albedo_image.image_view)
-    //         .image_layout(vk::ImageLayout::GENERAL)
-    //         .load_op(vk::AttachmentLoadOp::CLEAR)
-    //         .store_op(vk::AttachmentStoreOp::STORE);
-    //
-    //     let normal_attachment = vk::RenderingAttachmentInfo::default()
-    //         .image_view(current_frame.normal_image.image_view)
-    //         .image_layout(vk::ImageLayout::GENERAL)
-    //         .load_op(vk::AttachmentLoadOp::CLEAR)
-    //         .store_op(vk::AttachmentStoreOp::STORE);
-    //
-    //     let pos_attachment = vk::RenderingAttachmentInfo::default()
-    //         .image_view(current_frame.pos_image.image_view)
-    //         .image_layout(vk::ImageLayout::GENERAL)
-    //         .load_op(vk::AttachmentLoadOp::CLEAR)
-    //         .store_op(vk::AttachmentStoreOp::STORE);
-    //
-    //     let depth_attachment = vk::RenderingAttachmentInfo::default()
-    //         .image_view(current_frame.depth_image.image_view)
-    //         .image_layout(vk::ImageLayout::DEPTH_ATTACHMENT_OPTIMAL)
-    //         .load_op(vk::AttachmentLoadOp::CLEAR)
-    //         .store_op(vk::AttachmentStoreOp::STORE)
-    //         .clear_value(vk::ClearValue {
-    //             depth_stencil: vk::ClearDepthStencilValue {
-    //                 depth: 1.0,
-    //                 stencil: 0,
-    //             },
-    //         });
-    //
-    //     let color_attachments = [color_attachment, normal_attachment, pos_attachment];
-    //     let begin_render_info = vk::RenderingInfo::default()
-    //         .render_area(Rect2D {
-    //             extent: self.swapchain_info.swapchain_extent,
-    //             offset: vk::Offset2D { x: 0, y: 0 },
-    //         })
-    //         .layer_count(1)
-    //         .color_attachments(&color_attachments)
-    //         .depth_attachment(&depth_attachment);
-    //
-    //     unsafe {
-    //         self.device_info
-    //             .logical_device
-    //             .cmd_begin_rendering(current_frame.command_buffer, &begin_render_info)
-    //     }
-    //
-    //     let width = self.swapchain_info.swapchain_extent.width as f32;
-    //     let height = self.swapchain_info.swapchain_extent.height as f32;
-    //     self.set_viewport_scissor(width, height);
-    //
-    //     unsafe {
-    //         self.device_info.logical_device.cmd_bind_pipeline(
-    //             current_frame.command_buffer,
-    //             PipelineBindPoint::GRAPHICS,
-    //             self.frame_manager.gbuffer_pipeline.pipelines[0],
-    //         );
-    //     }
-    //
-    //     for (i, gpu_mesh) in self.gpu_mesh_data.iter().enumerate() {
-    //         unsafe {
-    //             self.device_info.logical_device.cmd_bind_descriptor_sets(
-    //                 current_frame.command_buffer,
-    //                 PipelineBindPoint::GRAPHICS,
-    //                 self.frame_manager.gbuffer_pipeline.pipeline_layout,
-    //                 0,
-    //                 &[current_frame.descriptor_gbuffer_set],
-    //                 &[i as u32 * self.frame_manager.model_ubo_alignment as u32],
-    //             )
-    //         }
-    //
-    //         unsafe {
-    //             self.device_info.logical_device.cmd_bind_index_buffer(
-    //                 current_frame.command_buffer,
-    //                 gpu_mesh.index_buffer.buffer,
-    //                 0,
-    //                 vk::IndexType::UINT32,
-    //             );
-    //
-    //             self.device_info.logical_device.cmd_bind_vertex_buffers(
-    //                 current_frame.command_buffer,
-    //                 0,
-    //                 &[gpu_mesh.vertex_buffer.buffer],
-    //                 &[0],
-    //             );
-    //
-    //             self.device_info.logical_device.cmd_draw_indexed(
-    //                 current_frame.command_buffer,
-    //                 gpu_mesh.index_count,
-    //                 1,
-    //                 0,
-    //                 0,
-    //                 0,
-    //             );
-    //         }
-    //     }
-    //
-    //     unsafe {
-    //         self.device_info
-    //             .logical_device
-    //             .cmd_end_rendering(current_frame.command_buffer);
-    //     }
-    // }
-    //
-    // fn render_cascade_shadow_map(&self) {
-    //     let current_frame = self.frame_manager.get_current_frame();
-    //
-    //     for cascade_index in 0..SHADOW_MAP_CASCADE_COUNT {
-    //         let depth_attachment = vk::RenderingAttachmentInfo::default()
-    //             .image_view(
-    //                 current_frame.shadow_cascades[cascade_index]
-    //                     .shadow_depth_image
-    //                     .image_view,
-    //             )
-    //             .image_layout(vk::ImageLayout::DEPTH_ATTACHMENT_OPTIMAL)
-    //             .load_op(vk::AttachmentLoadOp::CLEAR)
-    //             .store_op(vk::AttachmentStoreOp::STORE)
-    //             .clear_value(vk::ClearValue {
-    //                 depth_stencil: vk::ClearDepthStencilValue {
-    //                     depth: 1.0,
-    //                     stencil: 0,
-    //                 },
-    //             });
-    //
-    //         let begin_render_info = vk::RenderingInfo::default()
-    //             .render_area(Rect2D {
-    //                 extent: Extent2D {
-    //                     width: 4096,
-    //                     height: 4096,
-    //                 },
-    //                 offset: vk::Offset2D { x: 0, y: 0 },
-    //             })
-    //             .layer_count(1)
-    //             .depth_attachment(&depth_attachment);
-    //
-    //         unsafe {
-    //             self.device_info
-    //                 .logical_device
-    //                 .cmd_begin_rendering(current_frame.command_buffer, &begin_render_info)
-    //         }
-    //
-    //         self.set_viewport_scissor(4096.0, 4096.0);
-    //
-    //         unsafe {
-    //             self.device_info.logical_device.cmd_bind_pipeline(
-    //                 current_frame.command_buffer,
-    //                 PipelineBindPoint::GRAPHICS,
-    //                 self.frame_manager.shadow_map_pipeline.pipelines[0],
-    //             );
-    //         }
-    //
-    //         for (_i, gpu_mesh) in self.gpu_mesh_data.iter().enumerate() {
-    //             unsafe {
-    //                 self.device_info.logical_device.cmd_bind_descriptor_sets(
-    //                     current_frame.command_buffer,
-    //                     PipelineBindPoint::GRAPHICS,
-    //                     self.frame_manager.shadow_map_pipeline.pipeline_layout,
-    //                     0,
-    //                     &[current_frame.shadow_map_set],
-    //                     &[],
-    //                 )
-    //             }
-    //
-    //             let push = CascadeShadowPushConsts {
-    //                 pos: [0.0, 0.0, 0.0, 0.0],
-    //                 index: cascade_index as u32,
-    //             };
-    //
-    //             let x = bincode::serialize(&push).expect("");
-    //
-    //             unsafe {
-    //                 self.device_info.logical_device.cmd_push_constants(
-    //                     current_frame.command_buffer,
-    //                     self.frame_manager.shadow_map_pipeline.pipeline_layout,
-    //                     ShaderStageFlags::VERTEX,
-    //                     0,
-    //                     &x,
-    //                 );
-    //
-    //                 self.device_info.logical_device.cmd_bind_index_buffer(
-    //                     current_frame.command_buffer,
-    //                     gpu_mesh.index_buffer.buffer,
-    //                     0,
-    //                     vk::IndexType::UINT32,
-    //                 );
-    //
-    //                 self.device_info.logical_device.cmd_bind_vertex_buffers(
-    //                     current_frame.command_buffer,
-    //                     0,
-    //                     &[gpu_mesh.vertex_buffer.buffer],
-    //                     &[0],
-    //                 );
-    //
-    //                 self.device_info.logical_device.cmd_draw_indexed(
-    //                     current_frame.command_buffer,
-    //                     gpu_mesh.index_count,
-    //                     1,
-    //                     0,
-    //                     0,
-    //                     0,
-    //                 );
-    //             }
-    //         }
-    //
-    //         unsafe {
-    //             self.device_info
-    //                 .logical_device
-    //                 .cmd_end_rendering(current_frame.command_buffer);
-    //         }
-    //     }
-    // }
-    //
     fn set_viewport_scissor(&self, width: f32, height: f32) {
         let viewport = vk::Viewport {
             x: 0.0f32,
@@ -2392,88 +1915,15 @@ impl VulkanBackend {
                 .cmd_set_scissor(self.command_buffer, 0, &[scissor]);
         }
     }
-    //
-    // fn update_camera(&mut self) {
-    //     let view = self.camera.get_view_matrix();
-    //     let projection = self.camera.get_projection_matrix();
-    //
-    //     let current_frame = self.frame_manager.get_mut_current_frame();
-    //
-    //     let ubo = CameraMvpUbo {
-    //         view,
-    //         proj: projection,
-    //     };
-    //
-    //     current_frame.update_camera_mvp_buffer(ubo);
-    // }
-    //
-    // fn update_world(&mut self) {
-    //     let current_frame = self.frame_manager.get_mut_current_frame();
-    //
-    //     /*        let world_model_data = self
-    //         .gpu_mesh_data
-    //         .iter()
-    //         .map(|data| ModelDynamicUbo {
-    //             model: data,
-    //         })
-    //         .collect::<Vec<_>>();
-    //
-    //     current_frame.update_model_dynamic_buffer(world_model_data);
-    //
-    //     let mapped_memory_range = vk::MappedMemoryRange::default()
-    //         .memory(current_frame.model_dynamic_buffer.buffer_memory)
-    //         .size(self.frame_manager.model_ubo_alignment);
-    //
-    //     unsafe {
-    //         self.device_info
-    //             .logical_device
-    //             .flush_mapped_memory_ranges(&[mapped_memory_range])
-    //             .expect("failed to flush mapped memory range");
-    //     }*/
-    // }
-    //
-    //
-    //
-    //
-    // fn create_image_view(
-    //     device_info: &DeviceInfo,
-    //     image: vk::Image,
-    //     format: vk::Format,
-    //     image_aspect_flags: ImageAspectFlags,
-    // ) -> ImageView {
-    //     let view_info = vk::ImageViewCreateInfo::default()
-    //         .image(image)
-    //         .view_type(vk::ImageViewType::TYPE_2D)
-    //         .format(format)
-    //         .subresource_range(
-    //             vk::ImageSubresourceRange::default()
-    //                 .aspect_mask(image_aspect_flags)
-    //                 .base_mip_level(0)
-    //                 .level_count(1)
-    //                 .base_array_layer(0)
-    //                 .layer_count(1),
-    //         );
-    //
-    //     unsafe {
-    //         device_info
-    //             .logical_device
-    //             .create_image_view(&view_info, None)
-    //             .expect("failed to create image view")
-    //     }
-    // }
-    //
-    // fn create_texture_image_view(device_info: &DeviceInfo, image: vk::Image) -> ImageView {
-    //     Self::create_image_view(
-    //         device_info,
-    //         image,
-    //         vk::Format::R8G8B8A8_SRGB,
-    //         ImageAspectFlags::COLOR,
-    //     )
-    // }
 }
 
 impl Drop for VulkanBackend {
     fn drop(&mut self) {
+        // Registered so `destroy_all` frees them with the rest.
+        for pipeline in self.pipeline_compiles.finish() {
+            self.resource_registry.register_pipeline(pipeline);
+        }
+
         unsafe {
             self.device_info
                 .logical_device
@@ -2484,20 +1934,28 @@ impl Drop for VulkanBackend {
             self.resource_registry
                 .destroy_all(&self.device_info.logical_device);
 
+            for slot in &self.frames {
+                self.device_info
+                    .logical_device
+                    .destroy_semaphore(slot.acquire_semaphore, None);
+            }
+            for &semaphore in &self.render_semaphores {
+                self.device_info
+                    .logical_device
+                    .destroy_semaphore(semaphore, None);
+            }
             self.device_info
-                .logical_device
-                .destroy_semaphore(self.render_semaphore, None);
-            self.device_info
-                .logical_device
-                .destroy_semaphore(self.swapchain_semaphore, None);
-            self.device_info
-                .logical_device
-                .destroy_fence(self.render_fence, None);
+                .timelines
+                .destroy(&self.device_info.logical_device);
+            self.diagnostics.destroy(&self.device_info.logical_device);
 
-            // Command buffer is implicitly freed when its pool is destroyed.
+            // Command buffers are implicitly freed when their pool is destroyed.
             self.device_info
                 .logical_device
                 .destroy_command_pool(self.device_info.command_pool, None);
+            self.device_info
+                .logical_device
+                .destroy_command_pool(self.device_info.compute_command_pool, None);
 
             self.swapchain_info
                 .swapchain_device
@@ -2506,9 +1964,9 @@ impl Drop for VulkanBackend {
             self.device_info.logical_device.destroy_device(None);
 
             // Surface must be destroyed before the instance.
-            self._surface_info
+            self.surface_info
                 .surface_instance
-                .destroy_surface(self._surface_info.surface, None);
+                .destroy_surface(self.surface_info.surface, None);
 
             self.instance.destroy_instance(None);
             // _entry (ash::Entry) drops automatically — it owns the loaded library handle.
@@ -17,5 +17,6 @@ bitflags::bitflags! {
         const STORAGE       = 0b1000;
         const TRANSFER_SRC  = 0b0001_0000;
         const TRANSFER_DST  = 0b0010_0000;
+        const INDIRECT      = 0b0100_0000;
     }
 }
@@ -1,8 +1,9 @@
-use nalgebra::Matrix4;
+use crate::gpu_layout::GpuStruct;
+use common::math::Mat4;
 
 #[repr(C)]
-#[derive(Clone, Debug, Copy)]
+#[derive(Clone, Debug, Copy, GpuStruct)]
 pub struct CameraMvpUbo {
-    pub view: Matrix4<f32>,
-    pub proj: Matrix4<f32>,
+    pub view: Mat4,
+    pub proj: Mat4,
 }
@@ -0,0 +1,31 @@
+use common::PixelFormat;
+
+/// Optional device features the renderer adapts to. Queried when the device is created,
+/// and every supported one is enabled.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DeviceCapabilities {
+    /// BC1 to BC7 textures; all desktop GPUs.
+    pub texture_compression_bc: bool,
+    /// ASTC LDR textures; most mobile GPUs.
+    pub texture_compression_astc: bool,
+    /// Compute dispatches on the graphics queue. Passes that interleave compute with
+    /// drawing need it; Vulkan only guarantees compute on some queue family.
+    pub graphics_compute: bool,
+    /// `VK_KHR_present_id` and `VK_KHR_present_wait`, so the CPU can wait until a frame
+    /// is on screen.
+    pub present_wait: bool,
+    /// `vkCmdDrawIndexedIndirectCount`, so the GPU can pick how many indirect draws run.
+    /// Core in Vulkan 1.2 but optional; GPU culling falls back to direct draws without it.
+    pub draw_indirect_count: bool,
+}
+
+impl DeviceCapabilities {
+    /// True if images in `format` can be sampled directly.
+    pub fn supports(&self, format: PixelFormat) -> bool {
+        match format {
+            PixelFormat::Rgba8 => true,
+            PixelFormat::Bc5 | PixelFormat::Bc7 => self.texture_compression_bc,
+            PixelFormat::Astc4x4 => self.texture_compression_astc,
+        }
+    }
+}
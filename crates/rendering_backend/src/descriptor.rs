@@ -8,7 +8,7 @@ pub struct DescriptorSetHandle(pub usize);
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub struct DescriptorLayoutHandle(pub usize);
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum DescriptorType {
     UniformBuffer,
     StorageBuffer,
@@ -25,7 +25,7 @@ pub enum DescriptorWrite {
 }
 
 bitflags::bitflags! {
-    #[derive(Clone,Copy, Debug)]
+    #[derive(Clone,Copy, Debug, PartialEq, Eq, Hash)]
     pub struct ShaderStage: u32 {
         const VERTEX   = 0b0001;
         const FRAGMENT = 0b0010;
@@ -33,7 +33,7 @@ bitflags::bitflags! {
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct DescriptorBinding {
     pub binding: u32,
     pub descriptor_type: DescriptorType,
@@ -41,15 +41,39 @@ pub struct DescriptorBinding {
     pub stages: ShaderStage,
 }
 
-#[derive(Clone, Debug)]
+/// Bindings of a descriptor set layout. The backend creates one layout per distinct
+/// description, so sets allocated for equal descriptions are interchangeable.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct DescriptorLayoutDesc {
     pub bindings: Vec<DescriptorBinding>,
 }
 
+impl DescriptorLayoutDesc {
+    /// The same layout with bindings in ascending order, so descriptions that list them
+    /// differently compare equal.
+    pub(crate) fn normalized(mut self) -> Self {
+        self.bindings.sort_by_key(|binding| binding.binding);
+        self
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
 pub enum DescriptorValue {
     UniformBuffer(BufferHandle),
     StorageBuffer(BufferHandle),
     SampledImage(SampledImageInfo),
+    StorageImage(StorageImageInfo),
+}
+
+impl DescriptorValue {
+    /// The image bound, if any.
+    pub fn image(&self) -> Option<GpuImageHandle> {
+        match self {
+            DescriptorValue::SampledImage(info) => Some(info.image),
+            DescriptorValue::StorageImage(info) => Some(info.image),
+            DescriptorValue::UniformBuffer(_) | DescriptorValue::StorageBuffer(_) => None,
+        }
+    }
 }
 
 #[derive(Copy, Clone, Debug)]
@@ -58,7 +82,44 @@ pub struct SampledImageInfo {
     pub sampler: SamplerHandle,
 }
 
+/// One mip level of an image, written by compute shaders in the `General` layout. All
+/// layers of the mip are bound, as an array for cube maps.
+#[derive(Copy, Clone, Debug)]
+pub struct StorageImageInfo {
+    pub image: GpuImageHandle,
+    pub mip_level: u32,
+}
+
+#[derive(Copy, Clone, Debug)]
 pub struct DescriptorWriteDesc {
     pub binding: usize,
     pub value: DescriptorValue,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn binding(binding: u32, descriptor_type: DescriptorType) -> DescriptorBinding {
+        DescriptorBinding {
+            binding,
+            descriptor_type,
+            count: 1,
+            stages: ShaderStage::FRAGMENT,
+        }
+    }
+
+    #[test]
+    fn binding_order_does_not_distinguish_layouts() {
+        let uniform = binding(0, DescriptorType::UniformBuffer);
+        let texture = binding(1, DescriptorType::CombinedImageSampler);
+        let a = DescriptorLayoutDesc {
+            bindings: vec![uniform.clone(), texture.clone()],
+        };
+        let b = DescriptorLayoutDesc {
+            bindings: vec![texture, uniform],
+        };
+        assert_ne!(a, b);
+        assert_eq!(a.normalized(), b.normalized());
+    }
+}
@@ -0,0 +1,387 @@
+//! GPU buffer layout rules and validation against SPIR-V.
+//!
+//! `#[derive(GpuStruct)]` checks at compile time that a `#[repr(C)]` struct has the
+//! same field offsets as the std140/std430 block it feeds. [`validate_block`] compares
+//! the derived offsets with the block declared in a compiled shader.
+//! [`has_output_location`] checks a shader's output interface.
+
+use nalgebra::{Matrix3, Matrix4, Vector2, Vector3, Vector4};
+use std::collections::HashMap;
+use std::fmt;
+
+pub use rendering_macros::GpuStruct;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BlockLayout {
+    Std140,
+    Std430,
+}
+
+/// A type that can appear as a member of a GPU block.
+pub trait GpuType {
+    const ALIGN_STD140: usize;
+    const ALIGN_STD430: usize;
+    const SIZE_STD140: usize;
+    const SIZE_STD430: usize;
+}
+
+/// A block type whose layout was checked by `#[derive(GpuStruct)]`.
+pub trait GpuStruct: GpuType {
+    const LAYOUT: BlockLayout;
+    const FIELDS: &'static [GpuField];
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GpuField {
+    pub name: &'static str,
+    pub offset: usize,
+    pub size: usize,
+}
+
+/// Explicit padding bytes, for filling the holes std140 leaves after `vec3` and friends.
+#[repr(transparent)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Padding<const N: usize>([u8; N]);
+
+impl<const N: usize> Default for Padding<N> {
+    fn default() -> Self {
+        Self([0; N])
+    }
+}
+
+pub const fn round_up(value: usize, align: usize) -> usize {
+    value.div_ceil(align) * align
+}
+
+pub const fn max_align(aligns: &[usize]) -> usize {
+    let mut max = 1;
+    let mut i = 0;
+    while i < aligns.len() {
+        if aligns[i] > max {
+            max = aligns[i];
+        }
+        i += 1;
+    }
+    max
+}
+
+macro_rules! gpu_type {
+    ($ty:ty, $align:expr, $size:expr) => {
+        impl GpuType for $ty {
+            const ALIGN_STD140: usize = $align;
+            const ALIGN_STD430: usize = $align;
+            const SIZE_STD140: usize = $size;
+            const SIZE_STD430: usize = $size;
+        }
+    };
+}
+
+gpu_type!(f32, 4, 4);
+gpu_type!(i32, 4, 4);
+gpu_type!(u32, 4, 4);
+gpu_type!(Vector2<f32>, 8, 8);
+gpu_type!(Vector3<f32>, 16, 12);
+gpu_type!(Vector4<f32>, 16, 16);
+// Matrices are arrays of column vectors, and mat3 columns are padded to vec4.
+gpu_type!(Matrix3<f32>, 16, 48);
+gpu_type!(Matrix4<f32>, 16, 64);
+
+impl<const N: usize> GpuType for Padding<N> {
+    const ALIGN_STD140: usize = 1;
+    const ALIGN_STD430: usize = 1;
+    const SIZE_STD140: usize = N;
+    const SIZE_STD430: usize = N;
+}
+
+/// std140 rounds array strides up to a `vec4`; std430 keeps the element's own stride.
+impl<T: GpuType, const N: usize> GpuType for [T; N] {
+    const ALIGN_STD140: usize = round_up(T::ALIGN_STD140, 16);
+    const ALIGN_STD430: usize = T::ALIGN_STD430;
+    const SIZE_STD140: usize = round_up(T::SIZE_STD140, Self::ALIGN_STD140) * N;
+    const SIZE_STD430: usize = round_up(T::SIZE_STD430, T::ALIGN_STD430) * N;
+}
+
+/// Where a block lives in the shader interface.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BlockBinding {
+    Descriptor { set: u32, binding: u32 },
+    PushConstant,
+}
+
+#[derive(Debug)]
+pub enum LayoutError {
+    InvalidSpirv,
+    BlockNotFound(BlockBinding),
+    MemberCount { rust: usize, shader: usize },
+    MemberOffset { field: &'static str, rust: usize, shader: usize },
+    Size { rust: usize, shader: usize },
+}
+
+impl fmt::Display for LayoutError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LayoutError::InvalidSpirv => write!(f, "shader is not valid SPIR-V"),
+            LayoutError::BlockNotFound(binding) => write!(f, "no block at {binding:?}"),
+            LayoutError::MemberCount { rust, shader } => {
+                write!(f, "Rust struct has {rust} fields, shader block has {shader} members")
+            }
+            LayoutError::MemberOffset { field, rust, shader } => {
+                write!(f, "field `{field}` is at offset {rust} in Rust but {shader} in the shader")
+            }
+            LayoutError::Size { rust, shader } => {
+                write!(f, "Rust struct is {rust} bytes but the shader block needs {shader}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for LayoutError {}
+
+/// Member offsets and size of a block as declared in SPIR-V.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ReflectedBlock {
+    pub offsets: Vec<usize>,
+    /// End of the last member; std140 blocks may be padded past this on the Rust side.
+    pub size: usize,
+}
+
+/// Checks `T` against the block at `binding` in `spirv`. Padding fields are ignored.
+pub fn validate_block<T: GpuStruct>(spirv: &[u8], binding: BlockBinding) -> Result<(), LayoutError> {
+    let block = reflect_block(spirv, binding)?;
+    let fields: Vec<&GpuField> = T::FIELDS.iter().filter(|f| !f.name.starts_with('_')).collect();
+
+    if fields.len() != block.offsets.len() {
+        return Err(LayoutError::MemberCount { rust: fields.len(), shader: block.offsets.len() });
+    }
+    for (field, &shader) in fields.iter().zip(&block.offsets) {
+        if field.offset != shader {
+            return Err(LayoutError::MemberOffset { field: field.name, rust: field.offset, shader });
+        }
+    }
+    let rust = size_of::<T>();
+    if rust < block.size {
+        return Err(LayoutError::Size { rust, shader: block.size });
+    }
+    Ok(())
+}
+
+mod op {
+    pub const TYPE_INT: u32 = 21;
+    pub const TYPE_FLOAT: u32 = 22;
+    pub const TYPE_VECTOR: u32 = 23;
+    pub const TYPE_MATRIX: u32 = 24;
+    pub const TYPE_ARRAY: u32 = 28;
+    pub const TYPE_STRUCT: u32 = 30;
+    pub const TYPE_POINTER: u32 = 32;
+    pub const CONSTANT: u32 = 43;
+    pub const VARIABLE: u32 = 59;
+    pub const DECORATE: u32 = 71;
+    pub const MEMBER_DECORATE: u32 = 72;
+
+    pub const DECORATION_ARRAY_STRIDE: u32 = 6;
+    pub const DECORATION_MATRIX_STRIDE: u32 = 7;
+    pub const DECORATION_LOCATION: u32 = 30;
+    pub const DECORATION_BINDING: u32 = 33;
+    pub const DECORATION_DESCRIPTOR_SET: u32 = 34;
+    pub const DECORATION_OFFSET: u32 = 35;
+
+    pub const STORAGE_UNIFORM: u32 = 2;
+    pub const STORAGE_OUTPUT: u32 = 3;
+    pub const STORAGE_PUSH_CONSTANT: u32 = 9;
+    pub const STORAGE_STORAGE_BUFFER: u32 = 12;
+}
+
+const SPIRV_MAGIC: u32 = 0x0723_0203;
+
+enum SpirvType {
+    Scalar(usize),
+    Vector { component: u32, count: u32 },
+    Matrix { column: u32, count: u32 },
+    Array { length: u32 },
+    Struct(Vec<u32>),
+    Pointer { storage: u32, pointee: u32 },
+}
+
+/// The subset of a SPIR-V module needed to work out block layouts and outputs.
+#[derive(Default)]
+struct SpirvModule {
+    types: HashMap<u32, SpirvType>,
+    constants: HashMap<u32, u32>,
+    /// (pointer type, id) of every global variable.
+    variables: Vec<(u32, u32)>,
+    sets: HashMap<u32, u32>,
+    bindings: HashMap<u32, u32>,
+    locations: HashMap<u32, u32>,
+    array_strides: HashMap<u32, usize>,
+    member_offsets: HashMap<(u32, u32), usize>,
+    matrix_strides: HashMap<(u32, u32), usize>,
+}
+
+impl SpirvModule {
+    fn parse(spirv: &[u8]) -> Result<Self, LayoutError> {
+        if !spirv.len().is_multiple_of(4) || spirv.len() < 20 {
+            return Err(LayoutError::InvalidSpirv);
+        }
+        let words: Vec<u32> = spirv
+            .chunks_exact(4)
+            .map(|c| u32::from_le_bytes([c[0], c[1], c[2], c[3]]))
+            .collect();
+        if words[0] != SPIRV_MAGIC {
+            return Err(LayoutError::InvalidSpirv);
+        }
+
+        let mut module = SpirvModule::default();
+        let mut i = 5;
+        while i < words.len() {
+            let count = (words[i] >> 16) as usize;
+            if count == 0 || i + count > words.len() {
+                return Err(LayoutError::InvalidSpirv);
+            }
+            module.record(words[i] & 0xffff, &words[i + 1..i + count]);
+            i += count;
+        }
+        Ok(module)
+    }
+
+    fn record(&mut self, opcode: u32, operands: &[u32]) {
+        let ty = match (opcode, operands) {
+            (op::TYPE_INT | op::TYPE_FLOAT, &[id, width, ..]) => {
+                Some((id, SpirvType::Scalar(width as usize / 8)))
+            }
+            (op::TYPE_VECTOR, &[id, component, count]) => {
+                Some((id, SpirvType::Vector { component, count }))
+            }
+            (op::TYPE_MATRIX, &[id, column, count]) => {
+                Some((id, SpirvType::Matrix { column, count }))
+            }
+            (op::TYPE_ARRAY, &[id, _, length]) => Some((id, SpirvType::Array { length })),
+            (op::TYPE_STRUCT, &[id, ref members @ ..]) => {
+                Some((id, SpirvType::Struct(members.to_vec())))
+            }
+            (op::TYPE_POINTER, &[id, storage, pointee]) => {
+                Some((id, SpirvType::Pointer { storage, pointee }))
+            }
+            (op::CONSTANT, &[_, id, value, ..]) => {
+                self.constants.insert(id, value);
+                None
+            }
+            (op::VARIABLE, &[pointer, id, ..]) => {
+                self.variables.push((pointer, id));
+                None
+            }
+            (op::DECORATE, &[target, decoration, value, ..]) => {
+                match decoration {
+                    op::DECORATION_DESCRIPTOR_SET => {
+                        self.sets.insert(target, value);
+                    }
+                    op::DECORATION_BINDING => {
+                        self.bindings.insert(target, value);
+                    }
+                    op::DECORATION_LOCATION => {
+                        self.locations.insert(target, value);
+                    }
+                    op::DECORATION_ARRAY_STRIDE => {
+                        self.array_strides.insert(target, value as usize);
+                    }
+                    _ => {}
+                }
+                None
+            }
+            (op::MEMBER_DECORATE, &[target, member, decoration, value, ..]) => {
+                match decoration {
+                    op::DECORATION_OFFSET => {
+                        self.member_offsets.insert((target, member), value as usize);
+                    }
+                    op::DECORATION_MATRIX_STRIDE => {
+                        self.matrix_strides.insert((target, member), value as usize);
+                    }
+                    _ => {}
+                }
+                None
+            }
+            _ => None,
+        };
+        if let Some((id, ty)) = ty {
+            self.types.insert(id, ty);
+        }
+    }
+
+    fn find_block(&self, binding: BlockBinding) -> Option<u32> {
+        self.variables.iter().find_map(|&(pointer, id)| {
+            let Some(&SpirvType::Pointer { storage, pointee }) = self.types.get(&pointer) else {
+                return None;
+            };
+            let matches = match binding {
+                BlockBinding::PushConstant => storage == op::STORAGE_PUSH_CONSTANT,
+                BlockBinding::Descriptor { set, binding } => {
+                    (storage == op::STORAGE_UNIFORM || storage == op::STORAGE_STORAGE_BUFFER)
+                        && self.sets.get(&id).copied().unwrap_or(0) == set
+                        && self.bindings.get(&id) == Some(&binding)
+                }
+            };
+            matches.then_some(pointee)
+        })
+    }
+
+    fn has_output(&self, location: u32) -> bool {
+        self.variables.iter().any(|&(pointer, id)| {
+            matches!(
+                self.types.get(&pointer),
+                Some(&SpirvType::Pointer { storage: op::STORAGE_OUTPUT, .. })
+            ) && self.locations.get(&id) == Some(&location)
+        })
+    }
+
+    fn struct_layout(&self, id: u32) -> Result<ReflectedBlock, LayoutError> {
+        let Some(SpirvType::Struct(members)) = self.types.get(&id) else {
+            return Err(LayoutError::InvalidSpirv);
+        };
+
+        let mut offsets = Vec::with_capacity(members.len());
+        let mut size = 0;
+        for (index, &member) in members.iter().enumerate() {
+            let key = (id, index as u32);
+            let offset = *self.member_offsets.get(&key).ok_or(LayoutError::InvalidSpirv)?;
+            let member_size = self.type_size(member, self.matrix_strides.get(&key).copied())?;
+            size = size.max(offset + member_size);
+            offsets.push(offset);
+        }
+        Ok(ReflectedBlock { offsets, size })
+    }
+
+    fn type_size(&self, id: u32, matrix_stride: Option<usize>) -> Result<usize, LayoutError> {
+        match self.types.get(&id).ok_or(LayoutError::InvalidSpirv)? {
+            SpirvType::Scalar(bytes) => Ok(*bytes),
+            SpirvType::Vector { component, count } => {
+                Ok(self.type_size(*component, None)? * *count as usize)
+            }
+            SpirvType::Matrix { column, count } => {
+                let stride = match matrix_stride {
+                    Some(stride) => stride,
+                    None => round_up(self.type_size(*column, None)?, 16),
+                };
+                Ok(stride * *count as usize)
+            }
+            SpirvType::Array { length, .. } => {
+                let length = *self.constants.get(length).ok_or(LayoutError::InvalidSpirv)?;
+                let stride = *self.array_strides.get(&id).ok_or(LayoutError::InvalidSpirv)?;
+                Ok(stride * length as usize)
+            }
+            SpirvType::Struct(_) => Ok(self.struct_layout(id)?.size),
+            SpirvType::Pointer { .. } => Err(LayoutError::InvalidSpirv),
+        }
+    }
+}
+
+/// Reads member offsets of a uniform, storage or push-constant block from SPIR-V.
+pub fn reflect_block(spirv: &[u8], binding: BlockBinding) -> Result<ReflectedBlock, LayoutError> {
+    let module = SpirvModule::parse(spirv)?;
+    let block = module.find_block(binding).ok_or(LayoutError::BlockNotFound(binding))?;
+    module.struct_layout(block)
+}
+
+/// Whether `spirv` declares an output variable at `location`, e.g. whether a fragment
+/// shader writes a given color attachment.
+pub fn has_output_location(spirv: &[u8], location: u32) -> Result<bool, LayoutError> {
+    Ok(SpirvModule::parse(spirv)?.has_output(location))
+}
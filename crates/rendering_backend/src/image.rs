@@ -1,3 +1,5 @@
+use common::Color;
+
 #[derive(Copy, Clone, Debug)]
 pub struct GpuImageHandle(pub usize);
 
@@ -18,8 +20,19 @@ pub struct ImageDesc {
 #[derive(Clone, Copy, Debug)]
 pub enum TextureFormat {
     R8g8b8a8Unorm,
+    R8g8b8a8Srgb,
     R16g16b16a16Float,
     D32Float,
+    /// Unsigned integer IDs, e.g. the entity ID attachment.
+    R32Uint,
+    /// Block-compressed formats, sampled only. Check [`DeviceCapabilities`] first.
+    ///
+    /// [`DeviceCapabilities`]: crate::capabilities::DeviceCapabilities
+    Bc5Unorm,
+    Bc7Unorm,
+    Bc7Srgb,
+    Astc4x4Unorm,
+    Astc4x4Srgb,
     // add others as needed
 }
 
@@ -46,7 +59,7 @@ pub enum SampleCount {
 
 #[derive(Clone, Copy, Debug)]
 pub enum ClearValue {
-    Color([f32; 4]),
+    Color(Color),
     DepthStencil { depth: f32, stencil: u32 },
 }
 
@@ -1,9 +1,14 @@
+// Lets `#[derive(GpuStruct)]` refer to `::rendering_backend` from inside this crate.
+extern crate self as rendering_backend;
+
 pub mod backend_impl;
 pub mod buffer;
 pub mod camera;
+pub mod capabilities;
 pub mod descriptor;
+pub mod gpu_layout;
 pub mod image;
 pub mod memory;
 pub mod pipeline;
 pub mod sampler;
-pub mod transform;
+pub mod sync;
@@ -1,5 +1,134 @@
+use std::fmt;
+
 pub enum MemoryHint {
     GPUOnly,
     CPUToGPU,
     CPUWritable,
 }
+
+/// Device memory held by one kind of registered resource.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MemoryUsage {
+    pub live_count: usize,
+    pub live_bytes: u64,
+    /// Highest `live_bytes` seen so far.
+    pub peak_bytes: u64,
+    /// Bytes allocated during the last completed frame.
+    pub frame_allocated: u64,
+    /// Bytes released during the last completed frame.
+    pub frame_freed: u64,
+    current_allocated: u64,
+    current_freed: u64,
+}
+
+impl MemoryUsage {
+    pub(crate) fn allocate(&mut self, bytes: u64) {
+        self.live_count += 1;
+        self.live_bytes += bytes;
+        self.peak_bytes = self.peak_bytes.max(self.live_bytes);
+        self.current_allocated += bytes;
+    }
+
+    pub(crate) fn free(&mut self, bytes: u64) {
+        self.live_count -= 1;
+        self.live_bytes -= bytes;
+        self.current_freed += bytes;
+    }
+
+    /// Net change of `live_bytes` over the last completed frame.
+    pub fn frame_delta(&self) -> i64 {
+        self.frame_allocated as i64 - self.frame_freed as i64
+    }
+
+    fn end_frame(&mut self) {
+        self.frame_allocated = std::mem::take(&mut self.current_allocated);
+        self.frame_freed = std::mem::take(&mut self.current_freed);
+    }
+}
+
+/// Allocation accounting for resources registered with the backend. Transient staging
+/// buffers that never reach the registry are not counted.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GpuMemoryStats {
+    pub images: MemoryUsage,
+    pub buffers: MemoryUsage,
+    pub pipelines: usize,
+    /// Distinct pipeline layouts; pipelines with equal layouts share one.
+    pub pipeline_layouts: usize,
+    /// Distinct descriptor set layouts; equal descriptions share one.
+    pub descriptor_layouts: usize,
+    pub descriptor_sets: usize,
+    pub samplers: usize,
+}
+
+impl GpuMemoryStats {
+    pub fn live_bytes(&self) -> u64 {
+        self.images.live_bytes + self.buffers.live_bytes
+    }
+
+    /// Sum of the per-kind high-water marks. An upper bound on the true combined peak.
+    pub fn peak_bytes(&self) -> u64 {
+        self.images.peak_bytes + self.buffers.peak_bytes
+    }
+
+    pub(crate) fn end_frame(&mut self) {
+        self.images.end_frame();
+        self.buffers.end_frame();
+    }
+}
+
+impl fmt::Display for GpuMemoryStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "=== GPU memory ===")?;
+        for (name, usage) in [("images", &self.images), ("buffers", &self.buffers)] {
+            writeln!(
+                f,
+                "{:<8} {:>6} live  {:>10.2} MiB  peak {:>10.2} MiB  last frame {:+.2} MiB",
+                name,
+                usage.live_count,
+                mib(usage.live_bytes),
+                mib(usage.peak_bytes),
+                usage.frame_delta() as f64 / MIB,
+            )?;
+        }
+        writeln!(
+            f,
+            "total {:.2} MiB; {} pipelines ({} layouts), {} descriptor sets ({} layouts), {} samplers",
+            mib(self.live_bytes()),
+            self.pipelines,
+            self.pipeline_layouts,
+            self.descriptor_sets,
+            self.descriptor_layouts,
+            self.samplers
+        )
+    }
+}
+
+const MIB: f64 = 1024.0 * 1024.0;
+
+/// Bytes to mebibytes, for display.
+pub fn mib(bytes: u64) -> f64 {
+    bytes as f64 / MIB
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tracks_peaks_and_per_frame_deltas() {
+        let mut usage = MemoryUsage::default();
+        usage.allocate(300);
+        usage.allocate(200);
+        usage.free(300);
+        usage.end_frame();
+        assert_eq!(
+            (usage.live_count, usage.live_bytes, usage.peak_bytes),
+            (1, 200, 500)
+        );
+        assert_eq!(usage.frame_delta(), 200);
+
+        usage.end_frame();
+        assert_eq!((usage.frame_allocated, usage.frame_freed), (0, 0));
+    }
+}
@@ -1,13 +1,43 @@
 use crate::descriptor::{DescriptorLayoutHandle, ShaderStage};
 use crate::image::GpuImageHandle;
+use common::{PackedVertex, Vertex, VertexEncoding, VertexExtra, VertexSkin};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::mem::{offset_of, size_of};
 
 #[derive(Copy, Clone, Debug)]
 pub struct PipelineHandle(pub usize);
 
+/// A graphics pipeline compiling in the background, from
+/// `VulkanBackend::compile_graphics_pipeline`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct PendingPipelineHandle(pub usize);
+
+/// Why `VulkanBackend::compiled_pipeline` has no pipeline for a finished compile.
+#[derive(Debug)]
+pub enum PipelineCompileError {
+    /// Creating the pipeline panicked, with this message.
+    Panicked(String),
+}
+
+impl fmt::Display for PipelineCompileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PipelineCompileError::Panicked(message) => {
+                write!(f, "pipeline compile panicked: {}", message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for PipelineCompileError {}
+
 #[derive(Clone, Copy, Debug, Default)]
 pub enum PrimitiveTopology {
     #[default]
     TriangleList,
+    /// Each vertex after the second forms a triangle with the two before it.
+    TriangleStrip,
     LineList,
 }
 
@@ -24,21 +54,186 @@ pub struct PipelineDesc {
     pub depth_attachment: Option<GpuImageHandle>,
     pub push_constant_ranges: Vec<PushConstantDesc>,
     pub topology: PrimitiveTopology,
+    pub specialization: SpecializationConstants,
 }
 
-#[derive(Copy, Clone, Debug)]
+/// A compute pipeline. Dispatched between `VulkanBackend::begin_compute` and
+/// `submit_compute`, or into the frame between passes.
+#[derive(Clone, Debug)]
+pub struct ComputePipelineDesc {
+    pub shader: Vec<u8>,
+    pub layout: Vec<DescriptorLayoutHandle>,
+    pub push_constant_ranges: Vec<PushConstantDesc>,
+    pub specialization: SpecializationConstants,
+}
+
+/// Values for `layout(constant_id = N) const` declarations, applied to every shader stage
+/// of a pipeline. A stage only reads the ids it declares; constants left unset keep the
+/// default written in the shader. Lets one shader source be compiled into variants, e.g.
+/// per kernel size, without branching at runtime.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct SpecializationConstants {
+    /// Raw 32-bit value per constant id; bool, int, uint and float constants are all
+    /// four bytes.
+    values: BTreeMap<u32, u32>,
+}
+
+impl SpecializationConstants {
+    pub fn with_bool(self, constant_id: u32, value: bool) -> Self {
+        self.with_u32(constant_id, value as u32)
+    }
+
+    pub fn with_i32(self, constant_id: u32, value: i32) -> Self {
+        self.with_u32(constant_id, value as u32)
+    }
+
+    pub fn with_f32(self, constant_id: u32, value: f32) -> Self {
+        self.with_u32(constant_id, value.to_bits())
+    }
+
+    pub fn with_u32(mut self, constant_id: u32, value: u32) -> Self {
+        self.values.insert(constant_id, value);
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// `(constant_id, raw value)` pairs in ascending id order.
+    pub fn iter(&self) -> impl Iterator<Item = (u32, u32)> + '_ {
+        self.values.iter().map(|(&id, &value)| (id, value))
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub struct PushConstantDesc {
     pub stages: ShaderStage,
     pub offset: u32,
     pub size: usize,
 }
 
+/// Vertex buffers and attributes a pipeline reads. Empty for passes that generate
+/// their vertices in the shader, such as fullscreen quads.
 #[derive(Clone, Debug, Default)]
 pub struct VertexInputDesc {
     pub bindings: Vec<VertexBindingDesc>,
     pub attributes: Vec<VertexAttributeDesc>,
 }
 
+/// Binding that mesh `Vertex` data is bound to.
+pub const MESH_VERTEX_BINDING: u32 = 0;
+/// Binding that the optional `VertexExtra` stream is bound to.
+pub const MESH_EXTRA_BINDING: u32 = 1;
+/// Binding that the optional `VertexSkin` stream is bound to.
+pub const MESH_SKIN_BINDING: u32 = 2;
+
+impl VertexInputDesc {
+    /// `common::Vertex` at binding 0: position, UV, normal and tangent at locations 0-3.
+    pub fn mesh() -> Self {
+        Self::encoded_mesh(VertexEncoding::Full)
+    }
+
+    /// Mesh vertices at binding 0 in `encoding`, `common::Vertex` or
+    /// `common::PackedVertex`, with the same locations as [`Self::mesh`].
+    pub fn encoded_mesh(encoding: VertexEncoding) -> Self {
+        let attribute = |location, format, offset: usize| VertexAttributeDesc {
+            location,
+            binding: MESH_VERTEX_BINDING,
+            format,
+            offset: offset as u32,
+        };
+        let (stride, attributes) = match encoding {
+            VertexEncoding::Full => (
+                size_of::<Vertex>(),
+                vec![
+                    attribute(0, VertexFormat::Float32x3, offset_of!(Vertex, pos)),
+                    attribute(1, VertexFormat::Float32x2, offset_of!(Vertex, tex_coord)),
+                    attribute(2, VertexFormat::Float32x3, offset_of!(Vertex, normal)),
+                    attribute(3, VertexFormat::Float32x4, offset_of!(Vertex, tangent)),
+                ],
+            ),
+            VertexEncoding::Packed => {
+                type P = PackedVertex;
+                let snorm = VertexFormat::Snorm10_10_10_2;
+                (
+                    size_of::<P>(),
+                    vec![
+                        attribute(0, VertexFormat::Float16x4, offset_of!(P, pos)),
+                        attribute(1, VertexFormat::Float16x2, offset_of!(P, tex_coord)),
+                        attribute(2, snorm, offset_of!(P, normal)),
+                        attribute(3, snorm, offset_of!(P, tangent)),
+                    ],
+                )
+            }
+        };
+        Self {
+            bindings: vec![VertexBindingDesc {
+                binding: MESH_VERTEX_BINDING,
+                stride: stride as u32,
+                input_rate: VertexInputRate::Vertex,
+            }],
+            attributes,
+        }
+    }
+
+    /// [`Self::mesh`] plus `common::VertexExtra` at binding 1: the second UV set at
+    /// location 4 and the vertex color at location 5.
+    pub fn mesh_with_extras() -> Self {
+        Self::mesh().with_extras()
+    }
+
+    /// Adds `common::VertexExtra` at binding 1, as in [`Self::mesh_with_extras`].
+    pub fn with_extras(self) -> Self {
+        let mut desc = self;
+        desc.bindings.push(VertexBindingDesc {
+            binding: MESH_EXTRA_BINDING,
+            stride: size_of::<VertexExtra>() as u32,
+            input_rate: VertexInputRate::Vertex,
+        });
+        desc.attributes.extend([
+            VertexAttributeDesc {
+                location: 4,
+                binding: MESH_EXTRA_BINDING,
+                format: VertexFormat::Float32x2,
+                offset: offset_of!(VertexExtra, tex_coord1) as u32,
+            },
+            VertexAttributeDesc {
+                location: 5,
+                binding: MESH_EXTRA_BINDING,
+                format: VertexFormat::Float32x4,
+                offset: offset_of!(VertexExtra, color) as u32,
+            },
+        ]);
+        desc
+    }
+
+    /// Adds `common::VertexSkin` at binding 2: joint indices at location 6 and joint
+    /// weights at location 7.
+    pub fn with_skin(mut self) -> Self {
+        self.bindings.push(VertexBindingDesc {
+            binding: MESH_SKIN_BINDING,
+            stride: size_of::<VertexSkin>() as u32,
+            input_rate: VertexInputRate::Vertex,
+        });
+        self.attributes.extend([
+            VertexAttributeDesc {
+                location: 6,
+                binding: MESH_SKIN_BINDING,
+                format: VertexFormat::Uint32x4,
+                offset: offset_of!(VertexSkin, joints) as u32,
+            },
+            VertexAttributeDesc {
+                location: 7,
+                binding: MESH_SKIN_BINDING,
+                format: VertexFormat::Float32x4,
+                offset: offset_of!(VertexSkin, weights) as u32,
+            },
+        ]);
+        self
+    }
+}
+
 #[derive(Copy, Clone, Debug)]
 pub struct VertexBindingDesc {
     pub binding: u32,
@@ -70,6 +265,10 @@ pub enum VertexFormat {
     Uint32x2,
     Uint32x3,
     Uint32x4,
+    Float16x2,
+    Float16x4,
+    /// Three 10-bit and one 2-bit signed normalized value in 32 bits, x lowest.
+    Snorm10_10_10_2,
 }
 
 #[derive(Clone, Debug)]
@@ -78,6 +277,7 @@ pub struct RasterizationStateDesc {
     pub cull_mode: CullMode,
     pub front_face: FrontFace,
     pub depth_clamp_enable: bool,
+    /// Enables depth bias with factors supplied dynamically via `set_depth_bias`.
     pub depth_bias_enable: bool,
     pub discard_enable: bool,
 }
@@ -164,7 +364,7 @@ pub struct DepthStencilDesc {
     pub stencil_test_enable: bool,
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum CompareOp {
     Never,
     Less,
@@ -1,13 +1,13 @@
 #[derive(Copy, Clone, Debug)]
 pub struct SamplerHandle(pub usize);
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum Filter {
     Nearest,
     Linear,
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum SamplerAddressMode {
     Repeat,
     MirroredRepeat,
@@ -15,7 +15,9 @@ pub enum SamplerAddressMode {
     ClampToBorder,
 }
 
-#[derive(Clone, Copy, Debug)]
+/// Sampler state. The backend creates one sampler per distinct description and hands out
+/// the same handle for equal ones.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub struct SamplerDesc {
     pub mag_filter: Filter,
     pub min_filter: Filter,
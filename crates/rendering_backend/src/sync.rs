@@ -0,0 +1,50 @@
+/// A GPU queue timeline. Every submission to the queue signals the next value on it.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum GpuTimeline {
+    /// Frames and uploads.
+    Graphics,
+    /// Work submitted with `VulkanBackend::submit_compute`.
+    Compute,
+}
+
+/// A value on a timeline. It is reached once all work submitted up to it has finished,
+/// so later submissions (on any queue) can wait on it instead of on a fence.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct TimelinePoint {
+    pub timeline: GpuTimeline,
+    pub value: u64,
+}
+
+/// How an image is accessed next. The backend derives its layout, pipeline stages and
+/// access masks from the state, so a barrier only waits for the work that actually
+/// touched the image.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ResourceState {
+    /// Contents are undefined, e.g. right after creation.
+    Undefined,
+    ColorAttachment,
+    DepthAttachment,
+    /// Sampled by fragment shaders.
+    FragmentShaderRead,
+    /// Sampled by compute shaders.
+    ComputeShaderRead,
+    /// Written as a storage image by compute shaders.
+    ComputeShaderWrite,
+    TransferSrc,
+    TransferDst,
+    /// Handed to the presentation engine.
+    Present,
+}
+
+impl ResourceState {
+    /// True when no access in this state writes, so moving between two identical read
+    /// states needs no barrier.
+    pub fn is_read_only(self) -> bool {
+        matches!(
+            self,
+            ResourceState::FragmentShaderRead
+                | ResourceState::ComputeShaderRead
+                | ResourceState::TransferSrc
+        )
+    }
+}
@@ -0,0 +1,140 @@
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::{parse_macro_input, Data, DeriveInput, Fields, LitStr};
+
+/// Derives `rendering_backend::gpu_layout::GpuStruct` and checks at compile time that the
+/// `#[repr(C)]` layout matches the GLSL block layout.
+///
+/// Defaults to std140; use `#[gpu(std430)]` for storage buffers and push constants.
+#[proc_macro_derive(GpuStruct, attributes(gpu))]
+pub fn derive_gpu_struct(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match expand(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+fn expand(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let name = &input.ident;
+
+    if !input.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(&input.generics, "GpuStruct does not support generics"));
+    }
+    if !has_repr_c(input)? {
+        return Err(syn::Error::new_spanned(name, "GpuStruct requires #[repr(C)]"));
+    }
+
+    let std430 = parse_layout(input)?;
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(named) => &named.named,
+            _ => return Err(syn::Error::new_spanned(name, "GpuStruct requires named fields")),
+        },
+        _ => return Err(syn::Error::new_spanned(name, "GpuStruct can only be derived for structs")),
+    };
+
+    let (align_const, size_const, layout) = if std430 {
+        (format_ident!("ALIGN_STD430"), format_ident!("SIZE_STD430"), quote!(Std430))
+    } else {
+        (format_ident!("ALIGN_STD140"), format_ident!("SIZE_STD140"), quote!(Std140))
+    };
+    let layout_name = if std430 { "std430" } else { "std140" };
+
+    let idents: Vec<_> = fields.iter().filter_map(|f| f.ident.as_ref()).collect();
+    let types: Vec<_> = fields.iter().map(|f| &f.ty).collect();
+    let names: Vec<_> = idents.iter().map(|i| LitStr::new(&i.to_string(), i.span())).collect();
+    let offset_messages: Vec<_> = idents
+        .iter()
+        .map(|i| {
+            LitStr::new(
+                &format!("`{name}.{i}` is not at its {layout_name} offset; add explicit padding before it"),
+                i.span(),
+            )
+        })
+        .collect();
+    let size_messages: Vec<_> = idents
+        .iter()
+        .map(|i| {
+            LitStr::new(
+                &format!("`{name}.{i}` has a different size in {layout_name} than in Rust"),
+                i.span(),
+            )
+        })
+        .collect();
+    let struct_size_message = LitStr::new(
+        &format!("`{name}` size does not match {layout_name}; add trailing padding"),
+        name.span(),
+    );
+
+    let path = quote!(::rendering_backend::gpu_layout);
+
+    Ok(quote! {
+        impl #path::GpuType for #name {
+            const ALIGN_STD140: usize =
+                #path::round_up(#path::max_align(&[#(<#types as #path::GpuType>::ALIGN_STD140),*]), 16);
+            const ALIGN_STD430: usize =
+                #path::max_align(&[#(<#types as #path::GpuType>::ALIGN_STD430),*]);
+            const SIZE_STD140: usize = ::std::mem::size_of::<#name>();
+            const SIZE_STD430: usize = ::std::mem::size_of::<#name>();
+        }
+
+        impl #path::GpuStruct for #name {
+            const LAYOUT: #path::BlockLayout = #path::BlockLayout::#layout;
+            const FIELDS: &'static [#path::GpuField] = &[
+                #(#path::GpuField {
+                    name: #names,
+                    offset: ::std::mem::offset_of!(#name, #idents),
+                    size: ::std::mem::size_of::<#types>(),
+                }),*
+            ];
+        }
+
+        const _: () = {
+            let mut offset = 0usize;
+            #(
+                offset = #path::round_up(offset, <#types as #path::GpuType>::#align_const);
+                assert!(offset == ::std::mem::offset_of!(#name, #idents), #offset_messages);
+                assert!(
+                    <#types as #path::GpuType>::#size_const == ::std::mem::size_of::<#types>(),
+                    #size_messages
+                );
+                offset += <#types as #path::GpuType>::#size_const;
+            )*
+            let align = <#name as #path::GpuType>::#align_const;
+            assert!(#path::round_up(offset, align) == ::std::mem::size_of::<#name>(), #struct_size_message);
+        };
+    })
+}
+
+fn has_repr_c(input: &DeriveInput) -> syn::Result<bool> {
+    let mut repr_c = false;
+    for attr in input.attrs.iter().filter(|a| a.path().is_ident("repr")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("C") {
+                repr_c = true;
+            }
+            Ok(())
+        })?;
+    }
+    Ok(repr_c)
+}
+
+fn parse_layout(input: &DeriveInput) -> syn::Result<bool> {
+    let mut std430 = false;
+    for attr in input.attrs.iter().filter(|a| a.path().is_ident("gpu")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("std430") {
+                std430 = true;
+                Ok(())
+            } else if meta.path.is_ident("std140") {
+                std430 = false;
+                Ok(())
+            } else {
+                Err(meta.error("expected `std140` or `std430`"))
+            }
+        })?;
+    }
+    Ok(std430)
+}
@@ -1,5 +1,10 @@
 use ecs::component::Component;
+use ecs::snapshot::{HandleRemap, Persist, SnapshotError};
+use common::{Handle, MeshData};
+use nalgebra_glm::Vec3;
+use serde::{Deserialize, Serialize};
 use crate::dynamic_aabb::shape::{Shape, ShapeId, ShapeStore};
+use crate::SpatialWorld;
 
 /// Opaque handle to a registered collider. Wraps a Vec index.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -61,3 +66,50 @@ impl Default for ColliderStore {
 pub struct ColliderComponent {
     pub id: ColliderId,
 }
+
+/// A collider's shape as stored in snapshots, with mesh handles replaced by stable ids.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum SavedShape {
+    Sphere { radius: f32 },
+    Cuboid { half_extents: Vec3 },
+    Capsule { half_height: f32, radius: f32 },
+    Mesh { mesh: u128 },
+}
+
+/// Saves the collider's shape, read from the `SpatialWorld` the remap provides as
+/// context, and registers it there again as a new collider on load.
+impl Persist for ColliderComponent {
+    type Saved = SavedShape;
+
+    fn save(&self, remap: &mut dyn HandleRemap) -> Result<SavedShape, SnapshotError> {
+        let shape = remap
+            .context_mut::<SpatialWorld>()?
+            .get_collider_shape(self.id)
+            .cloned()
+            .ok_or_else(|| SnapshotError::UnresolvedHandle {
+                kind: std::any::type_name::<ColliderId>(),
+                id: self.id.raw().to_string(),
+            })?;
+        Ok(match shape {
+            Shape::Sphere { radius } => SavedShape::Sphere { radius },
+            Shape::Cuboid { half_extents } => SavedShape::Cuboid { half_extents },
+            Shape::Capsule { half_height, radius } => SavedShape::Capsule { half_height, radius },
+            Shape::Mesh { mesh_handle } => SavedShape::Mesh {
+                mesh: remap.save_handle::<MeshData>(mesh_handle.raw())?,
+            },
+        })
+    }
+
+    fn load(saved: SavedShape, remap: &mut dyn HandleRemap) -> Result<Self, SnapshotError> {
+        let shape = match saved {
+            SavedShape::Sphere { radius } => Shape::Sphere { radius },
+            SavedShape::Cuboid { half_extents } => Shape::Cuboid { half_extents },
+            SavedShape::Capsule { half_height, radius } => Shape::Capsule { half_height, radius },
+            SavedShape::Mesh { mesh } => Shape::Mesh {
+                mesh_handle: Handle::new(remap.load_handle::<MeshData>(mesh)?),
+            },
+        };
+        let id = remap.context_mut::<SpatialWorld>()?.register_collider(shape);
+        Ok(Self { id })
+    }
+}
@@ -1,4 +1,5 @@
 pub mod collider;
+pub mod query;
 pub mod shape;
 
 use crate::ColliderId;
@@ -191,7 +192,6 @@ pub(crate) type NodeId = usize;
 pub(crate) struct Node {
     aabb: AABB,
     /// Some for leaf nodes, None for internal nodes.
-    #[allow(dead_code)]
     collider: Option<ColliderId>,
     parent: Option<NodeId>,
     left: Option<NodeId>,
@@ -224,4 +224,44 @@ impl AABB {
         let d = self.upper - self.lower;
         2.0 * (d.x * d.y + d.y * d.z + d.z * d.x)
     }
+
+    /// Returns true if the two boxes overlap or touch.
+    pub fn intersects(&self, other: &AABB) -> bool {
+        self.lower.x <= other.upper.x
+            && self.upper.x >= other.lower.x
+            && self.lower.y <= other.upper.y
+            && self.upper.y >= other.lower.y
+            && self.lower.z <= other.upper.z
+            && self.upper.z >= other.lower.z
+    }
+
+    /// Returns true if `point` lies inside or on the box.
+    pub fn contains_point(&self, point: &Vec3) -> bool {
+        point.x >= self.lower.x
+            && point.x <= self.upper.x
+            && point.y >= self.lower.y
+            && point.y <= self.upper.y
+            && point.z >= self.lower.z
+            && point.z <= self.upper.z
+    }
+
+    /// Squared distance from `point` to the closest point of the box. Zero if inside.
+    pub fn distance_squared_to_point(&self, point: &Vec3) -> f32 {
+        let closest = min2(&max2(point, &self.lower), &self.upper);
+        nalgebra_glm::length2(&(closest - point))
+    }
+
+    /// Returns true if the sphere overlaps the box.
+    pub fn intersects_sphere(&self, center: &Vec3, radius: f32) -> bool {
+        self.distance_squared_to_point(center) <= radius * radius
+    }
+
+    /// Returns the box grown by `margin` on every side.
+    pub fn expanded(&self, margin: f32) -> Self {
+        let margin = Vec3::repeat(margin);
+        Self {
+            lower: self.lower - margin,
+            upper: self.upper + margin,
+        }
+    }
 }
@@ -0,0 +1,297 @@
+use super::{DynamicAABBTree, NodeId, AABB};
+use crate::ColliderId;
+use nalgebra_glm::Vec3;
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+/// A half-line starting at `origin`. `direction` does not need to be normalised, but
+/// hit distances are reported in multiples of its length.
+#[derive(Clone, Copy, Debug)]
+pub struct Ray {
+    pub origin: Vec3,
+    pub direction: Vec3,
+}
+
+impl Ray {
+    pub fn new(origin: Vec3, direction: Vec3) -> Self {
+        Self { origin, direction }
+    }
+
+    /// Returns the point at parameter `t` along the ray.
+    pub fn at(&self, t: f32) -> Vec3 {
+        self.origin + self.direction * t
+    }
+
+    /// Slab test against `aabb`. Returns the entry parameter in `[0, max_t]`, or None.
+    /// A ray starting inside the box hits at `t = 0`.
+    pub fn intersect_aabb(&self, aabb: &AABB, max_t: f32) -> Option<f32> {
+        let mut t_min = 0.0f32;
+        let mut t_max = max_t;
+
+        for axis in 0..3 {
+            let origin = self.origin[axis];
+            let direction = self.direction[axis];
+            let lower = aabb.lower[axis];
+            let upper = aabb.upper[axis];
+
+            if direction.abs() < f32::EPSILON {
+                if origin < lower || origin > upper {
+                    return None;
+                }
+                continue;
+            }
+
+            let inv = 1.0 / direction;
+            let mut t0 = (lower - origin) * inv;
+            let mut t1 = (upper - origin) * inv;
+            if t0 > t1 {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+            t_min = t_min.max(t0);
+            t_max = t_max.min(t1);
+            if t_min > t_max {
+                return None;
+            }
+        }
+
+        Some(t_min)
+    }
+}
+
+/// Closest collider hit by a ray cast. `distance` is the ray parameter of the AABB entry point.
+#[derive(Clone, Copy, Debug)]
+pub struct RayHit {
+    pub collider: ColliderId,
+    pub distance: f32,
+}
+
+/// Heap entry ordered so that `BinaryHeap` pops the smallest distance first.
+struct NodeDistance {
+    node: NodeId,
+    distance: f32,
+}
+
+impl PartialEq for NodeDistance {
+    fn eq(&self, other: &Self) -> bool {
+        self.distance == other.distance
+    }
+}
+
+impl Eq for NodeDistance {}
+
+impl PartialOrd for NodeDistance {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for NodeDistance {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.distance.total_cmp(&self.distance)
+    }
+}
+
+impl DynamicAABBTree {
+    fn root(&self) -> Option<NodeId> {
+        (self.node_count > 0).then_some(self.root_index)
+    }
+
+    /// Calls `visit` for every leaf whose AABB passes `overlaps`. Subtrees whose
+    /// bounds fail the test are skipped entirely.
+    fn visit_leaves(&self, overlaps: impl Fn(&AABB) -> bool, mut visit: impl FnMut(ColliderId)) {
+        let Some(root) = self.root() else {
+            return;
+        };
+
+        let mut stack = vec![root];
+        while let Some(node_id) = stack.pop() {
+            let node = &self.nodes[node_id];
+            if !overlaps(&node.aabb) {
+                continue;
+            }
+            if let Some(collider) = node.collider {
+                visit(collider);
+                continue;
+            }
+            stack.extend(node.left);
+            stack.extend(node.right);
+        }
+    }
+
+    /// Appends every collider whose leaf AABB overlaps `aabb` to `out`.
+    pub fn query_aabb(&self, aabb: &AABB, out: &mut Vec<ColliderId>) {
+        self.visit_leaves(|bounds| bounds.intersects(aabb), |id| out.push(id));
+    }
+
+    /// Appends every collider whose leaf AABB overlaps the sphere to `out`.
+    pub fn query_sphere(&self, center: &Vec3, radius: f32, out: &mut Vec<ColliderId>) {
+        self.visit_leaves(|bounds| bounds.intersects_sphere(center, radius), |id| out.push(id));
+    }
+
+    /// Appends every collider whose leaf AABB contains `point` to `out`.
+    pub fn query_point(&self, point: &Vec3, out: &mut Vec<ColliderId>) {
+        self.visit_leaves(|bounds| bounds.contains_point(point), |id| out.push(id));
+    }
+
+    /// Returns the closest leaf hit along `ray` within `max_distance`.
+    /// Children are visited nearest first and pruned against the best hit so far.
+    pub fn raycast(&self, ray: &Ray, max_distance: f32) -> Option<RayHit> {
+        self.cast(ray, 0.0, max_distance, |_| true)
+    }
+
+    /// Sweeps a sphere of `radius` along `ray` and returns the closest leaf accepted by
+    /// `filter`. Bounds are grown by the radius, so hits near box corners come early.
+    pub fn cast(
+        &self,
+        ray: &Ray,
+        radius: f32,
+        max_distance: f32,
+        filter: impl Fn(ColliderId) -> bool,
+    ) -> Option<RayHit> {
+        let root = self.root()?;
+        let mut best: Option<RayHit> = None;
+        let mut limit = max_distance;
+        let bounds = |node: NodeId| self.nodes[node].aabb.expanded(radius);
+
+        let mut heap = BinaryHeap::new();
+        if let Some(t) = ray.intersect_aabb(&bounds(root), limit) {
+            heap.push(NodeDistance { node: root, distance: t });
+        }
+
+        while let Some(NodeDistance { node: node_id, distance }) = heap.pop() {
+            if distance > limit {
+                break;
+            }
+            let node = &self.nodes[node_id];
+            if let Some(collider) = node.collider {
+                if filter(collider) {
+                    best = Some(RayHit { collider, distance });
+                    limit = distance;
+                }
+                continue;
+            }
+            for child in [node.left, node.right].into_iter().flatten() {
+                if let Some(t) = ray.intersect_aabb(&bounds(child), limit) {
+                    heap.push(NodeDistance { node: child, distance: t });
+                }
+            }
+        }
+
+        best
+    }
+
+    /// Returns up to `k` colliders nearest to `point`, sorted by distance to their
+    /// leaf AABB. Colliders containing the point report a distance of zero.
+    pub fn nearest(&self, point: &Vec3, k: usize) -> Vec<(ColliderId, f32)> {
+        let mut result = Vec::with_capacity(k);
+        let Some(root) = self.root() else {
+            return result;
+        };
+        if k == 0 {
+            return result;
+        }
+
+        let mut heap = BinaryHeap::new();
+        heap.push(NodeDistance {
+            node: root,
+            distance: self.nodes[root].aabb.distance_squared_to_point(point),
+        });
+
+        // Best-first: a leaf popped from the heap is closer than anything still queued.
+        while let Some(NodeDistance { node: node_id, distance }) = heap.pop() {
+            let node = &self.nodes[node_id];
+            if let Some(collider) = node.collider {
+                result.push((collider, distance.sqrt()));
+                if result.len() == k {
+                    break;
+                }
+                continue;
+            }
+            for child in [node.left, node.right].into_iter().flatten() {
+                heap.push(NodeDistance {
+                    node: child,
+                    distance: self.nodes[child].aabb.distance_squared_to_point(point),
+                });
+            }
+        }
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nalgebra_glm::vec3;
+
+    fn unit_box_at(center: Vec3) -> AABB {
+        let half = vec3(0.5, 0.5, 0.5);
+        AABB::new(center - half, center + half)
+    }
+
+    fn tree_with_row(count: u32) -> DynamicAABBTree {
+        let mut tree = DynamicAABBTree::default();
+        for i in 0..count {
+            tree.insert_leaf(unit_box_at(vec3(i as f32 * 2.0, 0.0, 0.0)), ColliderId(i));
+        }
+        tree
+    }
+
+    #[test]
+    fn empty_tree_returns_nothing() {
+        let tree = DynamicAABBTree::default();
+        let mut out = vec![];
+        tree.query_sphere(&Vec3::zeros(), 10.0, &mut out);
+        assert!(out.is_empty());
+        assert!(tree.raycast(&Ray::new(Vec3::zeros(), vec3(1.0, 0.0, 0.0)), 100.0).is_none());
+        assert!(tree.nearest(&Vec3::zeros(), 3).is_empty());
+    }
+
+    #[test]
+    fn overlap_queries_find_expected_leaves() {
+        let tree = tree_with_row(8);
+
+        let mut out = vec![];
+        tree.query_aabb(&AABB::new(vec3(1.8, -1.0, -1.0), vec3(4.2, 1.0, 1.0)), &mut out);
+        out.sort_by_key(|id| id.0);
+        assert_eq!(out, vec![ColliderId(1), ColliderId(2)]);
+
+        out.clear();
+        tree.query_sphere(&vec3(14.0, 0.0, 0.0), 1.0, &mut out);
+        assert_eq!(out, vec![ColliderId(7)]);
+    }
+
+    #[test]
+    fn raycast_returns_closest_hit() {
+        let tree = tree_with_row(8);
+        let ray = Ray::new(vec3(-5.0, 0.0, 0.0), vec3(1.0, 0.0, 0.0));
+
+        let hit = tree.raycast(&ray, 100.0).expect("ray should hit the first box");
+        assert_eq!(hit.collider, ColliderId(0));
+        assert!((hit.distance - 4.5).abs() < 1e-5);
+
+        assert!(tree.raycast(&ray, 4.0).is_none());
+    }
+
+    #[test]
+    fn sphere_cast_skips_filtered_leaves_and_inflates_bounds() {
+        let tree = tree_with_row(8);
+        let ray = Ray::new(vec3(-5.0, 0.0, 0.0), vec3(1.0, 0.0, 0.0));
+
+        let hit = tree
+            .cast(&ray, 0.25, 100.0, |id| id != ColliderId(0))
+            .expect("sphere should hit the second box");
+        assert_eq!(hit.collider, ColliderId(1));
+        assert!((hit.distance - 6.25).abs() < 1e-5);
+    }
+
+    #[test]
+    fn nearest_is_sorted_and_bounded() {
+        let tree = tree_with_row(8);
+        let nearest = tree.nearest(&vec3(6.2, 0.0, 0.0), 3);
+
+        let ids = nearest.iter().map(|(id, _)| id.0).collect::<Vec<_>>();
+        assert_eq!(ids, vec![3, 4, 2]);
+        assert!(nearest.windows(2).all(|w| w[0].1 <= w[1].1));
+    }
+}
@@ -1,6 +1,7 @@
 mod dynamic_aabb;
 
-pub use dynamic_aabb::collider::{ColliderId, ColliderComponent};
+pub use dynamic_aabb::collider::{ColliderId, ColliderComponent, SavedShape};
+pub use dynamic_aabb::query::{Ray, RayHit};
 pub use dynamic_aabb::shape::{Shape, ShapeId};
 pub use dynamic_aabb::AABB;
 
@@ -16,7 +17,8 @@ use dynamic_aabb::DynamicAABBTree;
 /// The typical per-frame loop is:
 /// 1. Call `clear_tree` to reset the tree.
 /// 2. For each entity with a collider and a transform, call `insert_collider`.
-/// 3. Query `iter_aabbs` for debug visualisation or downstream checks.
+/// 3. Run spatial queries (`raycast`, `query_sphere`, `nearest`, ...) or read
+///    `iter_aabbs` for debug visualisation.
 pub struct SpatialWorld {
     shape_store: ShapeStore,
     collider_store: ColliderStore,
@@ -97,6 +99,55 @@ impl SpatialWorld {
         self.tree.insert_leaf(aabb, id);
     }
 
+    // ---- Query API ----------------------------------------------------------
+
+    /// Returns every collider whose bounds overlap `aabb`.
+    pub fn query_aabb(&self, aabb: &AABB) -> Vec<ColliderId> {
+        let mut out = Vec::new();
+        self.tree.query_aabb(aabb, &mut out);
+        out
+    }
+
+    /// Returns every collider whose bounds overlap the sphere, e.g. "all enemies
+    /// within radius". Bounds are tested, not exact shapes.
+    pub fn query_sphere(&self, center: Vec3, radius: f32) -> Vec<ColliderId> {
+        let mut out = Vec::new();
+        self.tree.query_sphere(&center, radius, &mut out);
+        out
+    }
+
+    /// Returns every collider whose bounds contain `point`.
+    pub fn query_point(&self, point: Vec3) -> Vec<ColliderId> {
+        let mut out = Vec::new();
+        self.tree.query_point(&point, &mut out);
+        out
+    }
+
+    /// Casts a ray against collider bounds and returns the closest hit within
+    /// `max_distance`. Use for picking and line-of-sight checks.
+    pub fn raycast(&self, ray: &Ray, max_distance: f32) -> Option<RayHit> {
+        self.tree.raycast(ray, max_distance)
+    }
+
+    /// Sweeps a sphere of `radius` along `ray` and returns the closest collider within
+    /// `max_distance` that `filter` accepts, e.g. to skip the caster's own collider.
+    /// A radius of 0 is a filtered raycast.
+    pub fn sphere_cast(
+        &self,
+        ray: &Ray,
+        radius: f32,
+        max_distance: f32,
+        filter: impl Fn(ColliderId) -> bool,
+    ) -> Option<RayHit> {
+        self.tree.cast(ray, radius, max_distance, filter)
+    }
+
+    /// Returns up to `k` colliders closest to `point`, nearest first, with the
+    /// distance to each collider's bounds.
+    pub fn nearest(&self, point: Vec3, k: usize) -> Vec<(ColliderId, f32)> {
+        self.tree.nearest(&point, k)
+    }
+
     /// Returns an iterator over the AABBs of all nodes in the tree, including
     /// internal nodes. Internal node AABBs are union bounds over their subtree,
     /// which can be useful for visualising tree structure.
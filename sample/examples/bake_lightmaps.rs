@@ -0,0 +1,62 @@
+//! Bakes ambient occlusion lightmaps offline for a cube resting on the floor, writes them
+//! as PNGs and exits. Runs without a window: it only cooks the sample's meshes and
+//! reads them back.
+//!
+//! `cargo run -p sample --example bake_lightmaps [output_dir]`
+//!
+//! The output directory defaults to `lightmaps`. Copy the PNGs into the content
+//! directory to import them, then load each with `load_texture` into a
+//! `LightmapComponent` on the matching entity.
+
+use asset_pipeline::cook_pending;
+use asset_pipeline::lightmap_baker::{
+    AoBakeSettings, BakeInstance, bake_ambient_occlusion, save_lightmap,
+};
+use assets::emesh::read_emesh;
+use common::{Guid, MeshData};
+use nalgebra_glm::{Mat4, vec3};
+use project::{AssetRegistry, Project, resolve_cooked_path};
+use std::path::PathBuf;
+
+#[allow(dead_code)]
+mod assets_ids {
+    #[allow(unused_imports)]
+    use common::{Guid, guid};
+    include!(concat!(env!("OUT_DIR"), "/assets.rs"));
+}
+
+fn main() {
+    let output_dir = PathBuf::from(std::env::args().nth(1).unwrap_or("lightmaps".into()));
+    let project =
+        Project::load("sample/sample.eproj").expect("failed to load the sample project");
+    let registry = AssetRegistry::load_or_scan(&project.cache_dir, &project.content_dir)
+        .expect("failed to scan the sample content directory");
+    cook_pending(&registry, &project.cache_dir, &project.content_dir);
+
+    let read = |guid: Guid| -> MeshData {
+        let path = resolve_cooked_path(&project.cache_dir, &guid, "emesh");
+        read_emesh(&path)
+            .unwrap_or_else(|e| panic!("failed to read '{}': {:?}", path.display(), e))
+    };
+    let floor = read(assets_ids::FLOOR_OBJ);
+    let cube = read(assets_ids::CUBE_OBJ);
+
+    // The cube spans -1..1, so it rests on the floor at y = -1.
+    let scene = [
+        ("floor", &floor, Mat4::new_translation(&vec3(0.0, -1.0, 0.0))),
+        ("cube", &cube, Mat4::identity()),
+    ];
+    let instances: Vec<BakeInstance> = scene
+        .iter()
+        .map(|&(_, mesh, transform)| BakeInstance { mesh, transform })
+        .collect();
+    let lightmaps = bake_ambient_occlusion(&instances, &AoBakeSettings::default());
+
+    std::fs::create_dir_all(&output_dir).expect("failed to create the output directory");
+    for ((name, _, _), lightmap) in scene.iter().zip(&lightmaps) {
+        let path = output_dir.join(format!("{}_lightmap.png", name));
+        save_lightmap(lightmap, &path)
+            .unwrap_or_else(|e| panic!("failed to write '{}': {}", path.display(), e));
+        println!("Wrote {}", path.display());
+    }
+}
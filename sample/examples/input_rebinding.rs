@@ -0,0 +1,146 @@
+//! Runtime key rebinding. Press the "jump" key (Space at first) to make the cube hop.
+//! Press F1, then any other key, and jump moves to that key. Escape cancels a pending
+//! rebind.
+//!
+//! Systems only read input, so rebinding happens in a `GameState`, which gets the
+//! engine context before systems run each frame.
+//!
+//! `cargo run -p sample --example input_rebinding`
+
+use app::{App, GameState, Scene, Transition};
+use common::Color;
+use config::config::LightShadowSettings;
+use core::EngineContext;
+use core::components::{
+    CameraComponent, DirectionalLightComponent, GlobalTransformComponent, MaterialComponent,
+    MeshComponent, TransformComponent,
+};
+use core::system::{Context, System, SystemFunction};
+use core::types::transform::Transform;
+use ecs::command_buffer::Commands;
+use ecs::query::Query;
+use input::{InputBinding, InputEventKind, KeyCode};
+use nalgebra_glm::vec3;
+
+#[allow(dead_code)]
+mod assets {
+    #[allow(unused_imports)]
+    use common::{Guid, guid};
+    include!(concat!(env!("OUT_DIR"), "/assets.rs"));
+}
+
+const JUMP: &str = "jump";
+const GRAVITY: f32 = 20.0;
+
+/// Vertical speed of the hopping cube.
+#[derive(Default)]
+struct Hop {
+    velocity: f32,
+}
+
+fn hop_system(
+    mut query: Query<(&mut TransformComponent, &mut MeshComponent)>,
+    ctx: &mut Context,
+    _commands: &mut Commands,
+) {
+    let mut hop = ctx.res_mut::<Hop>();
+    for (transform, _) in query.iter() {
+        let grounded = transform.location.y <= 0.0;
+        if grounded && ctx.input.is_action_just_pressed(JUMP) {
+            hop.velocity = 8.0;
+        }
+        hop.velocity -= GRAVITY * ctx.dt;
+        transform.location.y = (transform.location.y + hop.velocity * ctx.dt).max(0.0);
+    }
+}
+
+/// Listens for F1 and rebinds [`JUMP`] to the next key pressed after it.
+struct Rebinding {
+    listening: bool,
+}
+
+impl GameState for Rebinding {
+    fn name(&self) -> &str {
+        "rebinding"
+    }
+
+    fn systems(&mut self) -> Vec<Box<dyn SystemFunction>> {
+        vec![Box::new(System::new(hop_system))]
+    }
+
+    fn on_enter(&mut self, ctx: &mut EngineContext, scene: &mut Scene) {
+        ctx.input_mut()
+            .bind_action(JUMP, vec![InputBinding::Key(KeyCode::Space)]);
+        ctx.insert_resource(Hop::default());
+
+        let cube_mesh = ctx.load_mesh(assets::CUBE_OBJ);
+        let brick = ctx.load_material(assets::BRICK_EMAT);
+        let world = ctx.get_world();
+        scene.add(world.create_entity((
+            TransformComponent(Transform::default()),
+            GlobalTransformComponent::default(),
+            MeshComponent {
+                mesh_handle: cube_mesh,
+            },
+            MaterialComponent {
+                material_handle: brick,
+            },
+        )));
+        scene.add(world.create_entity((
+            TransformComponent(Transform::default().with_location(vec3(0.0, 2.0, 8.0))),
+            CameraComponent {
+                near_clip: 0.1,
+                far_clip: 100.0,
+                fov: 60.0,
+                active: true,
+            },
+        )));
+        scene.add(world.create_entity((
+            TransformComponent(Transform::default().with_rotation(vec3(-0.8, 0.5, 0.0))),
+            DirectionalLightComponent {
+                color: Color::WHITE,
+                intensity: 1.0,
+                shadow: LightShadowSettings::default(),
+            },
+        )));
+        println!("Space jumps. Press F1 to rebind it.");
+    }
+
+    fn update(&mut self, ctx: &mut EngineContext, _dt: f32) -> Transition {
+        let pressed = ctx
+            .input()
+            .events()
+            .iter()
+            .find_map(|event| match event.kind {
+                InputEventKind::KeyPressed(key) => Some(key),
+                _ => None,
+            });
+        match pressed {
+            Some(KeyCode::F1) if !self.listening => {
+                self.listening = true;
+                println!("Press a key for {JUMP}, or Escape to cancel.");
+            }
+            Some(KeyCode::Escape) if self.listening => {
+                self.listening = false;
+                println!("Rebind cancelled.");
+            }
+            Some(key) if self.listening => {
+                self.listening = false;
+                ctx.input_mut()
+                    .bind_action(JUMP, vec![InputBinding::Key(key)]);
+                println!("{JUMP} is now bound to {key:?}.");
+            }
+            _ => {}
+        }
+        Transition::None
+    }
+}
+
+fn main() {
+    let mut app = App::builder()
+        .project("sample/sample.eproj")
+        .window_title("Input rebinding")
+        .build();
+    app.push_state(Rebinding { listening: false });
+    app.run();
+}
@@ -0,0 +1,143 @@
+//! Stress test: 10,000 cubes sharing one mesh and material, bobbing every frame. The
+//! renderer batches them into instanced indirect draws, so the frame time printed each
+//! second should stay flat as long as batching works.
+//!
+//! `cargo run -p sample --release --example instanced_cubes`
+
+use app::App;
+use common::Color;
+use config::config::LightShadowSettings;
+use core::components::{
+    CameraComponent, CameraControllerComponent, DirectionalLightComponent,
+    GlobalTransformComponent, MaterialComponent, MeshComponent, TransformComponent,
+};
+use core::system::{Context, System};
+use core::types::transform::Transform;
+use ecs::command_buffer::Commands;
+use ecs::query::Query;
+use input::{AnalogSource, AxisAction, AxisBinding, InputBinding, KeyCode};
+use nalgebra_glm::vec3;
+
+#[allow(dead_code)]
+mod assets {
+    #[allow(unused_imports)]
+    use common::{Guid, guid};
+    include!(concat!(env!("OUT_DIR"), "/assets.rs"));
+}
+
+const GRID: i32 = 100;
+const SPACING: f32 = 2.5;
+
+/// Frame statistics accumulated between reports.
+#[derive(Default)]
+struct FrameStats {
+    elapsed: f32,
+    frames: u32,
+    time: f32,
+}
+
+fn bob_system(
+    mut query: Query<(&mut TransformComponent, &mut MeshComponent)>,
+    ctx: &mut Context,
+    _commands: &mut Commands,
+) {
+    let mut stats = ctx.res_mut::<FrameStats>();
+    stats.time += ctx.dt;
+    let time = stats.time;
+    for (transform, _) in query.iter() {
+        let phase = (transform.location.x + transform.location.z) * 0.2;
+        transform.location.y = (time * 2.0 + phase).sin();
+    }
+}
+
+fn report_system(_query: Query<&mut CameraComponent>, ctx: &mut Context, _commands: &mut Commands) {
+    let mut stats = ctx.res_mut::<FrameStats>();
+    stats.elapsed += ctx.dt;
+    stats.frames += 1;
+    if stats.elapsed >= 1.0 {
+        let ms = stats.elapsed * 1000.0 / stats.frames as f32;
+        println!("{} frames, {ms:.2} ms/frame", stats.frames);
+        stats.elapsed = 0.0;
+        stats.frames = 0;
+    }
+}
+
+fn main() {
+    let mut app = App::builder()
+        .project("sample/sample.eproj")
+        .window_title("10k instanced cubes")
+        .vsync(false)
+        .build();
+
+    let ctx = app.engine_context_mut();
+    let input = ctx.input_mut();
+    input.bind_action("move_forward", vec![InputBinding::Key(KeyCode::W)]);
+    input.bind_action("move_backward", vec![InputBinding::Key(KeyCode::S)]);
+    input.bind_action("move_left", vec![InputBinding::Key(KeyCode::A)]);
+    input.bind_action("move_right", vec![InputBinding::Key(KeyCode::D)]);
+    input.bind_axis(
+        AxisAction::HORIZONTAL,
+        AxisBinding::composite("move_right", "move_left"),
+    );
+    input.bind_axis(
+        AxisAction::VERTICAL,
+        AxisBinding::composite("move_forward", "move_backward"),
+    );
+    input.bind_axis(
+        AxisAction::MOUSE_X,
+        AxisBinding::analog(AnalogSource::MouseX, 1.0),
+    );
+    input.bind_axis(
+        AxisAction::MOUSE_Y,
+        AxisBinding::analog(AnalogSource::MouseY, 1.0),
+    );
+    input.grab_cursor();
+
+    ctx.insert_resource(FrameStats::default());
+    let cube_mesh = ctx.load_mesh(assets::CUBE_OBJ);
+    let brick = ctx.load_material(assets::BRICK_EMAT);
+
+    let setup = ctx.world_setup();
+    let half = GRID as f32 * SPACING * 0.5;
+    for x in 0..GRID {
+        for z in 0..GRID {
+            let location = vec3(x as f32 * SPACING - half, 0.0, z as f32 * SPACING - half);
+            setup.world.create_entity((
+                TransformComponent(Transform::default().with_location(location)),
+                GlobalTransformComponent::default(),
+                MeshComponent {
+                    mesh_handle: cube_mesh,
+                },
+                MaterialComponent {
+                    material_handle: brick,
+                },
+            ));
+        }
+    }
+    setup.world.create_entity((
+        TransformComponent(
+            Transform::default()
+                .with_location(vec3(0.0, 40.0, half + 20.0))
+                .with_rotation(vec3(-0.6, 0.0, 0.0)),
+        ),
+        CameraComponent {
+            near_clip: 0.1,
+            far_clip: 1000.0,
+            fov: 70.0,
+            active: true,
+        },
+        CameraControllerComponent::new(50.0),
+    ));
+    setup.world.create_entity((
+        TransformComponent(Transform::default().with_rotation(vec3(-0.7, 0.6, 0.0))),
+        DirectionalLightComponent {
+            color: Color::WHITE,
+            intensity: 1.0,
+            shadow: LightShadowSettings::default(),
+        },
+    ));
+
+    ctx.register_system(Box::new(System::new(bob_system)));
+    ctx.register_system(Box::new(System::new(report_system)));
+    app.run();
+}
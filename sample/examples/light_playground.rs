@@ -0,0 +1,210 @@
+//! Shadows and lights: a sun whose cascaded shadows follow it as it turns, plus point
+//! lights circling a field of pillars.
+//!
+//! Q/E turn the sun, R/F raise and lower it, Space adds a point light, Backspace
+//! removes them all, and 1/2 toggle the sky fog. WASD and the mouse fly the camera.
+//!
+//! `cargo run -p sample --example light_playground`
+
+use app::App;
+use common::Color;
+use config::config::LightShadowSettings;
+use core::components::{
+    CameraComponent, CameraControllerComponent, DirectionalLightComponent,
+    GlobalTransformComponent, MaterialComponent, MeshComponent, PointLightComponent,
+    TransformComponent,
+};
+use core::environment::{FogMode, WorldEnvironment};
+use core::system::{Context, System};
+use core::types::transform::Transform;
+use ecs::command_buffer::Commands;
+use ecs::entity::Entity;
+use ecs::query::Query;
+use input::{AnalogSource, AxisAction, AxisBinding, InputBinding, KeyCode};
+use nalgebra_glm::vec3;
+
+#[allow(dead_code)]
+mod assets {
+    #[allow(unused_imports)]
+    use common::{Guid, guid};
+    include!(concat!(env!("OUT_DIR"), "/assets.rs"));
+}
+
+/// Radius of the circle point lights travel on.
+const ORBIT_RADIUS: f32 = 8.0;
+
+fn sun_system(
+    mut query: Query<(&mut TransformComponent, &mut DirectionalLightComponent)>,
+    ctx: &mut Context,
+    _commands: &mut Commands,
+) {
+    let turn = ctx.input.get_axis("turn_sun");
+    let raise = ctx.input.get_axis("raise_sun");
+    for (transform, _) in query.iter() {
+        transform.rotation.y += turn * ctx.dt;
+        // Keeps the sun above the horizon so the floor stays lit.
+        transform.rotation.x = (transform.rotation.x - raise * ctx.dt).clamp(-1.5, -0.1);
+    }
+}
+
+fn point_light_system(
+    mut query: Query<(Entity, &mut TransformComponent, &mut PointLightComponent)>,
+    ctx: &mut Context,
+    commands: &mut Commands,
+) {
+    let mut count = 0;
+    for (entity, transform, _) in query.iter() {
+        let location = transform.location;
+        let angle = location.z.atan2(location.x) + ctx.dt * 0.5;
+        transform.location = vec3(angle.cos(), 0.0, angle.sin()) * ORBIT_RADIUS;
+        transform.location.y = location.y;
+        if ctx.input.is_action_just_pressed("clear_lights") {
+            commands.remove_entity(entity);
+        }
+        count += 1;
+    }
+
+    if ctx.input.is_action_just_pressed("add_light") {
+        let angle = count as f32 * 2.4;
+        let location = vec3(angle.cos() * ORBIT_RADIUS, 1.5, angle.sin() * ORBIT_RADIUS);
+        commands.spawn_entity((
+            TransformComponent(Transform::default().with_location(location)),
+            PointLightComponent::new(Color::hsv(angle.to_degrees() % 360.0, 0.8, 1.0), 4.0, 10.0),
+        ));
+    }
+}
+
+fn fog_system(_query: Query<&mut CameraComponent>, ctx: &mut Context, _commands: &mut Commands) {
+    let mode = if ctx.input.is_action_just_pressed("fog_off") {
+        FogMode::Off
+    } else if ctx.input.is_action_just_pressed("fog_on") {
+        FogMode::ExponentialSquared
+    } else {
+        return;
+    };
+    ctx.res_mut::<WorldEnvironment>().fog.mode = mode;
+}
+
+fn bind_inputs(ctx: &mut core::EngineContext) {
+    let input = ctx.input_mut();
+    let keys = [
+        ("move_forward", KeyCode::W),
+        ("move_backward", KeyCode::S),
+        ("move_left", KeyCode::A),
+        ("move_right", KeyCode::D),
+        ("sun_left", KeyCode::Q),
+        ("sun_right", KeyCode::E),
+        ("sun_up", KeyCode::R),
+        ("sun_down", KeyCode::F),
+        ("add_light", KeyCode::Space),
+        ("clear_lights", KeyCode::Backspace),
+        ("fog_off", KeyCode::Key1),
+        ("fog_on", KeyCode::Key2),
+    ];
+    for (action, key) in keys {
+        input.bind_action(action, vec![InputBinding::Key(key)]);
+    }
+    input.bind_axis("turn_sun", AxisBinding::composite("sun_left", "sun_right"));
+    input.bind_axis("raise_sun", AxisBinding::composite("sun_up", "sun_down"));
+    input.bind_axis(
+        AxisAction::HORIZONTAL,
+        AxisBinding::composite("move_right", "move_left"),
+    );
+    input.bind_axis(
+        AxisAction::VERTICAL,
+        AxisBinding::composite("move_forward", "move_backward"),
+    );
+    input.bind_axis(
+        AxisAction::MOUSE_X,
+        AxisBinding::analog(AnalogSource::MouseX, 1.0),
+    );
+    input.bind_axis(
+        AxisAction::MOUSE_Y,
+        AxisBinding::analog(AnalogSource::MouseY, 1.0),
+    );
+    input.grab_cursor();
+}
+
+fn main() {
+    let mut app = App::builder()
+        .project("sample/sample.eproj")
+        .window_title("Light playground")
+        .build();
+
+    let ctx = app.engine_context_mut();
+    bind_inputs(ctx);
+
+    {
+        let mut environment = ctx.resources().get_mut::<WorldEnvironment>();
+        environment.clear_color = Color::srgb(0.45, 0.55, 0.7);
+        environment.ambient_intensity = 0.05;
+        environment.fog.color = environment.clear_color;
+        environment.fog.density = 0.03;
+    }
+
+    let floor_mesh = ctx.load_mesh(assets::FLOOR_OBJ);
+    let cube_mesh = ctx.load_mesh(assets::CUBE_OBJ);
+    let brick = ctx.load_material(assets::BRICK_EMAT);
+
+    let setup = ctx.world_setup();
+    setup.world.create_entity((
+        TransformComponent(Transform::default()),
+        GlobalTransformComponent::default(),
+        MeshComponent {
+            mesh_handle: floor_mesh,
+        },
+        MaterialComponent {
+            material_handle: brick,
+        },
+    ));
+    // Pillars of varying height, so shadows of different lengths overlap.
+    for x in -3i32..=3 {
+        for z in -3i32..=3 {
+            let height = 1.0 + ((x * 7 + z * 3).rem_euclid(5)) as f32;
+            setup.world.create_entity((
+                TransformComponent(
+                    Transform::default()
+                        .with_location(vec3(x as f32 * 4.0, height, z as f32 * 4.0))
+                        .with_scale(vec3(0.5, height, 0.5)),
+                ),
+                GlobalTransformComponent::default(),
+                MeshComponent {
+                    mesh_handle: cube_mesh,
+                },
+                MaterialComponent {
+                    material_handle: brick,
+                },
+            ));
+        }
+    }
+    setup.world.create_entity((
+        TransformComponent(
+            Transform::default()
+                .with_location(vec3(0.0, 12.0, 28.0))
+                .with_rotation(vec3(-0.4, 0.0, 0.0)),
+        ),
+        CameraComponent {
+            near_clip: 0.1,
+            far_clip: 500.0,
+            fov: 70.0,
+            active: true,
+        },
+        CameraControllerComponent::new(20.0),
+    ));
+    setup.world.create_entity((
+        TransformComponent(Transform::default().with_rotation(vec3(-0.5, 0.8, 0.0))),
+        DirectionalLightComponent {
+            color: Color::WHITE,
+            intensity: 1.0,
+            shadow: LightShadowSettings {
+                shadow_distance: Some(80.0),
+                ..Default::default()
+            },
+        },
+    ));
+
+    ctx.register_system(Box::new(System::new(sun_system)));
+    ctx.register_system(Box::new(System::new(point_light_system)));
+    ctx.register_system(Box::new(System::new(fog_system)));
+    app.run();
+}
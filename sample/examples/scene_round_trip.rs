@@ -0,0 +1,123 @@
+//! Saves the scene to bytes, clears the world, loads the bytes back and checks that the
+//! same entities and environment came back, then exits. Asset handles are saved as GUIDs
+//! and reloaded, so this covers the snapshot registry and the asset remap.
+//!
+//! `cargo run -p sample --example scene_round_trip`
+
+use app::{App, GameState, Scene, Transition};
+use common::Color;
+use config::config::LightShadowSettings;
+use core::EngineContext;
+use core::components::{
+    CameraComponent, DirectionalLightComponent, GlobalTransformComponent, MaterialComponent,
+    MeshComponent, PointLightComponent, TransformComponent,
+};
+use core::environment::WorldEnvironment;
+use core::types::transform::Transform;
+use ecs::world::World;
+use nalgebra_glm::vec3;
+
+#[allow(dead_code)]
+mod assets {
+    #[allow(unused_imports)]
+    use common::{Guid, guid};
+    include!(concat!(env!("OUT_DIR"), "/assets.rs"));
+}
+
+/// Locations of every transformed entity, sorted so they compare regardless of the
+/// order entities are loaded in.
+fn locations(world: &World) -> Vec<[f32; 3]> {
+    let mut locations = vec![];
+    world.for_each_component::<TransformComponent>(|_, transform| {
+        let location = transform.location;
+        locations.push([location.x, location.y, location.z]);
+    });
+    locations.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    locations
+}
+
+/// Runs the round trip on its first frame.
+struct RoundTrip;
+
+impl GameState for RoundTrip {
+    fn name(&self) -> &str {
+        "round trip"
+    }
+
+    fn on_enter(&mut self, ctx: &mut EngineContext, _scene: &mut Scene) {
+        let cube_mesh = ctx.load_mesh(assets::CUBE_OBJ);
+        let brick = ctx.load_material(assets::BRICK_EMAT);
+        ctx.resources().get_mut::<WorldEnvironment>().clear_color = Color::srgb(0.2, 0.3, 0.4);
+
+        // Entities are not added to the state's scene: the load replaces them.
+        let world = ctx.get_world();
+        for index in 0..5 {
+            world.create_entity((
+                TransformComponent(Transform::default().with_location(vec3(
+                    index as f32 * 2.0,
+                    0.0,
+                    0.0,
+                ))),
+                GlobalTransformComponent::default(),
+                MeshComponent {
+                    mesh_handle: cube_mesh,
+                },
+                MaterialComponent {
+                    material_handle: brick,
+                },
+            ));
+        }
+        world.create_entity((
+            TransformComponent(Transform::default().with_location(vec3(4.0, 2.0, 10.0))),
+            CameraComponent {
+                near_clip: 0.1,
+                far_clip: 100.0,
+                fov: 60.0,
+                active: true,
+            },
+        ));
+        world.create_entity((
+            TransformComponent(Transform::default().with_rotation(vec3(-0.8, 0.5, 0.0))),
+            DirectionalLightComponent {
+                color: Color::WHITE,
+                intensity: 1.0,
+                shadow: LightShadowSettings::default(),
+            },
+        ));
+        world.create_entity((
+            TransformComponent(Transform::default().with_location(vec3(4.0, 1.0, 2.0))),
+            PointLightComponent::new(Color::srgb(1.0, 0.5, 0.2), 3.0, 8.0),
+        ));
+    }
+
+    fn update(&mut self, ctx: &mut EngineContext, _dt: f32) -> Transition {
+        let before = locations(ctx.get_world());
+        let environment = ctx.resources().get::<WorldEnvironment>().clone();
+
+        let bytes = ctx.save_snapshot().expect("failed to save the scene");
+        ctx.get_world().clear();
+        *ctx.resources().get_mut::<WorldEnvironment>() = WorldEnvironment::default();
+        let loaded = ctx.load_snapshot(&bytes).expect("failed to load the scene");
+
+        let after = locations(ctx.get_world());
+        assert_eq!(before, after, "entity transforms changed in the round trip");
+        assert_eq!(*ctx.resources().get::<WorldEnvironment>(), environment);
+        println!(
+            "Round trip passed: {} entities in {} bytes.",
+            loaded.len(),
+            bytes.len()
+        );
+
+        ctx.request_exit();
+        Transition::None
+    }
+}
+
+fn main() {
+    let mut app = App::builder()
+        .project("sample/sample.eproj")
+        .window_title("Scene round trip")
+        .build();
+    app.push_state(RoundTrip);
+    app.run();
+}
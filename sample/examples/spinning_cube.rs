@@ -0,0 +1,80 @@
+//! A textured cube turning in front of a fixed camera: the smallest app that loads a
+//! mesh and material, spawns entities and runs a system.
+//!
+//! `cargo run -p sample --example spinning_cube`
+
+use app::App;
+use common::Color;
+use config::config::LightShadowSettings;
+use core::components::{
+    CameraComponent, DirectionalLightComponent, GlobalTransformComponent, MaterialComponent,
+    MeshComponent, TransformComponent,
+};
+use core::system::{Context, System};
+use core::types::transform::Transform;
+use ecs::command_buffer::Commands;
+use ecs::query::Query;
+use nalgebra_glm::vec3;
+
+#[allow(dead_code)]
+mod assets {
+    #[allow(unused_imports)]
+    use common::{Guid, guid};
+    include!(concat!(env!("OUT_DIR"), "/assets.rs"));
+}
+
+/// Radians per second around each axis.
+const SPIN: [f32; 3] = [0.4, 0.9, 0.0];
+
+fn spin_system(
+    mut query: Query<(&mut TransformComponent, &mut MeshComponent)>,
+    ctx: &mut Context,
+    _commands: &mut Commands,
+) {
+    for (transform, _) in query.iter() {
+        transform.rotation += vec3(SPIN[0], SPIN[1], SPIN[2]) * ctx.dt;
+    }
+}
+
+fn main() {
+    let mut app = App::builder()
+        .project("sample/sample.eproj")
+        .window_title("Spinning cube")
+        .build();
+
+    let ctx = app.engine_context_mut();
+    let cube_mesh = ctx.load_mesh(assets::CUBE_OBJ);
+    let brick = ctx.load_material(assets::BRICK_EMAT);
+
+    let setup = ctx.world_setup();
+    setup.world.create_entity((
+        TransformComponent(Transform::default()),
+        GlobalTransformComponent::default(),
+        MeshComponent {
+            mesh_handle: cube_mesh,
+        },
+        MaterialComponent {
+            material_handle: brick,
+        },
+    ));
+    setup.world.create_entity((
+        TransformComponent(Transform::default().with_location(vec3(0.0, 0.0, 5.0))),
+        CameraComponent {
+            near_clip: 0.1,
+            far_clip: 100.0,
+            fov: 60.0,
+            active: true,
+        },
+    ));
+    setup.world.create_entity((
+        TransformComponent(Transform::default().with_rotation(vec3(-0.8, 0.5, 0.0))),
+        DirectionalLightComponent {
+            color: Color::WHITE,
+            intensity: 1.0,
+            shadow: LightShadowSettings::default(),
+        },
+    ));
+
+    ctx.register_system(Box::new(System::new(spin_system)));
+    app.run();
+}
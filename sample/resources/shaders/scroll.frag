@@ -1,19 +1,33 @@
 #version 460
 
+// G-buffer: albedo.rgb + occlusion, octahedral normal.xy + roughness + metallic, emissive.rgb.
 layout(location = 0) out vec4 outColor;
 layout(location = 1) out vec4 outNormal;
-layout(location = 2) out vec4 outOrm;
+layout(location = 2) out vec4 outEmissive;
+// Entity ID buffer, for GPU picking. Shaders without this output are not pickable.
+layout(location = 3) out uint outEntityId;
 
 layout(location = 1) in vec2 fragTexCoord;
 layout(location = 3) in vec3 inNormal;
+layout(location = 8) flat in uint inEntityId;
 
 layout(set = 1, binding = 0) uniform sampler2D colorTexture;
 
+vec2 octEncode(vec3 n) {
+    n /= abs(n.x) + abs(n.y) + abs(n.z);
+    if (n.z < 0.0) {
+        vec2 signs = vec2(n.x >= 0.0 ? 1.0 : -1.0, n.y >= 0.0 ? 1.0 : -1.0);
+        n.xy = (1.0 - abs(n.yx)) * signs;
+    }
+    return n.xy;
+}
+
 void main() {
     vec4 tex = texture(colorTexture, fragTexCoord);
     // cyan tint + UV-driven brightness stripe to distinguish from default PBR
     float stripe = 0.75 + 0.25 * sin(fragTexCoord.x * 20.0);
-    outColor  = vec4(tex.r * stripe * 0.4, tex.g * stripe * 0.9, tex.b * stripe * 1.4, tex.a);
-    outNormal = vec4(normalize(inNormal), 0.0);
-    outOrm    = vec4(0.8, 0.0, 1.0, 0.0);
+    outColor  = vec4(tex.r * stripe * 0.4, tex.g * stripe * 0.9, tex.b * stripe * 1.4, 1.0);
+    outNormal = vec4(octEncode(normalize(inNormal)), 0.8, 0.0);
+    outEmissive = vec4(0.0);
+    outEntityId = inEntityId;
 }
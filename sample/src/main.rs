@@ -1,14 +1,18 @@
 use app::App;
+use common::Color;
+use config::config::LightShadowSettings;
+use core::app_exit::AppExit;
 use core::components::{
-    CameraComponent, CameraControllerComponent, DirectionalLightComponent, MaterialComponent,
-    MeshComponent, TransformComponent,
+    CameraComponent, CameraControllerComponent, DirectionalLightComponent,
+    GlobalTransformComponent, MaterialComponent, MaterialOverrideComponent, MeshComponent,
+    TransformComponent,
 };
 use core::system::{Context, System};
 use core::types::transform::Transform;
 use ecs::command_buffer::Commands;
 use ecs::query::Query;
-use input::{AnalogSource, AxisAction, AxisBinding, InputAction, InputBinding, KeyCode};
-use nalgebra_glm::vec3;
+use input::{AnalogSource, AxisAction, AxisBinding, InputBinding, KeyCode};
+use nalgebra_glm::{vec3, vec4};
 
 #[allow(dead_code)]
 mod assets {
@@ -35,19 +39,55 @@ fn spawn_cube_system(
 
     commands.spawn_entity((
         TransformComponent(Transform::default().with_location(vec3(x, y, z))),
+        GlobalTransformComponent::default(),
         MeshComponent { mesh_handle },
         MaterialComponent { material_handle },
+        // Spawned cubes share the brick material and differ only by a random tint.
+        MaterialOverrideComponent {
+            tint: vec4(rand::random(), rand::random(), rand::random(), 1.0),
+            ..Default::default()
+        },
     ));
 }
 
+fn rotate_sun_system(
+    mut query: Query<(&mut TransformComponent, &mut DirectionalLightComponent)>,
+    ctx: &mut Context,
+    _commands: &mut Commands,
+) {
+    let turn = ctx.input.get_axis("rotate_sun");
+    if turn == 0.0 {
+        return;
+    }
+
+    for (transform, _) in query.iter() {
+        transform.rotation.y += turn * ctx.dt;
+    }
+}
+
+fn quit_system(
+    _query: Query<&mut CameraComponent>,
+    ctx: &mut Context,
+    _commands: &mut Commands,
+) {
+    if ctx.input.is_action_just_pressed("quit") {
+        ctx.res_mut::<AppExit>().request();
+    }
+}
+
 fn main() {
-    let mut app = App::with_project("sample/sample.eproj");
+    let mut app = App::builder()
+        .project("sample/sample.eproj")
+        .window_title("Sample")
+        .build();
 
     {
         let ctx = app.engine_context_mut();
 
         ctx.input_mut()
             .bind_action("spawn_cube", vec![InputBinding::Key(KeyCode::Space)]);
+        ctx.input_mut()
+            .bind_action("quit", vec![InputBinding::Key(KeyCode::Escape)]);
         ctx.input_mut()
             .bind_action("move_forward", vec![InputBinding::Key(KeyCode::W)]);
         ctx.input_mut()
@@ -57,35 +97,36 @@ fn main() {
         ctx.input_mut()
             .bind_action("move_right", vec![InputBinding::Key(KeyCode::D)]);
 
+        ctx.input_mut()
+            .bind_action("rotate_sun_left", vec![InputBinding::Key(KeyCode::Q)]);
+        ctx.input_mut()
+            .bind_action("rotate_sun_right", vec![InputBinding::Key(KeyCode::E)]);
+
+        ctx.input_mut().bind_axis(
+            AxisAction::from("rotate_sun"),
+            AxisBinding::composite("rotate_sun_left", "rotate_sun_right"),
+        );
         ctx.input_mut().bind_axis(
             AxisAction::HORIZONTAL,
-            AxisBinding::Composite {
-                positive: InputAction::from("move_right"),
-                negative: InputAction::from("move_left"),
-            },
+            AxisBinding::composite("move_right", "move_left"),
         );
         ctx.input_mut().bind_axis(
             AxisAction::VERTICAL,
-            AxisBinding::Composite {
-                positive: InputAction::from("move_forward"),
-                negative: InputAction::from("move_backward"),
-            },
+            AxisBinding::composite("move_forward", "move_backward"),
         );
         ctx.input_mut().bind_axis(
             AxisAction::MOUSE_X,
-            AxisBinding::Analog {
-                source: AnalogSource::MouseX,
-                sensitivity: 5.0,
-            },
+            AxisBinding::analog(AnalogSource::MouseX, 1.0),
         );
         ctx.input_mut().bind_axis(
             AxisAction::MOUSE_Y,
-            AxisBinding::Analog {
-                source: AnalogSource::MouseY,
-                sensitivity: 5.0,
-            },
+            AxisBinding::analog(AnalogSource::MouseY, 1.0),
         );
 
+        // Mouse-look: lock the cursor and read raw motion. Released automatically while
+        // the window is unfocused.
+        ctx.input_mut().grab_cursor();
+
         let floor_mesh = ctx.load_mesh(assets::FLOOR_OBJ);
         let cube_mesh = ctx.load_mesh(assets::CUBE_OBJ);
         let mat = ctx.load_material(assets::BRICK_EMAT);
@@ -95,6 +136,7 @@ fn main() {
 
         setup.world.create_entity((
             TransformComponent(Transform::default()),
+            GlobalTransformComponent::default(),
             MeshComponent {
                 mesh_handle: floor_mesh,
             },
@@ -115,6 +157,7 @@ fn main() {
                             1.0 + y as f32 * 3.0,
                             z as f32 * 3.0,
                         ))),
+                        GlobalTransformComponent::default(),
                         MeshComponent {
                             mesh_handle: cube_mesh,
                         },
@@ -140,19 +183,18 @@ fn main() {
         ));
 
         setup.world.create_entity((
-            TransformComponent(
-                Transform::default().with_rotation(nalgebra_glm::normalize(&vec3(0.5, 1.0, 0.5))),
-            ),
+            // Pitched ~23 degrees below the horizon; Q/E rotate it around the vertical axis.
+            TransformComponent(Transform::default().with_rotation(vec3(-0.41, 0.76, 0.0))),
             DirectionalLightComponent {
-                ambient_color: vec3(1.0, 1.0, 1.0),
-                color: vec3(1.0, 1.0, 1.0),
-                ambient_intensity: 0.1,
+                color: Color::WHITE,
                 intensity: 1.0,
+                shadow: LightShadowSettings::default(),
             },
         ));
 
-        ctx.register_system(Box::new(System::new(core::systems::basic_camera_system)));
         ctx.register_system(Box::new(System::new(spawn_cube_system)));
+        ctx.register_system(Box::new(System::new(rotate_sun_system)));
+        ctx.register_system(Box::new(System::new(quit_system)));
     }
 
     app.run();
@@ -1,2 +0,0 @@
-// mod lifecycle;
-// mod material;
@@ -1,5 +0,0 @@
-extern crate nalgebra_glm as glm;
-
-pub mod terrain;
-
-pub mod engine_components;
@@ -1,2 +0,0 @@
-pub mod block_definitions;
-pub mod blocks;
@@ -1,6 +0,0 @@
-// pub mod blocks;
-// mod constants;
-// pub mod generator;
-// mod octree;
-// pub mod terrain;
-// pub mod terrain_material;